@@ -10,7 +10,9 @@ use std::sync::Arc;
 use parking_lot::{Mutex, RwLock};
 use tracing::info;
 
-use prose_core_client::dtos::{Availability, Emoji, MessageId, UserProfile};
+use prose_core_client::dtos::{
+    Availability, AvatarSize, Emoji, MessageId, UploadSource, Url, UserProfile,
+};
 use prose_core_client::infra::encryption::{EncryptionKeysRepository, SessionRepository};
 use prose_core_client::infra::general::OsRngProvider;
 use prose_core_client::{
@@ -26,6 +28,11 @@ pub trait ClientDelegate: Send + Sync {
     fn handle_event(&self, event: ClientEvent);
 }
 
+#[uniffi::export(callback_interface)]
+pub trait UploadProgressDelegate: Send + Sync {
+    fn handle_progress(&self, bytes_sent: u64, total_bytes: u64);
+}
+
 pub struct Client {
     jid: JID,
     client: RwLock<Option<ProseClient>>,
@@ -90,6 +97,11 @@ impl Client {
         Ok(())
     }
 
+    /// The schema version of the cache, e.g. to include it in bug reports.
+    pub async fn cache_schema_version(&self) -> Result<u32, ClientError> {
+        Ok(self.client().await?.cache.schema_version())
+    }
+
     pub async fn disconnect(&self) -> Result<(), ClientError> {
         self.client().await?.disconnect().await;
         Ok(())
@@ -100,6 +112,61 @@ impl Client {
         Ok(items.into_iter().map(Into::into).collect())
     }
 
+    pub async fn add_contact(
+        &self,
+        jid: JID,
+        name: Option<String>,
+        groups: Vec<String>,
+        request_presence_sub: bool,
+    ) -> Result<(), ClientError> {
+        self.client()
+            .await?
+            .contact_list
+            .add_contact(
+                &jid.to_bare().unwrap().into(),
+                name.as_deref(),
+                &groups,
+                request_presence_sub,
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_contact(&self, jid: JID) -> Result<(), ClientError> {
+        self.client()
+            .await?
+            .contact_list
+            .remove_contact(&jid.to_bare().unwrap().into())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn blocked_users(&self) -> Result<Vec<JID>, ClientError> {
+        let user_ids = self.client().await?.block_list.blocked_users().await?;
+        Ok(user_ids
+            .into_iter()
+            .map(|user_id| JID::from(user_id.into_inner()))
+            .collect())
+    }
+
+    pub async fn block_user(&self, jid: JID) -> Result<(), ClientError> {
+        self.client()
+            .await?
+            .block_list
+            .block_user(&jid.to_bare().unwrap().into())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn unblock_user(&self, jid: JID) -> Result<(), ClientError> {
+        self.client()
+            .await?
+            .block_list
+            .unblock_user(&jid.to_bare().unwrap().into())
+            .await?;
+        Ok(())
+    }
+
     pub async fn load_profile(&self, from: JID) -> Result<Option<UserProfile>, ClientError> {
         let profile = self
             .client()
@@ -110,20 +177,66 @@ impl Client {
         Ok(profile)
     }
 
+    pub async fn reset_device_session(&self, jid: JID, device_id: u32) -> Result<(), ClientError> {
+        self.client()
+            .await?
+            .user_data
+            .reset_user_device_session(&jid.to_bare().unwrap().into(), &device_id.into())
+            .await?;
+        Ok(())
+    }
+
     pub async fn save_profile(&self, profile: UserProfile) -> Result<(), ClientError> {
         let profile = self.client().await?.account.set_profile(profile).await?;
         Ok(profile)
     }
 
-    pub async fn load_avatar(&self, from: JID) -> Result<Option<PathBuf>, ClientError> {
-        todo!()
-        // let path = self
-        //     .client()
-        //     .await?
-        //     .user_data
-        //     .load_avatar(&from.to_bare().unwrap().into())
-        //     .await?;
-        // Ok(path)
+    pub async fn load_avatar(
+        &self,
+        from: JID,
+        size: AvatarSize,
+    ) -> Result<Option<PathBuf>, ClientError> {
+        let client = self.client().await?;
+        let Some(avatar) = client
+            .user_data
+            .load_user_avatar(&from.to_bare().unwrap().into())
+            .await?
+        else {
+            return Ok(None);
+        };
+        let path = client
+            .user_data
+            .load_avatar_with_size(&avatar, size)
+            .await?;
+        Ok(path)
+    }
+
+    /// Uploads the file at `path` and returns the URL it can be downloaded from. If `encrypt` is
+    /// set, the file is encrypted before it is uploaded and an `aesgcm://` URL is returned.
+    /// Dropping the returned future cancels the upload.
+    pub async fn upload_file(
+        &self,
+        path: PathBuf,
+        encrypt: bool,
+        progress: Box<dyn UploadProgressDelegate>,
+    ) -> Result<Url, ClientError> {
+        let client = self.client().await?;
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid file path {}", path.display()))?
+            .to_string();
+
+        let (_, upload) = client.uploads.upload_file(
+            UploadSource::Path(path),
+            &file_name,
+            None,
+            encrypt,
+            move |bytes_sent, total_bytes| progress.handle_progress(bytes_sent, total_bytes),
+        );
+        let slot = upload.await?;
+
+        Ok(slot.download_url)
     }
 
     pub async fn save_avatar(&self, image_path: PathBuf) -> Result<(), ClientError> {
@@ -246,11 +359,15 @@ impl Client {
         // Ok(text)
     }
 
-    pub async fn set_availability(&self, availability: Availability) -> Result<(), ClientError> {
+    pub async fn set_availability(
+        &self,
+        availability: Availability,
+        status_message: Option<String>,
+    ) -> Result<(), ClientError> {
         self.client()
             .await?
             .account
-            .set_availability(availability)
+            .set_availability(availability, status_message)
             .await?;
         Ok(())
     }
@@ -0,0 +1,120 @@
+// prose-core-client/prose-sdk-ffi
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use jid::BareJid;
+use parking_lot::RwLock;
+
+use prose_xmpp::ConnectionError;
+
+use crate::client::{Client, ClientDelegate};
+use crate::types::{ClientEvent, JID};
+use crate::ClientError;
+
+pub trait AccountClientDelegate: Send + Sync {
+    fn handle_event(&self, account: JID, event: ClientEvent);
+}
+
+/// Holds the clients of multiple accounts. Each client caches its data in its own subdirectory
+/// of `cache_dir` and can be connected and disconnected independently. Events of all clients are
+/// routed to a single delegate together with the account they belong to.
+pub struct ClientManager {
+    cache_dir: String,
+    delegate: Option<Arc<dyn AccountClientDelegate>>,
+    clients: RwLock<HashMap<BareJid, Arc<Client>>>,
+}
+
+impl ClientManager {
+    pub fn new(cache_dir: String, delegate: Option<Box<dyn AccountClientDelegate>>) -> Self {
+        Self {
+            cache_dir,
+            delegate: delegate.map(Arc::from),
+            clients: Default::default(),
+        }
+    }
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl ClientManager {
+    /// Returns the client for `jid`, creating it if needed. Clients are keyed by the normalized
+    /// JID of their account (e.g. `Alice@Prose.org` becomes `alice@prose.org`), which is also the
+    /// account their events are reported with and the name of their cache directory.
+    pub fn add_client(&self, jid: JID) -> Result<Arc<Client>, ClientError> {
+        let account = bare_jid(&jid)?;
+
+        if let Some(client) = self.clients.read().get(&account) {
+            return Ok(client.clone());
+        }
+
+        let delegate = self.delegate.clone().map(|delegate| {
+            Box::new(AccountDelegateWrapper {
+                account: JID::from(account.clone()),
+                delegate,
+            }) as Box<dyn ClientDelegate>
+        });
+
+        let client = Arc::new(Client::new(
+            JID::from(account.clone()),
+            self.cache_dir.clone(),
+            delegate,
+        )?);
+        Ok(self
+            .clients
+            .write()
+            .entry(account)
+            .or_insert(client)
+            .clone())
+    }
+
+    pub fn client(&self, jid: JID) -> Option<Arc<Client>> {
+        let account = bare_jid(&jid).ok()?;
+        self.clients.read().get(&account).cloned()
+    }
+
+    pub fn accounts(&self) -> Vec<JID> {
+        self.clients.read().keys().cloned().map(JID::from).collect()
+    }
+
+    pub async fn connect(&self, jid: JID, password: String) -> Result<(), ConnectionError> {
+        let client = self
+            .add_client(jid)
+            .map_err(|e| ConnectionError::Generic { msg: e.to_string() })?;
+        client.connect(password).await
+    }
+
+    pub async fn disconnect(&self, jid: JID) -> Result<(), ClientError> {
+        let Some(client) = self.client(jid) else {
+            return Ok(());
+        };
+        client.disconnect().await
+    }
+
+    /// Disconnects the client for `jid` and removes it from the manager. Its cached data is kept.
+    pub async fn remove_client(&self, jid: JID) -> Result<(), ClientError> {
+        let account = bare_jid(&jid)?;
+        let Some(client) = self.clients.write().remove(&account) else {
+            return Ok(());
+        };
+        client.disconnect().await
+    }
+}
+
+fn bare_jid(jid: &JID) -> Result<BareJid, ClientError> {
+    jid.to_bare()
+        .map_err(|e| ClientError::Generic { msg: e.to_string() })
+}
+
+struct AccountDelegateWrapper {
+    account: JID,
+    delegate: Arc<dyn AccountClientDelegate>,
+}
+
+impl ClientDelegate for AccountDelegateWrapper {
+    fn handle_event(&self, event: ClientEvent) {
+        self.delegate.handle_event(self.account.clone(), event)
+    }
+}
@@ -3,11 +3,13 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use prose_core_client::dtos::UploadError;
 use prose_core_client::FsAvatarRepositoryError;
 pub use uniffi_api::*;
 
 mod account_bookmarks_client;
 mod client;
+mod client_manager;
 mod logger;
 mod types;
 mod uniffi_api;
@@ -29,3 +31,9 @@ impl From<FsAvatarRepositoryError> for ClientError {
         ClientError::Generic { msg: e.to_string() }
     }
 }
+
+impl From<UploadError> for ClientError {
+    fn from(e: UploadError) -> Self {
+        ClientError::Generic { msg: e.to_string() }
+    }
+}
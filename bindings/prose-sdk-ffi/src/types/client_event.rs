@@ -37,6 +37,9 @@ pub enum ClientEvent {
         conversation: JID,
         message_ids: Vec<MessageId>,
     },
+
+    /// The cache couldn't be migrated and was started over empty.
+    CacheReset,
 }
 
 impl From<prose_core_client::ClientEvent> for ClientEvent {
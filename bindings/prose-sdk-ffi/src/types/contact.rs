@@ -3,9 +3,9 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use crate::types::JID;
+use crate::types::{DateTime, JID};
 use prose_core_client::dtos::{
-    Availability, Contact as CoreContact, Group as CoreGroup, UserStatus,
+    Availability, Contact as CoreContact, FallbackAvatar, Group as CoreGroup, UserStatus,
 };
 
 #[derive(Debug, PartialEq, Clone)]
@@ -18,18 +18,26 @@ pub enum Group {
 pub struct Contact {
     pub jid: JID,
     pub name: String,
+    pub fallback_avatar: FallbackAvatar,
     pub availability: Availability,
     pub status: Option<UserStatus>,
+    pub status_message: Option<String>,
+    pub last_seen: Option<DateTime>,
     pub group: Group,
 }
 
 impl From<CoreContact> for Contact {
     fn from(value: CoreContact) -> Self {
+        let fallback_avatar = value.fallback_avatar();
+
         Contact {
+            fallback_avatar,
             jid: value.id.into_inner().into(),
             name: value.name,
             availability: value.availability,
             status: value.status,
+            status_message: value.status_message,
+            last_seen: value.last_seen,
             group: value.group.into(),
         }
     }
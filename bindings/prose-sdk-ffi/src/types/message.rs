@@ -5,7 +5,7 @@
 
 use chrono::{DateTime as ChronoDateTime, Utc};
 
-use prose_core_client::dtos::{Emoji, Message as ProseMessage, MessageId};
+use prose_core_client::dtos::{DeliveryState, Emoji, Message as ProseMessage, MessageId};
 
 use crate::types::JID;
 
@@ -38,7 +38,7 @@ impl From<ProseMessage> for Message {
             timestamp: value.timestamp,
             is_read: value.flags.is_read,
             is_edited: value.flags.is_edited,
-            is_delivered: value.flags.is_delivered,
+            is_delivered: value.flags.delivery_state == DeliveryState::Delivered,
             // reactions: value.reactions.into_iter().map(Into::into).collect(),
         }
     }
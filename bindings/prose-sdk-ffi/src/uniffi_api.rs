@@ -10,15 +10,16 @@ pub use std::path::PathBuf;
 pub use jid::{BareJid, Error as JidParseError, FullJid};
 
 pub use prose_core_client::dtos::{
-    Address, Availability, Emoji, MessageId, MessageRemoteId, MessageServerId, Url, UserProfile,
-    UserStatus,
+    Address, Availability, AvatarSize, Emoji, FallbackAvatar, MessageId, MessageRemoteId,
+    MessageServerId, Url, UserProfile, UserStatus,
 };
 pub use prose_core_client::ConnectionEvent;
-pub use prose_xmpp::ConnectionError;
+pub use prose_xmpp::{ConnectionError, DisconnectReason, ForbiddenReason};
 
 pub use crate::types::{AccountBookmark, ClientEvent, Contact, DateTime, Group, JID};
 pub use crate::{
-    account_bookmarks_client::AccountBookmarksClient, client::*, logger::*, ClientError,
+    account_bookmarks_client::AccountBookmarksClient, client::*, client_manager::*, logger::*,
+    ClientError,
 };
 
 impl UniffiCustomTypeConverter for MessageId {
@@ -0,0 +1,111 @@
+// prose-core-client/prose-sdk-js
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use js_sys::{Array, Uint8Array};
+use web_sys::{Blob, BlobPropertyBag, Url};
+
+use prose_core_client::domain::shared::models::AvatarId;
+use prose_core_client::dtos::UserId;
+
+use crate::types::AvatarSize;
+
+struct CachedObjectURL {
+    avatar_id: AvatarId,
+    url: String,
+}
+
+thread_local! {
+    // The cache is shared between all `Client` instances, since these are created on the fly
+    // whenever an event is dispatched to the delegate.
+    static OBJECT_URLS: RefCell<HashMap<(UserId, AvatarSize), CachedObjectURL>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Returns the cached object URL for `user_id` and `size` if it was created for `avatar_id`.
+pub fn cached_object_url(
+    user_id: &UserId,
+    size: AvatarSize,
+    avatar_id: &AvatarId,
+) -> Option<String> {
+    OBJECT_URLS.with_borrow(|urls| {
+        urls.get(&(user_id.clone(), size))
+            .filter(|cached| &cached.avatar_id == avatar_id)
+            .map(|cached| cached.url.clone())
+    })
+}
+
+/// Caches `url` and revokes the object URL it replaces. If an object URL for the same avatar was
+/// cached in the meantime, `url` is revoked instead and the cached URL is returned.
+pub fn cache_object_url(
+    user_id: UserId,
+    size: AvatarSize,
+    avatar_id: AvatarId,
+    url: String,
+) -> String {
+    OBJECT_URLS.with_borrow_mut(|urls| {
+        let key = (user_id, size);
+
+        if let Some(cached) = urls.get(&key) {
+            if cached.avatar_id == avatar_id {
+                revoke_object_url(&url);
+                return cached.url.clone();
+            }
+        }
+
+        if let Some(stale) = urls.insert(
+            key,
+            CachedObjectURL {
+                avatar_id,
+                url: url.clone(),
+            },
+        ) {
+            revoke_object_url(&stale.url);
+        }
+        url
+    })
+}
+
+/// Removes and revokes all cached object URLs of the given users.
+pub fn invalidate_object_urls<'a>(user_ids: impl IntoIterator<Item = &'a UserId>) {
+    OBJECT_URLS.with_borrow_mut(|urls| {
+        for user_id in user_ids {
+            urls.retain(|(id, _), cached| {
+                if id != user_id {
+                    return true;
+                }
+                revoke_object_url(&cached.url);
+                false
+            });
+        }
+    })
+}
+
+/// Creates a `blob:` object URL from a base64-encoded `data:` URL.
+pub fn object_url_from_data_url(data_url: &str) -> Result<String> {
+    let (mime_type, base64_data) = data_url
+        .strip_prefix("data:")
+        .and_then(|url| url.split_once(";base64,"))
+        .ok_or(anyhow!("Expected a base64-encoded data URL."))?;
+
+    let data = general_purpose::STANDARD.decode(base64_data)?;
+    let parts = Array::of1(&Uint8Array::from(data.as_slice()));
+
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+        .map_err(|err| anyhow!("Failed to create Blob. {err:?}"))?;
+    Url::create_object_url_with_blob(&blob)
+        .map_err(|err| anyhow!("Failed to create object URL. {err:?}"))
+}
+
+fn revoke_object_url(url: &str) {
+    _ = Url::revoke_object_url(url);
+}
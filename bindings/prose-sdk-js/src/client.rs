@@ -5,6 +5,7 @@
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use base64::{engine::general_purpose, Engine as _};
@@ -14,20 +15,25 @@ use tracing::{info, Level};
 use tracing_subscriber::prelude::*;
 use wasm_bindgen::prelude::*;
 
-use prose_core_client::dtos::{MucId, SoftwareVersion, UserStatus};
+use prose_core_client::domain::connection::models::ReconnectPolicy;
+use prose_core_client::dtos::{MucId, NotificationPolicy, SoftwareVersion, UserId, UserStatus};
 use prose_core_client::infra::encryption::{EncryptionKeysRepository, SessionRepository};
 use prose_core_client::{open_store, Client as ProseClient, PlatformDriver, StoreAvatarRepository};
 
+use crate::avatar_url_cache::{
+    cache_object_url, cached_object_url, invalidate_object_urls, object_url_from_data_url,
+};
 use crate::connector::{Connector, ProseConnectionProvider};
 use crate::delegate::{Delegate, JSDelegate};
 use crate::encryption::{EncryptionService, JsEncryptionService};
 use crate::error::{Result, WasmError};
 use crate::log::{JSLogger, MakeJSLogWriter};
 use crate::types::{
-    try_user_id_vec_from_string_array, AccountInfo, Availability, Avatar, BareJid, Channel,
-    ChannelsArray, ConnectionError, Contact, ContactsArray, IntoJSArray, PresenceSubRequest,
-    PresenceSubRequestArray, PresenceSubRequestId, SidebarItem, SidebarItemsArray, UploadSlot,
-    UserBasicInfo, UserBasicInfoArray, UserMetadata, UserProfile,
+    try_user_id_vec_from_string_array, AccountInfo, Availability, Avatar, AvatarSize, BareJid,
+    Channel, ChannelsArray, ClientEventAsyncIterator, ClientEventIterator, ConnectionError,
+    Contact, ContactsArray, DeviceTrust, IntoJSArray, JoinRoomError, PresenceSubRequest,
+    PresenceSubRequestArray, PresenceSubRequestId, SidebarItem, SidebarItemsArray, StringArray,
+    UploadSlot, UserBasicInfo, UserBasicInfoArray, UserMetadata, UserProfile,
 };
 
 #[derive(Debug, PartialEq, Clone)]
@@ -55,6 +61,43 @@ pub struct ClientConfig {
 
     #[wasm_bindgen(skip)]
     pub client_os: Option<String>,
+
+    #[wasm_bindgen(skip)]
+    pub account: Option<String>,
+
+    /// Defines if the client should keep its data in memory only instead of in IndexedDB. The
+    /// client then starts every session with an empty cache and leaves no traces behind.
+    #[wasm_bindgen(js_name = "inMemoryCache")]
+    pub in_memory_cache: bool,
+
+    /// Defines if the client should reconnect automatically after the connection was lost.
+    #[wasm_bindgen(js_name = "reconnectEnabled")]
+    pub reconnect_enabled: bool,
+
+    /// The delay before the first reconnect attempt in milliseconds. Doubles with every failed
+    /// attempt.
+    #[wasm_bindgen(js_name = "reconnectBaseDelayMs")]
+    pub reconnect_base_delay_ms: u32,
+
+    /// The maximum delay between two reconnect attempts in milliseconds.
+    #[wasm_bindgen(js_name = "reconnectMaxDelayMs")]
+    pub reconnect_max_delay_ms: u32,
+
+    /// The number of reconnect attempts after which the client gives up. Unset to retry forever.
+    #[wasm_bindgen(js_name = "reconnectMaxAttempts")]
+    pub reconnect_max_attempts: Option<u32>,
+
+    /// The fraction (0.0 to 1.0) of each reconnect delay that is randomized.
+    #[wasm_bindgen(js_name = "reconnectJitter")]
+    pub reconnect_jitter: f64,
+
+    /// Defines if `notificationRequested` should be called for received messages.
+    #[wasm_bindgen(js_name = "notificationsEnabled")]
+    pub notifications_enabled: bool,
+
+    /// Defines if notifications should be suppressed while the user is set to do not disturb.
+    #[wasm_bindgen(js_name = "suppressNotificationsWhileDoNotDisturb")]
+    pub suppress_notifications_while_do_not_disturb: bool,
 }
 
 #[wasm_bindgen(js_class = "ProseClientConfig")]
@@ -94,6 +137,18 @@ impl ClientConfig {
         self.client_os = client_os.clone()
     }
 
+    /// The account the client is used for. When set, the client keeps its data in a database
+    /// of its own, so that clients of different accounts can be used side by side.
+    #[wasm_bindgen(getter)]
+    pub fn account(&self) -> Option<String> {
+        self.account.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_account(&mut self, account: Option<String>) {
+        self.account = account
+    }
+
     #[wasm_bindgen(getter, js_name = "loggingMinLevel")]
     pub fn logging_min_level(&self) -> String {
         self.logging_min_level.clone()
@@ -115,10 +170,43 @@ impl Default for ClientConfig {
             client_name: env!("CARGO_PKG_NAME").to_string(),
             client_version: env!("CARGO_PKG_VERSION").to_string(),
             client_os: None,
+            account: None,
+            in_memory_cache: false,
+            reconnect_enabled: true,
+            reconnect_base_delay_ms: 1000,
+            reconnect_max_delay_ms: 60_000,
+            reconnect_max_attempts: Some(10),
+            reconnect_jitter: 0.2,
+            notifications_enabled: false,
+            suppress_notifications_while_do_not_disturb: true,
         }
     }
 }
 
+impl ClientConfig {
+    fn database_name(&self) -> String {
+        match &self.account {
+            Some(account) => format!("ProseDB-{}", account.to_lowercase()),
+            None => "ProseDB".to_string(),
+        }
+    }
+
+    fn reconnect_policy(&self) -> Option<ReconnectPolicy> {
+        self.reconnect_enabled.then(|| ReconnectPolicy {
+            base_delay: Duration::from_millis(self.reconnect_base_delay_ms as u64),
+            max_delay: Duration::from_millis(self.reconnect_max_delay_ms as u64),
+            max_attempts: self.reconnect_max_attempts,
+            jitter: self.reconnect_jitter,
+        })
+    }
+
+    fn notification_policy(&self) -> Option<NotificationPolicy> {
+        self.notifications_enabled.then(|| NotificationPolicy {
+            suppress_while_do_not_disturb: self.suppress_notifications_while_do_not_disturb,
+        })
+    }
+}
+
 #[wasm_bindgen(js_name = "ProseClient")]
 pub struct Client {
     client: ProseClient,
@@ -135,8 +223,13 @@ impl Client {
         logger: JSLogger,
         config: Option<ClientConfig>,
     ) -> Result<Client> {
-        let store = open_store(PlatformDriver::new("ProseDB")).await?;
         let config = config.unwrap_or_default();
+        let driver = if config.in_memory_cache {
+            PlatformDriver::in_memory()
+        } else {
+            PlatformDriver::new(&config.database_name())
+        };
+        let store = open_store(driver).await?;
 
         static LOGGING_INITIALIZED: AtomicBool = AtomicBool::new(false);
         if !LOGGING_INITIALIZED.swap(true, Ordering::SeqCst) {
@@ -161,6 +254,8 @@ impl Client {
             version: config.client_version.clone(),
             os: config.client_os.clone(),
         };
+        let reconnect_policy = config.reconnect_policy();
+        let notification_policy = config.notification_policy();
 
         cfg_if! {
             if #[cfg(feature = "delay-requests")] {
@@ -183,8 +278,16 @@ impl Client {
             }
         }
 
+        let mut builder = ProseClient::builder();
+        if let Some(policy) = reconnect_policy {
+            builder = builder.set_reconnect_policy(policy);
+        }
+        if let Some(policy) = notification_policy {
+            builder = builder.set_notification_policy(policy);
+        }
+
         let client = Client {
-            client: ProseClient::builder()
+            client: builder
                 .set_connector_provider(provider)
                 .set_store(store.clone())
                 .set_avatar_repository(StoreAvatarRepository::new(store.clone()))
@@ -215,6 +318,32 @@ impl Client {
         Ok(())
     }
 
+    /// The round-trip time in milliseconds of the last ping answered by the server. Can be used
+    /// to display the connection quality. `undefined` if no ping was answered yet.
+    #[wasm_bindgen(js_name = "connectionLatency")]
+    pub fn connection_latency(&self) -> Option<u32> {
+        self.client
+            .connection_latency()
+            .map(|latency| latency.as_millis() as u32)
+    }
+
+    /// The number of sent messages which were not acknowledged by the server. Can be used to
+    /// warn the user about potentially lost messages after the connection was lost.
+    #[wasm_bindgen(js_name = "unackedMessageCount")]
+    pub fn unacked_message_count(&self) -> usize {
+        self.client.unacked_message_count()
+    }
+
+    /// Serves the cached sidebar and messages of `jid` before the connection is established.
+    #[wasm_bindgen(js_name = "loadCachedState")]
+    pub async fn load_cached_state(&self, jid: &BareJid) -> Result<()> {
+        self.client
+            .load_cached_state(&jid.into())
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
     #[wasm_bindgen(js_name = "startObservingRooms")]
     pub async fn start_observing_rooms(&self) -> Result<()> {
         self.client
@@ -241,6 +370,43 @@ impl Client {
             .collect_into_js_array::<SidebarItemsArray>()
     }
 
+    /// Sets the manual order of the sidebar items. Pass a String[] where each string is the
+    /// BareJid of a sidebar item. Items which are not contained in `jids` are sorted by their
+    /// last activity after the manually ordered items.
+    #[wasm_bindgen(js_name = "setSidebarOrder")]
+    pub async fn set_sidebar_order(&self, jids: Array) -> Result<()> {
+        let jids = jids
+            .iter()
+            .filter_map(|value| value.as_string())
+            .collect::<Vec<_>>();
+
+        let room_ids = self
+            .client
+            .sidebar
+            .sidebar_items()
+            .await
+            .into_iter()
+            .map(|item| item.room.to_generic_room().jid().clone())
+            .collect::<Vec<_>>();
+
+        let ordered_room_ids = jids
+            .iter()
+            .filter_map(|jid| {
+                room_ids
+                    .iter()
+                    .find(|room_id| &room_id.to_string() == jid)
+                    .cloned()
+            })
+            .collect();
+
+        self.client
+            .sidebar
+            .set_manual_order(ordered_room_ids)
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
     #[wasm_bindgen(js_name = "loadPublicChannels")]
     pub async fn load_public_channels(&self) -> Result<ChannelsArray> {
         Ok(self
@@ -277,6 +443,92 @@ impl Client {
             .into())
     }
 
+    /// Returns the fingerprint of this device's OMEMO identity key as eight groups of eight hex
+    /// characters, or `undefined` if OMEMO hasn't been initialized yet.
+    #[wasm_bindgen(js_name = "loadOwnDeviceFingerprint")]
+    pub async fn load_own_device_fingerprint(&self) -> Result<Option<String>> {
+        Ok(self
+            .client
+            .account
+            .load_own_device_fingerprint()
+            .await
+            .map_err(WasmError::from)?
+            .map(|fingerprint| fingerprint.to_string()))
+    }
+
+    /// Returns an `xmpp:` URI to be shown as QR code so that contacts can verify this device.
+    #[wasm_bindgen(js_name = "loadOwnDeviceVerificationURI")]
+    pub async fn load_own_device_verification_uri(&self) -> Result<Option<String>> {
+        Ok(self
+            .client
+            .account
+            .load_own_device_verification_uri()
+            .await
+            .map_err(WasmError::from)?)
+    }
+
+    /// Returns the fingerprint of the OMEMO identity key of the device with `deviceID` of `jid`,
+    /// or `undefined` if its identity is unknown.
+    #[wasm_bindgen(js_name = "loadDeviceFingerprint")]
+    pub async fn load_device_fingerprint(
+        &self,
+        jid: &BareJid,
+        device_id: u32,
+    ) -> Result<Option<String>> {
+        Ok(self
+            .client
+            .user_data
+            .load_user_device_fingerprint(&jid.into(), &device_id.into())
+            .await
+            .map_err(WasmError::from)?
+            .map(|fingerprint| fingerprint.to_string()))
+    }
+
+    /// Sets the trust of the device with `deviceID` of `jid`.
+    #[wasm_bindgen(js_name = "setDeviceTrust")]
+    pub async fn set_device_trust(
+        &self,
+        jid: &BareJid,
+        device_id: u32,
+        trust: DeviceTrust,
+    ) -> Result<()> {
+        self.client
+            .user_data
+            .set_user_device_trust(&jid.into(), &device_id.into(), trust.into())
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
+    /// Marks the device with `deviceID` of `jid` as verified if `fingerprint` matches the
+    /// fingerprint of its identity key. Returns `false` if it doesn't.
+    #[wasm_bindgen(js_name = "verifyDeviceFingerprint")]
+    pub async fn verify_device_fingerprint(
+        &self,
+        jid: &BareJid,
+        device_id: u32,
+        fingerprint: String,
+    ) -> Result<bool> {
+        Ok(self
+            .client
+            .user_data
+            .verify_user_device_fingerprint(&jid.into(), &device_id.into(), &fingerprint)
+            .await
+            .map_err(WasmError::from)?)
+    }
+
+    /// Discards the OMEMO session with the device with `deviceID` of `jid`. A new session is
+    /// started when sending the next message.
+    #[wasm_bindgen(js_name = "resetDeviceSession")]
+    pub async fn reset_device_session(&self, jid: &BareJid, device_id: u32) -> Result<()> {
+        self.client
+            .user_data
+            .reset_user_device_session(&jid.into(), &device_id.into())
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
     /// Creates the direct message or joins it if it already exists and returns the `BareJid`.
     /// Sends invites to all participants if the group was created.
     /// Pass a String[] as participants where each string is a valid BareJid.
@@ -339,26 +591,46 @@ impl Client {
     }
 
     /// Joins the room identified by `room_jid` and returns its `BareJid`.
+    ///
+    /// Fails with a `ProseJoinRoomError` whose type tells if the join should be retried with a
+    /// (different) password.
     #[wasm_bindgen(js_name = "joinRoom")]
-    pub async fn join_room(&self, room_jid: &BareJid, password: Option<String>) -> Result<BareJid> {
+    pub async fn join_room(
+        &self,
+        room_jid: &BareJid,
+        password: Option<String>,
+    ) -> std::result::Result<BareJid, JoinRoomError> {
         Ok(self
             .client
             .rooms
             .join_room(&MucId::from(room_jid.clone()), password.as_deref())
-            .await
-            .map_err(|err| WasmError::from(anyhow::Error::from(err)))?
+            .await?
+            .into_bare()
+            .into())
+    }
+
+    /// Joins the room that we were invited to. Fails with a `ProseJoinRoomError` like `joinRoom`.
+    #[wasm_bindgen(js_name = "acceptInvitation")]
+    pub async fn accept_invitation(
+        &self,
+        room_jid: &BareJid,
+    ) -> std::result::Result<BareJid, JoinRoomError> {
+        Ok(self
+            .client
+            .rooms
+            .accept_invitation(&MucId::from(room_jid.clone()))
+            .await?
             .into_bare()
             .into())
     }
 
-    /// Destroys the room identified by `room_jid`.
-    #[wasm_bindgen(js_name = "destroyRoom")]
-    pub async fn destroy_room(&self, room_jid: &BareJid) -> Result<()> {
+    /// Removes the room that we were invited to from the sidebar without joining it.
+    #[wasm_bindgen(js_name = "declineInvitation")]
+    pub async fn decline_invitation(&self, room_jid: &BareJid) -> Result<()> {
         self.client
             .rooms
-            .destroy_room(&MucId::from(room_jid.clone()))
-            .await
-            .map_err(|err| WasmError::from(anyhow::Error::from(err)))?;
+            .decline_invitation(&MucId::from(room_jid.clone()))
+            .await?;
         Ok(())
     }
 
@@ -387,13 +659,52 @@ impl Client {
         Ok(())
     }
 
-    /// Adds a contact to the roster and sends a presence subscription request.
+    /// Adds a contact to the roster and, unless `request_presence_sub` is `false`, sends
+    /// a presence subscription request.
     #[wasm_bindgen(js_name = "addContact")]
-    pub async fn add_contact(&self, jid: &BareJid) -> Result<()> {
+    pub async fn add_contact(
+        &self,
+        jid: &BareJid,
+        name: Option<String>,
+        groups: Option<StringArray>,
+        request_presence_sub: Option<bool>,
+    ) -> Result<()> {
+        let groups = groups
+            .map(|groups| Vec::<String>::try_from(&groups))
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(self
+            .client
+            .contact_list
+            .add_contact(
+                &jid.into(),
+                name.as_deref(),
+                &groups,
+                request_presence_sub.unwrap_or(true),
+            )
+            .await
+            .map_err(WasmError::from)?)
+    }
+
+    /// Sets the name of a contact in the roster. Pass `undefined` to remove the name.
+    #[wasm_bindgen(js_name = "renameContact")]
+    pub async fn rename_contact(&self, jid: &BareJid, name: Option<String>) -> Result<()> {
+        Ok(self
+            .client
+            .contact_list
+            .rename_contact(&jid.into(), name.as_deref())
+            .await
+            .map_err(WasmError::from)?)
+    }
+
+    /// Replaces the roster groups of a contact.
+    #[wasm_bindgen(js_name = "setContactGroups")]
+    pub async fn set_contact_groups(&self, jid: &BareJid, groups: &StringArray) -> Result<()> {
         Ok(self
             .client
             .contact_list
-            .add_contact(&jid.into())
+            .set_groups(&jid.into(), &Vec::<String>::try_from(groups)?)
             .await
             .map_err(WasmError::from)?)
     }
@@ -422,6 +733,19 @@ impl Client {
             .collect_into_js_array::<ContactsArray>())
     }
 
+    /// Returns when `jid` was last active. Resolves to `undefined` unless `jid` is a contact
+    /// with a mutual presence subscription.
+    #[wasm_bindgen(js_name = "loadLastActivity")]
+    pub async fn load_last_activity(&self, jid: &BareJid) -> Result<Option<js_sys::Date>> {
+        Ok(self
+            .client
+            .contact_list
+            .load_last_activity(&jid.into())
+            .await
+            .map_err(WasmError::from)?
+            .map(|ts| js_sys::Date::new(&JsValue::from(ts.timestamp_millis() as f64))))
+    }
+
     /// Requests a presence subscription from `jid`. Note that happens automatically when you
     /// call `add_contact`. This method can be useful though when our user needs to re-request
     /// the presence subscription in case the contact hasn't reacted in a while.
@@ -449,12 +773,17 @@ impl Client {
             .collect_into_js_array::<PresenceSubRequestArray>())
     }
 
-    /// Approves the presence subscription request identified by `id`.
+    /// Approves the presence subscription request identified by `id`. Unless `subscribe_back`
+    /// is `false`, a presence subscription is requested in return.
     #[wasm_bindgen(js_name = "approvePresenceSubscriptionRequest")]
-    pub async fn approve_presence_sub_request(&self, id: &PresenceSubRequestId) -> Result<()> {
+    pub async fn approve_presence_sub_request(
+        &self,
+        id: &PresenceSubRequestId,
+        subscribe_back: Option<bool>,
+    ) -> Result<()> {
         self.client
             .contact_list
-            .approve_presence_sub_request(id.as_ref())
+            .approve_presence_sub_request(id.as_ref(), subscribe_back.unwrap_or(true))
             .await
             .map_err(WasmError::from)?;
         Ok(())
@@ -484,6 +813,41 @@ impl Client {
         Ok(avatar)
     }
 
+    /// Returns a `blob:` object URL for the avatar of `jid` in the requested `size`. Object URLs
+    /// are cached until the avatar of `jid` changes, at which point they are revoked.
+    #[wasm_bindgen(js_name = "avatarURL")]
+    pub async fn avatar_url(&self, jid: &BareJid, size: AvatarSize) -> Result<Option<String>> {
+        let user_id = UserId::from(jid);
+
+        let Some(avatar) = self
+            .client
+            .user_data
+            .load_user_avatar(&user_id)
+            .await
+            .map_err(WasmError::from)?
+        else {
+            invalidate_object_urls([&user_id]);
+            return Ok(None);
+        };
+
+        if let Some(url) = cached_object_url(&user_id, size, &avatar.id) {
+            return Ok(Some(url));
+        }
+
+        let Some(data_url) = self
+            .client
+            .user_data
+            .load_avatar_with_size(&avatar, size.into())
+            .await
+            .map_err(WasmError::from)?
+        else {
+            return Ok(None);
+        };
+
+        let url = object_url_from_data_url(&data_url).map_err(WasmError::from)?;
+        Ok(Some(cache_object_url(user_id, size, avatar.id, url)))
+    }
+
     /// XEP-0084: User Avatar
     /// https://xmpp.org/extensions/xep-0084.html
     #[wasm_bindgen(js_name = "saveAvatar")]
@@ -502,6 +866,39 @@ impl Client {
         Ok(())
     }
 
+    /// XEP-0084: User Avatar
+    /// https://xmpp.org/extensions/xep-0084.html
+    ///
+    /// Publishes `imageData` as our avatar. The image should already be scaled down,
+    /// since images exceeding the configured maximum size are rejected.
+    #[wasm_bindgen(js_name = "setAvatar")]
+    pub async fn set_avatar(
+        &self,
+        image_data: &[u8],
+        mime_type: &str,
+        width: Option<u32>,
+        height: Option<u32>,
+    ) -> Result<()> {
+        self.client
+            .account
+            .set_avatar(image_data, width, height, mime_type)
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
+    /// XEP-0084: User Avatar
+    /// https://xmpp.org/extensions/xep-0084.html#pub-disable
+    #[wasm_bindgen(js_name = "deleteAvatar")]
+    pub async fn delete_avatar(&self) -> Result<()> {
+        self.client
+            .account
+            .delete_avatar()
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
     /// XEP-0292: vCard4 Over XMPP
     /// https://xmpp.org/extensions/xep-0292.html
     #[wasm_bindgen(js_name = "loadUserProfile")]
@@ -528,6 +925,18 @@ impl Client {
         Ok(())
     }
 
+    /// XEP-0292: vCard4 Over XMPP
+    /// https://xmpp.org/extensions/xep-0292.html
+    #[wasm_bindgen(js_name = "deleteUserProfile")]
+    pub async fn delete_user_profile(&self) -> Result<()> {
+        self.client
+            .account
+            .delete_profile()
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
     #[wasm_bindgen(js_name = "deleteCachedData")]
     pub async fn delete_cached_data(&self) -> Result<()> {
         self.client
@@ -538,6 +947,30 @@ impl Client {
         Ok(())
     }
 
+    /// Returns an async iterator over all events of the client, e.g. for use with `for await`.
+    /// Can be used instead of or alongside the delegate.
+    pub fn subscribe(&self) -> std::result::Result<ClientEventAsyncIterator, JsValue> {
+        ClientEventIterator::from(self.client.subscribe()).into_async_iterable()
+    }
+
+    /// The schema version of the cache, e.g. to include it in bug reports.
+    #[wasm_bindgen(getter, js_name = "cacheSchemaVersion")]
+    pub fn cache_schema_version(&self) -> u32 {
+        self.client.cache.schema_version()
+    }
+
+    /// Deletes expired items from the cache. Call this periodically, e.g. once a day, to keep
+    /// the cache from growing indefinitely.
+    #[wasm_bindgen(js_name = "runCacheMaintenance")]
+    pub async fn run_cache_maintenance(&self) -> Result<()> {
+        self.client
+            .cache
+            .run_maintenance()
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
     #[wasm_bindgen(js_name = "loadUserMetadata")]
     pub async fn load_user_metadata(&self, jid: &BareJid) -> Result<UserMetadata> {
         let metadata = self
@@ -553,10 +986,72 @@ impl Client {
     /// XMPP: Instant Messaging and Presence
     /// https://xmpp.org/rfcs/rfc6121.html#presence
     #[wasm_bindgen(js_name = "setAvailability")]
-    pub async fn set_availability(&self, availability: Availability) -> Result<()> {
+    pub async fn set_availability(
+        &self,
+        availability: Availability,
+        status_message: Option<String>,
+    ) -> Result<()> {
+        self.client
+            .account
+            .set_availability(availability.into(), status_message)
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
+    /// Returns whether read receipts are sent when marking messages as read.
+    #[wasm_bindgen(js_name = "sendReadReceipts")]
+    pub async fn send_read_receipts(&self) -> Result<bool> {
+        Ok(self
+            .client
+            .account
+            .send_read_receipts()
+            .await
+            .map_err(WasmError::from)?)
+    }
+
+    /// Enables or disables sending read receipts (XEP-0333) when marking messages as read.
+    #[wasm_bindgen(js_name = "setSendReadReceipts")]
+    pub async fn set_send_read_receipts(&self, enabled: bool) -> Result<()> {
+        self.client
+            .account
+            .set_send_read_receipts(enabled)
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
+    /// Automatically switches the availability to `availability` after no activity has been
+    /// reported via `reportUserActivity` for `afterSecs` seconds.
+    #[wasm_bindgen(js_name = "setAutoAway")]
+    pub async fn set_auto_away(&self, after_secs: u32, availability: Availability) -> Result<()> {
+        self.client
+            .account
+            .set_auto_away(
+                chrono::Duration::seconds(after_secs.into()),
+                availability.into(),
+            )
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = "disableAutoAway")]
+    pub async fn disable_auto_away(&self) -> Result<()> {
         self.client
             .account
-            .set_availability(availability.into())
+            .disable_auto_away()
+            .await
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+
+    /// Resets the idle timer and restores the previous availability if the user was idle.
+    #[wasm_bindgen(js_name = "reportUserActivity")]
+    pub async fn report_user_activity(&self) -> Result<()> {
+        self.client
+            .account
+            .report_user_activity()
             .await
             .map_err(WasmError::from)?;
         Ok(())
@@ -610,6 +1105,16 @@ impl Client {
         Ok(())
     }
 
+    /// Returns the maximum file size in bytes that the server accepts for uploads.
+    #[wasm_bindgen(js_name = "maxUploadFileSize")]
+    pub fn max_upload_file_size(&self) -> Result<u64> {
+        Ok(self
+            .client
+            .uploads
+            .max_file_size()
+            .map_err(WasmError::from)?)
+    }
+
     /// Request a slot for uploading a file to attach it to a message.
     #[wasm_bindgen(js_name = "requestUploadSlot")]
     pub async fn request_upload_slot(
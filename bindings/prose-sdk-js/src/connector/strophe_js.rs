@@ -13,13 +13,14 @@ use minidom::Element;
 use secrecy::{ExposeSecret, SecretString};
 use thiserror::Error;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::spawn_local;
 use web_sys::DomException;
 
 use prose_xmpp::client::ConnectorProvider;
 use prose_xmpp::connector::{
     Connection as ConnectionTrait, ConnectionError, ConnectionEvent, ConnectionEventHandler,
-    Connector as ConnectorTrait,
+    Connector as ConnectorTrait, DisconnectReason,
 };
 
 use crate::client::ClientConfig;
@@ -33,6 +34,9 @@ export interface ProseConnectionProvider {
 
 export interface ProseConnection {
     setEventHandler(handler: ProseConnectionEventHandler): void
+    /// Rejects with a `ProseConnectionErrorType`, a `DOMException` thrown by the transport or
+    /// with the condition sent by the server if the authentication failed (e.g. 'not-authorized'
+    /// or 'account-disabled') or the stream was closed with a stream error (e.g. 'conflict').
     connect(jid: string, password: string): Promise<void>
     disconnect(): void
     sendStanza(stanza: string): void
@@ -112,6 +116,18 @@ impl ConnectorTrait for Connector {
             .await;
 
         if let Err(err) = result {
+            if let Some(exception) = err.dyn_ref::<DomException>() {
+                return Err(JSConnectionError::from(exception.clone()).into());
+            }
+
+            if let Some(condition) = err.as_string() {
+                // Strophe reports SASL failures and stream errors with the defined condition sent
+                // by the server.
+                return Err(transport_error_from_condition(&condition)
+                    .or_else(|| stream_error_from_condition(&condition))
+                    .unwrap_or_else(|| ConnectionError::from_sasl_condition(&condition)));
+            }
+
             let Some(code) = err.as_f64().map(|code| code as i32) else {
                 return Err(ConnectionError::Generic {
                     msg: "strophe.js connector returned an invalid error code.".to_string(),
@@ -157,13 +173,29 @@ impl ConnectionTrait for Connection {
 
 #[wasm_bindgen(js_class = "ProseConnectionEventHandler")]
 impl EventHandler {
+    /// `error` is either the condition reported by strophe.js or a `DOMException` thrown by the
+    /// transport. Pass `undefined` if the connection was closed regularly.
     #[wasm_bindgen(js_name = "handleDisconnect")]
-    pub fn handle_disconnect(&self, error: Option<String>) {
+    pub fn handle_disconnect(&self, error: JsValue) {
+        let error = if error.is_undefined() || error.is_null() {
+            None
+        } else if let Some(exception) = error.dyn_ref::<DomException>() {
+            Some(JSConnectionError::from(exception.clone()).into())
+        } else {
+            let error = error.as_string().unwrap_or_else(|| format!("{error:?}"));
+
+            // Strophe handles stream errors itself and reports their condition as the
+            // disconnect reason.
+            Some(
+                transport_error_from_condition(&error)
+                    .or_else(|| stream_error_from_condition(&error))
+                    .unwrap_or_else(|| ConnectionError::Generic { msg: error }),
+            )
+        };
+
         let fut = (self.handler)(
             Box::new(self.connection.clone()),
-            ConnectionEvent::Disconnected {
-                error: error.map(|error| ConnectionError::Generic { msg: error }),
-            },
+            ConnectionEvent::Disconnected { error },
         );
         spawn_local(async move { fut.await })
     }
@@ -212,3 +244,63 @@ impl From<DomException> for JSConnectionError {
         }
     }
 }
+
+impl From<JSConnectionError> for ConnectionError {
+    fn from(value: JSConnectionError) -> Self {
+        match value {
+            JSConnectionError::DomException { name, message } => match name.as_str() {
+                "TimeoutError" => ConnectionError::TimedOut,
+                "NetworkError" => ConnectionError::ServerUnreachable { msg: message },
+                "SecurityError" => ConnectionError::TlsError { msg: message },
+                _ => ConnectionError::Generic {
+                    msg: format!("DomException {name}: {message}"),
+                },
+            },
+        }
+    }
+}
+
+/// Maps the conditions strophe.js reports for connections that failed or were lost on the
+/// transport level. Returns `None` for any other condition.
+fn transport_error_from_condition(condition: &str) -> Option<ConnectionError> {
+    match condition {
+        "connection-timeout" => Some(ConnectionError::TimedOut),
+        "host-unknown" | "host-gone" | "remote-connection-failed" => {
+            Some(ConnectionError::ServerUnreachable {
+                msg: format!("The server could not be reached ({condition})."),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Maps the stream error conditions defined in RFC 6120, Section 4.9.3 which strophe.js reports
+/// verbatim. Returns `None` for any other condition. `not-authorized` is left out on purpose,
+/// since strophe.js reports SASL failures with the same condition.
+fn stream_error_from_condition(condition: &str) -> Option<ConnectionError> {
+    match condition {
+        "bad-format"
+        | "bad-namespace-prefix"
+        | "conflict"
+        | "improper-addressing"
+        | "internal-server-error"
+        | "invalid-from"
+        | "invalid-namespace"
+        | "invalid-xml"
+        | "not-well-formed"
+        | "policy-violation"
+        | "reset"
+        | "resource-constraint"
+        | "restricted-xml"
+        | "see-other-host"
+        | "system-shutdown"
+        | "undefined-condition"
+        | "unsupported-encoding"
+        | "unsupported-feature"
+        | "unsupported-stanza-type"
+        | "unsupported-version" => Some(ConnectionError::StreamError {
+            reason: DisconnectReason::from_condition(condition),
+        }),
+        _ => None,
+    }
+}
@@ -8,10 +8,14 @@ use wasm_bindgen::prelude::*;
 
 use prose_core_client::dtos::{MessageId, MessageRemoteId};
 use prose_core_client::{ClientDelegate, ClientEvent, ClientRoomEventType, ConnectionEvent};
-use prose_xmpp::ConnectionError;
+use prose_xmpp::{ConnectionError, ForbiddenReason};
 
+use crate::avatar_url_cache::invalidate_object_urls;
 use crate::client::Client;
-use crate::types::{BareJid, BareJidArray};
+use crate::types::{
+    BareJid, BareJidArray, ConnectionError as ProseConnectionError, ConnectionErrorType,
+    ParticipantId, ParticipantIdsArray,
+};
 use crate::types::{IntoJSArray, RoomEnvelopeExt};
 
 #[wasm_bindgen(typescript_custom_section)]
@@ -24,16 +28,45 @@ export type ConnectionInvalidCredentialsError = {
     code: 'invalid_credentials';
 }
 
+/// The credentials were accepted, but the server refused the login.
+export type ConnectionForbiddenError = {
+    code: 'account_disabled' | 'credentials_expired';
+}
+
+export type ConnectionTlsError = {
+    code: 'tls_error';
+    message: string;
+}
+
+export type ConnectionServerUnreachableError = {
+    code: 'server_unreachable';
+    message: string;
+}
+
 export type ConnectionGenericError = {
     code: 'generic';
     message: string;
 }
 
-export type ConnectionError = ConnectionTimedOutError | ConnectionInvalidCredentialsError | ConnectionGenericError;
+export type ConnectionStreamError = {
+    code: 'conflict' | 'system_shutdown' | 'policy_violation' | 'stream_error';
+    message: string;
+    /// The number of seconds after which the client should try to reconnect. Not set if the
+    /// client should not reconnect automatically (e.g. because another session took over).
+    reconnectAfterSecs?: number;
+}
+
+export type ConnectionError = ConnectionTimedOutError | ConnectionInvalidCredentialsError | ConnectionForbiddenError | ConnectionTlsError | ConnectionServerUnreachableError | ConnectionStreamError | ConnectionGenericError;
 
 export interface ProseClientDelegate {
     clientConnected(): void
     clientDisconnected(client: ProseClient, error?: ConnectionError): void
+    /// The connection was lost and the client is about to make reconnect attempt `attempt`
+    /// (starting at 1).
+    clientReconnecting(client: ProseClient, attempt: number): void
+    /// The server didn't answer a ping in time, i.e. the connection is degraded. The client
+    /// disconnects if the next ping times out as well.
+    clientPingTimedOut(client: ProseClient): void
     
     /// The contents of the sidebar have changed.
     sidebarChanged(client: ProseClient): void
@@ -50,6 +83,12 @@ export interface ProseClientDelegate {
     /// Infos related to the logged-in user have changed.
     accountInfoChanged(client: ProseClient): void
 
+    /// The OMEMO devices of a user or their trust have changed.
+    userDevicesChanged(client: ProseClient, ids: JID[]): void
+
+    /// A previously unknown OMEMO device was announced by a user.
+    newDeviceDetected(client: ProseClient, id: JID, deviceId: number): void
+
     /// One or many messages were either received or sent.
     messagesAppended(client: ProseClient, room: Room, messageIDs: string[]): void
 
@@ -65,12 +104,39 @@ export interface ProseClientDelegate {
     /// Attributes changed like name or topic.
     roomAttributesChanged(client: ProseClient, room: Room): void
     
-    /// The list of participants has changed.
-    roomParticipantsChanged(client: ProseClient, room: Room): void
+    /// Participants joined, left or their details changed.
+    roomParticipantsChanged(client: ProseClient, room: Room, participantIDs: ParticipantId[]): void
     
     /// A user in `conversation` started or stopped typing.
     composingUsersChanged(client: ProseClient, room: Room): void
     
+    /// Messages were pinned or unpinned.
+    pinnedMessagesChanged(client: ProseClient, room: Room): void
+    
+    /// The room was destroyed and removed from the sidebar.
+    roomDestroyed(client: ProseClient, room: Room): void
+    
+    /// We were removed from the room, e.g. because we were kicked or banned. The room is
+    /// disconnected and its state contains the reason.
+    removedFromRoom(client: ProseClient, room: Room): void
+    
+    /// Devices of participants that haven't been trusted or distrusted yet were found while
+    /// encrypting a message. Depending on the trust policy, the message was either encrypted for
+    /// them or sending it failed. The user should decide whether to trust these devices.
+    encryptionRecipientsChanged(client: ProseClient, room: Room): void
+    
+    /// We were invited to the room by `sender`. The room is in the sidebar now and waits for the
+    /// invitation to be accepted or declined.
+    invitationReceived(client: ProseClient, room: Room, sender: JID): void
+    
+    /// Private messages were exchanged with the occupant `occupantId` of the room. These
+    /// messages are not part of the room's timeline.
+    privateMessagesAppended(client: ProseClient, room: Room, occupantId: string, messageIDs: string[]): void
+    
+    /// Messages were received that the user should be notified about. Only called if
+    /// `notificationsEnabled` is set in the `ProseClientConfig`.
+    notificationRequested(client: ProseClient, room: Room, messageIDs: string[]): void
+    
     /// The contact list has changed.
     contactListChanged(client: ProseClient): void
     
@@ -79,6 +145,10 @@ export interface ProseClientDelegate {
     
     /// The block list has changed.
     blockListChanged(client: ProseClient): void
+
+    /// The cache couldn't be migrated and was started over empty. OMEMO keys and unsent
+    /// messages were kept, everything else is loaded from the server again.
+    cacheReset(client: ProseClient): void
 }
 "#;
 
@@ -97,6 +167,12 @@ extern "C" {
         error: Option<JSConnectionError>,
     ) -> Result<(), JsValue>;
 
+    #[wasm_bindgen(method, catch, js_name = "clientReconnecting")]
+    fn client_reconnecting(this: &JSDelegate, client: Client, attempt: u32) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "clientPingTimedOut")]
+    fn client_ping_timed_out(this: &JSDelegate, client: Client) -> Result<(), JsValue>;
+
     #[wasm_bindgen(method, catch, js_name = "composingUsersChanged")]
     fn composing_users_changed(
         this: &JSDelegate,
@@ -121,6 +197,21 @@ extern "C" {
     #[wasm_bindgen(method, catch, js_name = "accountInfoChanged")]
     fn account_info_changed(this: &JSDelegate, client: Client) -> Result<(), JsValue>;
 
+    #[wasm_bindgen(method, catch, js_name = "userDevicesChanged")]
+    fn user_devices_changed(
+        this: &JSDelegate,
+        client: Client,
+        jids: BareJidArray,
+    ) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "newDeviceDetected")]
+    fn new_device_detected(
+        this: &JSDelegate,
+        client: Client,
+        jid: BareJid,
+        device_id: u32,
+    ) -> Result<(), JsValue>;
+
     #[wasm_bindgen(method, catch, js_name = "messagesAppended")]
     fn messages_appended(
         this: &JSDelegate,
@@ -164,6 +255,52 @@ extern "C" {
         this: &JSDelegate,
         client: Client,
         room: JsValue,
+        ids: ParticipantIdsArray,
+    ) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "pinnedMessagesChanged")]
+    fn pinned_messages_changed(
+        this: &JSDelegate,
+        client: Client,
+        room: JsValue,
+    ) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "roomDestroyed")]
+    fn room_destroyed(this: &JSDelegate, client: Client, room: JsValue) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "removedFromRoom")]
+    fn removed_from_room(this: &JSDelegate, client: Client, room: JsValue) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "encryptionRecipientsChanged")]
+    fn encryption_recipients_changed(
+        this: &JSDelegate,
+        client: Client,
+        room: JsValue,
+    ) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "invitationReceived")]
+    fn invitation_received(
+        this: &JSDelegate,
+        client: Client,
+        room: JsValue,
+        sender: BareJid,
+    ) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "privateMessagesAppended")]
+    fn private_messages_appended(
+        this: &JSDelegate,
+        client: Client,
+        room: JsValue,
+        occupant_id: String,
+        ids: Vec<JsValue>,
+    ) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "notificationRequested")]
+    fn notification_requested(
+        this: &JSDelegate,
+        client: Client,
+        room: JsValue,
+        ids: Vec<JsValue>,
     ) -> Result<(), JsValue>;
 
     #[wasm_bindgen(method, catch, js_name = "contactListChanged")]
@@ -174,12 +311,17 @@ extern "C" {
 
     #[wasm_bindgen(method, catch, js_name = "blockListChanged")]
     fn block_list_changed(this: &JSDelegate, client: Client) -> Result<(), JsValue>;
+
+    #[wasm_bindgen(method, catch, js_name = "cacheReset")]
+    fn cache_reset(this: &JSDelegate, client: Client) -> Result<(), JsValue>;
 }
 
 #[wasm_bindgen(getter_with_clone)]
 pub struct JSConnectionError {
     pub code: String,
     pub message: Option<String>,
+    #[wasm_bindgen(js_name = "reconnectAfterSecs")]
+    pub reconnect_after_secs: Option<u32>,
 }
 
 impl From<ConnectionError> for JSConnectionError {
@@ -188,14 +330,51 @@ impl From<ConnectionError> for JSConnectionError {
             ConnectionError::TimedOut => JSConnectionError {
                 code: "timed_out".to_string(),
                 message: None,
+                reconnect_after_secs: None,
             },
             ConnectionError::InvalidCredentials => JSConnectionError {
                 code: "invalid_credentials".to_string(),
                 message: None,
+                reconnect_after_secs: None,
             },
+            ConnectionError::Forbidden { reason } => JSConnectionError {
+                code: match reason {
+                    ForbiddenReason::AccountDisabled => "account_disabled",
+                    ForbiddenReason::CredentialsExpired => "credentials_expired",
+                }
+                .to_string(),
+                message: None,
+                reconnect_after_secs: None,
+            },
+            ConnectionError::TlsError { msg } => JSConnectionError {
+                code: "tls_error".to_string(),
+                message: Some(msg),
+                reconnect_after_secs: None,
+            },
+            ConnectionError::ServerUnreachable { msg } => JSConnectionError {
+                code: "server_unreachable".to_string(),
+                message: Some(msg),
+                reconnect_after_secs: None,
+            },
+            ConnectionError::StreamError { reason } => {
+                let error = ProseConnectionError::from(reason);
+
+                JSConnectionError {
+                    code: match error.kind {
+                        ConnectionErrorType::StreamConflict => "conflict",
+                        ConnectionErrorType::StreamSystemShutdown => "system_shutdown",
+                        ConnectionErrorType::StreamPolicyViolation => "policy_violation",
+                        _ => "stream_error",
+                    }
+                    .to_string(),
+                    message: Some(error.message),
+                    reconnect_after_secs: error.reconnect_after_secs,
+                }
+            }
             ConnectionError::Generic { msg } => JSConnectionError {
                 code: "generic".to_string(),
                 message: Some(msg),
+                reconnect_after_secs: None,
             },
         }
     }
@@ -260,6 +439,12 @@ impl Delegate {
             } => self
                 .inner
                 .client_disconnected(client, error.map(Into::into))?,
+            ClientEvent::ConnectionStatusChanged {
+                event: ConnectionEvent::Reconnecting { attempt },
+            } => self.inner.client_reconnecting(client, attempt)?,
+            ClientEvent::ConnectionStatusChanged {
+                event: ConnectionEvent::PingTimeout,
+            } => self.inner.client_ping_timed_out(client)?,
             ClientEvent::SidebarChanged => self.inner.sidebar_changed(client)?,
             ClientEvent::ContactChanged { ids } => self.inner.contact_changed(
                 client,
@@ -267,13 +452,29 @@ impl Delegate {
                     .map(|id| BareJid::from(id.into_inner()))
                     .collect_into_js_array::<BareJidArray>(),
             )?,
-            ClientEvent::AvatarChanged { ids } => self.inner.avatar_changed(
+            ClientEvent::AvatarChanged { ids } => {
+                invalidate_object_urls(&ids);
+                self.inner.avatar_changed(
+                    client,
+                    ids.into_iter()
+                        .map(|id| BareJid::from(id.into_inner()))
+                        .collect_into_js_array::<BareJidArray>(),
+                )?
+            }
+            ClientEvent::AccountInfoChanged => self.inner.account_info_changed(client)?,
+            ClientEvent::UserDevicesChanged { ids } => self.inner.user_devices_changed(
                 client,
                 ids.into_iter()
                     .map(|id| BareJid::from(id.into_inner()))
                     .collect_into_js_array::<BareJidArray>(),
             )?,
-            ClientEvent::AccountInfoChanged => self.inner.account_info_changed(client)?,
+            ClientEvent::NewDeviceDetected { user_id, device_id } => {
+                self.inner.new_device_detected(
+                    client,
+                    BareJid::from(user_id.into_inner()),
+                    device_id.into(),
+                )?
+            }
             ClientEvent::RoomChanged { room, r#type } => match r#type {
                 ClientRoomEventType::MessagesAppended { message_ids } => self
                     .inner
@@ -293,15 +494,57 @@ impl Delegate {
                 ClientRoomEventType::AttributesChanged => self
                     .inner
                     .room_attributes_changed(client, room.into_js_value())?,
-                ClientRoomEventType::ParticipantsChanged => self
+                ClientRoomEventType::ParticipantsChanged { ids } => {
+                    self.inner.room_participants_changed(
+                        client,
+                        room.into_js_value(),
+                        ids.into_iter()
+                            .map(ParticipantId::from)
+                            .collect_into_js_array::<ParticipantIdsArray>(),
+                    )?
+                }
+                ClientRoomEventType::PinnedMessagesChanged => self
+                    .inner
+                    .pinned_messages_changed(client, room.into_js_value())?,
+                ClientRoomEventType::Destroyed => {
+                    self.inner.room_destroyed(client, room.into_js_value())?
+                }
+                ClientRoomEventType::RemovedFromRoom { .. } => {
+                    self.inner.removed_from_room(client, room.into_js_value())?
+                }
+                ClientRoomEventType::EncryptionRecipientsChanged => self
                     .inner
-                    .room_participants_changed(client, room.into_js_value())?,
+                    .encryption_recipients_changed(client, room.into_js_value())?,
+                ClientRoomEventType::InvitationReceived { sender } => {
+                    self.inner.invitation_received(
+                        client,
+                        room.into_js_value(),
+                        BareJid::from(sender.into_inner()),
+                    )?
+                }
+                ClientRoomEventType::PrivateMessagesAppended {
+                    occupant_id,
+                    message_ids,
+                } => self.inner.private_messages_appended(
+                    client,
+                    room.into_js_value(),
+                    occupant_id.to_string(),
+                    message_ids.into_js_array(),
+                )?,
+                ClientRoomEventType::NotificationRequested { message_ids } => {
+                    self.inner.notification_requested(
+                        client,
+                        room.into_js_value(),
+                        message_ids.into_js_array(),
+                    )?
+                }
             },
             ClientEvent::ContactListChanged => self.inner.contact_list_changed(client)?,
             ClientEvent::PresenceSubRequestsChanged => {
                 self.inner.presence_sub_requests_changed(client)?
             }
             ClientEvent::BlockListChanged => self.inner.block_list_changed(client)?,
+            ClientEvent::CacheReset => self.inner.cache_reset(client)?,
         }
         Ok(())
     }
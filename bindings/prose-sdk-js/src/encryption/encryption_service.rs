@@ -30,7 +30,7 @@ use super::{
 #[wasm_bindgen(typescript_custom_section)]
 const TS_APPEND_CONTENT: &'static str = r#"
 export interface ProseEncryptionService {
-    async generateLocalEncryptionBundle(): Promise<LocalEncryptionBundle>
+    async generateLocalEncryptionBundle(preKeyCount: number): Promise<LocalEncryptionBundle>
     
     async processPreKeyBundle(
         repo: SignalRepo,
@@ -62,7 +62,10 @@ extern "C" {
     pub type JsEncryptionService;
 
     #[wasm_bindgen(method, catch, js_name = "generateLocalEncryptionBundle")]
-    fn generate_local_encryption_bundle(this: &JsEncryptionService) -> Result<JsValue, JsValue>;
+    fn generate_local_encryption_bundle(
+        this: &JsEncryptionService,
+        pre_key_count: u32,
+    ) -> Result<JsValue, JsValue>;
 
     #[wasm_bindgen(method, catch, js_name = "generatePreKeysWithIds")]
     fn generate_pre_keys_with_ids(
@@ -125,9 +128,10 @@ impl EncryptionServiceTrait for EncryptionService {
         &self,
         _account: &AccountId,
         device_id: DeviceId,
+        pre_key_count: u32,
     ) -> Result<LocalEncryptionBundle> {
         let bundle = JsLocalEncryptionBundle::try_from(
-            &await_promise(self.inner.generate_local_encryption_bundle()).await?,
+            &await_promise(self.inner.generate_local_encryption_bundle(pre_key_count)).await?,
         )
         .map_err(|err| anyhow!("{err}"))?;
 
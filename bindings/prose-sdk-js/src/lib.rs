@@ -11,6 +11,7 @@ extern crate core;
 
 use wasm_bindgen::prelude::*;
 
+mod avatar_url_cache;
 mod client;
 mod connector;
 mod delegate;
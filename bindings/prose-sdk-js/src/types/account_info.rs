@@ -47,4 +47,9 @@ impl AccountInfo {
             .as_ref()
             .map(|activity| activity.clone().into())
     }
+
+    #[wasm_bindgen(getter, js_name = "statusMessage")]
+    pub fn status_message(&self) -> Option<String> {
+        self.0.status_message.clone()
+    }
 }
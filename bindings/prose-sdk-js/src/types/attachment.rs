@@ -10,6 +10,7 @@ use wasm_bindgen::prelude::*;
 
 use prose_core_client::dtos;
 
+use crate::error::WasmError;
 use crate::types::UploadSlot;
 
 #[wasm_bindgen]
@@ -111,12 +112,31 @@ pub struct Thumbnail {
 
 #[wasm_bindgen]
 impl Attachment {
-    /// The URL of the attachment.
+    /// The URL of the attachment. This is an `aesgcm://` URL if the file was encrypted before it
+    /// was uploaded.
     #[wasm_bindgen(getter)]
     pub fn url(&self) -> String {
         self.metadata.url.to_string()
     }
 
+    /// The URL to download the attachment from. If `isEncrypted` is true, pass the downloaded
+    /// data through `decrypt` before using it.
+    #[wasm_bindgen(getter, js_name = "downloadURL")]
+    pub fn download_url(&self) -> String {
+        dtos::AttachmentEncryption::download_url(&self.metadata.url).to_string()
+    }
+
+    /// The file was encrypted before it was uploaded (XEP-0454).
+    #[wasm_bindgen(getter, js_name = "isEncrypted")]
+    pub fn is_encrypted(&self) -> bool {
+        dtos::AttachmentEncryption::from_url(&self.metadata.url).is_some()
+    }
+
+    /// Decrypts the data downloaded from `downloadURL` if needed.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, JsError> {
+        decrypt(&self.metadata.url, data)
+    }
+
     /// The type of the attachment.
     #[wasm_bindgen(getter, js_name = "type")]
     pub fn r#type(&self) -> AttachmentType {
@@ -184,12 +204,31 @@ impl Thumbnail {
         }
     }
 
-    /// The URL of the thumbnail.
+    /// The URL of the thumbnail. This is an `aesgcm://` URL if the thumbnail was encrypted
+    /// before it was uploaded.
     #[wasm_bindgen(getter)]
     pub fn url(&self) -> String {
         self.url.to_string()
     }
 
+    /// The URL to download the thumbnail from. If `isEncrypted` is true, pass the downloaded
+    /// data through `decrypt` before using it.
+    #[wasm_bindgen(getter, js_name = "downloadURL")]
+    pub fn download_url(&self) -> String {
+        dtos::AttachmentEncryption::download_url(&self.url).to_string()
+    }
+
+    /// The thumbnail was encrypted before it was uploaded (XEP-0454).
+    #[wasm_bindgen(getter, js_name = "isEncrypted")]
+    pub fn is_encrypted(&self) -> bool {
+        dtos::AttachmentEncryption::from_url(&self.url).is_some()
+    }
+
+    /// Decrypts the data downloaded from `downloadURL` if needed.
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, JsError> {
+        decrypt(&self.url, data)
+    }
+
     /// The media type of the thumbnail.
     #[wasm_bindgen(getter, js_name = "mediaType")]
     pub fn media_type(&self) -> String {
@@ -248,6 +287,19 @@ impl AttachmentMetadata {
     }
 }
 
+/// Decrypts the data downloaded from the `downloadURL` of `attachment` if needed.
+#[wasm_bindgen(js_name = "decryptAttachment")]
+pub fn decrypt_attachment(data: &[u8], attachment: &Attachment) -> Result<Vec<u8>, JsError> {
+    attachment.decrypt(data)
+}
+
+fn decrypt(url: &Url, data: &[u8]) -> Result<Vec<u8>, JsError> {
+    let Some(encryption) = dtos::AttachmentEncryption::from_url(url) else {
+        return Ok(data.to_vec());
+    };
+    Ok(encryption.decrypt(data).map_err(WasmError::from)?)
+}
+
 impl From<dtos::Attachment> for Attachment {
     fn from(value: dtos::Attachment) -> Self {
         let (r#type, duration, thumbnail) = match value.r#type {
@@ -0,0 +1,188 @@
+// prose-core-client/prose-sdk-js
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use futures::lock::Mutex;
+use futures::StreamExt;
+use js_sys::{Object, Reflect, Symbol};
+use wasm_bindgen::prelude::*;
+
+use prose_core_client::dtos::{RoomEnvelope, UserId};
+use prose_core_client::services::ClientEventStream;
+use prose_core_client::{ClientEvent as SdkClientEvent, ClientRoomEventType, ConnectionEvent};
+
+use crate::types::{BareJid, BareJidArray, IntoJSArray, RoomEnvelopeExt, RoomUpdate};
+
+#[wasm_bindgen(js_name = "ProseClientEventType")]
+#[derive(Debug, Clone)]
+pub enum ClientEventType {
+    Connected = 0,
+    Disconnected = 1,
+    Reconnecting = 2,
+    PingTimedOut = 3,
+    SidebarChanged = 4,
+    ContactChanged = 5,
+    ContactListChanged = 6,
+    PresenceSubRequestsChanged = 7,
+    BlockListChanged = 8,
+    AvatarChanged = 9,
+    AccountInfoChanged = 10,
+    UserDevicesChanged = 11,
+    NewDeviceDetected = 12,
+    RoomChanged = 13,
+    CacheReset = 14,
+}
+
+#[wasm_bindgen(js_name = "ProseClientEvent")]
+pub struct ClientEvent {
+    kind: ClientEventType,
+    user_ids: Vec<UserId>,
+    room: Option<RoomEnvelope>,
+    room_update: Option<ClientRoomEventType>,
+}
+
+#[wasm_bindgen(js_class = "ProseClientEvent")]
+impl ClientEvent {
+    #[wasm_bindgen(getter, js_name = "type")]
+    pub fn kind(&self) -> ClientEventType {
+        self.kind.clone()
+    }
+
+    /// The affected users for `ContactChanged`, `AvatarChanged`, `UserDevicesChanged` and
+    /// `NewDeviceDetected`.
+    #[wasm_bindgen(getter, js_name = "userIDs")]
+    pub fn user_ids(&self) -> BareJidArray {
+        self.user_ids
+            .iter()
+            .map(|id| BareJid::from(id.clone().into_inner()))
+            .collect_into_js_array::<BareJidArray>()
+    }
+
+    /// The affected room for `RoomChanged`.
+    #[wasm_bindgen(getter)]
+    pub fn room(&self) -> JsValue {
+        self.room
+            .clone()
+            .map(|room| room.into_js_value())
+            .unwrap_or(JsValue::UNDEFINED)
+    }
+
+    /// The update of the room for `RoomChanged`.
+    #[wasm_bindgen(getter, js_name = "roomUpdate")]
+    pub fn room_update(&self) -> Option<RoomUpdate> {
+        self.room_update.clone().map(RoomUpdate::from)
+    }
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "ProseClientEventIterator & AsyncIterable<ProseClientEvent>")]
+    pub type ClientEventAsyncIterator;
+}
+
+/// Delivers all events of the client in order. Implements the async iterator protocol, i.e.
+/// `next()` resolves to `{ value: ProseClientEvent, done: false }`.
+#[wasm_bindgen(js_name = "ProseClientEventIterator")]
+pub struct ClientEventIterator {
+    stream: Rc<Mutex<ClientEventStream>>,
+    missed_events: Arc<AtomicU64>,
+}
+
+#[wasm_bindgen(js_class = "ProseClientEventIterator")]
+impl ClientEventIterator {
+    pub async fn next(&self) -> Result<JsValue, JsValue> {
+        let stream = self.stream.clone();
+        let event = stream.lock().await.next().await;
+
+        let result = Object::new();
+        Reflect::set(&result, &"done".into(), &event.is_none().into())?;
+        Reflect::set(
+            &result,
+            &"value".into(),
+            &event
+                .map(|event| JsValue::from(ClientEvent::from(event)))
+                .unwrap_or(JsValue::UNDEFINED),
+        )?;
+        Ok(result.into())
+    }
+
+    /// The number of events that were dropped because the iterator wasn't consumed fast enough.
+    #[wasm_bindgen(getter, js_name = "missedEvents")]
+    pub fn missed_events(&self) -> f64 {
+        self.missed_events.load(Ordering::Relaxed) as f64
+    }
+}
+
+impl ClientEventIterator {
+    /// Makes the iterator usable with `for await`, which calls `[Symbol.asyncIterator]()` to
+    /// obtain the iterator.
+    pub fn into_async_iterable(self) -> Result<ClientEventAsyncIterator, JsValue> {
+        let iterator = JsValue::from(self);
+        // `Object.prototype.valueOf` returns `this`, which is what an iterator returns from
+        // `[Symbol.asyncIterator]()`.
+        let value_of = Reflect::get(&Object::new(), &"valueOf".into())?;
+        Reflect::set(&iterator, &Symbol::async_iterator(), &value_of)?;
+        Ok(iterator.unchecked_into())
+    }
+}
+
+impl From<ClientEventStream> for ClientEventIterator {
+    fn from(value: ClientEventStream) -> Self {
+        Self {
+            missed_events: value.missed_events_counter(),
+            stream: Rc::new(Mutex::new(value)),
+        }
+    }
+}
+
+impl From<SdkClientEvent> for ClientEvent {
+    fn from(value: SdkClientEvent) -> Self {
+        let (kind, user_ids) = match value {
+            SdkClientEvent::ConnectionStatusChanged { event } => {
+                let kind = match event {
+                    ConnectionEvent::Connect => ClientEventType::Connected,
+                    ConnectionEvent::Disconnect { .. } => ClientEventType::Disconnected,
+                    ConnectionEvent::Reconnecting { .. } => ClientEventType::Reconnecting,
+                    ConnectionEvent::PingTimeout => ClientEventType::PingTimedOut,
+                };
+                (kind, vec![])
+            }
+            SdkClientEvent::SidebarChanged => (ClientEventType::SidebarChanged, vec![]),
+            SdkClientEvent::ContactChanged { ids } => (ClientEventType::ContactChanged, ids),
+            SdkClientEvent::ContactListChanged => (ClientEventType::ContactListChanged, vec![]),
+            SdkClientEvent::PresenceSubRequestsChanged => {
+                (ClientEventType::PresenceSubRequestsChanged, vec![])
+            }
+            SdkClientEvent::BlockListChanged => (ClientEventType::BlockListChanged, vec![]),
+            SdkClientEvent::AvatarChanged { ids } => (ClientEventType::AvatarChanged, ids),
+            SdkClientEvent::AccountInfoChanged => (ClientEventType::AccountInfoChanged, vec![]),
+            SdkClientEvent::UserDevicesChanged { ids } => {
+                (ClientEventType::UserDevicesChanged, ids)
+            }
+            SdkClientEvent::NewDeviceDetected { user_id, .. } => {
+                (ClientEventType::NewDeviceDetected, vec![user_id])
+            }
+            SdkClientEvent::CacheReset => (ClientEventType::CacheReset, vec![]),
+            SdkClientEvent::RoomChanged { room, r#type } => {
+                return Self {
+                    kind: ClientEventType::RoomChanged,
+                    user_ids: vec![],
+                    room: Some(room),
+                    room_update: Some(r#type),
+                }
+            }
+        };
+
+        Self {
+            kind,
+            user_ids,
+            room: None,
+            room_update: None,
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use anyhow::{format_err, Result};
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use prose_xmpp::ConnectionError as CoreConnectionError;
+use prose_xmpp::{ConnectionError as CoreConnectionError, DisconnectReason, ForbiddenReason};
 
 #[wasm_bindgen(js_name = "ProseConnectionErrorType")]
 #[derive(Clone)]
@@ -9,6 +9,14 @@ pub enum ConnectionErrorType {
     TimedOut = 0,
     InvalidCredentials = 1,
     Generic = 2,
+    StreamConflict = 3,
+    StreamSystemShutdown = 4,
+    StreamPolicyViolation = 5,
+    StreamError = 6,
+    AccountDisabled = 7,
+    TlsError = 8,
+    ServerUnreachable = 9,
+    CredentialsExpired = 10,
 }
 
 impl TryFrom<i32> for ConnectionErrorType {
@@ -19,6 +27,14 @@ impl TryFrom<i32> for ConnectionErrorType {
             0 => Ok(Self::TimedOut),
             1 => Ok(Self::InvalidCredentials),
             2 => Ok(Self::Generic),
+            3 => Ok(Self::StreamConflict),
+            4 => Ok(Self::StreamSystemShutdown),
+            5 => Ok(Self::StreamPolicyViolation),
+            6 => Ok(Self::StreamError),
+            7 => Ok(Self::AccountDisabled),
+            8 => Ok(Self::TlsError),
+            9 => Ok(Self::ServerUnreachable),
+            10 => Ok(Self::CredentialsExpired),
             _ => Err(format_err!("Invalid ProseConnectionErrorType '{}'.", value)),
         }
     }
@@ -30,6 +46,8 @@ pub struct ConnectionError {
     pub kind: ConnectionErrorType,
     #[wasm_bindgen(skip)]
     pub message: String,
+    #[wasm_bindgen(skip)]
+    pub reconnect_after_secs: Option<u32>,
 }
 
 #[wasm_bindgen(js_class = "ProseConnectionError")]
@@ -43,6 +61,13 @@ impl ConnectionError {
     pub fn message(&self) -> String {
         self.message.clone()
     }
+
+    /// The number of seconds after which the client should try to reconnect. `undefined` if the
+    /// client should not reconnect automatically (e.g. because another session took over).
+    #[wasm_bindgen(getter, js_name = "reconnectAfterSecs")]
+    pub fn reconnect_after_secs(&self) -> Option<u32> {
+        self.reconnect_after_secs
+    }
 }
 
 impl From<ConnectionErrorType> for CoreConnectionError {
@@ -53,6 +78,33 @@ impl From<ConnectionErrorType> for CoreConnectionError {
             ConnectionErrorType::Generic => CoreConnectionError::Generic {
                 msg: "An unknown error occurred.".to_string(),
             },
+            ConnectionErrorType::StreamConflict => CoreConnectionError::StreamError {
+                reason: DisconnectReason::Conflict,
+            },
+            ConnectionErrorType::StreamSystemShutdown => CoreConnectionError::StreamError {
+                reason: DisconnectReason::SystemShutdown,
+            },
+            ConnectionErrorType::StreamPolicyViolation => CoreConnectionError::StreamError {
+                reason: DisconnectReason::PolicyViolation,
+            },
+            // Stream errors other than the ones above are reported with their condition, so that
+            // it isn't lost. See `ProseConnection.connect`.
+            ConnectionErrorType::StreamError => CoreConnectionError::Generic {
+                msg: "The server closed the connection without a stream error condition."
+                    .to_string(),
+            },
+            ConnectionErrorType::AccountDisabled => CoreConnectionError::Forbidden {
+                reason: ForbiddenReason::AccountDisabled,
+            },
+            ConnectionErrorType::CredentialsExpired => CoreConnectionError::Forbidden {
+                reason: ForbiddenReason::CredentialsExpired,
+            },
+            ConnectionErrorType::TlsError => CoreConnectionError::TlsError {
+                msg: "The TLS handshake failed.".to_string(),
+            },
+            ConnectionErrorType::ServerUnreachable => CoreConnectionError::ServerUnreachable {
+                msg: "The server could not be reached.".to_string(),
+            },
         }
     }
 }
@@ -63,15 +115,74 @@ impl From<CoreConnectionError> for ConnectionError {
             CoreConnectionError::TimedOut => Self {
                 kind: ConnectionErrorType::TimedOut,
                 message: "The connection timed out.".to_string(),
+                reconnect_after_secs: None,
             },
             CoreConnectionError::InvalidCredentials => Self {
                 kind: ConnectionErrorType::InvalidCredentials,
                 message: "Invalid credentials.".to_string(),
+                reconnect_after_secs: None,
+            },
+            CoreConnectionError::Forbidden {
+                reason: ForbiddenReason::AccountDisabled,
+            } => Self {
+                kind: ConnectionErrorType::AccountDisabled,
+                message: "The account was disabled.".to_string(),
+                reconnect_after_secs: None,
             },
+            CoreConnectionError::Forbidden {
+                reason: ForbiddenReason::CredentialsExpired,
+            } => Self {
+                kind: ConnectionErrorType::CredentialsExpired,
+                message: "The credentials have expired.".to_string(),
+                reconnect_after_secs: None,
+            },
+            CoreConnectionError::TlsError { msg } => Self {
+                kind: ConnectionErrorType::TlsError,
+                message: msg,
+                reconnect_after_secs: None,
+            },
+            CoreConnectionError::ServerUnreachable { msg } => Self {
+                kind: ConnectionErrorType::ServerUnreachable,
+                message: msg,
+                reconnect_after_secs: None,
+            },
+            CoreConnectionError::StreamError { reason } => Self::from(reason),
             CoreConnectionError::Generic { msg } => Self {
                 kind: ConnectionErrorType::Generic,
                 message: msg,
+                reconnect_after_secs: None,
             },
         }
     }
 }
+
+impl From<DisconnectReason> for ConnectionError {
+    fn from(reason: DisconnectReason) -> Self {
+        let reconnect_after_secs = reason.reconnect_delay().map(|delay| delay.as_secs() as u32);
+
+        let (kind, message) = match reason {
+            DisconnectReason::Conflict => (
+                ConnectionErrorType::StreamConflict,
+                "Another session took over the connection.".to_string(),
+            ),
+            DisconnectReason::SystemShutdown => (
+                ConnectionErrorType::StreamSystemShutdown,
+                "The server is shutting down.".to_string(),
+            ),
+            DisconnectReason::PolicyViolation => (
+                ConnectionErrorType::StreamPolicyViolation,
+                "The connection violated a server policy.".to_string(),
+            ),
+            DisconnectReason::Other { condition } => (
+                ConnectionErrorType::StreamError,
+                format!("The server closed the connection ({condition})."),
+            ),
+        };
+
+        Self {
+            kind,
+            message,
+            reconnect_after_secs,
+        }
+    }
+}
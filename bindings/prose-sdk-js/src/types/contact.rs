@@ -9,7 +9,7 @@ use prose_core_client::dtos::{
 };
 use wasm_bindgen::prelude::*;
 
-use super::{Avatar, BareJid};
+use super::{Avatar, BareJid, FallbackAvatar};
 
 #[wasm_bindgen]
 pub struct Contact(CoreContact);
@@ -95,6 +95,12 @@ impl Contact {
         self.0.avatar.clone().map(|avatar| avatar.into())
     }
 
+    /// The placeholder to display if `avatar` is undefined.
+    #[wasm_bindgen(getter, js_name = "fallbackAvatar")]
+    pub fn fallback_avatar(&self) -> FallbackAvatar {
+        self.0.fallback_avatar().into()
+    }
+
     #[wasm_bindgen(getter)]
     pub fn availability(&self) -> Availability {
         self.0.availability.clone().into()
@@ -108,6 +114,21 @@ impl Contact {
             .map(|activity| UserStatus(activity.clone()))
     }
 
+    /// The status message sent along with the contact's presence (e.g. "Back at 2pm").
+    #[wasm_bindgen(getter, js_name = "statusMessage")]
+    pub fn status_message(&self) -> Option<String> {
+        self.0.status_message.clone()
+    }
+
+    /// Since when the contact is idle if they're online, otherwise when they were last seen. Use
+    /// `loadLastActivity` on the client to ask the server of an offline contact.
+    #[wasm_bindgen(getter, js_name = "lastSeen")]
+    pub fn last_seen(&self) -> Option<js_sys::Date> {
+        self.0
+            .last_seen
+            .map(|ts| js_sys::Date::new(&JsValue::from(ts.timestamp_millis() as f64)))
+    }
+
     #[wasm_bindgen(getter)]
     pub fn group(&self) -> Group {
         self.0.group.clone().into()
@@ -0,0 +1,29 @@
+// prose-core-client/prose-sdk-js
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use prose_core_client::dtos::DeviceTrust as CoreDeviceTrust;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub enum DeviceTrust {
+    /// We haven't decided whether to trust the device yet.
+    Undecided = 0,
+    Untrusted = 1,
+    Trusted = 2,
+    /// The fingerprint of the device was verified.
+    Verified = 3,
+}
+
+impl From<DeviceTrust> for CoreDeviceTrust {
+    fn from(value: DeviceTrust) -> Self {
+        match value {
+            DeviceTrust::Undecided => CoreDeviceTrust::Undecided,
+            DeviceTrust::Untrusted => CoreDeviceTrust::Untrusted,
+            DeviceTrust::Trusted => CoreDeviceTrust::Trusted,
+            DeviceTrust::Verified => CoreDeviceTrust::Verified,
+        }
+    }
+}
@@ -0,0 +1,97 @@
+// prose-core-client/prose-sdk-js
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use tracing::error;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use prose_core_client::dtos::{self, ScalarRangeExt, StringIndexRangeExt};
+
+use crate::types::{IntoJSArray, Mention, MentionsArray};
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct Draft {
+    text: String,
+    mentions: Vec<Mention>,
+}
+
+#[wasm_bindgen]
+impl Draft {
+    #[wasm_bindgen(constructor)]
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            mentions: vec![],
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_text(&mut self, text: String) {
+        self.text = text
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn mentions(&self) -> MentionsArray {
+        self.mentions.iter().cloned().collect_into_js_array()
+    }
+
+    #[wasm_bindgen(js_name = "addMention")]
+    pub fn add_mention(&mut self, mention: Mention) {
+        self.mentions.push(mention)
+    }
+}
+
+impl TryFrom<Draft> for dtos::Draft {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Draft) -> Result<Self, Self::Error> {
+        let mentions = value
+            .mentions
+            .into_iter()
+            .map(|mention| {
+                Ok(dtos::Mention {
+                    user: mention.user.into(),
+                    range: mention
+                        .range
+                        .map(|r| r.to_scalar_range(&value.text))
+                        .transpose()?,
+                })
+            })
+            .collect::<Result<Vec<_>, Self::Error>>()?;
+
+        Ok(Self {
+            text: value.text.into(),
+            mentions,
+        })
+    }
+}
+
+impl From<dtos::Draft> for Draft {
+    fn from(value: dtos::Draft) -> Self {
+        let text = value.text.into_string();
+
+        let mentions = value
+            .mentions
+            .into_iter()
+            .filter_map(|mention| {
+                let Ok(range) = mention.range.map(|r| r.to_utf16_range(&text)).transpose() else {
+                    error!("Failed to convert mention range");
+                    return None;
+                };
+                Some(Mention {
+                    user: mention.user.into_inner().into(),
+                    range,
+                })
+            })
+            .collect();
+
+        Self { text, mentions }
+    }
+}
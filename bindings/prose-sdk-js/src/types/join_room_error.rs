@@ -0,0 +1,60 @@
+// prose-core-client/prose-sdk-js
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use prose_core_client::domain::rooms::models::RoomError;
+
+#[wasm_bindgen(js_name = "ProseJoinRoomErrorType")]
+#[derive(Clone)]
+pub enum JoinRoomErrorType {
+    /// The room requires a password or the provided password is incorrect.
+    InvalidPassword = 0,
+    /// The room is members-only and the user is not a member.
+    MembersOnly = 1,
+    /// The room has reached its maximum number of occupants.
+    RoomIsFull = 2,
+    /// The user is banned from the room.
+    Banned = 3,
+    Generic = 4,
+}
+
+#[wasm_bindgen(js_name = "ProseJoinRoomError")]
+pub struct JoinRoomError {
+    #[wasm_bindgen(skip)]
+    pub kind: JoinRoomErrorType,
+    #[wasm_bindgen(skip)]
+    pub message: String,
+}
+
+#[wasm_bindgen(js_class = "ProseJoinRoomError")]
+impl JoinRoomError {
+    #[wasm_bindgen(getter, js_name = "type")]
+    pub fn kind(&self) -> JoinRoomErrorType {
+        self.kind.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl From<anyhow::Error> for JoinRoomError {
+    fn from(value: anyhow::Error) -> Self {
+        let kind = match value.downcast_ref::<RoomError>() {
+            Some(RoomError::InvalidPassword) => JoinRoomErrorType::InvalidPassword,
+            Some(RoomError::MembersOnly) => JoinRoomErrorType::MembersOnly,
+            Some(RoomError::RoomIsFull) => JoinRoomErrorType::RoomIsFull,
+            Some(RoomError::Banned) => JoinRoomErrorType::Banned,
+            _ => JoinRoomErrorType::Generic,
+        };
+
+        Self {
+            kind,
+            message: value.to_string(),
+        }
+    }
+}
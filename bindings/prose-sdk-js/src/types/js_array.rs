@@ -40,6 +40,9 @@ extern "C" {
 
     #[wasm_bindgen(typescript_type = "MessageSender[]")]
     pub type MessageSendersArray;
+
+    #[wasm_bindgen(typescript_type = "ParticipantId[]")]
+    pub type ParticipantIdsArray;
 }
 
 impl From<Vec<prose_core_client::dtos::Message>> for MessagesArray {
@@ -34,6 +34,12 @@ pub struct Message {
     attachments: js_sys::Array,
     mentions: js_sys::Array,
     reply_to: Option<ReplyTo>,
+    thread_id: Option<String>,
+    thread_reply_count: u32,
+    last_thread_reply_at: Option<js_sys::Date>,
+    is_retracted: bool,
+    retraction_reason: Option<String>,
+    moderated_by: Option<MessageSender>,
 }
 
 #[wasm_bindgen]
@@ -60,9 +66,47 @@ pub struct MessageMetadata {
     pub is_transient: bool,
     #[wasm_bindgen(js_name = "isEncrypted")]
     pub is_encrypted: bool,
+    #[wasm_bindgen(js_name = "isUndecryptable")]
+    /// The message could not be decrypted on this device. Its body is empty, so a localized
+    /// placeholder should be shown instead. Decrypting it can be retried via
+    /// `Room.retryDecryption`.
+    pub is_undecryptable: bool,
+    #[wasm_bindgen(js_name = "senderDeviceId")]
+    /// The device that sent the encrypted message, if the message was encrypted.
+    pub sender_device_id: Option<u32>,
     #[wasm_bindgen(js_name = "isLastRead")]
     /// When contained in a list, this message is the last message that our user has read.
     pub is_last_read: bool,
+    #[wasm_bindgen(js_name = "isPending")]
+    /// The message was sent by our user but didn't reach the server yet.
+    pub is_pending: bool,
+    #[wasm_bindgen(js_name = "deliveryState")]
+    /// How far the message has travelled towards its recipient.
+    pub delivery_state: DeliveryState,
+    #[wasm_bindgen(getter_with_clone, js_name = "sendError")]
+    /// The reason why sending the message failed. The message can either be resent or discarded.
+    pub send_error: Option<String>,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub enum DeliveryState {
+    /// The message was sent by our user but didn't reach the server yet.
+    Sending = 0,
+    /// The message reached the server or, in a MUC room, was reflected back to us.
+    Sent = 1,
+    /// The recipient acknowledged the receipt of the message.
+    Delivered = 2,
+}
+
+impl From<dtos::DeliveryState> for DeliveryState {
+    fn from(value: dtos::DeliveryState) -> Self {
+        match value {
+            dtos::DeliveryState::Sending => DeliveryState::Sending,
+            dtos::DeliveryState::Sent => DeliveryState::Sent,
+            dtos::DeliveryState::Delivered => DeliveryState::Delivered,
+        }
+    }
 }
 
 impl From<dtos::Message> for Message {
@@ -98,7 +142,12 @@ impl From<dtos::Message> for Message {
                 is_edited: value.flags.is_edited,
                 is_transient: value.flags.is_transient,
                 is_encrypted: value.flags.is_encrypted,
+                is_undecryptable: value.flags.is_undecryptable,
+                sender_device_id: value.flags.sender_device_id.map(|id| id.into_inner()),
                 is_last_read: value.flags.is_last_read,
+                is_pending: value.flags.is_pending,
+                delivery_state: value.flags.delivery_state.into(),
+                send_error: value.flags.send_error,
             },
             reactions: value
                 .reactions
@@ -119,6 +168,14 @@ impl From<dtos::Message> for Message {
                     .map(|ts| js_sys::Date::new(&JsValue::from(ts.timestamp_millis() as f64))),
                 body: reply.body.map(|s| format!("<p>{s}</p>")),
             }),
+            thread_id: value.thread_id.map(|id| id.to_string()),
+            thread_reply_count: value.thread_reply_count,
+            last_thread_reply_at: value
+                .last_thread_reply_at
+                .map(|ts| js_sys::Date::new(&JsValue::from(ts.timestamp_millis() as f64))),
+            is_retracted: value.is_retracted,
+            retraction_reason: value.retraction_reason,
+            moderated_by: value.moderated_by.map(Into::into),
         }
     }
 }
@@ -195,6 +252,42 @@ impl Message {
     pub fn reply_to(&self) -> Option<ReplyTo> {
         self.reply_to.clone()
     }
+
+    #[wasm_bindgen(getter, js_name = "threadId")]
+    /// The thread this message belongs to, if any.
+    pub fn thread_id(&self) -> Option<String> {
+        self.thread_id.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = "threadReplyCount")]
+    /// The number of replies in the thread started by this message.
+    pub fn thread_reply_count(&self) -> u32 {
+        self.thread_reply_count
+    }
+
+    #[wasm_bindgen(getter, js_name = "lastThreadReplyAt")]
+    /// The time of the latest reply in the thread started by this message.
+    pub fn last_thread_reply_at(&self) -> Option<js_sys::Date> {
+        self.last_thread_reply_at.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = "isRetracted")]
+    /// The message was retracted and should be displayed as deleted.
+    pub fn is_retracted(&self) -> bool {
+        self.is_retracted
+    }
+
+    #[wasm_bindgen(getter, js_name = "retractionReason")]
+    /// The reason given for the retraction, if any.
+    pub fn retraction_reason(&self) -> Option<String> {
+        self.retraction_reason.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = "moderatedBy")]
+    /// The moderator who removed the message, if it wasn't retracted by its sender.
+    pub fn moderated_by(&self) -> Option<MessageSender> {
+        self.moderated_by.clone()
+    }
 }
 
 #[wasm_bindgen]
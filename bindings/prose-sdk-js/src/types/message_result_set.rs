@@ -5,7 +5,9 @@
 
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use prose_core_client::dtos::MessageResultSet as CoreMessageResultSet;
+use prose_core_client::dtos::{
+    DataSource, MessageCursor as CoreMessageCursor, MessageResultSet as CoreMessageResultSet,
+};
 
 use crate::types::MessagesArray;
 
@@ -20,11 +22,42 @@ impl MessageResultSet {
         self.0.messages.clone().into()
     }
 
-    /// Can be used to load more messages. `lastMessageId` might not be contained in `messages`.
-    /// If not set there are no more messages to load.
-    #[wasm_bindgen(getter, js_name = "lastMessageId")]
-    pub fn last_message_id(&self) -> Option<String> {
-        self.0.last_message_id.clone().map(|id| id.to_string())
+    /// Can be used to load older messages via `loadMessagesBefore`. If not set there are no
+    /// older messages to load.
+    #[wasm_bindgen(getter)]
+    pub fn before(&self) -> Option<MessageCursor> {
+        self.0.before.clone().map(MessageCursor)
+    }
+
+    /// Can be used to load newer messages via `loadMessagesAfter`. If not set `messages` reaches
+    /// up to the newest message of the room.
+    #[wasm_bindgen(getter)]
+    pub fn after(&self) -> Option<MessageCursor> {
+        self.0.after.clone().map(MessageCursor)
+    }
+
+    /// Whether there are no more messages to load in the direction in which `messages` were
+    /// loaded.
+    #[wasm_bindgen(getter, js_name = "isComplete")]
+    pub fn is_complete(&self) -> bool {
+        self.0.is_complete
+    }
+
+    /// Whether the messages were served from the local cache because the client is not
+    /// connected yet.
+    #[wasm_bindgen(getter, js_name = "isCached")]
+    pub fn is_cached(&self) -> bool {
+        self.0.source == DataSource::Cached
+    }
+}
+
+/// An opaque position in the message archive of a room.
+#[wasm_bindgen]
+pub struct MessageCursor(CoreMessageCursor);
+
+impl AsRef<CoreMessageCursor> for MessageCursor {
+    fn as_ref(&self) -> &CoreMessageCursor {
+        &self.0
     }
 }
 
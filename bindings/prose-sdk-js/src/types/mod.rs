@@ -6,20 +6,28 @@
 pub use account_info::AccountInfo;
 pub use attachment::{Attachment, Thumbnail};
 pub use channel::{Channel, ChannelsArray};
+pub use client_event::{
+    ClientEvent, ClientEventAsyncIterator, ClientEventIterator, ClientEventType,
+};
 pub use connection_error::{ConnectionError, ConnectionErrorType};
 pub use contact::{Availability, Contact, UserStatus};
+pub use device_trust::DeviceTrust;
+pub use draft::Draft;
 pub use jid::{BareJid, ParticipantId};
+pub use join_room_error::{JoinRoomError, JoinRoomErrorType};
 pub use js_array::*;
 pub use mention::Mention;
 pub use message::Message;
-pub use message_result_set::MessageResultSet;
+pub use message_result_set::{MessageCursor, MessageResultSet};
 pub use presence_sub_request::{PresenceSubRequest, PresenceSubRequestArray, PresenceSubRequestId};
-pub use room::RoomEnvelopeExt;
+pub use room::{RoomEnvelopeExt, RoomNotificationMode};
+pub use room_update::{RoomSubscription, RoomUpdate, RoomUpdateType};
 pub use send_message_request::SendMessageRequest;
 pub use sidebar_item::{SidebarItem, SidebarItemsArray};
-pub use upload_slot::UploadSlot;
+pub use upload_slot::{EncryptedFile, UploadSlot};
 pub use user_info::{
-    Avatar, ParticipantBasicInfo, ParticipantBasicInfoArray, ParticipantInfo, ParticipantInfoArray,
+    Avatar, AvatarSize, FallbackAvatar, ParticipantBasicInfo, ParticipantBasicInfoArray,
+    ParticipantInfo, ParticipantInfoArray, ParticipantProfile, ParticipantProfileArray,
     UserBasicInfo, UserBasicInfoArray,
 };
 pub use user_metadata::UserMetadata;
@@ -28,15 +36,20 @@ pub use user_profile::UserProfile;
 mod account_info;
 mod attachment;
 mod channel;
+mod client_event;
 mod connection_error;
 mod contact;
+mod device_trust;
+mod draft;
 mod jid;
+mod join_room_error;
 mod js_array;
 mod mention;
 mod message;
 mod message_result_set;
 mod presence_sub_request;
 mod room;
+mod room_update;
 mod send_message_request;
 mod sidebar_item;
 mod upload_slot;
@@ -8,16 +8,20 @@ use tracing::debug;
 use wasm_bindgen::prelude::wasm_bindgen;
 use wasm_bindgen::{JsError, JsValue};
 
-use prose_core_client::dtos::{MessageId, RoomEnvelope, RoomState as SdkRoomState};
+use prose_core_client::dtos::{
+    HistoryImportSummary as SdkHistoryImportSummary, MessageId, MucId, OccupantId, RoomEnvelope,
+    RoomNotificationMode as SdkRoomNotificationMode, RoomState as SdkRoomState,
+};
 use prose_core_client::services::{
     DirectMessage, Generic, Group, PrivateChannel, PublicChannel, Room as SdkRoom,
 };
 
 use crate::error::WasmError;
 use crate::types::{
-    try_user_id_vec_from_string_array, MessageResultSet, MessagesArray, ParticipantBasicInfo,
-    ParticipantBasicInfoArray, ParticipantInfo, ParticipantInfoArray, SendMessageRequest,
-    StringArray,
+    try_user_id_vec_from_string_array, BareJid, Draft, MessageCursor, MessageResultSet,
+    MessagesArray, ParticipantBasicInfo, ParticipantBasicInfoArray, ParticipantInfo,
+    ParticipantInfoArray, ParticipantProfile, ParticipantProfileArray, RoomSubscription,
+    RoomUpdate, SendMessageRequest, StringArray,
 };
 
 use super::IntoJSArray;
@@ -46,37 +50,83 @@ export interface RoomStateDisconnected extends RoomState {
     readonly canRetry?: boolean;
 }
 
+export interface RoomStateInvited extends RoomState {
+    type: RoomStateType.Invited
+    readonly invitedBy: JID;
+    readonly invitationReason?: string;
+}
+
 export interface RoomBase {
     readonly type: RoomType;
     readonly state: RoomState;
     readonly id: RoomID;
     readonly name: string;
     readonly participants: ParticipantInfo[];
+    readonly participantCount: number;
+
+    loadParticipantsWithProfiles(): Promise<ParticipantProfile[]>;
+    loadParticipants(offset: number, limit: number): Promise<ParticipantInfo[]>;
+
+    /// Calls `handler` for every update of this room until `unsubscribe` is called on the
+    /// returned subscription.
+    subscribe(handler: (update: RoomUpdate) => void): RoomSubscription;
 
     sendMessage(request: SendMessageRequest): Promise<void>;
     updateMessage(messageID: string, request: SendMessageRequest): Promise<void>;
-    retractMessage(messageID: string): Promise<void>;
+    retractMessage(messageID: string, reason?: string): Promise<void>;
     toggleReactionToMessage(id: string, emoji: string): Promise<void>;
+    /// Like `toggleReactionToMessage` but uses `currentEmojis` (the reactions we've already sent to
+    /// the message) instead of loading the message first.
+    setMyReactionsFromKnown(id: string, currentEmojis: string[] | undefined, emoji: string): Promise<void>;
     
     loadLatestMessages(): Promise<MessageResultSet>;
-    loadMessagesBefore(before: string): Promise<MessageResultSet>;
+    loadMessagesBefore(before: MessageCursor): Promise<MessageResultSet>;
+    loadMessagesAfter(after: MessageCursor): Promise<MessageResultSet>;
     loadMessagesWithIDs(messageIDs: string[]): Promise<Message[]>;
     loadUnreadMessages(): Promise<MessageResultSet>;
+    searchMessages(query: string): Promise<Message[]>;
+    importHistory(data: Uint8Array, progress?: (summary: HistoryImportSummary) => void): Promise<HistoryImportSummary>;
+    
+    pinMessage(messageID: string): Promise<void>;
+    unpinMessage(messageID: string): Promise<void>;
+    loadPinnedMessages(): Promise<Message[]>;
+    
+    resendFailedMessage(messageID: string): Promise<void>;
+    discardFailedMessage(messageID: string): Promise<void>;
+    retryDecryption(messageID: string): Promise<void>;
     
     setUserIsComposing(isComposing: boolean): Promise<void>;
     loadComposingUsers(): Promise<ParticipantBasicInfo[]>;
     
-    saveDraft(message?: string): Promise<void>;
-    loadDraft(): Promise<string>;
+    saveDraft(draft?: Draft): Promise<void>;
+    loadDraft(): Promise<Draft | undefined>;
     
     markAsRead(): Promise<void>;
+    markMessageAsRead(messageID: string): Promise<void>;
+    markAsReadUntil(messageID: string): Promise<void>;
     setLastReadMessage(messageID: string): Promise<void>;
+    
+    readonly notificationMode: RoomNotificationMode;
+    /// Controls which messages are counted as unread. Synced across devices.
+    setNotificationMode(mode: RoomNotificationMode): Promise<void>;
+    
+    readonly sendReadReceipts: boolean;
+    /// Controls whether read markers are sent to this room. Synced across devices.
+    setSendReadReceipts(enabled: boolean): Promise<void>;
 }
 
 export interface RoomMUC {
     readonly subject?: string;
     
     setTopic(topic?: string): Promise<void>;
+    /// Pass the full JID of the occupant, i.e. 'room@conference.prose.org/nickname'.
+    sendPrivateMessage(occupantId: string, request: SendMessageRequest): Promise<void>;
+    /// Removes the message of another occupant. Requires moderator privileges and a room that
+    /// supports message moderation.
+    moderateMessage(messageID: string, reason?: string): Promise<void>;
+    /// Destroys the room and redirects its occupants to `alternateRoom` if set. Requires owner
+    /// privileges.
+    destroy(alternateRoom?: JID, reason?: string): Promise<void>;
 }
 
 export interface RoomMutableName {
@@ -126,12 +176,21 @@ pub enum RoomType {
     Generic = 4,
 }
 
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub enum RoomNotificationMode {
+    All = 0,
+    MentionsOnly = 1,
+    None = 2,
+}
+
 #[wasm_bindgen]
 #[derive(Debug, Clone)]
 pub enum RoomStateType {
     Connecting = 0,
     Connected = 1,
     Disconnected = 2,
+    Invited = 3,
 }
 
 #[wasm_bindgen(skip_typescript)]
@@ -169,6 +228,8 @@ pub struct RoomState {
     kind: RoomStateType,
     error: Option<String>,
     can_retry: bool,
+    invited_by: Option<BareJid>,
+    invitation_reason: Option<String>,
 }
 
 #[wasm_bindgen]
@@ -187,6 +248,36 @@ impl RoomState {
     pub fn can_retry(&self) -> bool {
         self.can_retry
     }
+
+    #[wasm_bindgen(getter, js_name = "invitedBy")]
+    pub fn invited_by(&self) -> Option<BareJid> {
+        self.invited_by.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = "invitationReason")]
+    pub fn invitation_reason(&self) -> Option<String> {
+        self.invitation_reason.clone()
+    }
+}
+
+impl From<SdkRoomNotificationMode> for RoomNotificationMode {
+    fn from(value: SdkRoomNotificationMode) -> Self {
+        match value {
+            SdkRoomNotificationMode::All => Self::All,
+            SdkRoomNotificationMode::MentionsOnly => Self::MentionsOnly,
+            SdkRoomNotificationMode::None => Self::None,
+        }
+    }
+}
+
+impl From<RoomNotificationMode> for SdkRoomNotificationMode {
+    fn from(value: RoomNotificationMode) -> Self {
+        match value {
+            RoomNotificationMode::All => Self::All,
+            RoomNotificationMode::MentionsOnly => Self::MentionsOnly,
+            RoomNotificationMode::None => Self::None,
+        }
+    }
 }
 
 impl From<SdkRoomState> for RoomState {
@@ -196,16 +287,29 @@ impl From<SdkRoomState> for RoomState {
                 kind: RoomStateType::Connecting,
                 error: None,
                 can_retry: false,
+                invited_by: None,
+                invitation_reason: None,
             },
             SdkRoomState::Connected => Self {
                 kind: RoomStateType::Connected,
                 error: None,
                 can_retry: false,
+                invited_by: None,
+                invitation_reason: None,
             },
             SdkRoomState::Disconnected { error, can_retry } => Self {
                 kind: RoomStateType::Disconnected,
                 error,
                 can_retry,
+                invited_by: None,
+                invitation_reason: None,
+            },
+            SdkRoomState::Invited { sender, reason, .. } => Self {
+                kind: RoomStateType::Invited,
+                error: None,
+                can_retry: false,
+                invited_by: Some(BareJid::from(sender.into_inner())),
+                invitation_reason: reason,
             },
         }
     }
@@ -248,6 +352,50 @@ macro_rules! base_room_impl {
                     .collect_into_js_array::<ParticipantInfoArray>()
             }
 
+            #[wasm_bindgen(getter, js_name = "participantCount")]
+            pub fn participant_count(&self) -> usize {
+                self.room.participant_count()
+            }
+
+            #[wasm_bindgen(js_name = "loadParticipants")]
+            pub async fn load_participants(
+                &self,
+                offset: usize,
+                limit: usize,
+            ) -> Result<ParticipantInfoArray> {
+                Ok(self
+                    .room
+                    .load_participants(offset, limit)
+                    .await
+                    .map_err(WasmError::from)?
+                    .into_iter()
+                    .map(ParticipantInfo::from)
+                    .collect_into_js_array::<ParticipantInfoArray>())
+            }
+
+            #[wasm_bindgen(js_name = "loadParticipantsWithProfiles")]
+            pub async fn load_participants_with_profiles(&self) -> Result<ParticipantProfileArray> {
+                Ok(self
+                    .room
+                    .load_participants_with_profiles()
+                    .await
+                    .map_err(WasmError::from)?
+                    .into_iter()
+                    .map(ParticipantProfile::from)
+                    .collect_into_js_array::<ParticipantProfileArray>())
+            }
+
+            pub fn subscribe(&self, handler: js_sys::Function) -> RoomSubscription {
+                self.room
+                    .subscribe_with_handler(Box::new(move |update| {
+                        let update = JsValue::from(RoomUpdate::from(update));
+                        if let Err(err) = handler.call1(&JsValue::NULL, &update) {
+                            tracing::error!("Room update handler threw an error. {:?}", err);
+                        }
+                    }))
+                    .into()
+            }
+
             #[wasm_bindgen(js_name = "sendMessage")]
             pub async fn send_message(&self, request: SendMessageRequest) -> Result<()> {
                 debug!("Sending message…");
@@ -258,6 +406,32 @@ macro_rules! base_room_impl {
                 Ok(())
             }
 
+            #[wasm_bindgen(js_name = "replyInThread")]
+            pub async fn reply_in_thread(
+                &self,
+                parent_id: &str,
+                request: SendMessageRequest,
+            ) -> Result<()> {
+                self.room
+                    .reply_in_thread(
+                        parent_id.into(),
+                        request.try_into().map_err(WasmError::from)?,
+                    )
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
+            #[wasm_bindgen(js_name = "loadThread")]
+            pub async fn load_thread(&self, thread_id: &str) -> Result<MessagesArray> {
+                let messages = self
+                    .room
+                    .load_thread(&thread_id.into())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(messages.into())
+            }
+
             #[wasm_bindgen(js_name = "updateMessage")]
             pub async fn update_message(
                 &self,
@@ -275,9 +449,13 @@ macro_rules! base_room_impl {
             }
 
             #[wasm_bindgen(js_name = "retractMessage")]
-            pub async fn retract_message(&self, message_id: &str) -> Result<()> {
+            pub async fn retract_message(
+                &self,
+                message_id: &str,
+                reason: Option<String>,
+            ) -> Result<()> {
                 self.room
-                    .retract_message(message_id.into())
+                    .retract_message(message_id.into(), reason.as_deref())
                     .await
                     .map_err(WasmError::from)?;
                 Ok(())
@@ -292,6 +470,25 @@ macro_rules! base_room_impl {
                 Ok(())
             }
 
+            #[wasm_bindgen(js_name = "setMyReactionsFromKnown")]
+            pub async fn set_my_reactions_from_known(
+                &self,
+                id: &str,
+                current_emojis: Option<StringArray>,
+                emoji: &str,
+            ) -> Result<()> {
+                let current_emojis = current_emojis
+                    .map(|emojis| Vec::<String>::try_from(&emojis))
+                    .transpose()?
+                    .map(|emojis| emojis.into_iter().map(Into::into).collect());
+
+                self.room
+                    .set_my_reactions_from_known(id.into(), current_emojis, emoji.into())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
             #[wasm_bindgen(js_name = "loadLatestMessages")]
             pub async fn load_latest_messages(&self) -> Result<MessageResultSet> {
                 let messages = self
@@ -306,10 +503,13 @@ macro_rules! base_room_impl {
             }
 
             #[wasm_bindgen(js_name = "loadMessagesBefore")]
-            pub async fn load_messages_before(&self, message_id: &str) -> Result<MessageResultSet> {
+            pub async fn load_messages_before(
+                &self,
+                cursor: &MessageCursor,
+            ) -> Result<MessageResultSet> {
                 let messages = self
                     .room
-                    .load_messages_before(&message_id.into())
+                    .load_messages_before(cursor.as_ref())
                     .await
                     .map_err(WasmError::from)?;
 
@@ -318,6 +518,19 @@ macro_rules! base_room_impl {
                 Ok(messages.into())
             }
 
+            #[wasm_bindgen(js_name = "loadMessagesAfter")]
+            pub async fn load_messages_after(
+                &self,
+                cursor: &MessageCursor,
+            ) -> Result<MessageResultSet> {
+                let messages = self
+                    .room
+                    .load_messages_after(cursor.as_ref())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(messages.into())
+            }
+
             #[wasm_bindgen(js_name = "loadUnreadMessages")]
             pub async fn load_unread_messages(&self) -> Result<MessageResultSet> {
                 let messages = self
@@ -353,6 +566,94 @@ macro_rules! base_room_impl {
                 Ok(messages.into())
             }
 
+            #[wasm_bindgen(js_name = "searchMessages")]
+            pub async fn search_messages(&self, query: String) -> Result<MessagesArray> {
+                let messages = self
+                    .room
+                    .search_messages(&query)
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(messages.into())
+            }
+
+            /// Imports a JSON-lines history dump (see `ImportedMessage`) into the local cache.
+            /// `progress` is called after each batch of imported messages.
+            #[wasm_bindgen(js_name = "importHistory")]
+            pub async fn import_history(
+                &self,
+                data: &[u8],
+                progress: Option<js_sys::Function>,
+            ) -> Result<HistoryImportSummary> {
+                let summary = self
+                    .room
+                    .import_history(data, |summary| {
+                        let Some(progress) = &progress else {
+                            return;
+                        };
+                        let summary = JsValue::from(HistoryImportSummary::from(summary.clone()));
+                        if let Err(err) = progress.call1(&JsValue::NULL, &summary) {
+                            tracing::error!(
+                                "History import progress handler threw an error. {:?}",
+                                err
+                            );
+                        }
+                    })
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(summary.into())
+            }
+
+            #[wasm_bindgen(js_name = "pinMessage")]
+            pub async fn pin_message(&self, message_id: &str) -> Result<()> {
+                self.room
+                    .pin_message(message_id.into())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
+            #[wasm_bindgen(js_name = "unpinMessage")]
+            pub async fn unpin_message(&self, message_id: &str) -> Result<()> {
+                self.room
+                    .unpin_message(message_id.into())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
+            #[wasm_bindgen(js_name = "loadPinnedMessages")]
+            pub async fn pinned_messages(&self) -> Result<MessagesArray> {
+                let messages = self.room.pinned_messages().await.map_err(WasmError::from)?;
+                Ok(messages.into())
+            }
+
+            #[wasm_bindgen(js_name = "resendFailedMessage")]
+            pub async fn resend_failed_message(&self, message_id: &str) -> Result<()> {
+                self.room
+                    .resend_failed_message(message_id.into())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
+            #[wasm_bindgen(js_name = "retryDecryption")]
+            pub async fn retry_decryption(&self, message_id: &str) -> Result<()> {
+                self.room
+                    .retry_decryption(&message_id.into())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
+            #[wasm_bindgen(js_name = "discardFailedMessage")]
+            pub async fn discard_failed_message(&self, message_id: &str) -> Result<()> {
+                self.room
+                    .discard_failed_message(message_id.into())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
             #[wasm_bindgen(js_name = "setUserIsComposing")]
             pub async fn set_user_is_composing(&self, is_composing: bool) -> Result<()> {
                 self.room
@@ -375,17 +676,47 @@ macro_rules! base_room_impl {
             }
 
             #[wasm_bindgen(js_name = "saveDraft")]
-            pub async fn save_draft(&self, message: Option<String>) -> Result<()> {
+            pub async fn save_draft(&self, draft: Option<Draft>) -> Result<()> {
                 self.room
-                    .save_draft(message.as_deref())
+                    .save_draft(
+                        draft
+                            .map(TryInto::try_into)
+                            .transpose()
+                            .map_err(WasmError::from)?,
+                    )
                     .await
                     .map_err(WasmError::from)?;
                 Ok(())
             }
 
             #[wasm_bindgen(js_name = "loadDraft")]
-            pub async fn load_draft(&self) -> Result<Option<String>> {
-                Ok(self.room.load_draft().await.map_err(WasmError::from)?)
+            pub async fn load_draft(&self) -> Result<Option<Draft>> {
+                Ok(self
+                    .room
+                    .load_draft()
+                    .await
+                    .map_err(WasmError::from)?
+                    .map(Draft::from))
+            }
+
+            #[wasm_bindgen(getter, js_name = "notificationMode")]
+            pub fn notification_mode(&self) -> RoomNotificationMode {
+                self.room.notification_mode().into()
+            }
+
+            #[wasm_bindgen(js_name = "setNotificationMode")]
+            pub async fn set_notification_mode(&self, mode: RoomNotificationMode) {
+                self.room.set_notification_mode(mode.into()).await
+            }
+
+            #[wasm_bindgen(getter, js_name = "sendReadReceipts")]
+            pub fn send_read_receipts(&self) -> bool {
+                self.room.send_read_receipts()
+            }
+
+            #[wasm_bindgen(js_name = "setSendReadReceipts")]
+            pub async fn set_send_read_receipts(&self, enabled: bool) {
+                self.room.set_send_read_receipts(enabled).await
             }
 
             #[wasm_bindgen(js_name = "markAsRead")]
@@ -393,6 +724,24 @@ macro_rules! base_room_impl {
                 Ok(self.room.mark_as_read().await.map_err(WasmError::from)?)
             }
 
+            #[wasm_bindgen(js_name = "markMessageAsRead")]
+            pub async fn mark_message_as_read(&self, message_id: &str) -> Result<()> {
+                self.room
+                    .mark_message_as_read(message_id.into())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
+            #[wasm_bindgen(js_name = "markAsReadUntil")]
+            pub async fn mark_as_read_until(&self, message_id: &str) -> Result<()> {
+                self.room
+                    .mark_as_read_until(&message_id.into())
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
             #[wasm_bindgen(js_name = "setLastReadMessage")]
             pub async fn set_last_read_message(&self, message_id: &str) -> Result<()> {
                 self.room
@@ -419,6 +768,48 @@ macro_rules! muc_room_impl {
                 self.room.set_topic(topic).await.map_err(WasmError::from)?;
                 Ok(())
             }
+
+            #[wasm_bindgen(js_name = "sendPrivateMessage")]
+            pub async fn send_private_message(
+                &self,
+                occupant_id: &str,
+                request: SendMessageRequest,
+            ) -> Result<()> {
+                let occupant_id = OccupantId::from(occupant_id.parse::<jid::FullJid>()?);
+                self.room
+                    .send_private_message(
+                        &occupant_id,
+                        request.try_into().map_err(WasmError::from)?,
+                    )
+                    .await
+                    .map_err(WasmError::from)?;
+                Ok(())
+            }
+
+            #[wasm_bindgen(js_name = "moderateMessage")]
+            pub async fn moderate_message(
+                &self,
+                message_id: &str,
+                reason: Option<String>,
+            ) -> Result<()> {
+                self.room
+                    .moderate_message(message_id.into(), reason.as_deref())
+                    .await
+                    .map_err(|err| WasmError::from(anyhow::Error::from(err)))?;
+                Ok(())
+            }
+
+            pub async fn destroy(
+                &self,
+                alternate_room: Option<BareJid>,
+                reason: Option<String>,
+            ) -> Result<()> {
+                self.room
+                    .destroy(alternate_room.map(MucId::from), reason.as_deref())
+                    .await
+                    .map_err(|err| WasmError::from(anyhow::Error::from(err)))?;
+                Ok(())
+            }
         }
     };
 }
@@ -535,3 +926,21 @@ impl RoomEnvelopeExt for RoomEnvelope {
         }
     }
 }
+
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct HistoryImportSummary {
+    pub imported: u32,
+    pub skipped: u32,
+    pub failed: u32,
+}
+
+impl From<SdkHistoryImportSummary> for HistoryImportSummary {
+    fn from(value: SdkHistoryImportSummary) -> Self {
+        Self {
+            imported: value.imported,
+            skipped: value.skipped,
+            failed: value.failed,
+        }
+    }
+}
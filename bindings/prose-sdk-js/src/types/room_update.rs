@@ -0,0 +1,138 @@
+// prose-core-client/prose-sdk-js
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use prose_core_client::dtos::ParticipantId as SdkParticipantId;
+use prose_core_client::services::{RoomSubscriptionHandle, RoomUpdate as SdkRoomUpdate};
+use prose_core_client::ClientRoomEventType;
+
+use crate::types::{
+    IntoJSArray, IntoJSStringArray, ParticipantId, ParticipantIdsArray, StringArray,
+};
+
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub enum RoomUpdateType {
+    MessagesAppended = 0,
+    MessagesUpdated = 1,
+    MessagesDeleted = 2,
+    MessagesNeedReload = 3,
+    AttributesChanged = 4,
+    ParticipantsChanged = 5,
+    ComposingUsersChanged = 6,
+    PinnedMessagesChanged = 7,
+    Destroyed = 8,
+    RemovedFromRoom = 9,
+    EncryptionRecipientsChanged = 10,
+    InvitationReceived = 11,
+    PrivateMessagesAppended = 12,
+    NotificationRequested = 13,
+}
+
+#[wasm_bindgen]
+pub struct RoomUpdate {
+    kind: RoomUpdateType,
+    message_ids: Vec<String>,
+    participant_ids: Vec<SdkParticipantId>,
+}
+
+#[wasm_bindgen]
+impl RoomUpdate {
+    #[wasm_bindgen(getter, js_name = "type")]
+    pub fn kind(&self) -> RoomUpdateType {
+        self.kind.clone()
+    }
+
+    /// The affected messages for `MessagesAppended`, `MessagesUpdated`, `MessagesDeleted`,
+    /// `PrivateMessagesAppended` and `NotificationRequested`.
+    #[wasm_bindgen(getter, js_name = "messageIDs")]
+    pub fn message_ids(&self) -> StringArray {
+        self.message_ids.iter().collect_into_js_string_array()
+    }
+
+    /// The affected participants for `ParticipantsChanged`.
+    #[wasm_bindgen(getter, js_name = "participantIDs")]
+    pub fn participant_ids(&self) -> ParticipantIdsArray {
+        self.participant_ids
+            .iter()
+            .cloned()
+            .map(ParticipantId::from)
+            .collect_into_js_array()
+    }
+}
+
+/// Keeps a room subscription alive until `unsubscribe` is called or the object is freed.
+#[wasm_bindgen]
+pub struct RoomSubscription {
+    handle: Option<RoomSubscriptionHandle>,
+}
+
+#[wasm_bindgen]
+impl RoomSubscription {
+    pub fn unsubscribe(&mut self) {
+        self.handle.take();
+    }
+}
+
+impl From<RoomSubscriptionHandle> for RoomSubscription {
+    fn from(value: RoomSubscriptionHandle) -> Self {
+        Self {
+            handle: Some(value),
+        }
+    }
+}
+
+impl From<SdkRoomUpdate> for RoomUpdate {
+    fn from(value: SdkRoomUpdate) -> Self {
+        let mut participant_ids = vec![];
+
+        let (kind, message_ids) = match value {
+            ClientRoomEventType::MessagesAppended { message_ids } => {
+                (RoomUpdateType::MessagesAppended, message_ids)
+            }
+            ClientRoomEventType::MessagesUpdated { message_ids } => {
+                (RoomUpdateType::MessagesUpdated, message_ids)
+            }
+            ClientRoomEventType::MessagesDeleted { message_ids } => {
+                (RoomUpdateType::MessagesDeleted, message_ids)
+            }
+            ClientRoomEventType::MessagesNeedReload => (RoomUpdateType::MessagesNeedReload, vec![]),
+            ClientRoomEventType::AttributesChanged => (RoomUpdateType::AttributesChanged, vec![]),
+            ClientRoomEventType::ParticipantsChanged { ids } => {
+                participant_ids = ids;
+                (RoomUpdateType::ParticipantsChanged, vec![])
+            }
+            ClientRoomEventType::ComposingUsersChanged => {
+                (RoomUpdateType::ComposingUsersChanged, vec![])
+            }
+            ClientRoomEventType::PinnedMessagesChanged => {
+                (RoomUpdateType::PinnedMessagesChanged, vec![])
+            }
+            ClientRoomEventType::Destroyed => (RoomUpdateType::Destroyed, vec![]),
+            ClientRoomEventType::RemovedFromRoom { .. } => {
+                (RoomUpdateType::RemovedFromRoom, vec![])
+            }
+            ClientRoomEventType::EncryptionRecipientsChanged => {
+                (RoomUpdateType::EncryptionRecipientsChanged, vec![])
+            }
+            ClientRoomEventType::InvitationReceived { .. } => {
+                (RoomUpdateType::InvitationReceived, vec![])
+            }
+            ClientRoomEventType::PrivateMessagesAppended { message_ids, .. } => {
+                (RoomUpdateType::PrivateMessagesAppended, message_ids)
+            }
+            ClientRoomEventType::NotificationRequested { message_ids } => {
+                (RoomUpdateType::NotificationRequested, message_ids)
+            }
+        };
+
+        Self {
+            kind,
+            message_ids: message_ids.into_iter().map(|id| id.into_inner()).collect(),
+            participant_ids,
+        }
+    }
+}
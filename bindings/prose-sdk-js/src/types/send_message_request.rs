@@ -22,6 +22,10 @@ pub struct SendMessageRequest {
     body: Option<SendMessageRequestBody>,
     /// The URLs of the files to attach to the message.
     attachments: Vec<Attachment>,
+    /// The ID of the message this message replies to.
+    reply_to: Option<String>,
+    /// The ID of the thread to send this message to.
+    thread_id: Option<String>,
 }
 
 #[wasm_bindgen]
@@ -69,6 +73,8 @@ impl SendMessageRequest {
         Self {
             body: None,
             attachments: vec![],
+            reply_to: None,
+            thread_id: None,
         }
     }
 
@@ -124,6 +130,26 @@ impl SendMessageRequest {
 
         self.attachments = typed_array;
     }
+
+    #[wasm_bindgen(getter, js_name = "replyTo")]
+    pub fn reply_to(&self) -> Option<String> {
+        self.reply_to.clone()
+    }
+
+    #[wasm_bindgen(setter, js_name = "replyTo")]
+    pub fn set_reply_to(&mut self, reply_to: Option<String>) {
+        self.reply_to = reply_to;
+    }
+
+    #[wasm_bindgen(getter, js_name = "threadId")]
+    pub fn thread_id(&self) -> Option<String> {
+        self.thread_id.clone()
+    }
+
+    #[wasm_bindgen(setter, js_name = "threadId")]
+    pub fn set_thread_id(&mut self, thread_id: Option<String>) {
+        self.thread_id = thread_id;
+    }
 }
 
 impl TryFrom<SendMessageRequestBody> for dtos::SendMessageRequestBody {
@@ -147,6 +173,8 @@ impl TryFrom<SendMessageRequest> for dtos::SendMessageRequest {
                 .into_iter()
                 .map(TryFrom::try_from)
                 .collect::<Result<Vec<_>, _>>()?,
+            reply_to: value.reply_to.map(Into::into),
+            thread_id: value.thread_id.map(Into::into),
         })
     }
 }
@@ -10,7 +10,7 @@ use prose_core_client::dtos::{RoomEnvelope, SidebarItem as SidebarItemDTO};
 use prose_core_client::Client;
 
 use crate::error::WasmError;
-use crate::types::RoomEnvelopeExt;
+use crate::types::{RoomEnvelopeExt, RoomNotificationMode};
 
 #[wasm_bindgen]
 #[derive(Debug, Clone)]
@@ -30,6 +30,8 @@ export interface SidebarItem {
     readonly hasDraft: boolean;
     readonly unreadCount: number;
     readonly mentionsCount: number;
+    readonly notificationMode: RoomNotificationMode;
+    readonly orderIndex?: number;
     
     toggleFavorite(): Promise<void>;
     removeFromSidebar(): Promise<void>;
@@ -93,6 +95,16 @@ impl SidebarItem {
     pub fn mentions_count(&self) -> u32 {
         self.dto.mentions_count
     }
+
+    #[wasm_bindgen(getter, js_name = "notificationMode")]
+    pub fn notification_mode(&self) -> RoomNotificationMode {
+        self.dto.notification_mode.into()
+    }
+
+    #[wasm_bindgen(getter, js_name = "orderIndex")]
+    pub fn order_index(&self) -> Option<u32> {
+        self.dto.order_index
+    }
 }
 
 #[wasm_bindgen]
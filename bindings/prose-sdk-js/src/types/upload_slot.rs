@@ -5,10 +5,12 @@
 
 use mime::Mime;
 use url::Url;
-use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::prelude::{wasm_bindgen, JsError};
 
 use prose_core_client::dtos;
+use prose_core_client::dtos::AttachmentEncryption;
 
+use crate::error::WasmError;
 use crate::types::{IntoJSArray, UploadHeadersArray};
 
 #[wasm_bindgen]
@@ -58,6 +60,46 @@ impl UploadSlot {
     pub fn file_size(&self) -> u64 {
         self.file_size
     }
+
+    /// Use after uploading `file` to this slot. Replaces `downloadURL` with an `aesgcm://` URL
+    /// that contains the key to decrypt the file with, so that attachments created from this
+    /// slot can be decrypted by the recipients.
+    #[wasm_bindgen(js_name = "setEncryption")]
+    pub fn set_encryption(&mut self, file: &EncryptedFile) -> Result<(), JsError> {
+        self.download_url = file
+            .encryption
+            .encrypted_url(&self.download_url)
+            .map_err(WasmError::from)?;
+        Ok(())
+    }
+}
+
+/// A file that was encrypted with a fresh key for uploading it to a room that has encryption
+/// enabled (XEP-0454). Request an upload slot for the size of `data`, upload `data` and call
+/// `UploadSlot.setEncryption` afterwards.
+#[wasm_bindgen]
+pub struct EncryptedFile {
+    data: Vec<u8>,
+    encryption: AttachmentEncryption,
+}
+
+#[wasm_bindgen]
+impl EncryptedFile {
+    /// Encrypts `data`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(data: &[u8]) -> Result<EncryptedFile, JsError> {
+        let encryption = AttachmentEncryption::generate();
+        Ok(Self {
+            data: encryption.encrypt(data).map_err(WasmError::from)?,
+            encryption,
+        })
+    }
+
+    /// The encrypted data to upload.
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
 }
 
 #[wasm_bindgen]
@@ -6,10 +6,11 @@
 use wasm_bindgen::prelude::wasm_bindgen;
 
 use prose_core_client::dtos::{
-    Avatar as SdkAvatar, JabberClient as SdkJabberClient,
-    ParticipantBasicInfo as SdkParticipantBasicInfo, ParticipantInfo as SdkParticipantInfo,
-    RoomAffiliation as SdkRoomAffiliation, UserBasicInfo as SdkUserBasicInfo,
-    UserPresenceInfo as SdkUserPresenceInfo,
+    Avatar as SdkAvatar, AvatarSize as SdkAvatarSize, FallbackAvatar as SdkFallbackAvatar,
+    JabberClient as SdkJabberClient, ParticipantBasicInfo as SdkParticipantBasicInfo,
+    ParticipantInfo as SdkParticipantInfo, ParticipantProfile as SdkParticipantProfile,
+    RoomAffiliation as SdkRoomAffiliation, RoomRole as SdkRoomRole,
+    UserBasicInfo as SdkUserBasicInfo, UserPresenceInfo as SdkUserPresenceInfo,
 };
 
 use crate::types::{Availability, BareJid, ParticipantId, UserStatus};
@@ -18,10 +19,25 @@ use crate::types::{Availability, BareJid, ParticipantId, UserStatus};
 #[derive(Clone)]
 pub struct Avatar(SdkAvatar);
 
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct FallbackAvatar(SdkFallbackAvatar);
+
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct JabberClient(SdkJabberClient);
 
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AvatarSize {
+    /// Fits into 32x32 pixels.
+    Small = 0,
+    /// Fits into 64x64 pixels.
+    Medium = 1,
+    /// The avatar as it was cached.
+    Full = 2,
+}
+
 #[wasm_bindgen]
 #[derive(Clone)]
 pub enum RoomAffiliation {
@@ -32,6 +48,15 @@ pub enum RoomAffiliation {
     Owner = 4,
 }
 
+#[wasm_bindgen]
+#[derive(Clone)]
+pub enum RoomRole {
+    None = 0,
+    Visitor = 1,
+    Participant = 2,
+    Moderator = 3,
+}
+
 #[wasm_bindgen]
 pub struct UserBasicInfo(SdkUserBasicInfo);
 
@@ -44,6 +69,9 @@ pub struct ParticipantBasicInfo(SdkParticipantBasicInfo);
 #[wasm_bindgen]
 pub struct ParticipantInfo(SdkParticipantInfo);
 
+#[wasm_bindgen]
+pub struct ParticipantProfile(SdkParticipantProfile);
+
 #[wasm_bindgen]
 impl Avatar {
     #[wasm_bindgen(getter)]
@@ -55,6 +83,21 @@ impl Avatar {
     }
 }
 
+#[wasm_bindgen]
+impl FallbackAvatar {
+    /// The background color as `#rrggbb`.
+    #[wasm_bindgen(getter)]
+    pub fn color(&self) -> String {
+        self.0.color.clone()
+    }
+
+    /// Up to two initials of the user's name.
+    #[wasm_bindgen(getter)]
+    pub fn initials(&self) -> String {
+        self.0.initials.clone()
+    }
+}
+
 #[wasm_bindgen]
 impl JabberClient {
     #[wasm_bindgen(js_name = "toString")]
@@ -176,6 +219,12 @@ impl ParticipantInfo {
         self.0.avatar.clone().map(Into::into)
     }
 
+    /// The placeholder to display if `avatar` is undefined.
+    #[wasm_bindgen(getter, js_name = "fallbackAvatar")]
+    pub fn fallback_avatar(&self) -> FallbackAvatar {
+        self.0.fallback_avatar().into()
+    }
+
     #[wasm_bindgen(getter)]
     pub fn client(&self) -> Option<JabberClient> {
         self.0.client.clone().map(Into::into)
@@ -188,12 +237,71 @@ impl ParticipantInfo {
     }
 }
 
+#[wasm_bindgen]
+impl ParticipantProfile {
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> ParticipantId {
+        self.0.id.clone().into()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn jid(&self) -> Option<BareJid> {
+        self.0.user_id.clone().map(|id| id.into_inner().into())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.0.name.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = "fullName")]
+    pub fn full_name(&self) -> Option<String> {
+        self.0.full_name.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = "isSelf")]
+    pub fn is_self(&self) -> bool {
+        self.0.is_self
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn availability(&self) -> Availability {
+        self.0.availability.into()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn affiliation(&self) -> RoomAffiliation {
+        self.0.affiliation.into()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn role(&self) -> RoomRole {
+        self.0.role.into()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn avatar(&self) -> Option<Avatar> {
+        self.0.avatar.clone().map(Into::into)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn status(&self) -> Option<UserStatus> {
+        self.0.status.clone().map(Into::into)
+    }
+}
+
 impl From<SdkAvatar> for Avatar {
     fn from(value: SdkAvatar) -> Self {
         Self(value)
     }
 }
 
+impl From<SdkFallbackAvatar> for FallbackAvatar {
+    fn from(value: SdkFallbackAvatar) -> Self {
+        Self(value)
+    }
+}
+
 impl From<SdkJabberClient> for JabberClient {
     fn from(value: SdkJabberClient) -> Self {
         Self(value)
@@ -230,6 +338,22 @@ impl From<SdkParticipantInfo> for ParticipantInfo {
     }
 }
 
+impl From<SdkParticipantProfile> for ParticipantProfile {
+    fn from(value: SdkParticipantProfile) -> Self {
+        Self(value)
+    }
+}
+
+impl From<AvatarSize> for SdkAvatarSize {
+    fn from(value: AvatarSize) -> Self {
+        match value {
+            AvatarSize::Small => SdkAvatarSize::Small,
+            AvatarSize::Medium => SdkAvatarSize::Medium,
+            AvatarSize::Full => SdkAvatarSize::Full,
+        }
+    }
+}
+
 impl From<SdkRoomAffiliation> for RoomAffiliation {
     fn from(value: SdkRoomAffiliation) -> Self {
         match value {
@@ -242,6 +366,17 @@ impl From<SdkRoomAffiliation> for RoomAffiliation {
     }
 }
 
+impl From<SdkRoomRole> for RoomRole {
+    fn from(value: SdkRoomRole) -> Self {
+        match value {
+            SdkRoomRole::None => RoomRole::None,
+            SdkRoomRole::Visitor => RoomRole::Visitor,
+            SdkRoomRole::Participant => RoomRole::Participant,
+            SdkRoomRole::Moderator => RoomRole::Moderator,
+        }
+    }
+}
+
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(typescript_type = "UserBasicInfo[]")]
@@ -255,4 +390,7 @@ extern "C" {
 
     #[wasm_bindgen(typescript_type = "ParticipantInfo[]")]
     pub type ParticipantInfoArray;
+
+    #[wasm_bindgen(typescript_type = "ParticipantProfile[]")]
+    pub type ParticipantProfileArray;
 }
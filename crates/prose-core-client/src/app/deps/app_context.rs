@@ -3,14 +3,23 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::HashSet;
+use std::time::Duration;
+
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use jid::BareJid;
-use parking_lot::{MappedRwLockReadGuard, RwLock, RwLockReadGuard};
+use parking_lot::{MappedRwLockReadGuard, Mutex, RwLock, RwLockReadGuard};
 
-use crate::domain::connection::models::{ConnectionProperties, HttpUploadService, ServerFeatures};
+use crate::domain::account::models::AutoAway;
+use crate::domain::connection::models::{
+    ConnectionProperties, HttpUploadService, ReconnectPolicy, ReconnectState, ServerFeatures,
+};
+use crate::domain::encryption::models::TrustPolicy;
 use crate::domain::general::models::{Capabilities, SoftwareVersion};
-use crate::domain::shared::models::{AccountId, ConnectionState};
+use crate::domain::messaging::models::MessageId;
+use crate::domain::settings::models::NotificationPolicy;
+use crate::domain::shared::models::{AccountId, ConnectionState, RetentionPolicy};
 use crate::dtos::{DecryptionContext, MucId, UserResourceId};
 
 #[derive(Debug, Clone)]
@@ -21,14 +30,86 @@ pub struct AppConfig {
     pub max_message_pages_to_load: u32,
     /// The maximum duration to fetch messages into the past during catchup.
     pub max_catchup_duration_secs: i64,
+    /// The maximum size in bytes of a serialized message stanza. Larger messages are rejected
+    /// before being sent, since servers usually close the stream when receiving them.
+    pub max_stanza_size: usize,
+    /// Whether cached messages that cannot be read (e.g. because they were written by a newer
+    /// version of the client) should be skipped instead of failing the whole query.
+    pub skip_unreadable_cached_messages: bool,
+    /// The policy that decides which OMEMO devices of a recipient outgoing messages are
+    /// encrypted for. Falls back to `TrustPolicy::default()` if not set.
+    pub omemo_trust_policy: Option<TrustPolicy>,
+    /// The number of OMEMO PreKeys to keep available in our device bundle. PreKeys use the
+    /// contiguous IDs `1..=omemo_pre_key_count`.
+    pub omemo_pre_key_count: u32,
+    /// The minimum interval between two publishes of our device bundle after PreKeys were used.
+    /// PreKeys used within that interval are regenerated right away but published together.
+    pub pre_key_publish_debounce_interval_secs: i64,
+    /// Controls the automatic reconnect after the connection was lost. The client stays
+    /// disconnected if not set.
+    pub reconnect_policy: Option<ReconnectPolicy>,
+    /// Whether to enable XEP-0198 Stream Management, which allows resuming the session after a
+    /// short disconnect without losing messages.
+    pub stream_management_enabled: bool,
+    /// The maximum number of participants per room to keep in memory. Owners, admins and
+    /// recently active participants are preferred, the remaining ones are loaded on demand via
+    /// `Room::load_participants`. Unbounded if not set.
+    pub max_cached_participants: Option<usize>,
+    /// The maximum size in bytes of an avatar image.
+    ///
+    /// Avatars are checked against both this limit and `max_avatar_dimension` before anything is
+    /// uploaded. `max_avatar_size` limits the encoded image data, `max_avatar_dimension` its width
+    /// and height in pixels. Images passed to `set_avatar` must satisfy both. Images passed to
+    /// `set_avatar_from_data` or `set_avatar_from_url` are first scaled down to
+    /// `max_avatar_dimension` and re-encoded as JPEG, so only their encoded size can exceed a
+    /// limit.
+    pub max_avatar_size: usize,
+    /// The maximum width and height in pixels of an avatar image. See `max_avatar_size`.
+    pub max_avatar_dimension: u32,
+    /// The priority sent with our presence. When the same account is connected from multiple
+    /// devices, the server routes messages to the resource with the highest priority.
+    pub presence_priority: i8,
+    /// The number of seconds after which a participant whose last chat state was 'composing'
+    /// is no longer considered to be typing. When our user stops typing for that long without
+    /// saying so, we send a 'paused' chat state on their behalf.
+    pub composing_timeout_secs: i64,
+    /// The minimum number of seconds between two 'composing' chat states sent to a room while
+    /// our user keeps typing.
+    pub composing_refresh_interval_secs: i64,
+    /// The number of messages that are loaded and written at once when exporting the history
+    /// of a room.
+    pub history_export_page_size: u32,
+    /// The number of messages that are written in a single transaction when importing the
+    /// history of a room.
+    pub history_import_batch_size: u32,
+    /// The policy applied to the cache by `CacheService::run_maintenance`. The cache is only
+    /// pruned of expired items if not set.
+    pub retention_policy: Option<RetentionPolicy>,
+    /// The number of messages that are deleted in a single transaction when applying a
+    /// `RetentionPolicy`.
+    pub retention_delete_batch_size: u32,
+    /// Controls when `ClientRoomEventType::NotificationRequested` is dispatched for received
+    /// messages. No notifications are requested if not set.
+    pub notification_policy: Option<NotificationPolicy>,
 }
 
 pub struct AppContext {
     pub connection_properties: RwLock<Option<ConnectionProperties>>,
     pub connection_state: RwLock<ConnectionState>,
+    /// The account whose cached data is served while the client is not connected.
+    pub cached_account: RwLock<Option<AccountId>>,
+    pub auto_away: Mutex<AutoAway>,
+    pub reconnect_state: Mutex<ReconnectState>,
+    /// The round-trip time of the last ping answered by the server.
+    pub ping_round_trip_time: RwLock<Option<Duration>>,
+    /// The messages of the outbox that were sent in the current session, but which the server
+    /// didn't acknowledge (or reflect) yet. They're not resent unless the session is lost.
+    pub unconfirmed_message_ids: Mutex<HashSet<MessageId>>,
     pub capabilities: Capabilities,
     pub software_version: SoftwareVersion,
     pub config: AppConfig,
+    /// The schema version of the cache the client was built with.
+    pub cache_schema_version: u32,
 }
 
 impl AppContext {
@@ -36,13 +117,20 @@ impl AppContext {
         capabilities: Capabilities,
         software_version: SoftwareVersion,
         config: AppConfig,
+        cache_schema_version: u32,
     ) -> Self {
         Self {
             connection_properties: Default::default(),
             connection_state: Default::default(),
+            cached_account: Default::default(),
+            auto_away: Default::default(),
+            reconnect_state: Default::default(),
+            ping_round_trip_time: Default::default(),
+            unconfirmed_message_ids: Default::default(),
             capabilities,
             software_version,
             config,
+            cache_schema_version,
         }
     }
 }
@@ -53,6 +141,24 @@ impl Default for AppConfig {
             message_page_size: 100,
             max_message_pages_to_load: 5,
             max_catchup_duration_secs: 60 * 60 * 24 * 5,
+            max_stanza_size: 256 * 1024,
+            skip_unreadable_cached_messages: true,
+            omemo_trust_policy: None,
+            omemo_pre_key_count: 100,
+            pre_key_publish_debounce_interval_secs: 5,
+            reconnect_policy: None,
+            stream_management_enabled: false,
+            max_cached_participants: None,
+            max_avatar_size: 512 * 1024,
+            max_avatar_dimension: 1024,
+            presence_priority: 0,
+            composing_timeout_secs: 30,
+            composing_refresh_interval_secs: 10,
+            history_export_page_size: 500,
+            history_import_batch_size: 250,
+            retention_policy: None,
+            retention_delete_batch_size: 500,
+            notification_policy: None,
         }
     }
 }
@@ -78,6 +184,21 @@ impl AppContext {
         ))
     }
 
+    /// Returns the connected account or, if the client is not connected, the account whose cached
+    /// data was loaded via `set_cached_account`.
+    pub fn account(&self) -> Result<AccountId> {
+        if let Ok(account) = self.connected_account() {
+            return Ok(account);
+        }
+        self.cached_account.read().clone().ok_or(anyhow::anyhow!(
+            "Failed to read the user's account since the client is not connected."
+        ))
+    }
+
+    pub fn set_cached_account(&self, account: Option<AccountId>) {
+        *self.cached_account.write() = account;
+    }
+
     pub fn connection_timestamp(&self) -> Result<DateTime<Utc>> {
         self.connection_properties
             .read()
@@ -25,7 +25,7 @@ use crate::domain::encryption::services::{
 };
 use crate::domain::general::services::RequestHandlingService;
 use crate::domain::messaging::repos::{
-    DraftsRepository, MessagesRepository, OfflineMessagesRepository,
+    DraftsRepository, MessagesRepository, OfflineMessagesRepository, PendingMessagesRepository,
 };
 use crate::domain::messaging::services::{MessageArchiveDomainService, MessageIdProvider};
 use crate::domain::messaging::services::{
@@ -38,6 +38,7 @@ use crate::domain::rooms::services::{
 };
 use crate::domain::settings::repos::{AccountSettingsRepository, LocalRoomSettingsRepository};
 use crate::domain::settings::services::SyncedRoomSettingsService;
+use crate::domain::sidebar::repos::BookmarksRepository;
 use crate::domain::sidebar::services::{BookmarksService, SidebarDomainService};
 use crate::domain::uploads::services::UploadService;
 use crate::domain::user_info::repos::{
@@ -52,6 +53,7 @@ pub type DynAvatarRepository = Arc<dyn AvatarRepository>;
 pub type DynBlockListDomainService = Arc<dyn BlockListDomainService>;
 pub type DynBlockListRepository = Arc<dyn BlockListRepository>;
 pub type DynBlockListService = Arc<dyn BlockListService>;
+pub type DynBookmarksRepository = Arc<dyn BookmarksRepository>;
 pub type DynBookmarksService = Arc<dyn BookmarksService>;
 pub type DynClientEventDispatcher = Arc<dyn ClientEventDispatcherTrait>;
 pub type DynConnectedRoomsReadOnlyRepository = Arc<dyn ConnectedRoomsReadOnlyRepository>;
@@ -73,6 +75,7 @@ pub type DynMessageMigrationDomainService = Arc<dyn MessageMigrationDomainServic
 pub type DynMessagesRepository = Arc<dyn MessagesRepository>;
 pub type DynMessagingService = Arc<dyn MessagingService>;
 pub type DynOfflineMessagesRepository = Arc<dyn OfflineMessagesRepository>;
+pub type DynPendingMessagesRepository = Arc<dyn PendingMessagesRepository>;
 pub type DynPresenceSubRequestsRepository = Arc<dyn PresenceSubRequestsRepository>;
 pub type DynRequestHandlingService = Arc<dyn RequestHandlingService>;
 pub type DynRngProvider = Arc<dyn RngProvider>;
@@ -114,6 +117,7 @@ pub struct AppDependencies {
     pub messages_repo: DynMessagesRepository,
     pub messaging_service: DynMessagingService,
     pub offline_messages_repo: DynOfflineMessagesRepository,
+    pub pending_messages_repo: DynPendingMessagesRepository,
     pub request_handling_service: DynRequestHandlingService,
     pub rng_provider: DynRngProvider,
     pub room_attributes_service: DynRoomAttributesService,
@@ -122,6 +126,7 @@ pub struct AppDependencies {
     pub room_participation_service: DynRoomParticipationService,
     pub rooms_domain_service: DynRoomsDomainService,
     pub server_event_handler_queue: DynServerEventHandlerQueue,
+    pub session_repo: DynSessionRepository,
     pub short_id_provider: DynIDProvider,
     pub sidebar_domain_service: DynSidebarDomainService,
     pub time_provider: DynTimeProvider,
@@ -129,4 +134,5 @@ pub struct AppDependencies {
     pub user_account_service: DynUserAccountService,
     pub user_device_repo: DynUserDeviceRepository,
     pub user_info_domain_service: DynUserInfoDomainService,
+    pub user_profile_repo: DynUserProfileRepository,
 }
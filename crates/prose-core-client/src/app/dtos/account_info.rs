@@ -12,4 +12,5 @@ pub struct AccountInfo {
     pub avatar: Option<Avatar>,
     pub availability: Availability,
     pub status: Option<UserStatus>,
+    pub status_message: Option<String>,
 }
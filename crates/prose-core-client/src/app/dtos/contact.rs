@@ -3,9 +3,11 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use chrono::{DateTime, Utc};
+
 use crate::domain::contacts::models::PresenceSubscription;
 use crate::domain::shared::models::{Availability, UserId};
-use crate::domain::user_info::models::UserStatus;
+use crate::domain::user_info::models::{FallbackAvatar, UserStatus};
 use crate::dtos::Avatar;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -22,6 +24,17 @@ pub struct Contact {
     pub avatar: Option<Avatar>,
     pub availability: Availability,
     pub status: Option<UserStatus>,
+    pub status_message: Option<String>,
+    /// Since when the contact is idle if they're online, otherwise when we've last seen them or
+    /// their server reported them to be last active.
+    pub last_seen: Option<DateTime<Utc>>,
     pub group: Group,
     pub presence_subscription: PresenceSubscription,
 }
+
+impl Contact {
+    /// The placeholder to display if `avatar` is `None`.
+    pub fn fallback_avatar(&self) -> FallbackAvatar {
+        FallbackAvatar::for_user(&self.id, Some(&self.name))
+    }
+}
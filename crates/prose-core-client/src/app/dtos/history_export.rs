@@ -0,0 +1,163 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::domain::messaging::models::{Attachment, AttachmentKind};
+use crate::dtos::{Message, MessageSender, Reaction};
+
+/// The format in which the history of a room is exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A single `ExportedHistory` JSON document.
+    Json,
+    /// One line per message, followed by indented lines for its attachments and reactions.
+    PlainText,
+}
+
+/// A range of time including both `start` and `end`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateRange {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// The root of a JSON export. Fields are only ever added to this schema. Changes that would
+/// break existing readers increment `version`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedHistory {
+    pub version: u32,
+    pub room_id: String,
+    pub room_name: Option<String>,
+    /// Sorted chronologically.
+    pub messages: Vec<ExportedMessage>,
+}
+
+impl ExportedHistory {
+    pub const VERSION: u32 = 1;
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedMessage {
+    pub id: String,
+    pub from: ExportedSender,
+    pub timestamp: DateTime<Utc>,
+    /// The latest version of the body. `None` if the message was retracted or could not be
+    /// decrypted.
+    pub body: Option<String>,
+    /// The message was corrected after it was sent.
+    pub is_edited: bool,
+    pub is_retracted: bool,
+    /// The message could not be decrypted, so its body was left out.
+    pub is_redacted: bool,
+    pub attachments: Vec<ExportedAttachment>,
+    pub reactions: Vec<ExportedReaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedSender {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedAttachment {
+    pub kind: AttachmentKind,
+    pub url: Url,
+    pub media_type: String,
+    pub file_name: String,
+    pub file_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedReaction {
+    pub emoji: String,
+    pub from: Vec<ExportedSender>,
+}
+
+impl From<Message> for ExportedMessage {
+    fn from(value: Message) -> Self {
+        let is_redacted = value.flags.is_undecryptable;
+
+        Self {
+            id: value.id.to_string(),
+            from: value.from.into(),
+            timestamp: value.timestamp,
+            body: (!value.is_retracted && !is_redacted).then_some(value.body.raw),
+            is_edited: value.flags.is_edited,
+            is_retracted: value.is_retracted,
+            is_redacted,
+            attachments: value.attachments.into_iter().map(Into::into).collect(),
+            reactions: value.reactions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<MessageSender> for ExportedSender {
+    fn from(value: MessageSender) -> Self {
+        Self {
+            id: value.id.to_string(),
+            name: value.name,
+        }
+    }
+}
+
+impl From<Attachment> for ExportedAttachment {
+    fn from(value: Attachment) -> Self {
+        Self {
+            kind: value.r#type.kind(),
+            url: value.url,
+            media_type: value.media_type.to_string(),
+            file_name: value.file_name,
+            file_size: value.file_size,
+        }
+    }
+}
+
+impl From<Reaction> for ExportedReaction {
+    fn from(value: Reaction) -> Self {
+        Self {
+            emoji: value.emoji.to_string(),
+            from: value.from.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl ExportedMessage {
+    /// Formats the message for a plaintext export.
+    pub fn to_plain_text(&self) -> String {
+        let body = match (&self.body, self.is_retracted) {
+            (Some(body), _) => body.as_str(),
+            (None, true) => "<retracted>",
+            (None, false) => "<redacted>",
+        };
+
+        let mut text = format!(
+            "[{}] {}: {}{}\n",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+            self.from.name,
+            body,
+            if self.is_edited { " (edited)" } else { "" }
+        );
+
+        for attachment in &self.attachments {
+            text.push_str(&format!("    Attachment: {}\n", attachment.url));
+        }
+
+        for reaction in &self.reactions {
+            let names = reaction
+                .from
+                .iter()
+                .map(|sender| sender.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ");
+            text.push_str(&format!("    {}: {}\n", reaction.emoji, names));
+        }
+
+        text
+    }
+}
@@ -0,0 +1,66 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::messaging::models::{Attachment, AttachmentKind, AttachmentType};
+use crate::dtos::ExportedAttachment;
+
+/// A single line of a JSON-lines history dump as accepted by `Room::import_history`, e.g.
+///
+/// ```json
+/// {"stanza_id":"x2JfW5","id":"b94e","from":"jane@prose.org","timestamp":"2024-03-01T10:00:00Z","body":"Hello"}
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportedMessage {
+    /// The ID assigned by the server (XEP-0359). Messages whose stanza ID is cached already are
+    /// skipped.
+    pub stanza_id: Option<String>,
+    /// The ID assigned by the sender. Messages without a stanza ID are skipped if a message with
+    /// this ID is cached already.
+    pub id: Option<String>,
+    /// The real JID of the sender or, in a MUC room, their occupant JID.
+    pub from: String,
+    pub timestamp: DateTime<Utc>,
+    pub body: String,
+    #[serde(default)]
+    pub attachments: Vec<ExportedAttachment>,
+}
+
+/// The outcome of a history import. Passed to the progress callback after each batch as well.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryImportSummary {
+    pub imported: u32,
+    /// Messages that were cached already or occurred more than once in the dump.
+    pub skipped: u32,
+    /// Lines that could not be parsed, e.g. because they're not valid UTF-8 or JSON.
+    pub failed: u32,
+}
+
+impl TryFrom<ExportedAttachment> for Attachment {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ExportedAttachment) -> Result<Self> {
+        let r#type = match value.kind {
+            AttachmentKind::Audio => AttachmentType::Audio { duration: None },
+            AttachmentKind::Image => AttachmentType::Image { thumbnail: None },
+            AttachmentKind::Video => AttachmentType::Video {
+                duration: None,
+                thumbnail: None,
+            },
+            AttachmentKind::File => AttachmentType::File,
+        };
+
+        Ok(Self {
+            r#type,
+            url: value.url,
+            media_type: value.media_type.parse()?,
+            file_name: value.file_name,
+            file_size: value.file_size,
+        })
+    }
+}
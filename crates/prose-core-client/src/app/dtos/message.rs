@@ -5,9 +5,10 @@
 
 use chrono::{DateTime, Utc};
 
-use crate::domain::messaging::models::MessageId;
+use crate::domain::encryption::models::DeviceId;
+use crate::domain::messaging::models::{MessageId, ThreadId};
 use crate::domain::shared::models::ParticipantId;
-use crate::dtos::{Attachment, Avatar, Body, Emoji, Mention};
+use crate::dtos::{Attachment, Avatar, Body, DeviceTrust, Emoji, Mention};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Message {
@@ -20,17 +21,74 @@ pub struct Message {
     pub attachments: Vec<Attachment>,
     pub mentions: Vec<Mention>,
     pub reply_to: Option<ReplyTo>,
+    /// The thread (XEP-0201) this message belongs to.
+    pub thread_id: Option<ThreadId>,
+    /// The number of replies in the thread started by this message.
+    pub thread_reply_count: u32,
+    /// The time of the latest reply in the thread started by this message.
+    pub last_thread_reply_at: Option<DateTime<Utc>>,
+    /// The message was retracted by its sender or removed by a moderator. Its contents were
+    /// cleared, but it's kept as a tombstone in the conversation.
+    pub is_retracted: bool,
+    /// The reason given for the retraction, if any.
+    pub retraction_reason: Option<String>,
+    /// The moderator who removed the message, if it wasn't retracted by its sender.
+    pub moderated_by: Option<MessageSender>,
+}
+
+impl Message {
+    /// Returns the reactions sorted by the number of senders, most popular first. Reactions
+    /// with the same number of senders are sorted by the time they were last updated, most
+    /// recent first.
+    pub fn reactions_sorted_by_count(&self) -> Vec<&Reaction> {
+        let mut reactions = self.reactions.iter().collect::<Vec<_>>();
+        reactions.sort_by(|lhs, rhs| {
+            rhs.from
+                .len()
+                .cmp(&lhs.from.len())
+                .then_with(|| rhs.last_updated.cmp(&lhs.last_updated))
+        });
+        reactions
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct MessageFlags {
     pub is_read: bool,
     pub is_edited: bool,
-    pub is_delivered: bool,
+    pub delivery_state: DeliveryState,
     pub is_transient: bool,
     pub is_encrypted: bool,
+    /// Our trust in the device that sent the encrypted message. `None` if the message wasn't
+    /// encrypted or the trust is unknown. Can be used to flag messages from unverified devices.
+    pub sender_device_trust: Option<DeviceTrust>,
+    /// The device that sent the encrypted message. `None` if the message wasn't encrypted.
+    pub sender_device_id: Option<DeviceId>,
+    /// The message could not be decrypted. Its body is empty, so a placeholder should be shown
+    /// instead, e.g. naming `sender_device_id`. Decrypting it can be retried via
+    /// `Room::retry_decryption`.
+    pub is_undecryptable: bool,
     /// When contained in a list, this message is the last message that our user has read.
     pub is_last_read: bool,
+    /// The message was sent by our user but didn't reach the server yet.
+    pub is_pending: bool,
+    /// The reason why sending the message failed permanently. The message can either be resent
+    /// via `Room::resend_failed_message` or discarded via `Room::discard_failed_message`.
+    pub send_error: Option<String>,
+}
+
+/// How far an outgoing message has travelled towards its recipient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeliveryState {
+    /// The message was sent by our user but didn't reach the server yet.
+    Sending,
+    /// The message reached the server. For messages in a MUC room this is the case once the
+    /// room reflected it back to us.
+    #[default]
+    Sent,
+    /// The recipient acknowledged the message via a delivery receipt (XEP-0184) or a chat
+    /// marker (XEP-0333).
+    Delivered,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -44,6 +102,8 @@ pub struct MessageSender {
 pub struct Reaction {
     pub emoji: Emoji,
     pub from: Vec<MessageSender>,
+    /// The time when a sender was last added to this reaction, if known.
+    pub last_updated: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -3,16 +3,59 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::fmt::{Display, Formatter};
+
 use super::Message;
-use crate::dtos::MessageId;
+use crate::dtos::MessageServerId;
 
 #[derive(Debug, PartialEq)]
 pub struct MessageResultSet {
     /// The requested messages in the order from oldest to newest.
     pub messages: Vec<Message>,
-    /// Can be used to load more messages. `last_message_id` might not be contained in `messages`.
-    /// If not set there are no more messages to load.
-    pub last_message_id: Option<MessageId>,
+    /// Can be used to load older messages via `Room::load_messages_before`. The cursor might
+    /// point to a message which is not contained in `messages`. If not set there are no older
+    /// messages to load.
+    pub before: Option<MessageCursor>,
+    /// Can be used to load newer messages via `Room::load_messages_after`. If not set
+    /// `messages` reaches up to the newest message of the room.
+    pub after: Option<MessageCursor>,
+    /// Whether there are no more messages to load in the direction in which `messages`
+    /// were loaded.
+    pub is_complete: bool,
+    /// Whether the messages were loaded from the server or served from the local cache while
+    /// the client is not connected.
+    pub source: DataSource,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DataSource {
+    /// The data is up-to-date with the server.
+    #[default]
+    Live,
+    /// The data was served from the local cache and might be outdated.
+    Cached,
+}
+
+/// An opaque position in the message archive of a room.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageCursor(MessageServerId);
+
+impl MessageCursor {
+    pub fn server_id(&self) -> &MessageServerId {
+        &self.0
+    }
+}
+
+impl From<MessageServerId> for MessageCursor {
+    fn from(value: MessageServerId) -> Self {
+        Self(value)
+    }
+}
+
+impl Display for MessageCursor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
 }
 
 impl IntoIterator for MessageResultSet {
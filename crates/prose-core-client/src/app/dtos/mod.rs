@@ -7,8 +7,13 @@ pub use url::Url;
 
 pub use account_info::AccountInfo;
 pub use contact::{Contact, Group};
-pub use message::{Message, MessageFlags, MessageSender, Reaction, ReplyTo};
-pub use message_result_set::MessageResultSet;
+pub use history_export::{
+    DateRange, ExportFormat, ExportedAttachment, ExportedHistory, ExportedMessage,
+    ExportedReaction, ExportedSender,
+};
+pub use history_import::{HistoryImportSummary, ImportedMessage};
+pub use message::{DeliveryState, Message, MessageFlags, MessageSender, Reaction, ReplyTo};
+pub use message_result_set::{DataSource, MessageCursor, MessageResultSet};
 pub use presence_sub_request::{PresenceSubRequest, PresenceSubRequestId};
 pub use room_envelope::RoomEnvelope;
 pub use send_message_request::{Body as SendMessageRequestBody, SendMessageRequest};
@@ -21,29 +26,38 @@ pub use crate::domain::sidebar::models::Bookmark;
 pub use crate::domain::{
     contacts::models::PresenceSubscription,
     encryption::models::{
-        DecryptionContext, DeviceBundle, DeviceId, DeviceInfo, IdentityKey, IdentityKeyPair,
-        LocalEncryptionBundle, PreKey, PreKeyBundle, PreKeyId, PrivateKey, PublicKey, SessionData,
-        SignedPreKey, SignedPreKeyId, Trust as DeviceTrust,
+        DecryptionContext, DeviceBundle, DeviceId, DeviceInfo, Fingerprint, IdentityKey,
+        IdentityKeyPair, LocalEncryptionBundle, PreKey, PreKeyBundle, PreKeyId, PrivateKey,
+        PublicKey, SessionData, SignedPreKey, SignedPreKeyId, Trust as DeviceTrust, TrustPolicy,
     },
     general::models::SoftwareVersion,
     messaging::models::{
-        Attachment, AttachmentType, Body, Emoji, EncryptedPayload, EncryptionKey, Mention,
-        MessageId, MessageRemoteId, MessageServerId, Thumbnail,
+        Attachment, AttachmentEncryption, AttachmentKind, AttachmentType, Body, Draft, Emoji,
+        EncryptedPayload, EncryptionKey, Mention, MessageId, MessageRemoteId, MessageServerId,
+        ThreadId, Thumbnail,
+    },
+    rooms::models::{
+        Participant, PublicRoomInfo, RoomAffiliation, RoomConfiguration, RoomRemovalReason,
+        RoomRole, RoomState,
     },
-    rooms::models::{Participant, PublicRoomInfo, RoomAffiliation, RoomState},
+    settings::models::{NotificationPolicy, RoomNotificationMode},
     shared::models::{
-        AccountId, Availability, Markdown, MucId, OccupantId, ParticipantBasicInfo, ParticipantId,
-        ParticipantInfo, RoomId, ScalarRangeExt, StringIndexRangeExt, UnicodeScalarIndex,
-        UserBasicInfo, UserId, UserPresenceInfo, UserResourceId, Utf16Index, Utf8Index, HTML,
+        AccountId, Availability, CacheUsageReport, Markdown, MucId, OccupantId,
+        ParticipantBasicInfo, ParticipantId, ParticipantInfo, ParticipantProfile, RetentionPolicy,
+        RoomId, ScalarRangeExt, StoreUsage, StringIndexRangeExt, UnicodeScalarIndex, UserBasicInfo,
+        UserId, UserPresenceInfo, UserResourceId, Utf16Index, Utf8Index, HTML,
     },
-    uploads::models::UploadHeader,
+    uploads::models::{UploadError, UploadHandle, UploadHeader, UploadSource},
     user_info::models::{
-        Avatar, AvatarSource, JabberClient, LastActivity, UserInfo, UserMetadata, UserStatus,
+        Avatar, AvatarSize, AvatarSource, FallbackAvatar, JabberClient, LastActivity, PruneStats,
+        UserInfo, UserMetadata, UserStatus,
     },
 };
 
 mod account_info;
 mod contact;
+mod history_export;
+mod history_import;
 mod message;
 mod message_result_set;
 mod presence_sub_request;
@@ -3,12 +3,16 @@
 // Copyright: 2024, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use super::{Attachment, Markdown};
+use super::{Attachment, Markdown, MessageId, ThreadId};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct SendMessageRequest {
     pub body: Option<Body>,
     pub attachments: Vec<Attachment>,
+    /// The ID of the message this message replies to (XEP-0461).
+    pub reply_to: Option<MessageId>,
+    /// The thread (XEP-0201) to send this message to.
+    pub thread_id: Option<ThreadId>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -5,7 +5,7 @@
 
 use std::fmt::{Debug, Formatter};
 
-use crate::dtos::RoomEnvelope;
+use crate::dtos::{RoomEnvelope, RoomNotificationMode};
 
 #[derive(Clone, PartialEq)]
 pub struct SidebarItem {
@@ -15,6 +15,9 @@ pub struct SidebarItem {
     pub has_draft: bool,
     pub unread_count: u32,
     pub mentions_count: u32,
+    pub notification_mode: RoomNotificationMode,
+    /// The position of the item in the manually ordered section of the sidebar, if any.
+    pub order_index: Option<u32>,
 }
 
 impl Debug for SidebarItem {
@@ -27,6 +30,8 @@ impl Debug for SidebarItem {
             .field("has_draft", &self.has_draft)
             .field("unread_count", &self.unread_count)
             .field("mentions_count", &self.mentions_count)
+            .field("notification_mode", &self.notification_mode)
+            .field("order_index", &self.order_index)
             .finish()
     }
 }
@@ -3,10 +3,12 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use super::UploadHeader;
+use anyhow::Result;
 use mime::Mime;
 use url::Url;
 
+use super::{Attachment, AttachmentEncryption, AttachmentType, UploadHeader};
+
 pub struct UploadSlot {
     pub upload_url: Url,
     pub upload_headers: Vec<UploadHeader>,
@@ -15,3 +17,24 @@ pub struct UploadSlot {
     pub media_type: Mime,
     pub file_size: u64,
 }
+
+impl UploadSlot {
+    /// Builds an attachment of the given type pointing to the download URL of this slot. Use
+    /// e.g. `AttachmentType::Audio { duration: Some(12) }` for a voice message.
+    pub fn into_attachment(self, r#type: AttachmentType) -> Attachment {
+        Attachment {
+            r#type,
+            url: self.download_url,
+            media_type: self.media_type,
+            file_name: self.file_name,
+            file_size: Some(self.file_size),
+        }
+    }
+
+    /// Replaces the download URL with an `aesgcm://` URL carrying the key and IV the uploaded
+    /// file was encrypted with (XEP-0454).
+    pub fn encrypted(mut self, encryption: &AttachmentEncryption) -> Result<Self> {
+        self.download_url = encryption.encrypted_url(&self.download_url)?;
+        Ok(self)
+    }
+}
@@ -8,16 +8,19 @@ use async_trait::async_trait;
 
 use prose_proc_macros::InjectDependencies;
 
-use crate::app::deps::DynBlockListDomainService;
+use crate::app::deps::{DynBlockListDomainService, DynSidebarDomainService};
 use crate::app::event_handlers::{
     BlockListEvent, BlockListEventType, ServerEvent, ServerEventHandler,
 };
+use crate::domain::shared::models::RoomId;
 
 /// Handles block list related events.
 #[derive(InjectDependencies)]
 pub struct BlockListEventHandler {
     #[inject]
     block_list_domain_service: DynBlockListDomainService,
+    #[inject]
+    sidebar_domain_service: DynSidebarDomainService,
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -45,6 +48,10 @@ impl BlockListEventHandler {
                 self.block_list_domain_service
                     .handle_user_blocked(&user_id)
                     .await?;
+                // The user might have been blocked from another client…
+                self.sidebar_domain_service
+                    .remove_items(&[&RoomId::User(user_id)])
+                    .await?;
             }
             BlockListEventType::UserUnblocked { user_id } => {
                 self.block_list_domain_service
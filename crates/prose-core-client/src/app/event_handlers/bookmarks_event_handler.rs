@@ -44,7 +44,7 @@ impl BookmarksEventHandler {
             PubSubEventType::AddedOrUpdated { items: bookmarks } => {
                 let context = DecryptionContext::default();
                 self.sidebar_domain_service
-                    .extend_items_from_bookmarks(bookmarks, context.clone())
+                    .handle_changed_items(bookmarks, context.clone())
                     .await?;
                 self.encryption_domain_service
                     .finalize_decryption(context)
@@ -8,19 +8,33 @@ use async_trait::async_trait;
 
 use prose_proc_macros::InjectDependencies;
 
-use crate::app::deps::{DynAppContext, DynClientEventDispatcher, DynSidebarDomainService};
+use crate::app::deps::{
+    DynAccountSettingsRepository, DynAppContext, DynClientEventDispatcher,
+    DynConnectedRoomsReadOnlyRepository, DynSidebarDomainService, DynTimeProvider,
+    DynUserAccountService,
+};
 use crate::app::event_handlers::{ConnectionEvent, ServerEvent, ServerEventHandler};
-use crate::domain::shared::models::ConnectionState;
+use crate::domain::account::models::AutoAwayTransition;
+use crate::domain::account::services::send_availability;
+use crate::domain::shared::models::{Availability, ConnectionState};
 use crate::{ClientEvent, ConnectionEvent as ClientConnectionEvent};
 
 #[derive(InjectDependencies)]
 pub struct ConnectionEventHandler {
+    #[inject]
+    account_settings_repo: DynAccountSettingsRepository,
     #[inject]
     ctx: DynAppContext,
     #[inject]
     client_event_dispatcher: DynClientEventDispatcher,
     #[inject]
+    connected_rooms_repo: DynConnectedRoomsReadOnlyRepository,
+    #[inject]
     sidebar_domain_service: DynSidebarDomainService,
+    #[inject]
+    time_provider: DynTimeProvider,
+    #[inject]
+    user_account_service: DynUserAccountService,
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -41,23 +55,84 @@ impl ServerEventHandler for ConnectionEventHandler {
 impl ConnectionEventHandler {
     async fn handle_connection_event(&self, event: ConnectionEvent) -> Result<Option<ServerEvent>> {
         match event {
-            ConnectionEvent::Connected => {
+            ConnectionEvent::Connected { .. } => {
                 // We'll send an event from our `connect` method since we need to gather
                 // information about the server first. Once we'll fire the event SDK consumers
                 // can be sure that we have everything we need.
             }
             ConnectionEvent::Disconnected { error } => {
                 self.ctx.set_connection_state(ConnectionState::Disconnected);
+                self.ctx.ping_round_trip_time.write().take();
                 self.sidebar_domain_service.handle_disconnect().await?;
                 self.client_event_dispatcher
                     .dispatch_event(ClientEvent::ConnectionStatusChanged {
                         event: ClientConnectionEvent::Disconnect { error },
                     });
             }
+            ConnectionEvent::SessionReady => {
+                return Ok(Some(ServerEvent::Connection(ConnectionEvent::SessionReady)));
+            }
             ConnectionEvent::PingTimer => {
-                return Ok(Some(ServerEvent::Connection(ConnectionEvent::PingTimer)))
+                self.handle_auto_away_timer().await?;
+                return Ok(Some(ServerEvent::Connection(ConnectionEvent::PingTimer)));
+            }
+            ConnectionEvent::PingAnswered { round_trip_time } => {
+                self.ctx
+                    .ping_round_trip_time
+                    .write()
+                    .replace(round_trip_time);
+            }
+            ConnectionEvent::PingTimeout => {
+                self.client_event_dispatcher
+                    .dispatch_event(ClientEvent::ConnectionStatusChanged {
+                        event: ClientConnectionEvent::PingTimeout,
+                    });
+            }
+            ConnectionEvent::MessagesAcknowledged { .. } => {
+                return Ok(Some(ServerEvent::Connection(event)));
             }
         }
         Ok(None)
     }
+
+    async fn handle_auto_away_timer(&self) -> Result<()> {
+        let Ok(account) = self.ctx.connected_account() else {
+            return Ok(());
+        };
+
+        // We don't want to override an availability that the user set explicitly, like
+        // "Do not disturb".
+        let settings = self.account_settings_repo.get(&account).await?;
+        if settings.availability != Availability::Available {
+            return Ok(());
+        }
+
+        let Some(AutoAwayTransition::BecameIdle {
+            availability,
+            idle_since,
+        }) = self
+            .ctx
+            .auto_away
+            .lock()
+            .check_idle(self.time_provider.now())
+        else {
+            return Ok(());
+        };
+
+        send_availability(
+            &self.ctx,
+            &self.user_account_service,
+            &self.connected_rooms_repo,
+            &account,
+            availability,
+            settings.status_message,
+            Some(idle_since),
+        )
+        .await?;
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::AccountInfoChanged);
+
+        Ok(())
+    }
 }
@@ -50,9 +50,22 @@ impl ContactListEventHandler {
                     .handle_removed_contact(&event.contact_id)
                     .await?;
             }
-            ContactListEventType::ContactAddedOrPresenceSubscriptionUpdated { subscription } => {
+            ContactListEventType::ContactAddedOrUpdated {
+                name,
+                groups,
+                subscription,
+            } => {
+                let contact = Contact {
+                    id: event.contact_id,
+                    name,
+                    groups,
+                    presence_subscription: subscription,
+                };
                 self.contact_list_domain_service
-                    .handle_updated_contact(&event.contact_id, subscription)
+                    .handle_updated_contact(contact.clone())
+                    .await?;
+                self.user_info_domain_service
+                    .handle_contacts_changed(vec![contact])
                     .await?;
             }
             ContactListEventType::PresenceSubscriptionRequested { nickname } => {
@@ -63,10 +76,16 @@ impl ContactListEventHandler {
                     .handle_contacts_changed(vec![Contact {
                         id: event.contact_id,
                         name: nickname,
+                        groups: vec![],
                         presence_subscription: PresenceSubscription::Requested,
                     }])
                     .await?;
             }
+            ContactListEventType::PresenceSubscriptionWithdrawn => {
+                self.contact_list_domain_service
+                    .handle_presence_sub_request_withdrawn(&event.contact_id)
+                    .await?;
+            }
         }
 
         Ok(())
@@ -15,9 +15,10 @@ use prose_xmpp::stanza::message::Forwarded;
 use prose_xmpp::stanza::Message;
 
 use crate::app::deps::{
-    DynAppContext, DynClientEventDispatcher, DynConnectedRoomsReadOnlyRepository,
-    DynEncryptionDomainService, DynMessageIdProvider, DynMessagesRepository,
-    DynOfflineMessagesRepository, DynSidebarDomainService, DynTimeProvider,
+    DynAccountSettingsRepository, DynAppContext, DynBlockListDomainService,
+    DynClientEventDispatcher, DynConnectedRoomsReadOnlyRepository, DynEncryptionDomainService,
+    DynMessageIdProvider, DynMessagesRepository, DynMessagingService, DynOfflineMessagesRepository,
+    DynPendingMessagesRepository, DynSidebarDomainService, DynTimeProvider,
 };
 use crate::app::event_handlers::{MessageEvent, MessageEventType, ServerEvent, ServerEventHandler};
 use crate::domain::messaging::models::{
@@ -25,7 +26,7 @@ use crate::domain::messaging::models::{
 };
 use crate::domain::rooms::models::Room;
 use crate::domain::shared::models::{AccountId, ConnectionState, RoomId, UserEndpointId};
-use crate::dtos::{MessageRemoteId, OccupantId, ParticipantId};
+use crate::dtos::{Availability, MessageRemoteId, OccupantId, ParticipantId};
 use crate::infra::xmpp::util::MessageExt;
 use crate::ClientRoomEventType;
 
@@ -34,6 +35,10 @@ pub struct MessagesEventHandler {
     #[inject]
     ctx: DynAppContext,
     #[inject]
+    account_settings_repo: DynAccountSettingsRepository,
+    #[inject]
+    block_list_domain_service: DynBlockListDomainService,
+    #[inject]
     connected_rooms_repo: DynConnectedRoomsReadOnlyRepository,
     #[inject]
     encryption_domain_service: DynEncryptionDomainService,
@@ -42,6 +47,10 @@ pub struct MessagesEventHandler {
     #[inject]
     messages_repo: DynMessagesRepository,
     #[inject]
+    messaging_service: DynMessagingService,
+    #[inject]
+    pending_messages_repo: DynPendingMessagesRepository,
+    #[inject]
     sidebar_domain_service: DynSidebarDomainService,
     #[inject]
     time_provider: DynTimeProvider,
@@ -169,9 +178,24 @@ impl MessagesEventHandler {
 
                             message.from = message.to.take();
                             message.to = Some(room_id.into_bare().into());
-                            return self
-                                .handle_sent_message(account, MessageOrCarbon::Message(message))
-                                .await;
+
+                            let message_id = message
+                                .id
+                                .clone()
+                                .map(|id| MessageId::from(id.into_inner()));
+
+                            self.handle_sent_message(
+                                account.clone(),
+                                MessageOrCarbon::Message(message),
+                            )
+                            .await?;
+
+                            // The reflection tells us that the message reached the room…
+                            if let Some(message_id) = message_id {
+                                self.confirm_reflected_message(&account, room, message_id)
+                                    .await?;
+                            }
+                            return Ok(());
                         }
                     }
                 }
@@ -204,6 +228,27 @@ impl MessagesEventHandler {
             return Ok(());
         };
 
+        // The server should hold back messages from blocked users already, but they could
+        // still reach us in the time between blocking the user and the server acknowledging it.
+        if let Some(user_id) = from.to_user_id() {
+            if self
+                .block_list_domain_service
+                .is_user_blocked(&user_id)
+                .await
+                .unwrap_or_default()
+            {
+                info!("Ignoring message from blocked user {user_id}.");
+                return Ok(());
+            }
+        }
+
+        if let MessageOrCarbon::Message(message) = &message {
+            _ = self
+                .send_delivery_receipt_if_needed(&account, &from, message)
+                .await
+                .inspect_err(|err| error!("Could not send delivery receipt. {}", err.to_string()));
+        }
+
         let room_id = from.to_room_id();
         let room = self.connected_rooms_repo.get(&account, room_id.as_ref());
         let now = self.time_provider.now();
@@ -271,11 +316,120 @@ impl MessagesEventHandler {
             return Ok(());
         };
 
-        self.save_message_and_dispatch_event(&account, room, message)
+        // Keep active senders around in rooms with a bounded participant list…
+        room.with_participants_mut(|participants| participants.touch(&message.from));
+
+        let notify_message_id = self
+            .should_request_notification(&account, &room, &message)
+            .await
+            .then(|| message.id.clone());
+
+        self.save_message_and_dispatch_event(&account, room.clone(), message)
+            .await?;
+
+        if let Some(message_id) = notify_message_id {
+            self.client_event_dispatcher.dispatch_room_event(
+                room,
+                ClientRoomEventType::NotificationRequested {
+                    message_ids: vec![message_id],
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Decides whether a `ClientRoomEventType::NotificationRequested` should be dispatched for
+    /// the received `message` according to `AppConfig::notification_policy`.
+    async fn should_request_notification(
+        &self,
+        account: &AccountId,
+        room: &Room,
+        message: &MessageLike,
+    ) -> bool {
+        let Some(policy) = &self.ctx.config.notification_policy else {
+            return false;
+        };
+
+        if !room.should_notify_about_message(account, message) {
+            return false;
+        }
+
+        if !policy.suppress_while_do_not_disturb {
+            return true;
+        }
+
+        match self.account_settings_repo.get(account).await {
+            Ok(settings) => settings.availability != Availability::DoNotDisturb,
+            Err(err) => {
+                error!("Could not load account settings. {}", err.to_string());
+                true
+            }
+        }
+    }
+
+    /// Removes a message that was reflected by its MUC room from the outbox, so that it's not
+    /// reported as pending anymore.
+    async fn confirm_reflected_message(
+        &self,
+        account: &AccountId,
+        room: Room,
+        message_id: MessageId,
+    ) -> Result<()> {
+        if self
+            .pending_messages_repo
+            .get(account, &room.room_id, &message_id)
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        self.pending_messages_repo
+            .delete(account, &room.room_id, &message_id)
             .await?;
+        self.ctx.unconfirmed_message_ids.lock().remove(&message_id);
+
+        self.client_event_dispatcher.dispatch_room_event(
+            room,
+            ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![message_id],
+            },
+        );
+
         Ok(())
     }
 
+    /// Acknowledges the receipt of a direct message (XEP-0184) if its sender asked for it. Like
+    /// read receipts, delivery receipts are only sent if our user didn't opt out of them.
+    async fn send_delivery_receipt_if_needed(
+        &self,
+        account: &AccountId,
+        from: &UserEndpointId,
+        message: &Message,
+    ) -> Result<()> {
+        if message.type_ != MessageType::Chat || !message.requests_delivery_receipt() {
+            return Ok(());
+        }
+
+        let (Some(user_id), Some(message_id)) = (from.to_user_id(), message.id.clone()) else {
+            return Ok(());
+        };
+
+        if !self
+            .account_settings_repo
+            .get(account)
+            .await?
+            .send_read_receipts
+        {
+            return Ok(());
+        }
+
+        self.messaging_service
+            .send_delivery_receipt(&user_id, &MessageRemoteId::from(message_id))
+            .await
+    }
+
     async fn handle_sent_message(
         &self,
         account: AccountId,
@@ -362,14 +516,22 @@ impl MessagesEventHandler {
                 return Ok(());
             };
 
-            if matches!(message.payload, MessageLikePayload::Retraction { .. }) {
-                ClientRoomEventType::MessagesDeleted {
-                    message_ids: vec![message_id],
-                }
-            } else {
-                ClientRoomEventType::MessagesUpdated {
-                    message_ids: vec![message_id],
-                }
+            // Retracted messages are kept as a tombstone, so they're updated rather than
+            // deleted.
+            ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![message_id],
+            }
+        } else if let (
+            MessageLikePayload::Message {
+                is_transient: true, ..
+            },
+            ParticipantId::Occupant(occupant_id),
+        ) = (&message.payload, &message.from)
+        {
+            // Private messages from single occupants are not part of the room's timeline…
+            ClientRoomEventType::PrivateMessagesAppended {
+                occupant_id: occupant_id.clone(),
+                message_ids: vec![message.id.clone()],
             }
         } else {
             ClientRoomEventType::MessagesAppended {
@@ -12,8 +12,10 @@ pub use connection_event_handler::ConnectionEventHandler;
 pub use contact_list_event_handler::ContactListEventHandler;
 pub use event_handler_queue::ServerEventHandlerQueue;
 pub use messages_event_handler::MessagesEventHandler;
+pub use outbox_event_handler::OutboxEventHandler;
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 pub use prose_xmpp::Event as XMPPEvent;
+pub use reconnect_event_handler::ReconnectEventHandler;
 pub use requests_event_handler::RequestsEventHandler;
 pub use rooms_event_handler::RoomsEventHandler;
 pub use server_event::*;
@@ -21,7 +23,9 @@ pub use synced_room_settings_event_handler::SyncedRoomSettingsEventHandler;
 pub use user_devices_event_handler::UserDevicesEventHandler;
 pub use user_info_event_handler::UserInfoEventHandler;
 
+use crate::app::services::{ClientEventStream, RoomSubscriptionHandle, RoomUpdateHandler};
 use crate::domain::rooms::models::Room;
+use crate::domain::shared::models::RoomId;
 use crate::{ClientEvent, ClientRoomEventType};
 
 mod block_list_event_handler;
@@ -30,6 +34,8 @@ mod connection_event_handler;
 mod contact_list_event_handler;
 mod event_handler_queue;
 mod messages_event_handler;
+mod outbox_event_handler;
+mod reconnect_event_handler;
 mod requests_event_handler;
 mod rooms_event_handler;
 mod server_event;
@@ -54,4 +60,13 @@ pub trait ServerEventHandler: SendUnlessWasm + SyncUnlessWasm {
 pub trait ClientEventDispatcherTrait: SendUnlessWasm + SyncUnlessWasm {
     fn dispatch_event(&self, event: ClientEvent);
     fn dispatch_room_event(&self, room: Room, event: ClientRoomEventType);
+    /// Calls `handler` for every event of the room with `room_id` until the returned handle is
+    /// dropped.
+    fn subscribe_to_room(
+        &self,
+        room_id: RoomId,
+        handler: RoomUpdateHandler,
+    ) -> RoomSubscriptionHandle;
+    /// Returns a stream of all dispatched events, including room events.
+    fn subscribe_to_events(&self) -> ClientEventStream;
 }
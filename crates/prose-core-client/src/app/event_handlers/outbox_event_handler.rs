@@ -0,0 +1,179 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::collections::HashSet;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use prose_proc_macros::InjectDependencies;
+
+use crate::app::deps::{
+    DynAppContext, DynClientEventDispatcher, DynConnectedRoomsReadOnlyRepository,
+    DynPendingMessagesRepository, DynRoomFactory,
+};
+use crate::app::event_handlers::{ConnectionEvent, ServerEvent, ServerEventHandler};
+use crate::domain::messaging::models::MessageId;
+use crate::domain::rooms::models::RoomState;
+use crate::domain::shared::models::ConnectionState;
+use crate::ClientRoomEventType;
+
+/// Resends the messages in the outbox once we're back online and removes them from there once
+/// the server acknowledged them.
+#[derive(InjectDependencies)]
+pub struct OutboxEventHandler {
+    #[inject]
+    ctx: DynAppContext,
+    #[inject]
+    client_event_dispatcher: DynClientEventDispatcher,
+    #[inject]
+    connected_rooms_repo: DynConnectedRoomsReadOnlyRepository,
+    #[inject]
+    pending_messages_repo: DynPendingMessagesRepository,
+    #[inject]
+    room_factory: DynRoomFactory,
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl ServerEventHandler for OutboxEventHandler {
+    fn name(&self) -> &'static str {
+        "outbox"
+    }
+
+    async fn handle_event(&self, event: ServerEvent) -> Result<Option<ServerEvent>> {
+        match event {
+            // Direct messages can be sent as soon as we're connected. Rooms that are still
+            // being (re-)joined at that point are reconnected on the ping timer, so we'll try
+            // again then.
+            ServerEvent::Connection(ConnectionEvent::SessionReady | ConnectionEvent::PingTimer) => {
+                self.flush_outbox().await?;
+                Ok(Some(event))
+            }
+            ServerEvent::Connection(ConnectionEvent::MessagesAcknowledged { message_ids }) => {
+                self.confirm_messages(message_ids).await?;
+                Ok(None)
+            }
+            _ => Ok(Some(event)),
+        }
+    }
+}
+
+impl OutboxEventHandler {
+    async fn flush_outbox(&self) -> Result<()> {
+        if self.ctx.connection_state() != ConnectionState::Connected {
+            return Ok(());
+        }
+
+        let account = self.ctx.connected_account()?;
+        let unconfirmed_message_ids = self.ctx.unconfirmed_message_ids.lock().clone();
+        // Messages that were sent in this session already are waiting for the server to confirm
+        // them, so they must not be sent again…
+        let messages = self
+            .pending_messages_repo
+            .get_all(&account)
+            .await?
+            .into_iter()
+            .filter(|message| {
+                message.is_pending() && !unconfirmed_message_ids.contains(&message.id)
+            })
+            .collect::<Vec<_>>();
+
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        info!("Resending {} pending messages…", messages.len());
+
+        // Rooms in which a message couldn't be sent. Later messages of these rooms are held back
+        // so that they don't overtake the earlier ones.
+        let mut blocked_rooms = HashSet::new();
+
+        for message in messages {
+            if blocked_rooms.contains(&message.room_id) {
+                continue;
+            }
+
+            let Some(room) = self
+                .connected_rooms_repo
+                .get(&account, message.room_id.as_ref())
+            else {
+                blocked_rooms.insert(message.room_id.clone());
+                continue;
+            };
+
+            let room_id = message.room_id.clone();
+            let room_state = room.state();
+            let room = self.room_factory.build(room).to_generic_room();
+
+            match room_state {
+                RoomState::Connected => {
+                    if let Err(err) = room.send_pending_message(message).await {
+                        warn!(
+                            "Failed to resend pending message in {room_id}. {}",
+                            err.to_string()
+                        );
+                        blocked_rooms.insert(room_id);
+                    }
+                }
+                RoomState::Disconnected {
+                    error,
+                    can_retry: false,
+                } => {
+                    room.fail_pending_message(
+                        message,
+                        error.unwrap_or_else(|| "The room is not available anymore.".to_string()),
+                    )
+                    .await?;
+                }
+                RoomState::Pending
+                | RoomState::Invited { .. }
+                | RoomState::Connecting
+                | RoomState::Disconnected { .. } => {
+                    blocked_rooms.insert(room_id);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the messages with `message_ids` from the outbox since the server received them.
+    async fn confirm_messages(&self, message_ids: Vec<MessageId>) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+        let message_ids = message_ids.into_iter().collect::<HashSet<_>>();
+
+        let messages = self
+            .pending_messages_repo
+            .get_all(&account)
+            .await?
+            .into_iter()
+            .filter(|message| message.is_pending() && message_ids.contains(&message.id));
+
+        for message in messages {
+            self.pending_messages_repo
+                .delete(&account, &message.room_id, &message.id)
+                .await?;
+            self.ctx.unconfirmed_message_ids.lock().remove(&message.id);
+
+            let Some(room) = self
+                .connected_rooms_repo
+                .get(&account, message.room_id.as_ref())
+            else {
+                continue;
+            };
+
+            self.client_event_dispatcher.dispatch_room_event(
+                room,
+                ClientRoomEventType::MessagesUpdated {
+                    message_ids: vec![message.id],
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::app::deps::{AppDependencies, DynAppContext};
+use crate::app::event_handlers::{ConnectionEvent, ServerEvent, ServerEventHandler};
+use crate::app::services::ConnectionService;
+
+/// Reestablishes the connection after it was lost, if a `ReconnectPolicy` was configured.
+///
+/// Needs to run before the `ConnectionEventHandler`, which consumes the disconnect event.
+pub struct ReconnectEventHandler {
+    ctx: DynAppContext,
+    connection_service: Arc<ConnectionService>,
+}
+
+impl From<&AppDependencies> for ReconnectEventHandler {
+    fn from(deps: &AppDependencies) -> Self {
+        Self {
+            ctx: deps.ctx.clone(),
+            connection_service: Arc::new(ConnectionService::from(deps)),
+        }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl ServerEventHandler for ReconnectEventHandler {
+    fn name(&self) -> &'static str {
+        "reconnect"
+    }
+
+    async fn handle_event(&self, event: ServerEvent) -> Result<Option<ServerEvent>> {
+        match &event {
+            ServerEvent::Connection(ConnectionEvent::Disconnected { error })
+                if self.ctx.config.reconnect_policy.is_some() =>
+            {
+                // The reconnect runs detached from the event queue, since the backoff delays
+                // would otherwise block all other events…
+                let connection_service = self.connection_service.clone();
+                let error = error.clone();
+                prose_wasm_utils::spawn(async move { connection_service.reconnect(error).await });
+            }
+            _ => (),
+        }
+        Ok(Some(event))
+    }
+}
@@ -9,8 +9,12 @@ use chrono::Local;
 
 use prose_proc_macros::InjectDependencies;
 
-use crate::app::deps::{DynAppContext, DynRequestHandlingService, DynTimeProvider};
+use crate::app::deps::{
+    DynAppContext, DynContactListDomainService, DynRequestHandlingService, DynTimeProvider,
+};
 use crate::app::event_handlers::{RequestEvent, RequestEventType, ServerEvent, ServerEventHandler};
+use crate::domain::contacts::models::PresenceSubscription;
+use crate::domain::shared::models::{SenderId, UserId};
 
 /// Handles various server requests.
 #[derive(InjectDependencies)]
@@ -21,6 +25,8 @@ pub struct RequestsEventHandler {
     ctx: DynAppContext,
     #[inject]
     time_provider: DynTimeProvider,
+    #[inject]
+    contact_list_domain_service: DynContactListDomainService,
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -59,8 +65,28 @@ impl RequestsEventHandler {
                     .await?;
             }
             RequestEventType::LastActivity => {
+                // Our idle time is part of our presence, so it's only shared with entities that
+                // are allowed to see it…
+                if !self.is_subscribed_to_our_presence(&event.sender_id).await? {
+                    self.request_handling_service
+                        .decline_last_activity_request(&event.sender_id, &event.request_id)
+                        .await?;
+                    return Ok(());
+                }
+
+                let idle_seconds = self
+                    .ctx
+                    .auto_away
+                    .lock()
+                    .idle_duration(self.time_provider.now())
+                    .num_seconds() as u64;
+
                 self.request_handling_service
-                    .respond_to_last_activity_request(&event.sender_id, &event.request_id, 0)
+                    .respond_to_last_activity_request(
+                        &event.sender_id,
+                        &event.request_id,
+                        idle_seconds,
+                    )
                     .await?;
             }
             RequestEventType::Capabilities { id: _id } => {
@@ -84,4 +110,31 @@ impl RequestsEventHandler {
         }
         Ok(())
     }
+
+    /// Returns `true` if `sender_id` is one of our own resources or a contact that is
+    /// subscribed to our presence.
+    async fn is_subscribed_to_our_presence(&self, sender_id: &SenderId) -> Result<bool> {
+        let sender = sender_id.clone().into_inner().into_bare();
+        if sender.node().is_none() {
+            return Ok(false);
+        }
+        let user_id = UserId::from(sender);
+
+        if self.ctx.connected_account()? == user_id {
+            return Ok(true);
+        }
+
+        let Some(contact) = self
+            .contact_list_domain_service
+            .load_contact(&user_id)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        Ok(matches!(
+            contact.presence_subscription,
+            PresenceSubscription::Mutual | PresenceSubscription::TheyFollow
+        ))
+    }
 }
@@ -11,8 +11,9 @@ use prose_proc_macros::InjectDependencies;
 use prose_xmpp::TimeProvider;
 
 use crate::app::deps::{
-    DynAppContext, DynClientEventDispatcher, DynConnectedRoomsReadOnlyRepository,
-    DynSidebarDomainService, DynTimeProvider, DynUserInfoDomainService,
+    DynAppContext, DynBlockListDomainService, DynClientEventDispatcher,
+    DynConnectedRoomsReadOnlyRepository, DynContactListDomainService, DynSidebarDomainService,
+    DynTimeProvider, DynUserInfoDomainService,
 };
 use crate::app::event_handlers::ServerEventHandler;
 use crate::app::event_handlers::{
@@ -20,11 +21,8 @@ use crate::app::event_handlers::{
     UserStatusEvent, UserStatusEventType,
 };
 use crate::client_event::ClientRoomEventType;
-use crate::domain::rooms::models::Room;
-use crate::domain::rooms::services::{
-    CreateOrEnterRoomRequest, JoinRoomBehavior, JoinRoomFailureBehavior, JoinRoomRedirectBehavior,
-};
-use crate::domain::shared::models::{CachePolicy, ParticipantId, RoomId};
+use crate::domain::rooms::models::{Room, RoomRemovalReason};
+use crate::domain::shared::models::{CachePolicy, ParticipantId, RoomId, UserId};
 use crate::domain::user_info::models::UserInfoOptExt;
 use crate::dtos::Availability;
 use crate::ClientEvent;
@@ -34,8 +32,12 @@ pub struct RoomsEventHandler {
     #[inject]
     ctx: DynAppContext,
     #[inject]
+    block_list_domain_service: DynBlockListDomainService,
+    #[inject]
     connected_rooms_repo: DynConnectedRoomsReadOnlyRepository,
     #[inject]
+    contact_list_domain_service: DynContactListDomainService,
+    #[inject]
     sidebar_domain_service: DynSidebarDomainService,
     #[inject]
     client_event_dispatcher: DynClientEventDispatcher,
@@ -83,21 +85,46 @@ impl RoomsEventHandler {
             ))
     }
 
+    /// Remembers when a contact was last seen, once none of their resources is available
+    /// anymore.
+    async fn handle_user_went_offline(&self, user_id: &UserId) -> Result<()> {
+        let is_offline = self
+            .user_info_domain_service
+            .get_user_info(user_id, CachePolicy::ReturnCacheDataDontLoad)
+            .await?
+            .map_or(true, |info| info.availability == Availability::Unavailable);
+
+        if !is_offline {
+            return Ok(());
+        }
+
+        self.contact_list_domain_service
+            .set_last_seen(user_id, self.time_provider.now())
+            .await
+    }
+
     async fn handle_occupant_event(&self, event: OccupantEvent) -> Result<()> {
         let room = self.get_room(&event.occupant_id.room_id())?;
         let participant_id = ParticipantId::Occupant(event.occupant_id.clone());
 
         let participants_changed = match event.r#type {
-            OccupantEventType::AffiliationChanged { affiliation } => 'outer: {
+            OccupantEventType::AffiliationChanged { affiliation, role } => 'outer: {
                 let participants_changed = room.with_participants_mut(|participants| {
+                    let mut participants_changed = false;
+
                     if participants.get(&participant_id).map(|p| &p.affiliation)
                         != Some(&affiliation)
                     {
                         participants.set_affiliation(&participant_id, event.is_self, affiliation);
-                        true
-                    } else {
-                        false
+                        participants_changed = true;
+                    }
+
+                    if participants.get(&participant_id).map(|p| &p.role) != Some(&role) {
+                        participants.set_role(&participant_id, role);
+                        participants_changed = true;
                     }
+
+                    participants_changed
                 });
 
                 // Let's see if we knew the real id of the participant already, if not let's
@@ -130,6 +157,16 @@ impl RoomsEventHandler {
                     );
                 });
 
+                // Messages of the occupant might have been displayed with their nickname only,
+                // so let's have them reloaded with the proper name…
+                let message_ids = room.take_messages_with_unresolved_sender(&event.occupant_id);
+                if !message_ids.is_empty() {
+                    self.client_event_dispatcher.dispatch_room_event(
+                        room.clone(),
+                        ClientRoomEventType::MessagesUpdated { message_ids },
+                    );
+                }
+
                 true
             }
             OccupantEventType::DisconnectedByServer => {
@@ -143,20 +180,23 @@ impl RoomsEventHandler {
 
                 if event.is_self {
                     self.sidebar_domain_service
-                        .handle_removal_from_room(&event.occupant_id.muc_id(), false)
+                        .handle_removal_from_room(
+                            &event.occupant_id.muc_id(),
+                            RoomRemovalReason::ServiceShutdown,
+                        )
                         .await?;
                 }
 
                 true
             }
-            OccupantEventType::PermanentlyRemoved => 'outer: {
+            OccupantEventType::PermanentlyRemoved { reason } => 'outer: {
                 room.with_participants_mut(|participants| {
                     participants.remove(&participant_id);
                 });
 
                 if event.is_self {
                     self.sidebar_domain_service
-                        .handle_removal_from_room(&event.occupant_id.muc_id(), true)
+                        .handle_removal_from_room(&event.occupant_id.muc_id(), reason)
                         .await?;
                     // A SidebarChanged event will be sent instead
                     break 'outer false;
@@ -167,8 +207,12 @@ impl RoomsEventHandler {
         };
 
         if participants_changed {
-            self.client_event_dispatcher
-                .dispatch_room_event(room, ClientRoomEventType::ParticipantsChanged);
+            self.client_event_dispatcher.dispatch_room_event(
+                room,
+                ClientRoomEventType::ParticipantsChanged {
+                    ids: vec![participant_id],
+                },
+            );
         }
 
         Ok(())
@@ -204,21 +248,13 @@ impl RoomsEventHandler {
                         .dispatch_room_event(room, ClientRoomEventType::AttributesChanged)
                 }
             }
-            RoomEventType::ReceivedInvitation { sender, password } => {
-                info!(
-                    "Joining room {} after receiving invitation from {sender}…",
-                    event.room_id
-                );
+            RoomEventType::ReceivedInvitation {
+                sender,
+                password,
+                reason,
+            } => {
                 self.sidebar_domain_service
-                    .insert_item_by_creating_or_joining_room(CreateOrEnterRoomRequest::JoinRoom {
-                        room_id: event.room_id,
-                        password,
-                        behavior: JoinRoomBehavior {
-                            on_redirect: JoinRoomRedirectBehavior::FollowIfGone,
-                            on_failure: JoinRoomFailureBehavior::RetainOnError,
-                        },
-                        decryption_context: None,
-                    })
+                    .handle_received_invitation(&event.room_id, &sender, password, reason)
                     .await?;
             }
             RoomEventType::UserAdded {
@@ -244,8 +280,12 @@ impl RoomsEventHandler {
                     participants.add_user(&user_id, false, affiliation, name);
                 });
 
-                self.client_event_dispatcher
-                    .dispatch_room_event(room, ClientRoomEventType::ParticipantsChanged);
+                self.client_event_dispatcher.dispatch_room_event(
+                    room,
+                    ClientRoomEventType::ParticipantsChanged {
+                        ids: vec![ParticipantId::User(user_id)],
+                    },
+                );
             }
         }
 
@@ -254,6 +294,22 @@ impl RoomsEventHandler {
 
     async fn handle_user_status_event(&self, event: UserStatusEvent) -> Result<()> {
         let account = self.ctx.connected_account()?;
+
+        // Ignore presences and chat states of blocked users…
+        if let Some(user_id) = event
+            .user_id
+            .to_user_id()
+            .filter(|user_id| user_id != account.as_ref())
+        {
+            if self
+                .block_list_domain_service
+                .is_user_blocked(&user_id)
+                .await
+                .unwrap_or_default()
+            {
+                return Ok(());
+            }
+        }
         let room = self.get_room(&event.user_id.to_room_id()).ok();
 
         let is_self_event = room
@@ -280,11 +336,13 @@ impl RoomsEventHandler {
 
                     if room.sidebar_state().is_in_sidebar() {
                         if event.user_id.is_occupant_id() {
-                            // The participant list should be reloaded in the UI to reflect
+                            // The participant should be updated in the UI to reflect
                             // the new availability…
                             self.client_event_dispatcher.dispatch_room_event(
                                 room,
-                                ClientRoomEventType::ParticipantsChanged,
+                                ClientRoomEventType::ParticipantsChanged {
+                                    ids: vec![participant_id],
+                                },
                             );
                         }
 
@@ -300,6 +358,8 @@ impl RoomsEventHandler {
                     return Ok(());
                 };
 
+                let is_unavailable = presence.availability == Availability::Unavailable;
+
                 self.user_info_domain_service
                     .handle_user_presence_changed(&id, presence)
                     .await?;
@@ -309,6 +369,10 @@ impl RoomsEventHandler {
                     return Ok(());
                 }
 
+                if is_unavailable {
+                    self.handle_user_went_offline(&id.to_user_id()).await?;
+                }
+
                 if room_changed {
                     self.client_event_dispatcher
                         .dispatch_event(ClientEvent::SidebarChanged)
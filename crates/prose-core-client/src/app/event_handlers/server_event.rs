@@ -3,18 +3,21 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::time::Duration;
+
 use jid::BareJid;
 
-use prose_xmpp::ConnectionError;
+use prose_xmpp::{ConnectionError, SessionKind};
 
 use crate::domain::contacts::models::PresenceSubscription;
 use crate::domain::encryption::models::DeviceList;
+use crate::domain::messaging::models::MessageId;
 use crate::domain::settings::models::SyncedRoomSettings;
 use crate::domain::shared::models::MucId;
 use crate::domain::sidebar::models::Bookmark;
 use crate::domain::user_info::models::Presence;
 use crate::domain::{
-    rooms::models::{ComposeState, RoomAffiliation},
+    rooms::models::{ComposeState, RoomAffiliation, RoomRemovalReason, RoomRole},
     shared::models::{
         AnonOccupantId, CapabilitiesId, OccupantId, RequestId, SenderId, UserEndpointId, UserId,
         UserResourceId,
@@ -53,9 +56,24 @@ pub enum ServerEvent {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConnectionEvent {
-    Connected,
-    Disconnected { error: Option<ConnectionError> },
+    Connected {
+        session: SessionKind,
+    },
+    /// Dispatched by the client (not the server) once a new or restored session is fully set up
+    /// and we're ready to send messages.
+    SessionReady,
+    Disconnected {
+        error: Option<ConnectionError>,
+    },
     PingTimer,
+    PingAnswered {
+        round_trip_time: Duration,
+    },
+    PingTimeout,
+    /// The server acknowledged the receipt of the messages with `message_ids`.
+    MessagesAcknowledged {
+        message_ids: Vec<MessageId>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -120,6 +138,7 @@ pub enum RoomEventType {
     ReceivedInvitation {
         sender: UserResourceId,
         password: Option<String>,
+        reason: Option<String>,
     },
     /// A user was added via an invitation.
     UserAdded {
@@ -145,12 +164,16 @@ pub struct OccupantEvent {
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum OccupantEventType {
-    /// The occupant's affiliation was modified.
-    AffiliationChanged { affiliation: RoomAffiliation },
+    /// The occupant's affiliation or role was modified.
+    AffiliationChanged {
+        affiliation: RoomAffiliation,
+        role: RoomRole,
+    },
     /// The occupant was disconnected temporarily by the server, i.e. because of a restart.
     DisconnectedByServer,
-    /// The occupant was permanently removed/banned from the room.
-    PermanentlyRemoved,
+    /// The occupant was permanently removed from the room, i.e. kicked, banned or because
+    /// the room was made members-only.
+    PermanentlyRemoved { reason: RoomRemovalReason },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -184,13 +207,19 @@ pub type SyncedRoomSettingsEvent = PubSubEvent<RoomId, SyncedRoomSettings>;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ContactListEventType {
-    /// The contact was either added to our contact list or the presence subscription to or from
-    /// the contact changed.
-    ContactAddedOrPresenceSubscriptionUpdated { subscription: PresenceSubscription },
+    /// The contact was either added to our contact list or its name, groups or the presence
+    /// subscription to or from the contact changed.
+    ContactAddedOrUpdated {
+        name: Option<String>,
+        groups: Vec<String>,
+        subscription: PresenceSubscription,
+    },
     /// The contact was removed from our contact list.
     ContactRemoved,
     /// The contact requested to subscribe to our presence.
     PresenceSubscriptionRequested { nickname: Option<String> },
+    /// The contact withdrew its request to subscribe to our presence or unsubscribed from it.
+    PresenceSubscriptionWithdrawn,
 }
 
 #[derive(Debug, Clone, PartialEq)]
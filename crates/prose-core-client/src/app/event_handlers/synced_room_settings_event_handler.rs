@@ -15,7 +15,7 @@ use crate::app::deps::{
 use crate::app::event_handlers::{
     PubSubEventType, ServerEvent, ServerEventHandler, SyncedRoomSettingsEvent,
 };
-use crate::ClientEvent;
+use crate::{ClientEvent, ClientRoomEventType};
 
 #[derive(InjectDependencies)]
 pub struct SyncedRoomSettingsEventHandler {
@@ -62,8 +62,19 @@ impl SyncedRoomSettingsEventHandler {
                     };
 
                     info!("Applying updated room settings in {}", room.room_id);
-                    room.with_settings_mut(|settings| *settings = setting);
+                    let pinned_messages_changed = room.with_settings_mut(|settings| {
+                        let changed = settings.pinned_messages != setting.pinned_messages;
+                        *settings = setting;
+                        changed
+                    });
                     room.set_needs_update_statistics();
+
+                    if pinned_messages_changed {
+                        self.client_event_dispatcher.dispatch_room_event(
+                            room.clone(),
+                            ClientRoomEventType::PinnedMessagesChanged,
+                        );
+                    }
                 }
                 self.client_event_dispatcher
                     .dispatch_event(ClientEvent::SidebarChanged);
@@ -50,6 +50,19 @@ impl ServerEventHandler for UserInfoEventHandler {
 
 impl UserInfoEventHandler {
     async fn handle_user_info_event(&self, event: UserInfoEvent) -> Result<()> {
+        // Changing any of these affects how the user is displayed as a message sender…
+        if let UserInfoEventType::AvatarChanged { .. }
+        | UserInfoEventType::ProfileChanged { .. }
+        | UserInfoEventType::NicknameChanged { .. } = &event.r#type
+        {
+            for room in self
+                .connected_rooms_repo
+                .get_all(&self.ctx.connected_account()?)
+            {
+                room.invalidate_message_senders();
+            }
+        }
+
         match event.r#type {
             UserInfoEventType::AvatarChanged { metadata } => {
                 let avatar = Avatar::from_metadata(event.user_id.clone(), metadata);
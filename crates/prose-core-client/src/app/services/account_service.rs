@@ -4,16 +4,20 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use anyhow::Result;
+use chrono::Duration;
 use tracing::debug;
 
 use prose_proc_macros::InjectDependencies;
 use prose_xmpp::mods::AvatarData;
 
 use crate::app::deps::*;
-use crate::domain::account::services::UserProfileFormat;
+use crate::domain::account::models::AutoAwayTransition;
+use crate::domain::account::services::{send_availability, UserProfileFormat};
 use crate::domain::shared::models::{Availability, AvatarId, CachePolicy, ParticipantIdRef};
-use crate::domain::user_info::models::{Avatar, AvatarMetadata, UserProfile, UserStatus};
-use crate::dtos::{AccountInfo, DeviceId, DeviceInfo, UserProfile as UserProfileDTO};
+use crate::domain::user_info::models::{
+    Avatar, AvatarError, AvatarMetadata, UserProfile, UserStatus,
+};
+use crate::dtos::{AccountInfo, DeviceId, DeviceInfo, Fingerprint, UserProfile as UserProfileDTO};
 use crate::ClientEvent;
 
 #[derive(InjectDependencies)]
@@ -31,6 +35,8 @@ pub struct AccountService {
     #[inject]
     encryption_domain_service: DynEncryptionDomainService,
     #[inject]
+    time_provider: DynTimeProvider,
+    #[inject]
     user_account_service: DynUserAccountService,
     #[inject]
     user_info_domain_service: DynUserInfoDomainService,
@@ -53,8 +59,14 @@ impl AccountService {
             id: user_id,
             name,
             avatar: user_info.avatar,
-            availability: account_settings.availability,
+            availability: self
+                .ctx
+                .auto_away
+                .lock()
+                .idle_availability()
+                .unwrap_or(account_settings.availability),
             status: user_info.status,
+            status_message: account_settings.status_message,
         })
     }
 
@@ -62,6 +74,7 @@ impl AccountService {
         let account = self.ctx.connected_account()?;
         let user_id = account.to_user_id();
         let user_profile = UserProfile::from(user_profile);
+        user_profile.validate()?;
 
         let format = if self.ctx.server_features()?.vcard4 {
             UserProfileFormat::Vcard4
@@ -79,26 +92,53 @@ impl AccountService {
         Ok(())
     }
 
-    pub async fn set_availability(&self, availability: Availability) -> Result<()> {
-        let account = self.ctx.connected_account()?;
+    pub async fn delete_profile(&self) -> Result<()> {
+        let user_id = self.ctx.connected_account()?.to_user_id();
 
-        self.user_account_service
-            .set_availability(None, &self.ctx.capabilities, availability)
+        self.user_account_service.delete_profile().await?;
+        self.user_info_domain_service
+            .handle_user_profile_changed(&user_id, None)
             .await?;
 
-        for room in self.connected_rooms_repo.get_all(&account) {
-            let Some(occupant_id) = room.occupant_id() else {
-                continue;
-            };
-            self.user_account_service
-                .set_availability(Some(occupant_id), &self.ctx.capabilities, availability)
-                .await?
-        }
+        Ok(())
+    }
+
+    /// Sends a presence with `availability` and the optional `status_message` (e.g.
+    /// "Back at 2pm") to the server and to all connected rooms. Both are saved with the account
+    /// settings and sent again after reconnecting.
+    pub async fn set_availability(
+        &self,
+        availability: Availability,
+        status_message: Option<String>,
+    ) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        // Changing the availability manually counts as user activity. Since we're sending the
+        // new availability anyway, we can ignore a possible transition.
+        _ = self
+            .ctx
+            .auto_away
+            .lock()
+            .report_activity(self.time_provider.now());
+
+        send_availability(
+            &self.ctx,
+            &self.user_account_service,
+            &self.connected_rooms_repo,
+            &account,
+            availability,
+            status_message.clone(),
+            None,
+        )
+        .await?;
 
         self.account_settings_repo
             .update(
                 &account,
-                Box::new(move |settings| settings.availability = availability),
+                Box::new(move |settings| {
+                    settings.availability = availability;
+                    settings.status_message = status_message;
+                }),
             )
             .await?;
 
@@ -108,6 +148,62 @@ impl AccountService {
         Ok(())
     }
 
+    /// Automatically switches the availability to `to` after no activity has been reported via
+    /// `report_user_activity` for the duration of `after`. The previous availability is restored
+    /// on the next reported activity.
+    pub async fn set_auto_away(&self, after: Duration, to: Availability) -> Result<()> {
+        let transition = self
+            .ctx
+            .auto_away
+            .lock()
+            .configure(Some((after, to)), self.time_provider.now());
+        self.apply_auto_away_transition(transition).await
+    }
+
+    pub async fn disable_auto_away(&self) -> Result<()> {
+        let transition = self
+            .ctx
+            .auto_away
+            .lock()
+            .configure(None, self.time_provider.now());
+        self.apply_auto_away_transition(transition).await
+    }
+
+    /// Resets the idle timer of the auto-away mechanism and restores the previous availability
+    /// if the user was idle.
+    pub async fn report_user_activity(&self) -> Result<()> {
+        let transition = self
+            .ctx
+            .auto_away
+            .lock()
+            .report_activity(self.time_provider.now());
+        self.apply_auto_away_transition(transition).await
+    }
+
+    /// Returns whether read receipts are sent when marking messages as read.
+    pub async fn send_read_receipts(&self) -> Result<bool> {
+        let account = self.ctx.connected_account()?;
+        Ok(self
+            .account_settings_repo
+            .get(&account)
+            .await?
+            .send_read_receipts)
+    }
+
+    /// Enables or disables sending read receipts when marking messages as read. Read receipts
+    /// are enabled by default.
+    pub async fn set_send_read_receipts(&self, enabled: bool) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+        self.account_settings_repo
+            .update(
+                &account,
+                Box::new(move |settings| settings.send_read_receipts = enabled),
+            )
+            .await
+    }
+
+    /// Publishes `user_activity` (or clears it when `None`). The status is saved with the
+    /// account settings and published again on the next connect.
     pub async fn set_user_activity(&self, user_activity: Option<UserStatus>) -> Result<()> {
         let account = self.ctx.connected_account()?;
         let user_id = account.to_user_id();
@@ -115,30 +211,77 @@ impl AccountService {
         self.user_account_service
             .set_user_activity(user_activity.as_ref())
             .await?;
+
+        let saved_status = user_activity.clone();
+        self.account_settings_repo
+            .update(
+                &account,
+                Box::new(move |settings| settings.user_status = saved_status),
+            )
+            .await?;
+
         self.user_info_domain_service
             .handle_user_status_changed(&user_id, user_activity)
             .await?;
         Ok(())
     }
 
+    /// XEP-0084: User Avatar
+    /// https://xmpp.org/extensions/xep-0084.html
+    ///
+    /// Publishes the image data and metadata of our avatar and caches both locally. Fails with an
+    /// `AvatarError` before uploading anything if the image is too large or of an unsupported
+    /// type. If `width` or `height` are not given, they are read from the image.
     pub async fn set_avatar(
         &self,
         image_data: impl AsRef<[u8]>,
         width: Option<u32>,
         height: Option<u32>,
         mime_type: impl AsRef<str>,
-    ) -> Result<()> {
+    ) -> Result<AvatarMetadata> {
         let account = self.ctx.connected_account()?;
         let user_id = account.to_user_id();
         let image_data_len = image_data.as_ref().len();
+        let max_dimension = self.ctx.config.max_avatar_dimension;
+
+        if !AvatarError::SUPPORTED_MIME_TYPES.contains(&mime_type.as_ref()) {
+            return Err(AvatarError::UnsupportedMimeType {
+                mime_type: mime_type.as_ref().to_string(),
+            }
+            .into());
+        }
+
+        if image_data_len > self.ctx.config.max_avatar_size {
+            return Err(AvatarError::TooLarge {
+                size: image_data_len,
+                max_size: self.ctx.config.max_avatar_size,
+            }
+            .into());
+        }
+
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => image_dimensions(image_data.as_ref(), mime_type.as_ref())
+                .ok_or(AvatarError::UnknownDimensions)?,
+        };
+
+        if width > max_dimension || height > max_dimension {
+            return Err(AvatarError::DimensionsTooLarge {
+                width,
+                height,
+                max_dimension,
+            }
+            .into());
+        }
+
         let image_data = AvatarData::Data(image_data.as_ref().to_vec().into_boxed_slice());
 
         let metadata = AvatarMetadata {
             bytes: image_data_len,
             mime_type: mime_type.as_ref().to_string(),
             checksum: AvatarId::from_str_unchecked(image_data.generate_sha1_checksum()?.as_ref()),
-            width,
-            height,
+            width: Some(width),
+            height: Some(height),
             url: None,
         };
 
@@ -159,6 +302,7 @@ impl AccountService {
                 ParticipantIdRef::User(&user_id),
                 &metadata.clone().into_info(),
                 &image_data,
+                self.time_provider.now(),
             )
             .await?;
 
@@ -166,28 +310,103 @@ impl AccountService {
         self.user_info_domain_service
             .handle_avatar_changed(
                 &user_id,
-                Some(Avatar::from_metadata(user_id.clone(), metadata)),
+                Some(Avatar::from_metadata(user_id.clone(), metadata.clone())),
             )
             .await?;
 
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::AvatarChanged { ids: vec![user_id] });
+
+        Ok(metadata)
+    }
+
+    /// Disables our avatar by publishing empty avatar metadata.
+    pub async fn delete_avatar(&self) -> Result<()> {
+        let user_id = self.ctx.connected_account()?.to_user_id();
+
+        self.user_account_service.delete_avatar_metadata().await?;
+        self.user_info_domain_service
+            .handle_avatar_changed(&user_id, None)
+            .await?;
+
         Ok(())
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    pub async fn set_avatar_from_url(&self, image_path: &std::path::Path) -> Result<()> {
+    pub async fn set_avatar_from_url(
+        &self,
+        image_path: &std::path::Path,
+    ) -> Result<AvatarMetadata> {
+        debug!("Opening image at {:?}…", image_path);
+        self.set_avatar_from_image(image::open(image_path)?).await
+    }
+
+    /// Decodes `image_data`, scales it down to the maximum avatar dimensions and publishes it
+    /// as JPEG.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn set_avatar_from_data(
+        &self,
+        image_data: impl AsRef<[u8]>,
+    ) -> Result<AvatarMetadata> {
+        self.set_avatar_from_image(image::load_from_memory(image_data.as_ref())?)
+            .await
+    }
+
+    pub async fn load_device_infos(&self) -> Result<Vec<DeviceInfo>> {
+        self.encryption_domain_service
+            .load_device_infos(&self.ctx.connected_id()?.into_user_id())
+            .await
+    }
+
+    /// Returns the fingerprint of the identity key of this device, to be compared out-of-band by
+    /// the contacts who want to verify it.
+    pub async fn load_own_device_fingerprint(&self) -> Result<Option<Fingerprint>> {
+        Ok(self
+            .encryption_domain_service
+            .load_local_device_fingerprint()
+            .await?
+            .map(|(_, fingerprint)| fingerprint))
+    }
+
+    /// Returns an `xmpp:` URI that can be shown as QR code so that contacts can verify this
+    /// device by scanning it.
+    pub async fn load_own_device_verification_uri(&self) -> Result<Option<String>> {
+        let user_id = self.ctx.connected_id()?.into_user_id();
+        Ok(self
+            .encryption_domain_service
+            .load_local_device_fingerprint()
+            .await?
+            .map(|(device_id, fingerprint)| fingerprint.verification_uri(&user_id, &device_id)))
+    }
+
+    pub async fn delete_device(&self, device_id: &DeviceId) -> Result<()> {
+        self.encryption_domain_service
+            .delete_device(device_id)
+            .await
+    }
+
+    pub async fn disable_omemo(&self) -> Result<()> {
+        self.encryption_domain_service.disable_omemo().await
+    }
+}
+
+impl AccountService {
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn set_avatar_from_image(&self, img: image::DynamicImage) -> Result<AvatarMetadata> {
         use crate::infra::constants::MAX_IMAGE_DIMENSIONS;
         use image::{codecs::jpeg::JpegEncoder, GenericImageView};
         use std::time::Instant;
 
         let now = Instant::now();
-        debug!("Opening & resizing image at {:?}…", image_path);
+        debug!("Resizing image…");
 
-        let img =
-            image::open(image_path)?.thumbnail(MAX_IMAGE_DIMENSIONS.0, MAX_IMAGE_DIMENSIONS.1);
-        debug!(
-            "Opening image & resizing finished after {:.2?}",
-            now.elapsed()
-        );
+        let max_dimension = self
+            .ctx
+            .config
+            .max_avatar_dimension
+            .min(MAX_IMAGE_DIMENSIONS.0);
+        let img = img.thumbnail(max_dimension, max_dimension);
+        debug!("Resizing image finished after {:.2?}", now.elapsed());
 
         let mut image_data = Vec::new();
         let encoder = JpegEncoder::new_with_quality(&mut image_data, 94);
@@ -202,19 +421,58 @@ impl AccountService {
         .await
     }
 
-    pub async fn load_device_infos(&self) -> Result<Vec<DeviceInfo>> {
-        self.encryption_domain_service
-            .load_device_infos(&self.ctx.connected_id()?.into_user_id())
-            .await
-    }
+    async fn apply_auto_away_transition(
+        &self,
+        transition: Option<AutoAwayTransition>,
+    ) -> Result<()> {
+        let Some(transition) = transition else {
+            return Ok(());
+        };
 
-    pub async fn delete_device(&self, device_id: &DeviceId) -> Result<()> {
-        self.encryption_domain_service
-            .delete_device(device_id)
-            .await
-    }
+        let account = self.ctx.connected_account()?;
+        let settings = self.account_settings_repo.get(&account).await?;
+
+        match transition {
+            AutoAwayTransition::BecameIdle {
+                availability,
+                idle_since,
+            } => {
+                send_availability(
+                    &self.ctx,
+                    &self.user_account_service,
+                    &self.connected_rooms_repo,
+                    &account,
+                    availability,
+                    settings.status_message,
+                    Some(idle_since),
+                )
+                .await?
+            }
+            AutoAwayTransition::BecameActive => {
+                send_availability(
+                    &self.ctx,
+                    &self.user_account_service,
+                    &self.connected_rooms_repo,
+                    &account,
+                    settings.availability,
+                    settings.status_message,
+                    None,
+                )
+                .await?
+            }
+        }
 
-    pub async fn disable_omemo(&self) -> Result<()> {
-        self.encryption_domain_service.disable_omemo().await
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::AccountInfoChanged);
+
+        Ok(())
     }
 }
+
+/// Reads the dimensions of the image in `data` from its header without decoding it.
+fn image_dimensions(data: &[u8], mime_type: &str) -> Option<(u32, u32)> {
+    let format = image::ImageFormat::from_mime_type(mime_type)?;
+    image::ImageReader::with_format(std::io::Cursor::new(data), format)
+        .into_dimensions()
+        .ok()
+}
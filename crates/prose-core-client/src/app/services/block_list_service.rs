@@ -8,8 +8,10 @@ use futures::future::join_all;
 
 use prose_proc_macros::InjectDependencies;
 
-use crate::app::deps::{DynBlockListDomainService, DynUserInfoDomainService};
-use crate::domain::shared::models::{CachePolicy, UserBasicInfo, UserId};
+use crate::app::deps::{
+    DynBlockListDomainService, DynSidebarDomainService, DynUserInfoDomainService,
+};
+use crate::domain::shared::models::{CachePolicy, RoomId, UserBasicInfo, UserId};
 use crate::domain::user_info::models::UserInfoOptExt;
 
 #[derive(InjectDependencies)]
@@ -17,6 +19,8 @@ pub struct BlockListService {
     #[inject]
     block_list_domain_service: DynBlockListDomainService,
     #[inject]
+    sidebar_domain_service: DynSidebarDomainService,
+    #[inject]
     user_info_domain_service: DynUserInfoDomainService,
 }
 
@@ -35,8 +39,18 @@ impl BlockListService {
         Ok(blocked_users)
     }
 
+    /// Returns the ids of the blocked users without resolving their names.
+    pub async fn blocked_users(&self) -> Result<Vec<UserId>> {
+        self.block_list_domain_service.load_block_list().await
+    }
+
+    /// Blocks the user and removes the direct message with them from the sidebar. Messages and
+    /// presences from the user are ignored until they're unblocked again.
     pub async fn block_user(&self, user_id: &UserId) -> Result<()> {
         self.block_list_domain_service.block_user(user_id).await?;
+        self.sidebar_domain_service
+            .remove_items(&[&RoomId::User(user_id.clone())])
+            .await?;
         Ok(())
     }
 
@@ -1,13 +1,17 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 
 use prose_proc_macros::InjectDependencies;
 
 use crate::app::deps::{
     DynAccountSettingsRepository, DynAppContext, DynAvatarRepository, DynBlockListDomainService,
-    DynContactListDomainService, DynDraftsRepository, DynEncryptionDomainService,
-    DynLocalRoomSettingsRepository, DynMessagesRepository, DynSidebarDomainService,
-    DynUserInfoDomainService,
+    DynConnectedRoomsReadOnlyRepository, DynContactListDomainService, DynDraftsRepository,
+    DynEncryptionDomainService, DynLocalRoomSettingsRepository, DynMessagesRepository,
+    DynPendingMessagesRepository, DynSessionRepository, DynSidebarDomainService, DynTimeProvider,
+    DynUserInfoDomainService, DynUserProfileRepository,
 };
+use crate::domain::rooms::models::Room;
+use crate::dtos::{AccountId, CacheUsageReport, PruneStats, RetentionPolicy};
 
 #[derive(InjectDependencies)]
 pub struct CacheService {
@@ -20,6 +24,8 @@ pub struct CacheService {
     #[inject]
     block_list_domain_service: DynBlockListDomainService,
     #[inject]
+    connected_rooms_repo: DynConnectedRoomsReadOnlyRepository,
+    #[inject]
     contact_list_domain_service: DynContactListDomainService,
     #[inject]
     drafts_repo: DynDraftsRepository,
@@ -30,12 +36,25 @@ pub struct CacheService {
     #[inject]
     messages_repo: DynMessagesRepository,
     #[inject]
+    pending_messages_repo: DynPendingMessagesRepository,
+    #[inject]
+    session_repo: DynSessionRepository,
+    #[inject]
     sidebar_domain_service: DynSidebarDomainService,
     #[inject]
+    time_provider: DynTimeProvider,
+    #[inject]
     user_info_domain_service: DynUserInfoDomainService,
+    #[inject]
+    user_profile_repo: DynUserProfileRepository,
 }
 
 impl CacheService {
+    /// Returns the schema version of the cache, e.g. to include it in diagnostic reports.
+    pub fn schema_version(&self) -> u32 {
+        self.ctx.cache_schema_version
+    }
+
     pub async fn clear_cache(&self) -> Result<()> {
         let account = self.ctx.connected_account()?;
 
@@ -44,6 +63,7 @@ impl CacheService {
         self.drafts_repo.clear_cache(&account).await?;
         self.local_room_settings_repo.clear_cache(&account).await?;
         self.messages_repo.clear_cache(&account).await?;
+        self.pending_messages_repo.clear_cache(&account).await?;
 
         self.block_list_domain_service.clear_cache().await?;
         self.contact_list_domain_service.clear_cache().await?;
@@ -53,4 +73,108 @@ impl CacheService {
 
         Ok(())
     }
+
+    /// Deletes expired items from the cache and applies the `RetentionPolicy` set via
+    /// `ClientBuilder::set_retention_policy`, if any. Apps should call this periodically, e.g.
+    /// once a day, to keep the cache from growing indefinitely.
+    pub async fn run_maintenance(&self) -> Result<PruneStats> {
+        let mut stats = self.user_info_domain_service.prune_cache().await?;
+
+        if let Some(policy) = &self.ctx.config.retention_policy {
+            let retention_stats = self.apply_retention_policy(policy).await?;
+            stats.avatars += retention_stats.avatars;
+            stats.messages += retention_stats.messages;
+        }
+
+        Ok(stats)
+    }
+
+    /// Returns the number of cached items and the space they take up per store.
+    pub async fn cache_usage(&self) -> Result<CacheUsageReport> {
+        let account = self.ctx.connected_account()?;
+
+        Ok(CacheUsageReport {
+            messages: self.messages_repo.usage(&account).await?,
+            avatars: self.avatar_repo.usage(&account).await?,
+            user_profiles: self.user_profile_repo.usage(&account).await?,
+            encryption_sessions: self.session_repo.usage(&account).await?,
+        })
+    }
+
+    /// Deletes messages and avatars exceeding the limits of `policy`.
+    ///
+    /// Messages of a room are only deleted up to its last read message and never if they were
+    /// sent after a message that is still waiting to be sent. Rooms without a read marker are
+    /// skipped altogether.
+    pub async fn apply_retention_policy(&self, policy: &RetentionPolicy) -> Result<PruneStats> {
+        let account = self.ctx.connected_account()?;
+        let mut stats = PruneStats::default();
+
+        if policy.max_message_age.is_some() || policy.max_messages_per_room.is_some() {
+            for room in self.connected_rooms_repo.get_all(&account) {
+                let Some(cutoff) = self.message_cutoff(&account, &room, policy).await? else {
+                    continue;
+                };
+
+                stats.messages += self
+                    .messages_repo
+                    .delete_messages_before(
+                        &account,
+                        &room.room_id,
+                        cutoff,
+                        self.ctx.config.retention_delete_batch_size,
+                    )
+                    .await?;
+            }
+        }
+
+        if let Some(max_bytes) = policy.max_avatar_cache_bytes {
+            stats.avatars = self.avatar_repo.evict(&account, max_bytes).await?;
+        }
+
+        Ok(stats)
+    }
+}
+
+impl CacheService {
+    /// Returns the date before which the messages of `room` can be deleted according to
+    /// `policy` or `None` if no messages should be deleted.
+    async fn message_cutoff(
+        &self,
+        account: &AccountId,
+        room: &Room,
+        policy: &RetentionPolicy,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let Some(last_read_message) = room.settings().last_read_message else {
+            return Ok(None);
+        };
+
+        let mut cutoff = policy
+            .max_message_age
+            .map(|max_age| self.time_provider.now() - max_age);
+
+        if let Some(max_messages) = policy.max_messages_per_room {
+            // If the room contains fewer messages than allowed there's nothing to delete…
+            let nth_latest_message_timestamp = self
+                .messages_repo
+                .get_nth_latest_message_timestamp(account, &room.room_id, max_messages)
+                .await?;
+            cutoff = cutoff.max(nth_latest_message_timestamp);
+        }
+
+        let Some(mut cutoff) = cutoff else {
+            return Ok(None);
+        };
+        cutoff = cutoff.min(last_read_message.timestamp);
+
+        let pending_messages = self
+            .pending_messages_repo
+            .get_all_in_room(account, &room.room_id)
+            .await?;
+        if let Some(oldest_pending_message) = pending_messages.first() {
+            cutoff = cutoff.min(oldest_pending_message.timestamp);
+        }
+
+        Ok(Some(cutoff))
+    }
 }
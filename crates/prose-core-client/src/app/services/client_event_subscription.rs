@@ -0,0 +1,107 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::{stream, Stream, StreamExt};
+use tokio::sync::broadcast::{self, error::RecvError, Sender};
+
+use crate::ClientEvent;
+
+/// The number of events that are buffered for each subscriber. Once a subscriber falls behind by
+/// more than this, its oldest events are dropped.
+const EVENT_STREAM_CAPACITY: usize = 256;
+
+#[cfg(target_arch = "wasm32")]
+type BoxedEventStream = stream::LocalBoxStream<'static, ClientEvent>;
+#[cfg(not(target_arch = "wasm32"))]
+type BoxedEventStream = stream::BoxStream<'static, ClientEvent>;
+
+/// Broadcasts client events to all subscribed streams.
+pub struct ClientEventSubscriptions {
+    sender: Sender<ClientEvent>,
+}
+
+impl Default for ClientEventSubscriptions {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_STREAM_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl ClientEventSubscriptions {
+    pub fn subscribe(&self) -> ClientEventStream {
+        ClientEventStream::new(self.sender.subscribe())
+    }
+
+    pub fn dispatch(&self, event: &ClientEvent) {
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        _ = self.sender.send(event.clone());
+    }
+}
+
+/// A stream of all events of the client. The events are delivered in the order in which they
+/// were dispatched and without being coalesced. A subscriber that falls behind never blocks the
+/// client; instead its oldest events are dropped and counted in `missed_events`.
+pub struct ClientEventStream {
+    stream: BoxedEventStream,
+    missed_events: Arc<AtomicU64>,
+}
+
+impl ClientEventStream {
+    fn new(receiver: broadcast::Receiver<ClientEvent>) -> Self {
+        let missed_events = Arc::new(AtomicU64::new(0));
+
+        let stream = stream::unfold(
+            (receiver, missed_events.clone()),
+            |(mut receiver, missed_events)| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) => return Some((event, (receiver, missed_events))),
+                        Err(RecvError::Lagged(count)) => {
+                            missed_events.fetch_add(count, Ordering::Relaxed);
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        );
+
+        #[cfg(target_arch = "wasm32")]
+        let stream = stream.boxed_local();
+        #[cfg(not(target_arch = "wasm32"))]
+        let stream = stream.boxed();
+
+        Self {
+            stream,
+            missed_events,
+        }
+    }
+
+    /// Returns the number of events that were dropped because this stream wasn't consumed fast
+    /// enough.
+    pub fn missed_events(&self) -> u64 {
+        self.missed_events.load(Ordering::Relaxed)
+    }
+
+    /// Returns the counter behind `missed_events`, which can be read while the stream is being
+    /// polled elsewhere.
+    pub fn missed_events_counter(&self) -> Arc<AtomicU64> {
+        self.missed_events.clone()
+    }
+}
+
+impl Stream for ClientEventStream {
+    type Item = ClientEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.poll_next_unpin(cx)
+    }
+}
@@ -3,21 +3,26 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::HashSet;
+
 use chrono::{DateTime, Utc};
-use secrecy::SecretString;
+use rand::RngCore;
+use secrecy::{ExposeSecret, SecretString};
 use tracing::{error, info, warn};
 
+use prose_wasm_utils::sleep;
+
 use prose_proc_macros::InjectDependencies;
-use prose_xmpp::{ConnectionError, IDProvider, TimeProvider};
+use prose_xmpp::{ConnectionError, IDProvider, SessionKind, TimeProvider};
 
 use crate::app::deps::{
     DynAccountSettingsRepository, DynAppContext, DynBlockListDomainService,
     DynClientEventDispatcher, DynConnectionService, DynContactListDomainService,
     DynEncryptionDomainService, DynIDProvider, DynOfflineMessagesRepository,
-    DynServerEventHandlerQueue, DynSidebarDomainService, DynTimeProvider, DynUserAccountService,
-    DynUserInfoDomainService,
+    DynPendingMessagesRepository, DynRngProvider, DynServerEventHandlerQueue,
+    DynSidebarDomainService, DynTimeProvider, DynUserAccountService, DynUserInfoDomainService,
 };
-use crate::app::event_handlers::ServerEvent;
+use crate::app::event_handlers::{ConnectionEvent as ServerConnectionEvent, ServerEvent};
 use crate::client_event::ConnectionEvent;
 use crate::domain::connection::models::ConnectionProperties;
 use crate::domain::shared::models::{AccountId, ConnectionState};
@@ -53,7 +58,11 @@ pub struct ConnectionService {
     #[inject]
     offline_messages_repo: DynOfflineMessagesRepository,
     #[inject]
+    pending_messages_repo: DynPendingMessagesRepository,
+    #[inject]
     server_event_handler_queue: DynServerEventHandlerQueue,
+    #[inject]
+    rng_provider: DynRngProvider,
 }
 
 impl ConnectionService {
@@ -62,9 +71,146 @@ impl ConnectionService {
         user_id: &UserId,
         password: SecretString,
     ) -> Result<(), ConnectionError> {
+        // A deliberate connect supersedes any pending reconnect attempts…
+        self.ctx.reconnect_state.lock().cancel();
+
+        let reconnect_password = self
+            .ctx
+            .config
+            .reconnect_policy
+            .is_some()
+            .then(|| SecretString::from(password.expose_secret().to_string()));
+
+        self.establish_session(user_id, password).await?;
+
+        if let Some(password) = reconnect_password {
+            self.ctx.reconnect_state.lock().credentials = Some((user_id.clone(), password));
+        }
+
+        self.dispatch_session_ready().await;
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::ConnectionStatusChanged {
+                event: ConnectionEvent::Connect,
+            });
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::AccountInfoChanged);
+
+        Ok(())
+    }
+
+    /// Makes the cached data of `user_id` available while the client is not connected and fills
+    /// the sidebar from the locally cached bookmarks.
+    pub async fn load_cached_state(&self, user_id: &UserId) -> anyhow::Result<()> {
+        if self.ctx.connected_account().is_ok() {
+            return Ok(());
+        }
+
+        let account = AccountId::from(user_id.clone().into_inner());
+        self.ctx.set_cached_account(Some(account.clone()));
+        self.sidebar_domain_service
+            .load_cached_sidebar(&account)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn disconnect(&self) {
+        self.ctx.reconnect_state.lock().cancel();
+        self.connection_service.disconnect().await;
+        self.ctx.set_connection_state(ConnectionState::Disconnected);
+        _ = self.sidebar_domain_service.handle_disconnect().await;
+        self.ctx.connection_properties.write().take();
+        self.ctx.set_cached_account(None);
+    }
+
+    pub fn unacked_message_count(&self) -> usize {
+        self.connection_service.unacked_message_count()
+    }
+
+    /// Tries to reestablish the connection after it was lost with `error`, according to the
+    /// configured `ReconnectPolicy`. Does nothing if no policy is configured, the client was
+    /// disconnected deliberately or another reconnect is already in progress.
+    pub async fn reconnect(&self, error: Option<ConnectionError>) {
+        let Some(policy) = self.ctx.config.reconnect_policy.clone() else {
+            return;
+        };
+
+        let generation = {
+            let mut state = self.ctx.reconnect_state.lock();
+            if state.credentials.is_none() || state.is_reconnecting {
+                return;
+            }
+            state.is_reconnecting = true;
+            state.generation
+        };
+
+        let mut error = error;
+        let mut attempt = 0;
+
+        loop {
+            let Some(delay) = policy.delay_after_error(attempt, error.as_ref()) else {
+                info!("Giving up reconnecting after {attempt} attempts.");
+                self.ctx.reconnect_state.lock().cancel();
+
+                // The disconnect itself was reported already, but we'll let clients know
+                // about the error that made us give up.
+                if attempt > 0 {
+                    self.client_event_dispatcher.dispatch_event(
+                        ClientEvent::ConnectionStatusChanged {
+                            event: ConnectionEvent::Disconnect { error },
+                        },
+                    );
+                }
+                return;
+            };
+
+            let random = self.rng_provider.rng().next_u32() as f64 / u32::MAX as f64;
+            let delay = policy.apply_jitter(delay, random);
+
+            info!("Reconnecting in {}ms…", delay.as_millis());
+            sleep(delay).await;
+
+            let Some((user_id, password)) = self.reconnect_credentials(generation) else {
+                // The client was disconnected or connected deliberately in the meantime…
+                return;
+            };
+
+            self.client_event_dispatcher
+                .dispatch_event(ClientEvent::ConnectionStatusChanged {
+                    event: ConnectionEvent::Reconnecting {
+                        attempt: attempt + 1,
+                    },
+                });
+
+            match self.restore_session(&user_id, password).await {
+                Ok(_) => break,
+                Err(err) => {
+                    warn!("Reconnect attempt {} failed. {}", attempt + 1, err);
+                    error = Some(err);
+                    attempt += 1;
+                }
+            }
+        }
+
+        let mut state = self.ctx.reconnect_state.lock();
+        if state.generation == generation {
+            state.is_reconnecting = false;
+        }
+    }
+}
+
+impl ConnectionService {
+    async fn establish_session(
+        &self,
+        user_id: &UserId,
+        password: SecretString,
+    ) -> Result<SessionKind, ConnectionError> {
         self.ctx.set_connection_state(ConnectionState::Connecting);
         self.offline_messages_repo.drain();
 
+        let previous_connection_properties = self.ctx.connection_properties.read().clone();
+
         let account = AccountId::from(user_id.clone().into_inner());
 
         let settings = self
@@ -78,6 +224,8 @@ impl ConnectionService {
             .resource
             .unwrap_or_else(|| self.short_id_provider.new_id());
         let availability = settings.availability;
+        let user_status = settings.user_status;
+        let status_message = settings.status_message;
 
         let full_jid = user_id
             .with_resource(&resource)
@@ -95,12 +243,25 @@ impl ConnectionService {
             .set_connection_properties(connection_properties.clone());
 
         let connection_result = self.connection_service.connect(&full_jid, password).await;
-        match connection_result {
-            Ok(_) => (),
+        let session = match connection_result {
+            Ok(session) => session,
             Err(err) => {
                 self.ctx.reset_connection_properties();
                 return Err(err);
             }
+        };
+
+        if let (SessionKind::Resumed, Some(previous_connection_properties)) =
+            (session, previous_connection_properties)
+        {
+            // The server kept our session (presence, joined rooms, carbons…) alive and
+            // retransmitted what we've missed, so we can pick up right where we left off.
+            info!("Resumed previous session.");
+            self.ctx
+                .set_connection_properties(previous_connection_properties);
+            self.ctx.set_connection_state(ConnectionState::Connected);
+            self.apply_offline_messages().await;
+            return Ok(SessionKind::Resumed);
         }
 
         connection_properties.connection_timestamp = self.time_provider.now();
@@ -109,6 +270,10 @@ impl ConnectionService {
 
         self.reset_services_before_reconnect().await;
 
+        // Messages sent in the previous session which the server didn't confirm might have been
+        // lost, so they're handed back to the outbox to be sent again.
+        self.ctx.unconfirmed_message_ids.lock().clear();
+
         // https://xmpp.org/rfcs/rfc6121.html#roster-login
         if let Ok(contacts) = self
             .contact_list_domain_service
@@ -124,12 +289,32 @@ impl ConnectionService {
         };
 
         self.user_account_service
-            .set_availability(None, &self.ctx.capabilities, availability)
+            .set_availability(
+                None,
+                &self.ctx.capabilities,
+                availability,
+                status_message,
+                self.ctx.config.presence_priority,
+                None,
+            )
             .await
             .map_err(|err| ConnectionError::Generic {
                 msg: err.to_string(),
             })?;
 
+        if let Some(user_status) = user_status {
+            if let Err(err) = self
+                .user_account_service
+                .set_user_activity(Some(&user_status))
+                .await
+            {
+                error!(
+                    "Failed to restore user activity. Reason: {}",
+                    err.to_string()
+                );
+            }
+        }
+
         if let Err(err) = self
             .connection_service
             .set_message_carbons_enabled(true)
@@ -178,7 +363,12 @@ impl ConnectionService {
             })?;
 
         self.ctx.set_connection_state(ConnectionState::Connected);
+        self.apply_offline_messages().await;
+
+        Ok(SessionKind::New)
+    }
 
+    async fn apply_offline_messages(&self) {
         let offline_message_events = self.offline_messages_repo.drain();
         info!(
             "Applying {} cached offline messages…",
@@ -189,27 +379,96 @@ impl ConnectionService {
                 .handle_server_event(ServerEvent::Message(event))
                 .await;
         }
+    }
+
+    /// Lets our event handlers know that the session is set up, e.g. so that the outbox is
+    /// flushed.
+    async fn dispatch_session_ready(&self) {
+        self.server_event_handler_queue
+            .handle_server_event(ServerEvent::Connection(ServerConnectionEvent::SessionReady))
+            .await;
+    }
+
+    /// Reestablishes the session and rejoins all rooms that were connected before the connection
+    /// was lost. Dispatches a single `ClientEvent::ConnectionStatusChanged` event once done.
+    async fn restore_session(
+        &self,
+        user_id: &UserId,
+        password: SecretString,
+    ) -> Result<(), ConnectionError> {
+        if self.establish_session(user_id, password).await? == SessionKind::Resumed {
+            // Our rooms are still joined and nothing was lost, so there's no need to catch up.
+            self.dispatch_session_ready().await;
+            self.client_event_dispatcher
+                .dispatch_event(ClientEvent::ConnectionStatusChanged {
+                    event: ConnectionEvent::Connect,
+                });
+            return Ok(());
+        }
+
+        if let Some(context) = self.ctx.decryption_context() {
+            if let Err(err) = self.sidebar_domain_service.handle_reconnect(context).await {
+                error!(
+                    "Failed to rejoin rooms after reconnect. {}",
+                    err.to_string()
+                );
+            }
+        }
+
+        self.ctx.set_rooms_caught_up();
+
+        if let Some(context) = self.ctx.take_decryption_context() {
+            self.encryption_domain_service
+                .finalize_decryption(context)
+                .await;
+        }
+
+        // Messages still in the outbox are resent from there once the session is ready…
+        let outbox_message_ids = match self
+            .pending_messages_repo
+            .get_all(&AccountId::from(user_id.clone().into_inner()))
+            .await
+        {
+            Ok(messages) => messages
+                .into_iter()
+                .map(|message| message.id)
+                .collect::<HashSet<_>>(),
+            Err(err) => {
+                warn!("Failed to load outbox. {}", err.to_string());
+                HashSet::new()
+            }
+        };
+
+        if let Err(err) = self
+            .connection_service
+            .resend_unacknowledged_messages(&outbox_message_ids)
+            .await
+        {
+            warn!("Failed to resend unacknowledged messages. {}", err);
+        }
 
+        self.dispatch_session_ready().await;
         self.client_event_dispatcher
             .dispatch_event(ClientEvent::ConnectionStatusChanged {
                 event: ConnectionEvent::Connect,
             });
 
-        self.client_event_dispatcher
-            .dispatch_event(ClientEvent::AccountInfoChanged);
-
         Ok(())
     }
 
-    pub async fn disconnect(&self) {
-        self.connection_service.disconnect().await;
-        self.ctx.set_connection_state(ConnectionState::Disconnected);
-        _ = self.sidebar_domain_service.handle_disconnect().await;
-        self.ctx.connection_properties.write().take();
+    fn reconnect_credentials(&self, generation: u64) -> Option<(UserId, SecretString)> {
+        let state = self.ctx.reconnect_state.lock();
+        if state.generation != generation {
+            return None;
+        }
+        state.credentials.as_ref().map(|(user_id, password)| {
+            (
+                user_id.clone(),
+                SecretString::from(password.expose_secret().to_string()),
+            )
+        })
     }
-}
 
-impl ConnectionService {
     async fn reset_services_before_reconnect(&self) {
         _ = self
             .user_info_domain_service
@@ -4,14 +4,17 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use futures::future::join_all;
 
 use prose_proc_macros::InjectDependencies;
 
 use crate::app::deps::*;
 use crate::app::dtos::Contact as ContactDTO;
-use crate::domain::contacts::models::{Contact, PresenceSubRequest as DomainPresenceSubRequest};
-use crate::domain::shared::models::{AccountId, CachePolicy};
+use crate::domain::contacts::models::{
+    Contact, PresenceSubRequest as DomainPresenceSubRequest, PresenceSubscription,
+};
+use crate::domain::shared::models::{AccountId, Availability, CachePolicy};
 use crate::domain::user_info::models::UserInfoOptExt;
 use crate::dtos::{Group, PresenceSubRequest, PresenceSubRequestId, UserId};
 
@@ -27,7 +30,7 @@ pub struct ContactListService {
 
 impl ContactListService {
     pub async fn load_contacts(&self) -> Result<Vec<ContactDTO>> {
-        let account = self.ctx.connected_account()?;
+        let account = self.ctx.account()?;
         let domain_contacts = self.contact_list_domain_service.load_contacts().await?;
 
         let contacts = join_all(
@@ -40,16 +43,84 @@ impl ContactListService {
         Ok(contacts)
     }
 
-    pub async fn add_contact(&self, jid: &UserId) -> Result<()> {
-        self.contact_list_domain_service.add_contact(jid).await?;
+    /// Adds `user_id` to the contact list. If `request_presence_sub` is true, a presence
+    /// subscription request is sent to them as well.
+    pub async fn add_contact(
+        &self,
+        user_id: &UserId,
+        name: Option<&str>,
+        groups: &[String],
+        request_presence_sub: bool,
+    ) -> Result<()> {
+        let contact = self
+            .contact_list_domain_service
+            .add_contact(user_id, name, groups, request_presence_sub)
+            .await?;
+        self.user_info_domain_service
+            .handle_contacts_changed(vec![contact])
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_contact(&self, user_id: &UserId) -> Result<()> {
+        self.contact_list_domain_service
+            .remove_contact(user_id)
+            .await?;
         Ok(())
     }
 
-    pub async fn remove_contact(&self, jid: &UserId) -> Result<()> {
-        self.contact_list_domain_service.remove_contact(jid).await?;
+    /// Sets the name under which `user_id` is saved in the contact list. Pass `None` to remove it.
+    pub async fn rename_contact(&self, user_id: &UserId, name: Option<&str>) -> Result<()> {
+        let contact = self
+            .contact_list_domain_service
+            .rename_contact(user_id, name)
+            .await?;
+        self.user_info_domain_service
+            .handle_contacts_changed(vec![contact])
+            .await?;
+        Ok(())
+    }
+
+    /// Replaces the groups `user_id` is assigned to in the contact list.
+    pub async fn set_groups(&self, user_id: &UserId, groups: &[String]) -> Result<()> {
+        self.contact_list_domain_service
+            .set_contact_groups(user_id, groups)
+            .await?;
         Ok(())
     }
 
+    /// Returns when `user_id` was last active. Returns `None` if the contact doesn't share their
+    /// presence with us and we don't share ours with them, so that we don't ask for more than
+    /// they have agreed to. If their server doesn't tell, returns when we've last seen them.
+    pub async fn load_last_activity(&self, user_id: &UserId) -> Result<Option<DateTime<Utc>>> {
+        let is_mutual_contact = self
+            .contact_list_domain_service
+            .load_contact(user_id)
+            .await?
+            .is_some_and(|contact| contact.presence_subscription == PresenceSubscription::Mutual);
+
+        if !is_mutual_contact {
+            return Ok(None);
+        }
+
+        let Some(last_activity) = self
+            .user_info_domain_service
+            .load_last_activity(user_id)
+            .await?
+        else {
+            return self
+                .contact_list_domain_service
+                .load_last_seen(user_id)
+                .await;
+        };
+
+        self.contact_list_domain_service
+            .set_last_seen(user_id, last_activity)
+            .await?;
+
+        Ok(Some(last_activity))
+    }
+
     pub async fn request_presence_sub(&self, from: &UserId) -> Result<()> {
         self.contact_list_domain_service
             .request_presence_sub(from)
@@ -72,9 +143,15 @@ impl ContactListService {
         Ok(requests)
     }
 
-    pub async fn approve_presence_sub_request(&self, id: &PresenceSubRequestId) -> Result<()> {
+    /// Approves the presence subscription request identified by `id`. If `subscribe_back` is
+    /// true, a presence subscription is requested in return.
+    pub async fn approve_presence_sub_request(
+        &self,
+        id: &PresenceSubRequestId,
+        subscribe_back: bool,
+    ) -> Result<()> {
         self.contact_list_domain_service
-            .approve_presence_sub_request(&id.to_user_id())
+            .approve_presence_sub_request(&id.to_user_id(), subscribe_back)
             .await?;
         Ok(())
     }
@@ -105,6 +182,16 @@ impl ContactListService {
             .unwrap_or_default()
             .into_user_presence_info_or_fallback(contact.id);
 
+        // We only know since when an online contact is idle, but not when they were last active.
+        let last_seen = if user_info.availability == Availability::Unavailable {
+            self.contact_list_domain_service
+                .load_last_seen(&user_info.id)
+                .await
+                .unwrap_or_default()
+        } else {
+            user_info.idle_since
+        };
+
         ContactDTO {
             id: user_info.id,
             name: user_info.name,
@@ -112,6 +199,8 @@ impl ContactListService {
             avatar: user_info.avatar,
             availability: user_info.availability,
             status: user_info.status,
+            status_message: user_info.status_message,
+            last_seen,
             group,
             presence_subscription: contact.presence_subscription,
         }
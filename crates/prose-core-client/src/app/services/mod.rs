@@ -6,6 +6,8 @@
 pub use account_service::AccountService;
 pub use block_list_service::BlockListService;
 pub use cache_service::CacheService;
+pub use client_event_subscription::ClientEventStream;
+pub(crate) use client_event_subscription::ClientEventSubscriptions;
 pub use connection_service::ConnectionService;
 pub use contact_list_service::ContactListService;
 #[cfg(feature = "debug")]
@@ -13,6 +15,10 @@ pub use debug_service::DebugService;
 pub use preview_service::PreviewService;
 pub(crate) use room::RoomInner;
 pub use room::{DirectMessage, Generic, Group, PrivateChannel, PublicChannel, Room};
+pub(crate) use room_subscription::RoomSubscriptions;
+pub use room_subscription::{
+    RoomSubscription, RoomSubscriptionHandle, RoomUpdate, RoomUpdateHandler,
+};
 pub use rooms_service::RoomsService;
 pub use sidebar_service::SidebarService;
 pub use upload_service::UploadService;
@@ -21,12 +27,14 @@ pub use user_data_service::UserDataService;
 mod account_service;
 mod block_list_service;
 mod cache_service;
+mod client_event_subscription;
 mod connection_service;
 mod contact_list_service;
 #[cfg(feature = "debug")]
 mod debug_service;
 mod preview_service;
 pub(crate) mod room;
+mod room_subscription;
 mod rooms_service;
 mod sidebar_service;
 mod upload_service;
@@ -3,44 +3,68 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
+use std::io::{BufRead, BufReader, Read};
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::sync::Arc;
 
 use anyhow::{anyhow, bail, ensure, format_err, Result};
-use chrono::Duration;
+use chrono::{DateTime, Duration, Utc};
+use indexmap::IndexSet;
 use itertools::Itertools;
+use jid::Jid;
 use tracing::{debug, error, info, warn};
+use url::Url;
 
 use prose_markup::MarkdownParser;
+use prose_wasm_utils::{sleep, spawn};
 use prose_xmpp::TimeProvider;
 
 use crate::app::deps::{
-    DynAppContext, DynClientEventDispatcher, DynDraftsRepository, DynEncryptionDomainService,
-    DynMessageArchiveService, DynMessageIdProvider, DynMessagesRepository, DynMessagingService,
-    DynRoomAttributesService, DynRoomParticipationService, DynSidebarDomainService,
-    DynSyncedRoomSettingsService, DynTimeProvider, DynUserInfoDomainService,
+    DynAccountSettingsRepository, DynAppContext, DynClientEventDispatcher, DynDraftsRepository,
+    DynEncryptionDomainService, DynIDProvider, DynMessageArchiveService, DynMessageIdProvider,
+    DynMessagesRepository, DynMessagingService, DynPendingMessagesRepository,
+    DynRoomAttributesService, DynRoomManagementService, DynRoomParticipationService,
+    DynSidebarDomainService, DynSyncedRoomSettingsService, DynTimeProvider,
+    DynUserInfoDomainService,
 };
+use crate::app::services::{RoomSubscription, RoomSubscriptionHandle, RoomUpdateHandler};
+use crate::domain::encryption::models::{DeviceId, TrustPolicy};
+use crate::domain::encryption::services::EncryptionError;
 use crate::domain::messaging::models::{
-    send_message_request, ArchivedMessageRef, Emoji, Message, MessageId, MessageLike,
-    MessageLikeBody, MessageLikeError, MessageParser, MessageRemoteId, MessageTargetId, ThreadId,
+    send_message_request, ArchivedMessageRef, Attachment, Emoji, EncryptedPayload, Message,
+    MessageId, MessageLike, MessageLikeBody, MessageLikeEncryptionInfo, MessageLikeError,
+    MessageParser, MessageRemoteId, MessageTargetId, PendingMessage, PendingMessageState,
+    PinnedMessageRef, ReactionError, ReplyTo, ThreadId,
 };
 use crate::domain::messaging::models::{MessageLikePayload, SendMessageRequest};
-use crate::domain::rooms::models::{Room as DomainRoom, RoomAffiliation, RoomSpec};
-use crate::domain::settings::models::SyncedRoomSettings;
+use crate::domain::messaging::services::SendMessageError;
+use crate::domain::rooms::models::constants::{
+    COMPOSE_STATE_TIMER_INTERVAL, MAX_CONCURRENT_PROFILE_REQUESTS,
+};
+use crate::domain::rooms::models::{
+    ComposeState, Participant, Room as DomainRoom, RoomAffiliation, RoomConfiguration, RoomError,
+    RoomRole, RoomSpec,
+};
+use crate::domain::settings::models::{RoomNotificationMode, SyncedRoomSettings};
 use crate::domain::shared::models::{
-    AccountId, CachePolicy, MucId, ParticipantId, ParticipantInfo, RoomId, RoomType, StyledMessage,
+    AccountId, Availability, CachePolicy, ConnectionState, Markdown, MucId, ParticipantId,
+    ParticipantInfo, ParticipantProfile, RoomId, RoomType, StyledMessage,
 };
 use crate::domain::shared::utils::ContactNameBuilder;
+use crate::domain::user_info::models::UserInfoOptExt;
 use crate::dtos::{
-    Mention, Message as MessageDTO, MessageFlags as MessageFlagsDTO, MessageResultSet,
-    MessageSender, MessageServerId, ParticipantBasicInfo, Reaction as ReactionDTO,
-    ReplyTo as ReplyToDTO, RoomState, SendMessageRequest as SendMessageRequestDTO, UserId, HTML,
+    DataSource, DateRange, DeliveryState, Draft, ExportFormat, ExportedHistory, ExportedMessage,
+    HistoryImportSummary, ImportedMessage, Mention, Message as MessageDTO, MessageCursor,
+    MessageFlags as MessageFlagsDTO, MessageResultSet, MessageSender, MessageServerId, OccupantId,
+    ParticipantBasicInfo, Reaction as ReactionDTO, ReplyTo as ReplyToDTO, RoomState,
+    SendMessageRequest as SendMessageRequestDTO,
+    SendMessageRequestBody as SendMessageRequestBodyDTO, UserBasicInfo, UserId, HTML,
 };
 use crate::infra::xmpp::util::MessageExt;
+use crate::util::join_all;
 use crate::{ClientEvent, ClientRoomEventType};
 
 pub struct Room<Kind> {
@@ -83,16 +107,20 @@ impl MucRoom for Generic {}
 pub struct RoomInner {
     pub(crate) data: DomainRoom,
 
+    pub(crate) account_settings_repo: DynAccountSettingsRepository,
     pub(crate) attributes_service: DynRoomAttributesService,
     pub(crate) client_event_dispatcher: DynClientEventDispatcher,
     pub(crate) ctx: DynAppContext,
     pub(crate) drafts_repo: DynDraftsRepository,
     pub(crate) encryption_domain_service: DynEncryptionDomainService,
+    pub(crate) id_provider: DynIDProvider,
     pub(crate) message_archive_service: DynMessageArchiveService,
     pub(crate) message_id_provider: DynMessageIdProvider,
     pub(crate) message_repo: DynMessagesRepository,
     pub(crate) messaging_service: DynMessagingService,
     pub(crate) participation_service: DynRoomParticipationService,
+    pub(crate) pending_messages_repo: DynPendingMessagesRepository,
+    pub(crate) room_management_service: DynRoomManagementService,
     pub(crate) sidebar_domain_service: DynSidebarDomainService,
     pub(crate) synced_room_settings_service: DynSyncedRoomSettingsService,
     pub(crate) time_provider: DynTimeProvider,
@@ -172,10 +200,215 @@ impl<Kind> Room<Kind> {
         self.data.topic()
     }
 
+    /// Returns a stream of the updates of this room. The subscription ends when the stream is
+    /// dropped.
+    pub fn subscribe(&self) -> RoomSubscription {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let handle = self.client_event_dispatcher.subscribe_to_room(
+            self.data.room_id.clone(),
+            Box::new(move |update| {
+                _ = tx.send(update);
+            }),
+        );
+        RoomSubscription::new(rx, handle)
+    }
+
+    /// Calls `handler` for every update of this room until the returned handle is dropped.
+    pub fn subscribe_with_handler(&self, handler: RoomUpdateHandler) -> RoomSubscriptionHandle {
+        self.client_event_dispatcher
+            .subscribe_to_room(self.data.room_id.clone(), handler)
+    }
+
     pub fn participants(&self) -> Vec<ParticipantInfo> {
         self.data
             .with_participants(|p| p.iter().map(ParticipantInfo::from).collect())
     }
+
+    /// Returns the participant identified by `id` if it is kept in memory.
+    pub fn participant(&self, id: &ParticipantId) -> Option<ParticipantInfo> {
+        self.data.with_participants(|p| {
+            p.get(id)
+                .map(|participant| ParticipantInfo::from((id, participant)))
+        })
+    }
+
+    /// Returns the number of participants in the room. Includes the participants that were
+    /// evicted from memory if `AppConfig::max_cached_participants` is set.
+    pub fn participant_count(&self) -> usize {
+        self.data.with_participants(|p| p.total_len())
+    }
+
+    /// Returns up to `limit` participants starting at `offset`, sorted by affiliation and name.
+    /// If participants were evicted from memory, the occupants of the room are loaded via a
+    /// disco#items query and the ones we don't know about are returned with the information
+    /// derived from their occupant id.
+    pub async fn load_participants(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<ParticipantInfo>> {
+        let (mut participants, evicted_participants) = self.data.with_participants(|p| {
+            let participants = p.iter().map(ParticipantInfo::from).collect::<Vec<_>>();
+            let evicted_participants = p
+                .evicted_participant_ids()
+                .map(|id| (id.clone(), p.evicted_participant_real_id(id).cloned()))
+                .collect::<HashMap<_, _>>();
+            (participants, evicted_participants)
+        });
+
+        if !evicted_participants.is_empty() {
+            let occupant_ids = match &self.data.room_id {
+                RoomId::Muc(room_id) => self
+                    .participation_service
+                    .load_occupants(room_id)
+                    .await
+                    .unwrap_or_else(|err| {
+                        warn!("Failed to load occupants of {room_id}. {}", err.to_string());
+                        vec![]
+                    }),
+                RoomId::User(_) => vec![],
+            };
+
+            // Rooms may choose to not disclose their occupants, in which case we'll stick
+            // with what we've got…
+            let missing_participants = if occupant_ids.is_empty() {
+                evicted_participants
+                    .into_iter()
+                    .map(|(id, real_id)| {
+                        (
+                            id,
+                            Participant {
+                                real_id,
+                                ..Default::default()
+                            },
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                let known_ids = participants
+                    .iter()
+                    .map(|p| p.id.clone())
+                    .collect::<HashSet<_>>();
+
+                occupant_ids
+                    .into_iter()
+                    .map(ParticipantId::Occupant)
+                    .filter(|id| !known_ids.contains(id))
+                    .map(|id| {
+                        let real_id = evicted_participants.get(&id).cloned().flatten();
+                        (
+                            id,
+                            Participant {
+                                real_id,
+                                availability: Availability::Available,
+                                ..Default::default()
+                            },
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            participants.extend(
+                missing_participants
+                    .iter()
+                    .map(|(id, participant)| ParticipantInfo::from((id, participant))),
+            );
+        }
+
+        Ok(participants
+            .into_iter()
+            .sorted_by(|lhs, rhs| {
+                rhs.affiliation
+                    .cmp(&lhs.affiliation)
+                    .then_with(|| lhs.name.to_lowercase().cmp(&rhs.name.to_lowercase()))
+            })
+            .skip(offset)
+            .take(limit)
+            .collect())
+    }
+
+    /// Returns all participants of the room including their profiles. Cached profiles are used
+    /// where available, missing profiles are loaded in batches of
+    /// `MAX_CONCURRENT_PROFILE_REQUESTS`.
+    pub async fn load_participants_with_profiles(&self) -> Result<Vec<ParticipantProfile>> {
+        let participants = self.data.with_participants(|p| {
+            p.iter()
+                .map(|(id, participant)| (id.clone(), participant.clone()))
+                .collect::<Vec<_>>()
+        });
+
+        let mut user_infos = HashMap::new();
+        let mut missing_user_ids = vec![];
+
+        for user_id in participants.iter().filter_map(|(_, p)| p.real_id.as_ref()) {
+            match self
+                .user_info_domain_service
+                .get_user_info(user_id, CachePolicy::ReturnCacheDataDontLoad)
+                .await?
+            {
+                Some(user_info) => {
+                    user_infos.insert(user_id.clone(), user_info);
+                }
+                None => missing_user_ids.push(user_id.clone()),
+            }
+        }
+
+        for user_ids in missing_user_ids.chunks(MAX_CONCURRENT_PROFILE_REQUESTS) {
+            let results = join_all(user_ids.iter().map(|user_id| async move {
+                let result = self
+                    .user_info_domain_service
+                    .get_user_info(user_id, CachePolicy::ReturnCacheDataElseLoad)
+                    .await;
+                (user_id, result)
+            }))
+            .await;
+
+            for (user_id, result) in results {
+                match result {
+                    Ok(Some(user_info)) => {
+                        user_infos.insert(user_id.clone(), user_info);
+                    }
+                    Ok(None) => (),
+                    Err(err) => warn!("Failed to load profile of {user_id}. {}", err.to_string()),
+                }
+            }
+        }
+
+        Ok(participants
+            .into_iter()
+            .map(|(id, participant)| {
+                let user_info = participant
+                    .real_id
+                    .as_ref()
+                    .and_then(|user_id| user_infos.remove(user_id));
+
+                let name = match &user_info {
+                    Some(user_info) => user_info
+                        .display_name()
+                        .or_nickname(participant.name.presence.as_ref())
+                        .unwrap_or_participant_id(&id),
+                    None => participant.name().unwrap_or_participant_id(&id),
+                };
+                let full_name = user_info.as_ref().and_then(|info| info.full_name());
+                let (avatar, status) = user_info
+                    .map(|info| (info.avatar, info.status))
+                    .unwrap_or_default();
+
+                ParticipantProfile {
+                    id,
+                    user_id: participant.real_id,
+                    name,
+                    full_name,
+                    is_self: participant.is_self,
+                    availability: participant.availability,
+                    affiliation: participant.affiliation,
+                    role: participant.role,
+                    avatar: avatar.or(participant.avatar),
+                    status,
+                }
+            })
+            .collect())
+    }
 }
 
 impl<Kind> Room<Kind> {
@@ -197,51 +430,88 @@ impl<Kind> Room<Kind> {
             _ => (),
         }
 
-        self.process_send_message_request(
-            &self.ctx.connected_account()?,
-            request,
-            ProcessMessageAction::Send,
-        )
-        .await
+        let action = match request.thread_id.clone() {
+            Some(thread_id) => ProcessMessageAction::ReplyInThread { thread_id },
+            None => ProcessMessageAction::Send,
+        };
+
+        self.process_send_message_request(&self.ctx.connected_account()?, request, action)
+            .await
     }
 
-    pub async fn reply_to_message(
+    /// Replies to the message with `parent_id` in a thread. If the message isn't part of a thread
+    /// yet, a new thread is started with it.
+    pub async fn reply_in_thread(
         &self,
-        id: MessageId,
+        parent_id: MessageId,
         request: SendMessageRequestDTO,
     ) -> Result<()> {
         ensure!(!request.is_empty(), "SendMessageRequest is empty");
 
         let account = self.ctx.connected_account()?;
 
-        let Some(message) = self
+        let Some(mut parent) = self
             .message_repo
-            .get(&account, &self.data.room_id, &id)
+            .get(&account, &self.data.room_id, &parent_id)
             .await?
             .into_iter()
-            .next()
+            .find(|message| message.id == parent_id)
         else {
             bail!("Could not find message to reply to.")
         };
 
-        let MessageLikePayload::Message { thread_id, .. } = message.payload else {
+        let MessageLikePayload::Message { thread_id, .. } = &mut parent.payload else {
             bail!("Message is not a regular message and cannot be replied to.")
         };
 
-        let thread_id = thread_id.unwrap_or_else(|| ThreadId::from(message.id.into_inner()));
+        let thread_id = match thread_id {
+            Some(thread_id) => thread_id.clone(),
+            None => {
+                let new_thread_id = ThreadId::from(self.id_provider.new_id());
+                *thread_id = Some(new_thread_id.clone());
+                // Store the thread with the parent so that it's contained in the loaded thread…
+                self.message_repo
+                    .append(&account, &self.data.room_id, &[parent])
+                    .await?;
+                new_thread_id
+            }
+        };
 
         self.process_send_message_request(
-            &self.ctx.connected_account()?,
+            &account,
             request,
             ProcessMessageAction::ReplyInThread { thread_id },
         )
         .await
     }
 
-    pub async fn update_message(
+    #[deprecated(note = "Use `reply_in_thread` instead.")]
+    pub async fn reply_to_message(
         &self,
         id: MessageId,
         request: SendMessageRequestDTO,
+    ) -> Result<()> {
+        self.reply_in_thread(id, request).await
+    }
+
+    /// Loads the messages of the thread with `thread_id`, starting with the message that started
+    /// the thread.
+    pub async fn load_thread(&self, thread_id: &ThreadId) -> Result<Vec<MessageDTO>> {
+        let account = self.ctx.connected_account()?;
+        let message_ids = self
+            .message_repo
+            .get_thread(&account, &self.data.room_id, thread_id)
+            .await?
+            .into_iter()
+            .map(|message| message.id)
+            .collect::<Vec<_>>();
+        self.load_messages_with_ids(&message_ids).await
+    }
+
+    pub async fn update_message(
+        &self,
+        id: MessageId,
+        mut request: SendMessageRequestDTO,
     ) -> Result<()> {
         ensure!(!request.is_empty(), "SendMessageRequest is empty");
 
@@ -257,6 +527,23 @@ impl<Kind> Room<Kind> {
             bail!("Failed to resolve message id '{id}' to a server id")
         };
 
+        // A correction replaces the whole message. If only the attachments were changed we'll
+        // carry over the text of the original message so that it doesn't get blanked out…
+        if request.body.is_none() {
+            let messages = self
+                .message_repo
+                .get(&account, &self.data.room_id, &id)
+                .await?;
+
+            if let Some(message) = Message::reducing_messages(messages).pop() {
+                if !message.body.raw.is_empty() {
+                    request.body = Some(SendMessageRequestBodyDTO {
+                        text: Markdown::from(message.body.raw),
+                    });
+                }
+            }
+        }
+
         self.process_send_message_request(
             &account,
             request,
@@ -268,13 +555,18 @@ impl<Kind> Room<Kind> {
         .await
     }
 
+    /// Toggles `emoji` on the message with `id`.
+    ///
+    /// Fails with `ReactionError::MissingServerId` for messages in MUC rooms which haven't
+    /// received their StanzaId from the room yet. UIs should disable reactions on these
+    /// messages, i.e. messages in MUC rooms without a server id.
     pub async fn toggle_reaction_to_message(&self, id: MessageId, emoji: Emoji) -> Result<()> {
         let account = self.ctx.connected_account()?;
         let messages = self
             .message_repo
             .get(&account, &self.data.room_id, &id)
             .await?;
-        let user_jid = ParticipantId::from(account.into_user_id());
+        let user_jid = ParticipantId::from(account.clone().into_user_id());
 
         let mut message = Message::reducing_messages(messages)
             .pop()
@@ -286,27 +578,55 @@ impl<Kind> Room<Kind> {
             .cloned()
             .collect::<Vec<_>>();
 
-        match &self.data.room_id {
-            RoomId::User(room_id) => {
-                let Some(remote_id) = &message.remote_id else {
-                    bail!("Cannot react to message for which we do not have a RemoteId.")
-                };
-                self.messaging_service
-                    .react_to_chat_message(room_id, remote_id, &all_emojis)
-                    .await
-            }
-            RoomId::Muc(room_id) => {
-                let Some(stanza_id) = &message.server_id else {
-                    bail!("Cannot react to MUC message for which we do not have a StanzaId.")
-                };
-                self.messaging_service
-                    .react_to_muc_message(room_id, stanza_id, &all_emojis)
-                    .await
-            }
+        self.send_reactions(
+            &account,
+            &id,
+            message.remote_id.as_ref(),
+            message.server_id.as_ref(),
+            &all_emojis,
+        )
+        .await
+    }
+
+    /// Toggles `toggle` based on the reactions the caller knows we've already sent to the
+    /// message (e.g. from a rendered `MessageDTO`), which saves loading the message first.
+    /// Falls back to `toggle_reaction_to_message` if `current_my_emojis` is `None`.
+    pub async fn set_my_reactions_from_known(
+        &self,
+        id: MessageId,
+        current_my_emojis: Option<Vec<Emoji>>,
+        toggle: Emoji,
+    ) -> Result<()> {
+        let Some(mut all_emojis) = current_my_emojis else {
+            return self.toggle_reaction_to_message(id, toggle).await;
+        };
+
+        if let Some(idx) = all_emojis.iter().position(|emoji| emoji == &toggle) {
+            all_emojis.remove(idx);
+        } else {
+            all_emojis.push(toggle);
         }
+
+        let account = self.ctx.connected_account()?;
+        let Some(ids) = self
+            .message_repo
+            .resolve_message_id(&account, &self.data.room_id, &id)
+            .await?
+        else {
+            bail!("No message with id {}", id)
+        };
+
+        self.send_reactions(
+            &account,
+            &id,
+            ids.remote_id.as_ref(),
+            ids.server_id.as_ref(),
+            &all_emojis,
+        )
+        .await
     }
 
-    pub async fn retract_message(&self, id: MessageId) -> Result<()> {
+    pub async fn retract_message(&self, id: MessageId, reason: Option<&str>) -> Result<()> {
         let account = self.ctx.connected_account()?;
 
         let Some(remote_id) = self
@@ -319,7 +639,7 @@ impl<Kind> Room<Kind> {
         };
 
         self.messaging_service
-            .retract_message(&self.data.room_id, &remote_id)
+            .retract_message(&self.data.room_id, &remote_id, reason)
             .await
     }
 
@@ -334,56 +654,341 @@ impl<Kind> Room<Kind> {
             .await)
     }
 
+    /// Searches the cached messages of this room for messages containing all words of `query`.
+    /// Corrected messages are matched against their latest body. Sorted newest-first.
+    pub async fn search_messages(&self, query: &str) -> Result<Vec<MessageDTO>> {
+        let account = self.ctx.connected_account()?;
+        let matches = self
+            .message_repo
+            .search(
+                &account,
+                Some(&self.data.room_id),
+                query,
+                self.ctx.config.message_page_size as usize,
+            )
+            .await?;
+
+        // Corrections match on behalf of the message they correct…
+        let mut message_ids = IndexSet::new();
+        for message in matches {
+            let message_id = match message.payload.target_id() {
+                None => Some(message.id),
+                Some(MessageTargetId::ServerId(server_id)) => self
+                    .message_repo
+                    .resolve_server_id(&account, &self.data.room_id, server_id)
+                    .await?
+                    .map(|triple| triple.id),
+                Some(MessageTargetId::RemoteId(remote_id)) => self
+                    .message_repo
+                    .resolve_remote_id(&account, &self.data.room_id, remote_id)
+                    .await?
+                    .map(|triple| triple.id),
+            };
+            message_ids.extend(message_id);
+        }
+
+        let message_ids = message_ids.into_iter().collect::<Vec<_>>();
+        let parts = self
+            .message_repo
+            .get_all(&account, &self.data.room_id, &message_ids)
+            .await?;
+
+        // …but only if the latest version of the message still matches.
+        let words = query
+            .split_whitespace()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>();
+        let mut messages = self
+            .reduce_messages_and_add_sender(&account, parts)
+            .await
+            .into_iter()
+            .filter(|message| {
+                let body = message.body.raw.to_lowercase();
+                words.iter().all(|word| body.contains(word))
+            })
+            .collect::<Vec<_>>();
+        messages.sort_by(|lhs, rhs| rhs.timestamp.cmp(&lhs.timestamp));
+
+        Ok(messages)
+    }
+
+    /// Exports the cached history of this room, optionally limited to `range`. Messages that
+    /// could not be decrypted are exported as redacted placeholders without a body.
+    pub async fn export_history(
+        &self,
+        format: ExportFormat,
+        range: Option<DateRange>,
+    ) -> Result<Vec<u8>> {
+        let mut output = vec![];
+        self.export_history_to(format, range, |chunk| {
+            output.extend_from_slice(chunk);
+            Ok(())
+        })
+        .await?;
+        Ok(output)
+    }
+
+    /// Like `export_history` but hands the export to `write` in chunks of
+    /// `history_export_page_size` messages so that large histories don't need to be held in
+    /// memory at once.
+    pub async fn export_history_to(
+        &self,
+        format: ExportFormat,
+        range: Option<DateRange>,
+        mut write: impl FnMut(&[u8]) -> Result<()>,
+    ) -> Result<()> {
+        let account = self.ctx.account()?;
+        let page_size = self.ctx.config.history_export_page_size.max(1);
+        let (mut start, end) = match range {
+            Some(range) => (range.start, range.end),
+            None => (DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC),
+        };
+
+        if format == ExportFormat::Json {
+            let header = format!(
+                r#"{{"version":{},"room_id":{},"room_name":{},"messages":["#,
+                ExportedHistory::VERSION,
+                serde_json::to_string(&self.data.room_id.to_string())?,
+                serde_json::to_string(&self.name())?
+            );
+            write(header.as_bytes())?;
+        }
+
+        let mut limit = page_size;
+        let mut is_first_message = true;
+
+        loop {
+            let mut page = self
+                .message_repo
+                .get_messages_in_range(&account, &self.data.room_id, start, end, limit)
+                .await?;
+            let is_last_page = page.len() < limit as usize;
+
+            // Messages sharing a timestamp could be split across two pages. We therefore hold
+            // back the messages with the last timestamp and start the next page with them.
+            if !is_last_page {
+                let last_timestamp = page.last().map(|message| message.timestamp);
+                page.retain(|message| Some(message.timestamp) != last_timestamp);
+
+                if page.is_empty() {
+                    // All messages of the page share the same timestamp, so load a larger page.
+                    limit = limit.saturating_mul(2);
+                    continue;
+                }
+
+                start = last_timestamp.expect("Page must not be empty");
+                limit = page_size;
+            }
+
+            let message_ids = page
+                .into_iter()
+                .filter(|message| message.payload.target_id().is_none())
+                .map(|message| message.id)
+                .collect::<Vec<_>>();
+            let parts = self
+                .message_repo
+                .get_all(&account, &self.data.room_id, &message_ids)
+                .await?;
+            let messages = self.reduce_messages_and_add_sender(&account, parts).await;
+
+            let mut chunk = String::new();
+            for message in messages {
+                let message = ExportedMessage::from(message);
+                match format {
+                    ExportFormat::Json => {
+                        if !is_first_message {
+                            chunk.push(',');
+                        }
+                        chunk.push_str(&serde_json::to_string(&message)?);
+                    }
+                    ExportFormat::PlainText => chunk.push_str(&message.to_plain_text()),
+                }
+                is_first_message = false;
+            }
+
+            if !chunk.is_empty() {
+                write(chunk.as_bytes())?;
+            }
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        if format == ExportFormat::Json {
+            write(b"]}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Imports messages from a JSON-lines dump with one `ImportedMessage` per line into the
+    /// local cache, e.g. when migrating from another client. Messages whose stanza ID (or, if
+    /// they don't have one, whose ID) is cached already are skipped, lines that can't be parsed
+    /// are counted as failed.
+    ///
+    /// Messages are written in batches of `history_import_batch_size`, after each of which
+    /// `progress` is called. Instead of an event per batch, a single `MessagesNeedReload` event
+    /// is dispatched once the import is finished or failed after messages were imported.
+    pub async fn import_history(
+        &self,
+        reader: impl Read,
+        mut progress: impl FnMut(&HistoryImportSummary),
+    ) -> Result<HistoryImportSummary> {
+        let account = self.ctx.account()?;
+        let mut summary = HistoryImportSummary::default();
+
+        let result = self
+            .import_history_batches(&account, reader, &mut summary, &mut progress)
+            .await;
+
+        // The batches imported before a failure are in the cache already…
+        if summary.imported > 0 {
+            self.client_event_dispatcher
+                .dispatch_room_event(self.data.clone(), ClientRoomEventType::MessagesNeedReload);
+        }
+
+        result.map(|_| summary)
+    }
+
     pub async fn set_user_is_composing(&self, is_composing: bool) -> Result<()> {
+        let compose_state = if is_composing {
+            ComposeState::Composing
+        } else {
+            ComposeState::Idle
+        };
+
+        // Chat states are broadcast to every occupant in a MUC room, so we don't want to repeat
+        // them with every keystroke…
+        if !self.data.should_send_compose_state(
+            compose_state,
+            self.time_provider.now(),
+            Duration::seconds(self.ctx.config.composing_refresh_interval_secs),
+        ) {
+            return Ok(());
+        }
+
         self.messaging_service
             .set_user_is_composing(&self.data.room_id, is_composing)
-            .await
+            .await?;
+
+        if is_composing {
+            self.schedule_compose_state_timeout();
+        }
+
+        Ok(())
+    }
+
+    /// Sends a 'paused' chat state on behalf of our user if they stop typing without telling
+    /// us. The timeout is measured with the `TimeProvider`, so that it can be fast-forwarded.
+    fn schedule_compose_state_timeout(&self) {
+        let Some(sent_at) = self.data.composing_sent_at() else {
+            return;
+        };
+
+        let room = self.data.clone();
+        let messaging_service = self.messaging_service.clone();
+        let time_provider = self.time_provider.clone();
+        let timeout = Duration::seconds(self.ctx.config.composing_timeout_secs);
+
+        spawn(async move {
+            loop {
+                sleep(COMPOSE_STATE_TIMER_INTERVAL).await;
+
+                // A newer 'composing' state comes with its own timer…
+                if room.composing_sent_at() != Some(sent_at) {
+                    return;
+                }
+
+                if !room.should_pause_composing(time_provider.now(), timeout) {
+                    continue;
+                }
+
+                if let Err(err) = messaging_service
+                    .set_user_is_composing(&room.room_id, false)
+                    .await
+                {
+                    warn!(
+                        "Failed to send paused chat state to {}. {}",
+                        room.room_id,
+                        err.to_string()
+                    );
+                }
+                return;
+            }
+        });
     }
 
     pub async fn load_composing_users(&self) -> Result<Vec<ParticipantBasicInfo>> {
-        // If the chat state is 'composing' but older than 30 seconds we do not consider
-        // the user as currently typing.
-        let thirty_secs_ago = self.time_provider.now() - Duration::seconds(30);
+        // If the chat state is 'composing' but older than the configured timeout we do not
+        // consider the user as currently typing.
+        let started_after =
+            self.time_provider.now() - Duration::seconds(self.ctx.config.composing_timeout_secs);
         Ok(self
             .data
-            .with_participants(|p| p.composing_users(thirty_secs_ago)))
+            .with_participants(|p| p.composing_users(started_after)))
     }
 
-    pub async fn save_draft(&self, text: Option<&str>) -> Result<()> {
+    pub async fn save_draft(&self, draft: Option<Draft>) -> Result<()> {
         self.drafts_repo
-            .set(&self.ctx.connected_account()?, &self.data.room_id, text)
+            .set(
+                &self.ctx.connected_account()?,
+                &self.data.room_id,
+                draft.as_ref(),
+            )
             .await?;
         self.client_event_dispatcher
             .dispatch_event(ClientEvent::SidebarChanged);
         Ok(())
     }
 
-    pub async fn load_draft(&self) -> Result<Option<String>> {
+    pub async fn load_draft(&self) -> Result<Option<Draft>> {
         self.drafts_repo
             .get(&self.ctx.connected_account()?, &self.data.room_id)
             .await
     }
 
+    /// Deletes all locally cached messages of this room. The server's archive and the room's
+    /// synced settings are left untouched, so that the next call to `load_latest_messages`
+    /// fetches the messages from the server again.
+    pub async fn clear_local_history(&self) -> Result<()> {
+        let account = self.ctx.account()?;
+        self.message_repo
+            .clear_room(&account, &self.data.room_id)
+            .await?;
+
+        self.data.set_needs_update_statistics();
+
+        self.client_event_dispatcher
+            .dispatch_room_event(self.data.clone(), ClientRoomEventType::MessagesNeedReload);
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::SidebarChanged);
+
+        Ok(())
+    }
+
+    /// Loads the latest messages from the server or, if the client is not connected, from the
+    /// local cache (see `Client::load_cached_state`).
     pub async fn load_latest_messages(&self) -> Result<MessageResultSet> {
+        if self.ctx.connected_account().is_err() {
+            return self.load_cached_latest_messages().await;
+        }
+
         debug!("Loading latest messages from server…");
-        let messages = self.load_messages(None).await?;
+        let messages = self.load_messages(MessageRange::Before(None)).await?;
         Ok(messages)
     }
 
-    pub async fn load_messages_before(&self, stanza_id: &MessageId) -> Result<MessageResultSet> {
-        let account = self.ctx.connected_account()?;
-
-        let Some(server_id) = self
-            .message_repo
-            .resolve_message_id(&account, &self.data.room_id, stanza_id)
-            .await?
-            .and_then(|t| t.server_id)
-        else {
-            bail!("Failed to resolve message id '{stanza_id}' to a server id")
-        };
+    pub async fn load_messages_before(&self, cursor: &MessageCursor) -> Result<MessageResultSet> {
+        debug!("Loading messages before '{cursor}' from server…");
+        self.load_messages(MessageRange::Before(Some(cursor.server_id())))
+            .await
+    }
 
-        debug!("Loading latest messages before '{stanza_id}' from server…");
-        self.load_messages(Some(&server_id)).await
+    pub async fn load_messages_after(&self, cursor: &MessageCursor) -> Result<MessageResultSet> {
+        debug!("Loading messages after '{cursor}' from server…");
+        self.load_messages(MessageRange::After(cursor.server_id()))
+            .await
     }
 
     pub async fn load_unread_messages(&self) -> Result<MessageResultSet> {
@@ -391,282 +996,1278 @@ impl<Kind> Room<Kind> {
             return self.load_latest_messages().await;
         };
 
-        let account = self.ctx.connected_account()?;
-
+        // Unread messages are read from the cache, which we can serve before being connected.
+        let source = if self.ctx.connected_account().is_ok() {
+            DataSource::Live
+        } else {
+            DataSource::Cached
+        };
+        let account = self.ctx.account()?;
+
         let messages = self
             .message_repo
             .get_messages_after(&account, &self.data.room_id, last_read_message.timestamp)
             .await?;
 
-        Ok(MessageResultSet {
-            messages: self
-                .reduce_messages_and_add_sender(&account, messages)
-                .await,
-            last_message_id: None,
-        })
-    }
+        // Older messages can be loaded from the oldest unread message on…
+        let oldest_server_id = messages
+            .iter()
+            .find_map(|m| m.server_id.clone())
+            .unwrap_or(last_read_message.stanza_id);
+
+        Ok(MessageResultSet {
+            messages: self.reduce_timeline_messages(&account, messages).await,
+            before: Some(oldest_server_id.into()),
+            after: None,
+            is_complete: true,
+            source,
+        })
+    }
+
+    pub async fn set_last_read_message(&self, id: &MessageId) -> Result<()> {
+        self.move_read_marker(id, false).await
+    }
+
+    /// Moves the read marker to the message with `id`, which may also be older than the current
+    /// one, and updates the unread count accordingly. Fails if the message is newer than the
+    /// latest received message.
+    pub async fn mark_as_read_until(&self, id: &MessageId) -> Result<()> {
+        self.move_read_marker(id, true).await
+    }
+
+    /// Marks all messages in the room as read and sends a read receipt for the last received
+    /// message if applicable.
+    pub async fn mark_as_read(&self) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        let Some(message_ref) = self
+            .message_repo
+            .get_last_received_message(&account, &self.data.room_id, None)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let former_message_ref = self.data.settings().last_read_message;
+        self.set_last_read_message_ref(&account, Some(message_ref), false)
+            .await?;
+        // Everything up to the last received message is read now, no need to count again.
+        self.data.reset_statistics();
+        self.send_read_receipt_if_needed(&account, former_message_ref)
+            .await
+    }
+
+    /// Marks the messages up to and including the message with `id` as read and sends a read
+    /// receipt for it if applicable.
+    pub async fn mark_message_as_read(&self, id: MessageId) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        let former_message_ref = self.data.settings().last_read_message;
+        self.set_last_read_message(&id).await?;
+        self.send_read_receipt_if_needed(&account, former_message_ref)
+            .await
+    }
+
+    async fn move_read_marker(&self, id: &MessageId, ensure_not_past_latest: bool) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        let mut messages = self
+            .message_repo
+            .get(&account, &self.data.room_id, id)
+            .await?;
+
+        if messages.is_empty() {
+            return Err(anyhow!("No message exists with id {id}."));
+        }
+
+        let message = messages.swap_remove(0);
+
+        if ensure_not_past_latest {
+            let latest_message_ref = self
+                .message_repo
+                .get_last_received_message(&account, &self.data.room_id, None)
+                .await?;
+
+            ensure!(
+                latest_message_ref
+                    .map(|message_ref| message.timestamp <= message_ref.timestamp)
+                    .unwrap_or_default(),
+                "Cannot move the read marker past the latest received message."
+            );
+        }
+
+        if let Some(stanza_id) = message.server_id {
+            return self
+                .set_last_read_message_ref(
+                    &account,
+                    Some(ArchivedMessageRef {
+                        stanza_id,
+                        timestamp: message.timestamp,
+                    }),
+                    true,
+                )
+                .await;
+        }
+
+        self.set_last_read_message_ref(
+            &account,
+            self.message_repo
+                .get_last_received_message(&account, &self.data.room_id, Some(message.timestamp))
+                .await?,
+            true,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn encryption_enabled(&self) -> bool {
+        self.data.settings().encryption_enabled
+    }
+
+    pub async fn set_encryption_enabled(&self, enabled: bool) {
+        self.update_synced_settings(|settings| settings.encryption_enabled = enabled)
+            .await
+    }
+
+    pub fn notification_mode(&self) -> RoomNotificationMode {
+        self.data.settings().notifications
+    }
+
+    /// Sets which messages of this room are counted as unread. The setting is synced across our
+    /// devices.
+    pub async fn set_notification_mode(&self, mode: RoomNotificationMode) {
+        if self.notification_mode() == mode {
+            return;
+        }
+
+        self.update_synced_settings(|settings| settings.notifications = mode)
+            .await;
+        self.data.set_needs_update_statistics();
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::SidebarChanged);
+    }
+
+    pub fn send_read_receipts(&self) -> bool {
+        self.data.settings().send_read_receipts
+    }
+
+    /// Sets whether read markers are sent to this room. The setting is synced across our devices.
+    pub async fn set_send_read_receipts(&self, enabled: bool) {
+        self.update_synced_settings(|settings| settings.send_read_receipts = enabled)
+            .await
+    }
+
+    /// Pins the message with `id` to this room. The pinned messages are synced across our devices.
+    /// Fails if the message doesn't exist in this room.
+    pub async fn pin_message(&self, id: MessageId) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        let messages = self
+            .message_repo
+            .get(&account, &self.data.room_id, &id)
+            .await?;
+        let Some(message) = Message::reducing_messages(messages).into_iter().next() else {
+            bail!("Could not find message to pin.")
+        };
+
+        ensure!(
+            message.remote_id.is_some() || message.server_id.is_some(),
+            "Message '{id}' cannot be pinned since it has neither a remote nor a server id."
+        );
+
+        let pinned_message = PinnedMessageRef {
+            remote_id: message.remote_id,
+            stanza_id: message.server_id,
+            timestamp: message.timestamp,
+            pinned_by: account.to_user_id(),
+        };
+
+        let mut did_pin = false;
+        self.update_synced_settings(|settings| {
+            if settings.pinned_messages.iter().any(|pinned| {
+                pinned.references(
+                    pinned_message.remote_id.as_ref(),
+                    pinned_message.stanza_id.as_ref(),
+                )
+            }) {
+                return;
+            }
+            settings.pinned_messages.push(pinned_message);
+            did_pin = true;
+        })
+        .await;
+
+        if did_pin {
+            self.client_event_dispatcher.dispatch_room_event(
+                self.data.clone(),
+                ClientRoomEventType::PinnedMessagesChanged,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Unpins the message with `id`. Does nothing if the message isn't pinned.
+    pub async fn unpin_message(&self, id: MessageId) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        let Some(ids) = self
+            .message_repo
+            .resolve_message_id(&account, &self.data.room_id, &id)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let mut did_unpin = false;
+        self.update_synced_settings(|settings| {
+            let num_pinned_messages = settings.pinned_messages.len();
+            settings.pinned_messages.retain(|pinned| {
+                !pinned.references(ids.remote_id.as_ref(), ids.server_id.as_ref())
+            });
+            did_unpin = settings.pinned_messages.len() != num_pinned_messages;
+        })
+        .await;
+
+        if did_unpin {
+            self.client_event_dispatcher.dispatch_room_event(
+                self.data.clone(),
+                ClientRoomEventType::PinnedMessagesChanged,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Loads the pinned messages of this room. Messages that are not cached are loaded from the
+    /// server's archive.
+    pub async fn pinned_messages(&self) -> Result<Vec<MessageDTO>> {
+        let account = self.ctx.connected_account()?;
+        let pinned_messages = self.data.settings().pinned_messages;
+
+        let mut message_ids = vec![];
+        let mut missing_stanza_ids = vec![];
+
+        for pinned_message in &pinned_messages {
+            match self
+                .resolve_pinned_message_ref(&account, pinned_message)
+                .await?
+            {
+                Some(id) => message_ids.push(id),
+                None => missing_stanza_ids.extend(pinned_message.stanza_id.clone()),
+            }
+        }
+
+        if !missing_stanza_ids.is_empty() {
+            if let Err(err) = self
+                .load_archived_messages_with_ids(&account, &missing_stanza_ids)
+                .await
+            {
+                warn!(
+                    "Failed to load pinned messages from MAM. {}",
+                    err.to_string()
+                );
+            }
+
+            message_ids.clear();
+            for pinned_message in &pinned_messages {
+                message_ids.extend(
+                    self.resolve_pinned_message_ref(&account, pinned_message)
+                        .await?,
+                );
+            }
+        }
+
+        let messages = self
+            .message_repo
+            .get_all(&account, &self.data.room_id, &message_ids)
+            .await?;
+        Ok(self
+            .reduce_messages_and_add_sender(&account, messages)
+            .await)
+    }
+
+    /// Tries again to decrypt the message with `id` after decrypting it failed, e.g. after the
+    /// session with the sender's device was repaired. On success the placeholder is replaced
+    /// with the decrypted message.
+    pub async fn retry_decryption(&self, id: &MessageId) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        let Some(mut message) = self
+            .message_repo
+            .get(&account, &self.data.room_id, id)
+            .await?
+            .into_iter()
+            .find(|message| &message.id == id)
+        else {
+            bail!("Unknown message '{id}'.")
+        };
+
+        let sender_id = match &message.from {
+            ParticipantId::User(user_id) => user_id.clone(),
+            ParticipantId::Occupant(_) => self
+                .participant(&message.from)
+                .and_then(|participant| participant.user_id)
+                .ok_or_else(|| anyhow!("The real id of the sender of '{id}' is unknown."))?,
+        };
+
+        let MessageLikePayload::Message {
+            body,
+            encryption_info: Some(encryption_info),
+            ..
+        } = &mut message.payload
+        else {
+            bail!("Message '{id}' is not encrypted.")
+        };
+
+        let Some(payload) = encryption_info.undecrypted_payload.clone() else {
+            bail!("Message '{id}' does not need to be decrypted.")
+        };
+
+        let decrypted_message = self
+            .encryption_domain_service
+            .decrypt_message(&sender_id, &self.data.room_id, Some(id), payload, None)
+            .await?;
+
+        let message_body = StyledMessage::new(decrypted_message.message);
+        body.raw = message_body.to_string();
+        body.html = message_body.into_html();
+        *encryption_info = MessageLikeEncryptionInfo {
+            sender: decrypted_message.sender_device_id,
+            trust: decrypted_message.trust,
+            decryption_failed: false,
+            undecrypted_payload: None,
+        };
+
+        self.message_repo
+            .append(&account, &self.data.room_id, &[message])
+            .await?;
+
+        self.client_event_dispatcher.dispatch_room_event(
+            self.data.clone(),
+            ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![id.clone()],
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Resends the message with `id` after sending it failed. If we're offline the message is
+    /// queued and sent once we're back online.
+    pub async fn resend_failed_message(&self, id: MessageId) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        let Some(mut message) = self
+            .pending_messages_repo
+            .get(&account, &self.data.room_id, &id)
+            .await?
+        else {
+            bail!("Message '{id}' is not in the outbox.")
+        };
+
+        message.state = PendingMessageState::Pending;
+        self.pending_messages_repo.put(&account, &message).await?;
+
+        self.client_event_dispatcher.dispatch_room_event(
+            self.data.clone(),
+            ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![id],
+            },
+        );
+
+        if self.ctx.connection_state() != ConnectionState::Connected {
+            return Ok(());
+        }
+
+        self.send_pending_message(message).await
+    }
+
+    /// Removes the message with `id` from the outbox and deletes it. Does nothing if the message
+    /// isn't in the outbox, i.e. if it was sent already.
+    pub async fn discard_failed_message(&self, id: MessageId) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        if self
+            .pending_messages_repo
+            .get(&account, &self.data.room_id, &id)
+            .await?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        self.pending_messages_repo
+            .delete(&account, &self.data.room_id, &id)
+            .await?;
+        self.message_repo
+            .delete(&account, &self.data.room_id, &id)
+            .await?;
+
+        self.client_event_dispatcher.dispatch_room_event(
+            self.data.clone(),
+            ClientRoomEventType::MessagesDeleted {
+                message_ids: vec![id],
+            },
+        );
+
+        Ok(())
+    }
+}
+
+impl<Kind> Room<Kind> {
+    /// Sends a message from the outbox. A message that failed permanently is marked as failed and
+    /// doesn't return an error, so that it doesn't hold up the messages queued after it.
+    pub(crate) async fn send_pending_message(&self, message: PendingMessage) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+        let message_id = message.id.clone();
+
+        let (_, body, attachments) = self
+            .build_message_content(message.body, message.attachments, false)
+            .await?;
+
+        let result = self
+            .send_outbox_message(
+                &account,
+                SendMessageRequest {
+                    id: message_id.clone(),
+                    body,
+                    attachments,
+                    reply_to: message.reply_to,
+                },
+            )
+            .await;
+
+        if let Err(err) = result {
+            if !is_permanent_send_error(&err) {
+                return Err(err);
+            }
+        }
+
+        self.client_event_dispatcher.dispatch_room_event(
+            self.data.clone(),
+            ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![message_id],
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Marks a message in the outbox as failed without trying to send it, e.g. because its room
+    /// doesn't accept messages anymore.
+    pub(crate) async fn fail_pending_message(
+        &self,
+        mut message: PendingMessage,
+        error: impl Into<String>,
+    ) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+        let message_id = message.id.clone();
+
+        message.state = PendingMessageState::Failed {
+            error: error.into(),
+        };
+        self.pending_messages_repo.put(&account, &message).await?;
+
+        self.client_event_dispatcher.dispatch_room_event(
+            self.data.clone(),
+            ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![message_id],
+            },
+        );
+
+        Ok(())
+    }
+}
+
+pub enum ProcessMessageAction {
+    Send,
+    SendPrivate {
+        occupant_id: OccupantId,
+    },
+    ReplyInThread {
+        thread_id: ThreadId,
+    },
+    Update {
+        target_message_id: MessageId,
+        target_remote_id: MessageRemoteId,
+    },
+}
+
+impl<Kind> Room<Kind> {
+    async fn process_send_message_request(
+        &self,
+        account: &AccountId,
+        request: SendMessageRequestDTO,
+        action: ProcessMessageAction,
+    ) -> Result<()> {
+        let reply_to = match &request.reply_to {
+            Some(id) => Some(self.build_reply_to(account, id).await?),
+            None => None,
+        };
+
+        // Keep the original text and attachments so that we can rebuild the message if it needs
+        // to be resent…
+        let text = request.body.map(|body| body.text);
+        let attachments = request.attachments.clone();
+
+        // Process message body and attachments…
+        let is_private_message = matches!(action, ProcessMessageAction::SendPrivate { .. });
+        let (message_body, request_body, request_attachments) = self
+            .build_message_content(
+                text.clone(),
+                request.attachments.clone(),
+                is_private_message,
+            )
+            .await?;
+
+        let message_request = SendMessageRequest {
+            id: self.message_id_provider.new_id(),
+            body: request_body,
+            attachments: request_attachments,
+            reply_to: reply_to.clone(),
+        };
+
+        // Build appropriate payload…
+        let payload = match &action {
+            ProcessMessageAction::Send => MessageLikePayload::Message {
+                body: message_body,
+                attachments: request.attachments,
+                encryption_info: None,
+                is_transient: false,
+                reply_to,
+                thread_id: None,
+                is_markable: true,
+            },
+            ProcessMessageAction::SendPrivate { .. } => MessageLikePayload::Message {
+                body: message_body,
+                attachments: request.attachments,
+                encryption_info: None,
+                is_transient: true,
+                reply_to,
+                thread_id: None,
+                is_markable: true,
+            },
+            ProcessMessageAction::ReplyInThread { thread_id } => MessageLikePayload::Message {
+                body: message_body,
+                attachments: request.attachments,
+                encryption_info: None,
+                is_transient: false,
+                reply_to,
+                thread_id: Some(thread_id.clone()),
+                is_markable: true,
+            },
+            ProcessMessageAction::Update {
+                target_remote_id, ..
+            } => MessageLikePayload::Correction {
+                target_id: target_remote_id.clone().into(),
+                body: message_body,
+                attachments: request.attachments,
+                encryption_info: None,
+            },
+        };
+
+        // Save the unencrypted message so that we can look it up later…
+        let message_id = message_request.id.clone();
+        let timestamp = self.time_provider.now();
+        self.message_repo
+            .append(
+                &account,
+                &self.data.room_id,
+                &[MessageLike {
+                    id: message_id.clone(),
+                    remote_id: Some(message_id.to_string().into()),
+                    server_id: None,
+                    to: None,
+                    from: account.to_user_id().into(),
+                    timestamp,
+                    payload,
+                }],
+            )
+            .await?;
+
+        // Pass message to MessagingService and build a ClientRoomEvent…
+        let event = match action {
+            ProcessMessageAction::Send => {
+                // Put the message into the outbox first, so that it'll be resent if we're
+                // offline or the connection drops before the server received it…
+                self.pending_messages_repo
+                    .put(
+                        account,
+                        &PendingMessage {
+                            id: message_id.clone(),
+                            room_id: self.data.room_id.clone(),
+                            body: text,
+                            attachments,
+                            reply_to: message_request.reply_to.clone(),
+                            timestamp,
+                            state: PendingMessageState::Pending,
+                        },
+                    )
+                    .await?;
+
+                if self.ctx.connection_state() == ConnectionState::Connected {
+                    if let Err(err) = self.send_outbox_message(account, message_request).await {
+                        if is_permanent_send_error(&err) {
+                            // The message is in our cache already and is flagged as failed, so
+                            // that it can be resent via `resend_failed_message`…
+                            self.client_event_dispatcher.dispatch_room_event(
+                                self.data.clone(),
+                                ClientRoomEventType::MessagesAppended {
+                                    message_ids: vec![message_id],
+                                },
+                            );
+                            return Err(err);
+                        }
+                        warn!(
+                            "Failed to send message {message_id}. It will be resent once we're back online. {}",
+                            err.to_string()
+                        );
+                    }
+                }
+
+                ClientRoomEventType::MessagesAppended {
+                    message_ids: vec![message_id],
+                }
+            }
+            ProcessMessageAction::SendPrivate { occupant_id } => {
+                self.messaging_service
+                    .send_private_message(&occupant_id, message_request)
+                    .await?;
+                ClientRoomEventType::PrivateMessagesAppended {
+                    occupant_id,
+                    message_ids: vec![message_id],
+                }
+            }
+            ProcessMessageAction::ReplyInThread { thread_id } => {
+                self.messaging_service
+                    .send_message_to_thread(&self.data.room_id, &thread_id, message_request)
+                    .await?;
+                // TODO: Add parent message to this event?
+                ClientRoomEventType::MessagesAppended {
+                    message_ids: vec![message_id],
+                }
+            }
+            ProcessMessageAction::Update {
+                target_message_id,
+                target_remote_id,
+            } => {
+                self.messaging_service
+                    .update_message(&self.data.room_id, &target_remote_id, message_request)
+                    .await?;
+                ClientRoomEventType::MessagesUpdated {
+                    message_ids: vec![target_message_id],
+                }
+            }
+        };
+
+        // Dispatch event to notify UI about changes…
+        self.client_event_dispatcher
+            .dispatch_room_event(self.data.clone(), event);
+
+        Ok(())
+    }
+
+    async fn import_history_batches(
+        &self,
+        account: &AccountId,
+        reader: impl Read,
+        summary: &mut HistoryImportSummary,
+        progress: &mut impl FnMut(&HistoryImportSummary),
+    ) -> Result<()> {
+        let batch_size = self.ctx.config.history_import_batch_size.max(1) as usize;
+
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut imported_ids = HashSet::new();
+
+        for line in BufReader::new(reader).split(b'\n') {
+            let message = match String::from_utf8(line?)
+                .map_err(anyhow::Error::from)
+                .and_then(|line| {
+                    if line.trim().is_empty() {
+                        return Ok(None);
+                    }
+                    Ok(Some(serde_json::from_str::<ImportedMessage>(&line)?))
+                }) {
+                Ok(Some(message)) => message,
+                Ok(None) => continue,
+                Err(err) => {
+                    warn!("Failed to import message. {}", err.to_string());
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+
+            // Skip messages that occur more than once in the dump…
+            let imported_id = match (&message.stanza_id, &message.id) {
+                (Some(stanza_id), _) => Some(MessageTargetId::ServerId(stanza_id.as_str().into())),
+                (None, Some(id)) => Some(MessageTargetId::RemoteId(id.as_str().into())),
+                (None, None) => None,
+            };
+            if let Some(imported_id) = imported_id {
+                if !imported_ids.insert(imported_id) {
+                    summary.skipped += 1;
+                    continue;
+                }
+            }
+
+            match self.parse_imported_message(message) {
+                Ok(message) => batch.push(message),
+                Err(err) => {
+                    warn!("Failed to import message. {}", err.to_string());
+                    summary.failed += 1;
+                    continue;
+                }
+            }
+
+            if batch.len() >= batch_size {
+                self.append_imported_messages(account, &mut batch, summary)
+                    .await?;
+                progress(summary);
+            }
+        }
+
+        if !batch.is_empty() {
+            self.append_imported_messages(account, &mut batch, summary)
+                .await?;
+            progress(summary);
+        }
+
+        Ok(())
+    }
+
+    /// Appends the messages of `batch` that aren't cached yet and empties it.
+    async fn append_imported_messages(
+        &self,
+        account: &AccountId,
+        batch: &mut Vec<MessageLike>,
+        summary: &mut HistoryImportSummary,
+    ) -> Result<()> {
+        let cached_ids = self
+            .message_repo
+            .find_cached(account, &self.data.room_id, batch)
+            .await?;
+
+        let messages = batch
+            .drain(..)
+            .filter(|message| !cached_ids.contains(&message.id))
+            .collect::<Vec<_>>();
+
+        summary.skipped += cached_ids.len() as u32;
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        self.message_repo
+            .append(account, &self.data.room_id, &messages)
+            .await?;
+        summary.imported += messages.len() as u32;
+
+        Ok(())
+    }
+
+    fn parse_imported_message(&self, message: ImportedMessage) -> Result<MessageLike> {
+        let from = message.from.parse::<Jid>()?;
+
+        // Senders with the room's JID are occupants, all others are identified by their real JID…
+        let from = match self.data.room_id.muc_id() {
+            Some(muc_id) if from.to_bare() == **muc_id => {
+                ParticipantId::Occupant(OccupantId::from(from.try_into_full()?))
+            }
+            _ => ParticipantId::User(from.into_bare().into()),
+        };
+
+        let id = self.message_id_provider.new_id();
+        let body = Markdown::new(message.body);
+
+        Ok(MessageLike {
+            id: id.clone(),
+            remote_id: Some(
+                message
+                    .id
+                    .map(MessageRemoteId::from)
+                    .unwrap_or_else(|| id.to_string().into()),
+            ),
+            server_id: message.stanza_id.map(MessageServerId::from),
+            to: None,
+            from,
+            timestamp: message.timestamp,
+            payload: MessageLikePayload::Message {
+                body: MessageLikeBody {
+                    html: body.to_html(),
+                    raw: body.into_string(),
+                    mentions: vec![],
+                },
+                attachments: message
+                    .attachments
+                    .into_iter()
+                    .map(Attachment::try_from)
+                    .collect::<Result<_>>()?,
+                encryption_info: None,
+                is_transient: false,
+                reply_to: None,
+                thread_id: None,
+                // Imported messages are history, so they shouldn't be marked as read again…
+                is_markable: false,
+            },
+        })
+    }
+
+    /// Returns the body that is saved alongside the message, the (possibly encrypted) body that
+    /// is sent and the attachments that are sent alongside it. Files that were encrypted before
+    /// their upload (XEP-0454) carry their key in their URL and are thus only sent as part of an
+    /// encrypted body.
+    async fn build_message_content(
+        &self,
+        text: Option<Markdown>,
+        attachments: Vec<Attachment>,
+        is_private_message: bool,
+    ) -> Result<(
+        MessageLikeBody,
+        Option<send_message_request::Body>,
+        Vec<Attachment>,
+    )> {
+        let encrypted_attachment_urls = attachments
+            .iter()
+            .flat_map(Attachment::encrypted_urls)
+            .collect::<Vec<_>>();
+
+        if text.is_none() && encrypted_attachment_urls.is_empty() {
+            return Ok((MessageLikeBody::default(), None, attachments));
+        }
+
+        let has_text = text.is_some();
+        let (body, request_body) = self
+            .build_message_body(
+                text.unwrap_or_else(|| Markdown::new("")),
+                &encrypted_attachment_urls,
+                is_private_message,
+            )
+            .await?;
+
+        match request_body.payload {
+            send_message_request::Payload::Encrypted(_) => {
+                // The keys of encrypted files are only contained in the encrypted body. Their
+                // metadata and thumbnails are sent along with the download URLs of the encrypted
+                // uploads, so that receivers can match them with the URLs from the body.
+                let attachments = attachments
+                    .into_iter()
+                    .map(Attachment::without_keys)
+                    .collect();
+                Ok((body, Some(request_body), attachments))
+            }
+            send_message_request::Payload::Unencrypted { .. }
+                if !encrypted_attachment_urls.is_empty() =>
+            {
+                bail!("Encrypted attachments can only be sent in encrypted messages.")
+            }
+            send_message_request::Payload::Unencrypted { .. } if !has_text => {
+                Ok((MessageLikeBody::default(), None, attachments))
+            }
+            send_message_request::Payload::Unencrypted { .. } => {
+                Ok((body, Some(request_body), attachments))
+            }
+        }
+    }
+
+    /// Parses the Markdown `text` and returns the body that is saved alongside the message as
+    /// well as the (possibly encrypted) body that is sent. If the body is encrypted,
+    /// `encrypted_attachment_urls` are appended to it.
+    async fn build_message_body(
+        &self,
+        text: Markdown,
+        encrypted_attachment_urls: &[Url],
+        is_private_message: bool,
+    ) -> Result<(MessageLikeBody, send_message_request::Body)> {
+        // Parse markdown…
+        let parser = MarkdownParser::new(text.as_ref());
+        let html = HTML::new(parser.convert_to_html());
+        let fallback = StyledMessage::new(parser.convert_to_message_styling());
+        let mentions = parser
+            .collect_mentions()
+            .into_iter()
+            .map(|jid| Mention {
+                user: jid.into(),
+                range: None,
+            })
+            .collect::<Vec<_>>();
+
+        let message_body = MessageLikeBody {
+            raw: text.to_string(),
+            html,
+            mentions: mentions.clone(),
+        };
+
+        let mut plaintext = fallback.to_string();
+        for url in encrypted_attachment_urls {
+            if !plaintext.is_empty() {
+                plaintext.push('\n');
+            }
+            plaintext.push_str(url.as_str());
+        }
+
+        // Encrypt message if needed…
+        let payload = match self
+            .encrypt_message_if_needed(
+                plaintext,
+                is_private_message,
+                self.ctx.config.omemo_trust_policy,
+            )
+            .await?
+        {
+            Some(encrypted_payload) => send_message_request::Payload::Encrypted(encrypted_payload),
+            None => send_message_request::Payload::Unencrypted {
+                message: text,
+                fallback,
+            },
+        };
+
+        Ok((
+            message_body,
+            send_message_request::Body { payload, mentions },
+        ))
+    }
+
+    /// Sends a message from the outbox. The message stays there until the server confirmed it,
+    /// i.e. acknowledged it or, in a MUC room, reflected it back to us. If the server doesn't
+    /// acknowledge messages, direct messages are removed from the outbox right away.
+    /// If sending failed permanently the message is marked as failed, otherwise it stays pending.
+    async fn send_outbox_message(
+        &self,
+        account: &AccountId,
+        request: SendMessageRequest,
+    ) -> Result<()> {
+        let message_id = request.id.clone();
+
+        let Err(err) = self
+            .messaging_service
+            .send_message(&self.data.room_id, request)
+            .await
+        else {
+            self.data.reset_compose_state();
+
+            if self.data.room_id.is_muc_room()
+                || self.messaging_service.acknowledges_sent_messages()
+            {
+                self.ctx.unconfirmed_message_ids.lock().insert(message_id);
+                return Ok(());
+            }
+
+            self.pending_messages_repo
+                .delete(account, &self.data.room_id, &message_id)
+                .await?;
+            return Ok(());
+        };
+
+        if is_permanent_send_error(&err) {
+            if let Some(mut message) = self
+                .pending_messages_repo
+                .get(account, &self.data.room_id, &message_id)
+                .await?
+            {
+                message.state = PendingMessageState::Failed {
+                    error: err.to_string(),
+                };
+                self.pending_messages_repo.put(account, &message).await?;
+            }
+        }
+
+        Err(err)
+    }
+
+    async fn resolve_pinned_message_ref(
+        &self,
+        account: &AccountId,
+        pinned_message: &PinnedMessageRef,
+    ) -> Result<Option<MessageId>> {
+        if let Some(stanza_id) = &pinned_message.stanza_id {
+            if let Some(ids) = self
+                .message_repo
+                .resolve_server_id(account, &self.data.room_id, stanza_id)
+                .await?
+            {
+                return Ok(Some(ids.id));
+            }
+        }
+
+        if let Some(remote_id) = &pinned_message.remote_id {
+            if let Some(ids) = self
+                .message_repo
+                .resolve_remote_id(account, &self.data.room_id, remote_id)
+                .await?
+            {
+                return Ok(Some(ids.id));
+            }
+        }
 
-    pub async fn set_last_read_message(&self, id: &MessageId) -> Result<()> {
-        let account = self.ctx.connected_account()?;
+        Ok(None)
+    }
 
-        let mut messages = self
-            .message_repo
-            .get(&account, &self.data.room_id, id)
+    /// Loads the messages with the given ids from MAM and saves them in the MessagesRepository.
+    async fn load_archived_messages_with_ids(
+        &self,
+        account: &AccountId,
+        ids: &[MessageServerId],
+    ) -> Result<()> {
+        let archived_messages = self
+            .message_archive_service
+            .load_messages_with_ids(&self.data.room_id, ids)
             .await?;
 
-        if messages.is_empty() {
-            return Err(anyhow!("No message exists with id {id}."));
-        }
-
-        let message = messages.swap_remove(0);
+        let mut messages = vec![];
 
-        if let Some(stanza_id) = message.server_id {
-            return self
-                .set_last_read_message_ref(
-                    &account,
-                    Some(ArchivedMessageRef {
-                        stanza_id,
-                        timestamp: message.timestamp,
-                    }),
-                    true,
-                )
-                .await;
+        for archived_message in archived_messages {
+            match MessageParser::new(
+                self.message_id_provider.new_id(),
+                Some(self.data.clone()),
+                Default::default(),
+                self.encryption_domain_service.clone(),
+                None,
+            )
+            .parse_mam_message(archived_message)
+            .await
+            {
+                Ok(message) => messages.push(message),
+                Err(err) => error!("Failed to parse MAM message. {}", err.to_string()),
+            }
         }
 
-        self.set_last_read_message_ref(
-            &account,
-            self.message_repo
-                .get_last_received_message(&account, &self.data.room_id, Some(message.timestamp))
-                .await?,
-            true,
-        )
-        .await?;
+        self.message_repo
+            .append(account, &self.data.room_id, &messages)
+            .await?;
 
         Ok(())
     }
 
-    pub async fn mark_as_read(&self) -> Result<()> {
-        let account = self.ctx.connected_account()?;
-
-        let Some(message_ref) = self
+    /// Builds the XEP-0461 reference to the message with `id`. Messages that we don't have cached
+    /// are referenced by their raw ID.
+    async fn build_reply_to(&self, account: &AccountId, id: &MessageId) -> Result<ReplyTo> {
+        let messages = self
             .message_repo
-            .get_last_received_message(&account, &self.data.room_id, None)
-            .await?
-        else {
-            return Ok(());
+            .get(account, &self.data.room_id, id)
+            .await?;
+
+        let Some(message) = Message::reducing_messages(messages).into_iter().next() else {
+            return Ok(ReplyTo {
+                id: MessageTargetId::RemoteId(id.to_string().into()),
+                to: None,
+                quote: None,
+            });
         };
 
-        self.set_last_read_message_ref(&account, Some(message_ref), false)
-            .await?;
-        Ok(())
-    }
+        // In MUCs messages are referenced by the ID assigned by the server…
+        let target_id = if self.data.room_id.is_muc_room() {
+            message.server_id.map(MessageTargetId::from)
+        } else {
+            message.remote_id.map(MessageTargetId::from)
+        }
+        .unwrap_or_else(|| MessageTargetId::RemoteId(id.to_string().into()));
 
-    pub fn encryption_enabled(&self) -> bool {
-        self.data.settings().encryption_enabled
+        Ok(ReplyTo {
+            id: target_id,
+            to: Some(message.from),
+            quote: Some(message.body.raw),
+        })
     }
 
-    pub async fn set_encryption_enabled(&self, enabled: bool) {
-        self.update_synced_settings(|settings| settings.encryption_enabled = enabled)
-            .await
-    }
-}
+    /// Encrypts `message` for all participants of the room if encryption is enabled. Returns
+    /// `None` if the message should be sent unencrypted. If no `trust_policy` is given, the
+    /// default policy is used.
+    async fn encrypt_message_if_needed(
+        &self,
+        message: String,
+        is_private_message: bool,
+        trust_policy: Option<TrustPolicy>,
+    ) -> Result<Option<EncryptedPayload>> {
+        let supports_encryption = matches!(
+            self.data.r#type,
+            RoomType::DirectMessage | RoomType::Group | RoomType::PrivateChannel
+        );
 
-pub enum ProcessMessageAction {
-    Send,
-    ReplyInThread {
-        thread_id: ThreadId,
-    },
-    Update {
-        target_message_id: MessageId,
-        target_remote_id: MessageRemoteId,
-    },
-}
+        if !supports_encryption || !self.data.settings().encryption_enabled || is_private_message {
+            return Ok(None);
+        }
 
-impl<Kind> Room<Kind> {
-    async fn process_send_message_request(
-        &self,
-        account: &AccountId,
-        request: SendMessageRequestDTO,
-        action: ProcessMessageAction,
-    ) -> Result<()> {
-        let mut message_body = MessageLikeBody::default();
-        let mut message_request = SendMessageRequest {
-            id: self.message_id_provider.new_id(),
-            body: None,
-            attachments: request.attachments.clone(),
+        let user_ids = match self.data.cached_encryption_recipients() {
+            Some(user_ids) => user_ids,
+            None => {
+                let user_ids = self.resolve_encryption_recipients().await?;
+                self.data.cache_encryption_recipients(user_ids.clone());
+                user_ids
+            }
         };
 
-        // Process message body if there is one…
-        if let Some(body) = request.body {
-            // Parse markdown…
-            let parser = MarkdownParser::new(body.text.as_ref());
-            let html = HTML::new(parser.convert_to_html());
-            let fallback = StyledMessage::new(parser.convert_to_message_styling());
-            let mentions = parser
-                .collect_mentions()
-                .into_iter()
-                .map(|jid| Mention {
-                    user: jid.into(),
-                    range: None,
-                })
-                .collect::<Vec<_>>();
-
-            message_body = MessageLikeBody {
-                raw: body.text.to_string(),
-                html,
-                mentions: mentions.clone(),
-            };
+        let result = match self
+            .encryption_domain_service
+            .encrypt_message(&user_ids, message, trust_policy.unwrap_or_default())
+            .await
+        {
+            Ok(result) => {
+                self.report_undecided_devices(&result.undecided_devices);
+                result
+            }
+            Err(EncryptionError::UntrustedDevices { devices }) => {
+                self.report_undecided_devices(&devices);
+                return Err(EncryptionError::UntrustedDevices { devices }.into());
+            }
+            Err(err) => return Err(err.into()),
+        };
 
-            // Encrypt message if needed…
-            let payload = match self.data.r#type {
-                RoomType::DirectMessage | RoomType::Group | RoomType::PrivateChannel
-                    if self.data.settings().encryption_enabled =>
-                {
-                    let user_ids = self.data.with_participants(|p| {
-                        p.iter()
-                            .filter_map(|(_, participant)| {
-                                if participant.is_self {
-                                    return None;
-                                }
-                                participant.real_id.clone()
-                            })
-                            .sorted()
-                            .collect::<Vec<_>>()
-                    });
+        if !result.recipients_without_devices.is_empty() {
+            warn!(
+                "The following participants of {} won't be able to read the message since they don't have any usable OMEMO devices: {}",
+                self.data.room_id,
+                result.recipients_without_devices.iter().join(", ")
+            );
+        }
 
-                    send_message_request::Payload::Encrypted(
-                        self.encryption_domain_service
-                            .encrypt_message(user_ids, fallback.into_string())
-                            .await?,
-                    )
-                }
-                _ => send_message_request::Payload::Unencrypted {
-                    message: body.text,
-                    fallback,
-                },
-            };
+        Ok(Some(result.payload))
+    }
 
-            message_request.body = Some(send_message_request::Body { payload, mentions });
+    /// Returns the real ids of the participants to encrypt messages for. Participants whose real
+    /// id is unknown are covered by encrypting for all members of the room instead, which works
+    /// since groups and private channels are members-only. Fails with
+    /// `EncryptionError::UnresolvedParticipants` if the member list can't be loaded, rather than
+    /// sending a message these participants can't read.
+    /// Informs the UI about undecided devices of the recipients that it hasn't been told about
+    /// yet, regardless of whether the message was encrypted for them or not.
+    fn report_undecided_devices(&self, devices: &[(UserId, DeviceId)]) {
+        if self.data.insert_undecided_devices(devices) {
+            self.client_event_dispatcher.dispatch_room_event(
+                self.data.clone(),
+                ClientRoomEventType::EncryptionRecipientsChanged,
+            );
         }
+    }
 
-        // Build appropriate payload…
-        let payload = match &action {
-            ProcessMessageAction::Send => MessageLikePayload::Message {
-                body: message_body,
-                attachments: request.attachments,
-                encryption_info: None,
-                is_transient: false,
-                reply_to: None,
-                thread_id: None,
-            },
-            ProcessMessageAction::ReplyInThread { thread_id } => MessageLikePayload::Message {
-                body: message_body,
-                attachments: request.attachments,
-                encryption_info: None,
-                is_transient: false,
-                reply_to: None,
-                thread_id: Some(thread_id.clone()),
-            },
-            ProcessMessageAction::Update {
-                target_remote_id, ..
-            } => MessageLikePayload::Correction {
-                target_id: target_remote_id.clone().into(),
-                body: message_body,
-                attachments: request.attachments,
-                encryption_info: None,
-            },
-        };
-
-        // Save the unencrypted message so that we can look it up later…
-        let message_id = message_request.id.clone();
-        self.message_repo
-            .append(
-                &account,
-                &self.data.room_id,
-                &[MessageLike {
-                    id: message_id.clone(),
-                    remote_id: Some(message_id.to_string().into()),
-                    server_id: None,
-                    to: None,
-                    from: account.to_user_id().into(),
-                    timestamp: self.time_provider.now(),
-                    payload,
-                }],
-            )
-            .await?;
+    async fn resolve_encryption_recipients(&self) -> Result<Vec<UserId>> {
+        let (mut user_ids, unresolved_participants) = self.data.with_participants(|p| {
+            let mut user_ids = vec![];
+            let mut unresolved_participants = vec![];
 
-        // Pass message to MessagingService and build a ClientRoomEvent…
-        let event = match action {
-            ProcessMessageAction::Send => {
-                self.messaging_service
-                    .send_message(&self.data.room_id, message_request)
-                    .await?;
-                ClientRoomEventType::MessagesAppended {
-                    message_ids: vec![message_id],
+            for (id, participant) in p.iter() {
+                if participant.is_self {
+                    continue;
                 }
-            }
-            ProcessMessageAction::ReplyInThread { thread_id } => {
-                self.messaging_service
-                    .send_message_to_thread(&self.data.room_id, &thread_id, message_request)
-                    .await?;
-                // TODO: Add parent message to this event?
-                ClientRoomEventType::MessagesAppended {
-                    message_ids: vec![message_id],
+                match &participant.real_id {
+                    Some(real_id) => user_ids.push(real_id.clone()),
+                    None => unresolved_participants.push(id.clone()),
                 }
             }
-            ProcessMessageAction::Update {
-                target_message_id,
-                target_remote_id,
-            } => {
-                self.messaging_service
-                    .update_message(&self.data.room_id, &target_remote_id, message_request)
-                    .await?;
-                ClientRoomEventType::MessagesUpdated {
-                    message_ids: vec![target_message_id],
+
+            (user_ids, unresolved_participants)
+        });
+
+        if !unresolved_participants.is_empty() {
+            let account = self.ctx.connected_account()?.to_user_id();
+
+            let members = match self.data.room_id.muc_id() {
+                Some(muc_id) if self.data.r#type != RoomType::DirectMessage => self
+                    .room_management_service
+                    .load_room_members(muc_id)
+                    .await
+                    .unwrap_or_else(|err| {
+                        warn!(
+                            "Failed to load members of {}. {}",
+                            self.data.room_id,
+                            err.to_string()
+                        );
+                        vec![]
+                    }),
+                _ => vec![],
+            };
+
+            if members.is_empty() {
+                return Err(EncryptionError::UnresolvedParticipants {
+                    participants: unresolved_participants,
                 }
+                .into());
             }
-        };
 
-        // Dispatch event to notify UI about changes…
-        self.client_event_dispatcher
-            .dispatch_room_event(self.data.clone(), event);
+            user_ids.extend(
+                members
+                    .into_iter()
+                    .map(|member| member.id)
+                    .filter(|id| id != &account),
+            );
+        }
 
-        Ok(())
+        Ok(user_ids.into_iter().sorted().dedup().collect())
     }
 
-    async fn load_messages(&self, before: Option<&MessageServerId>) -> Result<MessageResultSet> {
+    async fn load_messages(&self, range: MessageRange<'_>) -> Result<MessageResultSet> {
         let account = self.ctx.connected_account()?;
         let message_page_size = self.ctx.config.message_page_size;
         let max_message_pages_to_load = self.ctx.config.max_message_pages_to_load as usize;
 
         let mut messages = vec![];
-        let mut last_message_id: Option<MessageServerId> = before.cloned();
-        let mut last_local_message_id: Option<MessageId> = None;
+        let mut cursor: Option<MessageServerId> = match range {
+            MessageRange::Before(before) => before.cloned(),
+            MessageRange::After(after) => Some(after.clone()),
+        };
+        // The ids of the oldest and newest archived messages we've loaded.
+        let mut oldest_server_id: Option<MessageServerId> = None;
+        let mut newest_server_id: Option<MessageServerId> = None;
+        let mut is_complete = false;
         let mut num_text_messages = 0;
         let mut text_message_ids = vec![];
         let mut loaded_pages = 0;
 
         while num_text_messages < message_page_size && loaded_pages < max_message_pages_to_load {
-            let page = self
-                .message_archive_service
-                .load_messages_before(
-                    &self.data.room_id,
-                    last_message_id.as_ref(),
-                    message_page_size,
-                )
-                .await?;
+            let mut page = match range {
+                MessageRange::Before(_) => {
+                    self.message_archive_service
+                        .load_messages_before(
+                            &self.data.room_id,
+                            cursor.as_ref(),
+                            message_page_size,
+                        )
+                        .await?
+                }
+                MessageRange::After(after) => {
+                    self.message_archive_service
+                        .load_messages_after(
+                            &self.data.room_id,
+                            cursor.as_ref().unwrap_or(after),
+                            message_page_size,
+                        )
+                        .await?
+                }
+            };
 
-            last_message_id = page
-                .messages
-                .first()
-                .map(|m| MessageServerId::from(m.id.as_ref()));
+            let (Some(first_message), Some(last_message)) =
+                (page.messages.first(), page.messages.last())
+            else {
+                is_complete = true;
+                break;
+            };
+            let first_server_id = MessageServerId::from(first_message.id.as_ref());
+            let last_server_id = MessageServerId::from(last_message.id.as_ref());
+
+            match range {
+                MessageRange::Before(_) => {
+                    newest_server_id.get_or_insert(last_server_id);
+                    oldest_server_id = Some(first_server_id.clone());
+                    cursor = Some(first_server_id);
+                    // We want `messages` in the order from newest to oldest in this case…
+                    page.messages.reverse();
+                }
+                MessageRange::After(_) => {
+                    oldest_server_id.get_or_insert(first_server_id);
+                    newest_server_id = Some(last_server_id.clone());
+                    cursor = Some(last_server_id);
+                }
+            }
 
-            // We're potentially loading multiple pages all oldest from newest, i.e.:
+            // When loading backwards we're potentially loading multiple pages all oldest from
+            // newest, i.e.:
             // Page 1: 4, 5, 6
             // Page 2: 1, 2, 3
             // and we want to push them into `messages` in the order 6, 5, 4, 3, 2, 1 which is
-            // why we need to iterate over each page in reverse…
-            for archive_message in page.messages.into_iter().rev() {
+            // why we've reversed each page above…
+            for archive_message in page.messages {
                 let inner_message = archive_message.forwarded.stanza.as_ref();
 
                 let is_our_message = inner_message
@@ -701,12 +2302,6 @@ impl<Kind> Room<Kind> {
                 }
                 .unwrap_or_else(|| self.message_id_provider.new_id());
 
-                if Some(archive_message.id.as_ref())
-                    == last_message_id.as_ref().map(|id| id.as_ref())
-                {
-                    last_local_message_id = Some(message_id.clone())
-                }
-
                 let parsed_message = match MessageParser::new(
                     message_id,
                     Some(self.data.clone()),
@@ -753,17 +2348,26 @@ impl<Kind> Room<Kind> {
             loaded_pages += 1;
 
             if page.is_last {
-                last_local_message_id = None;
+                is_complete = true;
                 break;
             }
         }
 
-        let later_targeting_earlier_messages = if before.is_some() && !text_message_ids.is_empty() {
+        // Messages are now sorted from oldest to newest, regardless of the direction in which
+        // we've loaded them.
+        if let MessageRange::Before(_) = range {
+            messages.reverse();
+        }
+
+        let is_loading_latest_messages = matches!(range, MessageRange::Before(None));
+
+        let later_targeting_earlier_messages = if !is_loading_latest_messages
+            && !text_message_ids.is_empty()
+        {
             // We want to only load messages that are newer than our newest message, since we might
             // have older messages in our cache from previous runs and these could mess up the
             // order if we'll append them to the end of our array for reducing.
-            // Note that `messages` is currently sorted from newest to oldest.
-            if let Some(newest_message_timestamp) = messages.first().as_ref().map(|m| m.timestamp) {
+            if let Some(newest_message_timestamp) = messages.last().as_ref().map(|m| m.timestamp) {
                 self.message_repo
                     .get_messages_targeting(
                         &account,
@@ -784,26 +2388,75 @@ impl<Kind> Room<Kind> {
             .append(&account, &self.data.room_id, &messages)
             .await?;
 
-        // So we have our `messages` in the order from newest to oldest (6, 5, 4, …) and need
-        // them in the order from oldest to newest (1, 2, 3, …) to reduce and return them.
         // `later_targeting_earlier_messages` is already in the order from oldest to newest and
-        // is guaranteed to only contain messages newer than those in `messages`. So we'll flip
-        // `messages`, chain `later_targeting_earlier_messages` and everything should be fine
-        // and dandy…
-        let result_set = MessageResultSet {
-            messages: self
-                .reduce_messages_and_add_sender(
-                    &account,
-                    messages
-                        .into_iter()
-                        .rev()
-                        .chain(later_targeting_earlier_messages.into_iter()),
-                )
-                .await,
-            last_message_id: last_local_message_id,
+        // is guaranteed to only contain messages newer than those in `messages`. So we'll
+        // chain them to `messages` and everything should be fine and dandy…
+        let messages = self
+            .reduce_timeline_messages(
+                &account,
+                messages
+                    .into_iter()
+                    .chain(later_targeting_earlier_messages.into_iter()),
+            )
+            .await;
+
+        let (before, after) = match range {
+            MessageRange::Before(before) => (
+                (!is_complete).then_some(cursor).flatten(),
+                before.map(|before| newest_server_id.unwrap_or_else(|| before.clone())),
+            ),
+            MessageRange::After(after) => (
+                Some(oldest_server_id.unwrap_or_else(|| after.clone())),
+                (!is_complete).then_some(cursor).flatten(),
+            ),
         };
 
-        Ok(result_set)
+        Ok(MessageResultSet {
+            messages,
+            before: before.map(Into::into),
+            after: after.map(Into::into),
+            is_complete,
+            source: DataSource::Live,
+        })
+    }
+
+    async fn load_cached_latest_messages(&self) -> Result<MessageResultSet> {
+        debug!("Loading latest messages from cache…");
+        let account = self.ctx.account()?;
+
+        let messages = self
+            .message_repo
+            .get_latest_messages(
+                &account,
+                &self.data.room_id,
+                self.ctx.config.message_page_size,
+            )
+            .await?;
+
+        // Older messages can be loaded from the server once we're connected…
+        let oldest_server_id = messages.iter().find_map(|m| m.server_id.clone());
+
+        Ok(MessageResultSet {
+            messages: self.reduce_timeline_messages(&account, messages).await,
+            before: oldest_server_id.map(Into::into),
+            after: None,
+            is_complete: false,
+            source: DataSource::Cached,
+        })
+    }
+
+    /// Like `reduce_messages_and_add_sender` but leaves out private messages exchanged with
+    /// single occupants, since these are not part of the room's timeline.
+    async fn reduce_timeline_messages(
+        &self,
+        account: &AccountId,
+        messages: impl IntoIterator<Item = MessageLike>,
+    ) -> Vec<MessageDTO> {
+        self.reduce_messages_and_add_sender(account, messages)
+            .await
+            .into_iter()
+            .filter(|message| !message.flags.is_transient)
+            .collect()
     }
 
     async fn reduce_messages_and_add_sender(
@@ -813,51 +2466,66 @@ impl<Kind> Room<Kind> {
     ) -> Vec<MessageDTO> {
         let messages = Message::reducing_messages(messages);
         let mut message_dtos = Vec::with_capacity(messages.len());
-        let mut message_senders = HashMap::new();
         let last_read_message_id = self
             .data
             .settings()
             .last_read_message
             .as_ref()
             .map(|msg| msg.stanza_id.clone());
+        let pending_messages = self
+            .pending_messages_repo
+            .get_all_in_room(account, &self.data.room_id)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|message| (message.id.clone(), message))
+            .collect::<HashMap<_, _>>();
 
-        async fn resolve_message_sender<'a, Kind>(
+        async fn resolve_message_sender<Kind>(
             room: &Room<Kind>,
-            id: Cow<'a, ParticipantId>,
-            map: &mut HashMap<ParticipantId, MessageSender>,
+            id: &ParticipantId,
         ) -> MessageSender {
-            if let Some(sender) = map.get(id.as_ref()) {
-                return sender.clone();
+            if let Some(sender) = room.data.cached_message_sender(id) {
+                return sender;
             };
-            let sender = room.resolve_message_sender(id.as_ref()).await;
-            map.insert(id.into_owned(), sender.clone());
+            let sender = room.resolve_message_sender(id).await;
+            room.data.cache_message_sender(sender.clone());
             sender
         }
 
         for message in messages {
-            let from =
-                resolve_message_sender(self, Cow::Borrowed(&message.from), &mut message_senders)
-                    .await;
+            let from = resolve_message_sender(self, &message.from).await;
+
+            if let ParticipantId::Occupant(occupant_id) = &message.from {
+                let has_real_id = self.data.with_participants(|p| {
+                    p.get(&message.from)
+                        .map(|participant| participant.real_id.is_some())
+                        .unwrap_or_else(|| p.evicted_participant_real_id(&message.from).is_some())
+                });
+                if !has_real_id {
+                    self.data
+                        .track_message_with_unresolved_sender(occupant_id, &message.id);
+                }
+            }
 
             let mut reactions = vec![];
             for reaction in message.reactions {
                 let mut from = vec![];
 
                 for sender in reaction.from {
-                    from.push(
-                        resolve_message_sender(self, Cow::Owned(sender), &mut message_senders)
-                            .await,
-                    );
+                    from.push(resolve_message_sender(self, &sender).await);
                 }
 
                 reactions.push(ReactionDTO {
                     emoji: reaction.emoji,
                     from,
+                    last_updated: reaction.last_updated,
                 })
             }
 
             let is_last_read_message =
                 message.server_id.is_some() && message.server_id == last_read_message_id;
+            let pending_message = pending_messages.get(&message.id);
 
             let reply_to = 'outer: {
                 if let Some(reply_to) = message.reply_to {
@@ -897,12 +2565,7 @@ impl<Kind> Room<Kind> {
 
                     Some(ReplyToDTO {
                         id: message_id,
-                        sender: resolve_message_sender(
-                            self,
-                            Cow::Borrowed(&replied_to_sender),
-                            &mut message_senders,
-                        )
-                        .await,
+                        sender: resolve_message_sender(self, &replied_to_sender).await,
                         timestamp,
                         body,
                     })
@@ -911,6 +2574,11 @@ impl<Kind> Room<Kind> {
                 }
             };
 
+            let moderated_by = match &message.moderated_by {
+                Some(moderator) => Some(resolve_message_sender(self, moderator).await),
+                None => None,
+            };
+
             message_dtos.push(MessageDTO {
                 id: message.id,
                 from,
@@ -919,15 +2587,34 @@ impl<Kind> Room<Kind> {
                 flags: MessageFlagsDTO {
                     is_read: message.flags.is_read,
                     is_edited: message.flags.is_edited,
-                    is_delivered: message.flags.is_delivered,
+                    delivery_state: if message.flags.is_delivered {
+                        DeliveryState::Delivered
+                    } else if pending_message.is_some_and(PendingMessage::is_pending) {
+                        DeliveryState::Sending
+                    } else {
+                        DeliveryState::Sent
+                    },
                     is_transient: message.flags.is_transient,
                     is_encrypted: message.flags.is_encrypted,
+                    sender_device_trust: message.flags.sender_device_trust,
+                    sender_device_id: message.flags.sender_device_id,
+                    is_undecryptable: message.flags.is_undecryptable,
                     is_last_read: is_last_read_message,
+                    is_pending: pending_message.is_some_and(PendingMessage::is_pending),
+                    send_error: pending_message
+                        .and_then(PendingMessage::error)
+                        .map(ToString::to_string),
                 },
                 reactions,
                 attachments: message.attachments,
                 mentions: message.mentions,
                 reply_to,
+                thread_id: message.thread_id,
+                thread_reply_count: message.thread_reply_count,
+                last_thread_reply_at: message.last_thread_reply_at,
+                is_retracted: message.is_retracted,
+                retraction_reason: message.retraction_reason,
+                moderated_by,
             });
         }
 
@@ -935,18 +2622,25 @@ impl<Kind> Room<Kind> {
     }
 
     async fn resolve_message_sender(&self, id: &ParticipantId) -> MessageSender {
-        let (name, avatar, mut real_id) = self
-            .data
-            .with_participants(|p| {
-                p.get(id).map(|p| {
+        let (name, avatar, mut real_id, is_evicted) = self.data.with_participants(|p| {
+            p.get(id)
+                .map(|participant| {
                     (
-                        Some(p.name().unwrap_or_participant_id(id)),
-                        p.avatar.clone(),
-                        p.real_id.clone(),
+                        Some(participant.name().unwrap_or_participant_id(id)),
+                        participant.avatar.clone(),
+                        participant.real_id.clone(),
+                        false,
                     )
                 })
-            })
-            .unwrap_or_else(|| (None, None, None));
+                .unwrap_or_else(|| {
+                    (
+                        None,
+                        None,
+                        p.evicted_participant_real_id(id).cloned(),
+                        p.is_evicted(id),
+                    )
+                })
+        });
 
         if let Some(name) = name {
             return MessageSender {
@@ -966,9 +2660,17 @@ impl<Kind> Room<Kind> {
             };
         };
 
+        // Participants that were evicted from a bounded participant list are still part of the
+        // room, so it's worth loading their profile…
+        let cache_policy = if is_evicted {
+            CachePolicy::ReturnCacheDataElseLoad
+        } else {
+            CachePolicy::ReturnCacheDataDontLoad
+        };
+
         let (name, avatar) = self
             .user_info_domain_service
-            .get_user_info(&real_id, CachePolicy::ReturnCacheDataDontLoad)
+            .get_user_info(&real_id, cache_policy)
             .await
             .unwrap_or_default()
             .map(|i| (i.display_name().unwrap_or_participant_id(id), i.avatar))
@@ -981,6 +2683,42 @@ impl<Kind> Room<Kind> {
         }
     }
 
+    async fn send_reactions(
+        &self,
+        account: &AccountId,
+        id: &MessageId,
+        remote_id: Option<&MessageRemoteId>,
+        server_id: Option<&MessageServerId>,
+        emojis: &[Emoji],
+    ) -> Result<()> {
+        match &self.data.room_id {
+            RoomId::User(room_id) => {
+                let Some(remote_id) = remote_id else {
+                    return Err(ReactionError::MissingRemoteId.into());
+                };
+                self.messaging_service
+                    .react_to_chat_message(room_id, remote_id, emojis)
+                    .await
+            }
+            RoomId::Muc(room_id) => {
+                // The StanzaId might have arrived (via the reflected message) in the meantime,
+                // so let's try resolving it once more before giving up…
+                let stanza_id = match server_id {
+                    Some(server_id) => server_id.clone(),
+                    None => self
+                        .message_repo
+                        .resolve_message_id(account, &self.data.room_id, id)
+                        .await?
+                        .and_then(|ids| ids.server_id)
+                        .ok_or(ReactionError::MissingServerId)?,
+                };
+                self.messaging_service
+                    .react_to_muc_message(room_id, &stanza_id, emojis)
+                    .await
+            }
+        }
+    }
+
     async fn show_system_message(&self, message: impl Into<String>) -> Result<()> {
         let id = self.message_id_provider.new_id();
         let message = message.into();
@@ -1007,6 +2745,7 @@ impl<Kind> Room<Kind> {
                         is_transient: true,
                         reply_to: None,
                         thread_id: None,
+                        is_markable: false,
                     },
                 }],
             )
@@ -1051,6 +2790,91 @@ impl<Kind> Room<Kind> {
         }
     }
 
+    /// Sends a displayed marker (XEP-0333) for the last read message if it changed compared to
+    /// `former_message_ref`. Markers are only sent in direct messages and groups whose MUC service
+    /// supports them, only if the sender requested them and only if our user didn't opt out of
+    /// sending read receipts, either for the account or for this room.
+    async fn send_read_receipt_if_needed(
+        &self,
+        account: &AccountId,
+        former_message_ref: Option<ArchivedMessageRef>,
+    ) -> Result<()> {
+        let Some(message_ref) = self.data.settings().last_read_message else {
+            return Ok(());
+        };
+
+        if former_message_ref.as_ref() == Some(&message_ref) {
+            return Ok(());
+        }
+
+        if !matches!(self.data.r#type, RoomType::DirectMessage | RoomType::Group) {
+            return Ok(());
+        }
+
+        // Direct messages announce support for markers per message (see `is_markable` below),
+        // groups via the features of the MUC service.
+        if self.data.r#type == RoomType::Group && !self.data.features.chat_markers {
+            return Ok(());
+        }
+
+        if !self.data.settings().send_read_receipts {
+            return Ok(());
+        }
+
+        let Some(ids) = self
+            .message_repo
+            .resolve_server_id(account, &self.data.room_id, &message_ref.stanza_id)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let Some(message) = self
+            .message_repo
+            .get(account, &self.data.room_id, &ids.id)
+            .await?
+            .into_iter()
+            .find(|message| message.payload.is_message())
+        else {
+            return Ok(());
+        };
+
+        let MessageLikePayload::Message {
+            is_markable: true, ..
+        } = message.payload
+        else {
+            return Ok(());
+        };
+
+        if message.from.to_user_id().as_ref() == Some(&account.to_user_id()) {
+            return Ok(());
+        }
+
+        // Markers reference the message's remote id in direct messages and the id assigned by
+        // the MUC service in groups.
+        let target_id = match self.data.r#type {
+            RoomType::DirectMessage => message.remote_id.map(MessageTargetId::from),
+            _ => message.server_id.map(MessageTargetId::from),
+        };
+
+        let Some(target_id) = target_id else {
+            return Ok(());
+        };
+
+        if !self
+            .account_settings_repo
+            .get(account)
+            .await?
+            .send_read_receipts
+        {
+            return Ok(());
+        }
+
+        self.messaging_service
+            .send_read_receipt(&self.data.room_id, &target_id)
+            .await
+    }
+
     async fn set_last_read_message_ref(
         &self,
         account: &AccountId,
@@ -1180,7 +3004,7 @@ impl Room<PublicChannel> {
 
         for user in user_jids.iter() {
             self.participation_service
-                .grant_membership(self.muc_id(), user)
+                .set_affiliation(self.muc_id(), user, RoomAffiliation::Member, None)
                 .await?;
         }
 
@@ -1201,6 +3025,384 @@ where
             .muc_id()
             .expect("MucRoom must have RoomId::Muc")
     }
+
+    /// Sends a private message to a single occupant of the room. Like private messages received
+    /// from other occupants, the message is not part of the room's timeline but announced via
+    /// `ClientRoomEventType::PrivateMessagesAppended`.
+    pub async fn send_private_message(
+        &self,
+        occupant_id: &OccupantId,
+        request: SendMessageRequestDTO,
+    ) -> Result<()> {
+        ensure!(!request.is_empty(), "SendMessageRequest is empty");
+        ensure!(
+            &occupant_id.muc_id() == self.muc_id(),
+            "Occupant {occupant_id} is not a member of room {}",
+            self.muc_id()
+        );
+
+        self.process_send_message_request(
+            &self.ctx.connected_account()?,
+            request,
+            ProcessMessageAction::SendPrivate {
+                occupant_id: occupant_id.clone(),
+            },
+        )
+        .await
+    }
+
+    /// Removes a message of another occupant from the room on behalf of a moderator
+    /// (XEP-0425). The message is replaced with a tombstone once the room confirmed the request.
+    pub async fn moderate_message(
+        &self,
+        id: MessageId,
+        reason: Option<&str>,
+    ) -> Result<(), RoomError> {
+        if !self.data.features.message_moderation {
+            return Err(RoomError::MessageModerationNotSupported);
+        }
+
+        let account = self.ctx.connected_account()?;
+
+        let Some(server_id) = self
+            .message_repo
+            .resolve_message_id(&account, &self.data.room_id, &id)
+            .await?
+            .and_then(|t| t.server_id)
+        else {
+            return Err(anyhow!("Failed to resolve message id '{id}' to a server id").into());
+        };
+
+        self.participation_service
+            .moderate_message(self.muc_id(), &server_id, reason)
+            .await?;
+
+        let moderator = self
+            .data
+            .occupant_id()
+            .map(ParticipantId::Occupant)
+            .unwrap_or_else(|| ParticipantId::User(account.to_user_id()));
+
+        self.message_repo
+            .append(
+                &account,
+                &self.data.room_id,
+                &[MessageLike {
+                    id: self.message_id_provider.new_id(),
+                    remote_id: None,
+                    server_id: None,
+                    to: Some(self.muc_id().clone().into_inner()),
+                    from: moderator.clone(),
+                    timestamp: self
+                        .data
+                        .features
+                        .local_time_to_server_time(self.time_provider.now()),
+                    payload: MessageLikePayload::Retraction {
+                        target_id: MessageTargetId::ServerId(server_id),
+                        reason: reason.map(ToString::to_string),
+                        moderated_by: Some(moderator),
+                    },
+                }],
+            )
+            .await?;
+
+        self.client_event_dispatcher.dispatch_room_event(
+            self.data.clone(),
+            ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![id],
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Destroys the room. Its occupants will be redirected to `alternate_room` if set. Requires
+    /// our user to be an owner of the room.
+    ///
+    /// The room and its bookmark are removed and its cached messages deleted. A
+    /// `ClientRoomEventType::Destroyed` and a `ClientEvent::SidebarChanged` event are dispatched
+    /// afterwards.
+    pub async fn destroy(
+        &self,
+        alternate_room: Option<MucId>,
+        reason: Option<&str>,
+    ) -> Result<(), RoomError> {
+        let is_owner = self.data.with_participants(|participants| {
+            participants
+                .values()
+                .any(|p| p.is_self && p.affiliation == RoomAffiliation::Owner)
+        });
+
+        if !is_owner {
+            return Err(RoomError::NotRoomOwner);
+        }
+
+        self.sidebar_domain_service
+            .destroy_room(self.muc_id(), alternate_room, reason)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Loads the current configuration of the room. Requires our user to be an owner of the room.
+    pub async fn load_configuration(&self) -> Result<RoomConfiguration, RoomError> {
+        if self.own_affiliation_and_role().0 != RoomAffiliation::Owner {
+            return Err(RoomError::NotRoomOwner);
+        }
+
+        self.room_management_service
+            .load_room_configuration(self.muc_id())
+            .await
+    }
+
+    /// Submits `configuration` to the room. Requires our user to be an owner of the room.
+    ///
+    /// Fields of the room's configuration form that are not covered by `RoomConfiguration` are
+    /// submitted unchanged. Afterwards the room's info and features are reloaded and a
+    /// `ClientRoomEventType::AttributesChanged` event is dispatched for the reloaded room.
+    pub async fn update_configuration(
+        &self,
+        configuration: RoomConfiguration,
+    ) -> Result<(), RoomError> {
+        if self.own_affiliation_and_role().0 != RoomAffiliation::Owner {
+            return Err(RoomError::NotRoomOwner);
+        }
+
+        self.room_management_service
+            .update_room_configuration(self.muc_id(), configuration)
+            .await?;
+
+        self.sidebar_domain_service
+            .handle_changed_room_config(self.muc_id())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Changes the affiliation of the user with `user_id`, who doesn't need to be in the room
+    /// currently. Owners may grant any affiliation, admins may only change the affiliation of
+    /// users below them and not grant admin or owner privileges.
+    pub async fn grant_affiliation(
+        &self,
+        user_id: &UserId,
+        affiliation: RoomAffiliation,
+    ) -> Result<(), RoomError> {
+        self.change_affiliation(user_id, affiliation, None).await
+    }
+
+    /// Revokes the membership of the user with `user_id`. See `grant_affiliation`.
+    pub async fn revoke_membership(&self, user_id: &UserId) -> Result<(), RoomError> {
+        self.grant_affiliation(user_id, RoomAffiliation::None).await
+    }
+
+    /// Bans the user with `user_id` from the room, i.e. sets their affiliation to outcast. The
+    /// same privileges as for `grant_affiliation` apply.
+    pub async fn ban_user(&self, user_id: &UserId, reason: Option<&str>) -> Result<(), RoomError> {
+        self.change_affiliation(user_id, RoomAffiliation::Outcast, reason)
+            .await
+    }
+
+    /// Removes the user with `user_id` from the room's ban list.
+    pub async fn unban_user(&self, user_id: &UserId) -> Result<(), RoomError> {
+        self.change_affiliation(user_id, RoomAffiliation::None, None)
+            .await
+    }
+
+    /// Loads the users on the room's ban list. Requires us to be an admin or owner.
+    pub async fn load_banned_users(&self) -> Result<Vec<UserBasicInfo>, RoomError> {
+        let (own_affiliation, _) = self.own_affiliation_and_role();
+        if own_affiliation < RoomAffiliation::Admin {
+            return Err(RoomError::InsufficientPrivileges);
+        }
+
+        let user_ids = self
+            .participation_service
+            .load_users_with_affiliation(self.muc_id(), RoomAffiliation::Outcast)
+            .await?;
+
+        let users = join_all(user_ids.into_iter().map(|id| async move {
+            self.user_info_domain_service
+                .get_user_info(&id, CachePolicy::ReturnCacheDataDontLoad)
+                .await
+                .unwrap_or_default()
+                .into_user_basic_info_or_fallback(id)
+        }))
+        .await;
+
+        Ok(users)
+    }
+
+    /// Kicks the occupant with `occupant_id` from the room, i.e. sets their role to none.
+    /// Requires us to be a moderator and the occupant to be neither admin nor owner.
+    pub async fn kick_user(
+        &self,
+        occupant_id: &OccupantId,
+        reason: Option<&str>,
+    ) -> Result<(), RoomError> {
+        let participant_id = ParticipantId::Occupant(occupant_id.clone());
+        let (_, own_role) = self.own_affiliation_and_role();
+        let target_affiliation = self
+            .data
+            .with_participants(|p| p.get(&participant_id).map(|p| p.affiliation))
+            .unwrap_or_default();
+
+        if own_role != RoomRole::Moderator || target_affiliation >= RoomAffiliation::Admin {
+            return Err(RoomError::InsufficientPrivileges);
+        }
+
+        self.participation_service
+            .set_role(occupant_id, RoomRole::None, reason)
+            .await?;
+
+        // Remove the occupant right away. The server's removal presence will follow.
+        self.data
+            .with_participants_mut(|participants| participants.remove(&participant_id));
+
+        self.client_event_dispatcher.dispatch_room_event(
+            self.data.clone(),
+            ClientRoomEventType::ParticipantsChanged {
+                ids: vec![participant_id],
+            },
+        );
+
+        Ok(())
+    }
+
+    async fn change_affiliation(
+        &self,
+        user_id: &UserId,
+        affiliation: RoomAffiliation,
+        reason: Option<&str>,
+    ) -> Result<(), RoomError> {
+        let (own_affiliation, _) = self.own_affiliation_and_role();
+        let current_affiliation = self.data.with_participants(|participants| {
+            participants
+                .values()
+                .filter(|p| p.real_id.as_ref() == Some(user_id))
+                .map(|p| p.affiliation)
+                .max()
+                .unwrap_or_default()
+        });
+
+        let is_permitted = match own_affiliation {
+            RoomAffiliation::Owner => true,
+            RoomAffiliation::Admin => {
+                current_affiliation < RoomAffiliation::Admin && affiliation < RoomAffiliation::Admin
+            }
+            _ => false,
+        };
+
+        if !is_permitted {
+            return Err(RoomError::InsufficientPrivileges);
+        }
+
+        self.participation_service
+            .set_affiliation(self.muc_id(), user_id, affiliation, reason)
+            .await?;
+
+        // Update our participants right away. The server's presence updates will follow for
+        // occupants, but not for users who aren't in the room.
+        let mut ids = self.data.with_participants(|participants| {
+            participants
+                .iter()
+                .filter(|(_, p)| p.real_id.as_ref() == Some(user_id))
+                .map(|(id, _)| id.clone())
+                .collect::<Vec<_>>()
+        });
+
+        if ids.is_empty() && affiliation >= RoomAffiliation::Member {
+            let name = self
+                .user_info_domain_service
+                .get_user_info(user_id, CachePolicy::ReturnCacheDataElseLoad)
+                .await?
+                .display_name()
+                .build();
+            self.data.with_participants_mut(|participants| {
+                participants.add_user(user_id, false, affiliation, name)
+            });
+            ids.push(ParticipantId::User(user_id.clone()));
+        } else {
+            self.data.with_participants_mut(|participants| {
+                for id in &ids {
+                    match id {
+                        // Banned users are removed from the room…
+                        _ if affiliation == RoomAffiliation::Outcast => participants.remove(id),
+                        // Users who aren't in the room are only listed as long as they're members.
+                        ParticipantId::User(_) if affiliation < RoomAffiliation::Member => {
+                            participants.remove(id)
+                        }
+                        _ => {
+                            let is_self = participants.get(id).map(|p| p.is_self).unwrap_or(false);
+                            participants.set_affiliation(id, is_self, affiliation)
+                        }
+                    }
+                }
+            });
+        }
+
+        if !ids.is_empty() {
+            self.client_event_dispatcher.dispatch_room_event(
+                self.data.clone(),
+                ClientRoomEventType::ParticipantsChanged { ids },
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Changes the role of the occupant with `occupant_id`. Moderators may grant or revoke voice,
+    /// i.e. switch occupants below admins between participant and visitor, while granting or
+    /// revoking the moderator role requires us to be an admin or owner.
+    pub async fn grant_role(
+        &self,
+        occupant_id: &OccupantId,
+        role: RoomRole,
+    ) -> Result<(), RoomError> {
+        let participant_id = ParticipantId::Occupant(occupant_id.clone());
+        let (own_affiliation, own_role) = self.own_affiliation_and_role();
+        let (target_affiliation, target_role) = self
+            .data
+            .with_participants(|p| p.get(&participant_id).map(|p| (p.affiliation, p.role)))
+            .unwrap_or_default();
+
+        let is_permitted = if role == RoomRole::Moderator || target_role == RoomRole::Moderator {
+            own_affiliation >= RoomAffiliation::Admin
+        } else {
+            own_role == RoomRole::Moderator && target_affiliation < RoomAffiliation::Admin
+        };
+
+        if !is_permitted {
+            return Err(RoomError::InsufficientPrivileges);
+        }
+
+        self.participation_service
+            .set_role(occupant_id, role, None)
+            .await?;
+
+        // Update the occupant right away. The server's presence update will follow.
+        self.data
+            .with_participants_mut(|participants| participants.set_role(&participant_id, role));
+
+        self.client_event_dispatcher.dispatch_room_event(
+            self.data.clone(),
+            ClientRoomEventType::ParticipantsChanged {
+                ids: vec![participant_id],
+            },
+        );
+
+        Ok(())
+    }
+
+    fn own_affiliation_and_role(&self) -> (RoomAffiliation, RoomRole) {
+        self.data
+            .with_participants(|participants| {
+                participants
+                    .values()
+                    .find(|p| p.is_self)
+                    .map(|p| (p.affiliation, p.role))
+            })
+            .unwrap_or_default()
+    }
 }
 
 impl<Kind> Room<Kind>
@@ -1237,3 +3439,17 @@ where
         Ok(())
     }
 }
+
+fn is_permanent_send_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<SendMessageError>()
+        .is_some_and(SendMessageError::is_permanent)
+}
+
+/// The direction and starting point in which to load messages from the archive.
+#[derive(Clone, Copy)]
+enum MessageRange<'a> {
+    /// Messages older than the given id or the latest messages if `None`.
+    Before(Option<&'a MessageServerId>),
+    /// Messages newer than the given id.
+    After(&'a MessageServerId),
+}
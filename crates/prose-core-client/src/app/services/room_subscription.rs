@@ -0,0 +1,112 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+
+use futures::Stream;
+use parking_lot::Mutex;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::domain::shared::models::RoomId;
+use crate::ClientRoomEventType;
+
+/// An update of a single room, as delivered to a room subscription.
+pub type RoomUpdate = ClientRoomEventType;
+
+#[cfg(target_arch = "wasm32")]
+pub type RoomUpdateHandler = Box<dyn Fn(RoomUpdate)>;
+#[cfg(not(target_arch = "wasm32"))]
+pub type RoomUpdateHandler = Box<dyn Fn(RoomUpdate) + Send + Sync>;
+
+#[cfg(target_arch = "wasm32")]
+type SharedRoomUpdateHandler = Arc<dyn Fn(RoomUpdate)>;
+#[cfg(not(target_arch = "wasm32"))]
+type SharedRoomUpdateHandler = Arc<dyn Fn(RoomUpdate) + Send + Sync>;
+
+/// Keeps track of the handlers that subscribed to updates of a single room.
+#[derive(Default)]
+pub struct RoomSubscriptions {
+    inner: Arc<Mutex<RoomSubscriptionsInner>>,
+}
+
+#[derive(Default)]
+struct RoomSubscriptionsInner {
+    next_id: u64,
+    subscribers: HashMap<u64, (RoomId, SharedRoomUpdateHandler)>,
+}
+
+impl RoomSubscriptions {
+    pub fn subscribe(&self, room_id: RoomId, handler: RoomUpdateHandler) -> RoomSubscriptionHandle {
+        let mut inner = self.inner.lock();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.subscribers.insert(id, (room_id, Arc::from(handler)));
+
+        RoomSubscriptionHandle {
+            id,
+            subscriptions: Arc::downgrade(&self.inner),
+        }
+    }
+
+    pub fn dispatch(&self, room_id: &RoomId, update: &RoomUpdate) {
+        // Collect the handlers first so that they can unsubscribe while being called.
+        let handlers = self
+            .inner
+            .lock()
+            .subscribers
+            .values()
+            .filter(|(subscribed_room_id, _)| subscribed_room_id == room_id)
+            .map(|(_, handler)| handler.clone())
+            .collect::<Vec<_>>();
+
+        for handler in handlers {
+            (handler)(update.clone())
+        }
+    }
+}
+
+/// Keeps a room subscription alive. The subscription ends when the handle is dropped.
+pub struct RoomSubscriptionHandle {
+    id: u64,
+    subscriptions: Weak<Mutex<RoomSubscriptionsInner>>,
+}
+
+impl Drop for RoomSubscriptionHandle {
+    fn drop(&mut self) {
+        let Some(subscriptions) = self.subscriptions.upgrade() else {
+            return;
+        };
+        subscriptions.lock().subscribers.remove(&self.id);
+    }
+}
+
+/// A stream of the updates of a single room. The subscription ends when it is dropped.
+pub struct RoomSubscription {
+    receiver: UnboundedReceiver<RoomUpdate>,
+    _handle: RoomSubscriptionHandle,
+}
+
+impl RoomSubscription {
+    pub(crate) fn new(
+        receiver: UnboundedReceiver<RoomUpdate>,
+        handle: RoomSubscriptionHandle,
+    ) -> Self {
+        Self {
+            receiver,
+            _handle: handle,
+        }
+    }
+}
+
+impl Stream for RoomSubscription {
+    type Item = RoomUpdate;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
@@ -3,6 +3,8 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::future::Future;
+
 use anyhow::{bail, Result};
 use tracing::info;
 
@@ -12,7 +14,7 @@ use crate::app::deps::{
     DynAppContext, DynEncryptionDomainService, DynRoomManagementService, DynSidebarDomainService,
 };
 use crate::domain::rooms::models::constants::MAX_PARTICIPANTS_PER_GROUP;
-use crate::domain::rooms::models::PublicRoomInfo;
+use crate::domain::rooms::models::{PublicRoomInfo, RoomError};
 use crate::domain::rooms::services::{
     CreateOrEnterRoomRequest, CreateRoomBehavior, CreateRoomType, JoinRoomBehavior,
 };
@@ -96,6 +98,52 @@ impl RoomsService {
             .await
     }
 
+    /// Joins the room with `room_id` like `join_room`, but invokes `password_prompt` with the
+    /// failed password whenever the room rejects it and retries with the returned password.
+    /// Returning `None` from `password_prompt` stops the attempt and returns
+    /// `RoomError::InvalidPassword`.
+    pub async fn join_room_with_password_prompt<F, Fut>(
+        &self,
+        room_id: &MucId,
+        initial_password: Option<&str>,
+        mut password_prompt: F,
+    ) -> Result<RoomId>
+    where
+        F: FnMut(Option<String>) -> Fut,
+        Fut: Future<Output = Option<String>>,
+    {
+        let mut password = initial_password.map(ToString::to_string);
+
+        loop {
+            match self.join_room(room_id, password.as_deref()).await {
+                Ok(room_id) => return Ok(room_id),
+                Err(error)
+                    if error
+                        .downcast_ref::<RoomError>()
+                        .is_some_and(RoomError::is_invalid_password_err) =>
+                {
+                    let Some(new_password) = password_prompt(password.take()).await else {
+                        return Err(error);
+                    };
+                    password = Some(new_password);
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Joins the room that we were invited to, using the password from the invitation.
+    pub async fn accept_invitation(&self, room_id: &MucId) -> Result<RoomId> {
+        self.sidebar_domain_service.accept_invitation(room_id).await
+    }
+
+    /// Removes the room that we were invited to from the sidebar without joining it.
+    pub async fn decline_invitation(&self, room_id: &MucId) -> Result<()> {
+        self.sidebar_domain_service
+            .decline_invitation(room_id)
+            .await
+    }
+
     pub async fn create_room_for_direct_message(&self, participant_jid: &UserId) -> Result<RoomId> {
         self.sidebar_domain_service
             .insert_item_by_creating_or_joining_room(CreateOrEnterRoomRequest::JoinDirectMessage {
@@ -149,9 +197,4 @@ impl RoomsService {
             })
             .await
     }
-
-    pub async fn destroy_room(&self, room_id: &MucId) -> Result<()> {
-        self.sidebar_domain_service.destroy_room(room_id).await?;
-        Ok(())
-    }
 }
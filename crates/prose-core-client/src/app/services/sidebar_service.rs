@@ -3,6 +3,8 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::cmp::Reverse;
+
 use anyhow::Result;
 use tracing::error;
 
@@ -34,13 +36,13 @@ pub struct SidebarService {
 
 impl SidebarService {
     pub async fn sidebar_items(&self) -> Vec<SidebarItemDTO> {
-        let Ok(account) = self.ctx.connected_account() else {
+        let Ok(account) = self.ctx.account() else {
             error!("Could not read sidebar items since Client is not connected");
             return vec![];
         };
 
         let rooms: Vec<Room> = self.connected_rooms_repo.get_all(&account);
-        let mut item_dtos = vec![];
+        let mut items = vec![];
 
         for room in rooms {
             if room.r#type == RoomType::Unknown || !room.sidebar_state().is_in_sidebar() {
@@ -60,7 +62,15 @@ impl SidebarService {
                 .unwrap_or_default();
 
             let is_favorite = room.sidebar_state() == RoomSidebarState::Favorite;
+            let order_index = room.order_index();
+            let notification_mode = room.settings().notifications;
             let id = room.room_id.clone();
+            let last_activity = self
+                .messages_repo
+                .get_last_received_message(&account, &id, None)
+                .await
+                .unwrap_or_default()
+                .map(|message_ref| message_ref.timestamp);
 
             let item_dto = SidebarItemDTO {
                 name: room.name().unwrap_or_else(|| id.to_string()),
@@ -74,11 +84,28 @@ impl SidebarService {
                     .is_some(),
                 unread_count: stats.unread_count,
                 mentions_count: stats.mentions_count,
+                notification_mode,
+                order_index,
             };
-            item_dtos.push(item_dto)
+            items.push((item_dto, last_activity))
         }
 
-        item_dtos
+        // Favorites go first, followed by the manually ordered items and then the rest sorted
+        // by their last activity.
+        items.sort_by_key(|(item, last_activity)| {
+            let section = match (item.is_favorite, item.order_index) {
+                (true, _) => 0,
+                (false, Some(_)) => 1,
+                (false, None) => 2,
+            };
+            (
+                section,
+                item.order_index.unwrap_or(u32::MAX),
+                Reverse(*last_activity),
+            )
+        });
+
+        items.into_iter().map(|(item, _)| item).collect()
     }
 
     pub async fn toggle_favorite(&self, jid: &RoomId) -> Result<()> {
@@ -88,6 +115,15 @@ impl SidebarService {
         Ok(())
     }
 
+    /// Sets the manual order of the sidebar items. Items which are not contained in `room_ids`
+    /// are sorted by their last activity after the manually ordered items.
+    pub async fn set_manual_order(&self, room_ids: Vec<RoomId>) -> Result<()> {
+        self.sidebar_domain_service
+            .set_manual_order(&room_ids)
+            .await?;
+        Ok(())
+    }
+
     pub async fn remove_from_sidebar(&self, jid: &RoomId) -> Result<()> {
         self.sidebar_domain_service.remove_items(&[jid]).await?;
         Ok(())
@@ -3,15 +3,19 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+#[cfg(not(target_arch = "wasm32"))]
+use std::future::Future;
 use std::path::Path;
 
-use anyhow::{format_err, Result};
+use anyhow::Result;
 use mime::Mime;
 
 use prose_proc_macros::InjectDependencies;
 
 use crate::app::deps::{DynAppContext, DynUploadService};
-use crate::dtos::UploadSlot;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::dtos::{AttachmentEncryption, UploadHandle, UploadSource};
+use crate::dtos::{UploadError, UploadSlot};
 use crate::util::PathExt;
 
 #[derive(InjectDependencies)]
@@ -23,6 +27,13 @@ pub struct UploadService {
 }
 
 impl UploadService {
+    /// Returns the maximum file size in bytes that the server's upload service accepts. Returns
+    /// `u64::MAX` if the server doesn't advertise a limit and fails if the server doesn't offer
+    /// an upload service at all.
+    pub fn max_file_size(&self) -> Result<u64> {
+        Ok(self.ctx.http_upload_service()?.max_file_size)
+    }
+
     pub async fn request_upload_slot(
         &self,
         file_name: &str,
@@ -32,9 +43,10 @@ impl UploadService {
         let service = self.ctx.http_upload_service()?;
 
         if file_size > service.max_file_size {
-            return Err(format_err!(
-                "File exceeds maximum file size of upload service."
-            ));
+            return Err(UploadError::FileTooLarge {
+                max_file_size: Some(service.max_file_size),
+            }
+            .into());
         }
 
         let media_type = media_type.unwrap_or_else(|| Path::new(file_name).media_type());
@@ -54,3 +66,79 @@ impl UploadService {
         })
     }
 }
+
+impl UploadService {
+    /// Requests an upload slot and uploads `source` to it. `progress` is called with the number
+    /// of bytes sent and the total number of bytes while the upload is in progress.
+    ///
+    /// If `encrypt` is set, which it should be for rooms that have encryption enabled, the file
+    /// is encrypted with a fresh key before it is uploaded and the download URL of the returned
+    /// slot is an `aesgcm://` URL (XEP-0454). Use the same value for the thumbnail of the file.
+    ///
+    /// Returns a handle to cancel the upload together with the upload itself, which resolves to
+    /// the slot the file was uploaded to.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn upload_file(
+        &self,
+        source: UploadSource,
+        file_name: &str,
+        media_type: Option<Mime>,
+        encrypt: bool,
+        progress: impl Fn(u64, u64) + Send + Sync + 'static,
+    ) -> (
+        UploadHandle,
+        impl Future<Output = Result<UploadSlot, UploadError>> + '_,
+    ) {
+        use futures::future::{AbortHandle, Abortable};
+        use futures::FutureExt;
+        use std::sync::Arc;
+
+        use crate::infra::uploads;
+
+        let (abort_handle, abort_registration) = AbortHandle::new_pair();
+        let file_name = file_name.to_string();
+
+        let upload = async move {
+            let (source, encryption) = if encrypt {
+                let data = source
+                    .read()
+                    .await
+                    .map_err(|err| UploadError::Anyhow(err.into()))?;
+                let encryption = AttachmentEncryption::generate();
+                let data = encryption.encrypt(&data)?;
+                (UploadSource::Bytes(data), Some(encryption))
+            } else {
+                (source, None)
+            };
+
+            let file_size = source
+                .size()
+                .map_err(|err| UploadError::Anyhow(err.into()))?;
+
+            let mut slot = self
+                .request_upload_slot(&file_name, file_size, media_type)
+                .await?;
+
+            uploads::upload_file(
+                &slot.upload_url,
+                &slot.upload_headers,
+                &slot.media_type,
+                slot.file_size,
+                source,
+                Arc::new(progress),
+            )
+            .await?;
+
+            if let Some(encryption) = encryption {
+                slot = slot.encrypted(&encryption)?;
+            }
+
+            Ok(slot)
+        };
+
+        let upload = Abortable::new(upload, abort_registration)
+            .map(|result| result.unwrap_or(Err(UploadError::Cancelled)));
+
+        (UploadHandle::new(abort_handle), upload)
+    }
+}
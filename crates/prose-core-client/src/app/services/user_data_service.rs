@@ -10,7 +10,9 @@ use prose_proc_macros::InjectDependencies;
 use crate::app::deps::{DynEncryptionDomainService, DynUserInfoDomainService};
 use crate::domain::shared::models::{CachePolicy, UserId};
 use crate::domain::user_info::models::PlatformImage;
-use crate::dtos::{Avatar, DeviceInfo, UserMetadata, UserProfile};
+use crate::dtos::{
+    Avatar, AvatarSize, DeviceId, DeviceInfo, DeviceTrust, Fingerprint, UserMetadata, UserProfile,
+};
 
 #[derive(InjectDependencies)]
 pub struct UserDataService {
@@ -22,11 +24,30 @@ pub struct UserDataService {
 
 impl UserDataService {
     pub async fn load_avatar(&self, avatar: &Avatar) -> Result<Option<PlatformImage>> {
+        self.load_avatar_with_size(avatar, AvatarSize::Full).await
+    }
+
+    /// Loads `avatar` in the given size. Downscaled variants are generated and cached the first
+    /// time they're requested.
+    pub async fn load_avatar_with_size(
+        &self,
+        avatar: &Avatar,
+        size: AvatarSize,
+    ) -> Result<Option<PlatformImage>> {
         self.user_info_domain_service
-            .load_avatar_image(avatar)
+            .load_avatar_image(avatar, size)
             .await
     }
 
+    /// Returns the current avatar of `user_id`, if they have one.
+    pub async fn load_user_avatar(&self, user_id: &UserId) -> Result<Option<Avatar>> {
+        Ok(self
+            .user_info_domain_service
+            .get_user_info(user_id, CachePolicy::ReturnCacheDataElseLoad)
+            .await?
+            .and_then(|info| info.avatar))
+    }
+
     pub async fn load_user_profile(&self, user_id: &UserId) -> Result<Option<UserProfile>> {
         Ok(self
             .user_info_domain_service
@@ -46,4 +67,50 @@ impl UserDataService {
             .load_device_infos(user_id)
             .await
     }
+
+    pub async fn load_user_device_fingerprint(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+    ) -> Result<Option<Fingerprint>> {
+        self.encryption_domain_service
+            .load_device_fingerprint(user_id, device_id)
+            .await
+    }
+
+    pub async fn set_user_device_trust(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+        trust: DeviceTrust,
+    ) -> Result<()> {
+        self.encryption_domain_service
+            .set_device_trust(user_id, device_id, trust)
+            .await
+    }
+
+    /// Marks the device as verified if `fingerprint` matches the fingerprint of its identity key.
+    /// Returns `false` if it doesn't.
+    pub async fn verify_user_device_fingerprint(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+        fingerprint: &str,
+    ) -> Result<bool> {
+        self.encryption_domain_service
+            .verify_device_fingerprint(user_id, device_id, fingerprint)
+            .await
+    }
+
+    /// Discards the OMEMO session with the given device. A new session is started when sending
+    /// the next message.
+    pub async fn reset_user_device_session(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+    ) -> Result<()> {
+        self.encryption_domain_service
+            .reset_session(user_id, device_id)
+            .await
+    }
 }
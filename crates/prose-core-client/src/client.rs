@@ -5,22 +5,24 @@
 
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use secrecy::SecretString;
 
-use crate::app::deps::DynAppContext;
+use crate::app::deps::{DynAppContext, DynClientEventDispatcher};
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 use prose_xmpp::ConnectionError;
 
 use crate::client_builder::{
     ClientBuilder, UndefinedAvatarRepository, UndefinedEncryptionService, UndefinedStore,
 };
-use crate::domain::shared::models::UserId;
+use crate::domain::shared::models::{RoomId, UserId};
 use crate::dtos::UserResourceId;
 use crate::services::{
-    AccountService, BlockListService, CacheService, ConnectionService, ContactListService,
-    PreviewService, RoomsService, SidebarService, UploadService, UserDataService,
+    AccountService, BlockListService, CacheService, ClientEventStream, ConnectionService,
+    ContactListService, PreviewService, RoomSubscription, RoomsService, SidebarService,
+    UploadService, UserDataService,
 };
 use crate::ClientEvent;
 
@@ -54,6 +56,7 @@ pub struct ClientInner {
     pub uploads: UploadService,
     pub user_data: UserDataService,
     pub(crate) connection: ConnectionService,
+    pub(crate) client_event_dispatcher: DynClientEventDispatcher,
 }
 
 impl From<Arc<ClientInner>> for Client {
@@ -79,6 +82,12 @@ impl Client {
         self.connection.connect(id, password).await
     }
 
+    /// Serves cached data for `id` (i.e. the sidebar and messages) before the connection is
+    /// established. Cached data is replaced with live data once `connect` succeeds.
+    pub async fn load_cached_state(&self, id: &UserId) -> anyhow::Result<()> {
+        self.connection.load_cached_state(id).await
+    }
+
     pub async fn disconnect(&self) {
         self.connection.disconnect().await
     }
@@ -86,4 +95,36 @@ impl Client {
     pub fn connected_user_id(&self) -> Option<UserResourceId> {
         self.ctx.connected_id().ok()
     }
+
+    /// Returns the round-trip time of the last ping answered by the server, which can serve as
+    /// an indicator of the connection quality. `None` if no ping was answered yet.
+    pub fn connection_latency(&self) -> Option<Duration> {
+        *self.ctx.ping_round_trip_time.read()
+    }
+
+    /// Returns the number of sent messages which were not acknowledged by the server. After the
+    /// connection was lost, these messages might not have reached their recipients and will be
+    /// resent on the next connect unless the previous session can be resumed.
+    pub fn unacked_message_count(&self) -> usize {
+        self.connection.unacked_message_count()
+    }
+
+    /// Returns a stream of all events of the client. Can be used instead of or alongside the
+    /// `ClientDelegate`. The subscription ends when the stream is dropped.
+    pub fn subscribe(&self) -> ClientEventStream {
+        self.client_event_dispatcher.subscribe_to_events()
+    }
+
+    /// Returns a stream of the updates of the room with `room_id`. The subscription ends when the
+    /// stream is dropped.
+    pub fn room_events(&self, room_id: RoomId) -> RoomSubscription {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let handle = self.client_event_dispatcher.subscribe_to_room(
+            room_id,
+            Box::new(move |update| {
+                _ = tx.send(update);
+            }),
+        );
+        RoomSubscription::new(rx, handle)
+    }
 }
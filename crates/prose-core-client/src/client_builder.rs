@@ -5,7 +5,7 @@
 
 use std::sync::Arc;
 
-use prose_store::prelude::{PlatformDriver, Store};
+use prose_store::prelude::{DriverError, PlatformDriver, Store};
 use prose_xmpp::client::ConnectorProvider;
 use prose_xmpp::{ns, IDProvider, SystemTimeProvider, TimeProvider, UUIDProvider};
 
@@ -14,26 +14,30 @@ use crate::app::deps::{
     DynMessageIdProvider, DynRngProvider, DynTimeProvider, DynUserDeviceIdProvider,
 };
 use crate::app::event_handlers::{
-    BlockListEventHandler, BookmarksEventHandler, ConnectionEventHandler, ContactListEventHandler,
-    MessagesEventHandler, RequestsEventHandler, RoomsEventHandler, ServerEventHandlerQueue,
+    BlockListEventHandler, BookmarksEventHandler, ClientEventDispatcherTrait,
+    ConnectionEventHandler, ContactListEventHandler, MessagesEventHandler, OutboxEventHandler,
+    ReconnectEventHandler, RequestsEventHandler, RoomsEventHandler, ServerEventHandlerQueue,
     SyncedRoomSettingsEventHandler, UserDevicesEventHandler, UserInfoEventHandler,
 };
 use crate::app::services::{
     AccountService, ConnectionService, ContactListService, RoomsService, UserDataService,
 };
 use crate::client::ClientInner;
+use crate::domain::connection::models::ReconnectPolicy;
 use crate::domain::encryption::services::{RandUserDeviceIdProvider, UserDeviceIdProvider};
 use crate::domain::general::models::{Capabilities, Feature, SoftwareVersion};
 use crate::domain::messaging::services::{MessageIdProvider, WrappingMessageIdProvider};
+use crate::domain::settings::models::NotificationPolicy;
+use crate::domain::shared::models::RetentionPolicy;
 use crate::domain::user_info::models::PROSE_IM_NODE;
 use crate::domain::user_info::repos::AvatarRepository;
 use crate::infra::general::{NanoIDProvider, OsRngProvider, RngProvider};
-use crate::infra::platform_dependencies::PlatformDependencies;
+use crate::infra::platform_dependencies::{open_store, PlatformDependencies};
 use crate::infra::xmpp::{XMPPClient, XMPPClientBuilder};
 use crate::services::{
     BlockListService, CacheService, PreviewService, SidebarService, UploadService,
 };
-use crate::{Client, ClientDelegate};
+use crate::{Client, ClientDelegate, ClientEvent};
 
 pub struct UndefinedStore;
 pub struct UndefinedAvatarRepository;
@@ -96,6 +100,15 @@ impl<A, E> ClientBuilder<UndefinedStore, A, E> {
             message_id_provider: self.message_id_provider,
         }
     }
+
+    /// Sets a store that keeps the cache in memory only, so that the client starts every session
+    /// with an empty cache and doesn't leave any traces behind.
+    pub async fn set_in_memory_store(
+        self,
+    ) -> Result<ClientBuilder<Store<PlatformDriver>, A, E>, DriverError> {
+        let store = open_store(PlatformDriver::in_memory()).await?;
+        Ok(self.set_store(store))
+    }
 }
 
 impl<D, E> ClientBuilder<D, UndefinedAvatarRepository, E> {
@@ -198,6 +211,38 @@ impl<D, A, E> ClientBuilder<D, A, E> {
         self
     }
 
+    /// Enables the automatic reconnect after the connection to the server was lost.
+    ///
+    /// Note that `set_config` replaces the policy, so call this method afterwards.
+    pub fn set_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.app_config.reconnect_policy = Some(policy);
+        self
+    }
+
+    /// Sets the priority sent with our presence (defaults to 0).
+    ///
+    /// Note that `set_config` replaces the priority, so call this method afterwards.
+    pub fn set_presence_priority(mut self, priority: i8) -> Self {
+        self.app_config.presence_priority = priority;
+        self
+    }
+
+    /// Sets the policy that limits the size of the local cache. Nothing is deleted until the app
+    /// calls `CacheService::run_maintenance`, which applies this policy. A different policy can be
+    /// applied once via `CacheService::apply_retention_policy`.
+    pub fn set_retention_policy(mut self, policy: RetentionPolicy) -> Self {
+        self.app_config.retention_policy = Some(policy);
+        self
+    }
+
+    /// Enables `ClientRoomEventType::NotificationRequested` for received messages.
+    ///
+    /// Note that `set_config` replaces the policy, so call this method afterwards.
+    pub fn set_notification_policy(mut self, policy: NotificationPolicy) -> Self {
+        self.app_config.notification_policy = Some(policy);
+        self
+    }
+
     pub fn set_delegate(mut self, delegate: Option<Box<dyn ClientDelegate>>) -> Self {
         self.delegate = delegate;
         self
@@ -258,10 +303,13 @@ impl<A: AvatarRepository + 'static> ClientBuilder<Store<PlatformDriver>, A, DynE
         let xmpp_client = Arc::new(
             {
                 let handler_queue = server_event_handler_queue.clone();
-                self.builder.set_event_handler(move |_, event| {
-                    let handler_queue = handler_queue.clone();
-                    async move { handler_queue.handle_event(event).await }
-                })
+                self.builder
+                    .set_max_stanza_size(self.app_config.max_stanza_size)
+                    .set_stream_management_enabled(self.app_config.stream_management_enabled)
+                    .set_event_handler(move |_, event| {
+                        let handler_queue = handler_queue.clone();
+                        async move { handler_queue.handle_event(event).await }
+                    })
             }
             .build(),
         );
@@ -275,8 +323,15 @@ impl<A: AvatarRepository + 'static> ClientBuilder<Store<PlatformDriver>, A, DynE
             crate::infra::events::CoalescingClientEventDispatcher::new(self.delegate),
         );
 
+        let cache_was_reset = self.store.was_reset();
+
         let dependencies: AppDependencies = PlatformDependencies {
-            ctx: AppContext::new(capabilities, self.software_version, self.app_config),
+            ctx: AppContext::new(
+                capabilities,
+                self.software_version,
+                self.app_config,
+                self.store.schema_version(),
+            ),
             encryption_service: self.encryption_service,
             id_provider: self.id_provider,
             message_id_provider: self.message_id_provider,
@@ -293,6 +348,7 @@ impl<A: AvatarRepository + 'static> ClientBuilder<Store<PlatformDriver>, A, DynE
         .into();
 
         server_event_handler_queue.set_handlers(vec![
+            Box::new(ReconnectEventHandler::from(&dependencies)),
             Box::new(ConnectionEventHandler::from(&dependencies)),
             Box::new(RequestsEventHandler::from(&dependencies)),
             Box::new(UserInfoEventHandler::from(&dependencies)),
@@ -303,6 +359,7 @@ impl<A: AvatarRepository + 'static> ClientBuilder<Store<PlatformDriver>, A, DynE
             Box::new(BlockListEventHandler::from(&dependencies)),
             Box::new(UserDevicesEventHandler::from(&dependencies)),
             Box::new(SyncedRoomSettingsEventHandler::from(&dependencies)),
+            Box::new(OutboxEventHandler::from(&dependencies)),
         ]);
 
         let client_inner = Arc::new(ClientInner {
@@ -319,11 +376,16 @@ impl<A: AvatarRepository + 'static> ClientBuilder<Store<PlatformDriver>, A, DynE
             user_data: UserDataService::from(&dependencies),
             cache: CacheService::from(&dependencies),
             block_list: BlockListService::from(&dependencies),
+            client_event_dispatcher: dependencies.client_event_dispatcher.clone(),
         });
 
         event_dispatcher.set_client_inner(Arc::downgrade(&client_inner));
         event_dispatcher.set_room_factory(dependencies.room_factory);
 
+        if cache_was_reset {
+            event_dispatcher.dispatch_event(ClientEvent::CacheReset);
+        }
+
         Client::from(client_inner)
     }
 }
@@ -8,8 +8,10 @@ use std::fmt::{Debug, Formatter};
 use prose_xmpp::ConnectionError;
 
 use crate::app::dtos::RoomEnvelope;
+use crate::domain::encryption::models::DeviceId;
 use crate::domain::messaging::models::MessageId;
-use crate::domain::shared::models::UserId;
+use crate::domain::rooms::models::RoomRemovalReason;
+use crate::domain::shared::models::{OccupantId, ParticipantId, UserId};
 
 #[derive(Clone, PartialEq)]
 pub enum ClientEvent {
@@ -37,10 +39,23 @@ pub enum ClientEvent {
     /// Infos related to the logged-in user have changed.
     AccountInfoChanged,
 
+    /// The OMEMO devices of a user or their trust have changed.
+    UserDevicesChanged { ids: Vec<UserId> },
+
+    /// A previously unknown OMEMO device was announced by a user.
+    NewDeviceDetected {
+        user_id: UserId,
+        device_id: DeviceId,
+    },
+
     RoomChanged {
         room: RoomEnvelope,
         r#type: ClientRoomEventType,
     },
+
+    /// The cache couldn't be migrated to the current version and was started over empty. Our
+    /// OMEMO keys and unsent messages were kept, everything else is loaded from the server again.
+    CacheReset,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -60,17 +75,61 @@ pub enum ClientRoomEventType {
     /// Attributes changed like name or topic.
     AttributesChanged,
 
-    /// The list of participants has changed.
-    ParticipantsChanged,
+    /// Participants were added, updated or removed. Use `Room::participant` to look up their
+    /// current state.
+    ParticipantsChanged { ids: Vec<ParticipantId> },
 
     /// A user in `conversation` started or stopped typing.
     ComposingUsersChanged,
+
+    /// Messages were pinned or unpinned.
+    PinnedMessagesChanged,
+
+    /// The room was destroyed, either by us or by another owner. It was removed from the sidebar
+    /// and its cached messages were deleted.
+    Destroyed,
+
+    /// Our user was removed from the room, e.g. because they were kicked or banned. The room is
+    /// disconnected now.
+    RemovedFromRoom { reason: RoomRemovalReason },
+
+    /// Devices of participants that we haven't decided to trust yet were found while encrypting
+    /// a message. Depending on the `TrustPolicy` the message was either encrypted for them or
+    /// sending it failed with `EncryptionError::UntrustedDevices`. Either way, the user should be
+    /// asked to trust or distrust these devices.
+    EncryptionRecipientsChanged,
+
+    /// Our user was invited to the room by `sender`. The room is in the sidebar now and waits
+    /// for the invitation to be either accepted or declined.
+    InvitationReceived { sender: UserId },
+
+    /// One or many private messages were either received from or sent to `occupant_id`. These
+    /// messages are not part of the room's timeline.
+    PrivateMessagesAppended {
+        occupant_id: OccupantId,
+        message_ids: Vec<MessageId>,
+    },
+
+    /// Messages were received that our user should be notified about. Messages in muted rooms
+    /// and, if the room is set to `RoomNotificationMode::MentionsOnly`, messages that don't
+    /// mention our user are left out. Only dispatched if `AppConfig::notification_policy` is set.
+    NotificationRequested { message_ids: Vec<MessageId> },
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConnectionEvent {
     Connect,
-    Disconnect { error: Option<ConnectionError> },
+    Disconnect {
+        error: Option<ConnectionError>,
+    },
+    /// The connection was lost and the client is about to make the (one-based) reconnect
+    /// attempt `attempt`.
+    Reconnecting {
+        attempt: u32,
+    },
+    /// The server didn't answer a ping in time. The connection is likely degraded and will be
+    /// closed if the next ping times out as well.
+    PingTimeout,
 }
 
 impl Debug for ClientEvent {
@@ -93,11 +152,21 @@ impl Debug for ClientEvent {
                 f.debug_struct("AvatarChanged").field("ids", &ids).finish()
             }
             ClientEvent::AccountInfoChanged => f.debug_struct("AccountInfoChanged").finish(),
+            ClientEvent::UserDevicesChanged { ids } => f
+                .debug_struct("UserDevicesChanged")
+                .field("ids", &ids)
+                .finish(),
+            ClientEvent::NewDeviceDetected { user_id, device_id } => f
+                .debug_struct("NewDeviceDetected")
+                .field("user_id", &user_id)
+                .field("device_id", &device_id)
+                .finish(),
             ClientEvent::RoomChanged { room, r#type } => f
                 .debug_struct("RoomChanged")
                 .field("room", &room.to_generic_room().jid())
                 .field("type", &r#type)
                 .finish(),
+            ClientEvent::CacheReset => f.debug_struct("CacheReset").finish(),
         }
     }
 }
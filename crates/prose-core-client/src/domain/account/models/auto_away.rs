@@ -0,0 +1,94 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::domain::shared::models::Availability;
+
+/// Tracks the user's activity and decides when to automatically switch the availability after a
+/// period of inactivity.
+#[derive(Debug, Clone, Default)]
+pub struct AutoAway {
+    config: Option<Config>,
+    last_activity: Option<DateTime<Utc>>,
+    is_idle: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Config {
+    after: Duration,
+    availability: Availability,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AutoAwayTransition {
+    /// The user became idle and the availability should be set to `availability`.
+    BecameIdle {
+        availability: Availability,
+        idle_since: DateTime<Utc>,
+    },
+    /// The user became active again and the configured availability should be restored.
+    BecameActive,
+}
+
+impl AutoAway {
+    /// Enables auto-away, i.e. switches to `availability` after `after` has passed without any
+    /// reported activity. Passing `None` disables auto-away.
+    pub fn configure(
+        &mut self,
+        config: Option<(Duration, Availability)>,
+        now: DateTime<Utc>,
+    ) -> Option<AutoAwayTransition> {
+        self.config = config.map(|(after, availability)| Config {
+            after,
+            availability,
+        });
+        self.report_activity(now)
+    }
+
+    pub fn report_activity(&mut self, now: DateTime<Utc>) -> Option<AutoAwayTransition> {
+        self.last_activity = Some(now);
+
+        if !self.is_idle {
+            return None;
+        }
+
+        self.is_idle = false;
+        Some(AutoAwayTransition::BecameActive)
+    }
+
+    /// Returns the transition to apply if the user has been inactive for longer than the
+    /// configured duration. The transition is only returned once per idle period.
+    pub fn check_idle(&mut self, now: DateTime<Utc>) -> Option<AutoAwayTransition> {
+        let config = self.config?;
+        let last_activity = *self.last_activity.get_or_insert(now);
+
+        if self.is_idle || now - last_activity < config.after {
+            return None;
+        }
+
+        self.is_idle = true;
+        Some(AutoAwayTransition::BecameIdle {
+            availability: config.availability,
+            idle_since: last_activity,
+        })
+    }
+
+    /// Returns the time that has passed since the last reported activity or zero if no activity
+    /// was reported yet.
+    pub fn idle_duration(&self, now: DateTime<Utc>) -> Duration {
+        self.last_activity
+            .map(|last_activity| (now - last_activity).max(Duration::zero()))
+            .unwrap_or_else(Duration::zero)
+    }
+
+    /// Returns the availability that was set automatically, if the user is idle.
+    pub fn idle_availability(&self) -> Option<Availability> {
+        if !self.is_idle {
+            return None;
+        }
+        self.config.map(|config| config.availability)
+    }
+}
@@ -0,0 +1,8 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+pub use auto_away::{AutoAway, AutoAwayTransition};
+
+mod auto_away;
@@ -0,0 +1,53 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::app::deps::{DynAppContext, DynConnectedRoomsReadOnlyRepository, DynUserAccountService};
+use crate::domain::shared::models::{AccountId, Availability};
+
+/// Sends a presence with `availability` to the server and to every connected room, so that our
+/// occupants show the same availability as the account itself.
+pub async fn send_availability(
+    ctx: &DynAppContext,
+    user_account_service: &DynUserAccountService,
+    connected_rooms_repo: &DynConnectedRoomsReadOnlyRepository,
+    account: &AccountId,
+    availability: Availability,
+    status_message: Option<String>,
+    idle_since: Option<DateTime<Utc>>,
+) -> Result<()> {
+    let priority = ctx.config.presence_priority;
+
+    user_account_service
+        .set_availability(
+            None,
+            &ctx.capabilities,
+            availability,
+            status_message.clone(),
+            priority,
+            idle_since,
+        )
+        .await?;
+
+    for room in connected_rooms_repo.get_all(account) {
+        let Some(occupant_id) = room.occupant_id() else {
+            continue;
+        };
+        user_account_service
+            .set_availability(
+                Some(occupant_id),
+                &ctx.capabilities,
+                availability,
+                status_message.clone(),
+                priority,
+                idle_since,
+            )
+            .await?
+    }
+
+    Ok(())
+}
@@ -3,8 +3,10 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+pub use availability::send_availability;
 pub use user_account_service::{UserAccountService, UserProfileFormat};
 
+mod availability;
 mod user_account_service;
 
 #[cfg(feature = "test")]
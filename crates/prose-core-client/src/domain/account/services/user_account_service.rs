@@ -5,6 +5,7 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
@@ -25,12 +26,16 @@ pub enum UserProfileFormat {
 pub trait UserAccountService: SendUnlessWasm + SyncUnlessWasm {
     async fn set_avatar_metadata(&self, metadata: &AvatarMetadata) -> Result<()>;
     async fn set_avatar_image(&self, checksum: &AvatarId, base64_image_data: String) -> Result<()>;
+    async fn delete_avatar_metadata(&self) -> Result<()>;
 
     async fn set_availability(
         &self,
         occupant_id: Option<OccupantId>,
         capabilities: &Capabilities,
         availability: Availability,
+        status_message: Option<String>,
+        priority: i8,
+        idle_since: Option<DateTime<Utc>>,
     ) -> Result<()>;
 
     async fn set_user_activity(&self, user_activity: Option<&UserStatus>) -> Result<()>;
@@ -4,7 +4,9 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 pub use connection_properties::ConnectionProperties;
+pub use reconnect_policy::{ReconnectPolicy, ReconnectState};
 pub use server_features::{HttpUploadService, ServerFeatures};
 
 mod connection_properties;
+mod reconnect_policy;
 mod server_features;
@@ -0,0 +1,193 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::time::Duration;
+
+use secrecy::SecretString;
+
+use prose_xmpp::ConnectionError;
+
+use crate::domain::shared::models::UserId;
+
+/// Configures how the client reconnects after the connection to the server was lost.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    /// The delay before the first reconnect attempt. The delay doubles with every failed attempt.
+    pub base_delay: Duration,
+    /// The maximum delay between two reconnect attempts.
+    pub max_delay: Duration,
+    /// The number of reconnect attempts after which the client gives up. `None` to retry forever.
+    pub max_attempts: Option<u32>,
+    /// The fraction (0.0 to 1.0) of each delay that is randomized, so that clients which lost
+    /// their connection at the same time don't all reconnect at once.
+    pub jitter: f64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_attempts: Some(10),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns the delay before the (zero-based) reconnect attempt `attempt` or `None` if the
+    /// client should give up.
+    pub fn delay_for_attempt(&self, attempt: u32) -> Option<Duration> {
+        if self
+            .max_attempts
+            .is_some_and(|max_attempts| attempt >= max_attempts)
+        {
+            return None;
+        }
+
+        let factor = 2u32.checked_pow(attempt).unwrap_or(u32::MAX);
+        Some(
+            self.base_delay
+                .checked_mul(factor)
+                .unwrap_or(self.max_delay)
+                .min(self.max_delay),
+        )
+    }
+
+    /// Returns the delay before the reconnect attempt `attempt` after the connection was lost
+    /// with `error` or `None` if the client should not try to reconnect.
+    pub fn delay_after_error(
+        &self,
+        attempt: u32,
+        error: Option<&ConnectionError>,
+    ) -> Option<Duration> {
+        let min_delay = match error {
+            Some(ConnectionError::InvalidCredentials | ConnectionError::Forbidden { .. }) => {
+                return None
+            }
+            Some(ConnectionError::StreamError { reason }) => reason.reconnect_delay()?,
+            _ => Duration::ZERO,
+        };
+        self.delay_for_attempt(attempt)
+            .map(|delay| delay.max(min_delay))
+    }
+
+    /// Shortens `delay` by up to `jitter` of its length. `random` is expected to be in the
+    /// range 0.0..1.0.
+    pub fn apply_jitter(&self, delay: Duration, random: f64) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0) * random.clamp(0.0, 1.0);
+        delay.mul_f64(1.0 - jitter)
+    }
+}
+
+/// Book-keeping for the automatic reconnect.
+#[derive(Debug, Default)]
+pub struct ReconnectState {
+    /// The credentials of the last successful connection. Cleared when the client is
+    /// disconnected deliberately.
+    pub credentials: Option<(UserId, SecretString)>,
+    /// Whether a reconnect is currently in progress.
+    pub is_reconnecting: bool,
+    /// Incremented whenever pending reconnect attempts should be abandoned.
+    pub generation: u64,
+}
+
+impl ReconnectState {
+    /// Forgets the stored credentials and abandons all pending reconnect attempts.
+    pub fn cancel(&mut self) {
+        self.credentials = None;
+        self.is_reconnecting = false;
+        self.generation += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use prose_xmpp::DisconnectReason;
+
+    use super::*;
+
+    #[test]
+    fn test_delay_for_attempt() {
+        let policy = ReconnectPolicy {
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(10),
+            max_attempts: Some(4),
+            jitter: 0.0,
+        };
+
+        assert_eq!(policy.delay_for_attempt(0), Some(Duration::from_secs(2)));
+        assert_eq!(policy.delay_for_attempt(1), Some(Duration::from_secs(4)));
+        assert_eq!(policy.delay_for_attempt(2), Some(Duration::from_secs(8)));
+        assert_eq!(policy.delay_for_attempt(3), Some(Duration::from_secs(10)));
+        assert_eq!(policy.delay_for_attempt(4), None);
+    }
+
+    #[test]
+    fn test_delay_for_attempt_does_not_overflow() {
+        let policy = ReconnectPolicy {
+            max_attempts: None,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay_for_attempt(100), Some(policy.max_delay));
+    }
+
+    #[test]
+    fn test_apply_jitter() {
+        let policy = ReconnectPolicy {
+            jitter: 0.5,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            policy.apply_jitter(Duration::from_secs(10), 0.0),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            policy.apply_jitter(Duration::from_secs(10), 0.5),
+            Duration::from_millis(7500)
+        );
+        assert_eq!(
+            policy.apply_jitter(Duration::from_secs(10), 1.0),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn test_delay_after_error() {
+        let policy = ReconnectPolicy::default();
+
+        assert_eq!(
+            policy.delay_after_error(0, Some(&ConnectionError::InvalidCredentials)),
+            None
+        );
+        assert_eq!(
+            policy.delay_after_error(
+                0,
+                Some(&ConnectionError::StreamError {
+                    reason: DisconnectReason::Conflict
+                })
+            ),
+            None
+        );
+        assert_eq!(
+            policy.delay_after_error(
+                0,
+                Some(&ConnectionError::StreamError {
+                    reason: DisconnectReason::SystemShutdown
+                })
+            ),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            policy.delay_after_error(0, Some(&ConnectionError::TimedOut)),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            policy.delay_after_error(0, None),
+            Some(Duration::from_secs(1))
+        );
+    }
+}
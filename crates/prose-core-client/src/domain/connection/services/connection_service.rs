@@ -3,14 +3,17 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::HashSet;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use secrecy::SecretString;
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
-use prose_xmpp::ConnectionError;
+use prose_xmpp::{ConnectionError, SessionKind};
 
 use crate::domain::connection::models::ServerFeatures;
+use crate::domain::messaging::models::MessageId;
 use crate::domain::shared::models::UserResourceId;
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -21,9 +24,19 @@ pub trait ConnectionService: SendUnlessWasm + SyncUnlessWasm {
         &self,
         jid: &UserResourceId,
         password: SecretString,
-    ) -> Result<(), ConnectionError>;
+    ) -> Result<SessionKind, ConnectionError>;
     async fn disconnect(&self);
 
+    /// Resends the message stanzas that were never acknowledged by the server before the
+    /// previous session was lost. They keep their original ids, so that they're not
+    /// duplicated when they're reflected back to us. Messages with an id in `skipped_ids` are
+    /// dropped instead, since they're resent from the outbox.
+    async fn resend_unacknowledged_messages(&self, skipped_ids: &HashSet<MessageId>) -> Result<()>;
+
+    /// Returns the number of sent messages which were not acknowledged by the server (yet).
+    /// Requires Stream Management (XEP-0198) to be enabled, otherwise returns 0.
+    fn unacked_message_count(&self) -> usize;
+
     async fn set_message_carbons_enabled(&self, is_enabled: bool) -> Result<()>;
     async fn load_server_features(&self) -> Result<ServerFeatures>;
 }
@@ -3,16 +3,20 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use serde::{Deserialize, Serialize};
+
 use crate::domain::shared::models::UserId;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Contact {
     pub id: UserId,
     pub name: Option<String>,
+    /// The roster groups the contact is assigned to.
+    pub groups: Vec<String>,
     pub presence_subscription: PresenceSubscription,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum PresenceSubscription {
     // We have requested to subscribe to the contact's presence, but they haven't approved yet.
     Requested,
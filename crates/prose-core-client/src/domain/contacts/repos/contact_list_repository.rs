@@ -5,10 +5,11 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
-use crate::domain::contacts::models::{Contact, PresenceSubscription};
+use crate::domain::contacts::models::Contact;
 use crate::domain::shared::models::{AccountId, UserId};
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -16,14 +17,26 @@ use crate::domain::shared::models::{AccountId, UserId};
 #[cfg_attr(feature = "test", mockall::automock)]
 pub trait ContactListRepository: SendUnlessWasm + SyncUnlessWasm {
     async fn get_all(&self, account: &AccountId) -> Result<Vec<Contact>>;
+    async fn get(&self, account: &AccountId, contact_id: &UserId) -> Result<Option<Contact>>;
 
-    async fn set(
+    /// Inserts or replaces `contact`. Returns `true` if the contact list changed.
+    async fn set(&self, account: &AccountId, contact: Contact) -> Result<bool>;
+    async fn delete(&self, account: &AccountId, contact_id: &UserId) -> Result<bool>;
+
+    /// Returns when `contact_id` was last seen (see `set_last_seen`).
+    async fn get_last_seen(
         &self,
         account: &AccountId,
         contact_id: &UserId,
-        subscription: PresenceSubscription,
-    ) -> Result<bool>;
-    async fn delete(&self, account: &AccountId, contact_id: &UserId) -> Result<bool>;
+    ) -> Result<Option<DateTime<Utc>>>;
+    /// Records when `contact_id` was last seen. The timestamp is kept when the roster is
+    /// reloaded. Does nothing if `contact_id` is not in the roster.
+    async fn set_last_seen(
+        &self,
+        account: &AccountId,
+        contact_id: &UserId,
+        last_seen: DateTime<Utc>,
+    ) -> Result<()>;
 
     async fn reset_before_reconnect(&self, account: &AccountId) -> Result<()>;
     async fn clear_cache(&self, account: &AccountId) -> Result<()>;
@@ -15,6 +15,8 @@ use crate::dtos::UserId;
 #[cfg_attr(feature = "test", mockall::automock)]
 pub trait BlockListDomainService: SendUnlessWasm + SyncUnlessWasm {
     async fn load_block_list(&self) -> Result<Vec<UserId>>;
+    /// Returns `true` if messages and presences from `user_id` should be ignored.
+    async fn is_user_blocked(&self, user_id: &UserId) -> Result<bool>;
     async fn block_user(&self, user_id: &UserId) -> Result<()>;
     async fn unblock_user(&self, user_id: &UserId) -> Result<()>;
     async fn clear_block_list(&self) -> Result<()>;
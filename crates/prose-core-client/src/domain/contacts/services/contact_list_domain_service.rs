@@ -5,10 +5,11 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
-use crate::domain::contacts::models::{Contact, PresenceSubRequest, PresenceSubscription};
+use crate::domain::contacts::models::{Contact, PresenceSubRequest};
 use crate::domain::shared::models::UserId;
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -16,29 +17,53 @@ use crate::domain::shared::models::UserId;
 #[cfg_attr(feature = "test", mockall::automock)]
 pub trait ContactListDomainService: SendUnlessWasm + SyncUnlessWasm {
     async fn load_contacts(&self) -> Result<Vec<Contact>>;
-    async fn add_contact(&self, user_id: &UserId) -> Result<()>;
+    /// Returns the contact `user_id` or `None` if they're not in the roster.
+    async fn load_contact(&self, user_id: &UserId) -> Result<Option<Contact>>;
+
+    /// Returns when the contact `user_id` was last seen or `None` if that is unknown.
+    async fn load_last_seen(&self, user_id: &UserId) -> Result<Option<DateTime<Utc>>>;
+    /// Remembers when the contact `user_id` was last seen. Does nothing if they're not in the
+    /// roster.
+    async fn set_last_seen(&self, user_id: &UserId, last_seen: DateTime<Utc>) -> Result<()>;
+
+    /// Adds `user_id` to the roster and optionally requests a presence subscription from them.
+    /// If they're in the roster already, `name` and non-empty `groups` are merged into the
+    /// existing entry. The cached contact list is updated optimistically. Returns the contact.
+    async fn add_contact(
+        &self,
+        user_id: &UserId,
+        name: Option<&str>,
+        groups: &[String],
+        request_presence_sub: bool,
+    ) -> Result<Contact>;
     async fn remove_contact(&self, user_id: &UserId) -> Result<()>;
 
+    /// Sets the name of a contact in the roster. Returns the updated contact.
+    async fn rename_contact(&self, user_id: &UserId, name: Option<&str>) -> Result<Contact>;
+    /// Replaces the roster groups of a contact. Returns the updated contact.
+    async fn set_contact_groups(&self, user_id: &UserId, groups: &[String]) -> Result<Contact>;
+
     /// Requests a presence subscription from `from`. Note that happens automatically when you
     /// call `add_contact`. This method can be useful though when our user needs to re-request
     /// the presence subscription in case the contact hasn't reacted in a while.
     async fn request_presence_sub(&self, from: &UserId) -> Result<()>;
 
     async fn load_presence_sub_requests(&self) -> Result<Vec<PresenceSubRequest>>;
-    async fn approve_presence_sub_request(&self, from: &UserId) -> Result<()>;
+    /// Approves the presence subscription request of `from` and adds them to the contact list.
+    /// If `subscribe_back` is true, a presence subscription is requested from `from` as well, so
+    /// that the subscription becomes mutual once they approve it.
+    async fn approve_presence_sub_request(&self, from: &UserId, subscribe_back: bool)
+        -> Result<()>;
     async fn deny_presence_sub_request(&self, from: &UserId) -> Result<()>;
 
-    async fn handle_updated_contact(
-        &self,
-        user_id: &UserId,
-        subscription: PresenceSubscription,
-    ) -> Result<()>;
+    async fn handle_updated_contact(&self, contact: Contact) -> Result<()>;
     async fn handle_removed_contact(&self, user_id: &UserId) -> Result<()>;
     async fn handle_presence_sub_request(
         &self,
         from: &UserId,
         nickname: Option<String>,
     ) -> Result<()>;
+    async fn handle_presence_sub_request_withdrawn(&self, from: &UserId) -> Result<()>;
 
     async fn reset_before_reconnect(&self) -> Result<()>;
     async fn clear_cache(&self) -> Result<()>;
@@ -16,7 +16,13 @@ use crate::dtos::UserId;
 #[cfg_attr(feature = "test", mockall::automock)]
 pub trait ContactListService: SendUnlessWasm + SyncUnlessWasm {
     async fn load_contacts(&self) -> Result<Vec<Contact>>;
-    async fn add_contact(&self, user_id: &UserId) -> Result<()>;
+    /// Adds `user_id` to the roster or updates the name and groups of an existing roster item.
+    async fn add_or_update_contact(
+        &self,
+        user_id: &UserId,
+        name: Option<&str>,
+        groups: &[String],
+    ) -> Result<()>;
     async fn remove_contact(&self, user_id: &UserId) -> Result<()>;
 
     async fn subscribe_to_presence(&self, user_id: &UserId) -> Result<()>;
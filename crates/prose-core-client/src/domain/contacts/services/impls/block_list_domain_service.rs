@@ -33,6 +33,12 @@ impl BlockListDomainServiceTrait for BlockListDomainService {
             .await
     }
 
+    async fn is_user_blocked(&self, user_id: &UserId) -> Result<bool> {
+        self.block_list_repo
+            .contains(&self.ctx.connected_account()?, user_id)
+            .await
+    }
+
     async fn block_user(&self, user_id: &UserId) -> Result<()> {
         self.block_list_service.block_user(user_id).await?;
 
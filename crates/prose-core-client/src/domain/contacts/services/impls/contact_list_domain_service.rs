@@ -3,8 +3,11 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use anyhow::Result;
+use std::future::Future;
+
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use prose_proc_macros::DependenciesStruct;
 
@@ -13,6 +16,7 @@ use crate::app::deps::{
     DynPresenceSubRequestsRepository,
 };
 use crate::domain::contacts::models::{Contact, PresenceSubRequest, PresenceSubscription};
+use crate::domain::shared::models::AccountId;
 use crate::dtos::UserId;
 use crate::ClientEvent;
 
@@ -31,54 +35,111 @@ pub struct ContactListDomainService {
 #[async_trait]
 impl ContactListDomainServiceTrait for ContactListDomainService {
     async fn load_contacts(&self) -> Result<Vec<Contact>> {
+        self.contact_list_repo.get_all(&self.ctx.account()?).await
+    }
+
+    async fn load_contact(&self, user_id: &UserId) -> Result<Option<Contact>> {
         self.contact_list_repo
-            .get_all(&self.ctx.connected_account()?)
+            .get(&self.ctx.account()?, user_id)
             .await
     }
 
-    async fn add_contact(&self, user_id: &UserId) -> Result<()> {
-        self.contact_list_service.add_contact(user_id).await?;
-        self.contact_list_service
-            .preapprove_subscription_request(user_id)
-            .await?;
-        self.contact_list_service
-            .subscribe_to_presence(user_id)
-            .await?;
+    async fn load_last_seen(&self, user_id: &UserId) -> Result<Option<DateTime<Utc>>> {
+        self.contact_list_repo
+            .get_last_seen(&self.ctx.account()?, user_id)
+            .await
+    }
 
-        if self
-            .contact_list_repo
-            .set(
-                &self.ctx.connected_account()?,
-                user_id,
-                PresenceSubscription::Requested,
-            )
-            .await?
-        {
-            self.client_event_dispatcher
-                .dispatch_event(ClientEvent::ContactListChanged);
-        }
+    async fn set_last_seen(&self, user_id: &UserId, last_seen: DateTime<Utc>) -> Result<()> {
+        self.contact_list_repo
+            .set_last_seen(&self.ctx.account()?, user_id, last_seen)
+            .await
+    }
 
-        Ok(())
+    async fn add_contact(
+        &self,
+        user_id: &UserId,
+        name: Option<&str>,
+        groups: &[String],
+        request_presence_sub: bool,
+    ) -> Result<Contact> {
+        let previous_contact = self
+            .contact_list_repo
+            .get(&self.ctx.connected_account()?, user_id)
+            .await?;
+        self.merge_contact(
+            previous_contact,
+            user_id,
+            name,
+            groups,
+            request_presence_sub,
+        )
+        .await
     }
 
     async fn remove_contact(&self, user_id: &UserId) -> Result<()> {
-        self.contact_list_service.remove_contact(user_id).await?;
-        self.contact_list_service
-            .revoke_presence_subscription(user_id)
-            .await?;
+        let account = self.ctx.connected_account()?;
+        let previous_contact = self.contact_list_repo.get(&account, user_id).await?;
 
-        if self
-            .contact_list_repo
-            .delete(&self.ctx.connected_account()?, user_id)
-            .await?
-        {
+        if self.contact_list_repo.delete(&account, user_id).await? {
             self.client_event_dispatcher
                 .dispatch_event(ClientEvent::ContactListChanged);
         }
 
+        if let Err(err) = self.contact_list_service.remove_contact(user_id).await {
+            if let Some(contact) = previous_contact {
+                self.save_contact(&account, None, contact).await?;
+            }
+            return Err(err);
+        }
+
+        // The server cancels the subscription in both directions when the roster item is removed
+        // (RFC 6121 §3.3). Drop a pending request, so that the contact doesn't show up as a
+        // pending request after being removed.
+        self.delete_presence_sub_request(&account, user_id).await?;
+
         Ok(())
     }
 
+    async fn rename_contact(&self, user_id: &UserId, name: Option<&str>) -> Result<Contact> {
+        let previous_contact = self.get_contact(user_id).await?;
+        let contact = Contact {
+            name: name.map(ToString::to_string),
+            ..previous_contact.clone()
+        };
+
+        self.update_contact_optimistically(
+            Some(previous_contact),
+            contact.clone(),
+            self.contact_list_service
+                .add_or_update_contact(user_id, name, &contact.groups),
+        )
+        .await?;
+
+        Ok(contact)
+    }
+
+    async fn set_contact_groups(&self, user_id: &UserId, groups: &[String]) -> Result<Contact> {
+        let previous_contact = self.get_contact(user_id).await?;
+        let contact = Contact {
+            groups: groups.to_vec(),
+            ..previous_contact.clone()
+        };
+
+        self.update_contact_optimistically(
+            Some(previous_contact),
+            contact.clone(),
+            self.contact_list_service.add_or_update_contact(
+                user_id,
+                contact.name.as_deref(),
+                groups,
+            ),
+        )
+        .await?;
+
+        Ok(contact)
+    }
+
     async fn request_presence_sub(&self, from: &UserId) -> Result<()> {
         self.contact_list_service
             .subscribe_to_presence(from)
@@ -92,21 +153,37 @@ impl ContactListDomainServiceTrait for ContactListDomainService {
             .await
     }
 
-    async fn approve_presence_sub_request(&self, from: &UserId) -> Result<()> {
+    async fn approve_presence_sub_request(
+        &self,
+        from: &UserId,
+        subscribe_back: bool,
+    ) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
         self.contact_list_service
             .approve_presence_sub_request(from)
             .await?;
 
-        if self
+        let request = self
             .presence_sub_requests_repo
-            .delete(&self.ctx.connected_account()?, from)
+            .get_all(&account)
             .await?
-        {
-            self.client_event_dispatcher
-                .dispatch_event(ClientEvent::PresenceSubRequestsChanged);
-        }
+            .into_iter()
+            .find(|request| &request.user_id == from);
+        self.delete_presence_sub_request(&account, from).await?;
 
-        self.add_contact(from).await?;
+        // The server adds the contact to our roster when we approve their request. We'll only
+        // add them explicitly if we want to subscribe back, so that we can store their nickname.
+        if subscribe_back {
+            let previous_contact = self.contact_list_repo.get(&account, from).await?;
+            // A name we gave the contact before takes precedence over the nickname they sent.
+            let name = previous_contact
+                .as_ref()
+                .and_then(|contact| contact.name.clone())
+                .or_else(|| request.and_then(|request| request.name));
+            self.merge_contact(previous_contact, from, name.as_deref(), &[], true)
+                .await?;
+        }
 
         Ok(())
     }
@@ -115,33 +192,16 @@ impl ContactListDomainServiceTrait for ContactListDomainService {
         self.contact_list_service
             .deny_presence_sub_request(from)
             .await?;
-
-        if self
-            .presence_sub_requests_repo
-            .delete(&self.ctx.connected_account()?, from)
-            .await?
-        {
-            self.client_event_dispatcher
-                .dispatch_event(ClientEvent::PresenceSubRequestsChanged);
-        }
-
+        self.delete_presence_sub_request(&self.ctx.connected_account()?, from)
+            .await?;
         Ok(())
     }
 
-    async fn handle_updated_contact(
-        &self,
-        user_id: &UserId,
-        subscription: PresenceSubscription,
-    ) -> Result<()> {
-        if self
-            .contact_list_repo
-            .set(&self.ctx.connected_account()?, user_id, subscription)
-            .await?
-        {
-            self.client_event_dispatcher
-                .dispatch_event(ClientEvent::ContactListChanged);
-        }
-        Ok(())
+    async fn handle_updated_contact(&self, contact: Contact) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+        let previous_contact = self.contact_list_repo.get(&account, &contact.id).await?;
+        self.save_contact(&account, previous_contact.as_ref(), contact)
+            .await
     }
 
     async fn handle_removed_contact(&self, user_id: &UserId) -> Result<()> {
@@ -178,6 +238,11 @@ impl ContactListDomainServiceTrait for ContactListDomainService {
         Ok(())
     }
 
+    async fn handle_presence_sub_request_withdrawn(&self, from: &UserId) -> Result<()> {
+        self.delete_presence_sub_request(&self.ctx.connected_account()?, from)
+            .await
+    }
+
     async fn reset_before_reconnect(&self) -> Result<()> {
         let account = self.ctx.connected_account()?;
         self.contact_list_repo
@@ -195,3 +260,147 @@ impl ContactListDomainServiceTrait for ContactListDomainService {
         Ok(())
     }
 }
+
+impl ContactListDomainService {
+    async fn delete_presence_sub_request(&self, account: &AccountId, from: &UserId) -> Result<()> {
+        if self
+            .presence_sub_requests_repo
+            .delete(account, from)
+            .await?
+        {
+            self.client_event_dispatcher
+                .dispatch_event(ClientEvent::PresenceSubRequestsChanged);
+        }
+        Ok(())
+    }
+
+    async fn get_contact(&self, user_id: &UserId) -> Result<Contact> {
+        self.contact_list_repo
+            .get(&self.ctx.connected_account()?, user_id)
+            .await?
+            .ok_or_else(|| anyhow!("{user_id} is not in the contact list."))
+    }
+
+    /// Adds the contact to the roster or merges the given attributes into `previous_contact`.
+    /// The name is kept if `name` is `None`, the groups if `groups` is empty and an existing
+    /// presence subscription is never downgraded.
+    async fn merge_contact(
+        &self,
+        previous_contact: Option<Contact>,
+        user_id: &UserId,
+        name: Option<&str>,
+        groups: &[String],
+        request_presence_sub: bool,
+    ) -> Result<Contact> {
+        let contact = match previous_contact.clone() {
+            Some(previous_contact) => Contact {
+                name: name.map(ToString::to_string).or(previous_contact.name),
+                groups: if groups.is_empty() {
+                    previous_contact.groups
+                } else {
+                    groups.to_vec()
+                },
+                presence_subscription: match previous_contact.presence_subscription {
+                    PresenceSubscription::None if request_presence_sub => {
+                        PresenceSubscription::Requested
+                    }
+                    subscription => subscription,
+                },
+                ..previous_contact
+            },
+            None => Contact {
+                id: user_id.clone(),
+                name: name.map(ToString::to_string),
+                groups: groups.to_vec(),
+                presence_subscription: if request_presence_sub {
+                    PresenceSubscription::Requested
+                } else {
+                    PresenceSubscription::None
+                },
+            },
+        };
+
+        self.update_contact_optimistically(
+            previous_contact,
+            contact.clone(),
+            self.contact_list_service.add_or_update_contact(
+                user_id,
+                contact.name.as_deref(),
+                &contact.groups,
+            ),
+        )
+        .await?;
+
+        if request_presence_sub {
+            self.contact_list_service
+                .preapprove_subscription_request(user_id)
+                .await?;
+            self.contact_list_service
+                .subscribe_to_presence(user_id)
+                .await?;
+        }
+
+        Ok(contact)
+    }
+
+    /// Saves `contact` to the cache before `request` is sent to the server, so that the change
+    /// is visible immediately. If `request` fails, `previous_contact` is restored.
+    async fn update_contact_optimistically(
+        &self,
+        previous_contact: Option<Contact>,
+        contact: Contact,
+        request: impl Future<Output = Result<()>>,
+    ) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        self.save_contact(&account, previous_contact.as_ref(), contact.clone())
+            .await?;
+
+        let Err(err) = request.await else {
+            return Ok(());
+        };
+
+        match previous_contact {
+            Some(previous_contact) => {
+                self.save_contact(&account, Some(&contact), previous_contact)
+                    .await?
+            }
+            None => {
+                if self.contact_list_repo.delete(&account, &contact.id).await? {
+                    self.client_event_dispatcher
+                        .dispatch_event(ClientEvent::ContactListChanged);
+                }
+            }
+        }
+
+        Err(err)
+    }
+
+    /// Saves `contact` and dispatches `ContactListChanged` if the contact was added or its
+    /// presence subscription changed, `ContactChanged` if only its name or groups changed.
+    async fn save_contact(
+        &self,
+        account: &AccountId,
+        previous_contact: Option<&Contact>,
+        contact: Contact,
+    ) -> Result<()> {
+        let is_contact_list_change = previous_contact.map(|c| c.presence_subscription)
+            != Some(contact.presence_subscription);
+        let contact_id = contact.id.clone();
+
+        if !self.contact_list_repo.set(account, contact).await? {
+            return Ok(());
+        }
+
+        self.client_event_dispatcher
+            .dispatch_event(if is_contact_list_change {
+                ClientEvent::ContactListChanged
+            } else {
+                ClientEvent::ContactChanged {
+                    ids: vec![contact_id],
+                }
+            });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,15 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use super::{DeviceId, Trust};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecryptedMessage {
+    pub message: String,
+    pub sender_device_id: DeviceId,
+    /// The trust in the sending device at the time of decryption. `None` if the trust is
+    /// unknown, e.g. because the message was looked up from the cache.
+    pub trust: Option<Trust>,
+}
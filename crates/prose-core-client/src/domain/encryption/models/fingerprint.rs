@@ -0,0 +1,122 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::fmt::{Display, Formatter};
+
+use itertools::Itertools;
+
+use crate::domain::shared::models::UserId;
+
+use super::{DeviceId, IdentityKey};
+
+/// The fingerprint of a device's identity key, used to verify a device out-of-band.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint(Box<[u8]>);
+
+impl Fingerprint {
+    /// Returns the fingerprint as groups of 8 lowercase hex characters. The leading key type
+    /// byte is omitted, so that the groups match what other OMEMO clients display.
+    pub fn hex_groups(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .skip(1)
+            .map(|b| format!("{:02x}", b))
+            .chunks(4)
+            .into_iter()
+            .map(|word| word.collect::<String>())
+            .collect()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns an `xmpp:` URI that can be encoded as QR code to verify the device with
+    /// `device_id` of `user_id`, in the format that Conversations and other OMEMO clients scan.
+    pub fn verification_uri(&self, user_id: &UserId, device_id: &DeviceId) -> String {
+        format!(
+            "xmpp:{}@{}?omemo-sid-{device_id}={}",
+            escape_uri_component(user_id.username(), b"!$()*+,;="),
+            escape_uri_component(&user_id.as_ref().domain().to_string(), b"!$&'()*+,;="),
+            self.hex_groups().concat()
+        )
+    }
+}
+
+/// Percent-encodes every byte of `value` except for unreserved characters and `allowed`, as
+/// required for the node and domain of an `xmpp:` URI (RFC 5122, section 2.2).
+fn escape_uri_component(value: &str, allowed: &[u8]) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) || allowed.contains(&byte) {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    escaped
+}
+
+impl From<IdentityKey> for Fingerprint {
+    fn from(value: IdentityKey) -> Self {
+        Self(value.into_inner())
+    }
+}
+
+impl From<&IdentityKey> for Fingerprint {
+    fn from(value: &IdentityKey) -> Self {
+        Self(value.as_ref().into())
+    }
+}
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.hex_groups().join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::user_id;
+
+    use super::*;
+
+    fn identity_key() -> IdentityKey {
+        let mut bytes = vec![0x05];
+        bytes.extend(0u8..32);
+        IdentityKey::from(bytes.as_slice())
+    }
+
+    #[test]
+    fn test_formats_fingerprint() {
+        let fingerprint = Fingerprint::from(identity_key());
+
+        assert_eq!(
+            fingerprint.to_string(),
+            "00010203 04050607 08090a0b 0c0d0e0f 10111213 14151617 18191a1b 1c1d1e1f"
+        );
+        assert_eq!(fingerprint.to_string(), identity_key().fingerprint());
+    }
+
+    #[test]
+    fn test_verification_uri() {
+        let fingerprint = Fingerprint::from(identity_key());
+
+        assert_eq!(
+            fingerprint.verification_uri(&user_id!("user@prose.org"), &DeviceId::from(12345)),
+            "xmpp:user@prose.org?omemo-sid-12345=000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+        );
+    }
+
+    #[test]
+    fn test_verification_uri_escapes_jid() {
+        let fingerprint = Fingerprint::from(identity_key());
+
+        assert_eq!(
+            fingerprint.verification_uri(&user_id!("jürgen#1+x@prose.org"), &DeviceId::from(1)),
+            "xmpp:j%C3%BCrgen%231+x@prose.org?omemo-sid-1=000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+        );
+    }
+}
@@ -3,22 +3,28 @@
 // Copyright: 2024, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+pub use decrypted_message::DecryptedMessage;
 pub use decryption_context::{DecryptionContext, DecryptionContextInner};
 pub use device::{Device, DeviceList};
 pub use device_bundle::{DeviceBundle, PreKeyBundle};
 pub use device_id::DeviceId;
 pub use device_info::DeviceInfo;
+pub use fingerprint::Fingerprint;
 pub use keys::*;
 pub use local_device::LocalDevice;
 pub use local_encryption_bundle::LocalEncryptionBundle;
 pub use session::{Session, Trust};
+pub use trust_policy::TrustPolicy;
 
+mod decrypted_message;
 mod decryption_context;
 mod device;
 mod device_bundle;
 mod device_id;
 mod device_info;
+mod fingerprint;
 mod keys;
 mod local_device;
 mod local_encryption_bundle;
 mod session;
+mod trust_policy;
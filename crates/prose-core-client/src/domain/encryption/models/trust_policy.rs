@@ -0,0 +1,35 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use super::{Session, Trust};
+
+/// Decides which devices of a recipient a message gets encrypted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrustPolicy {
+    /// Only encrypt for devices that were explicitly trusted or verified.
+    EncryptToTrustedOnly,
+    /// Encrypt for trusted devices and for devices we haven't made a decision about yet.
+    #[default]
+    EncryptToUndecided,
+    /// Like `EncryptToUndecided`, but undecided devices are marked as trusted before encrypting.
+    BlindTrust,
+}
+
+impl TrustPolicy {
+    pub fn allows(&self, session: &Session) -> bool {
+        match self {
+            Self::EncryptToTrustedOnly => session.is_trusted(),
+            Self::EncryptToUndecided | Self::BlindTrust => session.is_trusted_or_undecided(),
+        }
+    }
+
+    /// Returns the trust that should be stored for `session` before encrypting, if it changes.
+    pub fn trust_to_apply(&self, session: &Session) -> Option<Trust> {
+        match self {
+            Self::BlindTrust if session.trust == Trust::Undecided => Some(Trust::Trusted),
+            _ => None,
+        }
+    }
+}
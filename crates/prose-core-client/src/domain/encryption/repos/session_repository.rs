@@ -8,8 +8,8 @@ use async_trait::async_trait;
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
-use crate::domain::encryption::models::{DeviceId, Session};
-use crate::domain::shared::models::{AccountId, UserId};
+use crate::domain::encryption::models::{DeviceId, Session, Trust};
+use crate::domain::shared::models::{AccountId, StoreUsage, UserId};
 use crate::dtos::{IdentityKey, SessionData};
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -45,6 +45,14 @@ pub trait SessionRepository: SendUnlessWasm + SyncUnlessWasm {
         identity: IdentityKey,
     ) -> Result<bool>;
 
+    async fn put_trust(
+        &self,
+        account: &AccountId,
+        user_id: &UserId,
+        device_id: &DeviceId,
+        trust: Trust,
+    ) -> Result<()>;
+
     /// Marks all sessions not included in `device_ids` as inactive.
     async fn put_active_devices(
         &self,
@@ -53,5 +61,15 @@ pub trait SessionRepository: SendUnlessWasm + SyncUnlessWasm {
         device_ids: &[DeviceId],
     ) -> Result<()>;
 
+    /// Deletes the session and the identity of the given device.
+    async fn delete_session(
+        &self,
+        account: &AccountId,
+        user_id: &UserId,
+        device_id: &DeviceId,
+    ) -> Result<()>;
+
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage>;
+
     async fn clear_cache(&self, account: &AccountId) -> Result<()>;
 }
@@ -18,6 +18,8 @@ use crate::dtos::UserId;
 pub trait UserDeviceRepository: SendUnlessWasm + SyncUnlessWasm {
     /// Returns all devices associated with `user_id`.
     async fn get_all(&self, account: &AccountId, user_id: &UserId) -> Result<Vec<Device>>;
+    /// Returns the cached devices associated with `user_id` without loading them from the server.
+    async fn get_cached(&self, account: &AccountId, user_id: &UserId) -> Result<Vec<Device>>;
     /// Sets `devices` for `user_id`. Devices not contained in `devices` will be deleted.
     async fn set_all(
         &self,
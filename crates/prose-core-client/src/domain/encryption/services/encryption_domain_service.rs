@@ -8,9 +8,12 @@ use async_trait::async_trait;
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
-use crate::domain::encryption::models::{DecryptionContext, DeviceId, DeviceInfo, DeviceList};
+use crate::domain::encryption::models::{
+    DecryptedMessage, DecryptionContext, DeviceId, DeviceInfo, DeviceList, Fingerprint, Trust,
+    TrustPolicy,
+};
 use crate::domain::messaging::models::{EncryptedPayload, KeyTransportPayload, MessageId};
-use crate::domain::shared::models::{RoomId, UserId};
+use crate::domain::shared::models::{ParticipantId, RoomId, UserId};
 
 #[derive(Debug, thiserror::Error)]
 pub enum EncryptionError {
@@ -18,10 +21,34 @@ pub enum EncryptionError {
     NoDevices(UserId),
     #[error("The recipient does not have any trusted OMEMO-enabled devices.")]
     NoTrustedDevices(UserId),
+    #[error("The trust policy excludes all OMEMO-enabled devices of {user_id}.")]
+    AllDevicesUntrusted { user_id: UserId },
+    /// Returned for `TrustPolicy::EncryptToTrustedOnly` if recipients have devices we haven't
+    /// made a trust decision about yet. The user should be asked to trust or distrust them.
+    #[error("The recipients have undecided OMEMO-enabled devices.")]
+    UntrustedDevices { devices: Vec<(UserId, DeviceId)> },
+    /// The real ids of these participants are unknown, so their devices can't be resolved and
+    /// they wouldn't be able to read the message.
+    #[error(
+        "The devices of some participants can't be resolved because their real ids are unknown."
+    )]
+    UnresolvedParticipants { participants: Vec<ParticipantId> },
     #[error(transparent)]
     Other(#[from] anyhow::Error),
 }
 
+/// The result of encrypting a message for one or more recipients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncryptionResult {
+    pub payload: EncryptedPayload,
+    /// Recipients without any usable device, i.e. without an active session that is allowed by
+    /// the trust policy. These won't be able to read the message.
+    pub recipients_without_devices: Vec<UserId>,
+    /// Devices the message was encrypted for although we haven't made a trust decision about
+    /// them yet (or only trusted them blindly while encrypting).
+    pub undecided_devices: Vec<(UserId, DeviceId)>,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum DecryptionError {
     #[error("The message was not encrypted for this device.")]
@@ -36,17 +63,22 @@ pub enum DecryptionError {
 pub trait EncryptionDomainService: SendUnlessWasm + SyncUnlessWasm {
     async fn initialize(&self) -> Result<()>;
 
+    /// Encrypts `message` for all devices of `recipient_ids` and for our other devices.
+    /// Recipients without usable devices are skipped and reported in the result. Fails only if
+    /// none of the recipients has a usable device.
     async fn encrypt_message(
         &self,
-        recipient_ids: Vec<UserId>,
+        recipient_ids: &[UserId],
         message: String,
-    ) -> Result<EncryptedPayload, EncryptionError>;
+        trust_policy: TrustPolicy,
+    ) -> Result<EncryptionResult, EncryptionError>;
 
-    /// Decrypts the payload and returns the decrypted message.
+    /// Decrypts the payload and returns the decrypted message along with the sending device and
+    /// our trust in it.
     /// - If the payload does not contain an encrypted message, processes the key material and
     ///   returns None.
     /// - If decrypting the message fails, tries to look up the decrypted message in the
-    ///   MessagesRepository and returns it from there.
+    ///   MessagesRepository and returns it from there. The trust is unknown in that case.
     async fn decrypt_message(
         &self,
         sender_id: &UserId,
@@ -54,11 +86,41 @@ pub trait EncryptionDomainService: SendUnlessWasm + SyncUnlessWasm {
         message_id: Option<&MessageId>,
         payload: EncryptedPayload,
         context: Option<DecryptionContext>,
-    ) -> Result<String, DecryptionError>;
+    ) -> Result<DecryptedMessage, DecryptionError>;
 
     async fn finalize_decryption(&self, context: DecryptionContext);
 
     async fn load_device_infos(&self, user_id: &UserId) -> Result<Vec<DeviceInfo>>;
+    /// Returns the fingerprint of the identity key of the given device or `None` if we don't
+    /// know the device's identity yet.
+    async fn load_device_fingerprint(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+    ) -> Result<Option<Fingerprint>>;
+    /// Returns the id and the fingerprint of the identity key of this device or `None` if OMEMO
+    /// hasn't been initialized yet.
+    async fn load_local_device_fingerprint(&self) -> Result<Option<(DeviceId, Fingerprint)>>;
+    /// Changes the trust of the given device. Fails for the local device and for devices we
+    /// don't have a session with.
+    async fn set_device_trust(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+        trust: Trust,
+    ) -> Result<()>;
+    /// Compares `fingerprint` (in hex, optionally grouped) with the identity key of the given
+    /// device and marks the device as verified if they match. Returns `false` and leaves the
+    /// trust unchanged otherwise.
+    async fn verify_device_fingerprint(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+        fingerprint: &str,
+    ) -> Result<bool>;
+    /// Deletes the session with the given device so that a new one is started with the next
+    /// message. Useful to recover from a corrupted session. Fails for the local device.
+    async fn reset_session(&self, user_id: &UserId, device_id: &DeviceId) -> Result<()>;
     async fn delete_device(&self, device_id: &DeviceId) -> Result<()>;
     async fn disable_omemo(&self) -> Result<()>;
 
@@ -21,10 +21,13 @@ use crate::dtos::{PreKey, PreKeyId};
 #[async_trait]
 #[cfg_attr(feature = "test", mockall::automock)]
 pub trait EncryptionService: SendUnlessWasm + SyncUnlessWasm {
+    /// Generates a new identity and signed PreKey along with `pre_key_count` PreKeys, which use
+    /// the IDs `1..=pre_key_count`.
     async fn generate_local_encryption_bundle(
         &self,
         account: &AccountId,
         device_id: DeviceId,
+        pre_key_count: u32,
     ) -> Result<LocalEncryptionBundle>;
 
     async fn generate_pre_keys_with_ids(
@@ -10,6 +10,7 @@ use aes_gcm::aead::Aead;
 use aes_gcm::{AeadCore, Aes128Gcm, KeyInit};
 use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
 use parking_lot::Mutex;
 use rand::prelude::SliceRandom;
 use tracing::{error, info, warn};
@@ -18,27 +19,29 @@ use prose_proc_macros::DependenciesStruct;
 use prose_xmpp::TimeProvider;
 
 use crate::app::deps::{
-    DynAppContext, DynEncryptionKeysRepository, DynEncryptionService, DynMessagesRepository,
-    DynMessagingService, DynRngProvider, DynSessionRepository, DynTimeProvider,
-    DynUserDeviceIdProvider, DynUserDeviceRepository, DynUserDeviceService,
+    DynAppContext, DynClientEventDispatcher, DynEncryptionKeysRepository, DynEncryptionService,
+    DynMessagesRepository, DynMessagingService, DynRngProvider, DynSessionRepository,
+    DynTimeProvider, DynUserDeviceIdProvider, DynUserDeviceRepository, DynUserDeviceService,
 };
 use crate::domain::encryption::models::{
-    DecryptionContext, DecryptionContextInner, Device, DeviceId, DeviceInfo, DeviceList,
-    PreKeyBundle,
+    DecryptedMessage, DecryptionContext, DecryptionContextInner, Device, DeviceId, DeviceInfo,
+    DeviceList, Fingerprint, PreKeyBundle, Trust, TrustPolicy,
 };
 use crate::domain::encryption::services::encryption_domain_service::{
-    DecryptionError, EncryptionError,
+    DecryptionError, EncryptionError, EncryptionResult,
 };
 use crate::domain::messaging::models::{EncryptedPayload, KeyTransportPayload};
 use crate::domain::messaging::models::{MessageId, MessageLikePayload};
 use crate::domain::shared::models::{AccountId, UserId};
 use crate::dtos::{EncryptionKey, PreKeyId, RoomId};
 use crate::util::join_all;
+use crate::ClientEvent;
 
 use super::super::EncryptionDomainService as EncryptionDomainServiceTrait;
 
 #[derive(DependenciesStruct)]
 pub struct EncryptionDomainService {
+    client_event_dispatcher: DynClientEventDispatcher,
     ctx: DynAppContext,
     encryption_keys_repo: DynEncryptionKeysRepository,
     encryption_service: DynEncryptionService,
@@ -53,6 +56,9 @@ pub struct EncryptionDomainService {
 
     unpublish_device_attempts: Mutex<HashSet<DeviceId>>,
     repair_session_attempts: Mutex<HashSet<(UserId, DeviceId)>>,
+    /// PreKeys that were regenerated or dropped since our device bundle was last published.
+    unpublished_pre_key_ids: Mutex<HashSet<PreKeyId>>,
+    last_pre_key_publish: Mutex<Option<DateTime<Utc>>>,
 }
 
 const KEY_SIZE: usize = 16;
@@ -69,7 +75,7 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
         self.repair_session_attempts.lock().clear();
 
         // Initialize local bundle if needed…
-        let bundle = match self
+        let mut bundle = match self
             .encryption_keys_repo
             .get_local_device_bundle(&account)
             .await
@@ -82,6 +88,7 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
                     .generate_local_encryption_bundle(
                         &account,
                         self.user_device_id_provider.new_id(),
+                        self.ctx.config.omemo_pre_key_count,
                     )
                     .await
                     .context("Failed to generate local encryption bundle.")?;
@@ -95,6 +102,35 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
             }
         };
 
+        // Top up our PreKeys if the configured pool size grew…
+        let missing_pre_key_ids = (1..=self.ctx.config.omemo_pre_key_count)
+            .map(PreKeyId::from)
+            .filter(|id| !bundle.pre_keys.iter().any(|key| key.id == *id))
+            .collect::<Vec<_>>();
+
+        if !missing_pre_key_ids.is_empty() {
+            info!("Generating {} missing PreKeys…", missing_pre_key_ids.len());
+            let pre_keys = self
+                .encryption_service
+                .generate_pre_keys_with_ids(&account, missing_pre_key_ids.clone())
+                .await
+                .context("Failed to generate missing PreKeys")?;
+            self.encryption_keys_repo
+                .put_pre_keys(&account, pre_keys.as_slice())
+                .await
+                .context("Failed to save missing PreKeys")?;
+            self.unpublished_pre_key_ids
+                .lock()
+                .extend(missing_pre_key_ids);
+
+            bundle = self
+                .encryption_keys_repo
+                .get_local_device_bundle(&account)
+                .await?
+                .ok_or(anyhow!("Missing own device bundle"))?;
+            bundle.pre_keys.sort_by_key(|key| key.id);
+        }
+
         let user_id = account.to_user_id();
 
         let mut devices = self.user_device_repo.get_all(&account, &user_id).await?;
@@ -131,22 +167,23 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
                 .publish_device_bundle(bundle)
                 .await
                 .context("Failed to publish our device bundle")?;
+            self.unpublished_pre_key_ids.lock().clear();
         }
 
+        // Publish PreKeys that were regenerated during our last session, but not published yet…
+        self.publish_regenerated_pre_keys(&account, true)
+            .await
+            .context("Failed to publish regenerated PreKeys")?;
+
         Ok(())
     }
 
     async fn encrypt_message(
         &self,
-        recipient_ids: Vec<UserId>,
+        recipient_ids: &[UserId],
         message: String,
-    ) -> Result<EncryptedPayload, EncryptionError> {
-        print!("ENCRYPT >{message}<");
-
-        if message.ends_with("\n") {
-            println!("WHYYYY");
-        }
-
+        trust_policy: TrustPolicy,
+    ) -> Result<EncryptionResult, EncryptionError> {
         let account = self.ctx.connected_account()?;
         let current_user_id = account.to_user_id();
 
@@ -156,6 +193,14 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
             .await?
             .ok_or(anyhow!("Missing local encryption bundle"))?;
 
+        // Our own devices are handled separately below, so we're skipping ourselves here as
+        // well as any duplicates.
+        let mut seen_recipient_ids = HashSet::new();
+        let recipient_ids = recipient_ids
+            .iter()
+            .filter(|user_id| *user_id != &current_user_id && seen_recipient_ids.insert(*user_id))
+            .collect::<Vec<_>>();
+
         match self
             .start_sessions_if_needed(
                 &account,
@@ -198,40 +243,76 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
             }
         }
 
-        let their_active_device_ids = {
-            let mut all_active_device_ids = vec![];
+        let mut their_active_device_ids = vec![];
+        let mut recipients_without_devices = vec![];
+        let mut undecided_devices = vec![];
+        let mut encrypted_undecided_devices = vec![];
+        let mut last_error = None;
 
-            for recipient_id in &recipient_ids {
-                let sessions = self
-                    .session_repo
-                    .get_all_sessions(&account, &recipient_id)
-                    .await?
-                    .into_iter()
-                    .filter(|session| session.is_active)
-                    .collect::<Vec<_>>();
+        for recipient_id in &recipient_ids {
+            let sessions = self
+                .session_repo
+                .get_all_sessions(&account, recipient_id)
+                .await?
+                .into_iter()
+                .filter(|session| session.is_active)
+                .collect::<Vec<_>>();
+
+            if sessions.is_empty() {
+                recipients_without_devices.push((*recipient_id).clone());
+                last_error = Some(EncryptionError::NoDevices((*recipient_id).clone()));
+                continue;
+            }
 
-                if sessions.is_empty() {
-                    return Err(EncryptionError::NoDevices(recipient_id.clone()));
+            let mut active_device_ids = vec![];
+
+            for session in sessions {
+                if !trust_policy.allows(&session) {
+                    if session.trust == Trust::Undecided {
+                        undecided_devices.push(((*recipient_id).clone(), session.device_id));
+                    }
+                    continue;
                 }
 
-                let active_device_ids = sessions
-                    .into_iter()
-                    .filter_map(|session| {
-                        session
-                            .is_trusted_or_undecided()
-                            .then_some((recipient_id, session.device_id))
-                    })
-                    .collect::<Vec<_>>();
-
-                if active_device_ids.is_empty() {
-                    return Err(EncryptionError::NoDevices(recipient_id.clone()));
+                if session.trust == Trust::Undecided {
+                    encrypted_undecided_devices
+                        .push(((*recipient_id).clone(), session.device_id.clone()));
                 }
 
-                all_active_device_ids.extend(active_device_ids);
+                if let Some(trust) = trust_policy.trust_to_apply(&session) {
+                    self.session_repo
+                        .put_trust(&account, recipient_id, &session.device_id, trust)
+                        .await?;
+                }
+
+                active_device_ids.push((*recipient_id, session.device_id));
             }
 
-            all_active_device_ids
-        };
+            if active_device_ids.is_empty() {
+                recipients_without_devices.push((*recipient_id).clone());
+                last_error = Some(EncryptionError::AllDevicesUntrusted {
+                    user_id: (*recipient_id).clone(),
+                });
+                continue;
+            }
+
+            their_active_device_ids.extend(active_device_ids);
+        }
+
+        // Rather than silently leaving out devices the user hasn't decided about, we'll let them
+        // decide first.
+        if !undecided_devices.is_empty() {
+            return Err(EncryptionError::UntrustedDevices {
+                devices: undecided_devices,
+            });
+        }
+
+        // There's no point in sending a message that none of the recipients can read.
+        if their_active_device_ids.is_empty() {
+            if let Some(error) = last_error {
+                return Err(error);
+            }
+        }
 
         let nonce = Aes128Gcm::generate_nonce(self.rng_provider.rng());
         let dek = Aes128Gcm::generate_key(self.rng_provider.rng());
@@ -281,7 +362,11 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
             payload: payload[..message.len()].into(),
         };
 
-        Ok(payload)
+        Ok(EncryptionResult {
+            payload,
+            recipients_without_devices,
+            undecided_devices: encrypted_undecided_devices,
+        })
     }
 
     async fn decrypt_message(
@@ -291,9 +376,10 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
         message_id: Option<&MessageId>,
         payload: EncryptedPayload,
         context: Option<DecryptionContext>,
-    ) -> Result<String, DecryptionError> {
+    ) -> Result<DecryptedMessage, DecryptionError> {
         let needs_finalize_context = context.is_none();
         let context = context.unwrap_or_default();
+        let sender_device_id = payload.device_id.clone();
 
         // First try to decrypt the message. If that succeeds, great!
         let error = match self
@@ -304,7 +390,17 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
                 if needs_finalize_context {
                     self.finalize_decryption(context).await;
                 }
-                return Ok(message);
+                let trust = self
+                    .session_repo
+                    .get_session(&self.ctx.connected_account()?, sender_id, &sender_device_id)
+                    .await
+                    .unwrap_or_default()
+                    .map(|session| session.trust);
+                return Ok(DecryptedMessage {
+                    message,
+                    sender_device_id,
+                    trust,
+                });
             }
             Err(error) => error,
         };
@@ -331,7 +427,11 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
             return Err(error);
         };
 
-        Ok(body.raw.clone())
+        Ok(DecryptedMessage {
+            message: body.raw.clone(),
+            sender_device_id,
+            trust: None,
+        })
     }
 
     async fn finalize_decryption(&self, context: DecryptionContext) {
@@ -431,6 +531,137 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
         Ok(device_infos)
     }
 
+    async fn load_device_fingerprint(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+    ) -> Result<Option<Fingerprint>> {
+        let account = self.ctx.connected_account()?;
+
+        let fingerprint = self
+            .session_repo
+            .get_session(&account, user_id, device_id)
+            .await?
+            .and_then(|session| session.identity)
+            .map(Fingerprint::from);
+
+        Ok(fingerprint)
+    }
+
+    async fn load_local_device_fingerprint(&self) -> Result<Option<(DeviceId, Fingerprint)>> {
+        let account = self.ctx.connected_account()?;
+
+        let fingerprint = self
+            .encryption_keys_repo
+            .get_local_device(&account)
+            .await?
+            .map(|device| {
+                (
+                    device.device_id,
+                    Fingerprint::from(&device.identity_key_pair.identity_key),
+                )
+            });
+
+        Ok(fingerprint)
+    }
+
+    async fn set_device_trust(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+        trust: Trust,
+    ) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        if &account == user_id {
+            let local_device = self.encryption_keys_repo.get_local_device(&account).await?;
+            if local_device.map(|device| device.device_id).as_ref() == Some(device_id) {
+                bail!("The trust of the local device cannot be changed.")
+            }
+        }
+
+        let Some(session) = self
+            .session_repo
+            .get_session(&account, user_id, device_id)
+            .await?
+        else {
+            bail!("Unknown device {device_id} of {user_id}.")
+        };
+
+        if session.trust == trust {
+            return Ok(());
+        }
+
+        // Since `encrypt_message` consults the stored sessions every time, the device will be
+        // included or excluded according to its new trust from the next message on.
+        self.session_repo
+            .put_trust(&account, user_id, device_id, trust)
+            .await?;
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::UserDevicesChanged {
+                ids: vec![user_id.clone()],
+            });
+
+        Ok(())
+    }
+
+    async fn verify_device_fingerprint(
+        &self,
+        user_id: &UserId,
+        device_id: &DeviceId,
+        fingerprint: &str,
+    ) -> Result<bool> {
+        let Some(expected_fingerprint) = self.load_device_fingerprint(user_id, device_id).await?
+        else {
+            bail!("The identity of device {device_id} of {user_id} is unknown.")
+        };
+
+        let fingerprint = fingerprint
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_lowercase();
+
+        if fingerprint != expected_fingerprint.hex_groups().concat() {
+            return Ok(false);
+        }
+
+        self.set_device_trust(user_id, device_id, Trust::Verified)
+            .await?;
+        Ok(true)
+    }
+
+    async fn reset_session(&self, user_id: &UserId, device_id: &DeviceId) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        if &account == user_id {
+            let local_device = self.encryption_keys_repo.get_local_device(&account).await?;
+            if local_device.map(|device| device.device_id).as_ref() == Some(device_id) {
+                bail!("The session of the local device cannot be reset.")
+            }
+        }
+
+        info!("Resetting OMEMO session with {user_id} ({device_id})…");
+
+        // Without a session, `encrypt_message` will process a fresh PreKey bundle of the device.
+        self.session_repo
+            .delete_session(&account, user_id, device_id)
+            .await?;
+
+        // …and allow the session to be repaired again when decrypting a message fails.
+        self.repair_session_attempts
+            .lock()
+            .remove(&(user_id.clone(), device_id.clone()));
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::UserDevicesChanged {
+                ids: vec![user_id.clone()],
+            });
+
+        Ok(())
+    }
+
     async fn delete_device(&self, device_id: &DeviceId) -> Result<()> {
         let account = self.ctx.connected_account()?;
         let user_id = account.to_user_id();
@@ -522,12 +753,14 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
         device_list: DeviceList,
     ) -> Result<()> {
         let account = self.ctx.connected_account()?;
+        let known_device_ids = self.load_known_device_ids(&account, user_id).await?;
 
         // Did we just receive our own PubSub node?
         if &account != user_id {
             self.user_device_repo
-                .set_all(&account, user_id, device_list.devices)
+                .set_all(&account, user_id, device_list.devices.clone())
                 .await?;
+            self.dispatch_new_devices(user_id, &known_device_ids, &device_list, None);
             return Ok(());
         }
 
@@ -540,6 +773,13 @@ impl EncryptionDomainServiceTrait for EncryptionDomainService {
             return Ok(());
         };
 
+        self.dispatch_new_devices(
+            user_id,
+            &known_device_ids,
+            &device_list,
+            Some(&current_device.device_id),
+        );
+
         // … This step presents the risk of introducing a race condition: Two devices might
         // simultaneously try to announce themselves, unaware of the other's existence. The second
         // device would overwrite the first one. To mitigate this, devices MUST check that their
@@ -592,23 +832,67 @@ impl EncryptionDomainService {
         account: &AccountId,
         used_pre_key_ids: Vec<PreKeyId>,
     ) -> Result<()> {
-        // No missing IDs, nothing to do…
-        if used_pre_key_ids.is_empty() {
+        let max_pre_key_id = PreKeyId::from(self.ctx.config.omemo_pre_key_count);
+
+        // Skip PreKeys which we've regenerated already and which are waiting to be published. Used
+        // PreKeys above the configured pool size are not replenished, but still need to be removed
+        // from our published bundle…
+        let (missing_pre_key_ids, dropped_pre_key_ids): (Vec<_>, Vec<_>) = {
+            let unpublished_pre_key_ids = self.unpublished_pre_key_ids.lock();
+            used_pre_key_ids
+                .into_iter()
+                .filter(|id| !unpublished_pre_key_ids.contains(id))
+                .partition(|id| *id <= max_pre_key_id)
+        };
+
+        if !missing_pre_key_ids.is_empty() {
+            info!("Generating {} new PreKeys…", missing_pre_key_ids.len());
+            let refreshed_pre_keys = self
+                .encryption_service
+                .generate_pre_keys_with_ids(account, missing_pre_key_ids.clone())
+                .await
+                .context("Failed to re-generate deleted PreKeys")?;
+
+            info!("Saving new PreKeys…");
+            self.encryption_keys_repo
+                .put_pre_keys(&account, refreshed_pre_keys.as_slice())
+                .await
+                .context("Failed to save re-generated PreKeys…")?;
+        }
+
+        self.unpublished_pre_key_ids
+            .lock()
+            .extend(missing_pre_key_ids.into_iter().chain(dropped_pre_key_ids));
+
+        self.publish_regenerated_pre_keys(account, false).await
+    }
+
+    /// Publishes our device bundle if PreKeys were regenerated or dropped since it was last
+    /// published.
+    /// Unless `force` is set, the bundle is published at most once per
+    /// `pre_key_publish_debounce_interval_secs`. PreKeys regenerated in the meantime are published
+    /// with the next call after the interval has passed.
+    async fn publish_regenerated_pre_keys(&self, account: &AccountId, force: bool) -> Result<()> {
+        let pre_key_ids = self.unpublished_pre_key_ids.lock().clone();
+
+        // No changed PreKeys, nothing to do…
+        if pre_key_ids.is_empty() {
             return Ok(());
         }
 
-        info!("Generating {} new PreKeys…", used_pre_key_ids.len());
-        let refreshed_pre_keys = self
-            .encryption_service
-            .generate_pre_keys_with_ids(account, used_pre_key_ids)
-            .await
-            .context("Failed to re-generate deleted PreKeys")?;
+        let now = self.time_provider.now();
+        let debounce_interval =
+            Duration::seconds(self.ctx.config.pre_key_publish_debounce_interval_secs);
 
-        info!("Saving new PreKeys…");
-        self.encryption_keys_repo
-            .put_pre_keys(&account, refreshed_pre_keys.as_slice())
-            .await
-            .context("Failed to save re-generated PreKeys…")?;
+        if !force
+            && self
+                .last_pre_key_publish
+                .lock()
+                .is_some_and(|last_publish| now - last_publish < debounce_interval)
+        {
+            info!("Deferring publishing of {} new PreKeys…", pre_key_ids.len());
+            return Ok(());
+        }
 
         info!("Publishing bundle with new PreKeys…");
         let mut bundle = self
@@ -623,6 +907,11 @@ impl EncryptionDomainService {
             .await
             .context("Failed to publish device bundle with re-generated PreKeys")?;
 
+        self.unpublished_pre_key_ids
+            .lock()
+            .retain(|id| !pre_key_ids.contains(id));
+        *self.last_pre_key_publish.lock() = Some(now);
+
         Ok(())
     }
 
@@ -757,6 +1046,61 @@ impl EncryptionDomainService {
         Ok(())
     }
 
+    /// Dispatches a `ClientEvent::NewDeviceDetected` for each device in `device_list` that is not
+    /// contained in `known_devices`. Nothing is dispatched if we didn't know any devices of
+    /// `user_id` yet, since in that case all of them would be reported.
+    /// Returns the ids of all devices of `user_id` that we've seen before. Devices that were
+    /// removed from the user's device list are still known if we had a session with them, so
+    /// that they're not reported as new if they reappear.
+    async fn load_known_device_ids(
+        &self,
+        account: &AccountId,
+        user_id: &UserId,
+    ) -> Result<HashSet<DeviceId>> {
+        let mut known_device_ids = self
+            .user_device_repo
+            .get_cached(account, user_id)
+            .await?
+            .into_iter()
+            .map(|device| device.id)
+            .collect::<HashSet<_>>();
+
+        known_device_ids.extend(
+            self.session_repo
+                .get_all_sessions(account, user_id)
+                .await?
+                .into_iter()
+                .map(|session| session.device_id),
+        );
+
+        Ok(known_device_ids)
+    }
+
+    fn dispatch_new_devices(
+        &self,
+        user_id: &UserId,
+        known_device_ids: &HashSet<DeviceId>,
+        device_list: &DeviceList,
+        local_device_id: Option<&DeviceId>,
+    ) {
+        // Nothing is new when we're seeing the devices of a user for the first time…
+        if known_device_ids.is_empty() {
+            return;
+        }
+
+        for device in &device_list.devices {
+            if known_device_ids.contains(&device.id) || Some(&device.id) == local_device_id {
+                continue;
+            }
+
+            self.client_event_dispatcher
+                .dispatch_event(ClientEvent::NewDeviceDetected {
+                    user_id: user_id.clone(),
+                    device_id: device.id.clone(),
+                });
+        }
+    }
+
     fn build_local_device_label(&self) -> String {
         self.ctx
             .software_version
@@ -286,6 +286,7 @@ impl EncryptionService for SignalServiceHandle {
         &self,
         _account: &AccountId,
         device_id: DeviceId,
+        pre_key_count: u32,
     ) -> Result<LocalEncryptionBundle> {
         let now = Utc::now();
         let mut rng = self.rng_provider.rng();
@@ -305,7 +306,7 @@ impl EncryptionService for SignalServiceHandle {
                 signature: signed_pre_key_signature,
                 timestamp: now.timestamp() as u64,
             },
-            pre_keys: (1u32..101)
+            pre_keys: (1..=pre_key_count)
                 .map(|i| {
                     let key_pair = libsignal_protocol::KeyPair::generate(&mut rng);
                     let result = PublicKey::try_from(&key_pair.public_key).and_then(|public_key| {
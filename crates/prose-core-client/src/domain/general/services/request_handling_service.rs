@@ -45,4 +45,8 @@ pub trait RequestHandlingService: SendUnlessWasm + SyncUnlessWasm {
         id: &RequestId,
         last_active_seconds_ago: u64,
     ) -> Result<()>;
+
+    /// Responds with a `forbidden` error to a last activity request of an entity that is not
+    /// subscribed to our presence.
+    async fn decline_last_activity_request(&self, to: &SenderId, id: &RequestId) -> Result<()>;
 }
@@ -3,12 +3,15 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use anyhow::Result;
 use mime::Mime;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::util::mime_serde_shim;
 
+use super::AttachmentEncryption;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Attachment {
     pub r#type: AttachmentType,
@@ -19,21 +22,105 @@ pub struct Attachment {
     pub file_size: Option<u64>,
 }
 
+impl Attachment {
+    /// The key and IV to decrypt the file with, if it was encrypted before it was uploaded.
+    pub fn encryption(&self) -> Option<AttachmentEncryption> {
+        AttachmentEncryption::from_url(&self.url)
+    }
+
+    /// The URL to download the (possibly encrypted) file from.
+    pub fn download_url(&self) -> Url {
+        AttachmentEncryption::download_url(&self.url)
+    }
+
+    /// Decrypts the downloaded file if needed.
+    pub fn decrypt(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        match self.encryption() {
+            Some(encryption) => encryption.decrypt(&data),
+            None => Ok(data),
+        }
+    }
+
+    pub fn thumbnail(&self) -> Option<&Thumbnail> {
+        match &self.r#type {
+            AttachmentType::Image { thumbnail } | AttachmentType::Video { thumbnail, .. } => {
+                thumbnail.as_ref()
+            }
+            AttachmentType::Audio { .. } | AttachmentType::File => None,
+        }
+    }
+
+    pub fn thumbnail_mut(&mut self) -> Option<&mut Thumbnail> {
+        match &mut self.r#type {
+            AttachmentType::Image { thumbnail } | AttachmentType::Video { thumbnail, .. } => {
+                thumbnail.as_mut()
+            }
+            AttachmentType::Audio { .. } | AttachmentType::File => None,
+        }
+    }
+
+    /// The URLs of the file and its thumbnail that contain a key, i.e. that must only be sent
+    /// inside an encrypted message.
+    pub fn encrypted_urls(&self) -> Vec<Url> {
+        [
+            Some(&self.url),
+            self.thumbnail().map(|thumbnail| &thumbnail.url),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|url| AttachmentEncryption::is_encrypted_url(url))
+        .cloned()
+        .collect()
+    }
+
+    /// Returns the attachment with the download URLs of the file and its thumbnail, so that its
+    /// metadata can be sent in plaintext without revealing any keys.
+    pub fn without_keys(mut self) -> Self {
+        self.url = self.download_url();
+        if let Some(thumbnail) = self.thumbnail_mut() {
+            thumbnail.url = thumbnail.download_url();
+        }
+        self
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum AttachmentType {
     Audio {
+        /// The duration in seconds.
         duration: Option<u64>,
     },
     Image {
         thumbnail: Option<Thumbnail>,
     },
     Video {
+        /// The duration in seconds.
         duration: Option<u64>,
         thumbnail: Option<Thumbnail>,
     },
     File,
 }
 
+/// The kind of an attachment without the metadata that comes with its `AttachmentType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AttachmentKind {
+    Audio,
+    Image,
+    Video,
+    File,
+}
+
+impl AttachmentType {
+    pub fn kind(&self) -> AttachmentKind {
+        match self {
+            AttachmentType::Audio { .. } => AttachmentKind::Audio,
+            AttachmentType::Image { .. } => AttachmentKind::Image,
+            AttachmentType::Video { .. } => AttachmentKind::Video,
+            AttachmentType::File => AttachmentKind::File,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Thumbnail {
     pub url: Url,
@@ -42,3 +129,23 @@ pub struct Thumbnail {
     pub width: Option<u32>,
     pub height: Option<u32>,
 }
+
+impl Thumbnail {
+    /// The key and IV to decrypt the thumbnail with, if it was encrypted before it was uploaded.
+    pub fn encryption(&self) -> Option<AttachmentEncryption> {
+        AttachmentEncryption::from_url(&self.url)
+    }
+
+    /// The URL to download the (possibly encrypted) thumbnail from.
+    pub fn download_url(&self) -> Url {
+        AttachmentEncryption::download_url(&self.url)
+    }
+
+    /// Decrypts the downloaded thumbnail if needed.
+    pub fn decrypt(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        match self.encryption() {
+            Some(encryption) => encryption.decrypt(&data),
+            None => Ok(data),
+        }
+    }
+}
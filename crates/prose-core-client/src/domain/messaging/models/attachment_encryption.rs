@@ -0,0 +1,196 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use aes_gcm::aead::consts::U16;
+use aes_gcm::aead::Aead;
+use aes_gcm::aes::Aes256;
+use aes_gcm::{Aes256Gcm, AesGcm, Key, KeyInit, Nonce};
+use anyhow::{anyhow, Result};
+use rand::RngCore;
+use url::{Position, Url};
+
+const SCHEME: &str = "aesgcm";
+const KEY_SIZE: usize = 32;
+const IV_SIZE: usize = 12;
+/// Older clients used 16 byte IVs, which we still accept when decrypting.
+const LEGACY_IV_SIZE: usize = 16;
+
+type Aes256GcmLegacy = AesGcm<Aes256, U16>;
+
+/// The key and IV a file was encrypted with before it was uploaded (XEP-0454: OMEMO Media
+/// sharing). Both are transported in the fragment of the file's `aesgcm://` URL, which must thus
+/// only ever be sent inside an encrypted message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachmentEncryption {
+    key: [u8; KEY_SIZE],
+    iv: Vec<u8>,
+}
+
+impl AttachmentEncryption {
+    /// Generates a fresh key and IV. These must not be reused for another file.
+    pub fn generate() -> Self {
+        let mut rng = rand::thread_rng();
+        let mut key = [0; KEY_SIZE];
+        let mut iv = vec![0; IV_SIZE];
+        rng.fill_bytes(&mut key);
+        rng.fill_bytes(&mut iv);
+        Self { key, iv }
+    }
+
+    /// Returns `true` if `url` is an `aesgcm://` URL, regardless of whether its fragment holds
+    /// a valid key.
+    pub fn is_encrypted_url(url: &Url) -> bool {
+        url.scheme() == SCHEME
+    }
+
+    /// Parses the key and IV from the fragment of an `aesgcm://` URL. Returns `None` for any
+    /// other URL.
+    pub fn from_url(url: &Url) -> Option<Self> {
+        if url.scheme() != SCHEME {
+            return None;
+        }
+
+        let bytes = decode_hex(url.fragment()?)?;
+        let iv_size = bytes.len().checked_sub(KEY_SIZE)?;
+
+        if iv_size != IV_SIZE && iv_size != LEGACY_IV_SIZE {
+            return None;
+        }
+
+        Some(Self {
+            key: bytes[iv_size..].try_into().ok()?,
+            iv: bytes[..iv_size].to_vec(),
+        })
+    }
+
+    /// Returns `download_url` as an `aesgcm://` URL that carries the key and IV.
+    pub fn encrypted_url(&self, download_url: &Url) -> Result<Url> {
+        Ok(format!(
+            "{SCHEME}://{}#{}{}",
+            &download_url[Position::BeforeUsername..Position::AfterQuery],
+            encode_hex(&self.iv),
+            encode_hex(&self.key)
+        )
+        .parse()?)
+    }
+
+    /// Returns the `https` URL to download the encrypted file from if `url` is an `aesgcm://`
+    /// URL or `url` itself otherwise.
+    pub fn download_url(url: &Url) -> Url {
+        if url.scheme() != SCHEME {
+            return url.clone();
+        }
+
+        format!(
+            "https://{}",
+            &url[Position::BeforeUsername..Position::AfterQuery]
+        )
+        .parse()
+        .unwrap_or_else(|_| url.clone())
+    }
+
+    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let key = Key::<Aes256Gcm>::from_slice(&self.key);
+
+        match self.iv.len() {
+            IV_SIZE => Aes256Gcm::new(key).encrypt(Nonce::from_slice(&self.iv), data),
+            _ => Aes256GcmLegacy::new(key).encrypt(Nonce::from_slice(&self.iv), data),
+        }
+        .map_err(|err| anyhow!("Failed to encrypt file. {err}"))
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let key = Key::<Aes256Gcm>::from_slice(&self.key);
+
+        match self.iv.len() {
+            IV_SIZE => Aes256Gcm::new(key).decrypt(Nonce::from_slice(&self.iv), data),
+            _ => Aes256GcmLegacy::new(key).decrypt(Nonce::from_slice(&self.iv), data),
+        }
+        .map_err(|err| anyhow!("Failed to decrypt file. {err}"))
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypts_and_decrypts_file() -> Result<()> {
+        let encryption = AttachmentEncryption::generate();
+        let ciphertext = encryption.encrypt(b"Hello World")?;
+
+        assert_ne!(ciphertext.as_slice(), b"Hello World");
+        assert_eq!(encryption.decrypt(&ciphertext)?, b"Hello World");
+        assert!(AttachmentEncryption::generate()
+            .decrypt(&ciphertext)
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_url_roundtrip() -> Result<()> {
+        let download_url = Url::parse("https://uploads.prose.org/a1b2/image.jpg?v=1")?;
+        let encryption = AttachmentEncryption::generate();
+        let url = encryption.encrypted_url(&download_url)?;
+
+        assert_eq!(url.scheme(), "aesgcm");
+        assert_eq!(url.fragment().map(str::len), Some(88));
+        assert_eq!(AttachmentEncryption::from_url(&url), Some(encryption));
+        assert_eq!(AttachmentEncryption::download_url(&url), download_url);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parses_legacy_iv() -> Result<()> {
+        let url = Url::parse(&format!(
+            "aesgcm://uploads.prose.org/image.jpg#{}{}",
+            "00".repeat(LEGACY_IV_SIZE),
+            "11".repeat(KEY_SIZE)
+        ))?;
+
+        let encryption = AttachmentEncryption::from_url(&url).unwrap();
+        assert_eq!(encryption.iv, vec![0; LEGACY_IV_SIZE]);
+        assert_eq!(encryption.key, [0x11; KEY_SIZE]);
+        assert_eq!(
+            encryption.decrypt(&encryption.encrypt(b"Hello World")?)?,
+            b"Hello World"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignores_other_urls() -> Result<()> {
+        let url = Url::parse("https://uploads.prose.org/image.jpg")?;
+
+        assert_eq!(AttachmentEncryption::from_url(&url), None);
+        assert_eq!(AttachmentEncryption::download_url(&url), url);
+        assert_eq!(
+            AttachmentEncryption::from_url(&Url::parse(
+                "aesgcm://uploads.prose.org/image.jpg#abcd"
+            )?),
+            None
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::messaging::models::Mention;
+use crate::domain::shared::models::Markdown;
+
+/// A message that the user started composing in a room but didn't send yet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Draft {
+    pub text: Markdown,
+    /// The users mentioned in `text`, so that they can be restored when the draft is reopened.
+    pub mentions: Vec<Mention>,
+}
+
+impl Draft {
+    pub fn new(text: impl Into<Markdown>) -> Self {
+        Self {
+            text: text.into(),
+            mentions: vec![],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.as_ref().is_empty()
+    }
+}
@@ -3,6 +3,8 @@
 // Copyright: 2024, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use serde::{Deserialize, Serialize};
+
 use crate::dtos::DeviceId;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -11,7 +13,7 @@ pub enum EncryptedMessage {
     KeyTransport(KeyTransportPayload),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EncryptedPayload {
     /// The device id of the sender
     pub device_id: DeviceId,
@@ -40,7 +42,7 @@ pub struct KeyTransportPayload {
     pub keys: Vec<EncryptionKey>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EncryptionKey {
     /// The device id this key is encrypted for.
     pub device_id: DeviceId,
@@ -12,11 +12,12 @@ use tracing::{info, warn};
 
 use prose_utils::id_string;
 
+use crate::domain::encryption::models::{DeviceId, Trust};
 use crate::domain::messaging::models::message_id::MessageId;
 use crate::domain::shared::models::ParticipantId;
 use crate::dtos::{Attachment, MessageRemoteId, MessageServerId, HTML};
 
-use super::{Mention, MessageLike, MessageLikePayload, MessageTargetId};
+use super::{Mention, MessageLike, MessageLikePayload, MessageTargetId, ThreadId};
 
 id_string!(Emoji);
 
@@ -24,6 +25,9 @@ id_string!(Emoji);
 pub struct Reaction {
     pub emoji: Emoji,
     pub from: Vec<ParticipantId>,
+    /// The time when a sender was last added to this reaction. Only known for reactions that
+    /// were reduced from received messages.
+    pub last_updated: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -39,6 +43,13 @@ pub struct MessageFlags {
     pub is_delivered: bool,
     pub is_transient: bool,
     pub is_encrypted: bool,
+    /// Our trust in the device that sent the encrypted message. `None` if the message wasn't
+    /// encrypted or the trust is unknown.
+    pub sender_device_trust: Option<Trust>,
+    /// The device that sent the encrypted message. `None` if the message wasn't encrypted.
+    pub sender_device_id: Option<DeviceId>,
+    /// The message could not be decrypted. Its body is empty.
+    pub is_undecryptable: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -61,6 +72,18 @@ pub struct Message {
     pub attachments: Vec<Attachment>,
     pub mentions: Vec<Mention>,
     pub reply_to: Option<ReplyTo>,
+    pub thread_id: Option<ThreadId>,
+    /// The number of replies in the thread started by this message.
+    pub thread_reply_count: u32,
+    /// The time of the latest reply in the thread started by this message.
+    pub last_thread_reply_at: Option<DateTime<Utc>>,
+    /// The message was retracted by its sender or removed by a moderator. Its contents were
+    /// cleared, but it's kept as a tombstone in the conversation.
+    pub is_retracted: bool,
+    /// The reason given for the retraction, if any.
+    pub retraction_reason: Option<String>,
+    /// The moderator who removed the message, if it wasn't retracted by its sender (XEP-0425).
+    pub moderated_by: Option<ParticipantId>,
 }
 
 impl Message {
@@ -73,6 +96,7 @@ impl Message {
             self.reactions.push(Reaction {
                 emoji,
                 from: vec![user_id.clone()],
+                last_updated: None,
             });
             return;
         };
@@ -111,7 +135,8 @@ impl Message {
                     encryption_info,
                     is_transient: is_private,
                     reply_to,
-                    thread_id: _thread_id,
+                    thread_id,
+                    is_markable: _is_markable,
                 } => Message {
                     id: msg.id,
                     remote_id: msg.remote_id,
@@ -128,11 +153,23 @@ impl Message {
                         is_delivered: false,
                         is_transient: is_private,
                         is_encrypted: encryption_info.is_some(),
+                        is_undecryptable: encryption_info
+                            .as_ref()
+                            .is_some_and(|info| info.decryption_failed),
+                        sender_device_id: encryption_info.as_ref().map(|info| info.sender.clone()),
+                        sender_device_trust: encryption_info.and_then(|info| info.trust),
                     },
                     reactions: vec![],
                     attachments,
                     mentions: body.mentions,
                     reply_to,
+                    thread_id,
+                    thread_reply_count: 0,
+                    last_thread_reply_at: None,
+                    is_retracted: false,
+                    retraction_reason: None,
+                    moderated_by: None,
+                    moderated_by: None,
                 },
                 MessageLikePayload::Error { message: error } => Message {
                     id: msg.id,
@@ -149,6 +186,13 @@ impl Message {
                     attachments: vec![],
                     mentions: vec![],
                     reply_to: None,
+                    thread_id: None,
+                    thread_reply_count: 0,
+                    last_thread_reply_at: None,
+                    is_retracted: false,
+                    retraction_reason: None,
+                    moderated_by: None,
+                    moderated_by: None,
                 },
                 MessageLikePayload::Correction { .. }
                 | MessageLikePayload::DeliveryReceipt { .. }
@@ -202,10 +246,22 @@ impl Message {
                     message.mentions = body.mentions;
                     message.flags.is_edited = true;
                     message.attachments = attachments;
-                    message.flags.is_encrypted = encryption_info.is_some()
+                    message.flags.is_encrypted = encryption_info.is_some();
+                    message.flags.is_undecryptable = encryption_info
+                        .as_ref()
+                        .is_some_and(|info| info.decryption_failed);
+                    message.flags.sender_device_id =
+                        encryption_info.as_ref().map(|info| info.sender.clone());
+                    message.flags.sender_device_trust = encryption_info.and_then(|info| info.trust);
                 }
+                // Receipts may arrive from multiple devices of the recipient. The flags reflect
+                // whether at least one device confirmed the message and never revert. A displayed
+                // marker implies that the message was delivered (XEP-0333).
                 MessageLikePayload::DeliveryReceipt { .. } => message.flags.is_delivered = true,
-                MessageLikePayload::ReadReceipt { .. } => message.flags.is_read = true,
+                MessageLikePayload::ReadReceipt { .. } => {
+                    message.flags.is_delivered = true;
+                    message.flags.is_read = true;
+                }
                 MessageLikePayload::Reaction { mut emojis, .. } => {
                     let modifier_from = ParticipantId::from(modifier.from);
 
@@ -219,7 +275,9 @@ impl Message {
                             if emoji.as_ref() == reaction.emoji.as_ref() {
                                 // …add the author if needed
                                 if !reaction.from.contains(&modifier_from) {
-                                    reaction.from.push(modifier_from.clone())
+                                    reaction.from.push(modifier_from.clone());
+                                    reaction.last_updated =
+                                        reaction.last_updated.max(Some(modifier.timestamp));
                                 }
                                 // Remove the applied emoji from the list of emojis
                                 emojis.remove(idx as usize);
@@ -245,11 +303,28 @@ impl Message {
                         message.reactions.push(Reaction {
                             emoji: emoji.into_inner().into(),
                             from: vec![modifier_from.clone()],
+                            last_updated: Some(modifier.timestamp),
                         })
                     }
                 }
-                MessageLikePayload::Retraction { .. } => {
-                    messages_map.insert(message_id.clone(), None);
+                // Retracted messages are kept as a tombstone so that the conversation still
+                // shows that something was removed.
+                MessageLikePayload::Retraction {
+                    reason,
+                    moderated_by,
+                    ..
+                } => {
+                    message.body = Body {
+                        raw: String::new(),
+                        html: HTML::new(""),
+                    };
+                    message.attachments = vec![];
+                    message.mentions = vec![];
+                    message.reactions = vec![];
+                    message.reply_to = None;
+                    message.is_retracted = true;
+                    message.retraction_reason = reason;
+                    message.moderated_by = moderated_by;
                 }
                 MessageLikePayload::Message { .. } | MessageLikePayload::Error { .. } => {
                     unreachable!("Unexpected MessageLikePayload")
@@ -257,21 +332,75 @@ impl Message {
             }
         }
 
-        messages_map.into_values().filter_map(|msg| msg).collect()
+        let mut messages = messages_map
+            .into_values()
+            .filter_map(|msg| msg)
+            .collect::<Vec<_>>();
+        Self::count_thread_replies(&mut messages);
+        messages
+    }
+
+    /// Counts the replies of each thread contained in `messages`. The earliest message of a thread
+    /// is considered its parent and receives the count, independent of the order of `messages`.
+    fn count_thread_replies(messages: &mut [Message]) {
+        let mut parent_indexes = HashMap::<ThreadId, usize>::new();
+
+        for (idx, message) in messages.iter().enumerate() {
+            let Some(thread_id) = message.thread_id.clone() else {
+                continue;
+            };
+
+            parent_indexes
+                .entry(thread_id)
+                .and_modify(|parent_idx| {
+                    if message.timestamp < messages[*parent_idx].timestamp {
+                        *parent_idx = idx
+                    }
+                })
+                .or_insert(idx);
+        }
+
+        for idx in 0..messages.len() {
+            let Some(parent_idx) = messages[idx]
+                .thread_id
+                .as_ref()
+                .and_then(|thread_id| parent_indexes.get(thread_id))
+                .copied()
+            else {
+                continue;
+            };
+
+            if parent_idx == idx {
+                continue;
+            }
+
+            let timestamp = messages[idx].timestamp;
+            let parent = &mut messages[parent_idx];
+            parent.thread_reply_count += 1;
+            parent.last_thread_reply_at = parent.last_thread_reply_at.max(Some(timestamp));
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
+    use anyhow::Result;
     use chrono::TimeZone;
+    use jid::FullJid;
     use pretty_assertions::assert_eq;
+    use xmpp_parsers::message::MessageType;
 
-    use prose_xmpp::bare;
+    use prose_xmpp::stanza::message::chat_marker::{Displayed, Received};
+    use prose_xmpp::stanza::Message as XMPPMessage;
+    use prose_xmpp::{bare, full};
 
-    use crate::domain::messaging::models::MessageLikeBody;
+    use crate::domain::encryption::services::mocks::MockEncryptionDomainService;
+    use crate::domain::messaging::models::{AttachmentType, MessageLikeBody, MessageParser};
     use crate::domain::shared::models::UserId;
     use crate::test::MessageBuilder;
-    use crate::user_id;
+    use crate::{occupant_id, user_id};
 
     use super::*;
 
@@ -317,6 +446,13 @@ mod tests {
                     attachments: vec![],
                     mentions: vec![],
                     reply_to: None,
+                    thread_id: None,
+                    thread_reply_count: 0,
+                    last_thread_reply_at: None,
+                    is_retracted: false,
+                    retraction_reason: None,
+                    moderated_by: None,
+                    moderated_by: None,
                 },
                 Message {
                     id: "id2".into(),
@@ -333,12 +469,96 @@ mod tests {
                     attachments: vec![],
                     mentions: vec![],
                     reply_to: None,
+                    thread_id: None,
+                    thread_reply_count: 0,
+                    last_thread_reply_at: None,
+                    is_retracted: false,
+                    retraction_reason: None,
+                    moderated_by: None,
+                    moderated_by: None,
                 }
             ],
             reduced_message,
         )
     }
 
+    #[tokio::test]
+    async fn test_aggregates_receipts_from_multiple_resources() -> Result<()> {
+        let message = MessageLike {
+            id: "id1".into(),
+            remote_id: Some("mid1".into()),
+            server_id: None,
+            to: Some(bare!("a@prose.org")),
+            from: user_id!("a@prose.org").into(),
+            timestamp: Utc.with_ymd_and_hms(2023, 04, 07, 16, 00, 00).unwrap(),
+            payload: MessageLikePayload::message("Hello"),
+        };
+
+        let phone = full!("b@prose.org/phone");
+        let desktop = full!("b@prose.org/desktop");
+
+        let delivered = |from: &FullJid| {
+            XMPPMessage::new()
+                .set_type(MessageType::Chat)
+                .set_to(bare!("a@prose.org"))
+                .set_from(from.clone())
+                .set_received_marker(Received { id: "mid1".into() })
+        };
+        let displayed = |from: &FullJid| {
+            XMPPMessage::new()
+                .set_type(MessageType::Chat)
+                .set_to(bare!("a@prose.org"))
+                .set_from(from.clone())
+                .set_displayed_marker(Displayed { id: "mid1".into() })
+        };
+
+        let delivered_to_phone = parse_receipt(1, delivered(&phone)).await?;
+        let displayed_on_desktop = parse_receipt(2, displayed(&desktop)).await?;
+        let delivered_to_desktop = parse_receipt(3, delivered(&desktop)).await?;
+        let displayed_on_phone = parse_receipt(4, displayed(&phone)).await?;
+
+        // Delivered to the phone…
+        let reduced_message =
+            Message::reducing_messages([message.clone(), delivered_to_phone.clone()])
+                .pop()
+                .unwrap();
+        assert!(reduced_message.flags.is_delivered);
+        assert!(!reduced_message.flags.is_read);
+
+        // …displayed on the desktop before it acknowledged the delivery…
+        let reduced_message = Message::reducing_messages([
+            message.clone(),
+            delivered_to_phone.clone(),
+            displayed_on_desktop.clone(),
+        ])
+        .pop()
+        .unwrap();
+        assert!(reduced_message.flags.is_delivered);
+        assert!(reduced_message.flags.is_read);
+
+        // …and finally delivered to and displayed on both resources.
+        let reduced_message = Message::reducing_messages([
+            message.clone(),
+            delivered_to_phone,
+            displayed_on_desktop.clone(),
+            delivered_to_desktop,
+            displayed_on_phone,
+        ])
+        .pop()
+        .unwrap();
+        assert!(reduced_message.flags.is_delivered);
+        assert!(reduced_message.flags.is_read);
+
+        // A displayed marker alone implies delivery.
+        let reduced_message = Message::reducing_messages([message, displayed_on_desktop])
+            .pop()
+            .unwrap();
+        assert!(reduced_message.flags.is_delivered);
+        assert!(reduced_message.flags.is_read);
+
+        Ok(())
+    }
+
     #[test]
     fn test_toggle_reaction() {
         let mut message = MessageBuilder::new_with_index(1).build_message();
@@ -349,7 +569,8 @@ mod tests {
             message.reactions,
             vec![Reaction {
                 emoji: "🎉".into(),
-                from: vec![user_id!("a@prose.org").into()]
+                from: vec![user_id!("a@prose.org").into()],
+                last_updated: None,
             }]
         );
 
@@ -361,7 +582,8 @@ mod tests {
                 from: vec![
                     user_id!("a@prose.org").into(),
                     user_id!("b@prose.org").into()
-                ]
+                ],
+                last_updated: None,
             }]
         );
 
@@ -374,11 +596,13 @@ mod tests {
                     from: vec![
                         user_id!("a@prose.org").into(),
                         user_id!("b@prose.org").into()
-                    ]
+                    ],
+                    last_updated: None,
                 },
                 Reaction {
                     emoji: "✅".into(),
-                    from: vec![user_id!("b@prose.org").into()]
+                    from: vec![user_id!("b@prose.org").into()],
+                    last_updated: None,
                 }
             ]
         );
@@ -389,11 +613,13 @@ mod tests {
             vec![
                 Reaction {
                     emoji: "🎉".into(),
-                    from: vec![user_id!("b@prose.org").into()]
+                    from: vec![user_id!("b@prose.org").into()],
+                    last_updated: None,
                 },
                 Reaction {
                     emoji: "✅".into(),
-                    from: vec![user_id!("b@prose.org").into()]
+                    from: vec![user_id!("b@prose.org").into()],
+                    last_updated: None,
                 }
             ]
         );
@@ -404,16 +630,224 @@ mod tests {
             vec![
                 Reaction {
                     emoji: "🎉".into(),
-                    from: vec![]
+                    from: vec![],
+                    last_updated: None,
                 },
                 Reaction {
                     emoji: "✅".into(),
-                    from: vec![user_id!("b@prose.org").into()]
+                    from: vec![user_id!("b@prose.org").into()],
+                    last_updated: None,
                 }
             ]
         );
     }
 
+    #[test]
+    fn test_counts_thread_replies() {
+        let messages = vec![
+            MessageBuilder::new_with_index(1)
+                .set_payload(MessageLikePayload::message_in_thread(
+                    "Message 1",
+                    "thread-1",
+                ))
+                .build_message_like(),
+            MessageBuilder::new_with_index(2).build_message_like(),
+            MessageBuilder::new_with_index(3)
+                .set_payload(MessageLikePayload::message_in_thread(
+                    "Message 3",
+                    "thread-1",
+                ))
+                .build_message_like(),
+            MessageBuilder::new_with_index(4)
+                .set_payload(MessageLikePayload::message_in_thread(
+                    "Message 4",
+                    "thread-1",
+                ))
+                .build_message_like(),
+        ];
+
+        let reduced_messages = Message::reducing_messages(messages);
+
+        assert_eq!(
+            vec![
+                (
+                    2,
+                    Some(MessageBuilder::new_with_index(4).build_message().timestamp)
+                ),
+                (0, None),
+                (0, None),
+                (0, None),
+            ],
+            reduced_messages
+                .into_iter()
+                .map(|message| (message.thread_reply_count, message.last_thread_reply_at))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_counts_thread_replies_in_newest_first_order() {
+        let messages = vec![
+            MessageBuilder::new_with_index(3)
+                .set_payload(MessageLikePayload::message_in_thread(
+                    "Message 3",
+                    "thread-1",
+                ))
+                .build_message_like(),
+            MessageBuilder::new_with_index(2)
+                .set_payload(MessageLikePayload::message_in_thread(
+                    "Message 2",
+                    "thread-1",
+                ))
+                .build_message_like(),
+            MessageBuilder::new_with_index(1)
+                .set_payload(MessageLikePayload::message_in_thread(
+                    "Message 1",
+                    "thread-1",
+                ))
+                .build_message_like(),
+        ];
+
+        let reduced_messages = Message::reducing_messages(messages);
+
+        assert_eq!(
+            vec![
+                (0, None),
+                (0, None),
+                (
+                    2,
+                    Some(MessageBuilder::new_with_index(3).build_message().timestamp)
+                ),
+            ],
+            reduced_messages
+                .into_iter()
+                .map(|message| (message.thread_reply_count, message.last_thread_reply_at))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_corrections_preserve_html_and_mentions() {
+        let mention = Mention {
+            user: user_id!("a@prose.org"),
+            range: None,
+        };
+
+        let messages = vec![
+            MessageBuilder::new_with_index(1).build_message_like(),
+            MessageBuilder::new_with_index(2)
+                .set_payload(MessageLikePayload::Correction {
+                    target_id: MessageBuilder::remote_id_for_index(1).into(),
+                    body: MessageLikeBody {
+                        raw: "Hello **@a**".to_string(),
+                        html: HTML::new("<p>Hello <strong>@a</strong></p>"),
+                        mentions: vec![mention.clone()],
+                    },
+                    attachments: vec![],
+                    encryption_info: None,
+                })
+                .build_message_like(),
+        ];
+
+        let reduced_messages = Message::reducing_messages(messages);
+        assert_eq!(1, reduced_messages.len());
+
+        let message = &reduced_messages[0];
+        assert_eq!(
+            Body {
+                raw: "Hello **@a**".to_string(),
+                html: HTML::new("<p>Hello <strong>@a</strong></p>"),
+            },
+            message.body
+        );
+        assert_eq!(vec![mention], message.mentions);
+        assert!(message.flags.is_edited);
+    }
+
+    #[test]
+    fn test_keeps_retracted_messages_as_tombstone() {
+        let mut payload = MessageLikePayload::message("Message 1");
+        if let MessageLikePayload::Message { attachments, .. } = &mut payload {
+            attachments.push(Attachment {
+                r#type: AttachmentType::File,
+                url: "https://upload.prose.org/file.pdf".parse().unwrap(),
+                media_type: mime::APPLICATION_PDF,
+                file_name: "file.pdf".to_string(),
+                file_size: None,
+            });
+        }
+
+        let messages = vec![
+            MessageBuilder::new_with_index(1)
+                .set_payload(payload)
+                .build_message_like(),
+            MessageBuilder::new_with_index(2)
+                .set_payload(MessageLikePayload::Retraction {
+                    target_id: MessageBuilder::remote_id_for_index(1).into(),
+                    reason: Some("Typo".to_string()),
+                    moderated_by: None,
+                })
+                .build_message_like(),
+        ];
+
+        let reduced_messages = Message::reducing_messages(messages);
+        assert_eq!(1, reduced_messages.len());
+
+        let message = &reduced_messages[0];
+        let original = MessageBuilder::new_with_index(1).build_message();
+
+        assert_eq!(original.id, message.id);
+        assert_eq!(original.timestamp, message.timestamp);
+        assert_eq!(
+            Body {
+                raw: "".to_string(),
+                html: HTML::new(""),
+            },
+            message.body
+        );
+        assert!(message.attachments.is_empty());
+        assert!(message.is_retracted);
+        assert_eq!(Some("Typo".to_string()), message.retraction_reason);
+        assert_eq!(None, message.moderated_by);
+    }
+
+    #[test]
+    fn test_replaces_moderated_messages_with_tombstone() {
+        let messages = vec![
+            MessageBuilder::new_with_index(1).build_message_like(),
+            MessageBuilder::new_with_index(2)
+                .set_payload(MessageLikePayload::Retraction {
+                    target_id: MessageBuilder::stanza_id_for_index(1).into(),
+                    reason: Some("Spam".to_string()),
+                    moderated_by: Some(occupant_id!("room@conf.prose.org/mod").into()),
+                })
+                .build_message_like(),
+        ];
+
+        let reduced_messages = Message::reducing_messages(messages);
+
+        assert_eq!(
+            vec![(
+                Body {
+                    raw: "".to_string(),
+                    html: HTML::new(""),
+                },
+                true,
+                Some("Spam".to_string()),
+                Some(occupant_id!("room@conf.prose.org/mod").into())
+            )],
+            reduced_messages
+                .into_iter()
+                .map(|message| (
+                    message.body,
+                    message.is_retracted,
+                    message.retraction_reason,
+                    message.moderated_by
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn test_reactions_for_user() {
         let mut message = MessageBuilder::new_with_index(1).build_message();
@@ -424,10 +858,12 @@ mod tests {
                     user_id!("a@prose.org").into(),
                     user_id!("b@prose.org").into(),
                 ],
+                last_updated: None,
             },
             Reaction {
                 emoji: "✅".into(),
                 from: vec![user_id!("b@prose.org").into()],
+                last_updated: None,
             },
         ];
 
@@ -471,6 +907,7 @@ mod tests {
                     is_transient: false,
                     reply_to: None,
                     thread_id: None,
+                    is_markable: false,
                 },
             },
             MessageLike {
@@ -554,22 +991,43 @@ mod tests {
                 reactions: vec![
                     Reaction {
                         emoji: "👍".into(),
-                        from: vec![user_id!("c@prose.org").into()]
+                        from: vec![user_id!("c@prose.org").into()],
+                        last_updated: Some(Utc.with_ymd_and_hms(2023, 04, 07, 16, 00, 02).unwrap())
                     },
                     Reaction {
                         emoji: "📼".into(),
-                        from: vec![user_id!("b@prose.org").into()]
+                        from: vec![user_id!("b@prose.org").into()],
+                        last_updated: Some(Utc.with_ymd_and_hms(2023, 04, 07, 16, 00, 03).unwrap())
                     },
                     Reaction {
                         emoji: "🍿".into(),
-                        from: vec![user_id!("b@prose.org").into()]
+                        from: vec![user_id!("b@prose.org").into()],
+                        last_updated: Some(Utc.with_ymd_and_hms(2023, 04, 07, 16, 00, 03).unwrap())
                     }
                 ],
                 attachments: vec![],
                 mentions: vec![],
                 reply_to: None,
+                thread_id: None,
+                thread_reply_count: 0,
+                last_thread_reply_at: None,
+                is_retracted: false,
+                retraction_reason: None,
+                moderated_by: None,
             },
             reduced_message,
         )
     }
+
+    async fn parse_receipt(idx: u32, stanza: XMPPMessage) -> Result<MessageLike> {
+        MessageParser::new(
+            format!("receipt-{idx}").into(),
+            None,
+            Utc.with_ymd_and_hms(2023, 04, 07, 16, 00, idx).unwrap(),
+            Arc::new(MockEncryptionDomainService::new()),
+            None,
+        )
+        .parse_message(stanza.set_id(format!("receipt-mid-{idx}").into()))
+        .await
+    }
 }
@@ -22,6 +22,11 @@ id_string!(
     MessageId
 );
 
+id_string!(
+    /// The ID of the thread (XEP-0201) a message belongs to.
+    ThreadId
+);
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct MessageIdTriple {
     pub id: MessageId,
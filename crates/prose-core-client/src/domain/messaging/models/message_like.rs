@@ -11,9 +11,11 @@ use serde::{Deserialize, Serialize};
 
 use prose_xmpp::stanza::message;
 
-use crate::domain::encryption::models::DeviceId;
+use crate::domain::encryption::models::{DeviceId, Trust};
 use crate::domain::messaging::models::message_id::MessageId;
-use crate::domain::messaging::models::{Attachment, Mention, MessageTargetId, ReplyTo, ThreadId};
+use crate::domain::messaging::models::{
+    Attachment, EncryptedPayload, Mention, MessageTargetId, ReplyTo, ThreadId,
+};
 use crate::domain::shared::models::{ParticipantId, HTML};
 
 use super::{MessageRemoteId, MessageServerId};
@@ -35,6 +37,16 @@ pub struct MessageLike {
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct EncryptionInfo {
     pub sender: DeviceId,
+    /// Our trust in the sending device at the time the message was decrypted. `None` if unknown.
+    #[serde(default)]
+    pub trust: Option<Trust>,
+    /// Set if the message could not be decrypted. Its body is empty in that case.
+    #[serde(default)]
+    pub decryption_failed: bool,
+    /// The encrypted payload of a message that could not be decrypted, so that decrypting it can
+    /// be retried later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undecrypted_payload: Option<EncryptedPayload>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
@@ -88,6 +100,9 @@ pub enum Payload {
         is_transient: bool,
         reply_to: Option<ReplyTo>,
         thread_id: Option<ThreadId>,
+        /// Set if the sender requested chat markers (XEP-0333).
+        #[serde(default)]
+        is_markable: bool,
     },
     Reaction {
         target_id: MessageTargetId,
@@ -95,6 +110,12 @@ pub enum Payload {
     },
     Retraction {
         target_id: MessageTargetId,
+        /// The reason given for the retraction, if any.
+        #[serde(default)]
+        reason: Option<String>,
+        /// Set if the message was retracted by a moderator of a MUC room (XEP-0425).
+        #[serde(default)]
+        moderated_by: Option<ParticipantId>,
     },
 }
 
@@ -113,6 +134,29 @@ impl Payload {
         }
     }
 
+    /// Returns the body of messages and corrections.
+    pub fn body(&self) -> Option<&Body> {
+        match self {
+            Payload::Message { body, .. } | Payload::Correction { body, .. } => Some(body),
+            _ => None,
+        }
+    }
+
+    /// Returns the attachments of messages.
+    pub fn attachments(&self) -> &[Attachment] {
+        match self {
+            Payload::Message { attachments, .. } => attachments,
+            _ => &[],
+        }
+    }
+
+    pub fn thread_id(&self) -> Option<&ThreadId> {
+        match self {
+            Payload::Message { thread_id, .. } => thread_id.as_ref(),
+            _ => None,
+        }
+    }
+
     pub fn target_id(&self) -> Option<&MessageTargetId> {
         match self {
             Payload::Error { .. } => None,
@@ -121,7 +165,7 @@ impl Payload {
             Payload::ReadReceipt { target_id } => Some(target_id),
             Payload::Message { .. } => None,
             Payload::Reaction { target_id, .. } => Some(target_id),
-            Payload::Retraction { target_id } => Some(target_id),
+            Payload::Retraction { target_id, .. } => Some(target_id),
         }
     }
 }
@@ -6,6 +6,7 @@
 use anyhow::Result;
 use chrono::{DateTime, SubsecRound, Utc};
 use tracing::{error, warn};
+use url::Url;
 use xmpp_parsers::message::MessageType;
 
 use prose_xmpp::mods::chat::Carbon;
@@ -17,8 +18,8 @@ use crate::app::deps::DynEncryptionDomainService;
 use crate::domain::encryption::models::DecryptionContext;
 use crate::domain::messaging::models::message_like::Payload;
 use crate::domain::messaging::models::{
-    EncryptedMessage, MessageId, MessageLike, MessageLikeBody, MessageLikeEncryptionInfo,
-    MessageServerId, MessageTargetId, StanzaParseError,
+    Attachment, AttachmentEncryption, EncryptedMessage, MessageId, MessageLike, MessageLikeBody,
+    MessageLikeEncryptionInfo, MessageServerId, MessageTargetId, StanzaParseError,
 };
 use crate::domain::rooms::models::Room;
 use crate::domain::shared::models::{AnonOccupantId, RustStringRangeExt, StyledMessage};
@@ -152,16 +153,18 @@ impl MessageParser {
                         })
                 });
 
+            // Moderation notifications are sent from the bare JID of the room, so we're
+            // attributing them to the moderator instead.
             let from = from
                 .try_as_full()
-                .map_err(|_| StanzaParseError::ParseError {
+                .ok()
+                .cloned()
+                .or_else(|| message.moderator())
+                .ok_or_else(|| StanzaParseError::ParseError {
                     error: "Expected `from` attribute to contain FullJid for groupchat message"
                         .to_string(),
                 })?;
-            Ok((
-                ParticipantId::Occupant(OccupantId::from(from.clone())),
-                user_id,
-            ))
+            Ok((ParticipantId::Occupant(OccupantId::from(from)), user_id))
         } else {
             let user_id = UserId::from(from.to_bare());
             Ok((ParticipantId::User(user_id.clone()), Some(user_id)))
@@ -202,9 +205,19 @@ impl MessageParser {
         };
 
         if let Some(fastening) = message.fastening() {
-            if fastening.retract() {
+            if let Some(moderator) = message.moderator() {
+                return Ok(Payload::Retraction {
+                    target_id: MessageTargetId::ServerId(fastening.id.as_ref().into()),
+                    reason: fastening.moderated().and_then(|moderated| moderated.reason),
+                    moderated_by: Some(ParticipantId::Occupant(moderator.into())),
+                });
+            }
+
+            if let Some(retraction) = fastening.retraction() {
                 return Ok(Payload::Retraction {
                     target_id: MessageTargetId::RemoteId(fastening.id.as_ref().into()),
+                    reason: retraction.reason,
+                    moderated_by: None,
                 });
             }
         }
@@ -219,6 +232,12 @@ impl MessageParser {
             });
         }
 
+        if let Some(receipt) = message.delivery_receipt() {
+            return Ok(Payload::DeliveryReceipt {
+                target_id: MessageTargetId::RemoteId(receipt.id.into()),
+            });
+        }
+
         if let Some(marker) = message.displayed_marker() {
             return Ok(Payload::ReadReceipt {
                 target_id: if is_groupchat_message {
@@ -248,6 +267,14 @@ impl MessageParser {
                 ParsedMessageBody::EmptyMessage => return Err(MessageLikeError::NoPayload.into()),
             };
 
+            let mut attachments = message.attachments();
+            let fallback = match &encryption_info {
+                Some(info) if !info.decryption_failed => {
+                    extract_encrypted_attachments(fallback, &mut attachments)
+                }
+                _ => fallback,
+            };
+
             let mentions = message
                 .mentions()
                 .into_iter()
@@ -263,14 +290,14 @@ impl MessageParser {
                 })
                 .collect::<Vec<_>>();
 
-            let (raw, html, reply_to) = if let Some(markdown) = body {
+            // The quote of a reply is only contained in the plain <body>, but never in our
+            // Markdown content…
+            let reply_to = message.reply_to(fallback.as_ref());
+
+            let (raw, html) = if let Some(markdown) = body {
                 let html = markdown.to_html();
-                (markdown.into_string(), html, None)
+                (markdown.into_string(), html)
             } else {
-                // So far replies don't work with anything other than plain <body>, or in other
-                // words: Not with our Markdown content…
-                let reply_to = message.reply_to(fallback.as_ref());
-
                 let fallback = if let Some(reply_fallback_range) = message
                     .reply_fallback_range()
                     .and_then(|range| range.to_utf8_range(fallback.as_ref()).ok())
@@ -285,7 +312,7 @@ impl MessageParser {
                     fallback
                 };
 
-                (fallback.to_string(), fallback.into_html(), reply_to)
+                (fallback.to_string(), fallback.into_html())
             };
 
             if let Some(replace_id) = message.replace() {
@@ -296,7 +323,7 @@ impl MessageParser {
                         html,
                         mentions,
                     },
-                    attachments: message.attachments(),
+                    attachments,
                     encryption_info,
                 });
             }
@@ -307,13 +334,14 @@ impl MessageParser {
                     html,
                     mentions,
                 },
-                attachments: message.attachments(),
+                attachments,
                 encryption_info,
                 // A message that we consider a groupchat message but is of type 'chat' is
                 // usually a private message. We'll treat them as transient messages.
                 is_transient: is_groupchat_message && message.type_ == MessageType::Chat,
                 reply_to,
                 thread_id: message.thread.as_ref().map(|t| t.0.clone().into()),
+                is_markable: message.is_markable(),
             });
         }
 
@@ -333,17 +361,19 @@ impl MessageParser {
         if let (Some(sender_id), Some(omemo_element)) = (sender_id, message.omemo_element()) {
             let sender = DeviceId::from(omemo_element.header.sid);
             let encrypted_message = EncryptedMessage::from(omemo_element);
-            let decryption_result = match encrypted_message {
-                EncryptedMessage::Message(message) => {
-                    self.encryption_domain_service
+            let (decryption_result, payload) = match encrypted_message {
+                EncryptedMessage::Message(payload) => {
+                    let result = self
+                        .encryption_domain_service
                         .decrypt_message(
                             sender_id,
                             room_id,
                             Some(&self.message_id),
-                            message,
+                            payload.clone(),
                             self.decryption_context.clone(),
                         )
-                        .await
+                        .await;
+                    (result, payload)
                 }
                 EncryptedMessage::KeyTransport(payload) => {
                     let res = self
@@ -365,24 +395,31 @@ impl MessageParser {
             };
 
             let parsed_message = match decryption_result {
-                Ok(body) => ParsedMessageBody::EncryptedMessage(
-                    StyledMessage::new(body),
-                    MessageLikeEncryptionInfo { sender },
+                Ok(decrypted_message) => ParsedMessageBody::EncryptedMessage(
+                    StyledMessage::new(decrypted_message.message),
+                    MessageLikeEncryptionInfo {
+                        sender: decrypted_message.sender_device_id,
+                        trust: decrypted_message.trust,
+                        decryption_failed: false,
+                        undecrypted_payload: None,
+                    },
                 ),
                 Err(error) => {
                     error!(
                         "Failed to decrypt message from {sender_id}. {}",
                         error.to_string()
                     );
+                    // Rather than showing the sender's fallback text, we'll leave the body empty
+                    // so that the UI can show a localized placeholder. The payload is kept around
+                    // so that decrypting it can be retried.
                     ParsedMessageBody::EncryptedMessage(
-                        message
-                            .body()
-                            .unwrap_or(
-                                "Message failed to decrypt and did not contain a fallback text.",
-                            )
-                            .to_string()
-                            .into(),
-                        MessageLikeEncryptionInfo { sender },
+                        StyledMessage::new(""),
+                        MessageLikeEncryptionInfo {
+                            sender,
+                            trust: None,
+                            decryption_failed: true,
+                            undecrypted_payload: Some(payload),
+                        },
                     )
                 }
             };
@@ -398,6 +435,53 @@ impl MessageParser {
     }
 }
 
+/// Encrypted files (XEP-0454) are sent as `aesgcm://` URLs in the encrypted body, since their URLs
+/// contain the key. Moves these URLs from `body` to `attachments`.
+fn extract_encrypted_attachments(
+    body: StyledMessage,
+    attachments: &mut Vec<Attachment>,
+) -> StyledMessage {
+    let mut found_attachment = false;
+    let mut lines = vec![];
+
+    for line in body.as_ref().lines() {
+        match line.trim().parse::<Url>() {
+            Ok(url) if AttachmentEncryption::is_encrypted_url(&url) => {
+                found_attachment = true;
+                add_encrypted_url(url, attachments);
+            }
+            _ => lines.push(line),
+        }
+    }
+
+    if !found_attachment {
+        return body;
+    }
+
+    StyledMessage::new(lines.join("\n").trim())
+}
+
+/// Adds the key of `url` to the attachment or thumbnail with the matching download URL, which
+/// was sent along as plaintext metadata. Falls back to an attachment without metadata.
+fn add_encrypted_url(url: Url, attachments: &mut Vec<Attachment>) {
+    let download_url = AttachmentEncryption::download_url(&url);
+
+    for attachment in attachments.iter_mut() {
+        if attachment.url == url || attachment.url == download_url {
+            attachment.url = url;
+            return;
+        }
+        if let Some(thumbnail) = attachment.thumbnail_mut() {
+            if thumbnail.url == url || thumbnail.url == download_url {
+                thumbnail.url = url;
+                return;
+            }
+        }
+    }
+
+    attachments.push(Attachment::from(url))
+}
+
 /// Represents the body of the message.
 enum ParsedMessageBody {
     /// The message was sent unencrypted.
@@ -6,6 +6,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::domain::shared::models::UserId;
+
 use super::{MessageRemoteId, MessageServerId};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -19,3 +21,31 @@ pub struct MessageRef {
     pub id: MessageRemoteId,
     pub timestamp: DateTime<Utc>,
 }
+
+/// A reference to a pinned message. Since our local `MessageId`s differ between devices, the
+/// message is referenced by its remote and/or server id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PinnedMessageRef {
+    pub remote_id: Option<MessageRemoteId>,
+    pub stanza_id: Option<MessageServerId>,
+    pub timestamp: DateTime<Utc>,
+    pub pinned_by: UserId,
+}
+
+impl PinnedMessageRef {
+    /// Returns true if `self` references the message with the given ids. Server ids take
+    /// precedence over remote ids, since the latter are not guaranteed to be unique.
+    pub fn references(
+        &self,
+        remote_id: Option<&MessageRemoteId>,
+        stanza_id: Option<&MessageServerId>,
+    ) -> bool {
+        if let (Some(lhs), Some(rhs)) = (&self.stanza_id, stanza_id) {
+            return lhs == rhs;
+        }
+        if let (Some(lhs), Some(rhs)) = (&self.remote_id, remote_id) {
+            return lhs == rhs;
+        }
+        false
+    }
+}
@@ -3,7 +3,9 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-pub use attachment::{Attachment, AttachmentType, Thumbnail};
+pub use attachment::{Attachment, AttachmentKind, AttachmentType, Thumbnail};
+pub use attachment_encryption::AttachmentEncryption;
+pub use draft::Draft;
 pub use encrypted_message::{
     EncryptedMessage, EncryptedPayload, EncryptionKey, KeyTransportPayload,
 };
@@ -12,17 +14,21 @@ pub use mention::Mention;
 #[allow(unused_imports)] // Reaction is required in unit tests
 pub use message::{Body, Emoji, Message, MessageFlags, Reaction, ReplyTo};
 pub use message_id::{
-    MessageId, MessageIdTriple, MessageRemoteId, MessageServerId, MessageTargetId,
+    MessageId, MessageIdTriple, MessageRemoteId, MessageServerId, MessageTargetId, ThreadId,
 };
 pub use message_like::{
     Body as MessageLikeBody, EncryptionInfo as MessageLikeEncryptionInfo, MessageLike,
     Payload as MessageLikePayload,
 };
 pub use message_parser::{MessageLikeError, MessageParser};
-pub use message_ref::{ArchivedMessageRef, MessageRef};
+pub use message_ref::{ArchivedMessageRef, MessageRef, PinnedMessageRef};
+pub use pending_message::{PendingMessage, PendingMessageState};
+pub use reaction_error::ReactionError;
 pub use send_message_request::SendMessageRequest;
 
 mod attachment;
+mod attachment_encryption;
+mod draft;
 mod encrypted_message;
 mod error;
 mod mention;
@@ -31,4 +37,6 @@ mod message_id;
 mod message_like;
 mod message_parser;
 mod message_ref;
+mod pending_message;
+mod reaction_error;
 pub mod send_message_request;
@@ -0,0 +1,44 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::messaging::models::{Attachment, MessageId, ReplyTo};
+use crate::domain::shared::models::{Markdown, RoomId};
+
+/// A message that our user sent, but which didn't reach the server yet. Pending messages are
+/// kept in the outbox and are resent in their original order once we're back online.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingMessage {
+    pub id: MessageId,
+    pub room_id: RoomId,
+    pub body: Option<Markdown>,
+    pub attachments: Vec<Attachment>,
+    pub reply_to: Option<ReplyTo>,
+    pub timestamp: DateTime<Utc>,
+    pub state: PendingMessageState,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PendingMessageState {
+    /// The message waits to be (re-)sent.
+    Pending,
+    /// Sending the message failed permanently. It will only be resent on request of the user.
+    Failed { error: String },
+}
+
+impl PendingMessage {
+    pub fn is_pending(&self) -> bool {
+        self.state == PendingMessageState::Pending
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        match &self.state {
+            PendingMessageState::Pending => None,
+            PendingMessageState::Failed { error } => Some(error),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ReactionError {
+    /// Reactions in MUC rooms reference the StanzaId which is assigned by the room. Messages
+    /// that haven't been reflected by the room yet don't have one and can't be reacted to
+    /// until they do.
+    #[error("Cannot react to MUC message for which we do not have a StanzaId.")]
+    MissingServerId,
+    #[error("Cannot react to message for which we do not have a RemoteId.")]
+    MissingRemoteId,
+}
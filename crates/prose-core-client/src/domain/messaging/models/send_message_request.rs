@@ -5,7 +5,7 @@
 
 use crate::domain::shared::models::{Markdown, StyledMessage};
 
-use super::{Attachment, Mention};
+use super::{Attachment, Mention, ReplyTo};
 use super::{EncryptedPayload, MessageId};
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,6 +13,7 @@ pub struct SendMessageRequest {
     pub id: MessageId,
     pub body: Option<Body>,
     pub attachments: Vec<Attachment>,
+    pub reply_to: Option<ReplyTo>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
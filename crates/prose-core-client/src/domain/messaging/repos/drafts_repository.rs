@@ -8,6 +8,7 @@ use async_trait::async_trait;
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
+use crate::domain::messaging::models::Draft;
 use crate::domain::shared::models::AccountId;
 use crate::dtos::RoomId;
 
@@ -15,7 +16,8 @@ use crate::dtos::RoomId;
 #[async_trait]
 #[cfg_attr(feature = "test", mockall::automock)]
 pub trait DraftsRepository: SendUnlessWasm + SyncUnlessWasm {
-    async fn get(&self, account: &AccountId, room_id: &RoomId) -> Result<Option<String>>;
-    async fn set(&self, account: &AccountId, room_id: &RoomId, draft: Option<&str>) -> Result<()>;
+    async fn get(&self, account: &AccountId, room_id: &RoomId) -> Result<Option<Draft>>;
+    async fn set(&self, account: &AccountId, room_id: &RoomId, draft: Option<&Draft>)
+        -> Result<()>;
     async fn clear_cache(&self, account: &AccountId) -> Result<()>;
 }
@@ -3,6 +3,8 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::HashSet;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -10,10 +12,10 @@ use chrono::{DateTime, Utc};
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
 use crate::domain::messaging::models::{
-    ArchivedMessageRef, MessageId, MessageIdTriple, MessageLike, MessageRemoteId, MessageServerId,
-    MessageTargetId,
+    ArchivedMessageRef, Attachment, AttachmentKind, MessageId, MessageIdTriple, MessageLike,
+    MessageRemoteId, MessageServerId, MessageTargetId, ThreadId,
 };
-use crate::domain::shared::models::{AccountId, RoomId};
+use crate::domain::shared::models::{AccountId, RoomId, StoreUsage};
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[async_trait]
@@ -48,14 +50,40 @@ pub trait MessagesRepository: SendUnlessWasm + SyncUnlessWasm {
         room_id: &RoomId,
         id: &MessageServerId,
     ) -> Result<bool>;
+    /// Returns the IDs of those `messages` that are cached already, identified by their server
+    /// ID or, if they don't have one, by their remote ID. All messages are looked up in a single
+    /// transaction.
+    async fn find_cached(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        messages: &[MessageLike],
+    ) -> Result<HashSet<MessageId>>;
     async fn append(
         &self,
         account: &AccountId,
         room_id: &RoomId,
         messages: &[MessageLike],
     ) -> Result<()>;
+    /// Deletes the message with `id`. Messages targeting it are kept.
+    async fn delete(&self, account: &AccountId, room_id: &RoomId, id: &MessageId) -> Result<()>;
+    /// Deletes all messages of the room identified by `room_id`.
+    async fn clear_room(&self, account: &AccountId, room_id: &RoomId) -> Result<()>;
+    /// Deletes all messages (and their attachments) of the room identified by `room_id` that
+    /// were sent before `before`. Deletes happen in transactions of up to `batch_size` messages.
+    /// Returns the number of deleted messages.
+    async fn delete_messages_before(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        before: DateTime<Utc>,
+        batch_size: u32,
+    ) -> Result<usize>;
     async fn clear_cache(&self, account: &AccountId) -> Result<()>;
 
+    /// Returns the space taken up by the cached messages and attachments of `account`.
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage>;
+
     async fn resolve_server_id(
         &self,
         account: &AccountId,
@@ -86,6 +114,26 @@ pub trait MessagesRepository: SendUnlessWasm + SyncUnlessWasm {
         before: Option<DateTime<Utc>>,
     ) -> Result<Option<ArchivedMessageRef>>;
 
+    /// Returns up to `limit` messages and corrections whose body contains all words of `query`,
+    /// either in the room with `room_id` or in all rooms if `room_id` is `None`. Sorted
+    /// newest-first.
+    async fn search(
+        &self,
+        account: &AccountId,
+        room_id: Option<&RoomId>,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<MessageLike>>;
+
+    /// Returns all messages (without the messages targeting them) that belong to the thread with
+    /// `thread_id`. Sorted chronologically.
+    async fn get_thread(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        thread_id: &ThreadId,
+    ) -> Result<Vec<MessageLike>>;
+
     /// Returns all messages with a timestamp greater than `after`.
     async fn get_messages_after(
         &self,
@@ -93,4 +141,45 @@ pub trait MessagesRepository: SendUnlessWasm + SyncUnlessWasm {
         room_id: &RoomId,
         after: DateTime<Utc>,
     ) -> Result<Vec<MessageLike>>;
+
+    /// Returns the `limit` newest messages of the room. Sorted chronologically.
+    async fn get_latest_messages(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        limit: u32,
+    ) -> Result<Vec<MessageLike>>;
+
+    /// Returns the timestamp of the `n`th newest message of the room or `None` if the room
+    /// contains fewer messages. Only messages are counted, not the reactions, retractions or
+    /// corrections targeting them.
+    async fn get_nth_latest_message_timestamp(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        n: u32,
+    ) -> Result<Option<DateTime<Utc>>>;
+
+    /// Returns up to `limit` messages whose timestamp lies between `start` and `end` (both
+    /// included). Sorted chronologically.
+    async fn get_messages_in_range(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<MessageLike>>;
+
+    /// Returns the attachments of the messages in the room with `room_id` whose kind is
+    /// contained in `kinds`, or all attachments if `kinds` is empty. Sorted newest-first, skipping
+    /// `offset` attachments and returning up to `limit` attachments.
+    async fn get_attachments(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        kinds: &[AttachmentKind],
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Attachment>>;
 }
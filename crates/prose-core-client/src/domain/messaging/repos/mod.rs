@@ -6,14 +6,17 @@
 pub use drafts_repository::DraftsRepository;
 pub use messages_repository::MessagesRepository;
 pub use offline_messages_repository::OfflineMessagesRepository;
+pub use pending_messages_repository::PendingMessagesRepository;
 
 mod drafts_repository;
 mod messages_repository;
 mod offline_messages_repository;
+mod pending_messages_repository;
 
 #[cfg(feature = "test")]
 pub mod mocks {
     pub use super::drafts_repository::MockDraftsRepository;
     pub use super::messages_repository::MockMessagesRepository;
     pub use super::offline_messages_repository::MockOfflineMessagesRepository;
+    pub use super::pending_messages_repository::MockPendingMessagesRepository;
 }
@@ -0,0 +1,36 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
+
+use crate::domain::messaging::models::{MessageId, PendingMessage};
+use crate::domain::shared::models::AccountId;
+use crate::dtos::RoomId;
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+#[cfg_attr(feature = "test", mockall::automock)]
+pub trait PendingMessagesRepository: SendUnlessWasm + SyncUnlessWasm {
+    /// Returns all pending messages of `account`. Sorted chronologically.
+    async fn get_all(&self, account: &AccountId) -> Result<Vec<PendingMessage>>;
+    /// Returns all pending messages in `room_id`. Sorted chronologically.
+    async fn get_all_in_room(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+    ) -> Result<Vec<PendingMessage>>;
+    async fn get(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        id: &MessageId,
+    ) -> Result<Option<PendingMessage>>;
+    async fn put(&self, account: &AccountId, message: &PendingMessage) -> Result<()>;
+    async fn delete(&self, account: &AccountId, room_id: &RoomId, id: &MessageId) -> Result<()>;
+    async fn clear_cache(&self, account: &AccountId) -> Result<()>;
+}
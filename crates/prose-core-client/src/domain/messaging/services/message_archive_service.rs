@@ -46,4 +46,11 @@ pub trait MessageArchiveService: SendUnlessWasm + SyncUnlessWasm {
         since: DateTime<Utc>,
         batch_size: u32,
     ) -> Result<MessagePage>;
+
+    /// Returns the messages with the given ids. Requires urn:xmpp:mam:2#extended.
+    async fn load_messages_with_ids(
+        &self,
+        room_id: &RoomId,
+        ids: &[MessageServerId],
+    ) -> Result<Vec<ArchivedMessage>>;
 }
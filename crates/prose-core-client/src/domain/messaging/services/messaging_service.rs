@@ -10,10 +10,31 @@ use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 use prose_xmpp::stanza::message::mam::ArchivedMessage;
 
 use crate::domain::messaging::models::{
-    Emoji, KeyTransportPayload, MessageRemoteId, MessageServerId, SendMessageRequest, ThreadId,
+    Emoji, KeyTransportPayload, MessageRemoteId, MessageServerId, MessageTargetId,
+    SendMessageRequest, ThreadId,
 };
 use crate::domain::shared::models::RoomId;
-use crate::dtos::{MucId, UserId};
+use crate::dtos::{MucId, OccupantId, UserId};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SendMessageError {
+    #[error(
+        "The message stanza is too large to be sent ({size} bytes, maximum is {max_size} bytes)."
+    )]
+    StanzaTooLarge { size: usize, max_size: usize },
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl SendMessageError {
+    /// Returns `true` if sending the message again will fail the same way.
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            Self::StanzaTooLarge { .. } => true,
+            Self::Other(_) => false,
+        }
+    }
+}
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[async_trait]
@@ -21,6 +42,10 @@ use crate::dtos::{MucId, UserId};
 pub trait MessagingService: SendUnlessWasm + SyncUnlessWasm {
     async fn send_message(&self, room_id: &RoomId, request: SendMessageRequest) -> Result<()>;
 
+    /// Whether the server acknowledges the receipt of the messages we send (XEP-0198). If it
+    /// doesn't, a direct message counts as sent once it was handed to the connection.
+    fn acknowledges_sent_messages(&self) -> bool;
+
     async fn send_message_to_thread(
         &self,
         room_id: &RoomId,
@@ -28,6 +53,13 @@ pub trait MessagingService: SendUnlessWasm + SyncUnlessWasm {
         request: SendMessageRequest,
     ) -> Result<()>;
 
+    /// Sends a private message to a single occupant of a MUC room.
+    async fn send_private_message(
+        &self,
+        occupant_id: &OccupantId,
+        request: SendMessageRequest,
+    ) -> Result<()>;
+
     async fn send_key_transport_message(
         &self,
         user_id: &UserId,
@@ -41,7 +73,12 @@ pub trait MessagingService: SendUnlessWasm + SyncUnlessWasm {
         body: SendMessageRequest,
     ) -> Result<()>;
 
-    async fn retract_message(&self, room_id: &RoomId, message_id: &MessageRemoteId) -> Result<()>;
+    async fn retract_message(
+        &self,
+        room_id: &RoomId,
+        message_id: &MessageRemoteId,
+        reason: Option<&str>,
+    ) -> Result<()>;
 
     async fn react_to_chat_message(
         &self,
@@ -59,9 +96,14 @@ pub trait MessagingService: SendUnlessWasm + SyncUnlessWasm {
 
     async fn set_user_is_composing(&self, room_id: &RoomId, is_composing: bool) -> Result<()>;
 
-    async fn send_read_receipt(&self, room_id: &RoomId, message_id: &MessageRemoteId)
+    /// Sends a displayed marker (XEP-0333) for `message_id`, which is the message's remote id in
+    /// direct messages and its server id in MUC rooms.
+    async fn send_read_receipt(&self, room_id: &RoomId, message_id: &MessageTargetId)
         -> Result<()>;
 
+    /// Acknowledges the receipt of the direct message `message_id` (XEP-0184) sent by `to`.
+    async fn send_delivery_receipt(&self, to: &UserId, message_id: &MessageRemoteId) -> Result<()>;
+
     async fn relay_archived_message_to_room(
         &self,
         room_id: &RoomId,
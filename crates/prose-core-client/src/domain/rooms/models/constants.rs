@@ -3,4 +3,19 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::time::Duration;
+
 pub const MAX_PARTICIPANTS_PER_GROUP: usize = 9;
+
+/// The interval in which a sent 'composing' chat state is checked for having timed out.
+pub const COMPOSE_STATE_TIMER_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The maximum number of user profiles that are loaded concurrently when resolving the profiles
+/// of a room's participants.
+pub const MAX_CONCURRENT_PROFILE_REQUESTS: usize = 5;
+
+/// The maximum number of resolved message senders that are cached per room.
+pub const MAX_CACHED_MESSAGE_SENDERS: usize = 500;
+
+/// The maximum number of message ids that are tracked per occupant whose real id is unknown.
+pub const MAX_TRACKED_MESSAGES_PER_UNRESOLVED_SENDER: usize = 500;
@@ -8,8 +8,11 @@ pub use participant_list::{Participant, ParticipantList, ParticipantName, Regist
 pub use public_room_info::PublicRoomInfo;
 pub use room::{Room, RoomInfo, RoomSidebarState, RoomState};
 pub use room_affiliation::RoomAffiliation;
+pub use room_configuration::RoomConfiguration;
 pub use room_error::RoomError;
 pub use room_features::RoomFeatures;
+pub use room_removal_reason::RoomRemovalReason;
+pub use room_role::RoomRole;
 pub use room_session_info::{
     RoomConfig, RoomSessionInfo, RoomSessionMember, RoomSessionParticipant,
 };
@@ -21,7 +24,10 @@ mod participant_list;
 mod public_room_info;
 mod room;
 mod room_affiliation;
+mod room_configuration;
 mod room_error;
 mod room_features;
+mod room_removal_reason;
+mod room_role;
 mod room_session_info;
 mod room_spec;
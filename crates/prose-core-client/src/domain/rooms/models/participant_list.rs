@@ -14,7 +14,7 @@ use crate::domain::shared::models::{
 use crate::domain::shared::utils::ContactNameBuilder;
 use crate::domain::user_info::models::{Avatar, JabberClient, Presence};
 
-use super::{ComposeState, RoomAffiliation, RoomSessionParticipant};
+use super::{ComposeState, RoomAffiliation, RoomRole, RoomSessionParticipant};
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct ParticipantName {
@@ -37,6 +37,20 @@ impl ParticipantName {
 pub struct ParticipantList {
     anon_occupant_id_to_participant_id_map: HashMap<AnonOccupantId, ParticipantId>,
     participants_map: HashMap<ParticipantId, Participant>,
+    /// The maximum number of participants to keep. Unbounded if `None`.
+    capacity: Option<usize>,
+    /// Incremented for every change to a participant. Used to determine which participants
+    /// were least recently active.
+    activity_counter: u64,
+    last_activity: HashMap<ParticipantId, u64>,
+    /// Participants that were dropped to stay within `capacity` mapped to their real id (if
+    /// known). They're still part of the room but their details need to be loaded on demand.
+    evicted_participants: HashMap<ParticipantId, Option<UserId>>,
+    /// Incremented whenever participants are added or removed or their name or avatar change.
+    /// Presence and role changes don't affect it, since they're too frequent in large rooms.
+    revision: u64,
+    /// Incremented whenever participants are added or removed or their real id changes.
+    members_revision: u64,
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -47,6 +61,7 @@ pub struct Participant {
     pub name: ParticipantName,
     pub is_self: bool,
     pub affiliation: RoomAffiliation,
+    pub role: RoomRole,
     pub availability: Availability,
     pub avatar: Option<Avatar>,
     pub client: Option<JabberClient>,
@@ -87,6 +102,7 @@ impl ParticipantList {
                     },
                     is_self: false,
                     affiliation: RoomAffiliation::Owner,
+                    role: RoomRole::None,
                     availability: presence.availability,
                     avatar: presence.avatar,
                     client: presence.client,
@@ -96,6 +112,7 @@ impl ParticipantList {
                     compose_state_updated: Default::default(),
                 },
             )]),
+            ..Default::default()
         }
     }
 
@@ -127,6 +144,7 @@ impl ParticipantList {
                 },
                 is_self: p.is_self,
                 affiliation: p.affiliation,
+                role: p.role,
                 availability: p.presence.availability,
                 avatar: p.presence.avatar,
                 client: p.presence.client,
@@ -162,9 +180,18 @@ impl ParticipantList {
         Self {
             anon_occupant_id_to_participant_id_map,
             participants_map,
+            ..Default::default()
         }
     }
 
+    /// Limits the number of participants kept in memory to `capacity`. Owners, admins and
+    /// ourselves are always kept. Of the remaining participants the ones that were least
+    /// recently active are evicted first.
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        self.evict_participants_if_needed();
+    }
+
     /// Modifies the participant's availability or inserts a new participant with the availability
     /// if it didn't exist.
     pub fn set_availability(
@@ -173,6 +200,15 @@ impl ParticipantList {
         is_self: bool,
         availability: Availability,
     ) {
+        // No need to bring back an evicted participant only to mark them as unavailable…
+        if availability == Availability::Unavailable
+            && !self.participants_map.contains_key(id)
+            && self.evicted_participants.contains_key(id)
+        {
+            return;
+        }
+
+        let is_new = !self.participants_map.contains_key(id);
         let participant = self.participants_map.entry(id.clone()).or_default();
         participant.is_self = is_self;
         participant.availability = availability;
@@ -180,6 +216,8 @@ impl ParticipantList {
         if availability == Availability::Unavailable {
             participant.compose_state = ComposeState::Idle;
         }
+
+        self.did_change_participant(id, is_new);
     }
 
     /// Modifies the participant's presence or inserts a new participant with the presence
@@ -187,12 +225,22 @@ impl ParticipantList {
     pub fn set_presence(&mut self, id: &ParticipantId, is_self: bool, presence: Presence) {
         self.set_availability(id, is_self, presence.availability);
 
-        let participant = self.participants_map.entry(id.clone()).or_default();
+        let Some(participant) = self.participants_map.get_mut(id) else {
+            return;
+        };
+
+        let did_change_name_or_avatar =
+            participant.name.presence != presence.nickname || participant.avatar != presence.avatar;
+
         participant.name.presence = presence.nickname;
         participant.avatar = presence.avatar;
         participant.client = presence.client;
         participant.caps = presence.caps;
         participant.status = presence.status;
+
+        if did_change_name_or_avatar {
+            self.revision += 1;
+        }
     }
 
     /// Modifies the participant's affiliation or inserts a new participant with the affiliation
@@ -203,9 +251,22 @@ impl ParticipantList {
         is_self: bool,
         affiliation: RoomAffiliation,
     ) {
+        let is_new = !self.participants_map.contains_key(id);
         let participant = self.participants_map.entry(id.clone()).or_default();
         participant.affiliation = affiliation;
         participant.is_self = is_self;
+
+        self.did_change_participant(id, is_new);
+    }
+
+    /// Sets the participant's role. Does nothing if the participant doesn't exist.
+    pub fn set_role(&mut self, id: &ParticipantId, role: RoomRole) {
+        let Some(participant) = self.participants_map.get_mut(id) else {
+            return;
+        };
+        participant.role = role;
+
+        self.did_change_participant(id, false);
     }
 
     /// Sets the participant's compose state. Does nothing if the participant doesn't exist.
@@ -221,15 +282,22 @@ impl ParticipantList {
                 participant.compose_state = compose_state;
                 participant.compose_state_updated = timestamp.clone()
             });
+
+        if self.participants_map.contains_key(id) {
+            self.touch(id);
+        }
     }
 
     /// Sets the participant's avatar. Does nothing if the participant doesn't exist.
     pub fn set_avatar(&mut self, id: &ParticipantId, avatar: Option<Avatar>) {
-        self.participants_map
-            .entry(id.clone())
-            .and_modify(|participant| {
-                participant.avatar = avatar;
-            });
+        let Some(participant) = self.participants_map.get_mut(id) else {
+            return;
+        };
+
+        if participant.avatar != avatar {
+            participant.avatar = avatar;
+            self.revision += 1;
+        }
     }
 
     pub fn add_user(
@@ -257,6 +325,8 @@ impl ParticipantList {
         participant.affiliation = affiliation;
         participant.is_self = is_self;
         participant.name.vcard = name;
+
+        self.did_change_participant(&ParticipantId::User(real_id.clone()), true);
     }
 
     /// Sets the participant's real id, anonymous occupant id and name. Does nothing if the
@@ -268,18 +338,30 @@ impl ParticipantList {
         anon_occupant_id: Option<&AnonOccupantId>,
         name: Option<String>,
     ) {
-        let Some(participant) = self.participants_map.get_mut(id) else {
+        let did_change_real_id;
+
+        if let Some(evicted_real_id) = self.evicted_participants.get_mut(id) {
+            did_change_real_id = evicted_real_id.as_ref() != real_id;
+            *evicted_real_id = real_id.cloned();
+        } else if let Some(participant) = self.participants_map.get_mut(id) {
+            did_change_real_id = participant.real_id.as_ref() != real_id;
+            participant.real_id = real_id.cloned();
+            participant.anon_occupant_id = anon_occupant_id.cloned();
+            participant.name.vcard = name;
+        } else {
             return;
-        };
-
-        participant.real_id = real_id.cloned();
-        participant.anon_occupant_id = anon_occupant_id.cloned();
-        participant.name.vcard = name;
+        }
 
         // Remove registered user matching the real id…
         if let Some(real_id) = real_id {
-            self.participants_map
-                .remove(&ParticipantId::User(real_id.clone()));
+            let user_id = ParticipantId::User(real_id.clone());
+            let did_remove_user = self.participants_map.remove(&user_id).is_some()
+                | self.evicted_participants.remove(&user_id).is_some();
+            self.last_activity.remove(&user_id);
+
+            if did_remove_user {
+                self.members_revision += 1;
+            }
         }
 
         self.anon_occupant_id_to_participant_id_map
@@ -289,6 +371,11 @@ impl ParticipantList {
             self.anon_occupant_id_to_participant_id_map
                 .insert(anon_occupant_id.clone(), id.clone());
         }
+
+        self.revision += 1;
+        if did_change_real_id {
+            self.members_revision += 1;
+        }
     }
 
     pub fn get_user_id(&self, anon_occupant_id: &AnonOccupantId) -> Option<UserId> {
@@ -299,14 +386,49 @@ impl ParticipantList {
             return None;
         };
 
-        self.participants_map
-            .get(participant_id)
-            .and_then(|participant| participant.real_id.clone())
+        match self.participants_map.get(participant_id) {
+            Some(participant) => participant.real_id.clone(),
+            None => self
+                .evicted_participants
+                .get(participant_id)
+                .cloned()
+                .flatten(),
+        }
     }
 
     /// Removes the participant. Does nothing if the participant doesn't exist.
     pub fn remove(&mut self, id: &ParticipantId) {
-        self.participants_map.remove(id);
+        let did_remove = self.participants_map.remove(id).is_some()
+            | self.evicted_participants.remove(id).is_some();
+        self.last_activity.remove(id);
+
+        if did_remove {
+            self.revision += 1;
+            self.members_revision += 1;
+        }
+    }
+
+    /// Marks the participant as recently active (e.g. because they sent a message), so that
+    /// they're kept when participants need to be evicted. Does nothing if the participant
+    /// doesn't exist.
+    pub fn touch(&mut self, id: &ParticipantId) {
+        if !self.participants_map.contains_key(id) {
+            return;
+        }
+        self.activity_counter += 1;
+        self.last_activity.insert(id.clone(), self.activity_counter);
+    }
+
+    /// Returns a number that changes whenever participants are added or removed or their name or
+    /// avatar change. Can be used to invalidate information derived from the participants.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns a number that changes whenever participants are added or removed or their real
+    /// id changes, i.e. whenever the users that messages need to be encrypted for may change.
+    pub fn members_revision(&self) -> u64 {
+        self.members_revision
     }
 
     /// Returns the participant identified by `id` if it exists.
@@ -324,10 +446,35 @@ impl ParticipantList {
         self.participants_map.values()
     }
 
-    /// Returns the number of participants.
+    /// Returns the number of participants kept in memory.
     pub fn len(&self) -> usize {
         self.participants_map.len()
     }
+
+    /// Returns the number of participants including the ones that were evicted.
+    pub fn total_len(&self) -> usize {
+        self.participants_map.len() + self.evicted_participants.len()
+    }
+
+    /// Returns true if participants were evicted, i.e. if the list is not complete.
+    pub fn is_truncated(&self) -> bool {
+        !self.evicted_participants.is_empty()
+    }
+
+    /// Returns true if the participant was evicted to stay within the list's capacity.
+    pub fn is_evicted(&self, id: &ParticipantId) -> bool {
+        self.evicted_participants.contains_key(id)
+    }
+
+    /// Returns the real id of an evicted participant if it is known.
+    pub fn evicted_participant_real_id(&self, id: &ParticipantId) -> Option<&UserId> {
+        self.evicted_participants.get(id).and_then(Option::as_ref)
+    }
+
+    /// Returns the ids of the participants that were evicted.
+    pub fn evicted_participant_ids(&self) -> impl Iterator<Item = &ParticipantId> {
+        self.evicted_participants.keys()
+    }
 }
 
 impl ParticipantList {
@@ -354,10 +501,62 @@ impl ParticipantList {
     }
 }
 
+impl ParticipantList {
+    fn did_change_participant(&mut self, id: &ParticipantId, is_new: bool) {
+        self.evicted_participants.remove(id);
+        self.touch(id);
+        self.evict_participants_if_needed();
+
+        if is_new {
+            self.revision += 1;
+            self.members_revision += 1;
+        }
+    }
+
+    fn evict_participants_if_needed(&mut self) {
+        let Some(capacity) = self.capacity else {
+            return;
+        };
+
+        let excess = self.participants_map.len().saturating_sub(capacity);
+        if excess == 0 {
+            return;
+        }
+
+        // Prefer evicting unavailable participants, then the least recently active ones.
+        let evicted_ids = self
+            .participants_map
+            .iter()
+            .filter(|(_, participant)| {
+                !participant.is_self
+                    && participant.affiliation < RoomAffiliation::Admin
+                    && participant.compose_state == ComposeState::Idle
+            })
+            .sorted_by_key(|(id, participant)| {
+                (
+                    participant.availability != Availability::Unavailable,
+                    self.last_activity.get(*id).copied().unwrap_or_default(),
+                )
+            })
+            .take(excess)
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+
+        for id in evicted_ids {
+            let participant = self.participants_map.remove(&id);
+            self.last_activity.remove(&id);
+            self.evicted_participants
+                .insert(id, participant.and_then(|p| p.real_id));
+        }
+    }
+}
+
 #[cfg(feature = "test")]
 impl ParticipantList {
     pub fn extend_participants(&mut self, participants: HashMap<ParticipantId, Participant>) {
         self.participants_map.extend(participants);
+        self.revision += 1;
+        self.members_revision += 1;
     }
 }
 
@@ -547,6 +746,7 @@ mod tests {
                 anon_id: None,
                 real_id: Some(user_id!("a@prose.org")),
                 affiliation: RoomAffiliation::Member,
+                role: RoomRole::Participant,
                 presence: Presence {
                     availability: Availability::Available,
                     ..Default::default()
@@ -563,6 +763,7 @@ mod tests {
                         real_id: Some(user_id!("a@prose.org")),
                         name: ParticipantName::from_vcard("User A"),
                         affiliation: RoomAffiliation::Member,
+                        role: RoomRole::Participant,
                         availability: Availability::Available,
                         ..Default::default()
                     }
@@ -606,6 +807,7 @@ mod tests {
                         real_id: Some(user_id!("a@prose.org")),
                         name: ParticipantName::from_vcard("User A"),
                         affiliation: RoomAffiliation::Member,
+                        role: RoomRole::Participant,
                         availability: Availability::Available,
                         ..Default::default()
                     }
@@ -624,6 +826,177 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_evicts_least_recently_active_participants() {
+        let mut list = ParticipantList::default();
+        list.set_capacity(Some(2));
+
+        list.set_affiliation(
+            &occupant_id!("room@prose.org/owner").into(),
+            false,
+            RoomAffiliation::Owner,
+        );
+        list.set_availability(
+            &occupant_id!("room@prose.org/a").into(),
+            false,
+            Availability::Available,
+        );
+        list.set_ids_and_name(
+            &occupant_id!("room@prose.org/a").into(),
+            Some(&user_id!("a@prose.org")),
+            None,
+            None,
+        );
+        list.set_availability(
+            &occupant_id!("room@prose.org/b").into(),
+            false,
+            Availability::Available,
+        );
+
+        // The owner is kept even though they were the least recently active…
+        assert!(list
+            .get(&occupant_id!("room@prose.org/owner").into())
+            .is_some());
+        assert!(list.get(&occupant_id!("room@prose.org/a").into()).is_none());
+        assert!(list.get(&occupant_id!("room@prose.org/b").into()).is_some());
+        assert!(list.is_evicted(&occupant_id!("room@prose.org/a").into()));
+        assert_eq!(
+            list.evicted_participant_real_id(&occupant_id!("room@prose.org/a").into()),
+            Some(&user_id!("a@prose.org"))
+        );
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.total_len(), 3);
+
+        // An evicted participant leaving doesn't bring them back…
+        list.set_availability(
+            &occupant_id!("room@prose.org/a").into(),
+            false,
+            Availability::Unavailable,
+        );
+        assert!(list.is_evicted(&occupant_id!("room@prose.org/a").into()));
+
+        // …but a new presence does.
+        list.set_availability(
+            &occupant_id!("room@prose.org/a").into(),
+            false,
+            Availability::Away,
+        );
+        assert!(list.get(&occupant_id!("room@prose.org/a").into()).is_some());
+        assert!(list.is_evicted(&occupant_id!("room@prose.org/b").into()));
+        assert_eq!(list.total_len(), 3);
+
+        list.remove(&occupant_id!("room@prose.org/b").into());
+        assert!(!list.is_truncated());
+        assert_eq!(list.total_len(), 2);
+    }
+
+    #[test]
+    fn test_evicts_multiple_participants_when_shrinking_capacity() {
+        let mut list = ParticipantList::default();
+
+        for nick in ["a", "b", "c", "d"] {
+            list.set_availability(
+                &occupant_id!(format!("room@prose.org/{nick}")).into(),
+                false,
+                Availability::Available,
+            );
+        }
+        list.set_availability(
+            &occupant_id!("room@prose.org/b").into(),
+            false,
+            Availability::Unavailable,
+        );
+
+        list.set_capacity(Some(2));
+
+        // Unavailable participants go first, then the least recently active ones…
+        assert!(list.is_evicted(&occupant_id!("room@prose.org/a").into()));
+        assert!(list.is_evicted(&occupant_id!("room@prose.org/b").into()));
+        assert!(list.get(&occupant_id!("room@prose.org/c").into()).is_some());
+        assert!(list.get(&occupant_id!("room@prose.org/d").into()).is_some());
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.total_len(), 4);
+    }
+
+    #[test]
+    fn test_bumps_revision_only_when_participants_or_their_names_change() {
+        let mut list = ParticipantList::default();
+        let id = ParticipantId::from(occupant_id!("room@prose.org/a"));
+
+        list.set_availability(&id, false, Availability::Available);
+        let revision = list.revision();
+
+        // Presence changes without a new nickname or avatar don't matter…
+        list.set_availability(&id, false, Availability::Away);
+        list.set_presence(
+            &id,
+            false,
+            Presence {
+                availability: Availability::Available,
+                status: Some("Busy".to_string()),
+                ..Default::default()
+            },
+        );
+        list.set_role(&id, RoomRole::Moderator);
+        list.set_affiliation(&id, false, RoomAffiliation::Member);
+        assert_eq!(list.revision(), revision);
+
+        list.set_presence(
+            &id,
+            false,
+            Presence {
+                availability: Availability::Available,
+                nickname: Some("Alice".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(list.revision(), revision + 1);
+
+        list.set_availability(
+            &occupant_id!("room@prose.org/b").into(),
+            false,
+            Availability::Available,
+        );
+        assert_eq!(list.revision(), revision + 2);
+
+        list.remove(&occupant_id!("room@prose.org/c").into());
+        assert_eq!(list.revision(), revision + 2);
+
+        list.remove(&id);
+        assert_eq!(list.revision(), revision + 3);
+    }
+
+    #[test]
+    fn test_bumps_members_revision_only_when_members_change() {
+        let mut list = ParticipantList::default();
+        let id = ParticipantId::from(occupant_id!("room@prose.org/a"));
+
+        list.set_availability(&id, false, Availability::Available);
+        let revision = list.members_revision();
+
+        // Neither presences nor names change who messages are encrypted for…
+        list.set_presence(
+            &id,
+            false,
+            Presence {
+                availability: Availability::Away,
+                nickname: Some("Alice".to_string()),
+                ..Default::default()
+            },
+        );
+        list.set_ids_and_name(&id, None, None, Some("Alice".to_string()));
+        assert_eq!(list.members_revision(), revision);
+
+        list.set_ids_and_name(&id, Some(&user_id!("a@prose.org")), None, None);
+        assert_eq!(list.members_revision(), revision + 1);
+
+        list.set_ids_and_name(&id, Some(&user_id!("a@prose.org")), None, None);
+        assert_eq!(list.members_revision(), revision + 1);
+
+        list.remove(&id);
+        assert_eq!(list.members_revision(), revision + 2);
+    }
+
     #[test]
     fn test_registered_members_in_direct_message_room() {
         // Start with a fresh state…
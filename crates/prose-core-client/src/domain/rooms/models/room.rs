@@ -3,23 +3,30 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::sync::Arc;
 
 use anyhow::Result;
-use chrono::{DateTime, Utc};
-use parking_lot::RwLock;
+use chrono::{DateTime, Duration, Utc};
+use indexmap::{IndexMap, IndexSet};
+use parking_lot::{Mutex, MutexGuard, RwLock};
+use serde::{Deserialize, Serialize};
 
 use crate::app::deps::DynMessagesRepository;
-use crate::domain::messaging::models::MessageLikePayload;
+use crate::domain::encryption::models::DeviceId;
+use crate::domain::messaging::models::{MessageId, MessageLikePayload};
+use crate::domain::rooms::models::constants::{
+    MAX_CACHED_MESSAGE_SENDERS, MAX_TRACKED_MESSAGES_PER_UNRESOLVED_SENDER,
+};
 use crate::domain::rooms::models::{
-    ParticipantList, RegisteredMember, RoomFeatures, RoomSessionParticipant,
+    ComposeState, ParticipantList, RegisteredMember, RoomFeatures, RoomSessionParticipant,
 };
-use crate::domain::settings::models::SyncedRoomSettings;
-use crate::domain::shared::models::{AccountId, RoomId, RoomType, UserId};
+use crate::domain::settings::models::{RoomNotificationMode, SyncedRoomSettings};
+use crate::domain::shared::models::{AccountId, MucId, RoomId, RoomType, UserId};
 use crate::domain::sidebar::models::Bookmark;
 use crate::domain::user_info::models::Presence;
-use crate::dtos::{OccupantId, ParticipantId};
+use crate::dtos::{MessageSender, OccupantId, ParticipantId};
 
 /// Contains information about a connected room and its state.
 #[derive(Debug, Clone)]
@@ -27,7 +34,7 @@ pub struct Room {
     inner: Arc<RoomInner>,
 }
 
-#[derive(Debug, Clone, PartialEq, Copy)]
+#[derive(Debug, Clone, PartialEq, Copy, Serialize, Deserialize)]
 pub enum RoomSidebarState {
     /// The room is not visible in the sidebar.
     NotInSidebar,
@@ -51,6 +58,12 @@ pub enum RoomState {
     /// The room has been inserted from a bookmark and is waiting to be connected.
     #[default]
     Pending,
+    /// Our user was invited to the room but has neither accepted nor declined the invitation yet.
+    Invited {
+        sender: UserId,
+        password: Option<String>,
+        reason: Option<String>,
+    },
     Connecting,
     Connected,
     Disconnected {
@@ -88,6 +101,15 @@ pub struct RoomStatistics {
     pub unread_count: u32,
     /// The number of unread messages mentioning our user in this room.
     pub mentions_count: u32,
+    /// The ids of the messages that were counted already, so that redelivered messages aren't
+    /// counted twice.
+    counted_message_ids: HashSet<MessageId>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnreadMessageKind {
+    Regular,
+    Mention,
 }
 
 impl Default for RoomStatistics {
@@ -96,6 +118,7 @@ impl Default for RoomStatistics {
             needs_update: true,
             unread_count: 0,
             mentions_count: 0,
+            counted_message_ids: Default::default(),
         }
     }
 }
@@ -112,6 +135,8 @@ pub struct RoomDetails {
     pub participants: ParticipantList,
     /// Whether the room is visible in the sidebar.
     pub sidebar_state: RoomSidebarState,
+    /// The position of the room in the manually ordered section of the sidebar, if any.
+    pub order_index: Option<u32>,
     /// The state the room is in.
     pub state: RoomState,
     /// Some tidbits about this room.
@@ -120,10 +145,46 @@ pub struct RoomDetails {
     pub settings: SyncedRoomSettings,
 }
 
+#[derive(Debug)]
+struct SentComposeState {
+    state: ComposeState,
+    sent_at: DateTime<Utc>,
+    /// When our user last reported the state. Repeated 'composing' states are throttled, so
+    /// this can be later than `sent_at`.
+    reported_at: DateTime<Utc>,
+}
+
 #[derive(Debug)]
 struct RoomInner {
     info: RoomInfo,
     details: RwLock<RoomDetails>,
+    /// The last compose state we've sent to the room.
+    sent_compose_state: Mutex<Option<SentComposeState>>,
+    /// Senders of messages resolved while loading messages, so that loading consecutive pages
+    /// doesn't need to resolve the same senders again.
+    message_senders: Mutex<MessageSenderCache>,
+    /// Messages sent by occupants whose real id was unknown when the messages were loaded, so
+    /// that they can be reloaded once the real id becomes known.
+    messages_with_unresolved_sender: Mutex<HashMap<OccupantId, IndexSet<MessageId>>>,
+    /// The users messages are encrypted for, so that the member list doesn't need to be loaded
+    /// for every message.
+    encryption_recipients: Mutex<EncryptionRecipientCache>,
+}
+
+#[derive(Debug, Default)]
+struct MessageSenderCache {
+    /// The revision of the participant list the senders were resolved with.
+    participants_revision: u64,
+    senders: IndexMap<ParticipantId, MessageSender>,
+}
+
+#[derive(Debug, Default)]
+struct EncryptionRecipientCache {
+    /// The members revision of the participant list the recipients were resolved with.
+    members_revision: u64,
+    recipients: Option<Vec<UserId>>,
+    /// Devices of the recipients we haven't decided to trust yet and told the UI about.
+    undecided_devices: HashSet<(UserId, DeviceId)>,
 }
 
 impl Deref for Room {
@@ -140,9 +201,25 @@ impl Room {
             inner: Arc::new(RoomInner {
                 info,
                 details: RwLock::new(details),
+                sent_compose_state: Default::default(),
+                message_senders: Default::default(),
+                messages_with_unresolved_sender: Default::default(),
+                encryption_recipients: Default::default(),
             }),
         }
     }
+
+    fn message_sender_cache(&self) -> MutexGuard<'_, MessageSenderCache> {
+        let participants_revision = self.inner.details.read().participants.revision();
+        let mut cache = self.inner.message_senders.lock();
+
+        if cache.participants_revision != participants_revision {
+            cache.senders.clear();
+            cache.participants_revision = participants_revision;
+        }
+
+        cache
+    }
 }
 
 impl Room {
@@ -178,6 +255,81 @@ impl Room {
         f(&mut self.inner.details.write().participants)
     }
 
+    /// Returns the cached message sender with `id` unless the participants have changed since
+    /// it was cached.
+    pub fn cached_message_sender(&self, id: &ParticipantId) -> Option<MessageSender> {
+        self.message_sender_cache().senders.get(id).cloned()
+    }
+
+    pub fn cache_message_sender(&self, sender: MessageSender) {
+        let mut cache = self.message_sender_cache();
+        if cache.senders.len() >= MAX_CACHED_MESSAGE_SENDERS {
+            cache.senders.shift_remove_index(0);
+        }
+        cache.senders.insert(sender.id.clone(), sender);
+    }
+
+    /// Clears the cached message senders, e.g. because the name or avatar of a user changed.
+    pub fn invalidate_message_senders(&self) {
+        self.inner.message_senders.lock().senders.clear()
+    }
+
+    /// Returns the cached recipients of encrypted messages unless participants were added or
+    /// removed since they were cached.
+    pub fn cached_encryption_recipients(&self) -> Option<Vec<UserId>> {
+        let members_revision = self.inner.details.read().participants.members_revision();
+        let cache = self.inner.encryption_recipients.lock();
+
+        if cache.members_revision != members_revision {
+            return None;
+        }
+        cache.recipients.clone()
+    }
+
+    pub fn cache_encryption_recipients(&self, recipients: Vec<UserId>) {
+        let members_revision = self.inner.details.read().participants.members_revision();
+        let mut cache = self.inner.encryption_recipients.lock();
+        cache.members_revision = members_revision;
+        cache.recipients = Some(recipients);
+    }
+
+    /// Remembers `devices` as undecided devices of the recipients. Returns true if any of them
+    /// wasn't known before.
+    pub fn insert_undecided_devices(&self, devices: &[(UserId, DeviceId)]) -> bool {
+        let mut cache = self.inner.encryption_recipients.lock();
+        let mut has_new_devices = false;
+        for device in devices {
+            has_new_devices |= cache.undecided_devices.insert(device.clone());
+        }
+        has_new_devices
+    }
+
+    /// Remembers that the message with `message_id` was attributed to the occupant with
+    /// `occupant_id` while their real id was unknown.
+    pub fn track_message_with_unresolved_sender(
+        &self,
+        occupant_id: &OccupantId,
+        message_id: &MessageId,
+    ) {
+        let mut messages = self.inner.messages_with_unresolved_sender.lock();
+        let message_ids = messages.entry(occupant_id.clone()).or_default();
+        if message_ids.len() >= MAX_TRACKED_MESSAGES_PER_UNRESOLVED_SENDER {
+            message_ids.shift_remove_index(0);
+        }
+        message_ids.insert(message_id.clone());
+    }
+
+    /// Returns and forgets the ids of the messages that were attributed to the occupant with
+    /// `occupant_id` while their real id was unknown.
+    pub fn take_messages_with_unresolved_sender(&self, occupant_id: &OccupantId) -> Vec<MessageId> {
+        self.inner
+            .messages_with_unresolved_sender
+            .lock()
+            .remove(occupant_id)
+            .map(|ids| ids.into_iter().collect())
+            .unwrap_or_default()
+    }
+
     pub fn sidebar_state(&self) -> RoomSidebarState {
         self.inner.details.read().sidebar_state
     }
@@ -186,6 +338,14 @@ impl Room {
         self.inner.details.write().sidebar_state = state
     }
 
+    pub fn order_index(&self) -> Option<u32> {
+        self.inner.details.read().order_index
+    }
+
+    pub fn set_order_index(&self, order_index: Option<u32>) {
+        self.inner.details.write().order_index = order_index
+    }
+
     pub fn state(&self) -> RoomState {
         self.inner.details.read().state.clone()
     }
@@ -202,6 +362,49 @@ impl Room {
         self.inner.details.write().statistics.needs_update = true;
     }
 
+    /// Marks all messages as read without reloading them from the repository.
+    pub fn reset_statistics(&self) {
+        let mut stats = RoomStatistics::default();
+        stats.needs_update = false;
+        self.inner.details.write().statistics = stats;
+    }
+
+    /// Counts a newly received `message` towards the statistics without reloading all unread
+    /// messages from the repository. Does nothing if the statistics need to be recomputed anyway.
+    pub fn apply_received_message_to_statistics(&self, account: &AccountId, message: &MessageLike) {
+        let (last_read_message, notification_mode) = {
+            let guard = self.inner.details.read();
+            if guard.statistics.needs_update {
+                return;
+            }
+            (
+                guard.settings.last_read_message.clone(),
+                guard.settings.notifications,
+            )
+        };
+
+        // The message might have been read on another device already…
+        if let Some(last_read_message) = last_read_message {
+            if message.timestamp <= last_read_message.timestamp {
+                return;
+            }
+        }
+
+        let Some(kind) = self.unread_message_kind(account, message, notification_mode) else {
+            return;
+        };
+
+        let mut guard = self.inner.details.write();
+        let stats = &mut guard.statistics;
+        if stats.needs_update || !stats.counted_message_ids.insert(message.id.clone()) {
+            return;
+        }
+        stats.unread_count += 1;
+        if kind == UnreadMessageKind::Mention {
+            stats.mentions_count += 1;
+        }
+    }
+
     pub fn is_current_user(&self, account: &AccountId, participant: &ParticipantId) -> bool {
         if self.room_id.is_muc_room() {
             // We're generally trying to resolve OccupantIDs into UserIDs if possible.
@@ -219,16 +422,21 @@ impl Room {
         messages_repo: &DynMessagesRepository,
     ) -> Result<RoomStatistics> {
         match self.state() {
-            RoomState::Pending | RoomState::Connecting => return Ok(Default::default()),
+            RoomState::Pending | RoomState::Invited { .. } | RoomState::Connecting => {
+                return Ok(Default::default())
+            }
             RoomState::Connected | RoomState::Disconnected { .. } => (),
         }
 
-        let last_read_message = {
+        let (last_read_message, notification_mode) = {
             let guard = self.inner.details.read();
             if !guard.statistics.needs_update {
                 return Ok(guard.statistics.clone());
             }
-            guard.settings.last_read_message.clone()
+            (
+                guard.settings.last_read_message.clone(),
+                guard.settings.notifications,
+            )
         };
 
         let mut stats = RoomStatistics::default();
@@ -236,6 +444,11 @@ impl Room {
 
         self.inner.details.write().statistics = stats.clone();
 
+        // Muted rooms are never marked as unread.
+        if notification_mode == RoomNotificationMode::None {
+            return Ok(stats);
+        }
+
         let last_read_message_timestamp = last_read_message
             .map(|message_ref| message_ref.timestamp)
             .unwrap_or(DateTime::<Utc>::MIN_UTC);
@@ -244,42 +457,65 @@ impl Room {
             .get_messages_after(account, &self.room_id, last_read_message_timestamp)
             .await?;
 
-        let our_user_id = ParticipantId::User(account.to_user_id());
-        let our_participant_id = self.occupant_id().map(ParticipantId::Occupant);
-
-        let is_muc = self.room_id.is_muc_room();
-
         for message in messages {
-            let MessageLikePayload::Message { ref body, .. } = message.payload else {
-                continue;
-            };
-
-            let is_our_message = if is_muc {
-                // We're generally trying to resolve OccupantIDs into UserIDs if possible.
-                // So the sender could be either/or depending on the room configuration.
-                Some(&message.from) == our_participant_id.as_ref() || message.from == our_user_id
-            } else {
-                message.from == our_user_id
-            };
-
-            if is_our_message {
+            if stats.counted_message_ids.contains(&message.id) {
                 continue;
             }
 
-            for mention in &body.mentions {
-                if account == &mention.user {
+            match self.unread_message_kind(account, &message, notification_mode) {
+                Some(UnreadMessageKind::Mention) => {
+                    stats.unread_count += 1;
                     stats.mentions_count += 1;
-                    break;
                 }
+                Some(UnreadMessageKind::Regular) => stats.unread_count += 1,
+                None => continue,
             }
-
-            stats.unread_count += 1;
+            stats.counted_message_ids.insert(message.id);
         }
 
         self.inner.details.write().statistics = stats.clone();
         Ok(stats)
     }
 
+    /// Returns how `message` counts towards the unread messages of this room or `None` if it
+    /// doesn't count at all.
+    fn unread_message_kind(
+        &self,
+        account: &AccountId,
+        message: &MessageLike,
+        notification_mode: RoomNotificationMode,
+    ) -> Option<UnreadMessageKind> {
+        // Muted rooms are never marked as unread.
+        if notification_mode == RoomNotificationMode::None {
+            return None;
+        }
+
+        let MessageLikePayload::Message { ref body, .. } = message.payload else {
+            return None;
+        };
+
+        if self.is_current_user(account, &message.from) {
+            return None;
+        }
+
+        if body.mentions.iter().any(|mention| account == &mention.user) {
+            return Some(UnreadMessageKind::Mention);
+        }
+
+        if notification_mode == RoomNotificationMode::MentionsOnly {
+            return None;
+        }
+
+        Some(UnreadMessageKind::Regular)
+    }
+
+    /// Returns `true` if our user should be notified about `message`, i.e. if it would count
+    /// towards the unread messages of this room.
+    pub fn should_notify_about_message(&self, account: &AccountId, message: &MessageLike) -> bool {
+        self.unread_message_kind(account, message, self.settings().notifications)
+            .is_some()
+    }
+
     pub fn settings(&self) -> SyncedRoomSettings {
         self.inner.details.read().settings.clone()
     }
@@ -287,9 +523,76 @@ impl Room {
     pub fn with_settings_mut<T>(&self, f: impl FnOnce(&mut SyncedRoomSettings) -> T) -> T {
         f(&mut self.inner.details.write().settings)
     }
+
+    /// Returns `true` if `state` should be sent to the room and records it as sent. Repeating
+    /// the previously sent state is suppressed, except for `ComposeState::Composing` which is
+    /// refreshed once `refresh_interval` has elapsed.
+    pub fn should_send_compose_state(
+        &self,
+        state: ComposeState,
+        now: DateTime<Utc>,
+        refresh_interval: Duration,
+    ) -> bool {
+        let mut sent_compose_state = self.inner.sent_compose_state.lock();
+
+        if let Some(sent_state) = sent_compose_state.as_mut() {
+            if sent_state.state == state {
+                sent_state.reported_at = now;
+
+                if state == ComposeState::Idle || now - sent_state.sent_at < refresh_interval {
+                    return false;
+                }
+            }
+        }
+
+        sent_compose_state.replace(SentComposeState {
+            state,
+            sent_at: now,
+            reported_at: now,
+        });
+        true
+    }
+
+    /// Returns `true` if our user stopped composing without telling us, i.e. if the last sent
+    /// state is `ComposeState::Composing` and wasn't reported again within `timeout`. In that
+    /// case `ComposeState::Idle` is recorded as sent.
+    pub fn should_pause_composing(&self, now: DateTime<Utc>, timeout: Duration) -> bool {
+        let mut sent_compose_state = self.inner.sent_compose_state.lock();
+
+        if !sent_compose_state.as_ref().is_some_and(|sent_state| {
+            sent_state.state == ComposeState::Composing && now - sent_state.reported_at >= timeout
+        }) {
+            return false;
+        }
+
+        sent_compose_state.replace(SentComposeState {
+            state: ComposeState::Idle,
+            sent_at: now,
+            reported_at: now,
+        });
+        true
+    }
+
+    /// Returns when the last sent state was sent, if it is `ComposeState::Composing`.
+    pub fn composing_sent_at(&self) -> Option<DateTime<Utc>> {
+        self.inner
+            .sent_compose_state
+            .lock()
+            .as_ref()
+            .filter(|sent_state| sent_state.state == ComposeState::Composing)
+            .map(|sent_state| sent_state.sent_at)
+    }
+
+    /// Forgets the last sent compose state. Sent messages carry an 'active' chat state, which
+    /// ends a previously sent 'composing' state.
+    pub fn reset_compose_state(&self) {
+        self.inner.sent_compose_state.lock().take();
+    }
 }
 
 impl Room {
+    /// A placeholder for a bookmarked room until it's connected. Bookmarks with a pending
+    /// invitation result in a room in the `RoomState::Invited` state.
     pub fn pending(bookmark: &Bookmark, nickname: &str) -> Self {
         let participants = match &bookmark.jid {
             RoomId::User(user_id) => ParticipantList::for_direct_message(
@@ -313,7 +616,15 @@ impl Room {
                 topic: None,
                 participants,
                 sidebar_state: bookmark.sidebar_state,
-                state: RoomState::Pending,
+                order_index: bookmark.order_index,
+                state: match &bookmark.invitation {
+                    Some(invitation) => RoomState::Invited {
+                        sender: invitation.sender.clone(),
+                        password: invitation.password.clone(),
+                        reason: invitation.reason.clone(),
+                    },
+                    None => RoomState::Pending,
+                },
                 statistics: Default::default(),
                 settings: SyncedRoomSettings::new(bookmark.jid.clone()),
             },
@@ -334,6 +645,7 @@ impl Room {
                 topic: None,
                 participants: Default::default(),
                 sidebar_state,
+                order_index: None,
                 state: RoomState::Connecting,
                 statistics: Default::default(),
                 settings: SyncedRoomSettings::new(room_id.clone()),
@@ -341,12 +653,58 @@ impl Room {
         )
     }
 
+    /// A placeholder for a room that our user was invited to. The type of the room is unknown
+    /// until it's joined, so the room is treated as a `RoomType::Generic` until then.
+    pub fn invited(
+        room_id: &MucId,
+        nickname: &str,
+        sender: UserId,
+        password: Option<String>,
+        reason: Option<String>,
+    ) -> Self {
+        let room_id = RoomId::Muc(room_id.clone());
+
+        Self::new(
+            RoomInfo {
+                room_id: room_id.clone(),
+                user_nickname: nickname.to_string(),
+                r#type: RoomType::Generic,
+                features: Default::default(),
+            },
+            RoomDetails {
+                name: None,
+                description: None,
+                topic: None,
+                participants: Default::default(),
+                sidebar_state: RoomSidebarState::InSidebar,
+                order_index: None,
+                state: RoomState::Invited {
+                    sender,
+                    password,
+                    reason,
+                },
+                statistics: Default::default(),
+                settings: SyncedRoomSettings::new(room_id),
+            },
+        )
+    }
+
     pub fn is_connecting(&self) -> bool {
         self.inner.details.read().state == RoomState::Connecting
     }
     pub fn is_pending(&self) -> bool {
         self.inner.details.read().state == RoomState::Pending
     }
+    pub fn is_invited(&self) -> bool {
+        matches!(self.inner.details.read().state, RoomState::Invited { .. })
+    }
+
+    /// Returns `true` if the room is either connected or about to be, i.e. if it's neither
+    /// disconnected nor waiting for our user to accept an invitation.
+    pub fn is_joined(&self) -> bool {
+        let state = &self.inner.details.read().state;
+        !state.is_disconnected() && !matches!(state, RoomState::Invited { .. })
+    }
 
     // Resolves a pending room.
     pub fn by_resolving_with_info(
@@ -357,6 +715,7 @@ impl Room {
         info: RoomInfo,
         members: Vec<RegisteredMember>,
         participants: Vec<RoomSessionParticipant>,
+        max_participants: Option<usize>,
         settings: SyncedRoomSettings,
     ) -> Self {
         let mut details = self.inner.details.read().clone();
@@ -364,6 +723,7 @@ impl Room {
         details.description = description;
         details.topic = topic;
         details.participants = ParticipantList::new(members, participants);
+        details.participants.set_capacity(max_participants);
         details.state = RoomState::Connected;
         details.settings = settings;
 
@@ -371,12 +731,20 @@ impl Room {
     }
 
     pub fn by_changing_type(&self, new_type: RoomType) -> Self {
+        self.by_changing_type_and_features(new_type, self.features.clone())
+    }
+
+    pub fn by_changing_type_and_features(
+        &self,
+        new_type: RoomType,
+        features: RoomFeatures,
+    ) -> Self {
         Self::new(
             RoomInfo {
                 room_id: self.room_id.clone(),
                 user_nickname: self.user_nickname.clone(),
                 r#type: new_type,
-                features: self.features.clone(),
+                features,
             },
             self.inner.details.read().clone(),
         )
@@ -409,6 +777,7 @@ impl Room {
                     presence,
                 ),
                 sidebar_state,
+                order_index: None,
                 state: RoomState::Connected,
                 statistics: Default::default(),
                 settings,
@@ -429,6 +798,7 @@ impl Room {
                 topic: None,
                 participants: Default::default(),
                 sidebar_state: RoomSidebarState::InSidebar,
+                order_index: None,
                 state: Default::default(),
                 statistics: Default::default(),
                 settings: SyncedRoomSettings::new(room_id),
@@ -441,6 +811,12 @@ impl Room {
         info.user_nickname = nickname.into();
         Self::new(info, self.inner.details.read().clone())
     }
+
+    pub fn with_features(self, features: RoomFeatures) -> Self {
+        let mut info = self.inner.info.clone();
+        info.features = features;
+        Self::new(info, self.inner.details.read().clone())
+    }
 }
 
 impl RoomInfo {
@@ -465,7 +841,9 @@ impl PartialEq for Room {
 
 #[cfg(test)]
 mod tests {
+    use crate::domain::messaging::models::Mention;
     use crate::domain::shared::models::Availability;
+    use crate::test::{mock_data, MessageBuilder};
     use crate::user_id;
 
     use super::*;
@@ -506,6 +884,7 @@ mod tests {
                         },
                     ),
                     sidebar_state: RoomSidebarState::Favorite,
+                    order_index: None,
                     state: RoomState::Connected,
                     statistics: Default::default(),
                     settings: SyncedRoomSettings::new(user_id!("contact@prose.org").into()),
@@ -513,4 +892,78 @@ mod tests {
             )
         )
     }
+
+    #[test]
+    fn test_applies_received_messages_to_statistics() {
+        let account = mock_data::account();
+        let room = Room::direct_message(user_id!("contact@prose.org"), Availability::Available);
+        room.reset_statistics();
+
+        let mut mention = MessageBuilder::new_with_index(2)
+            .set_from(user_id!("contact@prose.org"))
+            .build_message_like();
+        if let MessageLikePayload::Message { body, .. } = &mut mention.payload {
+            body.mentions = vec![Mention {
+                user: account.to_user_id(),
+                range: None,
+            }];
+        }
+
+        room.apply_received_message_to_statistics(
+            &account,
+            &MessageBuilder::new_with_index(1)
+                .set_from(user_id!("contact@prose.org"))
+                .build_message_like(),
+        );
+        room.apply_received_message_to_statistics(&account, &mention);
+        room.apply_received_message_to_statistics(
+            &account,
+            &MessageBuilder::new_with_index(3)
+                .set_from(account.to_user_id())
+                .build_message_like(),
+        );
+
+        let stats = room.statistics();
+        assert_eq!(stats.unread_count, 2);
+        assert_eq!(stats.mentions_count, 1);
+
+        // A redelivered message is counted only once…
+        room.apply_received_message_to_statistics(&account, &mention);
+
+        let stats = room.statistics();
+        assert_eq!(stats.unread_count, 2);
+        assert_eq!(stats.mentions_count, 1);
+
+        room.reset_statistics();
+        assert_eq!(room.statistics().unread_count, 0);
+        assert_eq!(room.statistics().mentions_count, 0);
+    }
+
+    #[test]
+    fn test_throttles_and_pauses_compose_state() {
+        let room = Room::direct_message(user_id!("contact@prose.org"), Availability::Available);
+        let start = Utc::now();
+        let at = |secs: i64| start + Duration::seconds(secs);
+        let refresh_interval = Duration::seconds(10);
+        let timeout = Duration::seconds(30);
+
+        assert!(room.should_send_compose_state(ComposeState::Composing, at(0), refresh_interval));
+        assert!(!room.should_send_compose_state(ComposeState::Composing, at(5), refresh_interval));
+        assert_eq!(room.composing_sent_at(), Some(at(0)));
+        assert!(room.should_send_compose_state(ComposeState::Composing, at(10), refresh_interval));
+        assert_eq!(room.composing_sent_at(), Some(at(10)));
+
+        // The last refresh at 12s was throttled, but still counts as activity…
+        assert!(!room.should_send_compose_state(ComposeState::Composing, at(12), refresh_interval));
+        assert!(!room.should_pause_composing(at(41), timeout));
+        assert!(room.should_pause_composing(at(42), timeout));
+        assert_eq!(room.composing_sent_at(), None);
+        assert!(!room.should_pause_composing(at(100), timeout));
+        assert!(!room.should_send_compose_state(ComposeState::Idle, at(101), refresh_interval));
+
+        assert!(room.should_send_compose_state(ComposeState::Composing, at(102), refresh_interval));
+        room.reset_compose_state();
+        assert!(!room.should_pause_composing(at(200), timeout));
+        assert!(room.should_send_compose_state(ComposeState::Composing, at(201), refresh_interval));
+    }
 }
@@ -0,0 +1,25 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+/// The editable settings of a MUC room, backed by its muc#roomconfig form.
+///
+/// Fields of the form that aren't represented here are left untouched when the configuration
+/// is submitted.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RoomConfiguration {
+    /// Only members may enter the room.
+    pub members_only: bool,
+    /// Only occupants with voice may send messages.
+    pub moderated: bool,
+    /// The room continues to exist after the last occupant left.
+    pub persistent: bool,
+    /// The maximum number of history messages sent to occupants when they enter the room.
+    /// `None` if the server doesn't limit it.
+    pub max_history_fetch: Option<u32>,
+    /// Occupants may invite other users.
+    pub allow_invites: bool,
+    /// The password required to enter the room, if any.
+    pub password: Option<String>,
+}
@@ -21,6 +21,20 @@ pub enum RoomError {
     PublicChannelNameConflict,
     #[error("Group must have at least two participants.")]
     InvalidNumberOfParticipants,
+    #[error("The room does not support message moderation.")]
+    MessageModerationNotSupported,
+    #[error("Only owners of the room can perform this action.")]
+    NotRoomOwner,
+    #[error("You don't have the privileges required to perform this action.")]
+    InsufficientPrivileges,
+    #[error("The room requires a password or the password is incorrect.")]
+    InvalidPassword,
+    #[error("The room is members-only and you're not a member.")]
+    MembersOnly,
+    #[error("The room has reached its maximum number of occupants.")]
+    RoomIsFull,
+    #[error("You are banned from the room.")]
+    Banned,
     #[error(transparent)]
     RequestError(#[from] RequestError),
     #[error("{0}")]
@@ -39,6 +53,22 @@ pub struct GoneError {
 }
 
 impl RoomError {
+    /// Maps the errors returned by a room when entering it to the dedicated variants
+    /// (https://xmpp.org/extensions/xep-0045.html#enter-errorcodes).
+    pub(crate) fn from_join_error(error: RequestError) -> Self {
+        match error.defined_condition() {
+            Some(DefinedCondition::NotAuthorized) => Self::InvalidPassword,
+            Some(DefinedCondition::RegistrationRequired) => Self::MembersOnly,
+            Some(DefinedCondition::ServiceUnavailable) => Self::RoomIsFull,
+            Some(DefinedCondition::Forbidden) => Self::Banned,
+            _ => Self::RequestError(error),
+        }
+    }
+
+    pub fn is_invalid_password_err(&self) -> bool {
+        matches!(self, Self::InvalidPassword)
+    }
+
     pub(crate) fn is_gone_err(&self) -> bool {
         self.gone_err().is_some()
     }
@@ -72,6 +102,10 @@ impl RoomError {
     }
 
     pub(crate) fn is_registration_required_err(&self) -> bool {
+        if let Self::MembersOnly = self {
+            return true;
+        }
+
         let Self::RequestError(error) = &self else {
             return false;
         };
@@ -14,6 +14,10 @@ pub struct RoomFeatures {
     pub server_time_offset: TimeDelta,
     /// Does the server support XEP-0410 (MUC Self-Ping)?
     pub self_ping_optimization: bool,
+    /// Does the room support XEP-0425 (Message Moderation)?
+    pub message_moderation: bool,
+    /// Does the room support XEP-0333 (Chat Markers)?
+    pub chat_markers: bool,
 }
 
 impl RoomFeatures {
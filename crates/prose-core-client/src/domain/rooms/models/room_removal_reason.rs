@@ -0,0 +1,26 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+/// Why an occupant was removed from a room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomRemovalReason {
+    /// The MUC service shut down or restarted. The room can be rejoined later.
+    ServiceShutdown,
+    /// The occupant was kicked by a moderator. The room can be rejoined right away.
+    Kicked,
+    /// The occupant was banned from the room. The room can't be rejoined unless the ban is lifted.
+    Banned,
+    /// The occupant's membership was revoked.
+    MembershipRevoked,
+    /// The room was made members-only and the occupant isn't a member.
+    MembersOnly,
+}
+
+impl RoomRemovalReason {
+    /// Whether rejoining the room is pointless, i.e. whether the occupant was banned.
+    pub fn is_permanent(&self) -> bool {
+        *self == RoomRemovalReason::Banned
+    }
+}
@@ -0,0 +1,32 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::fmt::{Display, Formatter};
+
+/// The role of an occupant, which, unlike the affiliation, only lasts for the duration of their
+/// visit to the room.
+#[derive(Debug, PartialEq, Clone, Default, PartialOrd, Eq, Ord, Copy)]
+pub enum RoomRole {
+    /// The occupant is not in the room (anymore).
+    #[default]
+    None,
+    /// An occupant who may not send messages in moderated rooms.
+    Visitor,
+    /// A regular occupant.
+    Participant,
+    /// An occupant who may kick other occupants and grant or revoke voice.
+    Moderator,
+}
+
+impl Display for RoomRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoomRole::Moderator => write!(f, "moderator"),
+            RoomRole::Participant => write!(f, "participant"),
+            RoomRole::Visitor => write!(f, "visitor"),
+            RoomRole::None => write!(f, "none"),
+        }
+    }
+}
@@ -3,7 +3,7 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use crate::domain::rooms::models::RoomAffiliation;
+use crate::domain::rooms::models::{RoomAffiliation, RoomRole};
 use crate::domain::shared::models::{
     AnonOccupantId, MamVersion, MucId, OccupantId, RoomType, UserId,
 };
@@ -28,6 +28,8 @@ pub struct RoomConfig {
     pub room_type: RoomType,
     pub mam_version: Option<MamVersion>,
     pub supports_self_ping_optimization: bool,
+    pub supports_message_moderation: bool,
+    pub supports_chat_markers: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -43,5 +45,6 @@ pub struct RoomSessionParticipant {
     pub anon_id: Option<AnonOccupantId>,
     pub real_id: Option<UserId>,
     pub affiliation: RoomAffiliation,
+    pub role: RoomRole,
     pub presence: Presence,
 }
@@ -209,12 +209,13 @@ impl RoomsDomainServiceTrait for RoomsDomainService {
 
         match room.state() {
             RoomState::Pending
+            | RoomState::Invited { .. }
             | RoomState::Connecting
             | RoomState::Disconnected {
                 can_retry: false, ..
             } => {
-                // If the room is in the process of connecting or disconnected with no
-                // chance of retrying, we'll leave it alone.
+                // If the room is in the process of connecting, waiting for our user to accept
+                // an invitation or disconnected with no chance of retrying, we'll leave it alone.
                 return Ok(());
             }
             RoomState::Disconnected {
@@ -414,7 +415,7 @@ impl RoomsDomainServiceTrait for RoomsDomainService {
                         _ = self.connected_rooms_repo.set(&account, room);
                         _ = self
                             .room_management_service
-                            .destroy_room(new_room_id, None)
+                            .destroy_room(new_room_id, None, None)
                             .await;
                         return Err(err.into());
                     }
@@ -442,7 +443,7 @@ impl RoomsDomainServiceTrait for RoomsDomainService {
 
                     match self
                         .room_participation_service
-                        .grant_membership(new_room_id, &member)
+                        .set_affiliation(new_room_id, &member, RoomAffiliation::Member, None)
                         .await
                     {
                         Ok(_) => (),
@@ -460,7 +461,7 @@ impl RoomsDomainServiceTrait for RoomsDomainService {
                 debug!("Destroying old room {}…", room.room_id);
                 match self
                     .room_management_service
-                    .destroy_room(room_id, new_room.room_id.muc_id().cloned())
+                    .destroy_room(room_id, new_room.room_id.muc_id().cloned(), None)
                     .await
                 {
                     Ok(_) => (),
@@ -550,21 +551,35 @@ impl RoomsDomainServiceTrait for RoomsDomainService {
         room.set_name(config.room_name);
         room.set_description(config.room_description);
 
-        if room.r#type == config.room_type {
-            info!("Room type remained for {}.", room_id);
+        let features = RoomFeatures {
+            mam_version: config.mam_version,
+            server_time_offset: room.features.server_time_offset,
+            self_ping_optimization: config.supports_self_ping_optimization,
+            message_moderation: config.supports_message_moderation,
+            chat_markers: config.supports_chat_markers,
+        };
+
+        if room.r#type == config.room_type && room.features == features {
+            info!("Room type and features remained for {}.", room_id);
             return Ok(room);
         }
 
-        info!(
-            "Room type changed from {} to {} for {}.",
-            room.r#type, config.room_type, room_id
-        );
+        if room.r#type == config.room_type {
+            info!("Room features changed for {}.", room_id);
+        } else {
+            info!(
+                "Room type changed from {} to {} for {}.",
+                room.r#type, config.room_type, room_id
+            );
+        }
 
         self.connected_rooms_repo
             .update(
                 &account,
                 room_id.as_ref(),
-                Box::new(move |room| room.by_changing_type(config.room_type)),
+                Box::new(move |room| {
+                    room.by_changing_type_and_features(config.room_type, features)
+                }),
             )
             .ok_or(RoomError::RoomWasModified)
     }
@@ -580,15 +595,19 @@ impl RoomsDomainService {
         sidebar_state: RoomSidebarState,
         behavior: JoinRoomBehavior,
     ) -> Result<RoomStatus, RoomError> {
+        // Rooms that existed before we tried to join them (e.g. from a bookmark) are never
+        // removed, so that a failed attempt doesn't leave the sidebar without its item…
         let remove_or_retain_room_on_error =
-            |room: Room, error: &RoomError| match behavior.on_failure {
-                JoinRoomFailureBehavior::RemoveOnError => {
+            |room: RoomStatus, error: &RoomError| match (behavior.on_failure, room) {
+                (JoinRoomFailureBehavior::RemoveOnError, RoomStatus::IsNew(_)) => {
                     self.connected_rooms_repo.delete(account, room_id.as_ref());
                 }
-                JoinRoomFailureBehavior::RetainOnError => room.set_state(RoomState::Disconnected {
-                    error: Some(error.to_string()),
-                    can_retry: true,
-                }),
+                (_, RoomStatus::IsNew(room) | RoomStatus::Exists(room)) => {
+                    room.set_state(RoomState::Disconnected {
+                        error: Some(error.to_string()),
+                        can_retry: true,
+                    })
+                }
             };
 
         let display_name = self
@@ -607,7 +626,8 @@ impl RoomsDomainService {
         let info = 'info: loop {
             // Insert pending room so that we don't miss any stanzas for this room while we're
             // connecting to it…
-            let room = self.insert_connecting_room(account, &room_id, &nickname, sidebar_state)?;
+            let (room, nickname) =
+                self.insert_connecting_room(account, &room_id, &nickname, sidebar_state)?;
 
             let join_room = {
                 let room_id = room_id.clone();
@@ -645,7 +665,7 @@ impl RoomsDomainService {
                 }
                 Err(error) => {
                     let Some(gone_error) = error.gone_err() else {
-                        remove_or_retain_room_on_error(room.into(), &error);
+                        remove_or_retain_room_on_error(room, &error);
                         return Err(error);
                     };
 
@@ -657,7 +677,7 @@ impl RoomsDomainService {
                         }
                         (JoinRoomRedirectBehavior::FollowIfGone, None)
                         | (JoinRoomRedirectBehavior::FailIfGone, _) => {
-                            remove_or_retain_room_on_error(room.into(), &error);
+                            remove_or_retain_room_on_error(room, &error);
                             return Err(error);
                         }
                     }
@@ -694,6 +714,7 @@ impl RoomsDomainService {
         let contact_name = user_info.display_name().unwrap_or_username(user_id);
 
         let room_id = RoomId::User(user_id.clone());
+        let order_index = existing_room.as_ref().and_then(|room| room.order_index());
         let settings = self
             .synced_room_settings_service
             .load_settings(&room_id)
@@ -712,18 +733,22 @@ impl RoomsDomainService {
                 avatar: user_info.avatar,
                 caps: user_info.caps,
                 client: user_info.client,
+                idle_since: user_info.idle_since,
                 nickname: None,
                 priority: 0,
-                status: None,
+                status: user_info.status_message,
             },
             sidebar_state,
             RoomFeatures {
                 mam_version: features.mam_version,
                 server_time_offset: features.server_time_offset,
                 self_ping_optimization: false,
+                message_moderation: false,
+                chat_markers: false,
             },
             settings,
         );
+        room.set_order_index(order_index);
 
         self.connected_rooms_repo
             .set_or_replace(account, room.clone());
@@ -963,6 +988,7 @@ impl RoomsDomainService {
             // creating (but potentially connecting to) it…
             let room_is_new = self
                 .insert_connecting_room(account, &room_jid, &nickname, sidebar_state)?
+                .0
                 .is_new();
 
             let create_or_join_room = {
@@ -1033,7 +1059,7 @@ impl RoomsDomainService {
                     if info.room_has_been_created {
                         _ = self
                             .room_management_service
-                            .destroy_room(&room_jid, None)
+                            .destroy_room(&room_jid, None, None)
                             .await;
                     }
                     return Err(error.into());
@@ -1134,6 +1160,8 @@ impl RoomsDomainService {
                 mam_version: info.config.mam_version,
                 server_time_offset,
                 self_ping_optimization: info.config.supports_self_ping_optimization,
+                message_moderation: info.config.supports_message_moderation,
+                chat_markers: info.config.supports_chat_markers,
             },
         };
 
@@ -1143,6 +1171,8 @@ impl RoomsDomainService {
             .await
             .unwrap_or_default();
 
+        let max_participants = self.ctx.config.max_cached_participants;
+
         let Some(room) = self
             .connected_rooms_repo
             .update(account, info.room_id.as_ref(), {
@@ -1164,6 +1194,7 @@ impl RoomsDomainService {
                         room_info,
                         members,
                         info.participants,
+                        max_participants,
                         settings,
                     );
                     room
@@ -1202,22 +1233,35 @@ impl RoomsDomainService {
         Ok(true)
     }
 
+    /// Returns the connecting room along with the nickname that should be used to join it.
     fn insert_connecting_room(
         &self,
         account: &AccountId,
         room_id: &MucId,
         nickname: &str,
         sidebar_state: RoomSidebarState,
-    ) -> Result<RoomStatus, RoomError> {
+    ) -> Result<(RoomStatus, String), RoomError> {
         let room_id = RoomId::Muc(room_id.clone());
 
-        // If we have a pending room waiting for us, we'll switch that to connecting and do not
-        // insert a new one.
+        // If we have a pending room (or one we were invited to) waiting for us, we'll switch that
+        // to connecting and do not insert a new one.
         if let Some(pending_room) = self.connected_rooms_repo.get(account, room_id.as_ref()) {
-            if pending_room.state() == RoomState::Pending || pending_room.state().is_disconnected()
+            if pending_room.is_pending()
+                || pending_room.is_invited()
+                || pending_room.state().is_disconnected()
             {
+                // When rejoining a room (e.g. after a reconnect) we'll try to keep our
+                // previous nickname…
+                let nickname = pending_room
+                    .state()
+                    .is_disconnected()
+                    .then(|| pending_room.occupant_id())
+                    .flatten()
+                    .map(|occupant_id| occupant_id.nickname().to_string())
+                    .unwrap_or_else(|| nickname.to_string());
+
                 pending_room.set_state(RoomState::Connecting);
-                return Ok(RoomStatus::Exists(pending_room));
+                return Ok((RoomStatus::Exists(pending_room), nickname));
             }
         }
 
@@ -1225,7 +1269,7 @@ impl RoomsDomainService {
         self.connected_rooms_repo
             .set(account, room.clone())
             .map_err(|_| RoomError::RoomIsAlreadyConnected(room_id))?;
-        Ok(RoomStatus::IsNew(room))
+        Ok((RoomStatus::IsNew(room), nickname.to_string()))
     }
 
     /// Attempts to join a chat room with a unique nickname, retrying with modified nicknames upon conflicts.
@@ -11,7 +11,8 @@ use prose_xmpp::RequestError;
 
 use crate::domain::general::models::Capabilities;
 use crate::domain::rooms::models::{
-    PublicRoomInfo, RoomConfig, RoomError, RoomSessionInfo, RoomSpec,
+    PublicRoomInfo, RoomConfig, RoomConfiguration, RoomError, RoomSessionInfo, RoomSessionMember,
+    RoomSpec,
 };
 use crate::domain::shared::models::{MucId, OccupantId, UserId};
 use crate::dtos::Availability;
@@ -53,6 +54,20 @@ pub trait RoomManagementService: SendUnlessWasm + SyncUnlessWasm {
 
     async fn load_room_config(&self, room_id: &MucId) -> Result<RoomConfig, RoomError>;
 
+    /// Loads the editable configuration of the room via its configuration form.
+    async fn load_room_configuration(
+        &self,
+        room_id: &MucId,
+    ) -> Result<RoomConfiguration, RoomError>;
+
+    /// Submits `configuration` for the room. Form fields not covered by `RoomConfiguration`
+    /// keep their current values.
+    async fn update_room_configuration(
+        &self,
+        room_id: &MucId,
+        configuration: RoomConfiguration,
+    ) -> Result<(), RoomError>;
+
     async fn exit_room(&self, occupant_id: &OccupantId) -> Result<(), RoomError>;
 
     async fn set_room_owners(&self, room_id: &MucId, users: &[UserId]) -> Result<(), RoomError>;
@@ -60,10 +75,16 @@ pub trait RoomManagementService: SendUnlessWasm + SyncUnlessWasm {
     async fn send_self_ping(&self, occupant_id: &OccupantId) -> Result<(), RequestError>;
 
     /// Destroys the room identified by `room_id`. If specified sets `alternate_room` as
-    /// replacement room, so that users will be redirected there.
+    /// replacement room, so that users will be redirected there. `reason` is relayed to the
+    /// occupants of the room.
     async fn destroy_room(
         &self,
         room_id: &MucId,
         alternate_room: Option<MucId>,
+        reason: Option<&str>,
     ) -> Result<(), RoomError>;
+
+    /// Loads the owners, admins and members of the room from its affiliation lists.
+    async fn load_room_members(&self, room_id: &MucId)
+        -> Result<Vec<RoomSessionMember>, RoomError>;
 }
@@ -7,8 +7,9 @@ use async_trait::async_trait;
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
-use crate::domain::rooms::models::RoomError;
-use crate::domain::shared::models::{MucId, UserId};
+use crate::domain::messaging::models::MessageServerId;
+use crate::domain::rooms::models::{RoomAffiliation, RoomError, RoomRole};
+use crate::domain::shared::models::{MucId, OccupantId, UserId};
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[async_trait]
@@ -20,9 +21,43 @@ pub trait RoomParticipationService: SendUnlessWasm + SyncUnlessWasm {
         participants: &[UserId],
     ) -> Result<(), RoomError>;
 
-    async fn grant_membership(
+    /// Changes the affiliation of `participant`, who doesn't need to be in the room currently.
+    /// Setting the affiliation to `Outcast` bans the user.
+    async fn set_affiliation(
         &self,
         room_id: &MucId,
         participant: &UserId,
+        affiliation: RoomAffiliation,
+        reason: Option<&str>,
     ) -> Result<(), RoomError>;
+
+    /// Changes the role of the occupant identified by `occupant_id`. Setting the role to `None`
+    /// kicks the occupant.
+    async fn set_role(
+        &self,
+        occupant_id: &OccupantId,
+        role: RoomRole,
+        reason: Option<&str>,
+    ) -> Result<(), RoomError>;
+
+    /// Loads the ids of all users with `affiliation` from the room's admin list, e.g. the ban
+    /// list for `Outcast`.
+    async fn load_users_with_affiliation(
+        &self,
+        room_id: &MucId,
+        affiliation: RoomAffiliation,
+    ) -> Result<Vec<UserId>, RoomError>;
+
+    /// Asks the room to retract the message with `stanza_id` on behalf of a moderator
+    /// (XEP-0425).
+    async fn moderate_message(
+        &self,
+        room_id: &MucId,
+        stanza_id: &MessageServerId,
+        reason: Option<&str>,
+    ) -> Result<(), RoomError>;
+
+    /// Loads the ids of all occupants currently in the room via a disco#items query. Used when
+    /// the room keeps a bounded participant list.
+    async fn load_occupants(&self, room_id: &MucId) -> Result<Vec<OccupantId>, RoomError>;
 }
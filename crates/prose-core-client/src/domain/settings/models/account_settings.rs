@@ -4,21 +4,38 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use crate::domain::shared::models::Availability;
+use crate::domain::user_info::models::UserStatus;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct AccountSettings {
     /// The last configured availability
     pub availability: Availability,
+    /// The status message sent along with our presence
+    #[serde(default)]
+    pub status_message: Option<String>,
     /// The generated resource string use to form a FullJid
     pub resource: Option<String>,
+    /// Whether other participants should be notified when our user has read their messages
+    #[serde(default = "default_send_read_receipts")]
+    pub send_read_receipts: bool,
+    /// The last published user activity, restored when reconnecting
+    #[serde(default)]
+    pub user_status: Option<UserStatus>,
 }
 
 impl Default for AccountSettings {
     fn default() -> Self {
         AccountSettings {
             availability: Availability::Available,
+            status_message: None,
             resource: None,
+            send_read_receipts: default_send_read_receipts(),
+            user_status: None,
         }
     }
 }
+
+fn default_send_read_receipts() -> bool {
+    true
+}
@@ -5,8 +5,10 @@
 
 pub use account_settings::AccountSettings;
 pub use local_room_settings::LocalRoomSettings;
-pub use synced_room_settings::SyncedRoomSettings;
+pub use notification_policy::NotificationPolicy;
+pub use synced_room_settings::{RoomNotificationMode, SyncedRoomSettings};
 
 mod account_settings;
 mod local_room_settings;
+mod notification_policy;
 mod synced_room_settings;
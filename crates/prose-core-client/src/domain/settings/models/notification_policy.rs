@@ -0,0 +1,13 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+/// Controls when `ClientRoomEventType::NotificationRequested` is dispatched for received
+/// messages. Apps that show notifications based on these events don't have to check the
+/// availability of our user or the notification mode of the room themselves.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NotificationPolicy {
+    /// Don't request notifications while our user is `Availability::DoNotDisturb`.
+    pub suppress_while_do_not_disturb: bool,
+}
@@ -5,7 +5,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::domain::messaging::models::ArchivedMessageRef;
+use crate::domain::messaging::models::{ArchivedMessageRef, PinnedMessageRef};
 use crate::dtos::RoomId;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -13,6 +13,26 @@ pub struct SyncedRoomSettings {
     pub room_id: RoomId,
     pub encryption_enabled: bool,
     pub last_read_message: Option<ArchivedMessageRef>,
+    #[serde(default)]
+    pub pinned_messages: Vec<PinnedMessageRef>,
+    #[serde(default)]
+    pub notifications: RoomNotificationMode,
+    /// Whether read markers should be sent to this room. Read markers are only sent if they're
+    /// enabled in the account settings as well.
+    #[serde(default = "default_send_read_receipts")]
+    pub send_read_receipts: bool,
+}
+
+/// Controls which messages of a room are counted as unread.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RoomNotificationMode {
+    /// Every message is counted as unread.
+    #[default]
+    All,
+    /// Only messages that mention our user are counted as unread.
+    MentionsOnly,
+    /// The room is muted and never marked as unread.
+    None,
 }
 
 impl SyncedRoomSettings {
@@ -21,6 +41,13 @@ impl SyncedRoomSettings {
             room_id,
             encryption_enabled: false,
             last_read_message: Default::default(),
+            pinned_messages: vec![],
+            notifications: Default::default(),
+            send_read_receipts: default_send_read_receipts(),
         }
     }
 }
+
+fn default_send_read_receipts() -> bool {
+    true
+}
@@ -0,0 +1,48 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use prose_store::prelude::CollectionUsage;
+
+/// The number of cached items of a store and the space they take up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreUsage {
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+/// The space taken up by the caches of the connected account.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheUsageReport {
+    /// Messages including their attachments.
+    pub messages: StoreUsage,
+    pub avatars: StoreUsage,
+    pub user_profiles: StoreUsage,
+    pub encryption_sessions: StoreUsage,
+}
+
+impl CacheUsageReport {
+    pub fn total(&self) -> StoreUsage {
+        [
+            self.messages,
+            self.avatars,
+            self.user_profiles,
+            self.encryption_sessions,
+        ]
+        .into_iter()
+        .fold(StoreUsage::default(), |total, usage| StoreUsage {
+            rows: total.rows + usage.rows,
+            bytes: total.bytes + usage.bytes,
+        })
+    }
+}
+
+impl From<CollectionUsage> for StoreUsage {
+    fn from(value: CollectionUsage) -> Self {
+        Self {
+            rows: value.rows,
+            bytes: value.bytes,
+        }
+    }
+}
@@ -8,6 +8,7 @@ pub use anon_occupant_id::AnonOccupantId;
 pub use availability::Availability;
 pub use avatar_id::AvatarId;
 pub use cache_policy::CachePolicy;
+pub use cache_usage::{CacheUsageReport, StoreUsage};
 pub use capabilities_id::CapabilitiesId;
 pub use connection_state::ConnectionState;
 pub use mam_version::MamVersion;
@@ -16,6 +17,7 @@ pub use muc_id::MucId;
 pub use occupant_id::OccupantId;
 pub use participant_id::{ParticipantId, ParticipantIdRef};
 pub use request_id::RequestId;
+pub use retention_policy::RetentionPolicy;
 pub use room_id::RoomId;
 pub use room_type::RoomType;
 pub use sender_id::SenderId;
@@ -25,7 +27,9 @@ pub use string_index::{
 };
 pub use user_endpoint_id::UserEndpointId;
 pub use user_id::UserId;
-pub use user_info::{ParticipantBasicInfo, ParticipantInfo, UserBasicInfo, UserPresenceInfo};
+pub use user_info::{
+    ParticipantBasicInfo, ParticipantInfo, ParticipantProfile, UserBasicInfo, UserPresenceInfo,
+};
 pub use user_or_resource_id::UserOrResourceId;
 pub use user_resource_id::UserResourceId;
 
@@ -34,6 +38,7 @@ mod anon_occupant_id;
 mod availability;
 mod avatar_id;
 mod cache_policy;
+mod cache_usage;
 mod capabilities_id;
 mod connection_state;
 mod mam_version;
@@ -42,6 +47,7 @@ mod muc_id;
 mod occupant_id;
 mod participant_id;
 mod request_id;
+mod retention_policy;
 mod room_id;
 mod room_type;
 mod sender_id;
@@ -0,0 +1,20 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use chrono::TimeDelta;
+
+/// Limits how much data is kept in the local cache. Messages that were not read yet and messages
+/// that are still waiting to be sent are never deleted, regardless of the policy.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionPolicy {
+    /// Messages older than this are deleted.
+    pub max_message_age: Option<TimeDelta>,
+    /// Only the newest `max_messages_per_room` messages of each room are kept. Reactions,
+    /// retractions and corrections don't count towards this limit.
+    pub max_messages_per_room: Option<u32>,
+    /// The least recently updated avatars are deleted until the avatar cache fits into this
+    /// number of bytes.
+    pub max_avatar_cache_bytes: Option<u64>,
+}
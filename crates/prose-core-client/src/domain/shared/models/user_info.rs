@@ -3,9 +3,11 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use chrono::{DateTime, Utc};
+
 use super::{Availability, ParticipantId, UserId};
-use crate::domain::rooms::models::{Participant, RoomAffiliation};
-use crate::domain::user_info::models::{Avatar, JabberClient};
+use crate::domain::rooms::models::{Participant, RoomAffiliation, RoomRole};
+use crate::domain::user_info::models::{Avatar, FallbackAvatar, JabberClient};
 use crate::dtos::UserStatus;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -23,6 +25,8 @@ pub struct UserPresenceInfo {
     pub availability: Availability,
     pub avatar: Option<Avatar>,
     pub status: Option<UserStatus>,
+    pub status_message: Option<String>,
+    pub idle_since: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -45,6 +49,31 @@ pub struct ParticipantInfo {
     pub status: Option<String>,
 }
 
+impl ParticipantInfo {
+    /// The placeholder to display if `avatar` is `None`.
+    pub fn fallback_avatar(&self) -> FallbackAvatar {
+        match &self.user_id {
+            Some(user_id) => FallbackAvatar::for_user(user_id, Some(&self.name)),
+            None => FallbackAvatar::for_participant(&self.id, Some(&self.name)),
+        }
+    }
+}
+
+/// A participant of a room including the profile information of the associated user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticipantProfile {
+    pub id: ParticipantId,
+    pub user_id: Option<UserId>,
+    pub name: String,
+    pub full_name: Option<String>,
+    pub is_self: bool,
+    pub availability: Availability,
+    pub affiliation: RoomAffiliation,
+    pub role: RoomRole,
+    pub avatar: Option<Avatar>,
+    pub status: Option<UserStatus>,
+}
+
 impl From<(&ParticipantId, &Participant)> for ParticipantInfo {
     fn from(value: (&ParticipantId, &Participant)) -> Self {
         let (id, participant) = value;
@@ -4,4 +4,5 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 pub mod models;
+pub mod repos;
 pub mod services;
@@ -3,15 +3,38 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use serde::{Deserialize, Serialize};
+
 use crate::domain::rooms::models::RoomSidebarState;
-use crate::domain::shared::models::RoomId;
+use crate::domain::shared::models::{RoomId, UserId};
 
 use super::BookmarkType;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Bookmark {
     pub name: String,
     pub jid: RoomId,
     pub r#type: BookmarkType,
     pub sidebar_state: RoomSidebarState,
+    /// The position of the item in the manually ordered section of the sidebar, if any.
+    #[serde(default)]
+    pub order_index: Option<u32>,
+    /// Set if our user was invited to the room but didn't accept the invitation yet.
+    #[serde(default)]
+    pub invitation: Option<BookmarkInvitation>,
+}
+
+/// A pending invitation, saved with the bookmark so that it survives a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookmarkInvitation {
+    pub sender: UserId,
+    pub password: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl Bookmark {
+    /// Returns `false` if the room must not be joined before our user accepted the invitation.
+    pub fn autojoin(&self) -> bool {
+        self.invitation.is_none()
+    }
 }
@@ -3,7 +3,7 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-pub use bookmark::Bookmark;
+pub use bookmark::{Bookmark, BookmarkInvitation};
 pub use bookmark_type::BookmarkType;
 
 mod bookmark;
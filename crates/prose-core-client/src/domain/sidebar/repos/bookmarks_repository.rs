@@ -0,0 +1,27 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use async_trait::async_trait;
+use jid::BareJid;
+
+use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
+
+use crate::domain::shared::models::AccountId;
+use crate::domain::sidebar::models::Bookmark;
+
+/// A local copy of our bookmarks, which allows for drawing the sidebar before the connection
+/// is established.
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+#[cfg_attr(feature = "test", mockall::automock)]
+pub trait BookmarksRepository: SendUnlessWasm + SyncUnlessWasm {
+    async fn get_all(&self, account: &AccountId) -> Result<Vec<Bookmark>>;
+    /// Replaces all cached bookmarks of `account` with `bookmarks`.
+    async fn replace_all(&self, account: &AccountId, bookmarks: &[Bookmark]) -> Result<()>;
+    async fn put(&self, account: &AccountId, bookmark: &Bookmark) -> Result<()>;
+    async fn delete(&self, account: &AccountId, room_id: &BareJid) -> Result<()>;
+    async fn clear_cache(&self, account: &AccountId) -> Result<()>;
+}
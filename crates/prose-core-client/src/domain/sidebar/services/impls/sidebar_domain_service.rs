@@ -12,27 +12,34 @@ use prose_proc_macros::DependenciesStruct;
 use prose_wasm_utils::ProseFutureExt;
 
 use crate::app::deps::{
-    DynAppContext, DynBookmarksService, DynClientEventDispatcher, DynConnectedRoomsRepository,
-    DynRoomManagementService, DynRoomsDomainService,
+    DynAppContext, DynBookmarksRepository, DynBookmarksService, DynClientEventDispatcher,
+    DynConnectedRoomsRepository, DynMessagesRepository, DynRoomManagementService,
+    DynRoomsDomainService,
 };
 use crate::domain::encryption::models::DecryptionContext;
 use crate::domain::messaging::models::MessageLike;
-use crate::domain::rooms::models::{Room, RoomError, RoomSidebarState, RoomSpec, RoomState};
+use crate::domain::rooms::models::{
+    Room, RoomError, RoomRemovalReason, RoomSidebarState, RoomSpec, RoomState,
+};
 use crate::domain::rooms::services::impls::build_nickname;
 use crate::domain::rooms::services::{CreateOrEnterRoomRequest, JoinRoomBehavior};
-use crate::domain::shared::models::{MucId, ParticipantId, RoomId, RoomType, UserId};
-use crate::domain::sidebar::models::{Bookmark, BookmarkType};
+use crate::domain::shared::models::{
+    AccountId, MucId, ParticipantId, RoomId, RoomType, UserId, UserResourceId,
+};
+use crate::domain::sidebar::models::{Bookmark, BookmarkInvitation, BookmarkType};
 use crate::util::join_all;
-use crate::ClientEvent;
+use crate::{ClientEvent, ClientRoomEventType};
 
 use super::super::SidebarDomainService as SidebarDomainServiceTrait;
 
 #[derive(DependenciesStruct)]
 pub struct SidebarDomainService {
+    bookmarks_repo: DynBookmarksRepository,
     bookmarks_service: DynBookmarksService,
     client_event_dispatcher: DynClientEventDispatcher,
     connected_rooms_repo: DynConnectedRoomsRepository,
     ctx: DynAppContext,
+    messages_repo: DynMessagesRepository,
     room_management_service: DynRoomManagementService,
     rooms_domain_service: DynRoomsDomainService,
 }
@@ -43,13 +50,59 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
     /// Extends the sidebar with items by loading bookmarks from the remote PubSub node.
     ///
     /// Loads the remote bookmarks then proceeds with the logic details
-    /// in `extend_items_from_bookmarks`.
+    /// in `extend_items_from_bookmarks`. Cached rooms (see `load_cached_sidebar`) which are not
+    /// bookmarked anymore are removed and the local bookmarks cache is replaced.
     async fn populate_sidebar(&self, context: DecryptionContext) -> Result<()> {
+        let account = self.ctx.connected_account()?;
         let bookmarks = self.bookmarks_service.load_bookmarks().await?;
+
+        if let Err(err) = self.bookmarks_repo.replace_all(&account, &bookmarks).await {
+            error!("Failed to cache bookmarks. Reason: {}", err.to_string());
+        }
+
+        for room in self.connected_rooms_repo.get_all(&account) {
+            if room.state().is_disconnected()
+                && bookmarks.iter().find(|b| b.jid == room.room_id).is_none()
+            {
+                self.connected_rooms_repo
+                    .delete(&account, room.room_id.as_ref());
+            }
+        }
+
         self.extend_items_from_bookmarks(bookmarks, context).await?;
         Ok(())
     }
 
+    /// Fills the sidebar with disconnected rooms from the locally cached bookmarks so that it can
+    /// be drawn before the connection is established. The rooms will be replaced once
+    /// `populate_sidebar` is called after connecting.
+    ///
+    /// Dispatches a `ClientEvent::SidebarChanged` event after processing.
+    async fn load_cached_sidebar(&self, account: &AccountId) -> Result<()> {
+        let bookmarks = self.bookmarks_repo.get_all(account).await?;
+        let nickname = build_nickname(None, &account.to_user_id());
+
+        for bookmark in bookmarks {
+            if !bookmark.sidebar_state.is_in_sidebar() {
+                continue;
+            }
+            let room = Room::pending(&bookmark, &nickname);
+            // Pending invitations stay pending, everything else is connected later on…
+            if !room.is_invited() {
+                room.set_state(RoomState::Disconnected {
+                    error: None,
+                    can_retry: true,
+                });
+            }
+            self.connected_rooms_repo.set_or_replace(account, room);
+        }
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::SidebarChanged);
+
+        Ok(())
+    }
+
     /// Extends the sidebar with items from a collection of bookmarks.
     ///
     /// Iterates through the provided bookmarks and performs the following actions:
@@ -103,7 +156,12 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
                 room.set_sidebar_state(RoomSidebarState::InSidebar);
                 room
             }
-            Err(error) => return Err(error.into()),
+            Err(error) => {
+                // The connecting room has either been removed or marked as disconnected…
+                self.client_event_dispatcher
+                    .dispatch_event(ClientEvent::SidebarChanged);
+                return Err(error.into());
+            }
         };
 
         // If the room already existed and was silently returned by the RoomsDomainService, make
@@ -154,7 +212,7 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
             }
         };
 
-        room.set_needs_update_statistics();
+        room.apply_received_message_to_statistics(&account, message);
 
         match room.r#type {
             RoomType::DirectMessage => (),
@@ -177,7 +235,129 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
         Ok(())
     }
 
-    async fn destroy_room(&self, room_id: &MucId) -> Result<()> {
+    /// Inserts a sidebar item in the `RoomState::Invited` state for the room our user was invited
+    /// to, so that they can either accept or decline the invitation.
+    ///
+    /// - Invitations sent by our user (e.g. from another device) are accepted right away.
+    /// - Invitations to rooms that we're connected to already just make sure that the room
+    ///   appears in the sidebar.
+    /// - Repeated invitations to the same room, e.g. a direct and a mediated invitation sent
+    ///   together, update the pending invitation. The password of the earlier invitation is
+    ///   kept if the later one doesn't carry one.
+    /// - Saves a bookmark with autojoin off (see `Bookmark::invitation`), so that the invitation
+    ///   survives a restart and shows up on our other devices.
+    ///
+    /// Dispatches a `ClientEvent::SidebarChanged` and a `ClientRoomEventType::InvitationReceived`
+    /// event for new invitations.
+    async fn handle_received_invitation(
+        &self,
+        room_id: &MucId,
+        sender: &UserResourceId,
+        password: Option<String>,
+        reason: Option<String>,
+    ) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+        let sender = sender.to_user_id();
+
+        let existing_room = self.connected_rooms_repo.get(&account, room_id.as_ref());
+
+        if sender == account.to_user_id()
+            || existing_room
+                .as_ref()
+                .is_some_and(|room| !room.is_invited())
+        {
+            self.insert_item_by_creating_or_joining_room(CreateOrEnterRoomRequest::JoinRoom {
+                room_id: room_id.clone(),
+                password,
+                behavior: JoinRoomBehavior::system_initiated(),
+                decryption_context: None,
+            })
+            .await?;
+            return Ok(());
+        }
+
+        if let Some(room) = existing_room {
+            info!("Updating pending invitation to room {room_id} from {sender}…");
+            let (existing_password, existing_reason) = match room.state() {
+                RoomState::Invited {
+                    password, reason, ..
+                } => (password, reason),
+                _ => (None, None),
+            };
+            room.set_state(RoomState::Invited {
+                sender,
+                password: password.or(existing_password),
+                reason: reason.or(existing_reason),
+            });
+            self.save_bookmark_for_room(&room).await;
+            return Ok(());
+        }
+
+        info!("Received invitation to room {room_id} from {sender}…");
+
+        let room = Room::invited(
+            room_id,
+            &build_nickname(None, &account.to_user_id()),
+            sender.clone(),
+            password,
+            reason,
+        );
+        self.connected_rooms_repo
+            .set(&account, room.clone())
+            .map_err(|_| format_err!("Room {room_id} was inserted in the meantime."))?;
+
+        self.save_bookmark_for_room(&room).await;
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::SidebarChanged);
+        self.client_event_dispatcher
+            .dispatch_room_event(room, ClientRoomEventType::InvitationReceived { sender });
+
+        Ok(())
+    }
+
+    /// Joins the room our user was invited to and replaces its bookmark with one that has
+    /// autojoin on, so that it's joined automatically from now on.
+    async fn accept_invitation(&self, room_id: &MucId) -> Result<RoomId> {
+        let RoomState::Invited { password, .. } = self.try_get_room(room_id.as_ref())?.state()
+        else {
+            bail!("There is no pending invitation to room {room_id}.")
+        };
+
+        self.insert_item_by_creating_or_joining_room(CreateOrEnterRoomRequest::JoinRoom {
+            room_id: room_id.clone(),
+            password,
+            behavior: JoinRoomBehavior::user_initiated(),
+            decryption_context: None,
+        })
+        .await
+    }
+
+    /// Removes the sidebar item and the bookmark of the room our user was invited to.
+    ///
+    /// Dispatches a `ClientEvent::SidebarChanged` event after processing.
+    async fn decline_invitation(&self, room_id: &MucId) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        if !self.try_get_room(room_id.as_ref())?.is_invited() {
+            bail!("There is no pending invitation to room {room_id}.")
+        }
+
+        self.connected_rooms_repo.delete(&account, room_id.as_ref());
+        self.delete_bookmark(room_id.as_ref()).await;
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::SidebarChanged);
+
+        Ok(())
+    }
+
+    async fn destroy_room(
+        &self,
+        room_id: &MucId,
+        alternate_room: Option<MucId>,
+        reason: Option<&str>,
+    ) -> Result<()> {
         let account = self.ctx.connected_account()?;
 
         if self
@@ -190,7 +370,7 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
 
         match self
             .room_management_service
-            .destroy_room(room_id, None)
+            .destroy_room(room_id, alternate_room, reason)
             .await
         {
             Ok(_) => (),
@@ -202,7 +382,9 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
             }
         }
 
-        self.connected_rooms_repo.delete(&account, room_id.as_ref());
+        if let Some(room) = self.connected_rooms_repo.delete(&account, room_id.as_ref()) {
+            self.purge_destroyed_room(&account, room).await;
+        }
         self.delete_bookmark(room_id.as_ref()).await;
 
         self.client_event_dispatcher
@@ -275,6 +457,50 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
         Ok(())
     }
 
+    /// Sets the manual order of the sidebar items. Items which are not contained in `room_ids`
+    /// lose their position and are sorted by their last activity instead.
+    ///
+    /// - The bookmarks of all items whose position changed will be updated.
+    /// - `ClientEvent::SidebarChanged` will be dispatched after processing.
+    async fn set_manual_order(&self, room_ids: &[RoomId]) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+        let mut changed_rooms = vec![];
+
+        for room in self.connected_rooms_repo.get_all(&account) {
+            if !room.sidebar_state().is_in_sidebar() {
+                continue;
+            }
+
+            let order_index = room_ids
+                .iter()
+                .position(|id| id == &room.room_id)
+                .map(|idx| idx as u32);
+
+            if room.order_index() == order_index {
+                continue;
+            }
+
+            room.set_order_index(order_index);
+            changed_rooms.push(room);
+        }
+
+        if changed_rooms.is_empty() {
+            return Ok(());
+        }
+
+        join_all(
+            changed_rooms
+                .iter()
+                .map(|room| self.save_bookmark_for_room(room)),
+        )
+        .await;
+
+        self.client_event_dispatcher
+            .dispatch_event(ClientEvent::SidebarChanged);
+
+        Ok(())
+    }
+
     /// Reconfigures the sidebar item identified by `room_id` according to `spec`.
     ///
     /// If the item is not in the list of sidebar items no action is performed, otherwise:
@@ -330,6 +556,9 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
 
             self.disconnect_and_delete_room(&room).await;
 
+            // Items which are not in the sidebar don't take part in the manual ordering…
+            room.set_order_index(None);
+
             match room.r#type {
                 // For Groups and Private Channels we do not really delete the bookmarks. The reason
                 // is that Groups should always be connected so that our user can receive messages from
@@ -353,6 +582,28 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
         Ok(())
     }
 
+    /// Handles remote changes of bookmarks, e.g. made on another device.
+    ///
+    /// - Updates the local bookmarks cache.
+    /// - Proceeds like `extend_items_from_bookmarks`, i.e. joins rooms that were added, leaves
+    ///   rooms that were removed from the sidebar and updates the remaining items.
+    async fn handle_changed_items(
+        &self,
+        bookmarks: Vec<Bookmark>,
+        context: DecryptionContext,
+    ) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+
+        for bookmark in &bookmarks {
+            if let Err(err) = self.bookmarks_repo.put(&account, bookmark).await {
+                error!("Failed to cache bookmark. Reason: {}", err.to_string());
+            }
+        }
+
+        self.extend_items_from_bookmarks_with_context(bookmarks, context)
+            .await
+    }
+
     /// Handles remote deletion of bookmarks.
     ///
     /// - Disconnects channels and updates the repository state for each provided JID.
@@ -361,6 +612,7 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
     async fn handle_removed_items(&self, room_ids: &[BareJid]) -> Result<()> {
         let account = self.ctx.connected_account()?;
         for id in room_ids {
+            self.delete_cached_bookmark(&account, id).await;
             let Some(room) = self.connected_rooms_repo.get(&account, id) else {
                 continue;
             };
@@ -380,11 +632,17 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
     /// purged or deleted altogether. It should usually only happen when debugging.
     async fn handle_remote_purge(&self) -> Result<()> {
         // No need to delete the bookmarks here since that is the raison d'être for this method.
-        // We'll only need to delete the connected rooms.
-        for room in self
-            .connected_rooms_repo
-            .delete_all(&self.ctx.connected_account()?)
-        {
+        // We'll only need to delete the connected rooms and our local copy of the bookmarks.
+        let account = self.ctx.connected_account()?;
+
+        if let Err(err) = self.bookmarks_repo.clear_cache(&account).await {
+            error!(
+                "Failed to clear cached bookmarks. Reason: {}",
+                err.to_string()
+            );
+        }
+
+        for room in self.connected_rooms_repo.delete_all(&account) {
             if let Some(occupant_id) = room.occupant_id() {
                 self.room_management_service.exit_room(&occupant_id).await?;
             }
@@ -408,15 +666,14 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
     ) -> Result<()> {
         let account = self.ctx.connected_account()?;
 
-        let Some(alternate_room) = alternate_room else {
-            let Some(room) = self.connected_rooms_repo.get(&account, room_id.as_ref()) else {
-                return Ok(());
-            };
+        // Remove the destroyed room…
+        let Some(room) = self.connected_rooms_repo.delete(&account, room_id) else {
+            return Ok(());
+        };
+        self.purge_destroyed_room(&account, room.clone()).await;
 
-            room.set_state(RoomState::Disconnected {
-                error: Some("This room has been destroyed.".to_string()),
-                can_retry: false,
-            });
+        let Some(alternate_room) = alternate_room else {
+            self.delete_bookmark(room_id).await;
 
             self.client_event_dispatcher
                 .dispatch_event(ClientEvent::SidebarChanged);
@@ -424,11 +681,6 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
             return Ok(());
         };
 
-        // Remove the destroyed room…
-        let Some(room) = self.connected_rooms_repo.delete(&account, room_id) else {
-            return Ok(());
-        };
-
         // We're already connected to the alternate room.
         if self
             .connected_rooms_repo
@@ -448,6 +700,8 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
                     jid: alternate_room.clone().into(),
                     r#type: room.r#type.into(),
                     sidebar_state: room.sidebar_state(),
+                    order_index: room.order_index(),
+                    invitation: None,
                 },
                 &build_nickname(None, &self.ctx.connected_id()?.to_user_id()),
             ),
@@ -496,13 +750,14 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
 
     /// Handles removal from a room.
     ///
-    /// If the removal is temporary:
-    /// - Deletes the connected room.
-    /// - Sets an error on the corresponding sidebar item.
+    /// - Puts the connected room into a disconnected state with an error describing the `reason`.
+    /// - Dispatches a `ClientRoomEventType::RemovedFromRoom` event for the room.
     /// - Dispatches a `ClientEvent::SidebarChanged` event after processing.
-    ///
-    /// If the removal is permanent, follows the procedure described in `handle_destroyed_room`.
-    async fn handle_removal_from_room(&self, room_id: &MucId, is_permanent: bool) -> Result<()> {
+    async fn handle_removal_from_room(
+        &self,
+        room_id: &MucId,
+        reason: RoomRemovalReason,
+    ) -> Result<()> {
         let Some(room) = self
             .connected_rooms_repo
             .get(&self.ctx.connected_account()?, room_id)
@@ -510,18 +765,25 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
             return Ok(());
         };
 
+        let error = match reason {
+            RoomRemovalReason::ServiceShutdown => "You've been temporarily removed from this room.",
+            RoomRemovalReason::Kicked => "You've been kicked from this room.",
+            RoomRemovalReason::Banned => "You've been banned from this room.",
+            RoomRemovalReason::MembershipRevoked => {
+                "Your membership of this room has been revoked."
+            }
+            RoomRemovalReason::MembersOnly => {
+                "This room has been made members-only and you're not a member."
+            }
+        };
+
         room.set_state(RoomState::Disconnected {
-            error: Some(format!(
-                "You've been {} removed from this room.",
-                if is_permanent {
-                    "permanently"
-                } else {
-                    "temporarily"
-                }
-            )),
-            can_retry: !is_permanent,
+            error: Some(error.to_string()),
+            can_retry: !reason.is_permanent(),
         });
 
+        self.client_event_dispatcher
+            .dispatch_room_event(room, ClientRoomEventType::RemovedFromRoom { reason });
         self.client_event_dispatcher
             .dispatch_event(ClientEvent::SidebarChanged);
 
@@ -531,9 +793,10 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
     /// Handles a changed room configuration.
     ///
     /// - Reloads the configuration and adjusts the connected room accordingly.
-    /// - Replaces the connected room if the type of room changed.
+    /// - Replaces the connected room if the type or features of the room changed.
+    /// - Dispatches a `ClientRoomEventType::AttributesChanged` event for the reloaded room.
     /// - Updates the sidebar & associated bookmark to reflect the updated configuration.
-    /// - Dispatches a `ClientEvent::SidebarChanged` event after processing.
+    /// - Dispatches a `ClientEvent::SidebarChanged` event if the sidebar changed.
     async fn handle_changed_room_config(&self, room_id: &MucId) -> Result<()> {
         let Some(room) = self
             .connected_rooms_repo
@@ -549,22 +812,26 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
 
         let former_name = room.name();
         let former_type = room.r#type;
+        let former_features = room.features.clone();
 
         let room = self
             .rooms_domain_service
             .reevaluate_room_spec(room_id)
             .await?;
 
-        let new_name = room.name();
-        let new_type = room.r#type;
+        // The room might have been replaced, so make sure that its latest version is reported…
+        self.client_event_dispatcher
+            .dispatch_room_event(room.clone(), ClientRoomEventType::AttributesChanged);
+
+        let bookmark_changed = room.name() != former_name || room.r#type != former_type;
 
-        if new_name == former_name && new_type == former_type {
+        if bookmark_changed {
+            self.save_bookmark_for_room(&room).await;
+        } else if room.features == former_features {
             info!("No changes required for bookmark {}.", room_id);
             return Ok(());
         }
 
-        self.save_bookmark_for_room(&room).await;
-
         self.client_event_dispatcher
             .dispatch_event(ClientEvent::SidebarChanged);
 
@@ -609,6 +876,10 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
         };
 
         for room in self.connected_rooms_repo.get_all(&account) {
+            // Invitations stay pending, otherwise we'd join the room when reconnecting…
+            if room.is_invited() {
+                continue;
+            }
             room.set_state(RoomState::Disconnected {
                 error: None,
                 can_retry: true,
@@ -617,12 +888,59 @@ impl SidebarDomainServiceTrait for SidebarDomainService {
         Ok(())
     }
 
+    async fn handle_reconnect(&self, context: DecryptionContext) -> Result<()> {
+        let account = self.ctx.connected_account()?;
+        let mut join_room_futures = vec![];
+
+        for room in self.connected_rooms_repo.get_all(&account) {
+            let RoomState::Disconnected {
+                can_retry: true, ..
+            } = room.state()
+            else {
+                continue;
+            };
+
+            let request = match &room.room_id {
+                RoomId::Muc(room_id) => CreateOrEnterRoomRequest::JoinRoom {
+                    room_id: room_id.clone(),
+                    password: None,
+                    behavior: JoinRoomBehavior::system_initiated(),
+                    decryption_context: Some(context.clone()),
+                },
+                RoomId::User(user_id) => CreateOrEnterRoomRequest::JoinDirectMessage {
+                    participant: user_id.clone(),
+                    decryption_context: Some(context.clone()),
+                },
+            };
+
+            join_room_futures.push(async move {
+                let result = self
+                    .rooms_domain_service
+                    .create_or_join_room(request, room.sidebar_state())
+                    .await;
+                (room.room_id.clone(), result)
+            });
+        }
+
+        for (room_id, result) in join_all(join_room_futures).await {
+            if let Err(error) = result {
+                error!(
+                    "Failed to rejoin room '{room_id}' after reconnect. Reason: {}",
+                    error.to_string()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     /// Removes all connected rooms and sidebar items.
     ///
     /// Call this method after logging out.
     async fn clear_cache(&self) -> Result<()> {
-        self.connected_rooms_repo
-            .delete_all(&self.ctx.connected_account()?);
+        let account = self.ctx.connected_account()?;
+        self.connected_rooms_repo.delete_all(&account);
+        self.bookmarks_repo.clear_cache(&account).await?;
         Ok(())
     }
 }
@@ -641,6 +959,9 @@ impl SidebarDomainService {
         let rooms = self.connected_rooms_repo.get_all(&account);
         let mut rooms_changed = false;
 
+        // Rooms that we were invited to are joined if the invitation was accepted on another
+        // device, which is why they don't count as joined (see `Room::is_joined`).
+        //
         // We don't need to diff here between our connected rooms and the received bookmarks.
         // We're already receiving the diff from the PubSub node. Only when `populate_sidebar` is
         // called we're receiving all bookmarks at once, but in that case we won't have any
@@ -652,7 +973,7 @@ impl SidebarDomainService {
         for bookmark in &bookmarks {
             if rooms
                 .iter()
-                .find(|r| r.room_id == bookmark.jid && !r.state().is_disconnected())
+                .find(|r| r.room_id == bookmark.jid && r.is_joined())
                 .is_some()
             {
                 info!("Skipping {} which is already connected.", bookmark.jid);
@@ -692,13 +1013,32 @@ impl SidebarDomainService {
         for bookmark in bookmarks {
             if let Some(room) = rooms
                 .iter()
-                .find(|r| r.room_id == bookmark.jid && !r.state().is_disconnected())
+                .find(|r| r.room_id == bookmark.jid && r.is_joined())
             {
+                if room.sidebar_state().is_in_sidebar() && !bookmark.sidebar_state.is_in_sidebar() {
+                    // The item was removed from the sidebar on another device, so we'll leave the
+                    // room just like `remove_items` does (Groups stay connected though)…
+                    self.disconnect_and_delete_room(room).await;
+                    room.set_order_index(None);
+                    rooms_changed = true;
+                    continue;
+                }
                 if room.sidebar_state() != bookmark.sidebar_state {
                     // We have a room for that bookmark already, let's just update its sidebar_state…
                     room.set_sidebar_state(bookmark.sidebar_state);
                     rooms_changed = true;
                 }
+                if room.order_index() != bookmark.order_index {
+                    // …or its position in the sidebar which might have been changed on another
+                    // device.
+                    room.set_order_index(bookmark.order_index);
+                    rooms_changed = true;
+                }
+                continue;
+            }
+
+            // Rooms with a pending invitation are only joined once our user accepts it…
+            if !bookmark.autojoin() {
                 continue;
             }
 
@@ -932,6 +1272,14 @@ impl SidebarDomainService {
 
         if let Err(err) = self.bookmarks_service.save_bookmark(&bookmark).await {
             error!("Failed to save bookmark. Reason: {}", err.to_string());
+            return;
+        }
+
+        let Ok(account) = self.ctx.connected_account() else {
+            return;
+        };
+        if let Err(err) = self.bookmarks_repo.put(&account, &bookmark).await {
+            error!("Failed to cache bookmark. Reason: {}", err.to_string());
         }
     }
 
@@ -941,6 +1289,37 @@ impl SidebarDomainService {
 
         if let Err(err) = self.bookmarks_service.delete_bookmark(room_id).await {
             error!("Failed to delete bookmark. Reason: {}", err.to_string());
+            return;
+        }
+
+        let Ok(account) = self.ctx.connected_account() else {
+            return;
+        };
+        self.delete_cached_bookmark(&account, room_id).await;
+    }
+
+    /// Deletes the cached messages of the destroyed `room` and dispatches a
+    /// `ClientRoomEventType::Destroyed` event for it. Errors will be logged but otherwise ignored.
+    async fn purge_destroyed_room(&self, account: &AccountId, room: Room) {
+        if let Err(err) = self.messages_repo.clear_room(account, &room.room_id).await {
+            error!(
+                "Failed to delete messages of destroyed room {}. Reason: {}",
+                room.room_id,
+                err.to_string()
+            );
+        }
+
+        self.client_event_dispatcher
+            .dispatch_room_event(room, ClientRoomEventType::Destroyed);
+    }
+
+    /// Deletes the cached bookmark for `room_id`. Errors will be logged but otherwise ignored.
+    async fn delete_cached_bookmark(&self, account: &AccountId, room_id: &BareJid) {
+        if let Err(err) = self.bookmarks_repo.delete(account, room_id).await {
+            error!(
+                "Failed to delete cached bookmark. Reason: {}",
+                err.to_string()
+            );
         }
     }
 
@@ -970,11 +1349,26 @@ impl TryFrom<&Room> for Bookmark {
             RoomType::Generic => BookmarkType::Generic,
         };
 
+        let invitation = match value.state() {
+            RoomState::Invited {
+                sender,
+                password,
+                reason,
+            } => Some(BookmarkInvitation {
+                sender,
+                password,
+                reason,
+            }),
+            _ => None,
+        };
+
         Ok(Self {
             name: value.name().unwrap_or_else(|| value.room_id.to_string()),
             jid: value.room_id.clone(),
             r#type: bookmark_type,
             sidebar_state: value.sidebar_state(),
+            order_index: value.order_index(),
+            invitation,
         })
     }
 }
@@ -10,9 +10,9 @@ use jid::BareJid;
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
 use crate::domain::messaging::models::MessageLike;
-use crate::domain::rooms::models::RoomSpec;
+use crate::domain::rooms::models::{RoomRemovalReason, RoomSpec};
 use crate::domain::rooms::services::CreateOrEnterRoomRequest;
-use crate::domain::shared::models::{MucId, RoomId};
+use crate::domain::shared::models::{AccountId, MucId, RoomId, UserResourceId};
 use crate::domain::sidebar::models::Bookmark;
 use crate::dtos::DecryptionContext;
 
@@ -26,6 +26,10 @@ pub trait SidebarDomainService: SendUnlessWasm + SyncUnlessWasm {
     /// in `extend_items_from_bookmarks`.
     async fn populate_sidebar(&self, context: DecryptionContext) -> Result<()>;
 
+    /// Fills the sidebar with disconnected rooms from the locally cached bookmarks so that it can
+    /// be drawn before the connection is established.
+    async fn load_cached_sidebar(&self, account: &AccountId) -> Result<()>;
+
     /// Extends the sidebar with items from a collection of bookmarks.
     ///
     /// Iterates through the provided bookmarks and performs the following actions:
@@ -50,7 +54,8 @@ pub trait SidebarDomainService: SendUnlessWasm + SyncUnlessWasm {
     /// - If the room already exists in the sidebar, it returns the existing item.
     /// - For a new or joined room, it creates a new sidebar item.
     /// - Saves a bookmark for the new or joined room.
-    /// - Dispatches a `ClientEvent::SidebarChanged` event after processing.
+    /// - Dispatches a `ClientEvent::SidebarChanged` event after processing, even if the room
+    ///   could not be created or joined.
     async fn insert_item_by_creating_or_joining_room(
         &self,
         request: CreateOrEnterRoomRequest,
@@ -65,9 +70,43 @@ pub trait SidebarDomainService: SendUnlessWasm + SyncUnlessWasm {
     /// Dispatches a `ClientEvent::SidebarChanged` event after processing.
     async fn handle_received_message(&self, room_id: &RoomId, message: &MessageLike) -> Result<()>;
 
-    /// Destroys the room identified by `room_id` and the associated bookmark.
-    /// `ClientEvent::SidebarChanged` will be dispatched after processing.
-    async fn destroy_room(&self, room_id: &MucId) -> Result<()>;
+    /// Inserts a sidebar item in the `RoomState::Invited` state for the room our user was invited
+    /// to, so that they can either accept or decline the invitation.
+    ///
+    /// - Invitations sent by our user (e.g. from another device) are accepted right away.
+    /// - Invitations to rooms that we're connected to already just make sure that the room
+    ///   appears in the sidebar.
+    /// - Repeated invitations to the same room, e.g. a direct and a mediated invitation sent
+    ///   together, update the pending invitation. The password of the earlier invitation is
+    ///   kept if the later one doesn't carry one.
+    ///
+    /// Dispatches a `ClientEvent::SidebarChanged` and a `ClientRoomEventType::InvitationReceived`
+    /// event for new invitations.
+    async fn handle_received_invitation(
+        &self,
+        room_id: &MucId,
+        sender: &UserResourceId,
+        password: Option<String>,
+        reason: Option<String>,
+    ) -> Result<()>;
+
+    /// Joins the room our user was invited to and saves a bookmark for it, so that it's joined
+    /// automatically from now on.
+    async fn accept_invitation(&self, room_id: &MucId) -> Result<RoomId>;
+
+    /// Removes the sidebar item of the room our user was invited to.
+    ///
+    /// Dispatches a `ClientEvent::SidebarChanged` event after processing.
+    async fn decline_invitation(&self, room_id: &MucId) -> Result<()>;
+
+    /// Destroys the room identified by `room_id`, redirecting its occupants to `alternate_room`
+    /// if set. Cleans up like `handle_destroyed_room` afterwards.
+    async fn destroy_room(
+        &self,
+        room_id: &MucId,
+        alternate_room: Option<MucId>,
+        reason: Option<&str>,
+    ) -> Result<()>;
 
     /// Renames the sidebar item identified by `room_id` to `name`.
     ///
@@ -84,6 +123,10 @@ pub trait SidebarDomainService: SendUnlessWasm + SyncUnlessWasm {
     ///   - `ClientEvent::SidebarChanged` will be dispatched after processing.
     async fn toggle_item_is_favorite(&self, room_id: &RoomId) -> Result<()>;
 
+    /// Sets the manual order of the sidebar items. Items which are not contained in `room_ids`
+    /// lose their position and are sorted by their last activity instead.
+    async fn set_manual_order(&self, room_ids: &[RoomId]) -> Result<()>;
+
     /// Reconfigures the sidebar item identified by `room_id` according to `spec` and renames it
     /// to `new_name`.
     ///
@@ -108,6 +151,17 @@ pub trait SidebarDomainService: SendUnlessWasm + SyncUnlessWasm {
     /// - Dispatches a `ClientEvent::SidebarChanged` event after processing.
     async fn remove_items(&self, room_ids: &[&RoomId]) -> Result<()>;
 
+    /// Handles remote changes of bookmarks, e.g. made on another device.
+    ///
+    /// - Updates the local bookmarks cache.
+    /// - Proceeds like `extend_items_from_bookmarks`, i.e. joins rooms that were added, leaves
+    ///   rooms that were removed from the sidebar and updates the remaining items.
+    async fn handle_changed_items(
+        &self,
+        bookmarks: Vec<Bookmark>,
+        context: DecryptionContext,
+    ) -> Result<()>;
+
     /// Handles remote deletion of bookmarks.
     ///
     /// - Disconnects channels and updates the repository state for each provided JID.
@@ -125,7 +179,8 @@ pub trait SidebarDomainService: SendUnlessWasm + SyncUnlessWasm {
     /// Handles a destroyed room.
     ///
     /// - Removes the connected room.
-    /// - Deletes the corresponding sidebar item.
+    /// - Deletes the corresponding sidebar item and its cached messages.
+    /// - Dispatches a `ClientRoomEventType::Destroyed` event for the room.
     /// - Joins `alternate_room` if set (see `insert_item_by_creating_or_joining_room`).
     /// - Dispatches a `ClientEvent::SidebarChanged` event after processing.
     async fn handle_destroyed_room(
@@ -136,20 +191,22 @@ pub trait SidebarDomainService: SendUnlessWasm + SyncUnlessWasm {
 
     /// Handles removal from a room.
     ///
-    /// If the removal is temporary:
-    /// - Deletes the connected room.
-    /// - Sets an error on the corresponding sidebar item.
+    /// - Puts the connected room into a disconnected state with an error describing the `reason`.
+    /// - Dispatches a `ClientRoomEventType::RemovedFromRoom` event for the room.
     /// - Dispatches a `ClientEvent::SidebarChanged` event after processing.
-    ///
-    /// If the removal is permanent, follows the procedure described in `handle_destroyed_room`.
-    async fn handle_removal_from_room(&self, room_id: &MucId, is_permanent: bool) -> Result<()>;
+    async fn handle_removal_from_room(
+        &self,
+        room_id: &MucId,
+        reason: RoomRemovalReason,
+    ) -> Result<()>;
 
     /// Handles a changed room configuration.
     ///
     /// - Reloads the configuration and adjusts the connected room accordingly.
-    /// - Replaces the connected room if the type of room changed.
+    /// - Replaces the connected room if the type or features of the room changed.
+    /// - Dispatches a `ClientRoomEventType::AttributesChanged` event for the reloaded room.
     /// - Updates the sidebar & associated bookmark to reflect the updated configuration.
-    /// - Dispatches a `ClientEvent::SidebarChanged` event after processing.
+    /// - Dispatches a `ClientEvent::SidebarChanged` event if the sidebar changed.
     async fn handle_changed_room_config(&self, room_id: &MucId) -> Result<()>;
 
     /// Handles a ping timer event by reevaluating the connection of all rooms in the sidebar.
@@ -158,6 +215,13 @@ pub trait SidebarDomainService: SendUnlessWasm + SyncUnlessWasm {
     /// Handles a disconnect event by setting all rooms to disconnected.
     async fn handle_disconnect(&self) -> Result<()>;
 
+    /// Handles a successful automatic reconnect by rejoining all disconnected rooms with their
+    /// previous nicknames and catching up on the messages we've missed.
+    ///
+    /// Does not dispatch any `ClientEvent::SidebarChanged` events, since the caller dispatches
+    /// a single event for the whole recovery.
+    async fn handle_reconnect(&self, context: DecryptionContext) -> Result<()>;
+
     /// Removes all connected rooms and sidebar items.
     ///
     /// Call this method after logging out.
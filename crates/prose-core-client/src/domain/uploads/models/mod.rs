@@ -3,6 +3,12 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+pub use upload_error::UploadError;
+pub use upload_handle::UploadHandle;
 pub use upload_slot::{UploadHeader, UploadSlot};
+pub use upload_source::UploadSource;
 
+mod upload_error;
+mod upload_handle;
 mod upload_slot;
+mod upload_source;
@@ -0,0 +1,33 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+#[derive(thiserror::Error, Debug)]
+pub enum UploadError {
+    /// The file exceeds the maximum file size of the upload service. `max_file_size` is set if
+    /// the server told us about its limit.
+    #[error("The file exceeds the maximum file size of the upload service.")]
+    FileTooLarge { max_file_size: Option<u64> },
+    /// The server refused to hand out an upload slot, because our upload quota is exhausted.
+    #[error("The upload quota has been exceeded.")]
+    QuotaExceeded,
+    #[error("The upload was cancelled.")]
+    Cancelled,
+    #[error("The upload failed with HTTP status {status}.")]
+    UnexpectedStatus { status: u16 },
+    /// The HTTP request failed, e.g. because the connection was interrupted.
+    #[error("The upload failed. {0}")]
+    Transport(String),
+    #[error(transparent)]
+    Anyhow(anyhow::Error),
+}
+
+impl From<anyhow::Error> for UploadError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<UploadError>() {
+            Ok(error) => error,
+            Err(error) => Self::Anyhow(error),
+        }
+    }
+}
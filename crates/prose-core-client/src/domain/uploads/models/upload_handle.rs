@@ -0,0 +1,25 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use futures::future::AbortHandle;
+
+/// Allows to cancel a running upload.
+#[derive(Debug, Clone)]
+pub struct UploadHandle(AbortHandle);
+
+impl UploadHandle {
+    pub(crate) fn new(handle: AbortHandle) -> Self {
+        Self(handle)
+    }
+
+    /// Cancels the upload, which then fails with `UploadError::Cancelled`.
+    pub fn cancel(&self) {
+        self.0.abort()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.is_aborted()
+    }
+}
@@ -0,0 +1,33 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::io;
+use std::path::PathBuf;
+
+/// The contents of a file to upload.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UploadSource {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+impl UploadSource {
+    /// Returns the size of the file in bytes.
+    pub fn size(&self) -> io::Result<u64> {
+        match self {
+            Self::Path(path) => Ok(path.metadata()?.len()),
+            Self::Bytes(bytes) => Ok(bytes.len() as u64),
+        }
+    }
+
+    /// Reads the whole file into memory.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn read(self) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Path(path) => tokio::fs::read(path).await,
+            Self::Bytes(bytes) => Ok(bytes),
+        }
+    }
+}
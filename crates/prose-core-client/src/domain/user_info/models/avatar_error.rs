@@ -0,0 +1,27 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum AvatarError {
+    #[error("The avatar image is too large ({size} bytes). The maximum size is {max_size} bytes.")]
+    TooLarge { size: usize, max_size: usize },
+    #[error("The avatar image is too large ({width}x{height} pixels). The maximum dimension is {max_dimension} pixels.")]
+    DimensionsTooLarge {
+        width: u32,
+        height: u32,
+        max_dimension: u32,
+    },
+    #[error("Avatar images of type '{mime_type}' are not supported.")]
+    UnsupportedMimeType { mime_type: String },
+    #[error("The dimensions of the avatar image could not be determined.")]
+    UnknownDimensions,
+}
+
+impl AvatarError {
+    /// The image types accepted as avatars. PNG must be supported by all clients as per
+    /// XEP-0084, the others are widely supported in practice.
+    pub const SUPPORTED_MIME_TYPES: &'static [&'static str] =
+        &["image/png", "image/jpeg", "image/gif", "image/webp"];
+}
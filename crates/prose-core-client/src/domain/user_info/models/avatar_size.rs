@@ -0,0 +1,28 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use serde::{Deserialize, Serialize};
+
+/// The size variants in which avatars are cached and served.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AvatarSize {
+    /// Downscaled to fit into 32x32 pixels.
+    Small,
+    /// Downscaled to fit into 64x64 pixels.
+    Medium,
+    /// The image as it was saved to the cache.
+    Full,
+}
+
+impl AvatarSize {
+    /// Returns the maximum width and height of the variant or `None` for `AvatarSize::Full`.
+    pub fn max_dimension(&self) -> Option<u32> {
+        match self {
+            Self::Small => Some(32),
+            Self::Medium => Some(64),
+            Self::Full => None,
+        }
+    }
+}
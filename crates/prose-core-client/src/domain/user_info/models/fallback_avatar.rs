@@ -0,0 +1,181 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use sha1::{Digest, Sha1};
+
+use crate::domain::shared::models::{ParticipantId, UserId};
+
+const SATURATION: f64 = 0.6;
+const LIGHTNESS: f64 = 0.45;
+
+/// A placeholder for users without an avatar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FallbackAvatar {
+    /// The background color as `#rrggbb`. The hue is derived from the ID of the user as
+    /// described in XEP-0392 (Consistent Color Generation), so that the same user always gets
+    /// the same color.
+    pub color: String,
+    /// Up to two uppercased initials of the user's name.
+    pub initials: String,
+}
+
+impl FallbackAvatar {
+    /// Uses the formatted username of `user_id` if `name` is `None` or has no usable characters.
+    pub fn for_user(user_id: &UserId, name: Option<&str>) -> Self {
+        Self::new(
+            &user_id.to_string(),
+            name.and_then(initials)
+                .unwrap_or_else(|| initials(&user_id.formatted_username()).unwrap_or_default()),
+        )
+    }
+
+    /// Derives the color from the nickname of occupants whose real ID is unknown.
+    pub fn for_participant(participant_id: &ParticipantId, name: Option<&str>) -> Self {
+        match participant_id {
+            ParticipantId::User(user_id) => Self::for_user(user_id, name),
+            ParticipantId::Occupant(occupant_id) => Self::new(
+                occupant_id.nickname(),
+                name.and_then(initials).unwrap_or_else(|| {
+                    initials(&occupant_id.formatted_nickname()).unwrap_or_default()
+                }),
+            ),
+        }
+    }
+
+    fn new(id: &str, initials: String) -> Self {
+        let (r, g, b) = hsl_to_rgb(hue(id), SATURATION, LIGHTNESS);
+
+        Self {
+            color: format!("#{r:02x}{g:02x}{b:02x}"),
+            initials,
+        }
+    }
+}
+
+/// Returns the hue angle in degrees for `id` (XEP-0392, §5.1).
+fn hue(id: &str) -> f64 {
+    let hash = Sha1::digest(id.as_bytes());
+    u16::from_le_bytes([hash[0], hash[1]]) as f64 / 65536.0 * 360.0
+}
+
+/// Returns the first character of the first and the last word of `name`.
+fn initials(name: &str) -> Option<String> {
+    let mut words = name
+        .split_whitespace()
+        .filter_map(|word| word.chars().find(|c| c.is_alphanumeric()));
+
+    let first = words.next()?;
+    let last = words.last();
+
+    Some(
+        first
+            .to_uppercase()
+            .chain(last.into_iter().flat_map(char::to_uppercase))
+            .collect(),
+    )
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> (u8, u8, u8) {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+
+    let (r, g, b) = match sector as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f64| ((value + m) * 255.0).round() as u8;
+
+    (channel(r), channel(g), channel(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{occupant_id, user_id};
+
+    use super::*;
+
+    #[test]
+    fn test_initials() {
+        assert_eq!(initials("Jane Doe"), Some("JD".to_string()));
+        assert_eq!(initials("Jane Mary Doe"), Some("JD".to_string()));
+        assert_eq!(initials("  jane  "), Some("J".to_string()));
+        assert_eq!(initials("(jane) smith"), Some("JS".to_string()));
+        assert_eq!(initials(" - "), None);
+        assert_eq!(initials(""), None);
+    }
+
+    #[test]
+    fn test_fallback_avatar_for_user() {
+        let avatar = FallbackAvatar::for_user(&user_id!("jane.doe@prose.org"), None);
+        assert_eq!(avatar.initials, "JD");
+        assert_eq!(avatar.color.len(), 7);
+
+        // The color only depends on the ID…
+        let other_avatar = FallbackAvatar::for_user(&user_id!("jane.doe@prose.org"), Some("Jenny"));
+        assert_eq!(other_avatar.initials, "J");
+        assert_eq!(other_avatar.color, avatar.color);
+
+        assert_ne!(
+            FallbackAvatar::for_user(&user_id!("john@prose.org"), None).color,
+            avatar.color
+        );
+    }
+
+    #[test]
+    fn test_fallback_avatar_for_occupant() {
+        let avatar =
+            FallbackAvatar::for_participant(&occupant_id!("room@conf.prose.org/jane").into(), None);
+        assert_eq!(avatar.initials, "J");
+        assert_eq!(
+            avatar.color,
+            FallbackAvatar::new("jane", String::new()).color
+        );
+    }
+
+    #[test]
+    fn test_hue() {
+        // Test vectors from XEP-0392, §A.1
+        assert_eq!(format!("{:.3}", hue("Romeo")), "327.255");
+        assert_eq!(format!("{:.3}", hue("juliet@capulet.lit")), "209.410");
+        assert_eq!(format!("{:.3}", hue("😺")), "331.199");
+        assert_eq!(format!("{:.3}", hue("council")), "359.995");
+    }
+
+    #[test]
+    fn test_fallback_avatar_colors() {
+        assert_eq!(
+            FallbackAvatar::for_user(&user_id!("juliet@capulet.lit"), None).color,
+            "#2e74b8"
+        );
+        assert_eq!(
+            FallbackAvatar::for_user(&user_id!("jane.doe@prose.org"), None).color,
+            "#50b82e"
+        );
+        assert_eq!(
+            FallbackAvatar::for_user(&user_id!("john@prose.org"), None).color,
+            "#a72eb8"
+        );
+        assert_eq!(
+            FallbackAvatar::for_participant(&occupant_id!("room@conf.prose.org/jane").into(), None)
+                .color,
+            "#2e8cb8"
+        );
+    }
+
+    #[test]
+    fn test_hsl_to_rgb() {
+        assert_eq!(hsl_to_rgb(0.0, 1.0, 0.5), (255, 0, 0));
+        assert_eq!(hsl_to_rgb(120.0, 1.0, 0.5), (0, 255, 0));
+        assert_eq!(hsl_to_rgb(240.0, 1.0, 0.5), (0, 0, 255));
+        assert_eq!(hsl_to_rgb(0.0, 0.0, 1.0), (255, 255, 255));
+    }
+}
@@ -4,21 +4,31 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 pub use avatar::{Avatar, AvatarSource};
+pub use avatar_error::AvatarError;
 pub use avatar_metadata::{AvatarInfo, AvatarMetadata};
+pub use avatar_size::AvatarSize;
+pub use fallback_avatar::FallbackAvatar;
 pub use jabber_client::{JabberClient, PROSE_IM_NODE};
 pub use platform_image::PlatformImage;
 pub use presence::Presence;
+pub use prune_stats::PruneStats;
 pub use user_info::{ProfileName, UserInfo, UserInfoOptExt, UserName};
 pub use user_metadata::{LastActivity, UserMetadata};
 pub use user_profile::{Address, Image, UserProfile};
+pub use user_profile_error::UserProfileError;
 pub use user_status::UserStatus;
 
 mod avatar;
+mod avatar_error;
 mod avatar_metadata;
+mod avatar_size;
+mod fallback_avatar;
 mod jabber_client;
 mod platform_image;
 mod presence;
+mod prune_stats;
 mod user_info;
 mod user_metadata;
 mod user_profile;
+mod user_profile_error;
 mod user_status;
@@ -3,6 +3,8 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use chrono::{DateTime, Utc};
+
 use crate::domain::shared::models::{Availability, CapabilitiesId};
 use crate::domain::user_info::models::JabberClient;
 use crate::dtos::Avatar;
@@ -13,6 +15,8 @@ pub struct Presence {
     pub avatar: Option<Avatar>,
     pub caps: Option<CapabilitiesId>,
     pub client: Option<JabberClient>,
+    /// XEP-0319: Last User Interaction in Presence
+    pub idle_since: Option<DateTime<Utc>>,
     pub nickname: Option<String>,
     pub priority: i8,
     pub status: Option<String>,
@@ -31,6 +35,7 @@ mod tests {
                 avatar: None,
                 caps: None,
                 client: None,
+                idle_since: None,
                 nickname: None,
                 priority: 0,
                 status: None,
@@ -0,0 +1,13 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+/// The number of cached items that were deleted because they expired or exceeded the limits of
+/// a `RetentionPolicy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PruneStats {
+    pub user_profiles: usize,
+    pub avatars: usize,
+    pub messages: usize,
+}
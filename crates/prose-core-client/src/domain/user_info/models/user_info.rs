@@ -3,6 +3,7 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::domain::shared::models::{Availability, CapabilitiesId};
@@ -32,7 +33,11 @@ pub struct ProfileName {
 #[derive(Clone, PartialEq, Debug, Default)]
 pub struct UserInfo {
     pub status: Option<UserStatus>,
+    /// The status message of the user's highest-priority presence.
+    pub status_message: Option<String>,
     pub availability: Availability,
+    /// Since when the user's highest-priority presence is idle.
+    pub idle_since: Option<DateTime<Utc>>,
     pub avatar: Option<Avatar>,
     pub caps: Option<CapabilitiesId>,
     pub client: Option<JabberClient>,
@@ -68,6 +73,8 @@ impl UserInfo {
             availability: self.availability,
             avatar: self.avatar,
             status: self.status,
+            status_message: self.status_message,
+            idle_since: self.idle_since,
         }
     }
 }
@@ -152,6 +159,8 @@ impl UserInfoOptExt for Option<UserInfo> {
                 availability: Availability::Unavailable,
                 avatar: None,
                 status: None,
+                status_message: None,
+                idle_since: None,
             };
         };
         info.into_user_presence_info(user_id)
@@ -6,6 +6,8 @@
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use super::UserProfileError;
+
 #[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
 pub struct Address {
     pub locality: Option<String>,
@@ -32,3 +34,37 @@ pub struct UserProfile {
     pub address: Option<Address>,
     pub photo: Option<Image>,
 }
+
+impl UserProfile {
+    /// Checks the fields that have a well-defined format, so that we don't publish a profile
+    /// which other clients can't make sense of.
+    pub fn validate(&self) -> Result<(), UserProfileError> {
+        // Empty values are omitted when publishing the profile.
+        if let Some(email) = self.email.as_deref().map(str::trim) {
+            if !email.is_empty() && !is_valid_email(email) {
+                return Err(UserProfileError::InvalidEmail(email.to_string()));
+            }
+        }
+
+        if let Some(url) = &self.url {
+            if !["http", "https"].contains(&url.scheme()) || url.host_str().is_none() {
+                return Err(UserProfileError::InvalidUrl(url.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_valid_email(email: &str) -> bool {
+    let Some((local_part, domain)) = email.split_once('@') else {
+        return false;
+    };
+
+    !local_part.is_empty()
+        && !domain.is_empty()
+        && !domain.contains('@')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !email.contains(char::is_whitespace)
+}
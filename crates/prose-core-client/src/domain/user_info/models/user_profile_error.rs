@@ -0,0 +1,13 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+/// Identifies the field of a `UserProfile` that failed validation.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum UserProfileError {
+    #[error("'{0}' is not a valid email address.")]
+    InvalidEmail(String),
+    #[error("'{0}' is not a valid URL.")]
+    InvalidUrl(String),
+}
@@ -5,33 +5,48 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 use prose_xmpp::mods::AvatarData;
 
-use crate::domain::shared::models::{AccountId, AvatarId, ParticipantIdRef};
-use crate::domain::user_info::models::{AvatarInfo, PlatformImage};
+use crate::domain::shared::models::{AccountId, AvatarId, ParticipantIdRef, StoreUsage};
+use crate::domain::user_info::models::{AvatarInfo, AvatarSize, PlatformImage};
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[async_trait]
 #[cfg_attr(feature = "test", mockall::automock)]
 pub trait AvatarRepository: SendUnlessWasm + SyncUnlessWasm {
-    /// Returns the cached avatar.
+    /// Returns the cached avatar in the requested size. Downscaled variants are generated and
+    /// cached the first time they're requested.
     async fn get(
         &self,
         account: &AccountId,
         participant_id: ParticipantIdRef<'_>,
         avatar_id: &AvatarId,
+        size: AvatarSize,
     ) -> Result<Option<PlatformImage>>;
 
-    /// Saves the avatar to the local cache.
+    /// Saves the avatar to the local cache, replacing all previously cached variants of the
+    /// participant's avatar.
     async fn set(
         &self,
         account: &AccountId,
         participant_id: ParticipantIdRef<'_>,
         metadata: &AvatarInfo,
         image: &AvatarData,
+        updated_at: DateTime<Utc>,
     ) -> Result<()>;
 
+    /// Deletes all avatars that were cached before `older_than`. Returns the number of deleted
+    /// cache entries.
+    async fn prune(&self, account: &AccountId, older_than: DateTime<Utc>) -> Result<usize>;
+
+    /// Deletes the least recently updated avatars until the cached avatars take up at most
+    /// `max_bytes`. Returns the number of deleted cache entries.
+    async fn evict(&self, account: &AccountId, max_bytes: u64) -> Result<usize>;
+
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage>;
+
     async fn clear_cache(&self, account: &AccountId) -> Result<()>;
 }
@@ -5,10 +5,11 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
-use crate::domain::shared::models::{AccountId, ParticipantIdRef};
+use crate::domain::shared::models::{AccountId, ParticipantIdRef, StoreUsage};
 use crate::domain::user_info::models::UserProfile;
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -25,8 +26,15 @@ pub trait UserProfileRepository: SendUnlessWasm + SyncUnlessWasm {
         account: &AccountId,
         participant_id: ParticipantIdRef<'_>,
         profile: Option<&UserProfile>,
+        updated_at: DateTime<Utc>,
     ) -> Result<()>;
 
+    /// Deletes all profiles that were cached before `older_than`. Returns the number of deleted
+    /// profiles.
+    async fn prune(&self, account: &AccountId, older_than: DateTime<Utc>) -> Result<usize>;
+
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage>;
+
     async fn reset_before_reconnect(&self, account: &AccountId) -> Result<()>;
     async fn clear_cache(&self, account: &AccountId) -> Result<()>;
 }
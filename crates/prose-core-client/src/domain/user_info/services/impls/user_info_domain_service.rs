@@ -8,6 +8,7 @@ use std::collections::HashSet;
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
 use jid::Jid;
 use parking_lot::RwLock;
 use tracing::{error, warn};
@@ -24,13 +25,17 @@ use crate::domain::shared::models::{
     CachePolicy, ConnectionState, ParticipantIdRef, UserId, UserOrResourceId,
 };
 use crate::domain::user_info::models::{
-    Avatar, AvatarInfo, AvatarSource, Image, PlatformImage, Presence, ProfileName, UserInfo,
-    UserMetadata, UserProfile, UserStatus,
+    Avatar, AvatarInfo, AvatarSize, AvatarSource, Image, PlatformImage, Presence, ProfileName,
+    PruneStats, UserInfo, UserMetadata, UserProfile, UserStatus,
 };
 use crate::domain::user_info::services::UserInfoDomainService as UserInfoDomainServiceTrait;
 use crate::dtos::ParticipantId;
 use crate::ClientEvent;
 
+/// Cached profiles and avatars that haven't been updated for this many days are deleted by
+/// `prune_cache`.
+pub const CACHE_MAX_AGE_DAYS: i64 = 30;
+
 #[derive(DependenciesStruct)]
 pub struct UserInfoDomainService {
     avatar_repo: DynAvatarRepository,
@@ -118,7 +123,33 @@ impl UserInfoDomainServiceTrait for UserInfoDomainService {
         }
     }
 
-    async fn load_avatar_image(&self, avatar: &Avatar) -> Result<Option<PlatformImage>> {
+    async fn load_last_activity(&self, user_id: &UserId) -> Result<Option<DateTime<Utc>>> {
+        let account = self.ctx.connected_account()?;
+
+        if self.block_list_repo.contains(&account, user_id).await? {
+            return Ok(None);
+        }
+
+        if let Some(idle_since) = self
+            .user_info_repo
+            .get(&account, user_id)
+            .await?
+            .and_then(|info| info.idle_since)
+        {
+            return Ok(Some(idle_since));
+        }
+
+        Ok(self
+            .user_info_service
+            .load_last_activity(user_id, self.time_provider.now())
+            .await?)
+    }
+
+    async fn load_avatar_image(
+        &self,
+        avatar: &Avatar,
+        size: AvatarSize,
+    ) -> Result<Option<PlatformImage>> {
         let account = self.ctx.connected_account()?;
 
         // If we have a real id for the requested avatar, let's use that one. This fixes at least
@@ -130,7 +161,7 @@ impl UserInfoDomainServiceTrait for UserInfoDomainService {
                 .await
             {
                 if let Some(avatar) = info.avatar {
-                    if let Ok(Some(image)) = self.load_avatar_image(&avatar).await {
+                    if let Ok(Some(image)) = self.load_avatar_image(&avatar, size).await {
                         return Ok(Some(image));
                     }
                 }
@@ -139,7 +170,7 @@ impl UserInfoDomainServiceTrait for UserInfoDomainService {
 
         if let Some(image) = self
             .avatar_repo
-            .get(&account, avatar.owner(), &avatar.id)
+            .get(&account, avatar.owner(), &avatar.id, size)
             .await?
         {
             return Ok(Some(image));
@@ -224,11 +255,12 @@ impl UserInfoDomainServiceTrait for UserInfoDomainService {
                     mime_type: mime_type.clone(),
                 },
                 &data,
+                self.time_provider.now(),
             )
             .await?;
 
         self.avatar_repo
-            .get(&account, avatar.owner(), &avatar.id)
+            .get(&account, avatar.owner(), &avatar.id, size)
             .await
     }
 
@@ -248,6 +280,7 @@ impl UserInfoDomainServiceTrait for UserInfoDomainService {
 
         self.update_user_info(user_id, move |info| {
             info.availability = presence.availability;
+            info.status_message = presence.status;
             info.caps = presence.caps;
             info.client = presence.client;
             info.name.presence = presence.nickname;
@@ -289,7 +322,12 @@ impl UserInfoDomainServiceTrait for UserInfoDomainService {
         let account = self.ctx.connected_account()?;
 
         self.user_profile_repo
-            .set(&account, user_id.into(), profile.as_ref())
+            .set(
+                &account,
+                user_id.into(),
+                profile.as_ref(),
+                self.time_provider.now(),
+            )
             .await?;
 
         self.update_user_info(user_id, |info| {
@@ -321,6 +359,17 @@ impl UserInfoDomainServiceTrait for UserInfoDomainService {
         Ok(())
     }
 
+    async fn prune_cache(&self) -> Result<PruneStats> {
+        let account = self.ctx.connected_account()?;
+        let older_than = self.time_provider.now() - TimeDelta::days(CACHE_MAX_AGE_DAYS);
+
+        Ok(PruneStats {
+            user_profiles: self.user_profile_repo.prune(&account, older_than).await?,
+            avatars: self.avatar_repo.prune(&account, older_than).await?,
+            messages: 0,
+        })
+    }
+
     async fn reset_before_reconnect(&self) -> Result<()> {
         self.requested_vcards.write().clear();
         self.requested_avatars.write().clear();
@@ -431,7 +480,12 @@ impl UserInfoDomainService {
                 .await?;
         } else {
             self.user_profile_repo
-                .set(&account, participant_id, user_profile.as_ref())
+                .set(
+                    &account,
+                    participant_id,
+                    user_profile.as_ref(),
+                    self.time_provider.now(),
+                )
                 .await?;
         }
 
@@ -5,13 +5,15 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use prose_wasm_utils::{SendUnlessWasm, SyncUnlessWasm};
 
 use crate::domain::contacts::models::Contact;
 use crate::domain::shared::models::{CachePolicy, UserId, UserOrResourceId};
 use crate::domain::user_info::models::{
-    Avatar, PlatformImage, Presence, UserInfo, UserMetadata, UserProfile, UserStatus,
+    Avatar, AvatarSize, PlatformImage, Presence, PruneStats, UserInfo, UserMetadata, UserProfile,
+    UserStatus,
 };
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -32,7 +34,17 @@ pub trait UserInfoDomainService: SendUnlessWasm + SyncUnlessWasm {
 
     async fn get_user_metadata(&self, user_id: &UserId) -> Result<Option<UserMetadata>>;
 
-    async fn load_avatar_image(&self, avatar: &Avatar) -> Result<Option<PlatformImage>>;
+    /// Returns since when `user_id` is idle according to their presence (XEP-0319) or, if their
+    /// presence doesn't say, asks their server when they were last online (XEP-0012).
+    async fn load_last_activity(&self, user_id: &UserId) -> Result<Option<DateTime<Utc>>>;
+
+    /// Returns the image of `avatar` in the requested `size`. The image is loaded and cached if
+    /// needed.
+    async fn load_avatar_image(
+        &self,
+        avatar: &Avatar,
+        size: AvatarSize,
+    ) -> Result<Option<PlatformImage>>;
 
     async fn handle_user_presence_changed(
         &self,
@@ -62,6 +74,9 @@ pub trait UserInfoDomainService: SendUnlessWasm + SyncUnlessWasm {
 
     async fn handle_contacts_changed(&self, contacts: Vec<Contact>) -> Result<()>;
 
+    /// Deletes cached profiles and avatars that haven't been updated within `CACHE_MAX_AGE_DAYS`.
+    async fn prune_cache(&self) -> Result<PruneStats>;
+
     async fn reset_before_reconnect(&self) -> Result<()>;
     async fn clear_cache(&self) -> Result<()>;
 }
@@ -34,4 +34,12 @@ pub trait UserInfoService: SendUnlessWasm + SyncUnlessWasm {
         from: &UserResourceId,
         now: DateTime<Utc>,
     ) -> Result<Option<UserMetadata>, RequestError>;
+
+    /// Asks the server of `from` when they were last online (XEP-0012). Returns `None` if the
+    /// server refuses to tell.
+    async fn load_last_activity(
+        &self,
+        from: &UserId,
+        now: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, RequestError>;
 }
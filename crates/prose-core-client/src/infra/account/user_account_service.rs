@@ -5,7 +5,9 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use jid::Jid;
+use tracing::warn;
 
 use prose_xmpp::mods;
 
@@ -42,19 +44,29 @@ impl UserAccountService for XMPPClient {
         Ok(())
     }
 
+    async fn delete_avatar_metadata(&self) -> Result<()> {
+        let profile = self.client.get_mod::<mods::Profile>();
+        profile.delete_avatar_metadata().await?;
+        Ok(())
+    }
+
     async fn set_availability(
         &self,
         room_id: Option<OccupantId>,
         capabilities: &Capabilities,
         availability: Availability,
+        status_message: Option<String>,
+        priority: i8,
+        idle_since: Option<DateTime<Utc>>,
     ) -> Result<()> {
         let status_mod = self.client.get_mod::<mods::Status>();
         status_mod.send_presence(
             room_id.map(|id| Jid::from(id.into_inner())),
             Some(availability.try_into()?),
-            None,
+            status_message.as_deref(),
             Some(capabilities.into()),
-            None,
+            Some(priority),
+            idle_since,
         )
     }
 
@@ -88,7 +100,14 @@ impl UserAccountService for XMPPClient {
 
         match format {
             UserProfileFormat::Vcard4 => {
-                profile.publish_vcard4(user_profile.into(), None).await?;
+                profile
+                    .publish_vcard4(user_profile.clone().into(), None)
+                    .await?;
+
+                // Keep the legacy vCard in sync for clients that don't support vCard4 yet.
+                if let Err(error) = profile.publish_vcard_temp(user_profile.into()).await {
+                    warn!("Failed to publish vCard-temp. {}", error.to_string());
+                }
             }
             UserProfileFormat::VcardTemp => {
                 profile.publish_vcard_temp(user_profile.into()).await?;
@@ -104,6 +123,7 @@ impl UserAccountService for XMPPClient {
         let profile = self.client.get_mod::<mods::Profile>();
         profile.unpublish_vcard().await?;
         profile.delete_vcard().await?;
+        profile.publish_nickname(None).await?;
         Ok(())
     }
 }
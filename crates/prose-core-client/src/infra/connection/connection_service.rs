@@ -3,16 +3,20 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::HashSet;
+
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use chrono::Utc;
+use minidom::Element;
 use secrecy::SecretString;
 use tracing::{info, warn};
 
-use prose_xmpp::{mods, ns, ConnectionError};
+use prose_xmpp::{mods, ns, ConnectionError, SessionKind};
 
 use crate::domain::connection::models::{HttpUploadService, ServerFeatures};
 use crate::domain::connection::services::ConnectionService;
+use crate::domain::messaging::models::MessageId;
 use crate::domain::shared::models::MamVersion;
 use crate::dtos::UserResourceId;
 use crate::infra::xmpp::XMPPClient;
@@ -24,7 +28,7 @@ impl ConnectionService for XMPPClient {
         &self,
         jid: &UserResourceId,
         password: SecretString,
-    ) -> Result<(), ConnectionError> {
+    ) -> Result<SessionKind, ConnectionError> {
         self.client.connect(jid.as_ref(), password).await
     }
 
@@ -32,6 +36,37 @@ impl ConnectionService for XMPPClient {
         self.client.disconnect()
     }
 
+    async fn resend_unacknowledged_messages(&self, skipped_ids: &HashSet<MessageId>) -> Result<()> {
+        let messages = self
+            .client
+            .take_unacknowledged_stanzas()
+            .into_iter()
+            .filter(is_resendable_message)
+            .filter(|stanza| {
+                stanza
+                    .attr("id")
+                    .map_or(true, |id| !skipped_ids.contains(id))
+            })
+            .collect::<Vec<_>>();
+
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        info!("Resending {} unacknowledged messages…", messages.len());
+
+        for message in messages {
+            self.client.send_raw_stanza(message)?;
+        }
+
+        Ok(())
+    }
+
+    fn unacked_message_count(&self) -> usize {
+        self.client
+            .count_unacknowledged_stanzas(is_resendable_message)
+    }
+
     async fn set_message_carbons_enabled(&self, is_enabled: bool) -> Result<()> {
         let chat = self.client.get_mod::<mods::Chat>();
         chat.set_message_carbons_enabled(is_enabled)?;
@@ -162,3 +197,10 @@ impl ConnectionService for XMPPClient {
         Ok(server_features)
     }
 }
+
+/// Chat state notifications are outdated by the time we'd resend them, everything else
+/// (messages, reactions, corrections, retractions…) is worth resending.
+fn is_resendable_message(stanza: &Element) -> bool {
+    stanza.is("message", ns::JABBER_CLIENT)
+        && stanza.children().any(|child| !child.has_ns(ns::CHATSTATES))
+}
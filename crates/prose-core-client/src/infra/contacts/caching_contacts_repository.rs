@@ -3,25 +3,74 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::HashMap;
+
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use prose_store::prelude::{Entity, PlatformDriver, Store};
+use prose_store::{
+    define_entity, Database, IndexSpec, IndexedCollection, Query, QueryDirection, ReadTransaction,
+    ReadableCollection, WritableCollection, WriteTransaction,
+};
 
 use crate::app::deps::DynContactListService;
-use crate::domain::contacts::models::{Contact, PresenceSubscription};
+use crate::domain::contacts::models::Contact;
 use crate::domain::contacts::repos::ContactListRepository;
 use crate::domain::shared::models::{AccountId, UserId};
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContactRecord {
+    id: String,
+    account: AccountId,
+    payload: Contact,
+    /// When the contact was last seen, which is not part of the roster.
+    last_seen: Option<DateTime<Utc>>,
+}
+
+impl ContactRecord {
+    fn new(account: &AccountId, contact: &Contact, last_seen: Option<DateTime<Utc>>) -> Self {
+        Self {
+            id: Self::id(account, &contact.id),
+            account: account.clone(),
+            payload: contact.clone(),
+            last_seen,
+        }
+    }
+
+    fn id(account: &AccountId, contact_id: &UserId) -> String {
+        format!("{}-{}", account, contact_id)
+    }
+}
+
+mod columns {
+    pub const ACCOUNT: &str = "account";
+}
+
+define_entity!(ContactRecord, "contacts",
+    account_idx => { columns: [columns::ACCOUNT], unique: false }
+);
+
+/// Keeps the roster in memory and mirrors it to the store, so that the last known contacts can
+/// be served while we're offline.
 pub struct CachingContactsRepository {
     service: DynContactListService,
+    store: Store<PlatformDriver>,
     contacts: RwLock<Option<Vec<Contact>>>,
+    last_seen: RwLock<HashMap<UserId, DateTime<Utc>>>,
 }
 
 impl CachingContactsRepository {
-    pub fn new(service: DynContactListService) -> Self {
+    pub fn new(service: DynContactListService, store: Store<PlatformDriver>) -> Self {
         Self {
             service,
+            store,
             contacts: Default::default(),
+            last_seen: Default::default(),
         }
     }
 }
@@ -29,72 +78,214 @@ impl CachingContactsRepository {
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[async_trait]
 impl ContactListRepository for CachingContactsRepository {
-    async fn get_all(&self, _account: &AccountId) -> Result<Vec<Contact>> {
-        self.load_contacts_if_needed().await?;
+    async fn get_all(&self, account: &AccountId) -> Result<Vec<Contact>> {
+        self.load_contacts_if_needed(account).await?;
         Ok(self.contacts.read().clone().unwrap_or_else(|| vec![]))
     }
 
-    async fn set(
-        &self,
-        _account: &AccountId,
-        contact_id: &UserId,
-        subscription: PresenceSubscription,
-    ) -> Result<bool> {
-        self.load_contacts_if_needed().await?;
+    async fn get(&self, account: &AccountId, contact_id: &UserId) -> Result<Option<Contact>> {
+        self.load_contacts_if_needed(account).await?;
+
+        Ok(self
+            .contacts
+            .read()
+            .as_ref()
+            .and_then(|contacts| contacts.iter().find(|c| &c.id == contact_id).cloned()))
+    }
 
-        let mut guard = self.contacts.write();
-        let contacts = guard.get_or_insert_with(Default::default);
+    async fn set(&self, account: &AccountId, contact: Contact) -> Result<bool> {
+        self.load_contacts_if_needed(account).await?;
 
-        if let Some(contact) = contacts.iter_mut().find(|c| &c.id == contact_id) {
-            if contact.presence_subscription == subscription {
-                return Ok(false);
+        {
+            let mut guard = self.contacts.write();
+            let contacts = guard.get_or_insert_with(Default::default);
+
+            if let Some(existing_contact) = contacts.iter_mut().find(|c| c.id == contact.id) {
+                if existing_contact == &contact {
+                    return Ok(false);
+                }
+                *existing_contact = contact.clone();
+            } else {
+                contacts.push(contact.clone());
             }
-            contact.presence_subscription = subscription;
-            return Ok(true);
-        };
+        }
 
-        contacts.push(Contact {
-            id: contact_id.clone(),
-            name: Some(contact_id.formatted_username()),
-            presence_subscription: subscription,
-        });
+        let last_seen = self.last_seen.read().get(&contact.id).copied();
+        self.put_record(&ContactRecord::new(account, &contact, last_seen))
+            .await?;
 
         Ok(true)
     }
 
-    async fn delete(&self, _account: &AccountId, contact_id: &UserId) -> Result<bool> {
-        self.load_contacts_if_needed().await?;
+    async fn delete(&self, account: &AccountId, contact_id: &UserId) -> Result<bool> {
+        self.load_contacts_if_needed(account).await?;
 
-        let mut guard = self.contacts.write();
-        let contacts = guard.get_or_insert_with(Default::default);
+        {
+            let mut guard = self.contacts.write();
+            let contacts = guard.get_or_insert_with(Default::default);
 
-        let Some(idx) = contacts.iter().position(|c| &c.id == contact_id) else {
-            return Ok(false);
-        };
+            let Some(idx) = contacts.iter().position(|c| &c.id == contact_id) else {
+                return Ok(false);
+            };
+
+            contacts.swap_remove(idx);
+        }
+        self.last_seen.write().remove(contact_id);
 
-        contacts.swap_remove(idx);
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[ContactRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(ContactRecord::collection())?;
+        collection
+            .delete(&ContactRecord::id(account, contact_id))
+            .await?;
+        tx.commit().await?;
 
         Ok(true)
     }
 
-    async fn reset_before_reconnect(&self, account: &AccountId) -> Result<()> {
-        self.clear_cache(account).await
+    async fn get_last_seen(
+        &self,
+        account: &AccountId,
+        contact_id: &UserId,
+    ) -> Result<Option<DateTime<Utc>>> {
+        self.load_contacts_if_needed(account).await?;
+        Ok(self.last_seen.read().get(contact_id).copied())
     }
 
-    async fn clear_cache(&self, _account: &AccountId) -> Result<()> {
+    async fn set_last_seen(
+        &self,
+        account: &AccountId,
+        contact_id: &UserId,
+        last_seen: DateTime<Utc>,
+    ) -> Result<()> {
+        self.load_contacts_if_needed(account).await?;
+
+        let Some(contact) = self
+            .contacts
+            .read()
+            .as_ref()
+            .and_then(|contacts| contacts.iter().find(|c| &c.id == contact_id).cloned())
+        else {
+            return Ok(());
+        };
+
+        self.last_seen.write().insert(contact_id.clone(), last_seen);
+        self.put_record(&ContactRecord::new(account, &contact, Some(last_seen)))
+            .await
+    }
+
+    async fn reset_before_reconnect(&self, _account: &AccountId) -> Result<()> {
+        // Keep the stored contacts around, they'll be replaced once the roster is loaded again.
         self.contacts.write().take();
         Ok(())
     }
+
+    async fn clear_cache(&self, account: &AccountId) -> Result<()> {
+        self.contacts.write().take();
+        self.last_seen.write().clear();
+
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[ContactRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(ContactRecord::collection())?;
+        collection
+            .delete_all_in_index(&ContactRecord::account_idx(), Query::Only(account))
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
 }
 
 impl CachingContactsRepository {
-    async fn load_contacts_if_needed(&self) -> Result<()> {
+    async fn load_contacts_if_needed(&self, account: &AccountId) -> Result<()> {
         if self.contacts.read().is_some() {
             return Ok(());
         }
 
-        let contacts = self.service.load_contacts().await?;
+        let stored_records = self.load_stored_records(account).await?;
+        let last_seen = stored_records
+            .iter()
+            .filter_map(|record| {
+                record
+                    .last_seen
+                    .map(|last_seen| (record.payload.id.clone(), last_seen))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let contacts = match self.service.load_contacts().await {
+            Ok(contacts) => {
+                self.replace_stored_contacts(account, &contacts, &last_seen)
+                    .await?;
+                contacts
+            }
+            Err(error) => {
+                if stored_records.is_empty() {
+                    return Err(error);
+                }
+                // The in-memory contacts are reset before we reconnect, so the roster is loaded
+                // from the server again once we're back online.
+                warn!("Could not load contacts. Serving cached contacts instead. {error}");
+                stored_records
+                    .into_iter()
+                    .map(|record| record.payload)
+                    .collect()
+            }
+        };
+
+        *self.last_seen.write() = last_seen;
         self.contacts.write().replace(contacts);
         Ok(())
     }
+
+    async fn load_stored_records(&self, account: &AccountId) -> Result<Vec<ContactRecord>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[ContactRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(ContactRecord::collection())?;
+        let idx = collection.index(&ContactRecord::account_idx())?;
+        Ok(idx
+            .get_all_values::<ContactRecord>(Query::Only(account), QueryDirection::Forward, None)
+            .await?)
+    }
+
+    async fn replace_stored_contacts(
+        &self,
+        account: &AccountId,
+        contacts: &[Contact],
+        last_seen: &HashMap<UserId, DateTime<Utc>>,
+    ) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[ContactRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(ContactRecord::collection())?;
+        collection
+            .delete_all_in_index(&ContactRecord::account_idx(), Query::Only(account))
+            .await?;
+        for contact in contacts {
+            collection.put_entity(&ContactRecord::new(
+                account,
+                contact,
+                last_seen.get(&contact.id).copied(),
+            ))?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn put_record(&self, record: &ContactRecord) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[ContactRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(ContactRecord::collection())?;
+        collection.put_entity(record)?;
+        tx.commit().await?;
+        Ok(())
+    }
 }
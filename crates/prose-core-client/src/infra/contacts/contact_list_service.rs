@@ -29,9 +29,16 @@ impl ContactListService for XMPPClient {
         Ok(contacts)
     }
 
-    async fn add_contact(&self, user_id: &UserId) -> Result<()> {
+    async fn add_or_update_contact(
+        &self,
+        user_id: &UserId,
+        name: Option<&str>,
+        groups: &[String],
+    ) -> Result<()> {
         let roster_mod = self.client.get_mod::<mods::Roster>();
-        roster_mod.add_contact(user_id.as_ref(), None, None).await?;
+        roster_mod
+            .add_contact(user_id.as_ref(), name, groups)
+            .await?;
         Ok(())
     }
 
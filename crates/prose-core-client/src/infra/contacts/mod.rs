@@ -4,7 +4,7 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 pub use caching_block_list_repository::CachingBlockListRepository;
-pub use caching_contacts_repository::CachingContactsRepository;
+pub use caching_contacts_repository::{CachingContactsRepository, ContactRecord};
 pub use presence_sub_requests_repository::PresenceSubRequestsRepository;
 
 mod block_list_service;
@@ -86,6 +86,10 @@ impl UserDeviceRepositoryTrait for CachingUserDeviceRepository {
         Ok(device_list.devices)
     }
 
+    async fn get_cached(&self, account: &AccountId, user_id: &UserId) -> Result<Vec<Device>> {
+        self.fetch_devices(account, user_id).await
+    }
+
     async fn set_all(
         &self,
         account: &AccountId,
@@ -12,7 +12,7 @@ use prose_store::prelude::*;
 
 use crate::domain::encryption::models::{Session, Trust};
 use crate::domain::encryption::repos::SessionRepository as SessionRepositoryTrait;
-use crate::domain::shared::models::AccountId;
+use crate::domain::shared::models::{AccountId, StoreUsage};
 use crate::dtos::{DeviceId, IdentityKey, SessionData, UserId};
 use crate::infra::encryption::encryption_key_records::SessionRecord;
 
@@ -102,6 +102,20 @@ impl SessionRepositoryTrait for SessionRepository {
         .await
     }
 
+    async fn put_trust(
+        &self,
+        account: &AccountId,
+        user_id: &UserId,
+        device_id: &DeviceId,
+        trust: Trust,
+    ) -> Result<()> {
+        self.upsert_session(account, user_id, device_id, move |session| {
+            session.trust = trust
+        })
+        .await?;
+        Ok(())
+    }
+
     async fn put_active_devices(
         &self,
         account: &AccountId,
@@ -138,6 +152,40 @@ impl SessionRepositoryTrait for SessionRepository {
         Ok(())
     }
 
+    async fn delete_session(
+        &self,
+        account: &AccountId,
+        user_id: &UserId,
+        device_id: &DeviceId,
+    ) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[SessionRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(SessionRecord::collection())?;
+        collection
+            .delete_all_in_index(
+                &SessionRecord::device_idx(),
+                Query::Only((account, user_id, device_id)),
+            )
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[SessionRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(SessionRecord::collection())?;
+        let usage = collection
+            .index(&SessionRecord::account_idx())?
+            .usage(Query::Only(account))
+            .await?;
+        Ok(usage.into())
+    }
+
     async fn clear_cache(&self, account: &AccountId) -> Result<()> {
         let tx = self
             .store
@@ -14,15 +14,21 @@ use prose_wasm_utils::{spawn, ProseStreamExt, ReceiverStream};
 
 use crate::app::deps::DynRoomFactory;
 use crate::app::event_handlers::ClientEventDispatcherTrait;
+use crate::app::services::{
+    ClientEventStream, ClientEventSubscriptions, RoomSubscriptionHandle, RoomSubscriptions,
+    RoomUpdateHandler,
+};
 use crate::client::ClientInner;
 use crate::domain::rooms::models::Room;
-use crate::domain::shared::models::RoomType;
+use crate::domain::shared::models::{RoomId, RoomType};
 use crate::util::coalesce_client_events;
 use crate::{Client, ClientDelegate, ClientEvent, ClientRoomEventType, ConnectionEvent};
 
 pub struct CoalescingClientEventDispatcher {
     client_inner: Arc<OnceLock<Weak<ClientInner>>>,
     room_factory: OnceLock<DynRoomFactory>,
+    room_subscriptions: RoomSubscriptions,
+    event_subscriptions: ClientEventSubscriptions,
     sender: Sender<ClientEvent>,
     delegate: Option<Arc<Box<dyn ClientDelegate>>>,
 }
@@ -62,6 +68,8 @@ impl CoalescingClientEventDispatcher {
         Self {
             client_inner,
             room_factory: Default::default(),
+            room_subscriptions: Default::default(),
+            event_subscriptions: Default::default(),
             sender: tx,
             delegate,
         }
@@ -84,6 +92,8 @@ impl CoalescingClientEventDispatcher {
 
 impl ClientEventDispatcherTrait for CoalescingClientEventDispatcher {
     fn dispatch_event(&self, event: ClientEvent) {
+        self.event_subscriptions.dispatch(&event);
+
         if let ClientEvent::ConnectionStatusChanged {
             event: ConnectionEvent::Disconnect { .. },
         } = event
@@ -120,11 +130,27 @@ impl ClientEventDispatcherTrait for CoalescingClientEventDispatcher {
             .get()
             .expect("RoomFactory was not set on ClientEventDispatcher");
 
+        self.room_subscriptions.dispatch(&room.room_id, &event);
+
         debug!(room_id = %room.room_id, event = ?event, "Enqueuing room event");
 
-        _ = self.sender.try_send(ClientEvent::RoomChanged {
+        let event = ClientEvent::RoomChanged {
             room: room_factory.build(room),
             r#type: event,
-        })
+        };
+        self.event_subscriptions.dispatch(&event);
+        _ = self.sender.try_send(event)
+    }
+
+    fn subscribe_to_room(
+        &self,
+        room_id: RoomId,
+        handler: RoomUpdateHandler,
+    ) -> RoomSubscriptionHandle {
+        self.room_subscriptions.subscribe(room_id, handler)
+    }
+
+    fn subscribe_to_events(&self) -> ClientEventStream {
+        self.event_subscriptions.subscribe()
     }
 }
@@ -7,13 +7,20 @@ use std::sync::{Arc, OnceLock, Weak};
 
 use crate::app::deps::DynRoomFactory;
 use crate::app::event_handlers::ClientEventDispatcherTrait;
+use crate::app::services::{
+    ClientEventStream, ClientEventSubscriptions, RoomSubscriptionHandle, RoomSubscriptions,
+    RoomUpdateHandler,
+};
 use crate::client::ClientInner;
 use crate::domain::rooms::models::Room;
+use crate::domain::shared::models::RoomId;
 use crate::{Client, ClientDelegate, ClientEvent, ClientRoomEventType};
 
 pub struct ImmediateClientEventDispatcher {
     client_inner: Arc<OnceLock<Weak<ClientInner>>>,
     room_factory: OnceLock<DynRoomFactory>,
+    room_subscriptions: RoomSubscriptions,
+    event_subscriptions: ClientEventSubscriptions,
     delegate: Option<Box<dyn ClientDelegate>>,
 }
 
@@ -22,6 +29,8 @@ impl ImmediateClientEventDispatcher {
         Self {
             client_inner: Arc::new(Default::default()),
             room_factory: Default::default(),
+            room_subscriptions: Default::default(),
+            event_subscriptions: Default::default(),
             delegate,
         }
     }
@@ -52,15 +61,31 @@ impl ClientEventDispatcherTrait for ImmediateClientEventDispatcher {
             .get()
             .expect("RoomFactory was not set on ClientEventDispatcher");
 
+        self.room_subscriptions.dispatch(&room.room_id, &event);
+
         self.perform_dispatch_event(ClientEvent::RoomChanged {
             room: room_factory.build(room),
             r#type: event,
         });
     }
+
+    fn subscribe_to_room(
+        &self,
+        room_id: RoomId,
+        handler: RoomUpdateHandler,
+    ) -> RoomSubscriptionHandle {
+        self.room_subscriptions.subscribe(room_id, handler)
+    }
+
+    fn subscribe_to_events(&self) -> ClientEventStream {
+        self.event_subscriptions.subscribe()
+    }
 }
 
 impl ImmediateClientEventDispatcher {
     fn perform_dispatch_event(&self, event: ClientEvent) {
+        self.event_subscriptions.dispatch(&event);
+
         let Some(delegate) = &self.delegate else {
             return;
         };
@@ -88,6 +88,18 @@ impl RequestHandlingService for XMPPClient {
             .await?;
         Ok(())
     }
+
+    async fn decline_last_activity_request(
+        &self,
+        to: &SenderId,
+        id: &RequestId,
+    ) -> anyhow::Result<()> {
+        let profile = self.client.get_mod::<mods::Profile>();
+        profile
+            .send_last_activity_forbidden_response(to.clone().into_inner(), id.as_ref())
+            .await?;
+        Ok(())
+    }
 }
 
 impl Display for Feature {
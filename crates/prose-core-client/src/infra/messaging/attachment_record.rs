@@ -0,0 +1,65 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use prose_store::prelude::*;
+
+use crate::domain::messaging::models::{Attachment, AttachmentKind, MessageId};
+use crate::domain::shared::models::{AccountId, RoomId};
+
+/// An attachment of a cached message. Attachments are kept separately from their messages so
+/// that the media shared in a room can be listed without loading all of its messages.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttachmentRecord {
+    pub id: String,
+    pub account: AccountId,
+    pub room_id: RoomId,
+    pub message_id: MessageId,
+    pub timestamp: DateTime<Utc>,
+    pub kind: AttachmentKind,
+    pub attachment: Attachment,
+}
+
+mod columns {
+    pub const ACCOUNT: &str = "account";
+    pub const ROOM_ID: &str = "room_id";
+    pub const MESSAGE_ID: &str = "message_id";
+    pub const TIMESTAMP: &str = "timestamp";
+}
+
+define_entity!(AttachmentRecord, "attachments",
+    account_idx => { columns: [columns::ACCOUNT], unique: false },
+    room_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID], unique: false },
+    message_id_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID, columns::MESSAGE_ID], unique: false },
+    timestamp_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID, columns::TIMESTAMP], unique: false }
+);
+
+impl AttachmentRecord {
+    pub fn new(
+        account: AccountId,
+        room_id: RoomId,
+        message_id: MessageId,
+        timestamp: DateTime<Utc>,
+        index: usize,
+        attachment: Attachment,
+    ) -> Self {
+        let id = format!(
+            "{account}-{}-{message_id}-{index}",
+            room_id.to_raw_key_string()
+        );
+
+        Self {
+            id,
+            account,
+            room_id,
+            message_id,
+            timestamp,
+            kind: attachment.r#type.kind(),
+            attachment,
+        }
+    }
+}
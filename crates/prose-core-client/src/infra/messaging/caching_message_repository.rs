@@ -3,31 +3,75 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use std::collections::Bound;
+use std::collections::{Bound, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use tracing::warn;
 
 use prose_store::prelude::*;
 
 use crate::domain::messaging::models::{
-    ArchivedMessageRef, MessageId, MessageIdTriple, MessageLike, MessageRemoteId, MessageServerId,
-    MessageTargetId,
+    ArchivedMessageRef, Attachment, AttachmentKind, MessageId, MessageIdTriple, MessageLike,
+    MessageLikePayload, MessageRemoteId, MessageServerId, MessageTargetId, ThreadId,
 };
 use crate::domain::messaging::repos::MessagesRepository;
-use crate::domain::shared::models::{AccountId, RoomId};
-use crate::infra::messaging::MessageRecord;
+use crate::domain::shared::models::{AccountId, RoomId, StoreUsage};
+use crate::infra::messaging::{AttachmentRecord, MessageRecord, StoredPayload};
 
 // TODO: Incorporate MessageArchiveService, cache complete pages loaded from the server
 
+/// The number of messages that need to be deleted by `delete_messages_before` before the
+/// database is vacuumed.
+const VACUUM_THRESHOLD: usize = 5_000;
+
 pub struct CachingMessageRepository {
     store: Store<PlatformDriver>,
+    skip_unreadable_messages: bool,
+    deleted_since_vacuum: AtomicUsize,
 }
 
 impl CachingMessageRepository {
     pub fn new(store: Store<PlatformDriver>) -> Self {
-        Self { store }
+        Self {
+            store,
+            skip_unreadable_messages: true,
+            deleted_since_vacuum: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sets whether cached messages that cannot be read (e.g. because they contain a payload
+    /// that was added in a later version) should be skipped or fail the whole query.
+    pub fn set_skip_unreadable_messages(&mut self, skip: bool) {
+        self.skip_unreadable_messages = skip
+    }
+}
+
+impl CachingMessageRepository {
+    fn parse_record(&self, record: MessageRecord) -> Result<Option<MessageLike>> {
+        match MessageLike::try_from(record) {
+            Ok(message) => Ok(Some(message)),
+            Err(err) if self.skip_unreadable_messages => {
+                warn!("Skipping unreadable cached message. {}", err);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn parse_records(
+        &self,
+        records: impl IntoIterator<Item = MessageRecord>,
+    ) -> Result<Vec<MessageLike>> {
+        let mut messages = vec![];
+        for record in records {
+            if let Some(message) = self.parse_record(record)? {
+                messages.push(message);
+            }
+        }
+        Ok(messages)
     }
 }
 
@@ -68,34 +112,34 @@ impl MessagesRepository for CachingMessageRepository {
 
             if let Some(remote_id) = message.as_ref().and_then(|m| m.remote_id.as_ref()) {
                 messages.extend(
-                    &mut message_id_target_idx
-                        .get_all_values::<MessageRecord>(
-                            Query::Only((account, room_id, remote_id)),
-                            Default::default(),
-                            None,
-                        )
-                        .await?
-                        .into_iter()
-                        .map(MessageLike::from),
+                    self.parse_records(
+                        message_id_target_idx
+                            .get_all_values::<MessageRecord>(
+                                Query::Only((account, room_id, remote_id)),
+                                Default::default(),
+                                None,
+                            )
+                            .await?,
+                    )?,
                 );
             }
 
             if let Some(stanza_id) = message.as_ref().and_then(|m| m.server_id.as_ref()) {
                 messages.extend(
-                    &mut stanza_id_target_idx
-                        .get_all_values::<MessageRecord>(
-                            Query::Only((account, room_id, stanza_id)),
-                            Default::default(),
-                            None,
-                        )
-                        .await?
-                        .into_iter()
-                        .map(MessageLike::from),
+                    self.parse_records(
+                        stanza_id_target_idx
+                            .get_all_values::<MessageRecord>(
+                                Query::Only((account, room_id, stanza_id)),
+                                Default::default(),
+                                None,
+                            )
+                            .await?,
+                    )?,
                 );
             }
 
             if let Some(message) = message {
-                messages.push(message.into());
+                messages.extend(self.parse_record(message)?);
             }
         }
 
@@ -143,10 +187,11 @@ impl MessagesRepository for CachingMessageRepository {
             };
 
             messages.extend(
-                &mut targeting_messages
-                    .into_iter()
-                    .filter(|msg| &msg.timestamp > newer_than)
-                    .map(MessageLike::from),
+                self.parse_records(
+                    targeting_messages
+                        .into_iter()
+                        .filter(|msg| &msg.timestamp > newer_than),
+                )?,
             );
         }
 
@@ -170,6 +215,45 @@ impl MessagesRepository for CachingMessageRepository {
         Ok(flag)
     }
 
+    async fn find_cached(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        messages: &[MessageLike],
+    ) -> Result<HashSet<MessageId>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[MessageRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(MessageRecord::collection())?;
+        let server_id_idx = collection.index(&MessageRecord::server_id_idx())?;
+        let remote_id_idx = collection.index(&MessageRecord::remote_id_idx())?;
+
+        let mut cached_ids = HashSet::new();
+
+        for message in messages {
+            let is_cached = match (&message.server_id, &message.remote_id) {
+                (Some(server_id), _) => {
+                    server_id_idx
+                        .contains_key(&(account, room_id, server_id))
+                        .await?
+                }
+                (None, Some(remote_id)) => {
+                    remote_id_idx
+                        .contains_key(&(account, room_id, remote_id))
+                        .await?
+                }
+                (None, None) => false,
+            };
+
+            if is_cached {
+                cached_ids.insert(message.id.clone());
+            }
+        }
+
+        Ok(cached_ids)
+    }
+
     async fn append(
         &self,
         account: &AccountId,
@@ -178,9 +262,14 @@ impl MessagesRepository for CachingMessageRepository {
     ) -> Result<()> {
         let tx = self
             .store
-            .transaction_for_reading_and_writing(&[MessageRecord::collection()])
+            .transaction_for_reading_and_writing(&[
+                MessageRecord::collection(),
+                AttachmentRecord::collection(),
+            ])
             .await?;
         let collection = tx.writeable_collection(MessageRecord::collection())?;
+        let attachments_collection = tx.writeable_collection(AttachmentRecord::collection())?;
+
         for message in messages {
             collection.put_entity(&MessageRecord::from_message(
                 account.clone(),
@@ -188,24 +277,264 @@ impl MessagesRepository for CachingMessageRepository {
                 message.clone(),
             ))?;
         }
+
+        // Retractions, moderations and corrections change the attachments of the message they
+        // target, so we reindex the attachments of the targeted messages as well…
+        let message_id_idx = collection.index(&MessageRecord::message_id_idx())?;
+        let remote_id_idx = collection.index(&MessageRecord::remote_id_idx())?;
+        let server_id_idx = collection.index(&MessageRecord::server_id_idx())?;
+        let remote_id_target_idx = collection.index(&MessageRecord::remote_id_target_idx())?;
+        let server_id_target_idx = collection.index(&MessageRecord::server_id_target_idx())?;
+
+        let mut reindexed_ids = HashSet::new();
+
+        for message in messages {
+            let original_ids = match &message.payload {
+                MessageLikePayload::Message { .. } => vec![message.id.clone()],
+                MessageLikePayload::Retraction { target_id, .. }
+                | MessageLikePayload::Correction { target_id, .. } => {
+                    let originals = match target_id {
+                        MessageTargetId::RemoteId(id) => {
+                            remote_id_idx
+                                .get_all_values::<MessageRecord>(
+                                    Query::Only((account, room_id, id)),
+                                    Default::default(),
+                                    None,
+                                )
+                                .await?
+                        }
+                        MessageTargetId::ServerId(id) => {
+                            server_id_idx
+                                .get_all_values::<MessageRecord>(
+                                    Query::Only((account, room_id, id)),
+                                    Default::default(),
+                                    None,
+                                )
+                                .await?
+                        }
+                    };
+                    originals
+                        .into_iter()
+                        .map(|record| record.message_id)
+                        .collect()
+                }
+                _ => continue,
+            };
+
+            for id in original_ids {
+                if !reindexed_ids.insert(id.clone()) {
+                    continue;
+                }
+
+                attachments_collection
+                    .delete_all_in_index(
+                        &AttachmentRecord::message_id_idx(),
+                        Query::Only((account, room_id, &id)),
+                    )
+                    .await?;
+
+                let Some(original) = message_id_idx
+                    .get::<_, MessageRecord>(&(account, room_id, &id))
+                    .await?
+                else {
+                    continue;
+                };
+
+                let mut targeting_records = vec![];
+                if let Some(remote_id) = &original.remote_id {
+                    targeting_records.extend(
+                        remote_id_target_idx
+                            .get_all_values::<MessageRecord>(
+                                Query::Only((account, room_id, remote_id)),
+                                Default::default(),
+                                None,
+                            )
+                            .await?,
+                    );
+                }
+                if let Some(server_id) = &original.server_id {
+                    targeting_records.extend(
+                        server_id_target_idx
+                            .get_all_values::<MessageRecord>(
+                                Query::Only((account, room_id, server_id)),
+                                Default::default(),
+                                None,
+                            )
+                            .await?,
+                    );
+                }
+
+                let Some(original) = self.parse_record(original)? else {
+                    continue;
+                };
+                let targeting_messages = self.parse_records(targeting_records)?;
+
+                for (index, attachment) in current_attachments(&original, targeting_messages)
+                    .into_iter()
+                    .enumerate()
+                {
+                    attachments_collection.put_entity(&AttachmentRecord::new(
+                        account.clone(),
+                        room_id.clone(),
+                        original.id.clone(),
+                        original.timestamp,
+                        index,
+                        attachment,
+                    ))?;
+                }
+            }
+        }
+
         tx.commit().await?;
         Ok(())
     }
 
+    async fn delete(&self, account: &AccountId, room_id: &RoomId, id: &MessageId) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[
+                MessageRecord::collection(),
+                AttachmentRecord::collection(),
+            ])
+            .await?;
+        let collection = tx.writeable_collection(MessageRecord::collection())?;
+        let message_id_idx = collection.index(&MessageRecord::message_id_idx())?;
+        message_id_idx.delete(&(account, room_id, id)).await?;
+        tx.writeable_collection(AttachmentRecord::collection())?
+            .delete_all_in_index(
+                &AttachmentRecord::message_id_idx(),
+                Query::Only((account, room_id, id)),
+            )
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn clear_room(&self, account: &AccountId, room_id: &RoomId) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[
+                MessageRecord::collection(),
+                AttachmentRecord::collection(),
+            ])
+            .await?;
+        let collection = tx.writeable_collection(MessageRecord::collection())?;
+        collection
+            .delete_all_in_index(&MessageRecord::room_idx(), Query::Only((account, room_id)))
+            .await?;
+        tx.writeable_collection(AttachmentRecord::collection())?
+            .delete_all_in_index(
+                &AttachmentRecord::room_idx(),
+                Query::Only((account, room_id)),
+            )
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete_messages_before(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        before: DateTime<Utc>,
+        batch_size: u32,
+    ) -> Result<usize> {
+        let batch_size = batch_size.max(1) as usize;
+        let mut deleted_messages = 0;
+
+        // Delete in batches so that we don't block the database for too long…
+        loop {
+            let tx = self
+                .store
+                .transaction_for_reading_and_writing(&[
+                    MessageRecord::collection(),
+                    AttachmentRecord::collection(),
+                ])
+                .await?;
+            let collection = tx.writeable_collection(MessageRecord::collection())?;
+            let batch = collection
+                .index(&MessageRecord::timestamp_idx())?
+                .get_all_filtered(
+                    Query::Range {
+                        start: Bound::Included((account, room_id, &DateTime::<Utc>::MIN_UTC)),
+                        end: Bound::Excluded((account, room_id, &before)),
+                    },
+                    QueryDirection::Forward,
+                    Some(batch_size),
+                    |_, record: MessageRecord| Some((record.id, record.message_id)),
+                )
+                .await?;
+
+            let attachments = tx.writeable_collection(AttachmentRecord::collection())?;
+            for (id, message_id) in &batch {
+                collection.delete(id).await?;
+                attachments
+                    .delete_all_in_index(
+                        &AttachmentRecord::message_id_idx(),
+                        Query::Only((account, room_id, message_id)),
+                    )
+                    .await?;
+            }
+            tx.commit().await?;
+
+            deleted_messages += batch.len();
+            if batch.len() < batch_size {
+                break;
+            }
+        }
+
+        let deleted_since_vacuum = self
+            .deleted_since_vacuum
+            .fetch_add(deleted_messages, Ordering::Relaxed)
+            + deleted_messages;
+
+        if deleted_since_vacuum >= VACUUM_THRESHOLD {
+            self.deleted_since_vacuum.store(0, Ordering::Relaxed);
+            self.store.vacuum().await?;
+        }
+
+        Ok(deleted_messages)
+    }
+
     async fn clear_cache(&self, account: &AccountId) -> Result<()> {
         let tx = self
             .store
-            .transaction_for_reading_and_writing(&[MessageRecord::collection()])
+            .transaction_for_reading_and_writing(&[
+                MessageRecord::collection(),
+                AttachmentRecord::collection(),
+            ])
             .await?;
         let collection = tx.writeable_collection(MessageRecord::collection())?;
         collection
             .delete_all_in_index(&MessageRecord::account_idx(), Query::Only(account))
             .await?;
+        tx.writeable_collection(AttachmentRecord::collection())?
+            .delete_all_in_index(&AttachmentRecord::account_idx(), Query::Only(account))
+            .await?;
         tx.commit().await?;
 
         Ok(())
     }
 
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[MessageRecord::collection(), AttachmentRecord::collection()])
+            .await?;
+        let messages = tx
+            .readable_collection(MessageRecord::collection())?
+            .index(&MessageRecord::account_idx())?
+            .usage(Query::Only(account))
+            .await?;
+        let attachments = tx
+            .readable_collection(AttachmentRecord::collection())?
+            .index(&AttachmentRecord::account_idx())?
+            .usage(Query::Only(account))
+            .await?;
+        Ok((messages + attachments).into())
+    }
+
     async fn resolve_server_id(
         &self,
         account: &AccountId,
@@ -322,6 +651,81 @@ impl MessagesRepository for CachingMessageRepository {
         Ok(Some(message_ref))
     }
 
+    async fn search(
+        &self,
+        account: &AccountId,
+        room_id: Option<&RoomId>,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<MessageLike>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[MessageRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(MessageRecord::collection())?;
+
+        let records = match room_id {
+            Some(room_id) => {
+                collection
+                    .index(&MessageRecord::timestamp_idx())?
+                    .search::<MessageRecord>(
+                        Query::Range {
+                            start: Bound::Included((account, room_id, &DateTime::<Utc>::MIN_UTC)),
+                            end: Bound::Included((account, room_id, &DateTime::<Utc>::MAX_UTC)),
+                        },
+                        MessageRecord::body_fulltext_idx(),
+                        query,
+                        QueryDirection::Backward,
+                        Some(limit),
+                    )
+                    .await?
+            }
+            None => {
+                collection
+                    .index(&MessageRecord::account_timestamp_idx())?
+                    .search::<MessageRecord>(
+                        Query::Range {
+                            start: Bound::Included((account, &DateTime::<Utc>::MIN_UTC)),
+                            end: Bound::Included((account, &DateTime::<Utc>::MAX_UTC)),
+                        },
+                        MessageRecord::body_fulltext_idx(),
+                        query,
+                        QueryDirection::Backward,
+                        Some(limit),
+                    )
+                    .await?
+            }
+        };
+
+        self.parse_records(records)
+    }
+
+    async fn get_thread(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        thread_id: &ThreadId,
+    ) -> Result<Vec<MessageLike>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[MessageRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(MessageRecord::collection())?;
+        let thread_idx = collection.index(&MessageRecord::thread_id_idx())?;
+
+        let records = thread_idx
+            .get_all_values::<MessageRecord>(
+                Query::Only((account, room_id, thread_id)),
+                Default::default(),
+                None,
+            )
+            .await?;
+
+        let mut messages = self.parse_records(records)?;
+        messages.sort_by_key(|msg| msg.timestamp);
+        Ok(messages)
+    }
+
     async fn get_messages_after(
         &self,
         account: &AccountId,
@@ -335,18 +739,180 @@ impl MessagesRepository for CachingMessageRepository {
         let collection = tx.readable_collection(MessageRecord::collection())?;
         let room_idx = collection.index(&MessageRecord::timestamp_idx())?;
 
-        let messages = room_idx
-            .get_all_filtered::<MessageRecord, MessageLike>(
+        let records = room_idx
+            .get_all_filtered::<MessageRecord, MessageRecord>(
                 Query::Range {
                     start: Bound::Included((account, room_id, &after)),
                     end: Bound::Included((account, room_id, &DateTime::<Utc>::MAX_UTC)),
                 },
                 QueryDirection::default(),
                 None,
-                |_, message| (message.timestamp > after).then_some(MessageLike::from(message)),
+                |_, message| (message.timestamp > after).then_some(message),
             )
             .await?;
 
-        Ok(messages)
+        self.parse_records(records)
+    }
+
+    async fn get_latest_messages(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        limit: u32,
+    ) -> Result<Vec<MessageLike>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[MessageRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(MessageRecord::collection())?;
+        let room_idx = collection.index(&MessageRecord::timestamp_idx())?;
+
+        let mut records = room_idx
+            .get_all_values::<MessageRecord>(
+                Query::Range {
+                    start: Bound::Included((account, room_id, &DateTime::<Utc>::MIN_UTC)),
+                    end: Bound::Included((account, room_id, &DateTime::<Utc>::MAX_UTC)),
+                },
+                QueryDirection::Backward,
+                Some(limit as usize),
+            )
+            .await?;
+        records.reverse();
+
+        self.parse_records(records)
+    }
+
+    async fn get_nth_latest_message_timestamp(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        n: u32,
+    ) -> Result<Option<DateTime<Utc>>> {
+        if n == 0 {
+            return Ok(None);
+        }
+
+        let tx = self
+            .store
+            .transaction_for_reading(&[MessageRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(MessageRecord::collection())?;
+        let timestamp_idx = collection.index(&MessageRecord::timestamp_idx())?;
+
+        let timestamps = timestamp_idx
+            .get_all_filtered(
+                Query::Range {
+                    start: Bound::Included((account, room_id, &DateTime::<Utc>::MIN_UTC)),
+                    end: Bound::Included((account, room_id, &DateTime::<Utc>::MAX_UTC)),
+                },
+                QueryDirection::Backward,
+                Some(n as usize),
+                |_, record: MessageRecord| {
+                    matches!(
+                        record.payload,
+                        StoredPayload::Known(MessageLikePayload::Message { .. })
+                    )
+                    .then_some(record.timestamp)
+                },
+            )
+            .await?;
+
+        if timestamps.len() < n as usize {
+            return Ok(None);
+        }
+        Ok(timestamps.last().copied())
     }
+
+    async fn get_messages_in_range(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: u32,
+    ) -> Result<Vec<MessageLike>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[MessageRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(MessageRecord::collection())?;
+        let timestamp_idx = collection.index(&MessageRecord::timestamp_idx())?;
+
+        let records = timestamp_idx
+            .get_all_values::<MessageRecord>(
+                Query::Range {
+                    start: Bound::Included((account, room_id, &start)),
+                    end: Bound::Included((account, room_id, &end)),
+                },
+                QueryDirection::Forward,
+                Some(limit as usize),
+            )
+            .await?;
+
+        self.parse_records(records)
+    }
+
+    async fn get_attachments(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        kinds: &[AttachmentKind],
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<Attachment>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[AttachmentRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(AttachmentRecord::collection())?;
+        let timestamp_idx = collection.index(&AttachmentRecord::timestamp_idx())?;
+
+        let attachments = timestamp_idx
+            .get_all_filtered::<AttachmentRecord, Attachment>(
+                Query::Range {
+                    start: Bound::Included((account, room_id, &DateTime::<Utc>::MIN_UTC)),
+                    end: Bound::Included((account, room_id, &DateTime::<Utc>::MAX_UTC)),
+                },
+                QueryDirection::Backward,
+                Some(offset as usize + limit as usize),
+                |_, record| {
+                    (kinds.is_empty() || kinds.contains(&record.kind)).then_some(record.attachment)
+                },
+            )
+            .await?;
+
+        Ok(attachments.into_iter().skip(offset as usize).collect())
+    }
+}
+
+/// Returns the attachments `message` currently has, taking the messages targeting it into
+/// account. Retracted (or moderated) messages have no attachments, corrected messages have the
+/// attachments of their latest correction.
+fn current_attachments(
+    message: &MessageLike,
+    targeting_messages: Vec<MessageLike>,
+) -> Vec<Attachment> {
+    let mut latest_correction: Option<(DateTime<Utc>, Vec<Attachment>)> = None;
+
+    for targeting_message in targeting_messages {
+        match targeting_message.payload {
+            MessageLikePayload::Retraction { .. } => return vec![],
+            MessageLikePayload::Correction { attachments, .. } => {
+                if latest_correction
+                    .as_ref()
+                    .map(|(timestamp, _)| timestamp <= &targeting_message.timestamp)
+                    .unwrap_or(true)
+                {
+                    latest_correction = Some((targeting_message.timestamp, attachments));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    if let Some((_, attachments)) = latest_correction {
+        return attachments;
+    }
+
+    message.payload.attachments().to_vec()
 }
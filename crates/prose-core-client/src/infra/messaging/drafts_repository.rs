@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 
 use prose_store::prelude::*;
 
+use crate::domain::messaging::models::{Draft, Mention};
 use crate::domain::messaging::repos::DraftsRepository as DraftsRepositoryTrait;
 use crate::domain::shared::models::AccountId;
 use crate::dtos::RoomId;
@@ -19,15 +20,28 @@ pub struct DraftsRecord {
     account: AccountId,
     room_id: RoomId,
     text: String,
+    /// Drafts saved before mentions were stored alongside the text don't contain this field.
+    #[serde(default)]
+    mentions: Vec<Mention>,
 }
 
 impl DraftsRecord {
-    fn new(account: &AccountId, room_id: &RoomId, text: String) -> Self {
+    fn new(account: &AccountId, room_id: &RoomId, draft: &Draft) -> Self {
         Self {
             id: format!("{}.{}", account, room_id),
             account: account.clone(),
             room_id: room_id.clone(),
-            text,
+            text: draft.text.to_string(),
+            mentions: draft.mentions.clone(),
+        }
+    }
+}
+
+impl From<DraftsRecord> for Draft {
+    fn from(value: DraftsRecord) -> Self {
+        Self {
+            text: value.text.into(),
+            mentions: value.mentions,
         }
     }
 }
@@ -55,7 +69,7 @@ impl DraftsRepository {
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[async_trait]
 impl DraftsRepositoryTrait for DraftsRepository {
-    async fn get(&self, account: &AccountId, room_id: &RoomId) -> Result<Option<String>> {
+    async fn get(&self, account: &AccountId, room_id: &RoomId) -> Result<Option<Draft>> {
         let tx = self
             .store
             .transaction_for_reading(&[DraftsRecord::collection()])
@@ -63,10 +77,15 @@ impl DraftsRepositoryTrait for DraftsRepository {
         let collection = tx.readable_collection(DraftsRecord::collection())?;
         let idx = collection.index(&DraftsRecord::room_idx())?;
         let record = idx.get::<_, DraftsRecord>(&(account, room_id)).await?;
-        Ok(record.and_then(|r| (!r.text.is_empty()).then_some(r.text)))
+        Ok(record.and_then(|r| (!r.text.is_empty()).then(|| r.into())))
     }
 
-    async fn set(&self, account: &AccountId, room_id: &RoomId, draft: Option<&str>) -> Result<()> {
+    async fn set(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        draft: Option<&Draft>,
+    ) -> Result<()> {
         let tx = self
             .store
             .transaction_for_reading_and_writing(&[DraftsRecord::collection()])
@@ -75,7 +94,7 @@ impl DraftsRepositoryTrait for DraftsRepository {
 
         match draft {
             Some(draft) if !draft.is_empty() => {
-                collection.put_entity(&DraftsRecord::new(account, room_id, draft.to_string()))?;
+                collection.put_entity(&DraftsRecord::new(account, room_id, draft))?;
             }
             _ => {
                 let idx = collection.index(&DraftsRecord::room_idx())?;
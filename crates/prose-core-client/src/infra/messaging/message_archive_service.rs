@@ -11,6 +11,7 @@ use xmpp_parsers::mam::Complete;
 use prose_xmpp::mods;
 use prose_xmpp::stanza::mam::query;
 use prose_xmpp::stanza::mam::query::{DateTimeFilter, RangeFilter};
+use prose_xmpp::stanza::message::mam::ArchivedMessage;
 use prose_xmpp::stanza::message::stanza_id;
 
 use crate::domain::messaging::models::MessageServerId;
@@ -140,4 +141,46 @@ impl MessageArchiveService for XMPPClient {
             is_last: fin.complete == Complete::True,
         })
     }
+
+    async fn load_messages_with_ids(
+        &self,
+        room_id: &RoomId,
+        ids: &[MessageServerId],
+    ) -> Result<Vec<ArchivedMessage>> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mam = self.client.get_mod::<mods::MAM>();
+        let range = RangeFilter::Ids(ids.iter().map(|id| id.as_ref().into()).collect());
+
+        let mut query = query::Query {
+            filter: None,
+            rsm_filter: Some(query::RsmFilter {
+                range: None,
+                max: Some(ids.len()),
+            }),
+            flip_page: false,
+        };
+
+        let to = match room_id {
+            RoomId::User(id) => {
+                query.filter = Some(query::Filter {
+                    range: Some(range),
+                    with: Some(id.as_ref().clone().into()),
+                });
+                None
+            }
+            RoomId::Muc(id) => {
+                query.filter = Some(query::Filter {
+                    range: Some(range),
+                    with: None,
+                });
+                Some(id.as_ref())
+            }
+        };
+
+        let (messages, _) = mam.load_messages(to, query).await?;
+        Ok(messages)
+    }
 }
@@ -3,6 +3,7 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use anyhow::bail;
 use chrono::{DateTime, Utc};
 use jid::BareJid;
 use serde::{Deserialize, Serialize};
@@ -10,7 +11,7 @@ use serde::{Deserialize, Serialize};
 use prose_store::prelude::*;
 
 use crate::domain::messaging::models::{
-    MessageId, MessageLike, MessageLikePayload, MessageTargetId,
+    MessageId, MessageLike, MessageLikePayload, MessageTargetId, ThreadId,
 };
 use crate::domain::shared::models::AccountId;
 use crate::dtos::{MessageRemoteId, MessageServerId, ParticipantId, RoomId};
@@ -28,7 +29,24 @@ pub struct MessageRecord {
     pub to: Option<BareJid>,
     pub from: ParticipantId,
     pub timestamp: DateTime<Utc>,
-    pub payload: MessageLikePayload,
+    /// The thread of the payload, if any. Kept separately so that threads can be loaded without
+    /// scanning the whole room.
+    pub thread_id: Option<ThreadId>,
+    /// The raw text of the payload's body, if any. Kept separately so that it can be indexed
+    /// for searching.
+    pub body: Option<String>,
+    pub payload: StoredPayload,
+}
+
+/// The payload is stored untagged so that rows containing payloads we don't know (e.g. because
+/// they were written by a newer version of the client) don't fail a whole query. These rows are
+/// kept as they are and are parsed again on every read, so that they'll become available once
+/// the client understands their payload.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StoredPayload {
+    Known(MessageLikePayload),
+    Unknown(serde_json::Value),
 }
 
 mod columns {
@@ -40,6 +58,8 @@ mod columns {
     pub const REMOTE_ID: &str = "remote_id";
     pub const REMOTE_ID_TARGET: &str = "remote_id_target";
     pub const TIMESTAMP: &str = "timestamp";
+    pub const THREAD_ID: &str = "thread_id";
+    pub const BODY: &str = "body";
 }
 
 define_entity!(MessageRecord, "messages",
@@ -52,7 +72,9 @@ define_entity!(MessageRecord, "messages",
     // Can't be unique, because remote ids are not guaranteed to be unique…
     remote_id_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID, columns::REMOTE_ID], unique: false },
     remote_id_target_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID, columns::REMOTE_ID_TARGET], unique: false },
-    timestamp_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID, columns::TIMESTAMP], unique: false }
+    timestamp_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID, columns::TIMESTAMP], unique: false },
+    thread_id_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID, columns::THREAD_ID], unique: false },
+    account_timestamp_idx => { columns: [columns::ACCOUNT, columns::TIMESTAMP], unique: false }
 );
 
 impl KeyType for MessageId {
@@ -73,7 +95,18 @@ impl KeyType for MessageServerId {
     }
 }
 
+impl KeyType for ThreadId {
+    fn to_raw_key(&self) -> RawKey {
+        RawKey::Text(self.to_string())
+    }
+}
+
 impl MessageRecord {
+    /// The column that is indexed for full-text search.
+    pub fn body_fulltext_idx() -> &'static str {
+        columns::BODY
+    }
+
     pub fn from_message(account: AccountId, room_id: RoomId, value: MessageLike) -> Self {
         let (stanza_id_target, message_id_target) = match value.payload.target_id() {
             Some(MessageTargetId::RemoteId(id)) => (None, Some(id)),
@@ -95,21 +128,34 @@ impl MessageRecord {
             to: value.to,
             from: value.from,
             timestamp: value.timestamp,
-            payload: value.payload,
+            thread_id: value.payload.thread_id().cloned(),
+            body: value.payload.body().map(|body| body.raw.clone()),
+            payload: StoredPayload::Known(value.payload),
         }
     }
 }
 
-impl From<MessageRecord> for MessageLike {
-    fn from(value: MessageRecord) -> Self {
-        Self {
+impl TryFrom<MessageRecord> for MessageLike {
+    type Error = anyhow::Error;
+
+    fn try_from(value: MessageRecord) -> Result<Self, Self::Error> {
+        let payload = match value.payload {
+            StoredPayload::Known(payload) => payload,
+            StoredPayload::Unknown(payload) => bail!(
+                "Message {} has an unreadable payload of type {}",
+                value.message_id,
+                payload.get("type").unwrap_or(&serde_json::Value::Null)
+            ),
+        };
+
+        Ok(Self {
             id: value.message_id,
             remote_id: value.remote_id,
             server_id: value.server_id,
             to: value.to,
             from: value.from,
             timestamp: value.timestamp,
-            payload: value.payload,
-        }
+            payload,
+        })
     }
 }
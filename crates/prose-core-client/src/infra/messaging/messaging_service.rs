@@ -5,20 +5,23 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use minidom::Element;
 use xmpp_parsers::chatstates::ChatState;
 use xmpp_parsers::delay::Delay;
 use xmpp_parsers::message::{MessageType, Thread};
+use xmpp_parsers::receipts::Received;
 
 use prose_xmpp::mods;
+use prose_xmpp::stanza::message::chat_marker::Displayed;
 use prose_xmpp::stanza::message::mam::ArchivedMessage;
 use prose_xmpp::stanza::Message;
 
 use crate::domain::messaging::models::{
-    Emoji, KeyTransportPayload, MessageRemoteId, MessageServerId, SendMessageRequest,
-    StanzaParseError, ThreadId,
+    Emoji, KeyTransportPayload, MessageRemoteId, MessageServerId, MessageTargetId,
+    SendMessageRequest, StanzaParseError, ThreadId,
 };
-use crate::domain::messaging::services::MessagingService;
-use crate::dtos::{MucId, RoomId, UserId};
+use crate::domain::messaging::services::{MessagingService, SendMessageError};
+use crate::dtos::{MucId, OccupantId, RoomId, UserId};
 use crate::infra::xmpp::util::MessageExt;
 use crate::infra::xmpp::XMPPClient;
 
@@ -26,8 +29,6 @@ use crate::infra::xmpp::XMPPClient;
 #[async_trait]
 impl MessagingService for XMPPClient {
     async fn send_message(&self, room_id: &RoomId, request: SendMessageRequest) -> Result<()> {
-        let chat = self.client.get_mod::<mods::Chat>();
-
         let from = self.connected_jid().ok_or(anyhow::anyhow!(
             "Failed to read the user's JID since the client is not connected."
         ))?;
@@ -39,14 +40,20 @@ impl MessagingService for XMPPClient {
             .set_from(from)
             .set_to(room_id.clone().into_bare())
             .set_message_body(request.body)
+            .set_reply_to(request.reply_to)
             .set_chat_state(Some(ChatState::Active))
             .set_markable()
             .set_store(true);
+        if !room_id.is_muc_room() {
+            message = message.set_receipt_request();
+        }
         message.append_attachments(request.attachments);
 
-        chat.send_raw_message(message, false)?;
+        self.send_message_stanza(message)
+    }
 
-        Ok(())
+    fn acknowledges_sent_messages(&self) -> bool {
+        self.client.is_stream_management_active()
     }
 
     async fn send_message_to_thread(
@@ -55,8 +62,6 @@ impl MessagingService for XMPPClient {
         thread_id: &ThreadId,
         request: SendMessageRequest,
     ) -> Result<()> {
-        let chat = self.client.get_mod::<mods::Chat>();
-
         let from = self.connected_jid().ok_or(anyhow::anyhow!(
             "Failed to read the user's JID since the client is not connected."
         ))?;
@@ -67,15 +72,42 @@ impl MessagingService for XMPPClient {
             .set_from(from)
             .set_to(room_id.clone().into_bare())
             .set_message_body(request.body)
+            .set_reply_to(request.reply_to)
             .set_thread(Thread(thread_id.clone().into_inner()))
             .set_chat_state(Some(ChatState::Active))
             .set_markable()
             .set_store(true);
+        if !room_id.is_muc_room() {
+            message = message.set_receipt_request();
+        }
         message.append_attachments(request.attachments);
 
-        chat.send_raw_message(message, false)?;
+        self.send_message_stanza(message)
+    }
 
-        Ok(())
+    async fn send_private_message(
+        &self,
+        occupant_id: &OccupantId,
+        request: SendMessageRequest,
+    ) -> Result<()> {
+        let from = self.connected_jid().ok_or(anyhow::anyhow!(
+            "Failed to read the user's JID since the client is not connected."
+        ))?;
+
+        let mut message = Message::new()
+            .set_type(MessageType::Chat)
+            .set_id(request.id.into_inner().into())
+            .set_from(from)
+            .set_to(occupant_id.clone().into_inner())
+            .set_message_body(request.body)
+            .set_reply_to(request.reply_to)
+            .set_chat_state(Some(ChatState::Active))
+            .set_muc_private_message()
+            .set_receipt_request()
+            .set_markable();
+        message.append_attachments(request.attachments);
+
+        self.send_message_stanza(message)
     }
 
     async fn send_key_transport_message(
@@ -107,8 +139,6 @@ impl MessagingService for XMPPClient {
         message_id: &MessageRemoteId,
         request: SendMessageRequest,
     ) -> Result<()> {
-        let chat = self.client.get_mod::<mods::Chat>();
-
         let from = self.connected_jid().ok_or(anyhow::anyhow!(
             "Failed to read the user's JID since the client is not connected."
         ))?;
@@ -123,16 +153,21 @@ impl MessagingService for XMPPClient {
             .set_store(true);
         message.append_attachments(request.attachments);
 
-        chat.send_raw_message(message, false)?;
-        Ok(())
+        self.send_message_stanza(message)
     }
 
-    async fn retract_message(&self, room_id: &RoomId, message_id: &MessageRemoteId) -> Result<()> {
+    async fn retract_message(
+        &self,
+        room_id: &RoomId,
+        message_id: &MessageRemoteId,
+        reason: Option<&str>,
+    ) -> Result<()> {
         let chat = self.client.get_mod::<mods::Chat>();
         chat.retract_message(
             message_id.as_ref().into(),
             room_id.clone().into_bare(),
             &room_id.message_type(),
+            reason,
         )?;
         Ok(())
     }
@@ -169,29 +204,60 @@ impl MessagingService for XMPPClient {
 
     async fn set_user_is_composing(&self, room_id: &RoomId, is_composing: bool) -> Result<()> {
         let chat = self.client.get_mod::<mods::Chat>();
-        chat.send_chat_state(
-            room_id.clone().into_bare(),
-            if is_composing {
-                ChatState::Composing
-            } else {
-                ChatState::Paused
-            },
-            &room_id.message_type(),
-        )
+
+        let chat_state = if is_composing {
+            ChatState::Composing
+        } else {
+            ChatState::Paused
+        };
+
+        match room_id {
+            RoomId::User(user_id) => {
+                chat.send_chat_state(user_id.clone().into_inner(), chat_state, &MessageType::Chat)
+            }
+            RoomId::Muc(room_id) => {
+                // Chat states in MUC rooms are addressed to the room's bare JID which reflects
+                // them to all occupants from our occupant JID. We don't set a `from` here, so
+                // that our real JID doesn't end up in the stanza.
+                let message = Message::new()
+                    .set_type(MessageType::Groupchat)
+                    .set_to(room_id.clone().into_inner())
+                    .set_chat_state(Some(chat_state));
+                chat.send_raw_message(message, false)
+            }
+        }
     }
 
     async fn send_read_receipt(
         &self,
         room_id: &RoomId,
-        message_id: &MessageRemoteId,
+        message_id: &MessageTargetId,
     ) -> Result<()> {
         let chat = self.client.get_mod::<mods::Chat>();
-        chat.mark_message_received(
-            message_id.as_ref().into(),
-            room_id.clone().into_bare(),
-            &room_id.message_type(),
-        )?;
-        Ok(())
+
+        let message = Message::new()
+            .set_type(room_id.message_type())
+            .set_to(room_id.clone().into_bare())
+            .set_displayed_marker(Displayed {
+                id: message_id.clone().into_string().into(),
+            })
+            .set_store(true);
+
+        chat.send_raw_message(message, false)
+    }
+
+    async fn send_delivery_receipt(&self, to: &UserId, message_id: &MessageRemoteId) -> Result<()> {
+        let chat = self.client.get_mod::<mods::Chat>();
+
+        let message = Message::new()
+            .set_type(MessageType::Chat)
+            .set_to(to.clone().into_inner())
+            .set_delivery_receipt(Received {
+                id: message_id.to_string(),
+            })
+            .set_store(true);
+
+        chat.send_raw_message(message, false)
     }
 
     async fn relay_archived_message_to_room(
@@ -231,6 +297,21 @@ impl MessagingService for XMPPClient {
     }
 }
 
+impl XMPPClient {
+    /// Sends `message` unless its serialized form exceeds the configured maximum stanza size.
+    fn send_message_stanza(&self, message: Message) -> Result<()> {
+        if let Some(max_size) = self.max_stanza_size {
+            let size = String::from(&Element::from(message.clone())).len();
+            if size > max_size {
+                return Err(SendMessageError::StanzaTooLarge { size, max_size }.into());
+            }
+        }
+
+        let chat = self.client.get_mod::<mods::Chat>();
+        chat.send_raw_message(message, false)
+    }
+}
+
 trait RoomMessageType {
     fn message_type(&self) -> MessageType;
 }
@@ -3,14 +3,18 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+pub use attachment_record::AttachmentRecord;
 pub use caching_message_repository::CachingMessageRepository;
 pub use drafts_repository::{DraftsRecord, DraftsRepository};
-pub use message_record::MessageRecord;
+pub use message_record::{MessageRecord, StoredPayload};
 pub use offline_messages_repository::OfflineMessagesRepository;
+pub use pending_messages_repository::{PendingMessageRecord, PendingMessagesRepository};
 
+mod attachment_record;
 mod caching_message_repository;
 mod drafts_repository;
 mod message_archive_service;
 mod message_record;
 mod messaging_service;
 mod offline_messages_repository;
+mod pending_messages_repository;
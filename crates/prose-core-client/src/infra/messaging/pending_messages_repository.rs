@@ -0,0 +1,159 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use prose_store::prelude::*;
+
+use crate::domain::messaging::models::{MessageId, PendingMessage};
+use crate::domain::messaging::repos::PendingMessagesRepository as PendingMessagesRepositoryTrait;
+use crate::domain::shared::models::AccountId;
+use crate::dtos::RoomId;
+
+#[derive(Serialize, Deserialize)]
+pub struct PendingMessageRecord {
+    id: String,
+    account: AccountId,
+    room_id: RoomId,
+    message_id: MessageId,
+    message: PendingMessage,
+}
+
+impl PendingMessageRecord {
+    fn new(account: &AccountId, message: &PendingMessage) -> Self {
+        Self {
+            id: format!("{}.{}.{}", account, message.room_id, message.id),
+            account: account.clone(),
+            room_id: message.room_id.clone(),
+            message_id: message.id.clone(),
+            message: message.clone(),
+        }
+    }
+}
+
+mod columns {
+    pub const ACCOUNT: &str = "account";
+    pub const ROOM_ID: &str = "room_id";
+    pub const MESSAGE_ID: &str = "message_id";
+}
+
+define_entity!(PendingMessageRecord, "pending_messages",
+    account_idx => { columns: [columns::ACCOUNT], unique: false },
+    room_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID], unique: false },
+    message_id_idx => { columns: [columns::ACCOUNT, columns::ROOM_ID, columns::MESSAGE_ID], unique: true }
+);
+
+pub struct PendingMessagesRepository {
+    store: Store<PlatformDriver>,
+}
+
+impl PendingMessagesRepository {
+    pub fn new(store: Store<PlatformDriver>) -> Self {
+        Self { store }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl PendingMessagesRepositoryTrait for PendingMessagesRepository {
+    async fn get_all(&self, account: &AccountId) -> Result<Vec<PendingMessage>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[PendingMessageRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(PendingMessageRecord::collection())?;
+        let idx = collection.index(&PendingMessageRecord::account_idx())?;
+        let records = idx
+            .get_all_values::<PendingMessageRecord>(Query::Only(account), Default::default(), None)
+            .await?;
+        Ok(sorted_messages(records))
+    }
+
+    async fn get_all_in_room(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+    ) -> Result<Vec<PendingMessage>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[PendingMessageRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(PendingMessageRecord::collection())?;
+        let idx = collection.index(&PendingMessageRecord::room_idx())?;
+        let records = idx
+            .get_all_values::<PendingMessageRecord>(
+                Query::Only((account, room_id)),
+                Default::default(),
+                None,
+            )
+            .await?;
+        Ok(sorted_messages(records))
+    }
+
+    async fn get(
+        &self,
+        account: &AccountId,
+        room_id: &RoomId,
+        id: &MessageId,
+    ) -> Result<Option<PendingMessage>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[PendingMessageRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(PendingMessageRecord::collection())?;
+        let idx = collection.index(&PendingMessageRecord::message_id_idx())?;
+        let record = idx
+            .get::<_, PendingMessageRecord>(&(account, room_id, id))
+            .await?;
+        Ok(record.map(|record| record.message))
+    }
+
+    async fn put(&self, account: &AccountId, message: &PendingMessage) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[PendingMessageRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(PendingMessageRecord::collection())?;
+        collection.put_entity(&PendingMessageRecord::new(account, message))?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete(&self, account: &AccountId, room_id: &RoomId, id: &MessageId) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[PendingMessageRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(PendingMessageRecord::collection())?;
+        let idx = collection.index(&PendingMessageRecord::message_id_idx())?;
+        idx.delete(&(account, room_id, id)).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn clear_cache(&self, account: &AccountId) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[PendingMessageRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(PendingMessageRecord::collection())?;
+        collection
+            .delete_all_in_index(&PendingMessageRecord::account_idx(), Query::Only(account))
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+fn sorted_messages(records: Vec<PendingMessageRecord>) -> Vec<PendingMessage> {
+    let mut messages = records
+        .into_iter()
+        .map(|record| record.message)
+        .collect::<Vec<_>>();
+    messages.sort_by_key(|message| message.timestamp);
+    messages
+}
@@ -3,10 +3,10 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use prose_store::prelude::*;
+use prose_store::VersionChangeEvent;
 
 use crate::app::deps::{
     AppContext, AppDependencies, DynAvatarRepository, DynClientEventDispatcher,
@@ -34,7 +34,8 @@ use crate::domain::user_info::services::impls::{
     UserInfoDomainService, UserInfoDomainServiceDependencies,
 };
 use crate::infra::contacts::{
-    CachingBlockListRepository, CachingContactsRepository, PresenceSubRequestsRepository,
+    CachingBlockListRepository, CachingContactsRepository, ContactRecord,
+    PresenceSubRequestsRepository,
 };
 use crate::infra::encryption::{
     CachingUserDeviceRepository, EncryptionKeysRepository, KyberPreKeyRecord, LocalDeviceRecord,
@@ -42,14 +43,15 @@ use crate::infra::encryption::{
     UserDeviceRecord,
 };
 use crate::infra::messaging::{
-    CachingMessageRepository, DraftsRecord, DraftsRepository, MessageRecord,
-    OfflineMessagesRepository,
+    AttachmentRecord, CachingMessageRepository, DraftsRecord, DraftsRepository, MessageRecord,
+    OfflineMessagesRepository, PendingMessageRecord, PendingMessagesRepository,
 };
 use crate::infra::rooms::InMemoryConnectedRoomsRepository;
 use crate::infra::settings::{
     AccountSettingsRecord, AccountSettingsRepository, LocalRoomSettingsRecord,
     LocalRoomSettingsRepository,
 };
+use crate::infra::sidebar::{BookmarkRecord, BookmarksRepository};
 use crate::infra::user_info::{
     InMemoryUserInfoRepository, UserProfileRecord, UserProfileRepository,
 };
@@ -71,150 +73,223 @@ pub(crate) struct PlatformDependencies {
     pub xmpp: Arc<XMPPClient>,
 }
 
-const DB_VERSION: u32 = 31;
+pub const DB_VERSION: u32 = 38;
+
+/// Opens the cache and migrates it to `DB_VERSION`. If a migration fails because the schema of
+/// the cache doesn't match its version, the broken cache is moved aside and started over empty
+/// (see `Store::open_or_recover`). Our OMEMO keys and the outbox are carried over, since they
+/// can't be restored from the server. Use `Store::was_reset` to find out whether that happened.
+pub async fn open_store<D: Driver + Clone>(driver: D) -> Result<Store<D>, D::Error> {
+    Store::open_or_recover(
+        driver,
+        DB_VERSION,
+        &[
+            LocalDeviceRecord::collection(),
+            PreKeyRecord::collection(),
+            SignedPreKeyRecord::collection(),
+            KyberPreKeyRecord::collection(),
+            SessionRecord::collection(),
+            SenderKeyRecord::collection(),
+            PendingMessageRecord::collection(),
+        ],
+        |event| migrate_store::<D>(event),
+    )
+    .await
+}
 
-pub async fn open_store<D: Driver>(driver: D) -> Result<Store<D>, D::Error> {
-    let versions_changed = Arc::new(AtomicBool::new(false));
+/// Applies all migrations from `event.old_version` up to and including `event.new_version`.
+pub fn migrate_store<D: Driver>(
+    event: &VersionChangeEvent<D::UpgradeTransaction<'_>>,
+) -> Result<(), D::Error> {
+    // Migrations newer than `new_version` are skipped, so that the store can be opened at an
+    // older version in tests.
+    let migrates_to = |version: u32| event.old_version < version && version <= event.new_version;
+
+    let tx = &event.tx;
+
+    if migrates_to(10) {
+        create_collection::<D, AccountSettingsRecord>(&tx)?;
+        create_collection::<D, DraftsRecord>(&tx)?;
+        create_collection::<D, MessageRecord>(&tx)?;
+        tx.create_collection("user_info")?;
+        create_collection::<D, UserProfileRecord>(&tx)?;
+        #[cfg(target_arch = "wasm32")]
+        create_collection::<D, crate::infra::user_info::AvatarRecord>(&tx)?;
+    }
 
-    let inner_versions_changed = versions_changed.clone();
-    let store = Store::open(driver, DB_VERSION, move |event| {
-        let tx = &event.tx;
+    if migrates_to(13) {
+        tx.delete_collection(MessageRecord::collection())?;
+    }
 
-        inner_versions_changed.store(true, Ordering::Relaxed);
+    if migrates_to(14) {
+        create_collection::<D, MessageRecord>(&tx)?;
+    }
 
-        if event.old_version < 10 {
-            create_collection::<D, AccountSettingsRecord>(&tx)?;
-            create_collection::<D, DraftsRecord>(&tx)?;
-            create_collection::<D, MessageRecord>(&tx)?;
-            tx.create_collection("user_info")?;
-            create_collection::<D, UserProfileRecord>(&tx)?;
-            #[cfg(target_arch = "wasm32")]
-            create_collection::<D, crate::infra::user_info::AvatarRecord>(&tx)?;
-        }
+    if migrates_to(15) {
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection::<D, MessageRecord>(&tx)?;
+    }
 
-        if event.old_version < 13 {
-            tx.delete_collection(MessageRecord::collection())?;
-        }
+    if migrates_to(16) {
+        create_collection::<D, UserDeviceRecord>(&tx)?;
+        tx.create_collection("omemo_identity")?;
 
-        if event.old_version < 14 {
-            create_collection::<D, MessageRecord>(&tx)?;
-        }
+        create_collection::<D, LocalDeviceRecord>(&tx)?;
+        create_collection::<D, PreKeyRecord>(&tx)?;
+        create_collection::<D, SenderKeyRecord>(&tx)?;
+        create_collection::<D, SessionRecord>(&tx)?;
+        create_collection::<D, SignedPreKeyRecord>(&tx)?;
+    }
 
-        if event.old_version < 15 {
-            tx.delete_collection(MessageRecord::collection())?;
-            create_collection::<D, MessageRecord>(&tx)?;
-        }
+    if migrates_to(17) {
+        create_collection::<D, KyberPreKeyRecord>(&tx)?;
+    }
 
-        if event.old_version < 16 {
-            create_collection::<D, UserDeviceRecord>(&tx)?;
-            tx.create_collection("omemo_identity")?;
+    if migrates_to(19) {
+        tx.delete_collection("omemo_identity")?;
+        tx.delete_collection(SessionRecord::collection())?;
+        create_collection::<D, SessionRecord>(&tx)?;
+    }
 
-            create_collection::<D, LocalDeviceRecord>(&tx)?;
-            create_collection::<D, PreKeyRecord>(&tx)?;
-            create_collection::<D, SenderKeyRecord>(&tx)?;
-            create_collection::<D, SessionRecord>(&tx)?;
-            create_collection::<D, SignedPreKeyRecord>(&tx)?;
-        }
+    if migrates_to(20) {
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection::<D, MessageRecord>(&tx)?;
+        tx.delete_collection(DraftsRecord::collection())?;
+        create_collection::<D, DraftsRecord>(&tx)?;
+    }
 
-        if event.old_version < 17 {
-            create_collection::<D, KyberPreKeyRecord>(&tx)?;
-        }
+    if migrates_to(21) {
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection::<D, MessageRecord>(&tx)?;
+    }
 
-        if event.old_version < 19 {
-            tx.delete_collection("omemo_identity")?;
-            tx.delete_collection(SessionRecord::collection())?;
-            create_collection::<D, SessionRecord>(&tx)?;
-        }
+    if migrates_to(22) {
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection::<D, MessageRecord>(&tx)?;
+        tx.delete_collection(UserDeviceRecord::collection())?;
+        create_collection::<D, UserDeviceRecord>(&tx)?;
+    }
 
-        if event.old_version < 20 {
-            tx.delete_collection(MessageRecord::collection())?;
-            create_collection::<D, MessageRecord>(&tx)?;
-            tx.delete_collection(DraftsRecord::collection())?;
-            create_collection::<D, DraftsRecord>(&tx)?;
-        }
+    if migrates_to(23) {
+        create_collection::<D, LocalRoomSettingsRecord>(&tx)?;
+    }
 
-        if event.old_version < 21 {
-            tx.delete_collection(MessageRecord::collection())?;
-            create_collection::<D, MessageRecord>(&tx)?;
-        }
+    if migrates_to(24) {
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection::<D, MessageRecord>(&tx)?;
+    }
 
-        if event.old_version < 22 {
-            tx.delete_collection(MessageRecord::collection())?;
-            create_collection::<D, MessageRecord>(&tx)?;
-            tx.delete_collection(UserDeviceRecord::collection())?;
-            create_collection::<D, UserDeviceRecord>(&tx)?;
-        }
+    if migrates_to(26) {
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection::<D, MessageRecord>(&tx)?;
+    }
 
-        if event.old_version < 23 {
-            create_collection::<D, LocalRoomSettingsRecord>(&tx)?;
-        }
+    if migrates_to(27) {
+        tx.delete_collection(LocalDeviceRecord::collection())?;
+        tx.delete_collection(PreKeyRecord::collection())?;
+        tx.delete_collection(SenderKeyRecord::collection())?;
+        tx.delete_collection(SessionRecord::collection())?;
+        tx.delete_collection(SignedPreKeyRecord::collection())?;
+        tx.delete_collection(KyberPreKeyRecord::collection())?;
+
+        create_collection::<D, LocalDeviceRecord>(&tx)?;
+        create_collection::<D, PreKeyRecord>(&tx)?;
+        create_collection::<D, SenderKeyRecord>(&tx)?;
+        create_collection::<D, SessionRecord>(&tx)?;
+        create_collection::<D, SignedPreKeyRecord>(&tx)?;
+        create_collection::<D, KyberPreKeyRecord>(&tx)?;
+
+        tx.delete_collection("user_info")?;
+        tx.delete_collection(UserProfileRecord::collection())?;
+        tx.delete_collection(DraftsRecord::collection())?;
+        tx.delete_collection(UserDeviceRecord::collection())?;
+
+        tx.create_collection("user_info")?;
+        create_collection::<D, UserProfileRecord>(&tx)?;
+        create_collection::<D, DraftsRecord>(&tx)?;
+        create_collection::<D, UserDeviceRecord>(&tx)?;
+    }
 
-        if event.old_version < 24 {
-            tx.delete_collection(MessageRecord::collection())?;
-            create_collection::<D, MessageRecord>(&tx)?;
-        }
+    if migrates_to(28) {
+        tx.delete_collection("user_info")?;
+        tx.delete_collection(UserProfileRecord::collection())?;
 
-        if event.old_version < 26 {
-            tx.delete_collection(MessageRecord::collection())?;
-            create_collection::<D, MessageRecord>(&tx)?;
+        #[cfg(target_arch = "wasm32")]
+        {
+            tx.delete_collection(crate::infra::user_info::AvatarRecord::collection())?;
+            create_collection::<D, crate::infra::user_info::AvatarRecord>(&tx)?;
         }
 
-        if event.old_version < 27 {
-            tx.delete_collection(LocalDeviceRecord::collection())?;
-            tx.delete_collection(PreKeyRecord::collection())?;
-            tx.delete_collection(SenderKeyRecord::collection())?;
-            tx.delete_collection(SessionRecord::collection())?;
-            tx.delete_collection(SignedPreKeyRecord::collection())?;
-            tx.delete_collection(KyberPreKeyRecord::collection())?;
-
-            create_collection::<D, LocalDeviceRecord>(&tx)?;
-            create_collection::<D, PreKeyRecord>(&tx)?;
-            create_collection::<D, SenderKeyRecord>(&tx)?;
-            create_collection::<D, SessionRecord>(&tx)?;
-            create_collection::<D, SignedPreKeyRecord>(&tx)?;
-            create_collection::<D, KyberPreKeyRecord>(&tx)?;
-
-            tx.delete_collection("user_info")?;
-            tx.delete_collection(UserProfileRecord::collection())?;
-            tx.delete_collection(DraftsRecord::collection())?;
-            tx.delete_collection(UserDeviceRecord::collection())?;
-
-            tx.create_collection("user_info")?;
-            create_collection::<D, UserProfileRecord>(&tx)?;
-            create_collection::<D, DraftsRecord>(&tx)?;
-            create_collection::<D, UserDeviceRecord>(&tx)?;
-        }
+        create_collection::<D, UserProfileRecord>(&tx)?;
+    }
 
-        if event.old_version < 28 {
-            tx.delete_collection("user_info")?;
-            tx.delete_collection(UserProfileRecord::collection())?;
+    if migrates_to(31) {
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection::<D, MessageRecord>(&tx)?;
+    }
 
-            #[cfg(target_arch = "wasm32")]
-            {
-                tx.delete_collection(crate::infra::user_info::AvatarRecord::collection())?;
-                create_collection::<D, crate::infra::user_info::AvatarRecord>(&tx)?;
-            }
+    if migrates_to(32) {
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection_with_fulltext_indexes::<D, MessageRecord>(
+            &tx,
+            &[MessageRecord::body_fulltext_idx()],
+        )?;
+    }
 
-            create_collection::<D, UserProfileRecord>(&tx)?;
-        }
+    if migrates_to(33) {
+        create_collection::<D, PendingMessageRecord>(&tx)?;
+    }
 
-        if event.old_version < 31 {
-            tx.delete_collection(MessageRecord::collection())?;
-            create_collection::<D, MessageRecord>(&tx)?;
-        }
+    if migrates_to(34) {
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection_with_fulltext_indexes::<D, MessageRecord>(
+            &tx,
+            &[MessageRecord::body_fulltext_idx()],
+        )?;
+    }
 
-        Ok(())
-    })
-    .await?;
+    if migrates_to(35) {
+        create_collection::<D, BookmarkRecord>(&tx)?;
+    }
+
+    if migrates_to(36) {
+        // Attachments of messages that are already cached wouldn't be indexed otherwise.
+        tx.delete_collection(MessageRecord::collection())?;
+        create_collection_with_fulltext_indexes::<D, MessageRecord>(
+            &tx,
+            &[MessageRecord::body_fulltext_idx()],
+        )?;
+        create_collection::<D, AttachmentRecord>(&tx)?;
+    }
+
+    if migrates_to(37) {
+        create_collection::<D, ContactRecord>(&tx)?;
+    }
+
+    if migrates_to(38) {
+        // Contacts now remember when they were last seen.
+        tx.delete_collection(ContactRecord::collection())?;
+        create_collection::<D, ContactRecord>(&tx)?;
+    }
 
-    Ok(store)
+    Ok(())
 }
 
 fn create_collection<D: Driver, E: Entity>(tx: &D::UpgradeTransaction<'_>) -> Result<(), D::Error> {
+    create_collection_with_fulltext_indexes::<D, E>(tx, &[])
+}
+
+fn create_collection_with_fulltext_indexes<D: Driver, E: Entity>(
+    tx: &D::UpgradeTransaction<'_>,
+    fulltext_columns: &[&str],
+) -> Result<(), D::Error> {
     let collection = tx.create_collection(E::collection())?;
     for idx_spec in E::indexes() {
         collection.add_index(idx_spec)?;
     }
+    for column in fulltext_columns {
+        collection.add_fulltext_index(column)?;
+    }
     Ok(())
 }
 
@@ -228,7 +303,11 @@ impl From<PlatformDependencies> for AppDependencies {
         let drafts_repo = Arc::new(DraftsRepository::new(d.store.clone()));
         let id_provider = d.id_provider;
         let message_id_provider = d.message_id_provider;
-        let messages_repo = Arc::new(CachingMessageRepository::new(d.store.clone()));
+        let mut messages_repo = CachingMessageRepository::new(d.store.clone());
+        messages_repo.set_skip_unreadable_messages(ctx.config.skip_unreadable_cached_messages);
+        let messages_repo = Arc::new(messages_repo);
+        let pending_messages_repo = Arc::new(PendingMessagesRepository::new(d.store.clone()));
+        let session_repo = Arc::new(SessionRepository::new(d.store.clone()));
         let time_provider = d.time_provider;
         let user_device_repo = Arc::new(CachingUserDeviceRepository::new(
             d.store.clone(),
@@ -236,6 +315,7 @@ impl From<PlatformDependencies> for AppDependencies {
         ));
         let local_room_settings_repo = Arc::new(LocalRoomSettingsRepository::new(d.store.clone()));
         let block_list_repo = Arc::new(CachingBlockListRepository::new(d.xmpp.clone()));
+        let user_profile_repo = Arc::new(UserProfileRepository::new(d.store.clone()));
 
         let user_info_domain_service_dependencies = UserInfoDomainServiceDependencies {
             avatar_repo: avatar_repo.clone(),
@@ -243,7 +323,7 @@ impl From<PlatformDependencies> for AppDependencies {
             client_event_dispatcher: client_event_dispatcher.clone(),
             time_provider: time_provider.clone(),
             user_info_repo: Arc::new(InMemoryUserInfoRepository::new()),
-            user_profile_repo: Arc::new(UserProfileRepository::new(d.store.clone())),
+            user_profile_repo: user_profile_repo.clone(),
             user_info_service: d.xmpp.clone(),
             block_list_repo: block_list_repo.clone(),
         };
@@ -263,13 +343,14 @@ impl From<PlatformDependencies> for AppDependencies {
         ));
 
         let encryption_domain_service_dependencies = EncryptionDomainServiceDependencies {
+            client_event_dispatcher: client_event_dispatcher.clone(),
             ctx: ctx.clone(),
             encryption_keys_repo: Arc::new(EncryptionKeysRepository::new(d.store.clone())),
             encryption_service: d.encryption_service,
             message_repo: messages_repo.clone(),
             messaging_service: d.xmpp.clone(),
             rng_provider: d.rng_provider.clone(),
-            session_repo: Arc::new(SessionRepository::new(d.store.clone())),
+            session_repo: session_repo.clone(),
             time_provider: time_provider.clone(),
             user_device_id_provider: d.user_device_id_provider,
             user_device_repo: user_device_repo.clone(),
@@ -313,10 +394,12 @@ impl From<PlatformDependencies> for AppDependencies {
             Arc::new(RoomsDomainService::from(rooms_domain_service_dependencies));
 
         let sidebar_domain_service_dependencies = SidebarDomainServiceDependencies {
+            bookmarks_repo: Arc::new(BookmarksRepository::new(d.store.clone())),
             bookmarks_service: d.xmpp.clone(),
             client_event_dispatcher: client_event_dispatcher.clone(),
             connected_rooms_repo: connected_rooms_repo.clone(),
             ctx: ctx.clone(),
+            messages_repo: messages_repo.clone(),
             room_management_service: d.xmpp.clone(),
             rooms_domain_service: rooms_domain_service.clone(),
         };
@@ -328,7 +411,10 @@ impl From<PlatformDependencies> for AppDependencies {
         let contact_list_domain_service_dependencies = ContactListDomainServiceDependencies {
             ctx: ctx.clone(),
             client_event_dispatcher: client_event_dispatcher.clone(),
-            contact_list_repo: Arc::new(CachingContactsRepository::new(d.xmpp.clone())),
+            contact_list_repo: Arc::new(CachingContactsRepository::new(
+                d.xmpp.clone(),
+                d.store.clone(),
+            )),
             contact_list_service: d.xmpp.clone(),
             presence_sub_requests_repo: Arc::new(PresenceSubRequestsRepository::new()),
         };
@@ -349,12 +435,15 @@ impl From<PlatformDependencies> for AppDependencies {
         ));
 
         let room_factory = {
+            let account_settings_repo = account_settings_repo.clone();
             let client_event_dispatcher = client_event_dispatcher.clone();
             let ctx = ctx.clone();
             let drafts_repo = drafts_repo.clone();
             let encryption_domain_service = encryption_domain_service.clone();
+            let id_provider = id_provider.clone();
             let message_id_provider = message_id_provider.clone();
             let message_repo = messages_repo.clone();
+            let pending_messages_repo = pending_messages_repo.clone();
             let sidebar_domain_service = sidebar_domain_service.clone();
             let time_provider = time_provider.clone();
             let user_info_domain_service = user_info_domain_service.clone();
@@ -362,17 +451,21 @@ impl From<PlatformDependencies> for AppDependencies {
 
             RoomFactory::new(Arc::new(move |data| {
                 RoomInner {
+                    account_settings_repo: account_settings_repo.clone(),
                     attributes_service: xmpp.clone(),
                     client_event_dispatcher: client_event_dispatcher.clone(),
                     ctx: ctx.clone(),
                     data: data.clone(),
                     drafts_repo: drafts_repo.clone(),
                     encryption_domain_service: encryption_domain_service.clone(),
+                    id_provider: id_provider.clone(),
                     message_id_provider: message_id_provider.clone(),
                     message_archive_service: xmpp.clone(),
                     message_repo: message_repo.clone(),
                     messaging_service: xmpp.clone(),
                     participation_service: xmpp.clone(),
+                    pending_messages_repo: pending_messages_repo.clone(),
+                    room_management_service: xmpp.clone(),
                     synced_room_settings_service: xmpp.clone(),
                     sidebar_domain_service: sidebar_domain_service.clone(),
                     time_provider: time_provider.clone(),
@@ -400,6 +493,7 @@ impl From<PlatformDependencies> for AppDependencies {
             messages_repo,
             messaging_service: d.xmpp.clone(),
             offline_messages_repo: Arc::new(OfflineMessagesRepository::new()),
+            pending_messages_repo,
             request_handling_service: d.xmpp.clone(),
             rng_provider: d.rng_provider.clone(),
             room_attributes_service: d.xmpp.clone(),
@@ -408,6 +502,7 @@ impl From<PlatformDependencies> for AppDependencies {
             room_participation_service: d.xmpp.clone(),
             rooms_domain_service,
             server_event_handler_queue: d.server_event_handler_queue,
+            session_repo,
             short_id_provider: d.short_id_provider,
             sidebar_domain_service,
             time_provider,
@@ -415,6 +510,7 @@ impl From<PlatformDependencies> for AppDependencies {
             user_account_service: d.xmpp.clone(),
             user_device_repo,
             user_info_domain_service,
+            user_profile_repo,
         }
     }
 }
@@ -15,8 +15,8 @@ use prose_xmpp::{mods, RequestError};
 
 use crate::domain::general::models::Capabilities;
 use crate::domain::rooms::models::{
-    PublicRoomInfo, RoomAffiliation, RoomConfig, RoomError, RoomSessionInfo, RoomSessionMember,
-    RoomSpec,
+    PublicRoomInfo, RoomAffiliation, RoomConfig, RoomConfiguration, RoomError, RoomSessionInfo,
+    RoomSessionMember, RoomSpec,
 };
 use crate::domain::rooms::services::RoomManagementService;
 use crate::domain::shared::models::{MucId, OccupantId, RoomType, UserId};
@@ -87,7 +87,7 @@ impl RoomManagementService for XMPPClient {
             // If the room was created but doesn't match our spec, we'll try to delete it again.
             if room_has_been_created {
                 // Ignore the error since it would not be indicative of what happened.
-                _ = muc_mod.destroy_room(&room_jid, None).await;
+                _ = muc_mod.destroy_room(&room_jid, None, None).await;
             }
 
             return Err(RoomError::RoomValidationError(error.to_string()));
@@ -104,6 +104,8 @@ impl RoomManagementService for XMPPClient {
                 room_type: spec.room_type(),
                 mam_version: room_info.features.mam_version,
                 supports_self_ping_optimization: room_info.features.supports_self_ping_optimization,
+                supports_message_moderation: room_info.features.supports_message_moderation,
+                supports_chat_markers: room_info.features.supports_chat_markers,
             },
             topic: occupancy.subject,
             user_nickname,
@@ -130,7 +132,8 @@ impl RoomManagementService for XMPPClient {
                 Some(availability.try_into()?),
                 Some(capabilities.into()),
             )
-            .await?;
+            .await
+            .map_err(RoomError::from_join_error)?;
 
         // If we accidentally created the room, we'll return an ItemNotFound error since our
         // actual intention was to join an existing room.
@@ -218,9 +221,47 @@ impl RoomManagementService for XMPPClient {
             room_type,
             mam_version: room_info.features.mam_version,
             supports_self_ping_optimization: room_info.features.supports_self_ping_optimization,
+            supports_message_moderation: room_info.features.supports_message_moderation,
+            supports_chat_markers: room_info.features.supports_chat_markers,
         })
     }
 
+    async fn load_room_configuration(
+        &self,
+        room_id: &MucId,
+    ) -> Result<RoomConfiguration, RoomError> {
+        let muc_mod = self.client.get_mod::<mods::MUC>();
+        let mut configuration = None;
+
+        // We're only interested in the form, so we'll cancel the configuration right away…
+        muc_mod
+            .configure_room(room_id, |form: DataForm| {
+                configuration = Some(RoomConfiguration::try_from(&form));
+                Box::pin(async { Ok(RoomConfigResponse::Cancel) })
+            })
+            .await?;
+
+        let Some(configuration) = configuration else {
+            return Err(RequestError::UnexpectedResponse.into());
+        };
+        Ok(configuration?)
+    }
+
+    async fn update_room_configuration(
+        &self,
+        room_id: &MucId,
+        configuration: RoomConfiguration,
+    ) -> Result<(), RoomError> {
+        let muc_mod = self.client.get_mod::<mods::MUC>();
+        muc_mod
+            .configure_room(room_id, |form: DataForm| {
+                let response = configuration.populate_form(&form);
+                Box::pin(async move { Ok(RoomConfigResponse::Submit(response?)) })
+            })
+            .await?;
+        Ok(())
+    }
+
     async fn exit_room(&self, occupant_id: &OccupantId) -> Result<(), RoomError> {
         let muc_mod = self.client.get_mod::<mods::MUC>();
         muc_mod.exit_room(occupant_id.as_ref()).await?;
@@ -250,25 +291,18 @@ impl RoomManagementService for XMPPClient {
         &self,
         room_id: &MucId,
         alternate_room: Option<MucId>,
+        reason: Option<&str>,
     ) -> Result<(), RoomError> {
         let muc_mod = self.client.get_mod::<mods::MUC>();
         muc_mod
             .destroy_room(
                 room_id,
                 alternate_room.map(|id| id.clone().into_inner()).as_ref(),
+                reason,
             )
             .await?;
         Ok(())
     }
-}
-
-impl XMPPClient {
-    async fn load_room_info(&self, room_id: &MucId) -> Result<RoomInfo, RoomError> {
-        let caps = self.client.get_mod::<mods::Caps>();
-        Ok(RoomInfo::try_from(
-            caps.query_disco_info(room_id.clone(), None).await?,
-        )?)
-    }
 
     async fn load_room_members(&self, jid: &MucId) -> Result<Vec<RoomSessionMember>, RoomError> {
         let muc_mod = self.client.get_mod::<mods::MUC>();
@@ -303,3 +337,12 @@ impl XMPPClient {
         Ok(members)
     }
 }
+
+impl XMPPClient {
+    async fn load_room_info(&self, room_id: &MucId) -> Result<RoomInfo, RoomError> {
+        let caps = self.client.get_mod::<mods::Caps>();
+        Ok(RoomInfo::try_from(
+            caps.query_disco_info(room_id.clone(), None).await?,
+        )?)
+    }
+}
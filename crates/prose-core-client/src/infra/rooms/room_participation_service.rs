@@ -5,14 +5,14 @@
 
 use async_trait::async_trait;
 use jid::Jid;
-use xmpp_parsers::muc::user::Affiliation;
 
 use prose_xmpp::mods;
 use prose_xmpp::stanza::muc::{mediated_invite, MediatedInvite};
 
-use crate::domain::rooms::models::RoomError;
+use crate::domain::messaging::models::MessageServerId;
+use crate::domain::rooms::models::{RoomAffiliation, RoomError, RoomRole};
 use crate::domain::rooms::services::RoomParticipationService;
-use crate::domain::shared::models::MucId;
+use crate::domain::shared::models::{MucId, OccupantId};
 use crate::dtos::UserId;
 use crate::infra::xmpp::XMPPClient;
 
@@ -47,18 +47,82 @@ impl RoomParticipationService for XMPPClient {
         Ok(())
     }
 
-    async fn grant_membership(
+    async fn set_affiliation(
         &self,
         room_id: &MucId,
         participant: &UserId,
+        affiliation: RoomAffiliation,
+        reason: Option<&str>,
     ) -> Result<(), RoomError> {
         let muc_mod = self.client.get_mod::<mods::MUC>();
         muc_mod
-            .update_user_affiliations(
+            .update_user_affiliation(
                 room_id,
-                vec![(participant.clone().into_inner(), Affiliation::Member)],
+                participant.clone().into_inner(),
+                affiliation.into(),
+                reason,
             )
             .await?;
         Ok(())
     }
+
+    async fn set_role(
+        &self,
+        occupant_id: &OccupantId,
+        role: RoomRole,
+        reason: Option<&str>,
+    ) -> Result<(), RoomError> {
+        let muc_mod = self.client.get_mod::<mods::MUC>();
+        muc_mod
+            .update_occupant_role(
+                &occupant_id.muc_id(),
+                occupant_id.nickname(),
+                role.into(),
+                reason,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn load_users_with_affiliation(
+        &self,
+        room_id: &MucId,
+        affiliation: RoomAffiliation,
+    ) -> Result<Vec<UserId>, RoomError> {
+        let muc_mod = self.client.get_mod::<mods::MUC>();
+        let users = muc_mod
+            .request_users(room_id, affiliation.into())
+            .await?
+            .into_iter()
+            .filter_map(|user| {
+                let user_jid = user.jid.to_bare();
+                user_jid.node().is_some().then(|| UserId::from(user_jid))
+            })
+            .collect();
+        Ok(users)
+    }
+
+    async fn moderate_message(
+        &self,
+        room_id: &MucId,
+        stanza_id: &MessageServerId,
+        reason: Option<&str>,
+    ) -> Result<(), RoomError> {
+        let muc_mod = self.client.get_mod::<mods::MUC>();
+        muc_mod
+            .moderate_message(room_id, stanza_id.as_ref(), reason)
+            .await?;
+        Ok(())
+    }
+
+    async fn load_occupants(&self, room_id: &MucId) -> Result<Vec<OccupantId>, RoomError> {
+        let muc_mod = self.client.get_mod::<mods::MUC>();
+        let occupants = muc_mod
+            .load_occupants(room_id.as_ref())
+            .await?
+            .into_iter()
+            .map(OccupantId::from)
+            .collect();
+        Ok(occupants)
+    }
 }
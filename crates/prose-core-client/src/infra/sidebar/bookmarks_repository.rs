@@ -0,0 +1,131 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use async_trait::async_trait;
+use jid::BareJid;
+use serde::{Deserialize, Serialize};
+
+use prose_store::prelude::{Entity, PlatformDriver, Store};
+use prose_store::{
+    define_entity, Database, IndexSpec, IndexedCollection, Query, QueryDirection, ReadTransaction,
+    ReadableCollection, WritableCollection, WriteTransaction,
+};
+
+use crate::domain::shared::models::AccountId;
+use crate::domain::sidebar::models::Bookmark;
+use crate::domain::sidebar::repos::BookmarksRepository as BookmarksRepositoryTrait;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookmarkRecord {
+    id: String,
+    account: AccountId,
+    payload: Bookmark,
+}
+
+impl BookmarkRecord {
+    fn new(account: &AccountId, bookmark: &Bookmark) -> Self {
+        Self {
+            id: Self::id(account, bookmark.jid.as_ref()),
+            account: account.clone(),
+            payload: bookmark.clone(),
+        }
+    }
+
+    fn id(account: &AccountId, room_id: &BareJid) -> String {
+        format!("{}-{}", account, room_id)
+    }
+}
+
+mod columns {
+    pub const ACCOUNT: &str = "account";
+}
+
+define_entity!(BookmarkRecord, "bookmarks",
+    account_idx => { columns: [columns::ACCOUNT], unique: false }
+);
+
+pub struct BookmarksRepository {
+    store: Store<PlatformDriver>,
+}
+
+impl BookmarksRepository {
+    pub fn new(store: Store<PlatformDriver>) -> Self {
+        Self { store }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl BookmarksRepositoryTrait for BookmarksRepository {
+    async fn get_all(&self, account: &AccountId) -> Result<Vec<Bookmark>> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[BookmarkRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(BookmarkRecord::collection())?;
+        let idx = collection.index(&BookmarkRecord::account_idx())?;
+        let bookmarks = idx
+            .get_all_values::<BookmarkRecord>(Query::Only(account), QueryDirection::Forward, None)
+            .await?
+            .into_iter()
+            .map(|record| record.payload)
+            .collect();
+        Ok(bookmarks)
+    }
+
+    async fn replace_all(&self, account: &AccountId, bookmarks: &[Bookmark]) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[BookmarkRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(BookmarkRecord::collection())?;
+        collection
+            .delete_all_in_index(&BookmarkRecord::account_idx(), Query::Only(account))
+            .await?;
+        for bookmark in bookmarks {
+            collection.put_entity(&BookmarkRecord::new(account, bookmark))?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn put(&self, account: &AccountId, bookmark: &Bookmark) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[BookmarkRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(BookmarkRecord::collection())?;
+        collection.put_entity(&BookmarkRecord::new(account, bookmark))?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete(&self, account: &AccountId, room_id: &BareJid) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[BookmarkRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(BookmarkRecord::collection())?;
+        collection
+            .delete(&BookmarkRecord::id(account, room_id))
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn clear_cache(&self, account: &AccountId) -> Result<()> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[BookmarkRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(BookmarkRecord::collection())?;
+        collection
+            .delete_all_in_index(&BookmarkRecord::account_idx(), Query::Only(account))
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -3,4 +3,7 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+pub use bookmarks_repository::{BookmarkRecord, BookmarksRepository};
+
+mod bookmarks_repository;
 mod bookmarks_service;
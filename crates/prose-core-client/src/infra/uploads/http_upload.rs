@@ -0,0 +1,100 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use mime::Mime;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use tokio::io::AsyncReadExt;
+use url::Url;
+
+use crate::domain::uploads::models::{UploadError, UploadHeader, UploadSource};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Uploads `source` to `upload_url` via HTTP PUT (XEP-0363) and calls `progress` with the number
+/// of bytes sent so far and the total number of bytes after each chunk.
+pub async fn upload_file(
+    upload_url: &Url,
+    upload_headers: &[UploadHeader],
+    media_type: &Mime,
+    file_size: u64,
+    source: UploadSource,
+    progress: Arc<dyn Fn(u64, u64) + Send + Sync>,
+) -> Result<(), UploadError> {
+    let mut headers = HeaderMap::new();
+    for header in upload_headers {
+        headers.insert(
+            HeaderName::from_bytes(header.name.as_bytes())
+                .map_err(|err| anyhow!("Invalid upload header name. {err}"))?,
+            HeaderValue::from_str(&header.value)
+                .map_err(|err| anyhow!("Invalid upload header value. {err}"))?,
+        );
+    }
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(file_size));
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_str(media_type.as_ref())
+            .map_err(|err| anyhow!("Invalid media type. {err}"))?,
+    );
+
+    let mut bytes_sent = 0;
+    progress(bytes_sent, file_size);
+
+    let body = chunks(source)
+        .await
+        .map_err(|err| UploadError::Transport(err.to_string()))?
+        .inspect_ok(move |chunk| {
+            bytes_sent += chunk.len() as u64;
+            progress(bytes_sent, file_size);
+        });
+
+    let response = reqwest::Client::new()
+        .put(upload_url.clone())
+        .headers(headers)
+        .body(reqwest::Body::wrap_stream(body))
+        .send()
+        .await
+        .map_err(|err| UploadError::Transport(err.to_string()))?;
+
+    if !response.status().is_success() {
+        return Err(UploadError::UnexpectedStatus {
+            status: response.status().as_u16(),
+        });
+    }
+
+    Ok(())
+}
+
+async fn chunks(
+    source: UploadSource,
+) -> std::io::Result<BoxStream<'static, std::io::Result<Vec<u8>>>> {
+    let stream = match source {
+        UploadSource::Path(path) => {
+            let file = tokio::fs::File::open(path).await?;
+            stream::try_unfold(file, |mut file| async move {
+                let mut chunk = vec![0; CHUNK_SIZE];
+                let len = file.read(&mut chunk).await?;
+                if len == 0 {
+                    return Ok(None);
+                }
+                chunk.truncate(len);
+                Ok(Some((chunk, file)))
+            })
+            .boxed()
+        }
+        UploadSource::Bytes(bytes) => stream::iter(
+            bytes
+                .chunks(CHUNK_SIZE)
+                .map(|chunk| Ok(chunk.to_vec()))
+                .collect::<Vec<_>>(),
+        )
+        .boxed(),
+    };
+    Ok(stream)
+}
@@ -3,4 +3,9 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use http_upload::upload_file;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod http_upload;
 mod upload_service;
@@ -7,13 +7,17 @@ use anyhow::Result;
 use async_trait::async_trait;
 use jid::BareJid;
 use mime::Mime;
+use xmpp_parsers::stanza_error::{DefinedCondition, StanzaError};
 
 use prose_xmpp::mods::HttpUpload;
+use prose_xmpp::RequestError;
 
-use crate::domain::uploads::models::UploadSlot;
+use crate::domain::uploads::models::{UploadError, UploadSlot};
 use crate::domain::uploads::services::UploadService;
 use crate::infra::xmpp::XMPPClient;
 
+const NS_HTTP_UPLOAD: &str = "urn:xmpp:http:upload:0";
+
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[async_trait]
 impl UploadService for XMPPClient {
@@ -32,7 +36,34 @@ impl UploadService for XMPPClient {
                 file_size,
                 Some(media_type.as_ref()),
             )
-            .await?;
+            .await
+            .map_err(map_slot_request_error)?;
         Ok(slot_result.try_into()?)
     }
 }
+
+/// Maps the errors defined in https://xmpp.org/extensions/xep-0363.html#errors
+fn map_slot_request_error(error: anyhow::Error) -> anyhow::Error {
+    let Some(RequestError::XMPP { err }) = error.downcast_ref::<RequestError>() else {
+        return error;
+    };
+
+    match err.defined_condition {
+        DefinedCondition::NotAcceptable => UploadError::FileTooLarge {
+            max_file_size: max_file_size(err),
+        }
+        .into(),
+        DefinedCondition::ResourceConstraint => UploadError::QuotaExceeded.into(),
+        _ => error,
+    }
+}
+
+fn max_file_size(err: &StanzaError) -> Option<u64> {
+    err.other
+        .as_ref()
+        .filter(|elem| elem.is("file-too-large", NS_HTTP_UPLOAD))?
+        .get_child("max-file-size", NS_HTTP_UPLOAD)?
+        .text()
+        .parse()
+        .ok()
+}
@@ -0,0 +1,36 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::io::Write;
+
+use anyhow::Result;
+use image::codecs::jpeg::JpegEncoder;
+use image::{guess_format, DynamicImage, ImageFormat};
+
+const JPEG_QUALITY: u8 = 94;
+
+/// Decodes `data` and scales the image so that it fits into `max_dimensions`.
+pub(super) fn load_thumbnail(
+    data: &[u8],
+    mime_type: Option<&str>,
+    max_dimensions: (u32, u32),
+) -> Result<DynamicImage> {
+    let format = match mime_type.and_then(ImageFormat::from_mime_type) {
+        Some(format) => format,
+        None => guess_format(data)?,
+    };
+    Ok(image::load_from_memory_with_format(data, format)?
+        .thumbnail(max_dimensions.0, max_dimensions.1))
+}
+
+/// Encodes `img` as JPEG into `writer`.
+pub(super) fn write_jpeg(img: DynamicImage, writer: impl Write) -> Result<()> {
+    let encoder = JpegEncoder::new_with_quality(writer, JPEG_QUALITY);
+
+    // Sometimes we encounter e.g. rgb16 pngs and image-rs complains that the JPEG encoder
+    // cannot save these, so we convert the image to rgb8.
+    img.into_rgb8().write_with_encoder(encoder)?;
+    Ok(())
+}
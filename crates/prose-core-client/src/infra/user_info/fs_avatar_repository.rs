@@ -9,18 +9,22 @@ use std::{fs, io};
 use anyhow::Result;
 use async_trait::async_trait;
 use base64::DecodeError;
-use image::codecs::jpeg::JpegEncoder;
-use image::{guess_format, ImageError, ImageFormat};
+use chrono::{DateTime, Utc};
+use image::ImageError;
 use thiserror::Error;
 
 use prose_xmpp::mods::AvatarData;
 
-use crate::domain::shared::models::{AccountId, AvatarId, ParticipantIdRef};
-use crate::domain::user_info::models::{AvatarInfo, PlatformImage};
+use crate::domain::shared::models::{AccountId, AvatarId, ParticipantIdRef, StoreUsage};
+use crate::domain::user_info::models::{AvatarInfo, AvatarSize, PlatformImage};
 use crate::domain::user_info::repos::AvatarRepository;
 
+use super::avatar_image::{load_thumbnail, write_jpeg};
 use super::MAX_IMAGE_DIMENSIONS;
 
+/// The extension of the files next to the full-sized avatars that contain their `updated_at`.
+const UPDATED_AT_EXTENSION: &str = "updated_at";
+
 pub struct FsAvatarRepository {
     path: PathBuf,
 }
@@ -51,80 +55,260 @@ pub enum FsAvatarRepositoryError {
 impl AvatarRepository for FsAvatarRepository {
     async fn set(
         &self,
-        _account: &AccountId,
+        account: &AccountId,
         participant_id: ParticipantIdRef<'_>,
         info: &AvatarInfo,
         image_data: &AvatarData,
+        updated_at: DateTime<Utc>,
     ) -> Result<()> {
-        let image_buf_cow = image_data.data()?;
-        let image_buf = image_buf_cow.as_ref();
-        let image_format =
-            ImageFormat::from_mime_type(&info.mime_type).unwrap_or(guess_format(&image_buf)?);
-
-        let img = image::load_from_memory_with_format(&image_buf, image_format)?
-            .thumbnail(MAX_IMAGE_DIMENSIONS.0, MAX_IMAGE_DIMENSIONS.1);
+        let image_buf = image_data.data()?;
+        let img = load_thumbnail(&image_buf, Some(&info.mime_type), MAX_IMAGE_DIMENSIONS)?;
 
-        let output_path = self
-            .path
-            .join(self.filename_for(participant_id, &info.checksum));
+        let output_path =
+            self.filename_for(account, participant_id, &info.checksum, AvatarSize::Full);
 
         if let Some(parent_dir) = output_path.parent() {
             if !parent_dir.exists() {
-                fs::create_dir(parent_dir)?;
+                fs::create_dir_all(parent_dir)?;
             }
         }
 
-        let mut output_file = fs::File::create(&output_path)?;
-        let encoder = JpegEncoder::new_with_quality(&mut output_file, 94);
+        // Remove all variants of the previous avatar, so that they don't get served anymore.
+        self.remove_stale_files(account, participant_id, &info.checksum)?;
 
-        // Sometimes we encounter e.g. rgb16 pngs and image-rs complains that the JPEG encoder
-        // cannot save these, so we convert the image to rgb8.
-        img.into_rgb8().write_with_encoder(encoder)?;
+        write_jpeg(img, fs::File::create(&output_path)?)?;
+        fs::write(
+            output_path.with_extension(UPDATED_AT_EXTENSION),
+            updated_at.to_rfc3339(),
+        )?;
         Ok(())
     }
 
     async fn get(
         &self,
-        _account: &AccountId,
+        account: &AccountId,
         participant_id: ParticipantIdRef<'_>,
         avatar_id: &AvatarId,
+        size: AvatarSize,
     ) -> Result<Option<PlatformImage>> {
-        let path = self.filename_for(participant_id, &avatar_id);
+        let path = self.filename_for(account, participant_id, avatar_id, size);
         if path.exists() {
             return Ok(Some(path));
         }
-        return Ok(None);
+
+        let Some(max_dimension) = size.max_dimension() else {
+            return Ok(None);
+        };
+
+        let full_size_path =
+            self.filename_for(account, participant_id, avatar_id, AvatarSize::Full);
+        if !full_size_path.exists() {
+            return Ok(None);
+        }
+
+        let img = load_thumbnail(
+            &fs::read(&full_size_path)?,
+            Some("image/jpeg"),
+            (max_dimension, max_dimension),
+        )?;
+        write_jpeg(img, fs::File::create(&path)?)?;
+
+        Ok(Some(path))
     }
 
-    async fn clear_cache(&self, _account: &AccountId) -> Result<()> {
-        for entry in fs::read_dir(&self.path)? {
-            let entry = match entry {
-                Ok(entry) => entry,
-                Err(err) => return Err(err.into()),
-            };
-            let metadata = entry.metadata()?;
-            if metadata.is_file()
-                && entry.path().extension().and_then(|ext| ext.to_str()) == Some("jpg")
-            {
-                fs::remove_file(entry.path())?
+    /// Uses the `updated_at` saved next to each full-sized avatar and removes its downscaled
+    /// variants together with it.
+    async fn prune(&self, account: &AccountId, older_than: DateTime<Utc>) -> Result<usize> {
+        let mut pruned_files = 0;
+
+        for (path, _) in self.cached_files(account, UPDATED_AT_EXTENSION)? {
+            let updated_at = DateTime::parse_from_rfc3339(fs::read_to_string(&path)?.trim())?
+                .with_timezone(&Utc);
+
+            if updated_at >= older_than {
+                continue;
+            }
+
+            for size in [AvatarSize::Full, AvatarSize::Medium, AvatarSize::Small] {
+                let image_path = path.with_file_name(format!(
+                    "{}{}.jpg",
+                    path.file_stem().unwrap_or_default().to_string_lossy(),
+                    size_suffix(size)
+                ));
+                if image_path.exists() {
+                    fs::remove_file(image_path)?;
+                    pruned_files += 1;
+                }
+            }
+
+            fs::remove_file(path)?;
+        }
+
+        Ok(pruned_files)
+    }
+
+    /// Uses the modification dates of the files, since these are set when an avatar is saved.
+    async fn evict(&self, account: &AccountId, max_bytes: u64) -> Result<usize> {
+        let mut files = self
+            .cached_files(account, "jpg")?
+            .into_iter()
+            .map(|(path, metadata)| Ok((path, metadata.modified()?, metadata.len())))
+            .collect::<Result<Vec<_>, io::Error>>()?;
+
+        let mut total_bytes = files.iter().map(|(_, _, size)| size).sum::<u64>();
+        files.sort_by_key(|(_, modified, _)| *modified);
+
+        let mut evicted_files = 0;
+        for (path, _, size) in files {
+            if total_bytes <= max_bytes {
+                break;
+            }
+            let updated_at_path = path.with_extension(UPDATED_AT_EXTENSION);
+            if updated_at_path.exists() {
+                fs::remove_file(updated_at_path)?;
             }
+            fs::remove_file(path)?;
+            total_bytes -= size;
+            evicted_files += 1;
+        }
+
+        Ok(evicted_files)
+    }
+
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage> {
+        Ok(self.cached_files(account, "jpg")?.into_iter().fold(
+            StoreUsage::default(),
+            |usage, (_, metadata)| StoreUsage {
+                rows: usage.rows + 1,
+                bytes: usage.bytes + metadata.len(),
+            },
+        ))
+    }
+
+    async fn clear_cache(&self, account: &AccountId) -> Result<()> {
+        let account_dir = self.account_dir(account);
+        if account_dir.exists() {
+            fs::remove_dir_all(account_dir)?;
         }
         Ok(())
     }
 }
 
 impl FsAvatarRepository {
+    /// Returns the directory in which the avatars cached for `account` are saved.
+    fn account_dir(&self, account: &AccountId) -> PathBuf {
+        self.path.join(account.to_string())
+    }
+
+    /// Returns all files with the given extension cached for `account`. Avatars of occupants are
+    /// saved in a subdirectory per room.
+    fn cached_files(
+        &self,
+        account: &AccountId,
+        extension: &str,
+    ) -> Result<Vec<(PathBuf, fs::Metadata)>> {
+        let mut files = vec![];
+        let account_dir = self.account_dir(account);
+
+        if !account_dir.exists() {
+            return Ok(files);
+        }
+
+        let mut dirs = vec![account_dir];
+
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+
+                if metadata.is_dir() {
+                    dirs.push(entry.path());
+                    continue;
+                }
+
+                if entry.path().extension().and_then(|ext| ext.to_str()) == Some(extension) {
+                    files.push((entry.path(), metadata));
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Returns the path of the avatar in the given size. Full-sized avatars are saved as
+    /// `{account}/{id}-{checksum}.jpg` next to `{account}/{id}-{checksum}.updated_at`,
+    /// downscaled variants as `{account}/{id}-{checksum}-{max_dimension}.jpg`.
     fn filename_for(
         &self,
+        account: &AccountId,
         participant_id: ParticipantIdRef<'_>,
         image_checksum: &AvatarId,
+        size: AvatarSize,
     ) -> PathBuf {
+        let suffix = size_suffix(size);
+        let account_dir = self.account_dir(account);
+
         match participant_id {
-            ParticipantIdRef::User(id) => self.path.join(format!("{id}-{image_checksum}.jpg")),
-            ParticipantIdRef::Occupant(id) => self
-                .path
+            ParticipantIdRef::User(id) => {
+                account_dir.join(format!("{id}-{image_checksum}{suffix}.jpg"))
+            }
+            ParticipantIdRef::Occupant(id) => account_dir
                 .join(id.muc_id().as_ref().to_string())
-                .join(format!("{}-{image_checksum}.jpg", id.nickname())),
+                .join(format!("{}-{image_checksum}{suffix}.jpg", id.nickname())),
         }
     }
+
+    /// Deletes the files of all avatars of `participant_id` cached for `account` that don't
+    /// match `current_checksum`.
+    fn remove_stale_files(
+        &self,
+        account: &AccountId,
+        participant_id: ParticipantIdRef<'_>,
+        current_checksum: &AvatarId,
+    ) -> Result<()> {
+        let account_dir = self.account_dir(account);
+        let (dir, prefix) = match participant_id {
+            ParticipantIdRef::User(id) => (account_dir, format!("{id}-")),
+            ParticipantIdRef::Occupant(id) => (
+                account_dir.join(id.muc_id().as_ref().to_string()),
+                format!("{}-", id.nickname()),
+            ),
+        };
+        let current_checksum = current_checksum.to_string();
+
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_name = entry.file_name();
+            let Some(checksum) = file_name
+                .to_str()
+                .and_then(|name| name.strip_prefix(&prefix))
+                .and_then(|name| {
+                    name.strip_suffix(".jpg")
+                        .or_else(|| name.strip_suffix(&format!(".{UPDATED_AT_EXTENSION}")))
+                })
+                .map(|name| name.split_once('-').map_or(name, |(checksum, _)| checksum))
+            else {
+                continue;
+            };
+
+            // Checksums are hex-encoded SHA-1 hashes. This makes sure that we don't delete
+            // the avatars of other participants whose ids begin with the same prefix.
+            if checksum.is_empty() || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+                continue;
+            }
+
+            if checksum != current_checksum {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the suffix of the file name of the given size variant.
+fn size_suffix(size: AvatarSize) -> String {
+    size.max_dimension()
+        .map(|max_dimension| format!("-{max_dimension}"))
+        .unwrap_or_default()
 }
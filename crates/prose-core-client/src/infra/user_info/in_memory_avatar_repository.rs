@@ -0,0 +1,248 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use base64::engine::general_purpose;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+
+use prose_xmpp::mods::AvatarData;
+
+use crate::domain::shared::models::{
+    AccountId, AvatarId, ParticipantId, ParticipantIdRef, StoreUsage,
+};
+use crate::domain::user_info::models::{AvatarInfo, AvatarSize, PlatformImage};
+use crate::domain::user_info::repos::AvatarRepository;
+
+use super::avatar_image::{load_thumbnail, write_jpeg};
+
+/// Keeps avatars in memory instead of writing them to disk. Once the cached avatars exceed
+/// `max_bytes`, the least recently used ones are evicted.
+///
+/// Images are returned as data URLs. Since `PlatformImage` is a file path on non-wasm
+/// targets, the data URL is returned in place of the path there, which makes this repository
+/// suitable for tests but not for apps on these targets.
+pub struct InMemoryAvatarRepository {
+    max_bytes: u64,
+    cache: Mutex<Cache>,
+}
+
+#[derive(Default)]
+struct Cache {
+    // We're only saving one avatar per participant…
+    entries: HashMap<(AccountId, ParticipantId), Entry>,
+    total_bytes: u64,
+    /// Incremented on every access to determine the least recently used entry.
+    clock: u64,
+}
+
+struct Entry {
+    avatar_id: AvatarId,
+    mime_type: String,
+    data: Box<[u8]>,
+    /// JPEG data of the downscaled variants of the avatar.
+    variants: HashMap<AvatarSize, Vec<u8>>,
+    updated_at: DateTime<Utc>,
+    last_access: u64,
+}
+
+impl InMemoryAvatarRepository {
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            max_bytes,
+            cache: Default::default(),
+        }
+    }
+}
+
+impl Entry {
+    fn size(&self) -> u64 {
+        (self.data.len() + self.variants.values().map(Vec::len).sum::<usize>()) as u64
+    }
+
+    fn data_url(&self, size: AvatarSize) -> Option<PlatformImage> {
+        let (mime_type, data) = match size.max_dimension() {
+            None => (self.mime_type.as_str(), self.data.as_ref()),
+            Some(_) => ("image/jpeg", self.variants.get(&size)?.as_slice()),
+        };
+
+        Some(
+            format!(
+                "data:{mime_type};base64,{}",
+                general_purpose::STANDARD.encode(data)
+            )
+            .into(),
+        )
+    }
+}
+
+impl Cache {
+    fn insert(&mut self, key: (AccountId, ParticipantId), mut entry: Entry) {
+        self.clock += 1;
+        entry.last_access = self.clock;
+        self.total_bytes += entry.size();
+
+        if let Some(replaced_entry) = self.entries.insert(key, entry) {
+            self.total_bytes -= replaced_entry.size();
+        }
+    }
+
+    fn remove(&mut self, key: &(AccountId, ParticipantId)) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.total_bytes -= entry.size();
+        }
+    }
+
+    /// Removes the least recently used entries until the cache fits into `max_bytes`. Returns
+    /// the number of removed entries.
+    fn evict(&mut self, max_bytes: u64) -> usize {
+        let mut evicted_entries = 0;
+
+        while self.total_bytes > max_bytes {
+            let Some(key) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_access)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            self.remove(&key);
+            evicted_entries += 1;
+        }
+
+        evicted_entries
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl AvatarRepository for InMemoryAvatarRepository {
+    async fn get(
+        &self,
+        account: &AccountId,
+        participant_id: ParticipantIdRef<'_>,
+        avatar_id: &AvatarId,
+        size: AvatarSize,
+    ) -> Result<Option<PlatformImage>> {
+        let key = (account.clone(), participant_id.to_owned());
+        let mut cache = self.cache.lock();
+        cache.clock += 1;
+        let clock = cache.clock;
+
+        let Some(entry) = cache.entries.get_mut(&key) else {
+            return Ok(None);
+        };
+        if &entry.avatar_id != avatar_id {
+            return Ok(None);
+        }
+        entry.last_access = clock;
+
+        if let Some(image) = entry.data_url(size) {
+            return Ok(Some(image));
+        }
+
+        // The variant is requested for the first time, so we generate and cache it.
+        let Some(max_dimension) = size.max_dimension() else {
+            return Ok(None);
+        };
+        let img = load_thumbnail(
+            &entry.data,
+            Some(&entry.mime_type),
+            (max_dimension, max_dimension),
+        )?;
+        let mut jpeg_data = Vec::new();
+        write_jpeg(img, &mut jpeg_data)?;
+
+        let variant_size = jpeg_data.len() as u64;
+        entry.variants.insert(size, jpeg_data);
+        let image = entry.data_url(size);
+
+        cache.total_bytes += variant_size;
+        cache.evict(self.max_bytes);
+
+        Ok(image)
+    }
+
+    async fn set(
+        &self,
+        account: &AccountId,
+        participant_id: ParticipantIdRef<'_>,
+        metadata: &AvatarInfo,
+        image: &AvatarData,
+        updated_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let entry = Entry {
+            avatar_id: metadata.checksum.clone(),
+            mime_type: metadata.mime_type.clone(),
+            data: image.data()?.into_owned(),
+            variants: Default::default(),
+            updated_at,
+            last_access: 0,
+        };
+
+        let mut cache = self.cache.lock();
+        cache.insert((account.clone(), participant_id.to_owned()), entry);
+        cache.evict(self.max_bytes);
+
+        Ok(())
+    }
+
+    async fn prune(&self, account: &AccountId, older_than: DateTime<Utc>) -> Result<usize> {
+        let mut cache = self.cache.lock();
+        let stale_keys = cache
+            .entries
+            .iter()
+            .filter(|((entry_account, _), entry)| {
+                entry_account == account && entry.updated_at < older_than
+            })
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for key in &stale_keys {
+            cache.remove(key);
+        }
+
+        Ok(stale_keys.len())
+    }
+
+    /// Evicts the least recently used avatars of all accounts, since they share the budget.
+    async fn evict(&self, _account: &AccountId, max_bytes: u64) -> Result<usize> {
+        Ok(self.cache.lock().evict(max_bytes))
+    }
+
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage> {
+        Ok(self
+            .cache
+            .lock()
+            .entries
+            .iter()
+            .filter(|((entry_account, _), _)| entry_account == account)
+            .fold(StoreUsage::default(), |usage, (_, entry)| StoreUsage {
+                rows: usage.rows + 1,
+                bytes: usage.bytes + entry.size(),
+            }))
+    }
+
+    async fn clear_cache(&self, account: &AccountId) -> Result<()> {
+        let mut cache = self.cache.lock();
+        let keys = cache
+            .entries
+            .keys()
+            .filter(|(entry_account, _)| entry_account == account)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for key in &keys {
+            cache.remove(key);
+        }
+
+        Ok(())
+    }
+}
@@ -71,6 +71,8 @@ impl UserInfoRepository for InMemoryUserInfoRepository {
             .unwrap_or_default();
 
         user_info.availability = presence.availability;
+        user_info.status_message = presence.status;
+        user_info.idle_since = presence.idle_since;
 
         Ok(Some(user_info))
     }
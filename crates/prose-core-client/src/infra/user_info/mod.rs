@@ -3,6 +3,7 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+pub use in_memory_avatar_repository::InMemoryAvatarRepository;
 pub use in_memory_user_info_repository::InMemoryUserInfoRepository;
 pub(self) use presence_map::PresenceMap;
 pub use user_profile_repository::{UserProfileRecord, UserProfileRepository};
@@ -12,6 +13,8 @@ pub use fs_avatar_repository::*;
 #[cfg(target_arch = "wasm32")]
 pub use store_avatar_repository::*;
 
+mod avatar_image;
+mod in_memory_avatar_repository;
 mod in_memory_user_info_repository;
 mod presence_map;
 mod user_info_service;
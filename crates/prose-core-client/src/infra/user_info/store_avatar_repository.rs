@@ -3,18 +3,27 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::HashMap;
+
 use anyhow::Result;
 use async_trait::async_trait;
+use base64::engine::general_purpose;
+use base64::Engine;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use prose_store::prelude::*;
 use prose_store::{define_entity, RawKey};
 use prose_xmpp::mods::AvatarData;
 
-use crate::domain::shared::models::{AccountId, AvatarId, ParticipantId, ParticipantIdRef};
-use crate::domain::user_info::models::{AvatarInfo, PlatformImage};
+use crate::domain::shared::models::{
+    AccountId, AvatarId, ParticipantId, ParticipantIdRef, StoreUsage,
+};
+use crate::domain::user_info::models::{AvatarInfo, AvatarSize, PlatformImage};
 use crate::domain::user_info::repos::AvatarRepository;
 
+use super::avatar_image::{load_thumbnail, write_jpeg};
+
 pub struct StoreAvatarRepository {
     store: Store<PlatformDriver>,
 }
@@ -33,6 +42,13 @@ pub struct AvatarRecord {
     avatar_id: AvatarId,
     mime_type: String,
     base64_data: String,
+    /// Base64-encoded JPEG data of the downscaled variants of the avatar. Since a record is
+    /// replaced when a new avatar is saved, the variants of stale avatars are dropped with it.
+    #[serde(default)]
+    variants: HashMap<AvatarSize, String>,
+    /// Records written before this field was introduced are treated as expired.
+    #[serde(default)]
+    updated_at: Option<DateTime<Utc>>,
 }
 
 impl AvatarRecord {
@@ -41,6 +57,7 @@ impl AvatarRecord {
         participant_id: ParticipantId,
         image: &AvatarData,
         metadata: &AvatarInfo,
+        updated_at: DateTime<Utc>,
     ) -> Self {
         Self {
             id: format!("{account}.{}", participant_id.to_ref().to_raw_key_string()),
@@ -49,7 +66,46 @@ impl AvatarRecord {
             avatar_id: metadata.checksum.clone(),
             mime_type: metadata.mime_type.clone(),
             base64_data: image.base64().to_string(),
+            variants: Default::default(),
+            updated_at: Some(updated_at),
+        }
+    }
+
+    /// The number of bytes the record takes up in the store. Used by both `evict` and `usage`,
+    /// so that evicting down to the reported usage doesn't delete anything.
+    fn stored_size(&self) -> u64 {
+        serde_json::to_string(self).map_or(0, |json| json.len() as u64)
+    }
+
+    fn data_url(&self, size: AvatarSize) -> Option<PlatformImage> {
+        if size.max_dimension().is_none() {
+            return Some(format!(
+                "data:{};base64,{}",
+                self.mime_type, self.base64_data
+            ));
         }
+        self.variants
+            .get(&size)
+            .map(|data| format!("data:image/jpeg;base64,{data}"))
+    }
+
+    fn generate_variant(&mut self, size: AvatarSize) -> Result<()> {
+        let Some(max_dimension) = size.max_dimension() else {
+            return Ok(());
+        };
+
+        let img = load_thumbnail(
+            &general_purpose::STANDARD.decode(&self.base64_data)?,
+            Some(&self.mime_type),
+            (max_dimension, max_dimension),
+        )?;
+
+        let mut jpeg_data = Vec::new();
+        write_jpeg(img, &mut jpeg_data)?;
+        self.variants
+            .insert(size, general_purpose::STANDARD.encode(jpeg_data));
+
+        Ok(())
     }
 }
 
@@ -80,18 +136,48 @@ impl AvatarRepository for StoreAvatarRepository {
         account: &AccountId,
         participant_id: ParticipantIdRef<'_>,
         avatar_id: &AvatarId,
-    ) -> anyhow::Result<Option<PlatformImage>> {
+        size: AvatarSize,
+    ) -> Result<Option<PlatformImage>> {
+        let record = {
+            let tx = self
+                .store
+                .transaction_for_reading(&[AvatarRecord::collection()])
+                .await?;
+            let collection = tx.readable_collection(AvatarRecord::collection())?;
+            let idx = collection.index(&AvatarRecord::avatar_idx())?;
+            idx.get::<_, AvatarRecord>(&(account, participant_id, &avatar_id))
+                .await?
+        };
+
+        let Some(mut record) = record else {
+            return Ok(None);
+        };
+
+        if let Some(image) = record.data_url(size) {
+            return Ok(Some(image));
+        }
+
+        // The variant is requested for the first time, so we generate and cache it.
+        record.generate_variant(size)?;
+
         let tx = self
             .store
-            .transaction_for_reading(&[AvatarRecord::collection()])
+            .transaction_for_reading_and_writing(&[AvatarRecord::collection()])
             .await?;
-        let collection = tx.readable_collection(AvatarRecord::collection())?;
+        let collection = tx.writeable_collection(AvatarRecord::collection())?;
         let idx = collection.index(&AvatarRecord::avatar_idx())?;
 
-        return Ok(idx
+        // Don't resurrect the avatar if it was replaced in the meantime.
+        if idx
             .get::<_, AvatarRecord>(&(account, participant_id, &avatar_id))
             .await?
-            .map(|record| format!("data:{};base64,{}", record.mime_type, record.base64_data)));
+            .is_some()
+        {
+            collection.put_entity(&record)?;
+        }
+        tx.commit().await?;
+
+        Ok(record.data_url(size))
     }
 
     async fn set(
@@ -100,6 +186,7 @@ impl AvatarRepository for StoreAvatarRepository {
         participant_id: ParticipantIdRef<'_>,
         metadata: &AvatarInfo,
         image: &AvatarData,
+        updated_at: DateTime<Utc>,
     ) -> Result<()> {
         let tx = self
             .store
@@ -111,11 +198,96 @@ impl AvatarRepository for StoreAvatarRepository {
             participant_id.to_owned(),
             image,
             metadata,
+            updated_at,
         ))?;
         tx.commit().await?;
         Ok(())
     }
 
+    async fn prune(&self, account: &AccountId, older_than: DateTime<Utc>) -> Result<usize> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[AvatarRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(AvatarRecord::collection())?;
+        let stale_ids = collection
+            .index(&AvatarRecord::account_idx())?
+            .get_all_filtered(
+                Query::Only(account),
+                QueryDirection::Forward,
+                None,
+                |_, record: AvatarRecord| {
+                    (record.updated_at.map_or(true, |date| date < older_than)).then_some(record.id)
+                },
+            )
+            .await?;
+
+        for id in &stale_ids {
+            collection.delete(id).await?;
+        }
+        tx.commit().await?;
+
+        Ok(stale_ids.len())
+    }
+
+    async fn evict(&self, account: &AccountId, max_bytes: u64) -> Result<usize> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[AvatarRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(AvatarRecord::collection())?;
+        let mut records = collection
+            .index(&AvatarRecord::account_idx())?
+            .get_all_filtered(
+                Query::Only(account),
+                QueryDirection::Forward,
+                None,
+                |_, record: AvatarRecord| {
+                    let size = record.stored_size();
+                    Some((record.id, record.updated_at, size))
+                },
+            )
+            .await?;
+
+        let mut total_bytes = records.iter().map(|(_, _, size)| size).sum::<u64>();
+        // Records without an `updated_at` were written before the field was introduced, so
+        // they're the oldest and sort first.
+        records.sort_by_key(|(_, updated_at, _)| *updated_at);
+
+        let mut evicted_records = 0;
+        for (id, _, size) in records {
+            if total_bytes <= max_bytes {
+                break;
+            }
+            collection.delete(&id).await?;
+            total_bytes -= size;
+            evicted_records += 1;
+        }
+        tx.commit().await?;
+
+        Ok(evicted_records)
+    }
+
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[AvatarRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(AvatarRecord::collection())?;
+        let usage = collection
+            .index(&AvatarRecord::account_idx())?
+            .fold(
+                Query::Only(account),
+                StoreUsage::default(),
+                |usage, (_, record): (String, AvatarRecord)| StoreUsage {
+                    rows: usage.rows + 1,
+                    bytes: usage.bytes + record.stored_size(),
+                },
+            )
+            .await?;
+        Ok(usage)
+    }
+
     async fn clear_cache(&self, account: &AccountId) -> Result<()> {
         let tx = self
             .store
@@ -11,6 +11,7 @@ use chrono::{DateTime, Duration, Utc};
 use jid::Jid;
 use tracing::warn;
 use xmpp_parsers::hashes::Sha1HexAttribute;
+use xmpp_parsers::stanza_error::DefinedCondition;
 
 use prose_xmpp::mods::AvatarData;
 use prose_xmpp::{mods, RequestError};
@@ -86,4 +87,34 @@ impl UserInfoService for XMPPClient {
 
         Ok(Some(metadata))
     }
+
+    async fn load_last_activity(
+        &self,
+        from: &UserId,
+        now: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, RequestError> {
+        let profile = self.client.get_mod::<mods::Profile>();
+
+        match profile
+            .load_last_activity(Jid::from(from.clone().into_inner()))
+            .await
+        {
+            Ok(last_activity) => Ok(Some(now - Duration::seconds(last_activity.seconds as i64))),
+            Err(err)
+                if matches!(
+                    err.defined_condition(),
+                    Some(
+                        DefinedCondition::Forbidden
+                            | DefinedCondition::ItemNotFound
+                            | DefinedCondition::ServiceUnavailable
+                            | DefinedCondition::FeatureNotImplemented
+                    )
+                ) =>
+            {
+                warn!("Could not load the last activity of {from}: {err}");
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
 }
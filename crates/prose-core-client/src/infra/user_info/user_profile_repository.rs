@@ -5,11 +5,12 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use prose_store::prelude::*;
 
-use crate::domain::shared::models::{AccountId, ParticipantId, ParticipantIdRef};
+use crate::domain::shared::models::{AccountId, ParticipantId, ParticipantIdRef, StoreUsage};
 use crate::domain::user_info::models::UserProfile;
 use crate::domain::user_info::repos::UserProfileRepository as UserProfileRepositoryTrait;
 
@@ -19,6 +20,9 @@ pub struct UserProfileRecord {
     account: AccountId,
     participant_id: ParticipantId,
     payload: UserProfile,
+    /// Records written before this field was introduced are treated as expired.
+    #[serde(default)]
+    updated_at: Option<DateTime<Utc>>,
 }
 
 impl UserProfileRecord {
@@ -26,12 +30,14 @@ impl UserProfileRecord {
         account: &AccountId,
         participant_id: ParticipantIdRef<'_>,
         payload: UserProfile,
+        updated_at: DateTime<Utc>,
     ) -> Self {
         Self {
             id: format!("{account}.{}", participant_id.to_raw_key_string()),
             account: account.clone(),
             participant_id: participant_id.to_owned(),
             payload,
+            updated_at: Some(updated_at),
         }
     }
 }
@@ -81,6 +87,7 @@ impl UserProfileRepositoryTrait for UserProfileRepository {
         account: &AccountId,
         participant_id: ParticipantIdRef<'_>,
         profile: Option<&UserProfile>,
+        updated_at: DateTime<Utc>,
     ) -> Result<()> {
         let tx = self
             .store
@@ -93,6 +100,7 @@ impl UserProfileRepositoryTrait for UserProfileRepository {
                 account,
                 participant_id,
                 profile.clone(),
+                updated_at,
             ))?;
         } else {
             let idx = collection.index(&UserProfileRecord::user_idx())?;
@@ -103,6 +111,45 @@ impl UserProfileRepositoryTrait for UserProfileRepository {
         Ok(())
     }
 
+    async fn prune(&self, account: &AccountId, older_than: DateTime<Utc>) -> Result<usize> {
+        let tx = self
+            .store
+            .transaction_for_reading_and_writing(&[UserProfileRecord::collection()])
+            .await?;
+        let collection = tx.writeable_collection(UserProfileRecord::collection())?;
+        let stale_ids = collection
+            .index(&UserProfileRecord::account_idx())?
+            .get_all_filtered(
+                Query::Only(account),
+                QueryDirection::Forward,
+                None,
+                |_, record: UserProfileRecord| {
+                    (record.updated_at.map_or(true, |date| date < older_than)).then_some(record.id)
+                },
+            )
+            .await?;
+
+        for id in &stale_ids {
+            collection.delete(id).await?;
+        }
+        tx.commit().await?;
+
+        Ok(stale_ids.len())
+    }
+
+    async fn usage(&self, account: &AccountId) -> Result<StoreUsage> {
+        let tx = self
+            .store
+            .transaction_for_reading(&[UserProfileRecord::collection()])
+            .await?;
+        let collection = tx.readable_collection(UserProfileRecord::collection())?;
+        let usage = collection
+            .index(&UserProfileRecord::account_idx())?
+            .usage(Query::Only(account))
+            .await?;
+        Ok(usage.into())
+    }
+
     async fn reset_before_reconnect(&self, _account: &AccountId) -> Result<()> {
         Ok(())
     }
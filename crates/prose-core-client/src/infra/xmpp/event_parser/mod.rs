@@ -26,6 +26,7 @@ use crate::app::event_handlers::{
 };
 use crate::app::event_handlers::{SidebarBookmarkEvent, XMPPEvent};
 use crate::domain::contacts::models::PresenceSubscription;
+use crate::domain::messaging::models::MessageId;
 use crate::domain::rooms::models::ComposeState;
 use crate::domain::shared::models::{CapabilitiesId, MucId, RequestId, SenderId, UserEndpointId};
 use crate::dtos::{UserId, UserResourceId};
@@ -156,6 +157,7 @@ fn parse_muc_event(ctx: &mut Context, event: XMPPMUCEvent) -> Result<()> {
                 r#type: RoomEventType::ReceivedInvitation {
                     sender: UserResourceId::from(from),
                     password: invite.password,
+                    reason: invite.reason,
                 },
             })
         }
@@ -178,6 +180,7 @@ fn parse_muc_event(ctx: &mut Context, event: XMPPMUCEvent) -> Result<()> {
                 r#type: RoomEventType::ReceivedInvitation {
                     sender: UserResourceId::from(sender_jid.clone()),
                     password: invite.password,
+                    reason: embedded_invite.reason,
                 },
             })
         }
@@ -260,11 +263,25 @@ fn parse_ping_event(ctx: &mut Context, event: XMPPPingEvent) -> Result<()> {
 
 fn parse_client_event(ctx: &mut Context, event: XMPPClientEvent) -> Result<()> {
     match event {
-        XMPPClientEvent::Connected => ctx.push_event(ConnectionEvent::Connected),
+        XMPPClientEvent::Connected { session } => {
+            ctx.push_event(ConnectionEvent::Connected { session })
+        }
         XMPPClientEvent::Disconnected { error } => {
             ctx.push_event(ConnectionEvent::Disconnected { error })
         }
         XMPPClientEvent::PingTimer => ctx.push_event(ConnectionEvent::PingTimer),
+        XMPPClientEvent::PingAnswered { round_trip_time } => {
+            ctx.push_event(ConnectionEvent::PingAnswered { round_trip_time })
+        }
+        XMPPClientEvent::PingTimeout => ctx.push_event(ConnectionEvent::PingTimeout),
+        XMPPClientEvent::MessagesAcknowledged { ids } => {
+            ctx.push_event(ConnectionEvent::MessagesAcknowledged {
+                message_ids: ids
+                    .into_iter()
+                    .map(|id| MessageId::from(id.into_inner()))
+                    .collect(),
+            })
+        }
     }
 
     Ok(())
@@ -278,10 +295,18 @@ fn parse_roster_event(ctx: &mut Context, event: XMPPRosterEvent) -> Result<()> {
                 r#type: ContactListEventType::PresenceSubscriptionRequested { nickname },
             })
         }
+        XMPPRosterEvent::PresenceSubscriptionWithdrawn { from } => {
+            ctx.push_event(ContactListEvent {
+                contact_id: UserId::from(from),
+                r#type: ContactListEventType::PresenceSubscriptionWithdrawn,
+            })
+        }
         XMPPRosterEvent::RosterItemChanged { item } => {
             let event_type = match &item.subscription {
                 Subscription::Remove => ContactListEventType::ContactRemoved,
-                _ => ContactListEventType::ContactAddedOrPresenceSubscriptionUpdated {
+                _ => ContactListEventType::ContactAddedOrUpdated {
+                    name: item.name.clone(),
+                    groups: item.groups.iter().map(|group| group.0.clone()).collect(),
                     subscription: PresenceSubscription::from(&item),
                 },
             };
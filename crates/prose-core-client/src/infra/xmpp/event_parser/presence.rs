@@ -14,6 +14,7 @@ use crate::app::event_handlers::{
     OccupantEvent, OccupantEventType, RoomEvent, RoomEventType, UserStatusEvent,
     UserStatusEventType,
 };
+use crate::domain::rooms::models::RoomRemovalReason;
 use crate::domain::shared::models::{MucId, OccupantId, UserEndpointId};
 use crate::dtos::{Availability, UserId, UserResourceId};
 use crate::infra::xmpp::event_parser::{missing_attribute, missing_element, Context};
@@ -83,24 +84,19 @@ fn parse_muc_presence(
     });
 
     if availability == Availability::Unavailable {
-        if muc_user
-            .status
-            .iter()
-            .find(|s| match s {
-                Status::Banned
-                | Status::Kicked
-                | Status::RemovalFromRoom
-                | Status::ConfigMembersOnly => true,
-                _ => false,
-            })
-            .is_some()
-        {
+        if let Some(reason) = muc_user.status.iter().find_map(|s| match s {
+            Status::Banned => Some(RoomRemovalReason::Banned),
+            Status::Kicked => Some(RoomRemovalReason::Kicked),
+            Status::RemovalFromRoom => Some(RoomRemovalReason::MembershipRevoked),
+            Status::ConfigMembersOnly => Some(RoomRemovalReason::MembersOnly),
+            _ => None,
+        }) {
             ctx.push_event(OccupantEvent {
                 occupant_id,
                 anon_occupant_id,
                 real_id,
                 is_self: is_self_presence,
-                r#type: OccupantEventType::PermanentlyRemoved,
+                r#type: OccupantEventType::PermanentlyRemoved { reason },
             });
             return Ok(());
         }
@@ -137,6 +133,7 @@ fn parse_muc_presence(
         real_id,
         r#type: OccupantEventType::AffiliationChanged {
             affiliation: item.affiliation.clone().into(),
+            role: item.role.clone().into(),
         },
         is_self: is_self_presence,
     });
@@ -13,7 +13,7 @@ use crate::domain::rooms::models::RoomSidebarState;
 use prose_xmpp::{ElementExt, ParseError};
 
 use crate::domain::shared::models::{MucId, RoomId};
-use crate::domain::sidebar::models::{Bookmark, BookmarkType};
+use crate::domain::sidebar::models::{Bookmark, BookmarkInvitation, BookmarkType};
 use crate::dtos::UserId;
 
 pub mod ns {
@@ -49,11 +49,24 @@ impl TryFrom<Element> for Bookmark {
             }
         };
 
+        let invitation = value
+            .get_child("invitation", ns::PROSE_BOOKMARK)
+            .map(|invitation| -> Result<_, ParseError> {
+                Ok(BookmarkInvitation {
+                    sender: UserId::from(BareJid::from_str(&invitation.attr_req("from")?)?),
+                    password: invitation.attr("password").map(ToString::to_string),
+                    reason: invitation.attr("reason").map(ToString::to_string),
+                })
+            })
+            .transpose()?;
+
         Ok(Self {
             name: value.attr_req("name")?.to_string(),
             jid: room_id,
             r#type: bookmark_type,
             sidebar_state,
+            order_index: value.attr("order").map(u32::from_str).transpose()?,
+            invitation,
         })
     }
 }
@@ -72,6 +85,15 @@ impl From<Bookmark> for Element {
                 "sidebar",
                 value.sidebar_state.is_in_sidebar().then_some("1"),
             )
+            .attr("order", value.order_index)
+            .attr("autojoin", value.invitation.is_some().then_some("0"))
+            .append_all(value.invitation.map(|invitation| {
+                Element::builder("invitation", ns::PROSE_BOOKMARK)
+                    .attr("from", invitation.sender)
+                    .attr("password", invitation.password)
+                    .attr("reason", invitation.reason)
+                    .build()
+            }))
             .build()
     }
 }
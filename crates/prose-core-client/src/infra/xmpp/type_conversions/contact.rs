@@ -14,6 +14,11 @@ impl From<Item> for Contact {
         Contact {
             id: roster_item.jid.into(),
             name: roster_item.name,
+            groups: roster_item
+                .groups
+                .into_iter()
+                .map(|group| group.0)
+                .collect(),
             presence_subscription,
         }
     }
@@ -7,11 +7,13 @@ use minidom::Element;
 
 use prose_xmpp::{ElementExt, ParseError};
 
-use crate::domain::messaging::models::{ArchivedMessageRef, MessageRef};
+use crate::domain::messaging::models::{ArchivedMessageRef, MessageRef, PinnedMessageRef};
+use crate::domain::shared::models::UserId;
 
 pub mod ns {
     pub const PROSE_ARCHIVED_MESSAGE_REF: &str = "https://prose.org/protocol/archived_message_ref";
     pub const PROSE_MESSAGE_REF: &str = "https://prose.org/protocol/message_ref";
+    pub const PROSE_PINNED_MESSAGE_REF: &str = "https://prose.org/protocol/pinned_message_ref";
 }
 
 impl From<ArchivedMessageRef> for Element {
@@ -63,3 +65,35 @@ impl TryFrom<Element> for MessageRef {
         })
     }
 }
+
+impl From<PinnedMessageRef> for Element {
+    fn from(value: PinnedMessageRef) -> Self {
+        Element::builder("pinned-message-ref", ns::PROSE_PINNED_MESSAGE_REF)
+            .attr("id", value.remote_id)
+            .attr("stanza-id", value.stanza_id)
+            .attr("ts", value.timestamp.to_rfc3339())
+            .attr("by", value.pinned_by.to_string())
+            .build()
+    }
+}
+
+impl TryFrom<Element> for PinnedMessageRef {
+    type Error = ParseError;
+
+    fn try_from(value: Element) -> Result<Self, Self::Error> {
+        Ok(Self {
+            remote_id: value.attr("id").map(Into::into),
+            stanza_id: value.attr("stanza-id").map(Into::into),
+            timestamp: value
+                .attr_req("ts")?
+                .parse()
+                .map_err(|err: chrono::ParseError| ParseError::Generic {
+                    msg: err.to_string(),
+                })?,
+            pinned_by: value
+                .attr_req("by")?
+                .parse::<UserId>()
+                .map_err(ParseError::from)?,
+        })
+    }
+}
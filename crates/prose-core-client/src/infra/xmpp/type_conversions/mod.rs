@@ -15,7 +15,9 @@ pub(crate) mod encrypted_payload;
 pub(crate) mod mention;
 pub(crate) mod message_ref;
 pub(crate) mod room_affiliation;
+pub(crate) mod room_configuration;
 pub(crate) mod room_info;
+pub(crate) mod room_role;
 pub(crate) mod room_session_participant;
 pub(crate) mod room_spec;
 pub(crate) mod stanza_error;
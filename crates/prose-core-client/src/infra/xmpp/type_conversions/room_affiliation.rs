@@ -18,3 +18,15 @@ impl From<Affiliation> for RoomAffiliation {
         }
     }
 }
+
+impl From<RoomAffiliation> for Affiliation {
+    fn from(value: RoomAffiliation) -> Self {
+        match value {
+            RoomAffiliation::Owner => Affiliation::Owner,
+            RoomAffiliation::Admin => Affiliation::Admin,
+            RoomAffiliation::Member => Affiliation::Member,
+            RoomAffiliation::Outcast => Affiliation::Outcast,
+            RoomAffiliation::None => Affiliation::None,
+        }
+    }
+}
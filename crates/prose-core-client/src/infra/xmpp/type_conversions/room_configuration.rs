@@ -0,0 +1,183 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use xmpp_parsers::data_forms::{DataForm, DataFormType};
+
+use prose_xmpp::stanza::muc::ns::roomconfig as cfg;
+use prose_xmpp::{ns, parse_bool, ParseError};
+
+use crate::domain::rooms::models::RoomConfiguration;
+use crate::util::form_config::{FormValue, Value};
+use crate::util::{form_config, FormConfig};
+
+impl TryFrom<&DataForm> for RoomConfiguration {
+    type Error = ParseError;
+
+    fn try_from(form: &DataForm) -> Result<Self, Self::Error> {
+        let mut result = RoomConfiguration::default();
+
+        for field in &form.fields {
+            let Some(var) = &field.var else { continue };
+            let value = field.values.first().map(String::as_str);
+            let bool_value = || {
+                value
+                    .map(parse_bool)
+                    .transpose()
+                    .map(Option::unwrap_or_default)
+            };
+
+            match var.as_str() {
+                cfg::MEMBERS_ONLY => result.members_only = bool_value()?,
+                cfg::MODERATED_ROOM => result.moderated = bool_value()?,
+                cfg::PERSISTENT_ROOM => result.persistent = bool_value()?,
+                cfg::ALLOW_INVITES => result.allow_invites = bool_value()?,
+                cfg::MAX_HISTORY_FETCH => {
+                    result.max_history_fetch = value.and_then(|value| value.parse().ok())
+                }
+                cfg::ROOM_SECRET => {
+                    result.password = value.filter(|value| !value.is_empty()).map(Into::into)
+                }
+                _ => (),
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+impl RoomConfiguration {
+    /// Fills out the configuration `form` sent by the server. Fields which aren't covered by
+    /// `RoomConfiguration` and fields without a value keep the values the server sent.
+    pub fn populate_form(&self, form: &DataForm) -> Result<DataForm, form_config::Error> {
+        let mut form_values = vec![
+            FormValue::optional(cfg::MEMBERS_ONLY, Value::Boolean(self.members_only)),
+            FormValue::optional(cfg::MODERATED_ROOM, Value::Boolean(self.moderated)),
+            FormValue::optional(cfg::PERSISTENT_ROOM, Value::Boolean(self.persistent)),
+            FormValue::optional(cfg::ALLOW_INVITES, Value::Boolean(self.allow_invites)),
+            FormValue::optional(
+                cfg::ALLOW_MEMBER_INVITES,
+                Value::Boolean(self.allow_invites),
+            ),
+            FormValue::optional(
+                cfg::PASSWORD_PROTECTED_ROOM,
+                Value::Boolean(self.password.is_some()),
+            ),
+        ];
+
+        if let Some(max_history_fetch) = self.max_history_fetch {
+            form_values.push(FormValue::optional(
+                cfg::MAX_HISTORY_FETCH,
+                Value::TextSingle(max_history_fetch.to_string()),
+            ));
+        }
+
+        if let Some(password) = &self.password {
+            form_values.push(FormValue::optional(
+                cfg::ROOM_SECRET,
+                Value::TextSingle(password.clone()),
+            ));
+        }
+
+        Ok(DataForm {
+            type_: DataFormType::Submit,
+            form_type: Some(ns::MUC_ROOMCONFIG.to_string()),
+            title: None,
+            instructions: None,
+            fields: FormConfig::new(form_values).populate_form_fields(&form.fields)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xmpp_parsers::data_forms::{Field, FieldType};
+
+    use super::*;
+
+    fn configuration_form() -> DataForm {
+        DataForm {
+            type_: DataFormType::Form,
+            form_type: Some(ns::MUC_ROOMCONFIG.to_string()),
+            title: None,
+            instructions: None,
+            fields: vec![
+                Field::new(cfg::MEMBERS_ONLY, FieldType::Boolean).with_value("0"),
+                Field::new(cfg::MODERATED_ROOM, FieldType::Boolean).with_value("1"),
+                Field::new(cfg::PERSISTENT_ROOM, FieldType::Boolean).with_value("0"),
+                Field::new(cfg::ALLOW_INVITES, FieldType::Boolean).with_value("0"),
+                Field::new(cfg::MAX_HISTORY_FETCH, FieldType::TextSingle).with_value("20"),
+                Field::new(cfg::PASSWORD_PROTECTED_ROOM, FieldType::Boolean).with_value("0"),
+                Field::new(cfg::ROOM_SECRET, FieldType::TextPrivate),
+                Field::new(cfg::ROOM_NAME, FieldType::TextSingle).with_value("Room Name"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_parses_configuration_form() {
+        assert_eq!(
+            RoomConfiguration::try_from(&configuration_form()).unwrap(),
+            RoomConfiguration {
+                members_only: false,
+                moderated: true,
+                persistent: false,
+                max_history_fetch: Some(20),
+                allow_invites: false,
+                password: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_round_trips_configuration() {
+        let configuration = RoomConfiguration {
+            members_only: true,
+            moderated: false,
+            persistent: true,
+            max_history_fetch: Some(50),
+            allow_invites: true,
+            password: Some("secret".to_string()),
+        };
+
+        let form = configuration.populate_form(&configuration_form()).unwrap();
+
+        assert_eq!(form.type_, DataFormType::Submit);
+        assert_eq!(RoomConfiguration::try_from(&form).unwrap(), configuration);
+
+        // Fields not covered by RoomConfiguration are submitted unchanged…
+        assert!(form
+            .fields
+            .iter()
+            .any(|field| field.var.as_deref() == Some(cfg::ROOM_NAME)
+                && field.values == vec!["Room Name".to_string()]));
+    }
+
+    #[test]
+    fn test_omits_empty_fields() {
+        let configuration = RoomConfiguration {
+            max_history_fetch: None,
+            password: None,
+            ..Default::default()
+        };
+
+        let form = configuration.populate_form(&configuration_form()).unwrap();
+
+        let values = |var: &str| {
+            form.fields
+                .iter()
+                .find(|field| field.var.as_deref() == Some(var))
+                .map(|field| field.values.clone())
+                .unwrap()
+        };
+
+        // Empty values are not submitted, so that the server keeps its values…
+        assert_eq!(values(cfg::MAX_HISTORY_FETCH), vec!["20".to_string()]);
+        assert!(values(cfg::ROOM_SECRET).is_empty());
+        assert_eq!(
+            values(cfg::PASSWORD_PROTECTED_ROOM),
+            vec!["false".to_string()]
+        );
+    }
+}
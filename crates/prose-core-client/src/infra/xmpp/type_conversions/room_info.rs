@@ -67,6 +67,12 @@ pub struct Features {
     pub supports_self_ping_optimization: bool,
     /// This MUC will reflect the original message 'id' in 'groupchat' messages.
     pub supports_stable_id: bool,
+    /// XEP-0425: Message Moderation
+    /// https://xmpp.org/extensions/xep-0425.html
+    pub supports_message_moderation: bool,
+    /// XEP-0333: Chat Markers
+    /// https://xmpp.org/extensions/xep-0333.html
+    pub supports_chat_markers: bool,
     /// The supported MAM version
     pub mam_version: Option<MamVersion>,
 }
@@ -148,6 +154,8 @@ impl From<&[disco::Feature]> for Features {
                 feat::TEMPORARY => result.is_temporary = true,
                 feat::UNMODERATED => result.is_unmoderated = true,
                 feat::UNSECURED => result.is_unsecured = true,
+                ns::MODERATE => result.supports_message_moderation = true,
+                ns::CHAT_MARKERS => result.supports_chat_markers = true,
                 ns::MAM0 => {
                     result.mam_version = Some(
                         result
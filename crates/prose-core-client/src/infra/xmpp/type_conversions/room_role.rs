@@ -0,0 +1,30 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use xmpp_parsers::muc::user::Role;
+
+use crate::domain::rooms::models::RoomRole;
+
+impl From<Role> for RoomRole {
+    fn from(value: Role) -> Self {
+        match value {
+            Role::Moderator => RoomRole::Moderator,
+            Role::Participant => RoomRole::Participant,
+            Role::Visitor => RoomRole::Visitor,
+            Role::None => RoomRole::None,
+        }
+    }
+}
+
+impl From<RoomRole> for Role {
+    fn from(value: RoomRole) -> Self {
+        match value {
+            RoomRole::Moderator => Role::Moderator,
+            RoomRole::Participant => Role::Participant,
+            RoomRole::Visitor => Role::Visitor,
+            RoomRole::None => Role::None,
+        }
+    }
+}
@@ -49,6 +49,7 @@ impl TryFrom<Presence> for RoomSessionParticipant {
             anon_id: anon_occupant_id,
             real_id: real_id.clone(),
             affiliation: item.affiliation.clone().into(),
+            role: item.role.clone().into(),
             presence: value.to_domain_presence(occupant_id, real_id),
         })
     }
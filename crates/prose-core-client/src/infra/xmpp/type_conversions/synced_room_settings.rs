@@ -10,7 +10,7 @@ use xmpp_parsers::pubsub::PubSubPayload;
 
 use prose_xmpp::{ElementExt, ParseError};
 
-use crate::domain::settings::models::SyncedRoomSettings;
+use crate::domain::settings::models::{RoomNotificationMode, SyncedRoomSettings};
 use crate::dtos::RoomId;
 use crate::infra::xmpp::type_conversions::message_ref;
 
@@ -41,6 +41,34 @@ impl TryFrom<Element> for SyncedRoomSettings {
                 .map(|child| child.attr_req("type"))
                 .transpose()?
                 == Some("omemo"),
+            pinned_messages: value
+                .children()
+                .filter(|child| {
+                    child.is(
+                        "pinned-message-ref",
+                        message_ref::ns::PROSE_PINNED_MESSAGE_REF,
+                    )
+                })
+                .cloned()
+                .map(TryFrom::try_from)
+                .collect::<Result<Vec<_>, _>>()?,
+            notifications: value
+                .get_child("notifications", ns::PROSE_ROOM_SETTINGS)
+                .map(|child| match child.attr_req("mode")? {
+                    "all" => Ok(RoomNotificationMode::All),
+                    "mentions" => Ok(RoomNotificationMode::MentionsOnly),
+                    "none" => Ok(RoomNotificationMode::None),
+                    mode => Err(ParseError::Generic {
+                        msg: format!("Unknown notification mode '{mode}'"),
+                    }),
+                })
+                .transpose()?
+                .unwrap_or_default(),
+            send_read_receipts: value
+                .get_child("read-receipts", ns::PROSE_ROOM_SETTINGS)
+                .map(|child| child.attr_bool_req("enabled"))
+                .transpose()?
+                .unwrap_or(true),
         })
     }
 }
@@ -60,6 +88,21 @@ impl From<SyncedRoomSettings> for Element {
                     },
                 ),
             )
+            .append_all(value.pinned_messages)
+            .append(
+                Element::builder("notifications", ns::PROSE_ROOM_SETTINGS).attr(
+                    "mode",
+                    match value.notifications {
+                        RoomNotificationMode::All => "all",
+                        RoomNotificationMode::MentionsOnly => "mentions",
+                        RoomNotificationMode::None => "none",
+                    },
+                ),
+            )
+            .append(
+                Element::builder("read-receipts", ns::PROSE_ROOM_SETTINGS)
+                    .attr("enabled", value.send_read_receipts.to_string()),
+            )
             .build()
     }
 }
@@ -17,11 +17,15 @@ impl TryFrom<XMPPUserActivity> for UserStatus {
     fn try_from(value: XMPPUserActivity) -> Result<Self> {
         // Notice: sending with no icon indicates that we are willing to retract \
         //   any previously published activity.
-        let emoji = value
-            .activity
-            .as_ref()
-            .and_then(|activity| activity.specific.as_ref())
-            .and_then(|specific| specific.to_emoji());
+        // Activities published by other clients might not carry a specific activity, in which
+        // case we fall back to an emoji for the general one.
+        let emoji = value.activity.as_ref().and_then(|activity| {
+            activity
+                .specific
+                .as_ref()
+                .and_then(|specific| specific.to_emoji())
+                .or_else(|| activity.general.to_emoji())
+        });
 
         let Some(emoji) = emoji else {
             bail!("Missing emoji in UserActivity")
@@ -60,6 +64,27 @@ trait ToEmoji {
     fn to_emoji(&self) -> Option<String>;
 }
 
+impl ToEmoji for activity::General {
+    fn to_emoji(&self) -> Option<String> {
+        use activity::General;
+
+        match self {
+            General::DoingChores => Some("🧹".to_string()),
+            General::Drinking => Some("🥤".to_string()),
+            General::Eating => Some("🍽️".to_string()),
+            General::Exercising => Some("🏋️".to_string()),
+            General::Grooming => Some("🪞".to_string()),
+            General::HavingAppointment => Some("🗓️".to_string()),
+            General::Inactive => Some("💤".to_string()),
+            General::Relaxing => Some("🛋️".to_string()),
+            General::Talking => Some("💬".to_string()),
+            General::Traveling => Some("🧳".to_string()),
+            General::Undefined => None,
+            General::Working => Some("💼".to_string()),
+        }
+    }
+}
+
 impl ToEmoji for activity::Specific {
     fn to_emoji(&self) -> Option<String> {
         use activity::Specific;
@@ -135,3 +160,52 @@ impl ToEmoji for activity::Specific {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use prose_xmpp::stanza::user_activity::Activity;
+
+    use super::*;
+
+    #[test]
+    fn test_falls_back_to_emoji_of_general_activity() -> Result<()> {
+        let status = UserStatus::try_from(XMPPUserActivity {
+            activity: Some(Activity {
+                general: activity::General::Exercising,
+                specific: None,
+            }),
+            text: None,
+        })?;
+
+        assert_eq!(
+            UserStatus {
+                emoji: "🏋️".to_string(),
+                status: Some("exercising".to_string()),
+            },
+            status
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefers_emoji_of_specific_activity() -> Result<()> {
+        let status = UserStatus::try_from(XMPPUserActivity {
+            activity: Some(Activity {
+                general: activity::General::Drinking,
+                specific: Some(activity::Specific::HavingCoffee),
+            }),
+            text: Some("Coffee break".to_string()),
+        })?;
+
+        assert_eq!(
+            UserStatus {
+                emoji: "☕️".to_string(),
+                status: Some("Coffee break".to_string()),
+            },
+            status
+        );
+
+        Ok(())
+    }
+}
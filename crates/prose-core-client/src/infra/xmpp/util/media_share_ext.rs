@@ -10,6 +10,8 @@ use prose_xmpp::stanza::media_sharing::MediaShare;
 use prose_xmpp::stanza::references::ReferenceType;
 
 pub trait MediaShareExt {
+    /// Returns the first http(s) source or, for files encrypted with XEP-0454, the first
+    /// aesgcm source.
     fn first_http_source_url(&self) -> Option<Url>;
 }
 
@@ -30,7 +32,7 @@ impl MediaShareExt for MediaShare {
                     }
                 };
 
-                if url.scheme() == "https" || url.scheme() == "http" {
+                if matches!(url.scheme(), "https" | "http" | "aesgcm") {
                     return Some(url)
                 }
                 None
@@ -71,4 +73,27 @@ mod tests {
             "https://uploads.prose.org/image.jpg"
         );
     }
+    #[test]
+    fn test_accepts_aesgcm_source_url() {
+        let url = format!("aesgcm://uploads.prose.org/image.jpg#{}", "ab".repeat(44));
+
+        assert_eq!(
+            MediaShare {
+                file: File {
+                    media_type: "image/jpeg".to_string(),
+                    name: None,
+                    size: 100,
+                    desc: None,
+                    duration: None,
+                    hashes: vec![],
+                    thumbnails: vec![],
+                },
+                sources: vec![Reference::data_reference(url.clone())]
+            }
+            .first_http_source_url()
+            .unwrap()
+            .to_string(),
+            url
+        );
+    }
 }
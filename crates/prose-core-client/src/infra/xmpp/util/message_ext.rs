@@ -3,7 +3,9 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
-use minidom::Element;
+use jid::{FullJid, Jid};
+use mime::Mime;
+use minidom::{Element, NSChoice};
 use std::ops::Range;
 use tracing::{error, warn};
 use xmpp_parsers::message::MessageType;
@@ -11,10 +13,13 @@ use xmpp_parsers::{eme, legacy_omemo};
 
 use prose_xmpp::ns;
 use prose_xmpp::stanza::media_sharing::{MediaShare, OOB};
+use prose_xmpp::stanza::message::{Fallback, Range as FallbackRange, Reply};
+use prose_xmpp::stanza::references::{Reference, ReferenceType};
 use prose_xmpp::stanza::Message;
+use url::Url;
 
 use crate::domain::messaging::models::send_message_request::{Body, Payload};
-use crate::domain::messaging::models::{Attachment, MessageTargetId, ReplyTo};
+use crate::domain::messaging::models::{Attachment, AttachmentType, MessageTargetId, ReplyTo};
 use crate::domain::shared::models::{RustStringRangeExt, UserEndpointId};
 use crate::dtos::{MessageServerId, ParticipantId, RoomId, ScalarRangeExt, UnicodeScalarIndex};
 use crate::util::StringExt;
@@ -32,11 +37,18 @@ pub trait MessageExt {
     fn is_groupchat_message(&self) -> bool;
     fn set_message_body(self, body: Option<Body>) -> Self;
     fn set_omemo_payload(self, payload: impl Into<legacy_omemo::Encrypted>) -> Self;
+    /// Adds a XEP-0461 reply element. If the message has a plain body, the quote is prepended
+    /// to it and marked as fallback. Must be called after `set_message_body`.
+    fn set_reply_to(self, reply_to: Option<ReplyTo>) -> Self;
 
     /// Returns the value of the `from` attribute converted to a `UserEndpointId`, depending on
     /// the message type (groupchat or chat).
     fn sender(&self) -> Option<UserEndpointId>;
 
+    /// Returns the occupant JID of the moderator if the message is a moderation notification
+    /// (XEP-0425) for a retracted message.
+    fn moderator(&self) -> Option<FullJid>;
+
     /// Returns the value of the `to` attribute converted to a `RoomId`, depending on the
     /// message type (groupchat or chat)
     fn room_id(&self) -> Option<RoomId>;
@@ -65,15 +77,21 @@ impl MessageExt for Message {
             attachments.push(attachment)
         };
 
-        for share in self.media_shares() {
-            let attachment = match Attachment::try_from(share) {
+        for elem in self.media_share_elements() {
+            let attachment = match MediaShare::try_from(elem.clone())
+                .and_then(|share| Attachment::try_from(share))
+            {
                 Ok(attachment) => attachment,
                 Err(err) => {
-                    error!(
-                        "Failed to convert media-share to Attachment. {}",
+                    warn!(
+                        "Failed to convert media-share to Attachment. Falling back to file. {}",
                         err.to_string()
                     );
-                    continue;
+                    let Some(attachment) = file_attachment_from_media_share_element(elem) else {
+                        error!("Failed to salvage file attachment from media-share.");
+                        continue;
+                    };
+                    attachment
                 }
             };
             push_attachment_if_needed(attachment)
@@ -144,13 +162,50 @@ impl MessageExt for Message {
         self
     }
 
+    fn set_reply_to(mut self, reply_to: Option<ReplyTo>) -> Self {
+        let Some(reply_to) = reply_to else {
+            return self;
+        };
+
+        self = self.set_reply(Reply {
+            id: reply_to.id.into_string(),
+            to: reply_to.to.map(Jid::from),
+        });
+
+        // We don't want to leak the quote of an encrypted message in plain text…
+        if self.omemo_element().is_some() {
+            return self;
+        }
+
+        let (Some(quote), Some(body)) = (reply_to.quote, self.body()) else {
+            return self;
+        };
+
+        let quote = quote
+            .lines()
+            .map(|line| format!("> {line}\n"))
+            .collect::<String>();
+        let body = format!("{quote}{body}");
+
+        self.set_body(body).set_fallback(Fallback {
+            r#for: Some(ns::REPLY.to_string()),
+            subjects: vec![],
+            bodies: vec![FallbackRange {
+                start: Some(0),
+                end: Some(quote.chars().count()),
+            }],
+        })
+    }
+
     fn sender(&self) -> Option<UserEndpointId> {
         let Some(from) = self.from.clone() else {
             return None;
         };
 
         if self.is_groupchat_message() {
-            let Ok(from) = from.try_into_full() else {
+            // Moderation notifications are sent from the bare JID of the room, so we're
+            // attributing them to the moderator instead.
+            let Some(from) = from.try_into_full().ok().or_else(|| self.moderator()) else {
                 error!("Expected FullJid in received groupchat message");
                 return None;
             };
@@ -164,6 +219,14 @@ impl MessageExt for Message {
         .into()
     }
 
+    fn moderator(&self) -> Option<FullJid> {
+        self.fastening()
+            .and_then(|fastening| fastening.moderated())
+            .filter(|moderated| moderated.retract)
+            .and_then(|moderated| moderated.by)
+            .and_then(|by| by.try_into_full().ok())
+    }
+
     fn room_id(&self) -> Option<RoomId> {
         let Some(to) = self.to.clone() else {
             return None;
@@ -231,15 +294,48 @@ impl MessageExt for Message {
     }
 }
 
+/// Builds a plain file attachment from a 'media-sharing' element that couldn't be parsed
+/// (e.g. because of a malformed size or duration), so that the attachment isn't lost entirely.
+fn file_attachment_from_media_share_element(elem: &Element) -> Option<Attachment> {
+    let jingle_ft_ns = NSChoice::AnyOf(&[ns::JINGLE_FT, ns::JINGLE_FT_4]);
+
+    let url = elem
+        .get_child("sources", ns::SIMS)?
+        .children()
+        .filter_map(|child| Reference::try_from(child.clone()).ok())
+        .filter(|reference| reference.r#type == ReferenceType::Data)
+        .filter_map(|reference| reference.uri.parse::<Url>().ok())
+        .find(|url| url.scheme() == "http" || url.scheme() == "https")?;
+
+    let file = elem.get_child("file", jingle_ft_ns);
+    let mut attachment = Attachment::from(url);
+    attachment.r#type = AttachmentType::File;
+
+    if let Some(media_type) = file
+        .and_then(|file| file.get_child("media-type", jingle_ft_ns))
+        .and_then(|media_type| media_type.text().parse::<Mime>().ok())
+    {
+        attachment.media_type = media_type;
+    }
+
+    if let Some(file_name) = file
+        .and_then(|file| file.get_child("name", jingle_ft_ns))
+        .map(|name| name.text())
+        .filter(|name| !name.is_empty())
+    {
+        attachment.file_name = file_name;
+    }
+
+    Some(attachment)
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
 
     use anyhow::Result;
-    use mime::Mime;
-    use url::Url;
 
-    use crate::domain::messaging::models::{AttachmentType, Thumbnail};
+    use crate::domain::messaging::models::Thumbnail;
 
     use super::*;
 
@@ -294,6 +390,78 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_prefers_media_share_over_oob() -> Result<()> {
+        let xml = r#"<message xmlns='jabber:client' from='them@prose.org/res' to='me@prose.org' type='chat' id='id-1'>
+          <reference xmlns='urn:xmpp:reference:0' type='data'>
+            <media-sharing xmlns='urn:xmpp:sims:1'>
+              <file xmlns='urn:xmpp:jingle:apps:file-transfer:5'>
+                <media-type>audio/ogg</media-type>
+                <name>voice-message.oga</name>
+                <size>5120</size>
+                <duration xmlns='https://prose.org/protocol/audio-duration'>12</duration>
+              </file>
+              <sources>
+                <reference xmlns='urn:xmpp:reference:0' type='data' uri='https://upload.prose.org/voice-message.oga' />
+              </sources>
+            </media-sharing>
+          </reference>
+          <x xmlns='jabber:x:oob'>
+            <url>https://upload.prose.org/voice-message.oga</url>
+          </x>
+        </message>"#;
+
+        let message = Message::try_from(Element::from_str(xml)?)?;
+
+        assert_eq!(
+            message.attachments(),
+            vec![Attachment {
+                r#type: AttachmentType::Audio { duration: Some(12) },
+                url: "https://upload.prose.org/voice-message.oga".parse()?,
+                media_type: "audio/ogg".parse()?,
+                file_name: "voice-message.oga".to_string(),
+                file_size: Some(5120),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_degrades_malformed_media_share_to_file() -> Result<()> {
+        let xml = r#"<message xmlns='jabber:client' from='them@prose.org/res' to='me@prose.org' type='chat' id='id-1'>
+          <reference xmlns='urn:xmpp:reference:0' type='data'>
+            <media-sharing xmlns='urn:xmpp:sims:1'>
+              <file xmlns='urn:xmpp:jingle:apps:file-transfer:5'>
+                <media-type>audio/ogg</media-type>
+                <name>voice-message.oga</name>
+                <size>large</size>
+                <duration xmlns='https://prose.org/protocol/audio-duration'>twelve</duration>
+              </file>
+              <sources>
+                <reference xmlns='urn:xmpp:reference:0' type='data' uri='https://upload.prose.org/voice-message.oga' />
+              </sources>
+            </media-sharing>
+          </reference>
+        </message>"#;
+
+        let message = Message::try_from(Element::from_str(xml)?)?;
+
+        assert!(message.media_shares().is_empty());
+        assert_eq!(
+            message.attachments(),
+            vec![Attachment {
+                r#type: AttachmentType::File,
+                url: "https://upload.prose.org/voice-message.oga".parse()?,
+                media_type: "audio/ogg".parse()?,
+                file_name: "voice-message.oga".to_string(),
+                file_size: None,
+            }]
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn test_appends_attachments() -> Result<()> {
         let mut message = Message::new().set_body("Hello World");
@@ -3,7 +3,9 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use chrono::{DateTime, Utc};
 use xmpp_parsers::caps::Caps;
+use xmpp_parsers::idle::Idle;
 use xmpp_parsers::nick::Nick;
 use xmpp_parsers::presence;
 use xmpp_parsers::vcard_update::VCardUpdate;
@@ -22,6 +24,7 @@ pub trait PresenceExt {
     fn avatar_id(&self) -> Option<AvatarId>;
     fn nickname(&self) -> Option<String>;
     fn caps(&self) -> Option<Caps>;
+    fn idle_since(&self) -> Option<DateTime<Utc>>;
     fn muc_user(&self) -> Option<MucUser>;
 
     fn to_domain_presence(
@@ -81,6 +84,15 @@ impl PresenceExt for presence::Presence {
             .and_then(|p| Caps::try_from(p).ok())
     }
 
+    fn idle_since(&self) -> Option<DateTime<Utc>> {
+        self.payloads
+            .iter()
+            .find(|p| p.is("idle", ns::IDLE))
+            .cloned()
+            .and_then(|p| Idle::try_from(p).ok())
+            .map(|idle| idle.since.0.with_timezone(&Utc))
+    }
+
     fn muc_user(&self) -> Option<MucUser> {
         self.payloads
             .iter()
@@ -113,6 +125,7 @@ impl PresenceExt for presence::Presence {
             avatar,
             caps,
             client,
+            idle_since: self.idle_since(),
             status: self.statuses.first_key_value().map(|v| v.1.clone()),
             nickname: self.nickname(),
         }
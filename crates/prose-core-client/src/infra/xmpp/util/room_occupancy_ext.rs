@@ -64,6 +64,7 @@ fn self_participant(
         anon_id: presence.anon_occupant_id(),
         real_id: real_id.clone(),
         affiliation: item.affiliation.clone().into(),
+        role: item.role.clone().into(),
         presence: presence.to_domain_presence(occupant_id, real_id),
     })
 }
@@ -78,7 +79,7 @@ mod tests {
 
     use crate::domain::shared::models::AnonOccupantId;
     use crate::domain::user_info::models::Presence;
-    use crate::dtos::{Availability, RoomAffiliation};
+    use crate::dtos::{Availability, RoomAffiliation, RoomRole};
     use crate::{occupant_id, user_id};
 
     use super::*;
@@ -135,6 +136,7 @@ mod tests {
                     anon_id: Some(AnonOccupantId::from("occ_1")),
                     real_id: Some(user_id!("user_a@prose.org")),
                     affiliation: RoomAffiliation::Member,
+                    role: RoomRole::Moderator,
                     presence: Presence {
                         availability: Availability::Available,
                         ..Default::default()
@@ -146,6 +148,7 @@ mod tests {
                     anon_id: Some(AnonOccupantId::from("occ_2")),
                     real_id: Some(user_id!("user_b@prose.org")),
                     affiliation: RoomAffiliation::Member,
+                    role: RoomRole::Participant,
                     presence: Presence {
                         availability: Availability::Available,
                         ..Default::default()
@@ -157,6 +160,7 @@ mod tests {
                     anon_id: Some(AnonOccupantId::from("occ_3")),
                     real_id: Some(user_id!("me@prose.org")),
                     affiliation: RoomAffiliation::Member,
+                    role: RoomRole::Visitor,
                     presence: Presence {
                         availability: Availability::Available,
                         ..Default::default()
@@ -14,12 +14,14 @@ use prose_xmpp::{Client, ClientBuilder, Event, IDProvider, TimeProvider};
 #[derive(Clone)]
 pub struct XMPPClient {
     pub(crate) client: Arc<Client>,
+    pub(crate) max_stanza_size: Option<usize>,
 }
 
 impl XMPPClient {
     pub fn builder() -> XMPPClientBuilder {
         XMPPClientBuilder {
             builder: Client::builder(),
+            max_stanza_size: None,
         }
     }
 }
@@ -34,6 +36,7 @@ impl Deref for XMPPClient {
 
 pub struct XMPPClientBuilder {
     builder: ClientBuilder,
+    max_stanza_size: Option<usize>,
 }
 
 impl XMPPClientBuilder {
@@ -52,6 +55,16 @@ impl XMPPClientBuilder {
         self
     }
 
+    pub fn set_max_stanza_size(mut self, max_stanza_size: usize) -> Self {
+        self.max_stanza_size = Some(max_stanza_size);
+        self
+    }
+
+    pub fn set_stream_management_enabled(mut self, is_enabled: bool) -> Self {
+        self.builder = self.builder.set_stream_management_enabled(is_enabled);
+        self
+    }
+
     pub fn set_event_handler<T>(
         mut self,
         handler: impl Fn(Client, Event) -> T + SendUnlessWasm + SyncUnlessWasm + 'static,
@@ -68,6 +81,7 @@ impl XMPPClientBuilder {
 
         XMPPClient {
             client: Arc::new(client),
+            max_stanza_size: self.max_stanza_size,
         }
     }
 }
@@ -23,6 +23,7 @@ pub use util::account_bookmarks_client::{AccountBookmark, AccountBookmarksClient
 #[cfg(feature = "debug")]
 pub use util::RandomDelayProxyTransformer;
 
+pub use crate::infra::user_info::InMemoryAvatarRepository;
 #[cfg(target_arch = "wasm32")]
 pub use crate::infra::user_info::StoreAvatarRepository;
 #[cfg(not(target_arch = "wasm32"))]
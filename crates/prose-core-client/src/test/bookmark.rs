@@ -16,6 +16,8 @@ impl Bookmark {
             jid,
             r#type: BookmarkType::DirectMessage,
             sidebar_state: RoomSidebarState::NotInSidebar,
+            order_index: None,
+            invitation: None,
         }
     }
 
@@ -27,6 +29,8 @@ impl Bookmark {
             jid,
             r#type: BookmarkType::Group,
             sidebar_state: RoomSidebarState::NotInSidebar,
+            order_index: None,
+            invitation: None,
         }
     }
 
@@ -38,6 +42,8 @@ impl Bookmark {
             jid,
             r#type: BookmarkType::PublicChannel,
             sidebar_state: RoomSidebarState::NotInSidebar,
+            order_index: None,
+            invitation: None,
         }
     }
 
@@ -49,6 +55,8 @@ impl Bookmark {
             jid,
             r#type: BookmarkType::PrivateChannel,
             sidebar_state: RoomSidebarState::NotInSidebar,
+            order_index: None,
+            invitation: None,
         }
     }
 }
@@ -17,12 +17,12 @@ use prose_xmpp::test::BareJidTestAdditions;
 
 use crate::domain::messaging::models::{
     Body, Message, MessageFlags, MessageId, MessageLike, MessageLikeBody, MessageLikePayload,
-    MessageRemoteId, MessageServerId, Reaction,
+    MessageRemoteId, MessageServerId, Reaction, ThreadId,
 };
 use crate::domain::shared::models::AnonOccupantId;
 use crate::dtos::{
-    Mention, Message as MessageDTO, MessageFlags as MessageFlagsDTO, MessageSender, ParticipantId,
-    Reaction as ReactionDTO,
+    DeliveryState, Mention, Message as MessageDTO, MessageFlags as MessageFlagsDTO, MessageSender,
+    ParticipantId, Reaction as ReactionDTO,
 };
 use crate::test::mock_data;
 
@@ -54,6 +54,10 @@ impl MessageBuilder {
     pub fn stanza_id_for_index(idx: u32) -> MessageServerId {
         format!("res-{}", idx).into()
     }
+
+    pub fn timestamp_for_index(idx: u32) -> DateTime<Utc> {
+        mock_data::reference_date() + Duration::minutes(idx.into())
+    }
 }
 
 impl MessageLikePayload {
@@ -70,7 +74,16 @@ impl MessageLikePayload {
             is_transient: false,
             reply_to: None,
             thread_id: None,
+            is_markable: false,
+        }
+    }
+
+    pub fn message_in_thread(body: impl Into<String>, thread: impl Into<ThreadId>) -> Self {
+        let mut payload = Self::message(body);
+        if let Self::Message { thread_id, .. } = &mut payload {
+            *thread_id = Some(thread.into());
         }
+        payload
     }
 }
 
@@ -103,7 +116,7 @@ impl MessageBuilder {
     pub fn new_with_index(idx: u32) -> Self {
         Self::new_with_id(
             Self::id_for_index(idx),
-            mock_data::reference_date() + Duration::minutes(idx.into()),
+            Self::timestamp_for_index(idx),
             MessageLikePayload::message(format!("Message {}", idx)),
         )
         .set_remote_id(Some(Self::remote_id_for_index(idx)))
@@ -200,11 +213,20 @@ impl MessageBuilder {
                 is_delivered: self.is_delivered,
                 is_transient: false,
                 is_encrypted: false,
+                sender_device_trust: None,
+                sender_device_id: None,
+                is_undecryptable: false,
             },
             reactions: self.reactions,
             attachments: vec![],
             mentions: vec![],
             reply_to: None,
+            thread_id: None,
+            thread_reply_count: 0,
+            last_thread_reply_at: None,
+            is_retracted: false,
+            retraction_reason: None,
+            moderated_by: None,
         }
     }
 
@@ -230,10 +252,19 @@ impl MessageBuilder {
             flags: MessageFlagsDTO {
                 is_read: self.is_read,
                 is_edited: self.is_edited,
-                is_delivered: self.is_delivered,
+                delivery_state: if self.is_delivered {
+                    DeliveryState::Delivered
+                } else {
+                    DeliveryState::Sent
+                },
                 is_transient: false,
                 is_encrypted: false,
+                sender_device_trust: None,
+                sender_device_id: None,
+                is_undecryptable: false,
                 is_last_read: false,
+                is_pending: false,
+                send_error: None,
             },
             reactions: self
                 .reactions
@@ -252,11 +283,18 @@ impl MessageBuilder {
                             avatar: None,
                         })
                         .collect(),
+                    last_updated: reaction.last_updated,
                 })
                 .collect(),
             attachments: vec![],
             mentions: vec![],
             reply_to: None,
+            thread_id: None,
+            thread_reply_count: 0,
+            last_thread_reply_at: None,
+            is_retracted: false,
+            retraction_reason: None,
+            moderated_by: None,
         }
     }
 
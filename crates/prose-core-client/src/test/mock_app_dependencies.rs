@@ -14,10 +14,11 @@ use prose_xmpp::bare;
 use prose_xmpp::test::IncrementingIDProvider;
 
 use crate::app::deps::{
-    AppContext, AppDependencies, DynAppContext, DynBookmarksService, DynClientEventDispatcher,
-    DynDraftsRepository, DynEncryptionDomainService, DynIDProvider, DynMessageArchiveService,
-    DynMessageIdProvider, DynMessagesRepository, DynMessagingService, DynRngProvider,
-    DynRoomAttributesService, DynRoomParticipationService, DynSidebarDomainService,
+    AppContext, AppDependencies, DynAccountSettingsRepository, DynAppContext, DynBookmarksService,
+    DynClientEventDispatcher, DynDraftsRepository, DynEncryptionDomainService, DynIDProvider,
+    DynMessageArchiveService, DynMessageIdProvider, DynMessagesRepository, DynMessagingService,
+    DynPendingMessagesRepository, DynRngProvider, DynRoomAttributesService,
+    DynRoomManagementService, DynRoomParticipationService, DynSidebarDomainService,
     DynSyncedRoomSettingsService, DynTimeProvider, DynUserInfoDomainService,
 };
 use crate::app::event_handlers::{MockClientEventDispatcherTrait, ServerEventHandlerQueue};
@@ -25,16 +26,26 @@ use crate::app::services::RoomInner;
 use crate::domain::account::services::mocks::MockUserAccountService;
 use crate::domain::connection::models::{ConnectionProperties, ServerFeatures};
 use crate::domain::connection::services::mocks::MockConnectionService;
-use crate::domain::contacts::repos::mocks::MockBlockListRepository;
+use crate::domain::contacts::repos::mocks::{
+    MockBlockListRepository, MockContactListRepository, MockPresenceSubRequestsRepository,
+};
+use crate::domain::contacts::services::impls::ContactListDomainServiceDependencies;
 use crate::domain::contacts::services::mocks::{
-    MockBlockListDomainService, MockContactListDomainService,
+    MockBlockListDomainService, MockContactListDomainService, MockContactListService,
+};
+use crate::domain::encryption::repos::mocks::{
+    MockEncryptionKeysRepository, MockSessionRepository, MockUserDeviceRepository,
 };
-use crate::domain::encryption::repos::mocks::MockUserDeviceRepository;
-use crate::domain::encryption::services::mocks::MockEncryptionDomainService;
+use crate::domain::encryption::services::impls::EncryptionDomainServiceDependencies;
+use crate::domain::encryption::services::mocks::{
+    MockEncryptionDomainService, MockEncryptionService, MockUserDeviceService,
+};
+use crate::domain::encryption::services::IncrementingUserDeviceIdProvider;
 use crate::domain::general::models::Capabilities;
 use crate::domain::general::services::mocks::MockRequestHandlingService;
 use crate::domain::messaging::repos::mocks::{
     MockDraftsRepository, MockMessagesRepository, MockOfflineMessagesRepository,
+    MockPendingMessagesRepository,
 };
 use crate::domain::messaging::services::mocks::{
     MockMessageArchiveDomainService, MockMessageArchiveService, MockMessageMigrationDomainService,
@@ -55,6 +66,7 @@ use crate::domain::settings::repos::mocks::{
 };
 use crate::domain::settings::services::mocks::MockSyncedRoomSettingsService;
 use crate::domain::shared::models::{AccountId, ConnectionState};
+use crate::domain::sidebar::repos::mocks::MockBookmarksRepository;
 use crate::domain::sidebar::services::impls::SidebarDomainServiceDependencies;
 use crate::domain::sidebar::services::mocks::{MockBookmarksService, MockSidebarDomainService};
 use crate::domain::uploads::services::mocks::MockUploadService;
@@ -103,9 +115,15 @@ impl Default for AppContext {
                 decryption_context: Some(DecryptionContext::default()),
             })),
             connection_state: RwLock::new(ConnectionState::Connected),
+            cached_account: Default::default(),
+            auto_away: Default::default(),
+            reconnect_state: Default::default(),
+            ping_round_trip_time: Default::default(),
+            unconfirmed_message_ids: Default::default(),
             capabilities: Capabilities::new("Prose", "https://prose.org", vec![]),
             software_version: Default::default(),
             config: Default::default(),
+            cache_schema_version: Default::default(),
         }
     }
 }
@@ -135,6 +153,8 @@ pub struct MockAppDependencies {
     pub messages_repo: MockMessagesRepository,
     pub messaging_service: MockMessagingService,
     pub offline_message_repo: MockOfflineMessagesRepository,
+    #[derivative(Default(value = "empty_pending_messages_repo()"))]
+    pub pending_messages_repo: MockPendingMessagesRepository,
     pub synced_room_settings_service: MockSyncedRoomSettingsService,
     pub request_handling_service: MockRequestHandlingService,
     #[derivative(Default(value = "Arc::new(StepRngProvider::default())"))]
@@ -143,6 +163,7 @@ pub struct MockAppDependencies {
     pub room_management_service: MockRoomManagementService,
     pub room_participation_service: MockRoomParticipationService,
     pub room_attributes_service: MockRoomAttributesService,
+    pub session_repo: MockSessionRepository,
     #[derivative(Default(value = "Arc::new(IncrementingIDProvider::new(\"short-id\"))"))]
     pub short_id_provider: DynIDProvider,
     pub sidebar_domain_service: MockSidebarDomainService,
@@ -152,6 +173,7 @@ pub struct MockAppDependencies {
     pub user_account_service: MockUserAccountService,
     pub user_device_repo: MockUserDeviceRepository,
     pub user_info_domain_service: MockUserInfoDomainService,
+    pub user_profile_repo: MockUserProfileRepository,
 }
 
 impl MockAppDependencies {
@@ -163,6 +185,7 @@ impl MockAppDependencies {
 impl From<MockAppDependencies> for AppDependencies {
     fn from(mock: MockAppDependencies) -> Self {
         let client_event_dispatcher = Arc::new(mock.client_event_dispatcher);
+        let account_settings_repo = Arc::new(mock.account_settings_repo);
         let connected_rooms_repo = Arc::new(mock.connected_rooms_repo);
         let ctx = Arc::new(mock.ctx);
         let drafts_repo = Arc::new(mock.drafts_repo);
@@ -170,6 +193,7 @@ impl From<MockAppDependencies> for AppDependencies {
         let message_archive_service = Arc::new(mock.message_archive_service);
         let messages_repo = Arc::new(mock.messages_repo);
         let messaging_service = Arc::new(mock.messaging_service);
+        let pending_messages_repo = Arc::new(mock.pending_messages_repo);
         let room_management_service = Arc::new(mock.room_management_service);
         let room_participation_service = Arc::new(mock.room_participation_service);
         let room_attributes_service = Arc::new(mock.room_attributes_service);
@@ -178,15 +202,19 @@ impl From<MockAppDependencies> for AppDependencies {
         let synced_room_settings_service = Arc::new(mock.synced_room_settings_service);
 
         let room_factory = {
+            let account_settings_repo = account_settings_repo.clone();
             let client_event_dispatcher = client_event_dispatcher.clone();
             let ctx = ctx.clone();
             let drafts_repo = drafts_repo.clone();
             let encryption_domain_service = encryption_domain_service.clone();
+            let id_provider = mock.id_provider.clone();
             let message_id_provider = mock.message_id_provider.clone();
             let message_archive_service = message_archive_service.clone();
             let message_repo = messages_repo.clone();
             let messaging_service = messaging_service.clone();
             let participation_service = room_participation_service.clone();
+            let pending_messages_repo = pending_messages_repo.clone();
+            let room_management_service = room_management_service.clone();
             let sidebar_domain_service = sidebar_domain_service.clone();
             let time_provider = mock.time_provider.clone();
             let topic_service = room_attributes_service.clone();
@@ -195,17 +223,21 @@ impl From<MockAppDependencies> for AppDependencies {
 
             RoomFactory::new(Arc::new(move |data| {
                 RoomInner {
+                    account_settings_repo: account_settings_repo.clone(),
                     attributes_service: topic_service.clone(),
                     client_event_dispatcher: client_event_dispatcher.clone(),
                     ctx: ctx.clone(),
                     data: data.clone(),
                     drafts_repo: drafts_repo.clone(),
                     encryption_domain_service: encryption_domain_service.clone(),
+                    id_provider: id_provider.clone(),
                     message_id_provider: message_id_provider.clone(),
                     message_archive_service: message_archive_service.clone(),
                     message_repo: message_repo.clone(),
                     messaging_service: messaging_service.clone(),
                     participation_service: participation_service.clone(),
+                    pending_messages_repo: pending_messages_repo.clone(),
+                    room_management_service: room_management_service.clone(),
                     synced_room_settings_service: synced_room_settings_service.clone(),
                     sidebar_domain_service: sidebar_domain_service.clone(),
                     time_provider: time_provider.clone(),
@@ -216,7 +248,7 @@ impl From<MockAppDependencies> for AppDependencies {
         };
 
         AppDependencies {
-            account_settings_repo: Arc::new(mock.account_settings_repo),
+            account_settings_repo,
             avatar_repo: Arc::new(mock.avatar_repo),
             block_list_domain_service: Arc::new(mock.block_list_domain_service),
             client_event_dispatcher,
@@ -232,13 +264,20 @@ impl From<MockAppDependencies> for AppDependencies {
             messages_repo,
             messaging_service,
             offline_messages_repo: Arc::new(mock.offline_message_repo),
+            pending_messages_repo,
             request_handling_service: Arc::new(mock.request_handling_service),
             room_factory,
             room_management_service,
             room_participation_service,
             room_attributes_service,
             rooms_domain_service: Arc::new(mock.rooms_domain_service),
-            server_event_handler_queue: Arc::new(ServerEventHandlerQueue::new()),
+            server_event_handler_queue: {
+                // Events dispatched by services themselves (e.g. after connecting) go nowhere…
+                let queue = ServerEventHandlerQueue::new();
+                queue.set_handlers(vec![]);
+                Arc::new(queue)
+            },
+            session_repo: Arc::new(mock.session_repo),
             short_id_provider: mock.short_id_provider,
             sidebar_domain_service,
             time_provider: mock.time_provider,
@@ -246,18 +285,23 @@ impl From<MockAppDependencies> for AppDependencies {
             user_account_service: Arc::new(mock.user_account_service),
             user_device_repo: Arc::new(mock.user_device_repo),
             user_info_domain_service,
+            user_profile_repo: Arc::new(mock.user_profile_repo),
             contact_list_domain_service: Arc::new(mock.contact_list_domain_service),
             rng_provider: Arc::new(OsRngProvider),
         }
     }
 }
 
-#[derive(Default)]
+#[derive(Derivative)]
+#[derivative(Default)]
 pub struct MockSidebarDomainServiceDependencies {
+    #[derivative(Default(value = "accepting_bookmarks_repo()"))]
+    pub bookmarks_repo: MockBookmarksRepository,
     pub bookmarks_service: MockBookmarksService,
     pub client_event_dispatcher: MockClientEventDispatcherTrait,
     pub connected_rooms_repo: MockConnectedRoomsReadWriteRepository,
     pub ctx: AppContext,
+    pub messages_repo: MockMessagesRepository,
     pub room_management_service: MockRoomManagementService,
     pub rooms_domain_service: MockRoomsDomainService,
 }
@@ -271,10 +315,12 @@ impl MockSidebarDomainServiceDependencies {
 impl From<MockSidebarDomainServiceDependencies> for SidebarDomainServiceDependencies {
     fn from(value: MockSidebarDomainServiceDependencies) -> Self {
         Self {
+            bookmarks_repo: Arc::new(value.bookmarks_repo),
             bookmarks_service: Arc::new(value.bookmarks_service),
             client_event_dispatcher: Arc::new(value.client_event_dispatcher),
             connected_rooms_repo: Arc::new(value.connected_rooms_repo),
             ctx: Arc::new(value.ctx),
+            messages_repo: Arc::new(value.messages_repo),
             room_management_service: Arc::new(value.room_management_service),
             rooms_domain_service: Arc::new(value.rooms_domain_service),
         }
@@ -326,6 +372,33 @@ impl From<MockRoomsDomainServiceDependencies> for RoomsDomainServiceDependencies
     }
 }
 
+#[derive(Default)]
+pub struct MockContactListDomainServiceDependencies {
+    pub client_event_dispatcher: MockClientEventDispatcherTrait,
+    pub contact_list_repo: MockContactListRepository,
+    pub contact_list_service: MockContactListService,
+    pub ctx: AppContext,
+    pub presence_sub_requests_repo: MockPresenceSubRequestsRepository,
+}
+
+impl MockContactListDomainServiceDependencies {
+    pub fn into_deps(self) -> ContactListDomainServiceDependencies {
+        ContactListDomainServiceDependencies::from(self)
+    }
+}
+
+impl From<MockContactListDomainServiceDependencies> for ContactListDomainServiceDependencies {
+    fn from(value: MockContactListDomainServiceDependencies) -> Self {
+        Self {
+            ctx: Arc::new(value.ctx),
+            client_event_dispatcher: Arc::new(value.client_event_dispatcher),
+            contact_list_repo: Arc::new(value.contact_list_repo),
+            contact_list_service: Arc::new(value.contact_list_service),
+            presence_sub_requests_repo: Arc::new(value.presence_sub_requests_repo),
+        }
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(Default)]
 pub struct MockUserInfoDomainServiceDependencies {
@@ -361,15 +434,59 @@ impl From<MockUserInfoDomainServiceDependencies> for UserInfoDomainServiceDepend
     }
 }
 
+#[derive(Derivative)]
+#[derivative(Default)]
+pub struct MockEncryptionDomainServiceDependencies {
+    pub client_event_dispatcher: MockClientEventDispatcherTrait,
+    pub ctx: AppContext,
+    pub encryption_keys_repo: MockEncryptionKeysRepository,
+    pub encryption_service: MockEncryptionService,
+    pub message_repo: MockMessagesRepository,
+    pub messaging_service: MockMessagingService,
+    pub session_repo: MockSessionRepository,
+    #[derivative(Default(value = "Arc::new(ConstantTimeProvider::new(mock_reference_date()))"))]
+    pub time_provider: DynTimeProvider,
+    pub user_device_repo: MockUserDeviceRepository,
+    pub user_device_service: MockUserDeviceService,
+}
+
+impl MockEncryptionDomainServiceDependencies {
+    pub fn into_deps(self) -> EncryptionDomainServiceDependencies {
+        EncryptionDomainServiceDependencies::from(self)
+    }
+}
+
+impl From<MockEncryptionDomainServiceDependencies> for EncryptionDomainServiceDependencies {
+    fn from(value: MockEncryptionDomainServiceDependencies) -> Self {
+        Self {
+            client_event_dispatcher: Arc::new(value.client_event_dispatcher),
+            ctx: Arc::new(value.ctx),
+            encryption_keys_repo: Arc::new(value.encryption_keys_repo),
+            encryption_service: Arc::new(value.encryption_service),
+            message_repo: Arc::new(value.message_repo),
+            messaging_service: Arc::new(value.messaging_service),
+            rng_provider: Arc::new(StepRngProvider::default()),
+            session_repo: Arc::new(value.session_repo),
+            time_provider: Arc::new(value.time_provider),
+            user_device_id_provider: Arc::new(IncrementingUserDeviceIdProvider::new(1)),
+            user_device_repo: Arc::new(value.user_device_repo),
+            user_device_service: Arc::new(value.user_device_service),
+        }
+    }
+}
+
 #[derive(Derivative)]
 #[derivative(Default)]
 pub struct MockRoomFactoryDependencies {
+    pub account_settings_repo: MockAccountSettingsRepository,
     pub attributes_service: MockRoomAttributesService,
     pub bookmarks_service: MockBookmarksService,
     pub client_event_dispatcher: MockClientEventDispatcherTrait,
     pub ctx: AppContext,
     pub drafts_repo: MockDraftsRepository,
     pub encryption_domain_service: MockEncryptionDomainService,
+    #[derivative(Default(value = "Arc::new(IncrementingIDProvider::new(\"id\"))"))]
+    pub id_provider: DynIDProvider,
     #[derivative(Default(
         value = "Arc::new(WrappingMessageIdProvider::incrementing(\"msg-id\"))"
     ))]
@@ -378,6 +495,9 @@ pub struct MockRoomFactoryDependencies {
     pub message_repo: MockMessagesRepository,
     pub messaging_service: MockMessagingService,
     pub participation_service: MockRoomParticipationService,
+    #[derivative(Default(value = "empty_pending_messages_repo()"))]
+    pub pending_messages_repo: MockPendingMessagesRepository,
+    pub room_management_service: MockRoomManagementService,
     pub synced_room_settings_service: MockSyncedRoomSettingsService,
     pub sidebar_domain_service: MockSidebarDomainService,
     #[derivative(Default(value = "Arc::new(ConstantTimeProvider::new(mock_reference_date()))"))]
@@ -386,16 +506,20 @@ pub struct MockRoomFactoryDependencies {
 }
 
 pub struct MockSealedRoomFactoryDependencies {
+    pub account_settings_repo: DynAccountSettingsRepository,
     pub bookmarks_service: DynBookmarksService,
     pub client_event_dispatcher: DynClientEventDispatcher,
     pub ctx: DynAppContext,
     pub drafts_repo: DynDraftsRepository,
     pub encryption_domain_service: DynEncryptionDomainService,
+    pub id_provider: DynIDProvider,
     pub message_id_provider: DynMessageIdProvider,
     pub message_archive_service: DynMessageArchiveService,
     pub message_repo: DynMessagesRepository,
     pub messaging_service: DynMessagingService,
     pub participation_service: DynRoomParticipationService,
+    pub pending_messages_repo: DynPendingMessagesRepository,
+    pub room_management_service: DynRoomManagementService,
     pub synced_room_settings_service: DynSyncedRoomSettingsService,
     pub sidebar_domain_service: DynSidebarDomainService,
     pub time_provider: DynTimeProvider,
@@ -406,16 +530,20 @@ pub struct MockSealedRoomFactoryDependencies {
 impl From<MockRoomFactoryDependencies> for MockSealedRoomFactoryDependencies {
     fn from(value: MockRoomFactoryDependencies) -> Self {
         Self {
+            account_settings_repo: Arc::new(value.account_settings_repo),
             bookmarks_service: Arc::new(value.bookmarks_service),
             client_event_dispatcher: Arc::new(value.client_event_dispatcher),
             ctx: Arc::new(value.ctx),
             drafts_repo: Arc::new(value.drafts_repo),
             encryption_domain_service: Arc::new(value.encryption_domain_service),
+            id_provider: value.id_provider,
             message_id_provider: value.message_id_provider,
             message_archive_service: Arc::new(value.message_archive_service),
             message_repo: Arc::new(value.message_repo),
             messaging_service: Arc::new(value.messaging_service),
             participation_service: Arc::new(value.participation_service),
+            pending_messages_repo: Arc::new(value.pending_messages_repo),
+            room_management_service: Arc::new(value.room_management_service),
             synced_room_settings_service: Arc::new(value.synced_room_settings_service),
             sidebar_domain_service: Arc::new(value.sidebar_domain_service),
             time_provider: Arc::new(value.time_provider),
@@ -429,17 +557,21 @@ impl From<MockSealedRoomFactoryDependencies> for RoomFactory {
     fn from(value: MockSealedRoomFactoryDependencies) -> Self {
         RoomFactory::new(Arc::new(move |data| {
             RoomInner {
+                account_settings_repo: value.account_settings_repo.clone(),
                 attributes_service: value.topic_service.clone(),
                 client_event_dispatcher: value.client_event_dispatcher.clone(),
                 ctx: value.ctx.clone(),
                 data: data.clone(),
                 drafts_repo: value.drafts_repo.clone(),
                 encryption_domain_service: value.encryption_domain_service.clone(),
+                id_provider: value.id_provider.clone(),
                 message_id_provider: value.message_id_provider.clone(),
                 message_archive_service: value.message_archive_service.clone(),
                 message_repo: value.message_repo.clone(),
                 messaging_service: value.messaging_service.clone(),
                 participation_service: value.participation_service.clone(),
+                pending_messages_repo: value.pending_messages_repo.clone(),
+                room_management_service: value.room_management_service.clone(),
                 synced_room_settings_service: value.synced_room_settings_service.clone(),
                 sidebar_domain_service: value.sidebar_domain_service.clone(),
                 time_provider: value.time_provider.clone(),
@@ -463,3 +595,27 @@ impl RoomFactory {
         ))
     }
 }
+
+/// Returns a repo with an empty outbox, since the outbox is consulted whenever messages are
+/// loaded.
+fn empty_pending_messages_repo() -> MockPendingMessagesRepository {
+    let mut repo = MockPendingMessagesRepository::new();
+    repo.expect_get_all_in_room()
+        .returning(|_, _| Box::pin(async { Ok(vec![]) }));
+    repo
+}
+
+/// Returns a repo that accepts all writes, since the local bookmarks cache is updated alongside
+/// every change to the remote bookmarks.
+fn accepting_bookmarks_repo() -> MockBookmarksRepository {
+    let mut repo = MockBookmarksRepository::new();
+    repo.expect_replace_all()
+        .returning(|_, _| Box::pin(async { Ok(()) }));
+    repo.expect_put()
+        .returning(|_, _| Box::pin(async { Ok(()) }));
+    repo.expect_delete()
+        .returning(|_, _| Box::pin(async { Ok(()) }));
+    repo.expect_clear_cache()
+        .returning(|_| Box::pin(async { Ok(()) }));
+    repo
+}
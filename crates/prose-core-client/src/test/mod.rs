@@ -12,8 +12,10 @@ use minidom::Element;
 pub use constant_time_provider::ConstantTimeProvider;
 pub use message_builder::MessageBuilder;
 pub use mock_app_dependencies::{
-    MockAppDependencies, MockRoomFactoryDependencies, MockRoomsDomainServiceDependencies,
-    MockSidebarDomainServiceDependencies, MockUserInfoDomainServiceDependencies,
+    MockAppDependencies, MockContactListDomainServiceDependencies,
+    MockEncryptionDomainServiceDependencies, MockRoomFactoryDependencies,
+    MockRoomsDomainServiceDependencies, MockSidebarDomainServiceDependencies,
+    MockUserInfoDomainServiceDependencies,
 };
 use prose_xmpp::test::BareJidTestAdditions;
 use prose_xmpp::Client;
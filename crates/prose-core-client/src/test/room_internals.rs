@@ -106,12 +106,13 @@ impl Room {
 
     pub fn is_disconnected(&self) -> DisconnectedState {
         match self.state() {
-            RoomState::Pending | RoomState::Connecting | RoomState::Connected => {
-                DisconnectedState {
-                    is_disconnected: false,
-                    can_retry: false,
-                }
-            }
+            RoomState::Pending
+            | RoomState::Invited { .. }
+            | RoomState::Connecting
+            | RoomState::Connected => DisconnectedState {
+                is_disconnected: false,
+                can_retry: false,
+            },
             RoomState::Disconnected { can_retry, .. } => DisconnectedState {
                 is_disconnected: true,
                 can_retry,
@@ -133,6 +134,7 @@ impl Participant {
             name: Default::default(),
             is_self: false,
             affiliation: RoomAffiliation::Owner,
+            role: Default::default(),
             compose_state: Default::default(),
             compose_state_updated: Default::default(),
             availability: Availability::Unavailable,
@@ -151,6 +153,7 @@ impl Participant {
             name: Default::default(),
             is_self: false,
             affiliation: RoomAffiliation::Owner,
+            role: Default::default(),
             compose_state: Default::default(),
             compose_state_updated: Default::default(),
             availability: Availability::Unavailable,
@@ -43,6 +43,8 @@ impl RoomSessionInfo {
                 room_type,
                 mam_version: None,
                 supports_self_ping_optimization: false,
+                supports_message_moderation: false,
+                supports_chat_markers: false,
             },
             topic: None,
             user_nickname: mock_data::account_jid().username().to_string(),
@@ -31,6 +31,14 @@ pub fn coalesce_client_events(events: &mut Vec<ClientEvent>) {
             true
         }
         (ClientEvent::AccountInfoChanged, ClientEvent::AccountInfoChanged) => true,
+        (ClientEvent::CacheReset, ClientEvent::CacheReset) => true,
+        (
+            ClientEvent::UserDevicesChanged { ids: ids_a },
+            ClientEvent::UserDevicesChanged { ids: ids_b },
+        ) => {
+            ids_b.extend(ids_a.drain(..));
+            true
+        }
         (
             ClientEvent::RoomChanged {
                 room: room_a,
@@ -50,7 +58,10 @@ pub fn coalesce_client_events(events: &mut Vec<ClientEvent>) {
         (ClientEvent::BlockListChanged, _) => false,
         (ClientEvent::AvatarChanged { .. }, _) => false,
         (ClientEvent::AccountInfoChanged, _) => false,
+        (ClientEvent::UserDevicesChanged { .. }, _) => false,
+        (ClientEvent::NewDeviceDetected { .. }, _) => false,
         (ClientEvent::RoomChanged { .. }, _) => false,
+        (ClientEvent::CacheReset, _) => false,
     });
 }
 
@@ -88,21 +99,72 @@ fn should_dedup_room_events(
         }
         (ClientRoomEventType::MessagesNeedReload, ClientRoomEventType::MessagesNeedReload) => true,
         (ClientRoomEventType::AttributesChanged, ClientRoomEventType::AttributesChanged) => true,
-        (ClientRoomEventType::ParticipantsChanged, ClientRoomEventType::ParticipantsChanged) => {
+        (
+            ClientRoomEventType::ParticipantsChanged { ids: ids_a },
+            ClientRoomEventType::ParticipantsChanged { ids: ids_b },
+        ) => {
+            ids_b.extend(ids_a.drain(..));
             true
         }
         (
             ClientRoomEventType::ComposingUsersChanged,
             ClientRoomEventType::ComposingUsersChanged,
         ) => true,
+        (
+            ClientRoomEventType::PinnedMessagesChanged,
+            ClientRoomEventType::PinnedMessagesChanged,
+        ) => true,
+        (ClientRoomEventType::Destroyed, ClientRoomEventType::Destroyed) => true,
+        (
+            ClientRoomEventType::RemovedFromRoom { reason: reason_a },
+            ClientRoomEventType::RemovedFromRoom { reason: reason_b },
+        ) => reason_a == reason_b,
+        (
+            ClientRoomEventType::EncryptionRecipientsChanged,
+            ClientRoomEventType::EncryptionRecipientsChanged,
+        ) => true,
+        (
+            ClientRoomEventType::InvitationReceived { sender: sender_a },
+            ClientRoomEventType::InvitationReceived { sender: sender_b },
+        ) => sender_a == sender_b,
+        (
+            ClientRoomEventType::PrivateMessagesAppended {
+                occupant_id: occupant_a,
+                message_ids: ids_a,
+            },
+            ClientRoomEventType::PrivateMessagesAppended {
+                occupant_id: occupant_b,
+                message_ids: ids_b,
+            },
+        ) => {
+            if occupant_a != occupant_b {
+                return false;
+            }
+            ids_b.extend(ids_a.drain(..));
+            true
+        }
+        (
+            ClientRoomEventType::NotificationRequested { message_ids: ids_a },
+            ClientRoomEventType::NotificationRequested { message_ids: ids_b },
+        ) => {
+            ids_b.extend(ids_a.drain(..));
+            true
+        }
 
         (ClientRoomEventType::MessagesAppended { .. }, _) => false,
         (ClientRoomEventType::MessagesUpdated { .. }, _) => false,
         (ClientRoomEventType::MessagesDeleted { .. }, _) => false,
         (ClientRoomEventType::MessagesNeedReload, _) => false,
         (ClientRoomEventType::AttributesChanged, _) => false,
-        (ClientRoomEventType::ParticipantsChanged, _) => false,
+        (ClientRoomEventType::ParticipantsChanged { .. }, _) => false,
         (ClientRoomEventType::ComposingUsersChanged, _) => false,
+        (ClientRoomEventType::PinnedMessagesChanged, _) => false,
+        (ClientRoomEventType::Destroyed, _) => false,
+        (ClientRoomEventType::RemovedFromRoom { .. }, _) => false,
+        (ClientRoomEventType::EncryptionRecipientsChanged, _) => false,
+        (ClientRoomEventType::InvitationReceived { .. }, _) => false,
+        (ClientRoomEventType::PrivateMessagesAppended { .. }, _) => false,
+        (ClientRoomEventType::NotificationRequested { .. }, _) => false,
     }
 }
 
@@ -141,7 +203,10 @@ fn order_key_for_client_event(event: &ClientEvent) -> i32 {
         ClientEvent::BlockListChanged => 5,
         ClientEvent::AvatarChanged { .. } => 6,
         ClientEvent::AccountInfoChanged => 7,
-        ClientEvent::RoomChanged { .. } => 8,
+        ClientEvent::UserDevicesChanged { .. } => 8,
+        ClientEvent::NewDeviceDetected { .. } => 9,
+        ClientEvent::RoomChanged { .. } => 10,
+        ClientEvent::CacheReset => 11,
     }
 }
 
@@ -152,8 +217,15 @@ fn order_key_for_room_event(event: &ClientRoomEventType) -> i32 {
         ClientRoomEventType::MessagesDeleted { .. } => 2,
         ClientRoomEventType::MessagesNeedReload => 3,
         ClientRoomEventType::AttributesChanged => 4,
-        ClientRoomEventType::ParticipantsChanged => 5,
+        ClientRoomEventType::ParticipantsChanged { .. } => 5,
         ClientRoomEventType::ComposingUsersChanged => 6,
+        ClientRoomEventType::PinnedMessagesChanged => 7,
+        ClientRoomEventType::Destroyed => 8,
+        ClientRoomEventType::RemovedFromRoom { .. } => 9,
+        ClientRoomEventType::EncryptionRecipientsChanged => 10,
+        ClientRoomEventType::InvitationReceived { .. } => 11,
+        ClientRoomEventType::PrivateMessagesAppended { .. } => 12,
+        ClientRoomEventType::NotificationRequested { .. } => 13,
     }
 }
 
@@ -8,8 +8,9 @@ use mockall::predicate;
 
 use prose_core_client::domain::rooms::models::Room;
 use prose_core_client::domain::settings::models::AccountSettings;
-use prose_core_client::domain::shared::models::{MucId, OccupantId, UserId};
-use prose_core_client::dtos::Availability;
+use prose_core_client::domain::shared::models::{AvatarId, MucId, OccupantId, UserId};
+use prose_core_client::domain::user_info::models::{AvatarError, AvatarMetadata, UserProfileError};
+use prose_core_client::dtos::{Availability, UserProfile};
 use prose_core_client::services::AccountService;
 use prose_core_client::test::{mock_data, MockAppDependencies};
 use prose_core_client::{muc_id, occupant_id, user_id, ClientEvent};
@@ -25,8 +26,11 @@ async fn test_set_availability_updates_settings() -> Result<()> {
             predicate::always(),
             predicate::always(),
             predicate::eq(Availability::Away),
+            predicate::eq(None),
+            predicate::eq(0),
+            predicate::eq(None),
         )
-        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+        .return_once(|_, _, _, _, _, _| Box::pin(async { Ok(()) }));
 
     deps.connected_rooms_repo
         .expect_get_all()
@@ -53,7 +57,7 @@ async fn test_set_availability_updates_settings() -> Result<()> {
         .return_once(|_| ());
 
     let service = AccountService::from(&deps.into_deps());
-    service.set_availability(Availability::Away).await?;
+    service.set_availability(Availability::Away, None).await?;
 
     Ok(())
 }
@@ -69,8 +73,11 @@ async fn test_sends_availability_to_all_rooms() -> Result<()> {
             predicate::eq(None),
             predicate::always(),
             predicate::eq(Availability::Away),
+            predicate::eq(None),
+            predicate::eq(0),
+            predicate::eq(None),
         )
-        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+        .return_once(|_, _, _, _, _, _| Box::pin(async { Ok(()) }));
 
     deps.connected_rooms_repo
         .expect_get_all()
@@ -92,8 +99,11 @@ async fn test_sends_availability_to_all_rooms() -> Result<()> {
             predicate::eq(Some(occupant_id!("prc@conf.prose.org/nick"))),
             predicate::always(),
             predicate::eq(Availability::Away),
+            predicate::eq(None),
+            predicate::eq(0),
+            predicate::eq(None),
         )
-        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+        .return_once(|_, _, _, _, _, _| Box::pin(async { Ok(()) }));
     deps.user_account_service
         .expect_set_availability()
         .once()
@@ -101,8 +111,11 @@ async fn test_sends_availability_to_all_rooms() -> Result<()> {
             predicate::eq(Some(occupant_id!("pc@conf.prose.org/nick"))),
             predicate::always(),
             predicate::eq(Availability::Away),
+            predicate::eq(None),
+            predicate::eq(0),
+            predicate::eq(None),
         )
-        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+        .return_once(|_, _, _, _, _, _| Box::pin(async { Ok(()) }));
     deps.user_account_service
         .expect_set_availability()
         .once()
@@ -110,8 +123,11 @@ async fn test_sends_availability_to_all_rooms() -> Result<()> {
             predicate::eq(Some(occupant_id!("group@conf.prose.org/nick"))),
             predicate::always(),
             predicate::eq(Availability::Away),
+            predicate::eq(None),
+            predicate::eq(0),
+            predicate::eq(None),
         )
-        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+        .return_once(|_, _, _, _, _, _| Box::pin(async { Ok(()) }));
 
     deps.account_settings_repo
         .expect_update()
@@ -125,7 +141,244 @@ async fn test_sends_availability_to_all_rooms() -> Result<()> {
         .return_once(|_| ());
 
     let service = AccountService::from(&deps.into_deps());
-    service.set_availability(Availability::Away).await?;
+    service.set_availability(Availability::Away, None).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_oversized_avatar() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.ctx.config.max_avatar_size = 4;
+
+    // No expectations on user_account_service, i.e. nothing must be uploaded.
+    let service = AccountService::from(&deps.into_deps());
+    let err = service
+        .set_avatar(&[0u8; 5], None, None, "image/png")
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast_ref::<AvatarError>(),
+        Some(&AvatarError::TooLarge {
+            size: 5,
+            max_size: 4
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_unsupported_avatar_type() -> Result<()> {
+    let deps = MockAppDependencies::default();
+
+    let service = AccountService::from(&deps.into_deps());
+    let err = service
+        .set_avatar(&[0u8; 5], None, None, "image/tiff")
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast_ref::<AvatarError>(),
+        Some(&AvatarError::UnsupportedMimeType {
+            mime_type: "image/tiff".to_string()
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_avatar_exceeding_max_dimension() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.ctx.config.max_avatar_dimension = 100;
+
+    let service = AccountService::from(&deps.into_deps());
+    let err = service
+        .set_avatar(&[0u8; 5], Some(100), Some(101), "image/png")
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast_ref::<AvatarError>(),
+        Some(&AvatarError::DimensionsTooLarge {
+            width: 100,
+            height: 101,
+            max_dimension: 100
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reads_avatar_dimensions_from_image() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.ctx.config.max_avatar_dimension = 100;
+
+    let mut image_data = Vec::new();
+    image::RgbImage::new(200, 150).write_to(
+        &mut std::io::Cursor::new(&mut image_data),
+        image::ImageFormat::Png,
+    )?;
+
+    let service = AccountService::from(&deps.into_deps());
+    let err = service
+        .set_avatar(&image_data, None, None, "image/png")
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast_ref::<AvatarError>(),
+        Some(&AvatarError::DimensionsTooLarge {
+            width: 200,
+            height: 150,
+            max_dimension: 100
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_avatar_with_unknown_dimensions() -> Result<()> {
+    let deps = MockAppDependencies::default();
+
+    let service = AccountService::from(&deps.into_deps());
+    let err = service
+        .set_avatar(&[0u8; 5], None, None, "image/png")
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast_ref::<AvatarError>(),
+        Some(&AvatarError::UnknownDimensions)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_avatar() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    // SHA-1 of [0, 0, 0, 0, 0]
+    let checksum = AvatarId::from_str_unchecked("a10909c2cdcaf5adb7e6b092a4faba558b62bd96");
+    let expected_metadata = AvatarMetadata {
+        bytes: 5,
+        mime_type: "image/png".to_string(),
+        checksum: checksum.clone(),
+        width: Some(32),
+        height: Some(32),
+        url: None,
+    };
+
+    deps.user_account_service
+        .expect_set_avatar_image()
+        .once()
+        .with(predicate::eq(checksum), predicate::always())
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    deps.user_account_service
+        .expect_set_avatar_metadata()
+        .once()
+        .with(predicate::eq(expected_metadata.clone()))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+    deps.avatar_repo
+        .expect_set()
+        .once()
+        .return_once(|_, _, _, _, _| Box::pin(async { Ok(()) }));
+    deps.user_info_domain_service
+        .expect_handle_avatar_changed()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::AvatarChanged {
+            ids: vec![mock_data::account_jid().into_user_id()],
+        }))
+        .return_once(|_| ());
+
+    let service = AccountService::from(&deps.into_deps());
+    let metadata = service
+        .set_avatar(&[0u8; 5], Some(32), Some(32), "image/png")
+        .await?;
+
+    assert_eq!(metadata, expected_metadata);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_avatar() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.user_account_service
+        .expect_delete_avatar_metadata()
+        .once()
+        .return_once(|| Box::pin(async { Ok(()) }));
+
+    deps.user_info_domain_service
+        .expect_handle_avatar_changed()
+        .once()
+        .with(
+            predicate::eq(mock_data::account_jid().into_user_id()),
+            predicate::eq(None),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    let service = AccountService::from(&deps.into_deps());
+    service.delete_avatar().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_profile_with_invalid_email() -> Result<()> {
+    let deps = MockAppDependencies::default();
+
+    // No expectations on user_account_service, i.e. nothing must be published.
+    let service = AccountService::from(&deps.into_deps());
+    let err = service
+        .set_profile(UserProfile {
+            first_name: Some("Jane".to_string()),
+            email: Some("jane.prose.org".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.downcast_ref::<UserProfileError>(),
+        Some(&UserProfileError::InvalidEmail(
+            "jane.prose.org".to_string()
+        ))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_delete_profile() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.user_account_service
+        .expect_delete_profile()
+        .once()
+        .return_once(|| Box::pin(async { Ok(()) }));
+
+    deps.user_info_domain_service
+        .expect_handle_user_profile_changed()
+        .once()
+        .with(
+            predicate::eq(mock_data::account_jid().into_user_id()),
+            predicate::eq(None),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    let service = AccountService::from(&deps.into_deps());
+    service.delete_profile().await?;
 
     Ok(())
 }
@@ -0,0 +1,194 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use chrono::{TimeDelta, TimeZone, Utc};
+use mockall::predicate;
+use pretty_assertions::assert_eq;
+
+use prose_core_client::domain::messaging::models::{
+    ArchivedMessageRef, PendingMessage, PendingMessageState,
+};
+use prose_core_client::domain::rooms::models::Room;
+use prose_core_client::domain::shared::models::{MucId, RetentionPolicy, RoomId, UserId};
+use prose_core_client::domain::user_info::models::PruneStats;
+use prose_core_client::dtos::Availability;
+use prose_core_client::services::CacheService;
+use prose_core_client::test::{mock_data, MessageBuilder, MockAppDependencies};
+use prose_core_client::{muc_id, user_id};
+
+#[tokio::test]
+async fn test_applies_retention_policy() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    // Reference date is 2021-09-06 00:00:00 UTC.
+    let last_read_room = Room::group(muc_id!("read@conf.prose.org"));
+    last_read_room.with_settings_mut(|settings| {
+        settings.last_read_message = Some(ArchivedMessageRef {
+            stanza_id: MessageBuilder::stanza_id_for_index(1),
+            timestamp: Utc.with_ymd_and_hms(2021, 09, 04, 12, 0, 0).unwrap(),
+        })
+    });
+    let pending_room = Room::direct_message(user_id!("a@prose.org"), Availability::Available);
+    pending_room.with_settings_mut(|settings| {
+        settings.last_read_message = Some(ArchivedMessageRef {
+            stanza_id: MessageBuilder::stanza_id_for_index(2),
+            timestamp: Utc.with_ymd_and_hms(2021, 09, 05, 12, 0, 0).unwrap(),
+        })
+    });
+    let unread_room = Room::group(muc_id!("unread@conf.prose.org"));
+
+    deps.connected_rooms_repo
+        .expect_get_all()
+        .once()
+        .return_once(move |_| vec![last_read_room, pending_room, unread_room]);
+
+    deps.pending_messages_repo = Default::default();
+    deps.pending_messages_repo
+        .expect_get_all_in_room()
+        .times(2)
+        .returning(|_, room_id| {
+            let pending_messages = if *room_id == RoomId::from(user_id!("a@prose.org")) {
+                vec![PendingMessage {
+                    id: MessageBuilder::id_for_index(3),
+                    room_id: room_id.clone(),
+                    body: None,
+                    attachments: vec![],
+                    reply_to: None,
+                    timestamp: Utc.with_ymd_and_hms(2021, 09, 03, 0, 0, 0).unwrap(),
+                    state: PendingMessageState::Pending,
+                }]
+            } else {
+                vec![]
+            };
+            Box::pin(async move { Ok(pending_messages) })
+        });
+
+    deps.messages_repo
+        .expect_delete_messages_before()
+        .once()
+        .with(
+            predicate::eq(mock_data::account()),
+            predicate::eq(RoomId::from(muc_id!("read@conf.prose.org"))),
+            predicate::eq(Utc.with_ymd_and_hms(2021, 09, 04, 12, 0, 0).unwrap()),
+            predicate::always(),
+        )
+        .return_once(|_, _, _, _| Box::pin(async { Ok(3) }));
+    deps.messages_repo
+        .expect_delete_messages_before()
+        .once()
+        .with(
+            predicate::eq(mock_data::account()),
+            predicate::eq(RoomId::from(user_id!("a@prose.org"))),
+            predicate::eq(Utc.with_ymd_and_hms(2021, 09, 03, 0, 0, 0).unwrap()),
+            predicate::always(),
+        )
+        .return_once(|_, _, _, _| Box::pin(async { Ok(1) }));
+
+    deps.avatar_repo
+        .expect_evict()
+        .once()
+        .with(predicate::eq(mock_data::account()), predicate::eq(1024))
+        .return_once(|_, _| Box::pin(async { Ok(2) }));
+
+    let service = CacheService::from(&deps.into_deps());
+    let stats = service
+        .apply_retention_policy(&RetentionPolicy {
+            max_message_age: Some(TimeDelta::days(1)),
+            max_messages_per_room: None,
+            max_avatar_cache_bytes: Some(1024),
+        })
+        .await?;
+
+    assert_eq!(
+        PruneStats {
+            user_profiles: 0,
+            avatars: 2,
+            messages: 4,
+        },
+        stats
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_keeps_newest_messages_per_room() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    let last_read_message = ArchivedMessageRef {
+        stanza_id: MessageBuilder::stanza_id_for_index(1),
+        timestamp: Utc.with_ymd_and_hms(2021, 09, 05, 12, 0, 0).unwrap(),
+    };
+    let full_room = Room::group(muc_id!("full@conf.prose.org"));
+    full_room
+        .with_settings_mut(|settings| settings.last_read_message = Some(last_read_message.clone()));
+    let small_room = Room::group(muc_id!("small@conf.prose.org"));
+    small_room.with_settings_mut(|settings| settings.last_read_message = Some(last_read_message));
+
+    deps.connected_rooms_repo
+        .expect_get_all()
+        .once()
+        .return_once(move |_| vec![full_room, small_room]);
+
+    deps.pending_messages_repo = Default::default();
+    deps.pending_messages_repo
+        .expect_get_all_in_room()
+        .once()
+        .returning(|_, _| Box::pin(async { Ok(vec![]) }));
+
+    deps.messages_repo
+        .expect_get_nth_latest_message_timestamp()
+        .once()
+        .with(
+            predicate::eq(mock_data::account()),
+            predicate::eq(RoomId::from(muc_id!("full@conf.prose.org"))),
+            predicate::eq(2),
+        )
+        .return_once(|_, _, _| {
+            Box::pin(async { Ok(Some(Utc.with_ymd_and_hms(2021, 09, 04, 0, 0, 0).unwrap())) })
+        });
+    // The room contains fewer messages than allowed, so nothing is deleted…
+    deps.messages_repo
+        .expect_get_nth_latest_message_timestamp()
+        .once()
+        .with(
+            predicate::eq(mock_data::account()),
+            predicate::eq(RoomId::from(muc_id!("small@conf.prose.org"))),
+            predicate::eq(2),
+        )
+        .return_once(|_, _, _| Box::pin(async { Ok(None) }));
+
+    deps.messages_repo
+        .expect_delete_messages_before()
+        .once()
+        .with(
+            predicate::eq(mock_data::account()),
+            predicate::eq(RoomId::from(muc_id!("full@conf.prose.org"))),
+            predicate::eq(Utc.with_ymd_and_hms(2021, 09, 04, 0, 0, 0).unwrap()),
+            predicate::always(),
+        )
+        .return_once(|_, _, _, _| Box::pin(async { Ok(5) }));
+
+    let service = CacheService::from(&deps.into_deps());
+    let stats = service
+        .apply_retention_policy(&RetentionPolicy {
+            max_message_age: None,
+            max_messages_per_room: Some(2),
+            max_avatar_cache_bytes: None,
+        })
+        .await?;
+
+    assert_eq!(
+        PruneStats {
+            user_profiles: 0,
+            avatars: 0,
+            messages: 5,
+        },
+        stats
+    );
+
+    Ok(())
+}
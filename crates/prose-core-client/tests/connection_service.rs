@@ -4,20 +4,25 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 use anyhow::Result;
-use mockall::predicate;
+use mockall::{predicate, Sequence};
 use secrecy::{ExposeSecret, SecretString};
+use tokio::time::Instant;
 
 use prose_core_client::app::deps::DynAppContext;
 use prose_core_client::app::services::ConnectionService;
-use prose_core_client::domain::connection::models::ServerFeatures;
+use prose_core_client::domain::connection::models::{ReconnectPolicy, ServerFeatures};
 use prose_core_client::domain::settings::models::AccountSettings;
-use prose_core_client::domain::shared::models::{AccountId, Availability, UserId, UserResourceId};
+use prose_core_client::domain::shared::models::{
+    AccountId, Availability, ConnectionState, UserId, UserResourceId,
+};
+use prose_core_client::domain::user_info::models::UserStatus;
 use prose_core_client::test::MockAppDependencies;
 use prose_core_client::{account_id, user_id, user_resource_id, ClientEvent, ConnectionEvent};
 use prose_xmpp::test::ConstantIDProvider;
-use prose_xmpp::{bare, ConnectionError};
+use prose_xmpp::{bare, ConnectionError, SessionKind};
 
 #[tokio::test]
 async fn test_starts_available_and_generates_resource() -> Result<()> {
@@ -83,8 +88,11 @@ async fn test_starts_available_and_generates_resource() -> Result<()> {
             predicate::always(),
             predicate::always(),
             predicate::eq(Availability::Available),
+            predicate::eq(None),
+            predicate::eq(0),
+            predicate::eq(None),
         )
-        .return_once(|_, _, _| Box::pin(async { Ok(Default::default()) }));
+        .return_once(|_, _, _, _, _, _| Box::pin(async { Ok(Default::default()) }));
     deps.connection_service
         .expect_load_server_features()
         .once()
@@ -154,6 +162,417 @@ async fn test_starts_available_and_generates_resource() -> Result<()> {
 async fn test_restores_availability_and_resource() -> Result<()> {
     let mut deps = MockAppDependencies::default();
 
+    let mut settings = AccountSettings::default();
+    settings.availability = Availability::DoNotDisturb;
+    settings.resource = Some("restored-res".to_string());
+    expect_restored_session(&mut deps, settings, 0);
+
+    let deps = deps.into_deps();
+
+    let service = ConnectionService::from(&deps);
+
+    service
+        .connect(&user_id!("jane.doe@prose.org"), "my-password".into())
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restores_status_message_and_priority() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.ctx.config.presence_priority = 5;
+
+    let mut settings = AccountSettings::default();
+    settings.availability = Availability::DoNotDisturb;
+    settings.resource = Some("restored-res".to_string());
+    settings.status_message = Some("Back at 2pm".to_string());
+    expect_restored_session(&mut deps, settings, 5);
+
+    let deps = deps.into_deps();
+
+    let service = ConnectionService::from(&deps);
+
+    service
+        .connect(&user_id!("jane.doe@prose.org"), "my-password".into())
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restores_user_activity() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    let mut settings = AccountSettings::default();
+    settings.availability = Availability::DoNotDisturb;
+    settings.resource = Some("restored-res".to_string());
+    settings.user_status = Some(UserStatus {
+        emoji: "🍕".to_string(),
+        status: Some("Eating pizza".to_string()),
+    });
+    expect_restored_session(&mut deps, settings, 0);
+
+    deps.user_account_service
+        .expect_set_user_activity()
+        .once()
+        .return_once(|status| {
+            assert_eq!(
+                status,
+                Some(&UserStatus {
+                    emoji: "🍕".to_string(),
+                    status: Some("Eating pizza".to_string()),
+                })
+            );
+            Box::pin(async { Ok(()) })
+        });
+
+    let deps = deps.into_deps();
+
+    let service = ConnectionService::from(&deps);
+
+    service
+        .connect(&user_id!("jane.doe@prose.org"), "my-password".into())
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+/// Test that the ConnectionService sets the connected_jid on AppContext before it
+/// starts connecting and clears it if the connection fails. It's important that the connected_jid
+/// is set immediately so that when events come in while the connection is in progress,
+/// event handlers already have access to the connected_jid.
+async fn test_connection_failure() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    let ctx = Arc::new(OnceLock::<DynAppContext>::new());
+
+    deps.short_id_provider = Arc::new(ConstantIDProvider::new("resource-id"));
+
+    deps.offline_message_repo
+        .expect_drain()
+        .once()
+        .return_once(|| vec![]);
+
+    deps.account_settings_repo
+        .expect_get()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(Default::default()) }));
+    {
+        let ctx = ctx.clone();
+        deps.connection_service
+            .expect_connect()
+            .once()
+            .return_once(move |_, _| {
+                assert_eq!(
+                    ctx.get().unwrap().connected_id().ok(),
+                    Some(user_resource_id!("jane.doe@prose.org/resource-id"))
+                );
+                Box::pin(async {
+                    Err(ConnectionError::Generic {
+                        msg: "Failure".to_string(),
+                    })
+                })
+            });
+    }
+
+    let deps = deps.into_deps();
+    ctx.set(deps.ctx.clone()).map_err(|_| ()).unwrap();
+
+    let service = ConnectionService::from(&deps);
+
+    *deps.ctx.connection_properties.write() = None;
+    assert!(deps.ctx.connected_id().is_err());
+    assert!(deps.ctx.muc_service().is_err());
+
+    assert!(service
+        .connect(&user_id!("jane.doe@prose.org"), "my-password".into())
+        .await
+        .is_err());
+
+    assert!(deps.ctx.connected_id().is_err());
+    assert!(deps.ctx.muc_service().is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_skips_session_setup_when_session_was_resumed() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.short_id_provider = Arc::new(ConstantIDProvider::new("resource-id"));
+
+    deps.offline_message_repo
+        .expect_drain()
+        .times(2)
+        .returning(|| vec![]);
+    deps.account_settings_repo
+        .expect_get()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(Default::default()) }));
+    deps.connection_service
+        .expect_connect()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(SessionKind::Resumed) }));
+
+    deps.contact_list_domain_service
+        .expect_load_contacts()
+        .never();
+    deps.connection_service
+        .expect_load_server_features()
+        .never();
+    deps.encryption_domain_service.expect_initialize().never();
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::ConnectionStatusChanged {
+            event: ConnectionEvent::Connect,
+        }))
+        .return_once(|_| ());
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::AccountInfoChanged))
+        .return_once(|_| ());
+
+    let deps = deps.into_deps();
+    let service = ConnectionService::from(&deps);
+
+    let connected_id = deps.ctx.connected_id()?;
+    let muc_service = deps.ctx.muc_service()?;
+
+    service
+        .connect(&user_id!("jane.doe@prose.org"), "my-password".into())
+        .await?;
+
+    // The properties of the previous session are still valid…
+    assert_eq!(deps.ctx.connected_id()?, connected_id);
+    assert_eq!(deps.ctx.muc_service()?, muc_service);
+    assert_eq!(deps.ctx.connection_state(), ConnectionState::Connected);
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_reconnects_after_connection_loss() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    let mut seq = Sequence::new();
+
+    deps.ctx.config.reconnect_policy = Some(reconnect_policy(Some(3)));
+    deps.ctx.reconnect_state.lock().credentials = Some((
+        user_id!("jane.doe@prose.org"),
+        SecretString::from("my-password".to_string()),
+    ));
+
+    deps.offline_message_repo
+        .expect_drain()
+        .times(2)
+        .returning(|| vec![]);
+    deps.account_settings_repo
+        .expect_get()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(Default::default()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::eq(ClientEvent::ConnectionStatusChanged {
+            event: ConnectionEvent::Reconnecting { attempt: 1 },
+        }))
+        .return_once(|_| ());
+    deps.connection_service
+        .expect_connect()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::always(),
+            predicate::function(|pw: &SecretString| pw.expose_secret() == "my-password"),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(SessionKind::Resumed) }));
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::eq(ClientEvent::ConnectionStatusChanged {
+            event: ConnectionEvent::Connect,
+        }))
+        .return_once(|_| ());
+
+    let deps = deps.into_deps();
+    let service = ConnectionService::from(&deps);
+
+    let start = Instant::now();
+    service.reconnect(None).await;
+
+    assert_eq!(start.elapsed(), Duration::from_secs(1));
+    assert_eq!(deps.ctx.connection_state(), ConnectionState::Connected);
+    assert!(!deps.ctx.reconnect_state.lock().is_reconnecting);
+    assert!(deps.ctx.reconnect_state.lock().credentials.is_some());
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_backs_off_and_gives_up_reconnecting_after_max_attempts() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    let mut seq = Sequence::new();
+
+    deps.ctx.config.reconnect_policy = Some(reconnect_policy(Some(3)));
+    deps.ctx.reconnect_state.lock().credentials = Some((
+        user_id!("jane.doe@prose.org"),
+        SecretString::from("my-password".to_string()),
+    ));
+
+    deps.offline_message_repo
+        .expect_drain()
+        .times(3)
+        .returning(|| vec![]);
+    deps.account_settings_repo
+        .expect_get()
+        .times(3)
+        .returning(|_| Box::pin(async { Ok(Default::default()) }));
+
+    for attempt in 1..=3 {
+        deps.client_event_dispatcher
+            .expect_dispatch_event()
+            .once()
+            .in_sequence(&mut seq)
+            .with(predicate::eq(ClientEvent::ConnectionStatusChanged {
+                event: ConnectionEvent::Reconnecting { attempt },
+            }))
+            .return_once(|_| ());
+        deps.connection_service
+            .expect_connect()
+            .once()
+            .in_sequence(&mut seq)
+            .return_once(|_, _| Box::pin(async { Err(ConnectionError::TimedOut) }));
+    }
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::eq(ClientEvent::ConnectionStatusChanged {
+            event: ConnectionEvent::Disconnect {
+                error: Some(ConnectionError::TimedOut),
+            },
+        }))
+        .return_once(|_| ());
+
+    let deps = deps.into_deps();
+    let service = ConnectionService::from(&deps);
+
+    let start = Instant::now();
+    service.reconnect(None).await;
+
+    // 1s + 2s + 4s (capped at max_delay)…
+    assert_eq!(start.elapsed(), Duration::from_secs(7));
+    assert!(!deps.ctx.reconnect_state.lock().is_reconnecting);
+    assert!(deps.ctx.reconnect_state.lock().credentials.is_none());
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_does_not_reconnect_after_invalid_credentials() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.ctx.config.reconnect_policy = Some(reconnect_policy(None));
+    deps.ctx.reconnect_state.lock().credentials = Some((
+        user_id!("jane.doe@prose.org"),
+        SecretString::from("my-password".to_string()),
+    ));
+
+    deps.connection_service.expect_connect().never();
+    deps.client_event_dispatcher.expect_dispatch_event().never();
+
+    let deps = deps.into_deps();
+    let service = ConnectionService::from(&deps);
+
+    service
+        .reconnect(Some(ConnectionError::InvalidCredentials))
+        .await;
+
+    assert!(deps.ctx.reconnect_state.lock().credentials.is_none());
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_disconnect_cancels_pending_reconnect() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.ctx.config.reconnect_policy = Some(reconnect_policy(None));
+    deps.ctx.reconnect_state.lock().credentials = Some((
+        user_id!("jane.doe@prose.org"),
+        SecretString::from("my-password".to_string()),
+    ));
+
+    deps.connection_service
+        .expect_disconnect()
+        .once()
+        .return_once(|| Box::pin(async {}));
+    deps.sidebar_domain_service
+        .expect_handle_disconnect()
+        .once()
+        .return_once(|| Box::pin(async { Ok(()) }));
+    deps.connection_service.expect_connect().never();
+    deps.client_event_dispatcher.expect_dispatch_event().never();
+
+    let deps = deps.into_deps();
+    let service = ConnectionService::from(&deps);
+
+    // The reconnect waits for its first delay, while we disconnect deliberately…
+    tokio::join!(service.reconnect(None), service.disconnect());
+
+    assert!(!deps.ctx.reconnect_state.lock().is_reconnecting);
+    assert!(deps.ctx.reconnect_state.lock().credentials.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_disconnect_forgets_cached_account() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.connection_service
+        .expect_disconnect()
+        .once()
+        .return_once(|| Box::pin(async {}));
+    deps.sidebar_domain_service
+        .expect_handle_disconnect()
+        .once()
+        .return_once(|| Box::pin(async { Ok(()) }));
+
+    let deps = deps.into_deps();
+    deps.ctx
+        .set_cached_account(Some(account_id!("jane.doe@prose.org")));
+
+    let service = ConnectionService::from(&deps);
+    service.disconnect().await;
+
+    assert!(deps.ctx.cached_account.read().is_none());
+
+    Ok(())
+}
+
+/// Sets up the expectations for a successful connect of jane.doe@prose.org with the stored
+/// `settings`, which must contain a resource. The presence is expected to be sent with the
+/// availability and status message of `settings` and `priority`.
+fn expect_restored_session(
+    deps: &mut MockAppDependencies,
+    settings: AccountSettings,
+    priority: i8,
+) {
+    let resource = settings
+        .resource
+        .clone()
+        .expect("Expected settings with a resource");
+    let availability = settings.availability;
+    let status_message = settings.status_message.clone();
+
     deps.offline_message_repo
         .expect_drain()
         .times(2)
@@ -188,19 +607,16 @@ async fn test_restores_availability_and_resource() -> Result<()> {
     deps.account_settings_repo
         .expect_get()
         .once()
-        .return_once(|_| {
-            Box::pin(async {
-                let mut account_settings = AccountSettings::default();
-                account_settings.availability = Availability::DoNotDisturb;
-                account_settings.resource = Some("restored-res".to_string());
-                Ok(account_settings)
-            })
-        });
+        .return_once(|_| Box::pin(async { Ok(settings) }));
     deps.connection_service
         .expect_connect()
         .once()
         .with(
-            predicate::eq(user_resource_id!("jane.doe@prose.org/restored-res")),
+            predicate::eq(
+                user_id!("jane.doe@prose.org")
+                    .with_resource(&resource)
+                    .unwrap(),
+            ),
             predicate::always(),
         )
         .return_once(|_, _| Box::pin(async { Ok(Default::default()) }));
@@ -218,9 +634,12 @@ async fn test_restores_availability_and_resource() -> Result<()> {
         .with(
             predicate::always(),
             predicate::always(),
-            predicate::eq(Availability::DoNotDisturb),
+            predicate::eq(availability),
+            predicate::eq(status_message),
+            predicate::eq(priority),
+            predicate::eq(None),
         )
-        .return_once(|_, _, _| Box::pin(async { Ok(Default::default()) }));
+        .return_once(|_, _, _, _, _, _| Box::pin(async { Ok(Default::default()) }));
     deps.connection_service
         .expect_load_server_features()
         .once()
@@ -232,12 +651,12 @@ async fn test_restores_availability_and_resource() -> Result<()> {
             predicate::eq(account_id!("jane.doe@prose.org")),
             predicate::always(),
         )
-        .return_once(|_, f| {
-            Box::pin(async {
+        .return_once(move |_, f| {
+            Box::pin(async move {
                 let mut settings = AccountSettings::default();
                 f(&mut settings);
-                assert_eq!(settings.availability, Availability::DoNotDisturb);
-                assert_eq!(settings.resource, Some("restored-res".to_string()));
+                assert_eq!(settings.availability, availability);
+                assert_eq!(settings.resource, Some(resource));
                 Ok(())
             })
         });
@@ -259,72 +678,13 @@ async fn test_restores_availability_and_resource() -> Result<()> {
         .once()
         .with(predicate::eq(ClientEvent::AccountInfoChanged))
         .return_once(|_| ());
-
-    let deps = deps.into_deps();
-
-    let service = ConnectionService::from(&deps);
-
-    service
-        .connect(&user_id!("jane.doe@prose.org"), "my-password".into())
-        .await?;
-
-    Ok(())
 }
 
-#[tokio::test]
-/// Test that the ConnectionService sets the connected_jid on AppContext before it
-/// starts connecting and clears it if the connection fails. It's important that the connected_jid
-/// is set immediately so that when events come in while the connection is in progress,
-/// event handlers already have access to the connected_jid.
-async fn test_connection_failure() -> Result<()> {
-    let mut deps = MockAppDependencies::default();
-    let ctx = Arc::new(OnceLock::<DynAppContext>::new());
-
-    deps.short_id_provider = Arc::new(ConstantIDProvider::new("resource-id"));
-
-    deps.offline_message_repo
-        .expect_drain()
-        .once()
-        .return_once(|| vec![]);
-
-    deps.account_settings_repo
-        .expect_get()
-        .once()
-        .return_once(|_| Box::pin(async { Ok(Default::default()) }));
-    {
-        let ctx = ctx.clone();
-        deps.connection_service
-            .expect_connect()
-            .once()
-            .return_once(move |_, _| {
-                assert_eq!(
-                    ctx.get().unwrap().connected_id().ok(),
-                    Some(user_resource_id!("jane.doe@prose.org/resource-id"))
-                );
-                Box::pin(async {
-                    Err(ConnectionError::Generic {
-                        msg: "Failure".to_string(),
-                    })
-                })
-            });
+fn reconnect_policy(max_attempts: Option<u32>) -> ReconnectPolicy {
+    ReconnectPolicy {
+        base_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(4),
+        max_attempts,
+        jitter: 0.0,
     }
-
-    let deps = deps.into_deps();
-    ctx.set(deps.ctx.clone()).map_err(|_| ()).unwrap();
-
-    let service = ConnectionService::from(&deps);
-
-    *deps.ctx.connection_properties.write() = None;
-    assert!(deps.ctx.connected_id().is_err());
-    assert!(deps.ctx.muc_service().is_err());
-
-    assert!(service
-        .connect(&user_id!("jane.doe@prose.org"), "my-password".into())
-        .await
-        .is_err());
-
-    assert!(deps.ctx.connected_id().is_err());
-    assert!(deps.ctx.muc_service().is_err());
-
-    Ok(())
 }
@@ -0,0 +1,373 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::{format_err, Result};
+use mockall::{predicate, Sequence};
+
+use prose_core_client::domain::contacts::models::{
+    Contact, PresenceSubRequest, PresenceSubscription,
+};
+use prose_core_client::domain::contacts::services::impls::ContactListDomainService;
+use prose_core_client::domain::contacts::services::ContactListDomainService as ContactListDomainServiceTrait;
+use prose_core_client::dtos::UserId;
+use prose_core_client::test::MockContactListDomainServiceDependencies;
+use prose_core_client::{user_id, ClientEvent};
+
+#[tokio::test]
+async fn test_adds_contact_without_presence_sub_request() -> Result<()> {
+    let mut deps = MockContactListDomainServiceDependencies::default();
+
+    let contact = Contact {
+        id: user_id!("friend@prose.org"),
+        name: Some("Jimmy".to_string()),
+        groups: vec!["Friends".to_string()],
+        presence_subscription: PresenceSubscription::None,
+    };
+
+    deps.contact_list_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(None) }));
+    deps.contact_list_repo
+        .expect_set()
+        .once()
+        .with(predicate::always(), predicate::eq(contact.clone()))
+        .return_once(|_, _| Box::pin(async { Ok(true) }));
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::ContactListChanged))
+        .return_once(|_| ());
+    deps.contact_list_service
+        .expect_add_or_update_contact()
+        .once()
+        .return_once(|user_id, name, groups| {
+            assert_eq!(&user_id!("friend@prose.org"), user_id);
+            assert_eq!(Some("Jimmy"), name);
+            assert_eq!(["Friends".to_string()], groups);
+            Box::pin(async { Ok(()) })
+        });
+
+    let service = ContactListDomainService::from(deps.into_deps());
+    let added_contact = service
+        .add_contact(
+            &user_id!("friend@prose.org"),
+            Some("Jimmy"),
+            &["Friends".to_string()],
+            false,
+        )
+        .await?;
+
+    assert_eq!(added_contact, contact);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reverts_renamed_contact_on_failure() -> Result<()> {
+    let mut deps = MockContactListDomainServiceDependencies::default();
+    let mut seq = Sequence::new();
+
+    let contact = Contact {
+        id: user_id!("friend@prose.org"),
+        name: Some("Jimmy".to_string()),
+        groups: vec![],
+        presence_subscription: PresenceSubscription::Mutual,
+    };
+    let renamed_contact = Contact {
+        name: Some("Jim".to_string()),
+        ..contact.clone()
+    };
+
+    {
+        let contact = contact.clone();
+        deps.contact_list_repo
+            .expect_get()
+            .once()
+            .return_once(|_, _| Box::pin(async { Ok(Some(contact)) }));
+    }
+    deps.contact_list_repo
+        .expect_set()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::always(), predicate::eq(renamed_contact))
+        .return_once(|_, _| Box::pin(async { Ok(true) }));
+    deps.contact_list_service
+        .expect_add_or_update_contact()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _, _| Box::pin(async { Err(format_err!("Request failed")) }));
+    deps.contact_list_repo
+        .expect_set()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::always(), predicate::eq(contact))
+        .return_once(|_, _| Box::pin(async { Ok(true) }));
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .times(2)
+        .with(predicate::eq(ClientEvent::ContactChanged {
+            ids: vec![user_id!("friend@prose.org")],
+        }))
+        .return_const(());
+
+    let service = ContactListDomainService::from(deps.into_deps());
+    let result = service
+        .rename_contact(&user_id!("friend@prose.org"), Some("Jim"))
+        .await;
+
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_adding_existing_contact_keeps_its_attributes() -> Result<()> {
+    let mut deps = MockContactListDomainServiceDependencies::default();
+
+    let contact = Contact {
+        id: user_id!("friend@prose.org"),
+        name: Some("Jimmy".to_string()),
+        groups: vec!["Friends".to_string()],
+        presence_subscription: PresenceSubscription::Mutual,
+    };
+
+    {
+        let contact = contact.clone();
+        deps.contact_list_repo
+            .expect_get()
+            .once()
+            .return_once(|_, _| Box::pin(async { Ok(Some(contact)) }));
+    }
+    deps.contact_list_repo
+        .expect_set()
+        .once()
+        .with(predicate::always(), predicate::eq(contact.clone()))
+        .return_once(|_, _| Box::pin(async { Ok(false) }));
+    deps.client_event_dispatcher.expect_dispatch_event().never();
+    deps.contact_list_service
+        .expect_add_or_update_contact()
+        .once()
+        .return_once(|_, name, groups| {
+            assert_eq!(Some("Jimmy"), name);
+            assert_eq!(["Friends".to_string()], groups);
+            Box::pin(async { Ok(()) })
+        });
+
+    let service = ContactListDomainService::from(deps.into_deps());
+    let updated_contact = service
+        .add_contact(&user_id!("friend@prose.org"), None, &[], false)
+        .await?;
+
+    assert_eq!(updated_contact, contact);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_approving_presence_sub_request_keeps_existing_contact() -> Result<()> {
+    let mut deps = MockContactListDomainServiceDependencies::default();
+
+    deps.contact_list_service
+        .expect_approve_presence_sub_request()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(()) }));
+    deps.presence_sub_requests_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_| {
+            Box::pin(async {
+                Ok(vec![PresenceSubRequest {
+                    user_id: user_id!("friend@prose.org"),
+                    name: Some("Jimmy".to_string()),
+                }])
+            })
+        });
+    deps.presence_sub_requests_repo
+        .expect_delete()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(false) }));
+
+    deps.contact_list_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _| {
+            Box::pin(async {
+                Ok(Some(Contact {
+                    id: user_id!("friend@prose.org"),
+                    name: Some("Jim".to_string()),
+                    groups: vec!["Work".to_string()],
+                    presence_subscription: PresenceSubscription::None,
+                }))
+            })
+        });
+    deps.contact_list_repo
+        .expect_set()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(Contact {
+                id: user_id!("friend@prose.org"),
+                name: Some("Jim".to_string()),
+                groups: vec!["Work".to_string()],
+                presence_subscription: PresenceSubscription::Requested,
+            }),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(true) }));
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::ContactListChanged))
+        .return_once(|_| ());
+    deps.contact_list_service
+        .expect_add_or_update_contact()
+        .once()
+        .return_once(|_, name, groups| {
+            assert_eq!(Some("Jim"), name);
+            assert_eq!(["Work".to_string()], groups);
+            Box::pin(async { Ok(()) })
+        });
+    deps.contact_list_service
+        .expect_preapprove_subscription_request()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(()) }));
+    deps.contact_list_service
+        .expect_subscribe_to_presence()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    let service = ContactListDomainService::from(deps.into_deps());
+    service
+        .approve_presence_sub_request(&user_id!("friend@prose.org"), true)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_approves_presence_sub_request_and_subscribes_back() -> Result<()> {
+    let mut deps = MockContactListDomainServiceDependencies::default();
+
+    deps.contact_list_service
+        .expect_approve_presence_sub_request()
+        .once()
+        .with(predicate::eq(user_id!("friend@prose.org")))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+    deps.presence_sub_requests_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_| {
+            Box::pin(async {
+                Ok(vec![PresenceSubRequest {
+                    user_id: user_id!("friend@prose.org"),
+                    name: Some("Jimmy".to_string()),
+                }])
+            })
+        });
+    deps.presence_sub_requests_repo
+        .expect_delete()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(user_id!("friend@prose.org")),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(true) }));
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::PresenceSubRequestsChanged))
+        .return_once(|_| ());
+
+    deps.contact_list_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(None) }));
+    deps.contact_list_repo
+        .expect_set()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(Contact {
+                id: user_id!("friend@prose.org"),
+                name: Some("Jimmy".to_string()),
+                groups: vec![],
+                presence_subscription: PresenceSubscription::Requested,
+            }),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(true) }));
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::ContactListChanged))
+        .return_once(|_| ());
+    deps.contact_list_service
+        .expect_add_or_update_contact()
+        .once()
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+    deps.contact_list_service
+        .expect_preapprove_subscription_request()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(()) }));
+    deps.contact_list_service
+        .expect_subscribe_to_presence()
+        .once()
+        .with(predicate::eq(user_id!("friend@prose.org")))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    let service = ContactListDomainService::from(deps.into_deps());
+    service
+        .approve_presence_sub_request(&user_id!("friend@prose.org"), true)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ignores_duplicate_and_withdrawn_presence_sub_requests() -> Result<()> {
+    let mut deps = MockContactListDomainServiceDependencies::default();
+    let mut seq = Sequence::new();
+
+    deps.presence_sub_requests_repo
+        .expect_set()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(true) }));
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::eq(ClientEvent::PresenceSubRequestsChanged))
+        .return_once(|_| ());
+    // The server re-sends pending requests after reconnecting.
+    deps.presence_sub_requests_repo
+        .expect_set()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(false) }));
+    deps.presence_sub_requests_repo
+        .expect_delete()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(true) }));
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::eq(ClientEvent::PresenceSubRequestsChanged))
+        .return_once(|_| ());
+
+    let service = ContactListDomainService::from(deps.into_deps());
+    service
+        .handle_presence_sub_request(&user_id!("friend@prose.org"), None)
+        .await?;
+    service
+        .handle_presence_sub_request(&user_id!("friend@prose.org"), None)
+        .await?;
+    service
+        .handle_presence_sub_request_withdrawn(&user_id!("friend@prose.org"))
+        .await?;
+
+    Ok(())
+}
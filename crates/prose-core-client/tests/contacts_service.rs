@@ -4,6 +4,8 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use mockall::predicate;
 use pretty_assertions::assert_eq;
 
 use prose_core_client::app::dtos::Contact as ContactDTO;
@@ -27,16 +29,19 @@ async fn test_assembles_contact_dto() -> Result<()> {
                     Contact {
                         id: user_id!("a@prose.org"),
                         name: None,
+                        groups: vec![],
                         presence_subscription: PresenceSubscription::Mutual,
                     },
                     Contact {
                         id: user_id!("b@prose.org"),
                         name: None,
+                        groups: vec![],
                         presence_subscription: PresenceSubscription::WeFollow,
                     },
                     Contact {
                         id: user_id!("john.doe@prose.org"),
                         name: None,
+                        groups: vec![],
                         presence_subscription: PresenceSubscription::TheyFollow,
                     },
                 ])
@@ -77,6 +82,15 @@ async fn test_assembles_contact_dto() -> Result<()> {
             Box::pin(async move { Ok(info) })
         });
 
+    // Only offline contacts are looked up…
+    deps.contact_list_domain_service
+        .expect_load_last_seen()
+        .once()
+        .with(predicate::eq(user_id!("john.doe@prose.org")))
+        .return_once(|_| {
+            Box::pin(async { Ok(Some(Utc.with_ymd_and_hms(2024, 02, 19, 13, 30, 0).unwrap())) })
+        });
+
     let service = ContactListService::from(&deps.into_deps());
 
     let contacts = service.load_contacts().await?;
@@ -89,6 +103,8 @@ async fn test_assembles_contact_dto() -> Result<()> {
                 avatar: None,
                 availability: Availability::Available,
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::Mutual,
             },
@@ -99,6 +115,8 @@ async fn test_assembles_contact_dto() -> Result<()> {
                 avatar: None,
                 availability: Availability::Available,
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::WeFollow,
             },
@@ -109,6 +127,8 @@ async fn test_assembles_contact_dto() -> Result<()> {
                 avatar: None,
                 availability: Availability::Unavailable,
                 status: None,
+                status_message: None,
+                last_seen: Some(Utc.with_ymd_and_hms(2024, 02, 19, 13, 30, 0).unwrap()),
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::TheyFollow,
             }
@@ -118,3 +138,105 @@ async fn test_assembles_contact_dto() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_loads_last_activity_only_for_mutual_contacts() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.contact_list_domain_service
+        .expect_load_contacts()
+        .never();
+    deps.contact_list_domain_service
+        .expect_load_contact()
+        .times(3)
+        .returning(|user_id| {
+            let contact = [
+                (user_id!("a@prose.org"), PresenceSubscription::Mutual),
+                (user_id!("b@prose.org"), PresenceSubscription::WeFollow),
+            ]
+            .into_iter()
+            .find(|(id, _)| id == user_id)
+            .map(|(id, presence_subscription)| Contact {
+                id,
+                name: None,
+                groups: vec![],
+                presence_subscription,
+            });
+            Box::pin(async move { Ok(contact) })
+        });
+
+    deps.user_info_domain_service
+        .expect_load_last_activity()
+        .once()
+        .with(predicate::eq(user_id!("a@prose.org")))
+        .return_once(|_| {
+            Box::pin(async { Ok(Some(Utc.with_ymd_and_hms(2024, 02, 19, 13, 30, 0).unwrap())) })
+        });
+    deps.contact_list_domain_service
+        .expect_set_last_seen()
+        .once()
+        .with(
+            predicate::eq(user_id!("a@prose.org")),
+            predicate::eq(Utc.with_ymd_and_hms(2024, 02, 19, 13, 30, 0).unwrap()),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    let service = ContactListService::from(&deps.into_deps());
+
+    assert_eq!(
+        service.load_last_activity(&user_id!("a@prose.org")).await?,
+        Some(Utc.with_ymd_and_hms(2024, 02, 19, 13, 30, 0).unwrap())
+    );
+    assert_eq!(
+        service.load_last_activity(&user_id!("b@prose.org")).await?,
+        None
+    );
+    assert_eq!(
+        service
+            .load_last_activity(&user_id!("stranger@prose.org"))
+            .await?,
+        None
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_falls_back_to_last_seen_if_server_does_not_report_last_activity() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.contact_list_domain_service
+        .expect_load_contact()
+        .once()
+        .return_once(|_| {
+            Box::pin(async {
+                Ok(Some(Contact {
+                    id: user_id!("a@prose.org"),
+                    name: None,
+                    groups: vec![],
+                    presence_subscription: PresenceSubscription::Mutual,
+                }))
+            })
+        });
+    deps.user_info_domain_service
+        .expect_load_last_activity()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(None) }));
+    deps.contact_list_domain_service
+        .expect_set_last_seen()
+        .never();
+    deps.contact_list_domain_service
+        .expect_load_last_seen()
+        .once()
+        .with(predicate::eq(user_id!("a@prose.org")))
+        .return_once(|_| {
+            Box::pin(async { Ok(Some(Utc.with_ymd_and_hms(2024, 02, 18, 9, 0, 0).unwrap())) })
+        });
+
+    let service = ContactListService::from(&deps.into_deps());
+
+    assert_eq!(
+        service.load_last_activity(&user_id!("a@prose.org")).await?,
+        Some(Utc.with_ymd_and_hms(2024, 02, 18, 9, 0, 0).unwrap())
+    );
+
+    Ok(())
+}
@@ -0,0 +1,501 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use mockall::{predicate, Sequence};
+
+use prose_core_client::domain::encryption::models::{
+    DecryptionContext, Device, DeviceBundle, DeviceId, DeviceList, IdentityKey, IdentityKeyPair,
+    LocalDevice, LocalEncryptionBundle, PreKey, PreKeyId, PrivateKey, PublicKey,
+    PublicSignedPreKey, Session, SignedPreKey, SignedPreKeyId, Trust,
+};
+use prose_core_client::domain::encryption::services::impls::EncryptionDomainService;
+use prose_core_client::domain::encryption::services::EncryptionDomainService as EncryptionDomainServiceTrait;
+use prose_core_client::domain::shared::models::UserId;
+use prose_core_client::test::{ConstantTimeProvider, MockEncryptionDomainServiceDependencies};
+use prose_core_client::{user_id, ClientEvent};
+use prose_xmpp::TimeProvider;
+
+#[tokio::test]
+async fn test_coalesces_pre_key_publishes_within_debounce_interval() -> Result<()> {
+    let mut deps = MockEncryptionDomainServiceDependencies::default();
+    let mut seq = Sequence::new();
+
+    let time_provider = ConstantTimeProvider::ymd(2024, 05, 01);
+    deps.time_provider = Arc::new(time_provider.clone());
+    deps.ctx.config.pre_key_publish_debounce_interval_secs = 5;
+
+    deps.encryption_keys_repo
+        .expect_get_local_device()
+        .returning(|_| Box::pin(async { Ok(Some(local_device())) }));
+
+    deps.encryption_service
+        .expect_generate_pre_keys_with_ids()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::always(), predicate::eq(vec![PreKeyId::from(1)]))
+        .return_once(|_, ids| Box::pin(async { Ok(pre_keys(ids)) }));
+    deps.encryption_keys_repo
+        .expect_put_pre_keys()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    deps.encryption_keys_repo
+        .expect_get_local_device_bundle()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_| Box::pin(async { Ok(Some(device_bundle())) }));
+    {
+        let time_provider = time_provider.clone();
+        deps.user_device_service
+            .expect_publish_device_bundle()
+            .once()
+            .in_sequence(&mut seq)
+            .withf(move |_| {
+                time_provider.now() == Utc.with_ymd_and_hms(2024, 05, 01, 0, 0, 0).unwrap()
+            })
+            .return_once(|_| Box::pin(async { Ok(()) }));
+    }
+
+    // PreKey 2 is regenerated right away, but published only after the interval passed…
+    deps.encryption_service
+        .expect_generate_pre_keys_with_ids()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::always(), predicate::eq(vec![PreKeyId::from(2)]))
+        .return_once(|_, ids| Box::pin(async { Ok(pre_keys(ids)) }));
+    deps.encryption_keys_repo
+        .expect_put_pre_keys()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    deps.encryption_keys_repo
+        .expect_get_local_device_bundle()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_| Box::pin(async { Ok(Some(device_bundle())) }));
+    {
+        let time_provider = time_provider.clone();
+        deps.user_device_service
+            .expect_publish_device_bundle()
+            .once()
+            .in_sequence(&mut seq)
+            .withf(move |_| {
+                time_provider.now() == Utc.with_ymd_and_hms(2024, 05, 01, 0, 0, 6).unwrap()
+            })
+            .return_once(|_| Box::pin(async { Ok(()) }));
+    }
+
+    let service = EncryptionDomainService::from(deps.into_deps());
+
+    service.finalize_decryption(decryption_context([1])).await;
+
+    time_provider.set_ymd_hms(2024, 05, 01, 0, 0, 2);
+    service.finalize_decryption(decryption_context([2])).await;
+
+    // PreKey 2 is waiting to be published already, so it is not regenerated again…
+    time_provider.set_ymd_hms(2024, 05, 01, 0, 0, 3);
+    service.finalize_decryption(decryption_context([2])).await;
+
+    time_provider.set_ymd_hms(2024, 05, 01, 0, 0, 6);
+    service
+        .finalize_decryption(DecryptionContext::default())
+        .await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_publishes_bundle_without_regenerating_pre_keys_outside_of_pool() -> Result<()> {
+    let mut deps = MockEncryptionDomainServiceDependencies::default();
+
+    deps.ctx.config.omemo_pre_key_count = 100;
+
+    deps.encryption_keys_repo
+        .expect_get_local_device()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(Some(local_device())) }));
+
+    deps.encryption_service
+        .expect_generate_pre_keys_with_ids()
+        .never();
+    deps.encryption_keys_repo.expect_put_pre_keys().never();
+
+    deps.encryption_keys_repo
+        .expect_get_local_device_bundle()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(Some(device_bundle())) }));
+    deps.user_device_service
+        .expect_publish_device_bundle()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    let service = EncryptionDomainService::from(deps.into_deps());
+    service.finalize_decryption(decryption_context([150])).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_generates_configured_number_of_pre_keys_for_new_bundle() -> Result<()> {
+    let mut deps = MockEncryptionDomainServiceDependencies::default();
+
+    deps.ctx.config.omemo_pre_key_count = 50;
+
+    deps.encryption_keys_repo
+        .expect_get_local_device_bundle()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(None) }));
+    deps.encryption_service
+        .expect_generate_local_encryption_bundle()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(DeviceId::from(1)),
+            predicate::eq(50),
+        )
+        .return_once(|_, _, count| Box::pin(async move { Ok(local_encryption_bundle(1..=count)) }));
+    deps.encryption_keys_repo
+        .expect_put_local_encryption_bundle()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    // The generated bundle already matches the pool size, so nothing needs to be topped up…
+    deps.encryption_service
+        .expect_generate_pre_keys_with_ids()
+        .never();
+    deps.encryption_keys_repo.expect_put_pre_keys().never();
+
+    deps.user_device_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(vec![]) }));
+    deps.user_device_service
+        .expect_publish_device_list()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(()) }));
+    deps.user_device_service
+        .expect_load_device_bundle()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(None) }));
+    deps.user_device_service
+        .expect_publish_device_bundle()
+        .once()
+        .withf(|bundle| bundle.pre_keys.len() == 50)
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    let service = EncryptionDomainService::from(deps.into_deps());
+    service.initialize().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tops_up_pre_keys_when_pool_grows() -> Result<()> {
+    let mut deps = MockEncryptionDomainServiceDependencies::default();
+    let mut seq = Sequence::new();
+
+    deps.ctx.config.omemo_pre_key_count = 102;
+
+    deps.encryption_keys_repo
+        .expect_get_local_device_bundle()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_| Box::pin(async { Ok(Some(device_bundle_with_pre_keys(1..=100))) }));
+    deps.encryption_service
+        .expect_generate_pre_keys_with_ids()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::always(),
+            predicate::eq(vec![PreKeyId::from(101), PreKeyId::from(102)]),
+        )
+        .return_once(|_, ids| Box::pin(async { Ok(pre_keys(ids)) }));
+    deps.encryption_keys_repo
+        .expect_put_pre_keys()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    deps.encryption_keys_repo
+        .expect_get_local_device_bundle()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_| Box::pin(async { Ok(Some(device_bundle_with_pre_keys(1..=102))) }));
+
+    deps.user_device_repo
+        .expect_get_all()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(vec![device(1)]) }));
+    deps.user_device_service
+        .expect_load_device_bundle()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(Some(device_bundle_with_pre_keys(1..=100))) }));
+
+    // The new PreKeys are published right away…
+    deps.encryption_keys_repo
+        .expect_get_local_device_bundle()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_| Box::pin(async { Ok(Some(device_bundle_with_pre_keys(1..=102))) }));
+    deps.user_device_service
+        .expect_publish_device_bundle()
+        .once()
+        .in_sequence(&mut seq)
+        .withf(|bundle| bundle.pre_keys.len() == 102)
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    let service = EncryptionDomainService::from(deps.into_deps());
+    service.initialize().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_does_not_touch_pre_keys_when_pool_size_is_unchanged() -> Result<()> {
+    let mut deps = MockEncryptionDomainServiceDependencies::default();
+
+    deps.ctx.config.omemo_pre_key_count = 100;
+
+    deps.encryption_keys_repo
+        .expect_get_local_device_bundle()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(Some(device_bundle_with_pre_keys(1..=100))) }));
+    deps.encryption_service
+        .expect_generate_pre_keys_with_ids()
+        .never();
+    deps.encryption_keys_repo.expect_put_pre_keys().never();
+
+    deps.user_device_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(vec![device(1)]) }));
+    deps.user_device_service
+        .expect_load_device_bundle()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(Some(device_bundle_with_pre_keys(1..=100))) }));
+    deps.user_device_service
+        .expect_publish_device_bundle()
+        .never();
+
+    let service = EncryptionDomainService::from(deps.into_deps());
+    service.initialize().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_drops_used_pre_keys_above_shrunk_pool() -> Result<()> {
+    let mut deps = MockEncryptionDomainServiceDependencies::default();
+    let mut seq = Sequence::new();
+
+    // The pool shrank from 100 to 50, so PreKey 80 is used but must not be replenished…
+    deps.ctx.config.omemo_pre_key_count = 50;
+
+    deps.encryption_keys_repo
+        .expect_get_local_device()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(Some(local_device())) }));
+
+    deps.encryption_service
+        .expect_generate_pre_keys_with_ids()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::always(), predicate::eq(vec![PreKeyId::from(10)]))
+        .return_once(|_, ids| Box::pin(async { Ok(pre_keys(ids)) }));
+    deps.encryption_keys_repo
+        .expect_put_pre_keys()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    // … but it still needs to be removed from our published bundle.
+    deps.encryption_keys_repo
+        .expect_get_local_device_bundle()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_| Box::pin(async { Ok(Some(device_bundle_with_pre_keys(1..=50))) }));
+    deps.user_device_service
+        .expect_publish_device_bundle()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    let service = EncryptionDomainService::from(deps.into_deps());
+    service
+        .finalize_decryption(decryption_context([10, 80]))
+        .await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reports_only_devices_that_were_never_seen_before() -> Result<()> {
+    let mut deps = MockEncryptionDomainServiceDependencies::default();
+
+    deps.user_device_repo
+        .expect_get_cached()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(user_id!("them@prose.org")),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(vec![device(1)]) }));
+
+    // Device 2 was removed from the device list earlier, but we had a session with it…
+    deps.session_repo
+        .expect_get_all_sessions()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(user_id!("them@prose.org")),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(vec![session(2)]) }));
+
+    deps.user_device_repo
+        .expect_set_all()
+        .once()
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::NewDeviceDetected {
+            user_id: user_id!("them@prose.org"),
+            device_id: DeviceId::from(3),
+        }))
+        .return_once(|_| ());
+
+    let service = EncryptionDomainService::from(deps.into_deps());
+    service
+        .handle_received_device_list(
+            &user_id!("them@prose.org"),
+            DeviceList {
+                devices: vec![device(1), device(2), device(3)],
+            },
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_does_not_report_devices_of_unknown_user() -> Result<()> {
+    let mut deps = MockEncryptionDomainServiceDependencies::default();
+
+    deps.user_device_repo
+        .expect_get_cached()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(vec![]) }));
+    deps.session_repo
+        .expect_get_all_sessions()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(vec![]) }));
+    deps.user_device_repo
+        .expect_set_all()
+        .once()
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+    deps.client_event_dispatcher.expect_dispatch_event().never();
+
+    let service = EncryptionDomainService::from(deps.into_deps());
+    service
+        .handle_received_device_list(
+            &user_id!("them@prose.org"),
+            DeviceList {
+                devices: vec![device(1), device(2)],
+            },
+        )
+        .await?;
+
+    Ok(())
+}
+
+fn device(id: u32) -> Device {
+    Device {
+        id: DeviceId::from(id),
+        label: None,
+    }
+}
+
+fn session(device_id: u32) -> Session {
+    Session {
+        user_id: user_id!("them@prose.org"),
+        device_id: DeviceId::from(device_id),
+        trust: Trust::Undecided,
+        is_active: false,
+        identity: None,
+        data: None,
+    }
+}
+
+fn decryption_context(used_pre_keys: impl IntoIterator<Item = u32>) -> DecryptionContext {
+    let context = DecryptionContext::default();
+    for id in used_pre_keys {
+        context.insert_used_pre_key(PreKeyId::from(id));
+    }
+    context
+}
+
+fn local_device() -> LocalDevice {
+    LocalDevice {
+        device_id: DeviceId::from(1),
+        identity_key_pair: IdentityKeyPair {
+            identity_key: IdentityKey::from([5u8; 33].as_slice()),
+            private_key: PrivateKey::from([6u8; 32].as_slice()),
+        },
+    }
+}
+
+fn device_bundle() -> DeviceBundle {
+    DeviceBundle {
+        device_id: DeviceId::from(1),
+        signed_pre_key: PublicSignedPreKey {
+            id: SignedPreKeyId::from(1),
+            key: PublicKey::from([7u8; 33].as_slice()),
+            signature: Box::new([8u8; 64]),
+        },
+        identity_key: IdentityKey::from([5u8; 33].as_slice()),
+        pre_keys: vec![],
+    }
+}
+
+fn device_bundle_with_pre_keys(ids: impl IntoIterator<Item = u32>) -> DeviceBundle {
+    DeviceBundle {
+        pre_keys: pre_keys(ids.into_iter().map(PreKeyId::from).collect())
+            .into_iter()
+            .map(PreKey::into_public_pre_key)
+            .collect(),
+        ..device_bundle()
+    }
+}
+
+fn local_encryption_bundle(ids: impl IntoIterator<Item = u32>) -> LocalEncryptionBundle {
+    LocalEncryptionBundle {
+        device_id: DeviceId::from(1),
+        identity_key_pair: local_device().identity_key_pair,
+        signed_pre_key: SignedPreKey {
+            id: SignedPreKeyId::from(1),
+            public_key: PublicKey::from([7u8; 33].as_slice()),
+            private_key: PrivateKey::from([9u8; 32].as_slice()),
+            signature: Box::new([8u8; 64]),
+            timestamp: 0,
+        },
+        pre_keys: pre_keys(ids.into_iter().map(PreKeyId::from).collect()),
+    }
+}
+
+fn pre_keys(ids: Vec<PreKeyId>) -> Vec<PreKey> {
+    ids.into_iter()
+        .map(|id| PreKey {
+            id,
+            public_key: PublicKey::from([1u8; 33].as_slice()),
+            private_key: PrivateKey::from([2u8; 32].as_slice()),
+        })
+        .collect()
+}
@@ -3,23 +3,30 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::time::Duration;
+
 use anyhow::Result;
 
 use prose_core_client::app::event_handlers::XMPPEvent;
 use prose_core_client::app::event_handlers::{ConnectionEvent, ServerEvent};
+use prose_core_client::dtos::MessageId;
 use prose_core_client::parse_xmpp_event;
 use prose_proc_macros::mt_test;
 use prose_xmpp::client::Event as XMPPClientEvent;
-use prose_xmpp::ConnectionError;
+use prose_xmpp::{ConnectionError, SessionKind};
 
 #[mt_test]
 async fn test_connected() -> Result<()> {
-    let input_event = XMPPEvent::Client(XMPPClientEvent::Connected);
+    let input_event = XMPPEvent::Client(XMPPClientEvent::Connected {
+        session: SessionKind::Resumed,
+    });
     let output_events = parse_xmpp_event(input_event)?;
 
     assert_eq!(
         output_events,
-        vec![ServerEvent::Connection(ConnectionEvent::Connected)]
+        vec![ServerEvent::Connection(ConnectionEvent::Connected {
+            session: SessionKind::Resumed
+        })]
     );
 
     Ok(())
@@ -51,3 +58,47 @@ async fn test_disconnected() -> Result<()> {
 
     Ok(())
 }
+
+#[mt_test]
+async fn test_ping_events() -> Result<()> {
+    let input_event = XMPPEvent::Client(XMPPClientEvent::PingAnswered {
+        round_trip_time: Duration::from_millis(120),
+    });
+    let output_events = parse_xmpp_event(input_event)?;
+
+    assert_eq!(
+        output_events,
+        vec![ServerEvent::Connection(ConnectionEvent::PingAnswered {
+            round_trip_time: Duration::from_millis(120)
+        })]
+    );
+
+    let input_event = XMPPEvent::Client(XMPPClientEvent::PingTimeout);
+    let output_events = parse_xmpp_event(input_event)?;
+
+    assert_eq!(
+        output_events,
+        vec![ServerEvent::Connection(ConnectionEvent::PingTimeout)]
+    );
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_messages_acknowledged() -> Result<()> {
+    let input_event = XMPPEvent::Client(XMPPClientEvent::MessagesAcknowledged {
+        ids: vec!["msg-1".into(), "msg-2".into()],
+    });
+    let output_events = parse_xmpp_event(input_event)?;
+
+    assert_eq!(
+        output_events,
+        vec![ServerEvent::Connection(
+            ConnectionEvent::MessagesAcknowledged {
+                message_ids: vec![MessageId::from("msg-1"), MessageId::from("msg-2")]
+            }
+        )]
+    );
+
+    Ok(())
+}
@@ -34,6 +34,28 @@ async fn test_presence_subscription_request() -> Result<()> {
     Ok(())
 }
 
+#[mt_test]
+async fn test_presence_subscription_withdrawn() -> Result<()> {
+    // https://xmpp.org/rfcs/rfc6121.html#sub-unsub
+
+    let events = parse_xml(
+        r#"
+        <presence xmlns="jabber:client" from="user@prose.org" type="unsubscribe" />
+        "#,
+    )
+    .await?;
+
+    assert_eq!(
+        events,
+        vec![ServerEvent::ContactList(ContactListEvent {
+            contact_id: user_id!("user@prose.org"),
+            r#type: ContactListEventType::PresenceSubscriptionWithdrawn,
+        })]
+    );
+
+    Ok(())
+}
+
 #[mt_test]
 async fn test_contact_removed() -> Result<()> {
     let events = parse_xml(
@@ -75,7 +97,9 @@ async fn test_presence_subscription_requested() -> Result<()> {
         events,
         vec![ServerEvent::ContactList(ContactListEvent {
             contact_id: user_id!("user@prose.org"),
-            r#type: ContactListEventType::ContactAddedOrPresenceSubscriptionUpdated {
+            r#type: ContactListEventType::ContactAddedOrUpdated {
+                name: None,
+                groups: vec![],
                 subscription: PresenceSubscription::Requested
             },
         })]
@@ -104,7 +128,9 @@ async fn test_mutual_presence_subscription() -> Result<()> {
         events,
         vec![ServerEvent::ContactList(ContactListEvent {
             contact_id: user_id!("user@prose.org"),
-            r#type: ContactListEventType::ContactAddedOrPresenceSubscriptionUpdated {
+            r#type: ContactListEventType::ContactAddedOrUpdated {
+                name: None,
+                groups: vec!["Buddies".to_string(), "Contacts".to_string()],
                 subscription: PresenceSubscription::Mutual
             },
         })]
@@ -130,7 +156,9 @@ async fn test_contact_follows_us() -> Result<()> {
         events,
         vec![ServerEvent::ContactList(ContactListEvent {
             contact_id: user_id!("user@prose.org"),
-            r#type: ContactListEventType::ContactAddedOrPresenceSubscriptionUpdated {
+            r#type: ContactListEventType::ContactAddedOrUpdated {
+                name: None,
+                groups: vec![],
                 subscription: PresenceSubscription::TheyFollow
             },
         })]
@@ -156,7 +184,9 @@ async fn test_we_follow_contact() -> Result<()> {
         events,
         vec![ServerEvent::ContactList(ContactListEvent {
             contact_id: user_id!("user@prose.org"),
-            r#type: ContactListEventType::ContactAddedOrPresenceSubscriptionUpdated {
+            r#type: ContactListEventType::ContactAddedOrUpdated {
+                name: None,
+                groups: vec![],
                 subscription: PresenceSubscription::WeFollow
             },
         })]
@@ -184,7 +184,9 @@ async fn test_user_was_permanently_removed() -> Result<()> {
                 anon_occupant_id: None,
                 real_id: None,
                 is_self: false,
-                r#type: OccupantEventType::PermanentlyRemoved
+                r#type: OccupantEventType::PermanentlyRemoved {
+                    reason: RoomRemovalReason::Kicked
+                }
             })
         ]
     );
@@ -285,7 +287,8 @@ async fn test_user_entered_room() -> Result<()> {
                 real_id: Some(user_id!("user@prose.org")),
                 is_self: false,
                 r#type: OccupantEventType::AffiliationChanged {
-                    affiliation: RoomAffiliation::None
+                    affiliation: RoomAffiliation::None,
+                    role: RoomRole::Participant,
                 },
             }),
         ]
@@ -346,7 +349,8 @@ async fn test_affiliation_change_with_multiple_resources() -> Result<()> {
                 real_id: Some(user_id!("user@prose.org")),
                 is_self: false,
                 r#type: OccupantEventType::AffiliationChanged {
-                    affiliation: RoomAffiliation::None
+                    affiliation: RoomAffiliation::None,
+                    role: RoomRole::Participant,
                 },
             }),
         ],
@@ -412,7 +416,8 @@ async fn test_received_invite() -> Result<()> {
             room_id: muc_id!("room@prose.org").into(),
             r#type: RoomEventType::ReceivedInvitation {
                 sender: user_resource_id!("user@prose.org/res"),
-                password: Some("cauldronburn".to_string())
+                password: Some("cauldronburn".to_string()),
+                reason: Some("Hey Hecate, this is the place for all good witches!".to_string()),
             },
         })]
     );
@@ -437,7 +442,8 @@ async fn test_received_invite() -> Result<()> {
             room_id: muc_id!("room@prose.org").into(),
             r#type: RoomEventType::ReceivedInvitation {
                 sender: user_resource_id!("user@prose.org/res"),
-                password: Some("cauldronburn".to_string())
+                password: Some("cauldronburn".to_string()),
+                reason: Some("Hey Hecate, this is the place for all good witches!".to_string()),
             },
         })]
     );
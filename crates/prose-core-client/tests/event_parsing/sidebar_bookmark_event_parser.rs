@@ -8,7 +8,7 @@ use anyhow::Result;
 use prose_core_client::app::event_handlers::{PubSubEventType, ServerEvent, SidebarBookmarkEvent};
 use prose_core_client::domain::rooms::models::RoomSidebarState;
 use prose_core_client::domain::shared::models::{MucId, UserId};
-use prose_core_client::domain::sidebar::models::{Bookmark, BookmarkType};
+use prose_core_client::domain::sidebar::models::{Bookmark, BookmarkInvitation, BookmarkType};
 use prose_core_client::test::parse_xml;
 use prose_core_client::{muc_id, user_id};
 use prose_proc_macros::mt_test;
@@ -49,19 +49,25 @@ async fn test_added_or_updated_items() -> Result<()> {
                         name: "Private Channel".to_string(),
                         jid: muc_id!("pc@conference.prose.org").into(),
                         r#type: BookmarkType::PrivateChannel,
-                        sidebar_state: RoomSidebarState::Favorite
+                        sidebar_state: RoomSidebarState::Favorite,
+                        order_index: None,
+                        invitation: None,
                     },
                     Bookmark {
                         name: "Group".to_string(),
                         jid: muc_id!("group@conference.prose.org").into(),
                         r#type: BookmarkType::Group,
-                        sidebar_state: RoomSidebarState::NotInSidebar
+                        sidebar_state: RoomSidebarState::NotInSidebar,
+                        order_index: None,
+                        invitation: None,
                     },
                     Bookmark {
                         name: "Direct Message".to_string(),
                         jid: user_id!("user@prose.org").into(),
                         r#type: BookmarkType::DirectMessage,
-                        sidebar_state: RoomSidebarState::InSidebar
+                        sidebar_state: RoomSidebarState::InSidebar,
+                        order_index: None,
+                        invitation: None,
                     }
                 ]
             },
@@ -71,6 +77,49 @@ async fn test_added_or_updated_items() -> Result<()> {
     Ok(())
 }
 
+#[mt_test]
+async fn test_added_item_with_pending_invitation() -> Result<()> {
+    let events = parse_xml(
+        r#"
+        <message xmlns="jabber:client" from="user@prose.org" type="headline">
+            <event xmlns='http://jabber.org/protocol/pubsub#event'>
+                <items node="https://prose.org/protocol/bookmark">
+                    <item id="room@conference.prose.org">
+                        <bookmark xmlns="https://prose.org/protocol/bookmark" name="room@conference.prose.org" jid="room@conference.prose.org" type="generic" sidebar="1" autojoin="0">
+                            <invitation from="other@prose.org" password="secret" reason="Join us" />
+                        </bookmark>
+                    </item>
+                </items>
+            </event>
+        </message>
+      "#,
+    )
+    .await?;
+
+    assert_eq!(
+        events,
+        vec![ServerEvent::SidebarBookmark(SidebarBookmarkEvent {
+            user_id: user_id!("user@prose.org"),
+            r#type: PubSubEventType::AddedOrUpdated {
+                items: vec![Bookmark {
+                    name: "room@conference.prose.org".to_string(),
+                    jid: muc_id!("room@conference.prose.org").into(),
+                    r#type: BookmarkType::Generic,
+                    sidebar_state: RoomSidebarState::InSidebar,
+                    order_index: None,
+                    invitation: Some(BookmarkInvitation {
+                        sender: user_id!("other@prose.org"),
+                        password: Some("secret".to_string()),
+                        reason: Some("Join us".to_string()),
+                    }),
+                }]
+            },
+        })]
+    );
+
+    Ok(())
+}
+
 #[mt_test]
 async fn test_deleted_items() -> Result<()> {
     // Delete And Notify (https://xmpp.org/extensions/xep-0060.html#example-119)
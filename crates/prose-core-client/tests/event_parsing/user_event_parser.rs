@@ -6,6 +6,7 @@
 use std::str::FromStr;
 
 use anyhow::Result;
+use chrono::{TimeZone, Utc};
 use pretty_assertions::assert_eq;
 
 use prose_core_client::app::event_handlers::{
@@ -114,6 +115,36 @@ async fn test_user_presence_and_capabilities_changed() -> Result<()> {
     Ok(())
 }
 
+#[mt_test]
+async fn test_user_presence_with_idle_since() -> Result<()> {
+    // XEP-0319: Last User Interaction in Presence (https://xmpp.org/extensions/xep-0319.html)
+    let events = parse_xml(
+        r#"
+        <presence xmlns="jabber:client" from="user@prose.org/res">
+            <show>away</show>
+            <idle xmlns="urn:xmpp:idle:1" since="2024-02-19T14:30:00+01:00" />
+        </presence>
+        "#,
+    )
+    .await?;
+
+    assert_eq!(
+        events,
+        vec![ServerEvent::UserStatus(UserStatusEvent {
+            user_id: user_resource_id!("user@prose.org/res").into(),
+            r#type: UserStatusEventType::PresenceChanged {
+                presence: Presence {
+                    availability: Availability::Away,
+                    idle_since: Some(Utc.with_ymd_and_hms(2024, 2, 19, 13, 30, 0).unwrap()),
+                    ..Default::default()
+                }
+            },
+        })]
+    );
+
+    Ok(())
+}
+
 #[mt_test]
 async fn test_compose_state_changed() -> Result<()> {
     // XEP-0085: Chat State Notifications (https://xmpp.org/extensions/xep-0085.html#top)
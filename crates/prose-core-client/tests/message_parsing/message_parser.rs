@@ -16,12 +16,16 @@ use xmpp_parsers::mam::QueryId;
 use xmpp_parsers::message::MessageType;
 use xmpp_parsers::muc::user::{Affiliation, Role};
 
+use prose_core_client::domain::encryption::models::{DecryptedMessage, DeviceId};
 use prose_core_client::domain::encryption::services::mocks::MockEncryptionDomainService;
+use prose_core_client::domain::encryption::services::DecryptionError;
 use prose_core_client::domain::messaging::models::{
-    MessageLike, MessageLikeBody, MessageLikePayload, MessageParser, MessageTargetId, ReplyTo,
+    EncryptedPayload, MessageLike, MessageLikeBody, MessageLikeEncryptionInfo, MessageLikePayload,
+    MessageParser, MessageTargetId, ReplyTo,
 };
 use prose_core_client::dtos::{
-    Attachment, AttachmentType, Mention, OccupantId, ParticipantId, UnicodeScalarIndex, UserId,
+    Attachment, AttachmentType, Mention, OccupantId, ParticipantId, Thumbnail, UnicodeScalarIndex,
+    UserId,
 };
 use prose_core_client::infra::xmpp::util::MessageExt;
 use prose_core_client::{occupant_id, user_id};
@@ -78,6 +82,8 @@ async fn test_parse_chat_message() -> Result<()> {
                 attachments: vec![],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: None,
             },
         },
@@ -128,6 +134,8 @@ async fn test_parse_groupchat_message() -> Result<()> {
                 attachments: vec![],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: None,
             },
         },
@@ -183,6 +191,8 @@ async fn test_parse_sent_carbon_message() -> Result<()> {
                 attachments: vec![],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: None,
             },
         },
@@ -241,6 +251,8 @@ async fn test_parse_mam_groupchat_message() -> Result<()> {
                 attachments: vec![],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: None,
             },
         },
@@ -305,6 +317,8 @@ async fn test_parse_mam_groupchat_message_with_real_jid() -> Result<()> {
                 attachments: vec![],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: None,
             },
         }
@@ -362,6 +376,8 @@ async fn test_parse_mam_chat_message() -> Result<()> {
                 attachments: vec![],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: None,
             },
         },
@@ -412,6 +428,8 @@ async fn test_parse_delayed_message() -> Result<()> {
                 attachments: vec![],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: None,
             },
         },
@@ -469,6 +487,8 @@ async fn test_message_with_attachment_and_empty_body() -> Result<()> {
                 }],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: None,
             },
         },
@@ -519,6 +539,8 @@ async fn test_reply() -> Result<()> {
                 attachments: vec![],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: Some(ReplyTo {
                     id: MessageTargetId::RemoteId("message-id-1".into()),
                     to: Some(ParticipantId::User(user_id!("them@prose.org"))),
@@ -565,6 +587,8 @@ async fn test_reply_without_quote() -> Result<()> {
                 attachments: vec![],
                 encryption_info: None,
                 is_transient: false,
+                thread_id: None,
+                is_markable: false,
                 reply_to: Some(ReplyTo {
                     id: MessageTargetId::RemoteId("message-id-1".into()),
                     to: Some(ParticipantId::User(user_id!("them@prose.org"))),
@@ -577,3 +601,149 @@ async fn test_reply_without_quote() -> Result<()> {
 
     Ok(())
 }
+
+#[mt_test]
+async fn test_parse_undecryptable_message() -> Result<()> {
+    let encrypted_payload = EncryptedPayload {
+        device_id: DeviceId::from(2),
+        iv: Box::new([1, 2, 3]),
+        keys: vec![],
+        payload: Box::new([4, 5, 6]),
+    };
+
+    let message = Message::new()
+        .set_id("message-id-1".into())
+        .set_type(MessageType::Chat)
+        .set_to(bare!("me@prose.org"))
+        .set_from(full!("them@prose.org/resource"))
+        .set_body("[This message is OMEMO encrypted]")
+        .set_omemo_payload(encrypted_payload.clone());
+
+    let mut encryption_domain_service = MockEncryptionDomainService::new();
+    encryption_domain_service
+        .expect_decrypt_message()
+        .once()
+        .return_once(|_, _, _, _, _| {
+            Box::pin(async { Err(DecryptionError::NotEncryptedForThisDevice) })
+        });
+
+    let parsed_message = MessageParser::new(
+        "local-id-1".into(),
+        None,
+        Default::default(),
+        Arc::new(encryption_domain_service),
+        None,
+    )
+    .parse_message(message)
+    .await?;
+
+    // Neither the sender's fallback text nor a hardcoded placeholder is used as the body, so that
+    // the UI can show a localized placeholder instead…
+    let MessageLikePayload::Message {
+        body,
+        encryption_info,
+        ..
+    } = parsed_message.payload
+    else {
+        panic!("Expected a message payload");
+    };
+
+    assert_eq!("", body.raw);
+    assert_eq!(
+        Some(MessageLikeEncryptionInfo {
+            sender: DeviceId::from(2),
+            trust: None,
+            decryption_failed: true,
+            undecrypted_payload: Some(encrypted_payload),
+        }),
+        encryption_info
+    );
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_merges_keys_of_encrypted_attachments_with_their_metadata() -> Result<()> {
+    let key =
+        "000102030405060708090a0b000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    let attachment = |url: &str, thumbnail_url: &str| -> Result<Attachment> {
+        Ok(Attachment {
+            r#type: AttachmentType::Image {
+                thumbnail: Some(Thumbnail {
+                    url: thumbnail_url.parse()?,
+                    media_type: mime::IMAGE_JPEG,
+                    width: Some(100),
+                    height: Some(100),
+                }),
+            },
+            url: url.parse()?,
+            media_type: mime::IMAGE_JPEG,
+            file_name: "image.jpg".to_string(),
+            file_size: Some(1024),
+        })
+    };
+
+    // The metadata is sent with the download URLs, while the keys are only in the encrypted body…
+    let mut message = Message::new()
+        .set_id("message-id-1".into())
+        .set_type(MessageType::Chat)
+        .set_to(bare!("me@prose.org"))
+        .set_from(full!("them@prose.org/resource"))
+        .set_body("[This message is OMEMO encrypted]")
+        .set_omemo_payload(EncryptedPayload {
+            device_id: DeviceId::from(2),
+            iv: Box::new([1, 2, 3]),
+            keys: vec![],
+            payload: Box::new([4, 5, 6]),
+        });
+    message.append_attachments(vec![attachment(
+        "https://uploads.prose.org/image.jpg",
+        "https://uploads.prose.org/thumbnail.jpg",
+    )?]);
+
+    let mut encryption_domain_service = MockEncryptionDomainService::new();
+    encryption_domain_service
+        .expect_decrypt_message()
+        .once()
+        .return_once(move |_, _, _, _, _| {
+            Box::pin(async move {
+                Ok(DecryptedMessage {
+                    message: format!(
+                        "Look\naesgcm://uploads.prose.org/image.jpg#{key}\naesgcm://uploads.prose.org/thumbnail.jpg#{key}"
+                    ),
+                    sender_device_id: DeviceId::from(2),
+                    trust: None,
+                })
+            })
+        });
+
+    let parsed_message = MessageParser::new(
+        "local-id-1".into(),
+        None,
+        Default::default(),
+        Arc::new(encryption_domain_service),
+        None,
+    )
+    .parse_message(message)
+    .await?;
+
+    let MessageLikePayload::Message {
+        body, attachments, ..
+    } = parsed_message.payload
+    else {
+        panic!("Expected a message payload");
+    };
+
+    // …so that they're merged into a single attachment.
+    assert_eq!("Look", body.raw);
+    assert_eq!(
+        vec![attachment(
+            &format!("aesgcm://uploads.prose.org/image.jpg#{key}"),
+            &format!("aesgcm://uploads.prose.org/thumbnail.jpg#{key}"),
+        )?],
+        attachments
+    );
+
+    Ok(())
+}
@@ -16,12 +16,14 @@ use prose_core_client::app::event_handlers::{
 };
 use prose_core_client::domain::connection::models::ConnectionProperties;
 use prose_core_client::domain::messaging::models::{
-    MessageIdTriple, MessageLike, MessageLikeBody, MessageLikePayload,
+    MessageIdTriple, MessageLike, MessageLikeBody, MessageLikePayload, PendingMessage,
+    PendingMessageState,
 };
 use prose_core_client::domain::messaging::services::WrappingMessageIdProvider;
 use prose_core_client::domain::rooms::models::{Room, RoomInfo};
+use prose_core_client::domain::settings::models::{AccountSettings, NotificationPolicy};
 use prose_core_client::domain::shared::models::{
-    MucId, OccupantId, RoomId, RoomType, UserId, UserResourceId,
+    Markdown, MucId, OccupantId, RoomId, RoomType, UserId, UserResourceId,
 };
 use prose_core_client::dtos::{
     Availability, MessageId, MessageRemoteId, MessageServerId, ParticipantId,
@@ -135,6 +137,11 @@ async fn test_receiving_message_adds_item_to_sidebar_if_needed() -> Result<()> {
 async fn test_receiving_message_from_new_contact_creates_room() -> Result<()> {
     let mut deps = MockAppDependencies::default();
     deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
+
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(false) }));
     let mut seq = Sequence::new();
 
     let room = Room::direct_message(user_id!("jane.doe@prose.org"), Availability::Unavailable);
@@ -420,7 +427,8 @@ async fn test_parses_private_message_in_muc_room() -> Result<()> {
         .in_sequence(&mut seq)
         .with(
             predicate::eq(room),
-            predicate::eq(ClientRoomEventType::MessagesAppended {
+            predicate::eq(ClientRoomEventType::PrivateMessagesAppended {
+                occupant_id: occupant_id!("room@conference.prose.org/other-user"),
                 message_ids: vec!["msg-id-1".into()],
             }),
         )
@@ -441,6 +449,11 @@ async fn test_dispatches_messages_appended_for_new_received_message() -> Result<
     let mut deps = MockAppDependencies::default();
     deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
 
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(false) }));
+
     let room = Room::group(muc_id!("user@prose.org"));
 
     deps.sidebar_domain_service
@@ -494,6 +507,159 @@ async fn test_dispatches_messages_appended_for_new_received_message() -> Result<
     Ok(())
 }
 
+#[tokio::test]
+async fn test_dispatches_notification_requested_for_received_message() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
+    deps.ctx.config.notification_policy = Some(NotificationPolicy {
+        suppress_while_do_not_disturb: true,
+    });
+
+    let room = Room::direct_message(user_id!("user@prose.org"), Availability::Available);
+
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(false) }));
+    deps.sidebar_domain_service
+        .expect_handle_received_message()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    {
+        let room = room.clone();
+        deps.connected_rooms_repo
+            .expect_get()
+            .return_once(|_, _| Some(room));
+    }
+    deps.messages_repo
+        .expect_contains()
+        .once()
+        .return_once(|_, _, _| Box::pin(async { Ok(false) }));
+    deps.messages_repo
+        .expect_append()
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+    deps.account_settings_repo
+        .expect_get()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(AccountSettings::default()) }));
+
+    let mut seq = Sequence::new();
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(room.clone()),
+            predicate::eq(ClientRoomEventType::MessagesAppended {
+                message_ids: vec!["msg-id-1".into()],
+            }),
+        )
+        .return_once(|_, _| ());
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(room),
+            predicate::eq(ClientRoomEventType::NotificationRequested {
+                message_ids: vec!["msg-id-1".into()],
+            }),
+        )
+        .return_once(|_, _| ());
+
+    let event_handler = MessagesEventHandler::from(&deps.into_deps());
+    event_handler
+        .handle_event(ServerEvent::Message(MessageEvent {
+            r#type: MessageEventType::Received(
+                Message::default()
+                    .set_to(account_jid())
+                    .set_stanza_id(StanzaId {
+                        id: "stanza-id".into(),
+                        by: bare!("user@prose.org").into(),
+                    })
+                    .set_from(jid!("user@prose.org"))
+                    .set_body("Hello World"),
+            ),
+        }))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_suppresses_notification_while_do_not_disturb() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
+    deps.ctx.config.notification_policy = Some(NotificationPolicy {
+        suppress_while_do_not_disturb: true,
+    });
+
+    let room = Room::direct_message(user_id!("user@prose.org"), Availability::Available);
+
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(false) }));
+    deps.sidebar_domain_service
+        .expect_handle_received_message()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    {
+        let room = room.clone();
+        deps.connected_rooms_repo
+            .expect_get()
+            .return_once(|_, _| Some(room));
+    }
+    deps.messages_repo
+        .expect_contains()
+        .once()
+        .return_once(|_, _, _| Box::pin(async { Ok(false) }));
+    deps.messages_repo
+        .expect_append()
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+    deps.account_settings_repo
+        .expect_get()
+        .once()
+        .return_once(|_| {
+            Box::pin(async {
+                Ok(AccountSettings {
+                    availability: Availability::DoNotDisturb,
+                    ..Default::default()
+                })
+            })
+        });
+
+    // The message is appended as usual, but no notification is requested…
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::eq(room),
+            predicate::eq(ClientRoomEventType::MessagesAppended {
+                message_ids: vec!["msg-id-1".into()],
+            }),
+        )
+        .return_once(|_, _| ());
+
+    let event_handler = MessagesEventHandler::from(&deps.into_deps());
+    event_handler
+        .handle_event(ServerEvent::Message(MessageEvent {
+            r#type: MessageEventType::Received(
+                Message::default()
+                    .set_to(account_jid())
+                    .set_stanza_id(StanzaId {
+                        id: "stanza-id".into(),
+                        by: bare!("user@prose.org").into(),
+                    })
+                    .set_from(jid!("user@prose.org"))
+                    .set_body("Hello World"),
+            ),
+        }))
+        .await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_dispatches_messages_appended_for_sent_carbon() -> Result<()> {
     let mut deps = MockAppDependencies::default();
@@ -642,6 +808,12 @@ async fn test_dispatches_messages_appended_for_muc_carbon() -> Result<()> {
         )
         .return_once(|_, _| ());
 
+    // The message wasn't sent from this client, so there's nothing to confirm…
+    deps.pending_messages_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _, _| Box::pin(async { Ok(None) }));
+
     let event_handler = MessagesEventHandler::from(&deps.into_deps());
     event_handler
         .handle_event(ServerEvent::Message(MessageEvent {
@@ -663,6 +835,127 @@ async fn test_dispatches_messages_appended_for_muc_carbon() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_reflected_muc_message_confirms_pending_message() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
+
+    let room = Room::mock(RoomInfo {
+        room_id: RoomId::Muc(muc_id!("room@groups.prose.org")),
+        user_nickname: "me".to_string(),
+        r#type: RoomType::PrivateChannel,
+        features: Default::default(),
+    });
+
+    deps.ctx
+        .unconfirmed_message_ids
+        .lock()
+        .insert(MessageId::from("message-id"));
+
+    {
+        let room = room.clone();
+        deps.connected_rooms_repo
+            .expect_get()
+            .times(2)
+            .returning(move |_, _| Some(room.clone()));
+    }
+
+    deps.messages_repo
+        .expect_contains()
+        .once()
+        .return_once(|_, _, _| Box::pin(async { Ok(false) }));
+    deps.messages_repo
+        .expect_resolve_remote_id()
+        .once()
+        .return_once(|_, _, _| Box::pin(async { Ok(None) }));
+    deps.messages_repo
+        .expect_append()
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+
+    let mut seq = Sequence::new();
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(room.clone()),
+            predicate::eq(ClientRoomEventType::MessagesAppended {
+                message_ids: vec!["msg-id-1".into()],
+            }),
+        )
+        .return_once(|_, _| ());
+
+    deps.pending_messages_repo
+        .expect_get()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::Muc(muc_id!("room@groups.prose.org"))),
+            predicate::eq(MessageId::from("message-id")),
+        )
+        .return_once(|_, _, _| {
+            Box::pin(async {
+                Ok(Some(PendingMessage {
+                    id: "message-id".into(),
+                    room_id: RoomId::Muc(muc_id!("room@groups.prose.org")),
+                    body: Some(Markdown::new("Hello World")),
+                    attachments: vec![],
+                    reply_to: None,
+                    timestamp: Utc.with_ymd_and_hms(2024, 05, 01, 0, 0, 0).unwrap(),
+                    state: PendingMessageState::Pending,
+                }))
+            })
+        });
+    deps.pending_messages_repo
+        .expect_delete()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::Muc(muc_id!("room@groups.prose.org"))),
+            predicate::eq(MessageId::from("message-id")),
+        )
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(room),
+            predicate::eq(ClientRoomEventType::MessagesUpdated {
+                message_ids: vec!["message-id".into()],
+            }),
+        )
+        .return_once(|_, _| ());
+
+    let deps = deps.into_deps();
+
+    let event_handler = MessagesEventHandler::from(&deps);
+    event_handler
+        .handle_event(ServerEvent::Message(MessageEvent {
+            r#type: MessageEventType::Received(
+                Message::new()
+                    .set_id("message-id".into())
+                    .set_type(MessageType::Groupchat)
+                    .set_from(full!("room@groups.prose.org/me"))
+                    .set_to(full!("me@prose.org/res"))
+                    .set_body("Hello World")
+                    .set_stanza_id(prose_xmpp::stanza::message::stanza_id::StanzaId {
+                        id: "Qiuahv1eo3C222uKhOqjPiW0".into(),
+                        by: bare!("user@prose.org").into(),
+                    }),
+            ),
+        }))
+        .await?;
+
+    assert!(deps.ctx.unconfirmed_message_ids.lock().is_empty());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_dispatches_messages_updated_for_existing_received_message() -> Result<()> {
     let mut deps = MockAppDependencies::default();
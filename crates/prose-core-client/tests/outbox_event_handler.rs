@@ -0,0 +1,219 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use mockall::predicate;
+
+use prose_core_client::app::event_handlers::{
+    ConnectionEvent, OutboxEventHandler, ServerEvent, ServerEventHandler,
+};
+use prose_core_client::domain::messaging::models::{
+    MessageId, PendingMessage, PendingMessageState,
+};
+use prose_core_client::domain::rooms::models::Room;
+use prose_core_client::domain::shared::models::{ConnectionState, Markdown, RoomId, UserId};
+use prose_core_client::dtos::{Availability, RoomState};
+use prose_core_client::test::MockAppDependencies;
+use prose_core_client::{user_id, ClientRoomEventType};
+use prose_xmpp::bare;
+
+#[tokio::test]
+async fn test_fails_pending_message_of_unavailable_room_when_session_is_ready() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    let room = Room::direct_message(user_id!("other@prose.org"), Availability::Available)
+        .with_state(RoomState::Disconnected {
+            error: Some("Room was destroyed".to_string()),
+            can_retry: false,
+        });
+
+    deps.pending_messages_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(vec![pending_message("msg-1")]) }));
+
+    {
+        let room = room.clone();
+        deps.connected_rooms_repo
+            .expect_get()
+            .once()
+            .with(predicate::always(), predicate::eq(bare!("other@prose.org")))
+            .return_once(|_, _| Some(room));
+    }
+
+    deps.pending_messages_repo
+        .expect_put()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(PendingMessage {
+                state: PendingMessageState::Failed {
+                    error: "Room was destroyed".to_string(),
+                },
+                ..pending_message("msg-1")
+            }),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::eq(room),
+            predicate::eq(ClientRoomEventType::MessagesUpdated {
+                message_ids: vec!["msg-1".into()],
+            }),
+        )
+        .return_once(|_, _| ());
+
+    let event_handler = OutboxEventHandler::from(&deps.into_deps());
+    event_handler
+        .handle_event(ServerEvent::Connection(ConnectionEvent::SessionReady))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_holds_back_later_messages_of_unavailable_room() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.pending_messages_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_| {
+            Box::pin(async { Ok(vec![pending_message("msg-1"), pending_message("msg-2")]) })
+        });
+
+    // The room is looked up only once, since the second message must not overtake the first…
+    deps.connected_rooms_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _| None);
+
+    deps.pending_messages_repo.expect_put().never();
+
+    let event_handler = OutboxEventHandler::from(&deps.into_deps());
+    event_handler
+        .handle_event(ServerEvent::Connection(ConnectionEvent::PingTimer))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_does_not_flush_outbox_while_connecting() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    *deps.ctx.connection_state.write() = ConnectionState::Connecting;
+    deps.pending_messages_repo.expect_get_all().never();
+
+    let event_handler = OutboxEventHandler::from(&deps.into_deps());
+    event_handler
+        .handle_event(ServerEvent::Connection(ConnectionEvent::SessionReady))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_does_not_resend_unconfirmed_messages() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.ctx
+        .unconfirmed_message_ids
+        .lock()
+        .insert("msg-1".into());
+
+    deps.pending_messages_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(vec![pending_message("msg-1")]) }));
+
+    deps.connected_rooms_repo.expect_get().never();
+
+    let event_handler = OutboxEventHandler::from(&deps.into_deps());
+    event_handler
+        .handle_event(ServerEvent::Connection(ConnectionEvent::PingTimer))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_removes_acknowledged_messages_from_outbox() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    let room = Room::direct_message(user_id!("other@prose.org"), Availability::Available);
+
+    deps.ctx
+        .unconfirmed_message_ids
+        .lock()
+        .insert("msg-1".into());
+
+    deps.pending_messages_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_| {
+            Box::pin(async { Ok(vec![pending_message("msg-1"), pending_message("msg-2")]) })
+        });
+
+    deps.pending_messages_repo
+        .expect_delete()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::User(user_id!("other@prose.org"))),
+            predicate::eq(MessageId::from("msg-1")),
+        )
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+
+    {
+        let room = room.clone();
+        deps.connected_rooms_repo
+            .expect_get()
+            .once()
+            .return_once(|_, _| Some(room));
+    }
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::eq(room),
+            predicate::eq(ClientRoomEventType::MessagesUpdated {
+                message_ids: vec!["msg-1".into()],
+            }),
+        )
+        .return_once(|_, _| ());
+
+    let deps = deps.into_deps();
+
+    let event_handler = OutboxEventHandler::from(&deps);
+    event_handler
+        .handle_event(ServerEvent::Connection(
+            ConnectionEvent::MessagesAcknowledged {
+                message_ids: vec!["msg-1".into(), "unknown-msg".into()],
+            },
+        ))
+        .await?;
+
+    assert!(deps.ctx.unconfirmed_message_ids.lock().is_empty());
+
+    Ok(())
+}
+
+fn pending_message(id: &str) -> PendingMessage {
+    PendingMessage {
+        id: id.into(),
+        room_id: RoomId::User(user_id!("other@prose.org")),
+        body: Some(Markdown::new("Hello")),
+        attachments: vec![],
+        reply_to: None,
+        timestamp: Utc.with_ymd_and_hms(2024, 05, 01, 0, 0, 0).unwrap(),
+        state: PendingMessageState::Pending,
+    }
+}
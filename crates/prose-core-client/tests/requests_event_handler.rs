@@ -12,11 +12,12 @@ use mockall::predicate;
 use prose_core_client::app::event_handlers::{
     RequestEvent, RequestEventType, RequestsEventHandler, ServerEvent, ServerEventHandler,
 };
+use prose_core_client::domain::contacts::models::{Contact, PresenceSubscription};
 use prose_core_client::domain::general::models::{Capabilities, Feature};
-use prose_core_client::domain::shared::models::{CapabilitiesId, RequestId, SenderId};
+use prose_core_client::domain::shared::models::{CapabilitiesId, RequestId, SenderId, UserId};
 use prose_core_client::dtos::SoftwareVersion;
-use prose_core_client::sender_id;
 use prose_core_client::test::{ConstantTimeProvider, MockAppDependencies};
+use prose_core_client::{sender_id, user_id};
 use prose_xmpp::ns;
 
 #[tokio::test]
@@ -119,6 +120,12 @@ async fn test_handles_last_activity_request() -> Result<()> {
         os: None,
     };
 
+    deps.contact_list_domain_service
+        .expect_load_contact()
+        .once()
+        .with(predicate::eq(user_id!("sender@prose.org")))
+        .return_once(|_| Box::pin(async { Ok(Some(contact(PresenceSubscription::Mutual))) }));
+
     deps.request_handling_service
         .expect_respond_to_last_activity_request()
         .once()
@@ -141,6 +148,78 @@ async fn test_handles_last_activity_request() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_responds_with_idle_time_to_last_activity_request() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.time_provider = Arc::new(ConstantTimeProvider::ymd_hms(2023, 09, 10, 10, 05, 30));
+    deps.ctx
+        .auto_away
+        .lock()
+        .report_activity(Utc.with_ymd_and_hms(2023, 09, 10, 10, 00, 00).unwrap());
+
+    deps.contact_list_domain_service
+        .expect_load_contact()
+        .once()
+        .with(predicate::eq(user_id!("sender@prose.org")))
+        .return_once(|_| Box::pin(async { Ok(Some(contact(PresenceSubscription::Mutual))) }));
+
+    deps.request_handling_service
+        .expect_respond_to_last_activity_request()
+        .once()
+        .with(
+            predicate::eq(sender_id!("sender@prose.org")),
+            predicate::eq(RequestId::from("my-request")),
+            predicate::eq(330),
+        )
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+
+    let event_handler = RequestsEventHandler::from(&deps.into_deps());
+    event_handler
+        .handle_event(ServerEvent::Request(RequestEvent {
+            sender_id: sender_id!("sender@prose.org"),
+            request_id: RequestId::from("my-request"),
+            r#type: RequestEventType::LastActivity,
+        }))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_declines_last_activity_request_of_unsubscribed_users() -> Result<()> {
+    for contact in [None, Some(contact(PresenceSubscription::WeFollow))] {
+        let mut deps = MockAppDependencies::default();
+
+        deps.contact_list_domain_service
+            .expect_load_contact()
+            .once()
+            .return_once(|_| Box::pin(async { Ok(contact) }));
+
+        deps.request_handling_service
+            .expect_respond_to_last_activity_request()
+            .never();
+        deps.request_handling_service
+            .expect_decline_last_activity_request()
+            .once()
+            .with(
+                predicate::eq(sender_id!("sender@prose.org")),
+                predicate::eq(RequestId::from("my-request")),
+            )
+            .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+        let event_handler = RequestsEventHandler::from(&deps.into_deps());
+        event_handler
+            .handle_event(ServerEvent::Request(RequestEvent {
+                sender_id: sender_id!("sender@prose.org"),
+                request_id: RequestId::from("my-request"),
+                r#type: RequestEventType::LastActivity,
+            }))
+            .await?;
+    }
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_handles_disco_request() -> Result<()> {
     let mut deps = MockAppDependencies::default();
@@ -177,3 +256,12 @@ async fn test_handles_disco_request() -> Result<()> {
 
     Ok(())
 }
+
+fn contact(presence_subscription: PresenceSubscription) -> Contact {
+    Contact {
+        id: user_id!("sender@prose.org"),
+        name: None,
+        groups: vec![],
+        presence_subscription,
+    }
+}
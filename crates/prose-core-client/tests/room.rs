@@ -4,23 +4,38 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use anyhow::Result;
-use chrono::{TimeZone, Utc};
-use mockall::predicate;
+use chrono::{DateTime, TimeZone, Utc};
+use mockall::{predicate, Sequence};
 use pretty_assertions::assert_eq;
+use std::collections::HashSet;
 use std::iter;
-use std::sync::Arc;
-
-use prose_core_client::domain::messaging::models::{MessageIdTriple, MessageLikePayload, Reaction};
+use std::sync::{Arc, Mutex};
+
+use prose_core_client::domain::encryption::models::{DecryptedMessage, TrustPolicy};
+use prose_core_client::domain::encryption::services::{EncryptionError, EncryptionResult};
+use prose_core_client::domain::messaging::models::{
+    send_message_request, ArchivedMessageRef, Attachment, AttachmentType, EncryptedPayload,
+    MessageIdTriple, MessageLikeBody, MessageLikeEncryptionInfo, MessageLikePayload,
+    MessageTargetId, Reaction, ReactionError, Thumbnail,
+};
 use prose_core_client::domain::messaging::services::{MessagePage, WrappingMessageIdProvider};
-use prose_core_client::domain::rooms::models::{RegisteredMember, Room, RoomAffiliation};
+use prose_core_client::domain::rooms::models::{
+    ComposeState, RegisteredMember, Room, RoomAffiliation, RoomConfiguration, RoomError,
+    RoomFeatures, RoomRole, RoomSessionMember,
+};
 use prose_core_client::domain::rooms::services::RoomFactory;
 use prose_core_client::domain::shared::models::{CachePolicy, MucId, OccupantId, RoomId, UserId};
-use prose_core_client::domain::user_info::models::{UserInfo, UserName};
+use prose_core_client::domain::user_info::models::{Presence, ProfileName, UserInfo, UserName};
 use prose_core_client::dtos::{
-    Availability, MessageId, MessageResultSet, MessageServerId, Participant,
+    Availability, DataSource, DateRange, DeviceId, ExportFormat, ExportedHistory,
+    HistoryImportSummary, Markdown, MessageCursor, MessageResultSet, MessageServerId, Participant,
+    ParticipantBasicInfo, ParticipantId, ParticipantProfile, SendMessageRequest,
+    SendMessageRequestBody,
+};
+use prose_core_client::test::{
+    mock_data, ConstantTimeProvider, MessageBuilder, MockRoomFactoryDependencies,
 };
-use prose_core_client::test::{mock_data, MessageBuilder, MockRoomFactoryDependencies};
-use prose_core_client::{muc_id, occupant_id, user_id};
+use prose_core_client::{muc_id, occupant_id, user_id, ClientEvent, ClientRoomEventType};
 use prose_xmpp::jid;
 use prose_xmpp::stanza::message::MucUser;
 
@@ -113,6 +128,246 @@ async fn test_load_messages_with_ids_resolves_real_jids() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_load_participants_with_profiles() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    let internals = Room::group(muc_id!("room@conference.prose.org")).by_adding_participants([
+        (
+            occupant_id!("room@conference.prose.org/a"),
+            Participant {
+                is_self: true,
+                role: RoomRole::Moderator,
+                ..Participant::owner()
+                    .set_real_id(&user_id!("a@prose.org"))
+                    .set_availability(Availability::Available)
+            },
+        ),
+        (
+            occupant_id!("room@conference.prose.org/b"),
+            Participant {
+                role: RoomRole::Participant,
+                ..Participant::member().set_real_id(&user_id!("b@prose.org"))
+            },
+        ),
+        (
+            occupant_id!("room@conference.prose.org/c"),
+            Participant::member()
+                .set_real_id(&user_id!("c@prose.org"))
+                .set_vcard_name("Carl Doe"),
+        ),
+        (
+            occupant_id!("room@conference.prose.org/denise_doe"),
+            Participant::member(),
+        ),
+    ]);
+
+    // Aron's profile is cached…
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .once()
+        .with(
+            predicate::eq(user_id!("a@prose.org")),
+            predicate::eq(CachePolicy::ReturnCacheDataDontLoad),
+        )
+        .return_once(|_, _| {
+            Box::pin(async {
+                Ok(Some(UserInfo {
+                    name: UserName {
+                        nickname: Some("Aron Doe".to_string()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }))
+            })
+        });
+
+    // …the others need to be loaded.
+    for user_id in [user_id!("b@prose.org"), user_id!("c@prose.org")] {
+        deps.user_info_domain_service
+            .expect_get_user_info()
+            .once()
+            .with(
+                predicate::eq(user_id),
+                predicate::eq(CachePolicy::ReturnCacheDataDontLoad),
+            )
+            .return_once(|_, _| Box::pin(async { Ok(None) }));
+    }
+
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .once()
+        .with(
+            predicate::eq(user_id!("b@prose.org")),
+            predicate::eq(CachePolicy::ReturnCacheDataElseLoad),
+        )
+        .return_once(|_, _| {
+            Box::pin(async {
+                Ok(Some(UserInfo {
+                    name: UserName {
+                        vcard: Some(ProfileName {
+                            first_name: Some("Bernhard".to_string()),
+                            last_name: Some("Doe".to_string()),
+                            nickname: None,
+                        }),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }))
+            })
+        });
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .once()
+        .with(
+            predicate::eq(user_id!("c@prose.org")),
+            predicate::eq(CachePolicy::ReturnCacheDataElseLoad),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(None) }));
+
+    let room = RoomFactory::from(deps).build(internals).to_generic_room();
+
+    let mut profiles = room.load_participants_with_profiles().await?;
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+
+    assert_eq!(
+        profiles,
+        vec![
+            ParticipantProfile {
+                id: occupant_id!("room@conference.prose.org/a").into(),
+                user_id: Some(user_id!("a@prose.org")),
+                name: "Aron Doe".to_string(),
+                full_name: None,
+                is_self: true,
+                availability: Availability::Available,
+                affiliation: RoomAffiliation::Owner,
+                role: RoomRole::Moderator,
+                avatar: None,
+                status: None,
+            },
+            ParticipantProfile {
+                id: occupant_id!("room@conference.prose.org/b").into(),
+                user_id: Some(user_id!("b@prose.org")),
+                name: "Bernhard Doe".to_string(),
+                full_name: Some("Bernhard Doe".to_string()),
+                is_self: false,
+                availability: Availability::Unavailable,
+                affiliation: RoomAffiliation::Owner,
+                role: RoomRole::Participant,
+                avatar: None,
+                status: None,
+            },
+            ParticipantProfile {
+                id: occupant_id!("room@conference.prose.org/c").into(),
+                user_id: Some(user_id!("c@prose.org")),
+                name: "Carl Doe".to_string(),
+                full_name: None,
+                is_self: false,
+                availability: Availability::Unavailable,
+                affiliation: RoomAffiliation::Owner,
+                role: RoomRole::None,
+                avatar: None,
+                status: None,
+            },
+            ParticipantProfile {
+                id: occupant_id!("room@conference.prose.org/denise_doe").into(),
+                user_id: None,
+                name: "Denise Doe".to_string(),
+                full_name: None,
+                is_self: false,
+                availability: Availability::Unavailable,
+                affiliation: RoomAffiliation::Owner,
+                role: RoomRole::None,
+                avatar: None,
+                status: None,
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_load_participants_falls_back_to_occupants_of_room() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    let internals = Room::group(muc_id!("room@conference.prose.org")).by_adding_participants([
+        (
+            occupant_id!("room@conference.prose.org/a"),
+            Participant::owner().set_real_id(&user_id!("a@prose.org")),
+        ),
+        (
+            occupant_id!("room@conference.prose.org/b"),
+            Participant {
+                real_id: Some(user_id!("b@prose.org")),
+                affiliation: RoomAffiliation::Member,
+                availability: Availability::Available,
+                ..Default::default()
+            },
+        ),
+        (
+            occupant_id!("room@conference.prose.org/c"),
+            Participant {
+                real_id: Some(user_id!("c@prose.org")),
+                affiliation: RoomAffiliation::Member,
+                ..Default::default()
+            },
+        ),
+    ]);
+    // Participant c is unavailable and thus evicted first…
+    internals.with_participants_mut(|p| p.set_capacity(Some(2)));
+
+    deps.participation_service
+        .expect_load_occupants()
+        .once()
+        .with(predicate::eq(muc_id!("room@conference.prose.org")))
+        .return_once(|_| {
+            Box::pin(async {
+                Ok(vec![
+                    occupant_id!("room@conference.prose.org/a"),
+                    occupant_id!("room@conference.prose.org/b"),
+                    occupant_id!("room@conference.prose.org/c"),
+                    occupant_id!("room@conference.prose.org/d"),
+                ])
+            })
+        });
+
+    let room = RoomFactory::from(deps).build(internals).to_generic_room();
+
+    assert_eq!(room.participants().len(), 2);
+    assert_eq!(room.participant_count(), 3);
+
+    let participants = room
+        .load_participants(1, 10)
+        .await?
+        .into_iter()
+        .map(|p| (p.id, p.user_id, p.availability))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        participants,
+        vec![
+            (
+                occupant_id!("room@conference.prose.org/b").into(),
+                Some(user_id!("b@prose.org")),
+                Availability::Available
+            ),
+            (
+                occupant_id!("room@conference.prose.org/c").into(),
+                Some(user_id!("c@prose.org")),
+                Availability::Available
+            ),
+            (
+                occupant_id!("room@conference.prose.org/d").into(),
+                None,
+                Availability::Available
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_load_latest_messages_resolves_real_jids() -> Result<()> {
     let mut deps = MockRoomFactoryDependencies::default();
@@ -224,7 +479,10 @@ async fn test_load_latest_messages_resolves_real_jids() -> Result<()> {
                     .set_from_name("Denise Doe")
                     .build_message_dto(),
             ],
-            last_message_id: None
+            before: None,
+            after: None,
+            is_complete: true,
+            source: DataSource::Live,
         },
         room.load_latest_messages().await?,
     );
@@ -340,415 +598,1872 @@ async fn test_toggle_reaction_in_muc_room() -> Result<()> {
 }
 
 #[tokio::test]
-async fn test_renames_channel_in_sidebar() -> Result<()> {
+async fn test_toggle_reaction_in_muc_room_resolves_missing_server_id() -> Result<()> {
     let mut deps = MockRoomFactoryDependencies::default();
 
-    deps.sidebar_domain_service
-        .expect_rename_item()
+    deps.message_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _, _| {
+            Box::pin(async {
+                Ok(vec![MessageBuilder::new_with_index(1)
+                    .set_server_id(None)
+                    .build_message_like()])
+            })
+        });
+
+    deps.message_repo
+        .expect_resolve_message_id()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::Muc(muc_id!("room@conference.prose.org"))),
+            predicate::eq(MessageBuilder::id_for_index(1)),
+        )
+        .return_once(|_, _, _| {
+            Box::pin(async {
+                Ok(Some(MessageIdTriple {
+                    id: MessageBuilder::id_for_index(1),
+                    remote_id: Some(MessageBuilder::remote_id_for_index(1)),
+                    server_id: Some(MessageBuilder::stanza_id_for_index(1)),
+                }))
+            })
+        });
+
+    deps.messaging_service
+        .expect_react_to_muc_message()
         .once()
         .with(
             predicate::eq(muc_id!("room@conference.prose.org")),
-            predicate::eq("New Name"),
+            predicate::eq(MessageBuilder::stanza_id_for_index(1)),
+            predicate::eq(vec!["🍕".into()]),
         )
-        .return_once(|_, _| Box::pin(async { Ok(()) }));
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
 
     let room = RoomFactory::from(deps)
-        .build(Room::public_channel(muc_id!("room@conference.prose.org")).with_name("Old Name"))
+        .build(Room::group(muc_id!("room@conference.prose.org")))
         .to_generic_room();
-
-    room.set_name("New Name").await?;
+    room.toggle_reaction_to_message(MessageBuilder::id_for_index(1), "🍕".into())
+        .await?;
 
     Ok(())
 }
 
 #[tokio::test]
-async fn test_fills_result_set_when_loading_messages() -> Result<()> {
+async fn test_toggle_reaction_in_muc_room_fails_without_server_id() -> Result<()> {
     let mut deps = MockRoomFactoryDependencies::default();
-    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
-
-    deps.ctx.config.message_page_size = 5;
 
-    deps.message_archive_service
-        .expect_load_messages_before()
+    deps.message_repo
+        .expect_get()
         .once()
-        .return_once(|_, before, page_size| {
-            assert_eq!(5, page_size);
-            assert!(before.is_none());
-
+        .return_once(|_, _, _| {
             Box::pin(async {
-                Ok(MessagePage {
-                    messages: vec![
-                        MessageBuilder::new_with_index(100)
-                            .set_from(user_id!("b@prose.org"))
-                            .set_payload(MessageLikePayload::Reaction {
-                                target_id: MessageBuilder::remote_id_for_index(90).into(),
-                                emojis: vec!["✅".into()],
-                            })
-                            .build_archived_message("q1", None),
-                        MessageBuilder::new_with_index(101)
-                            .set_from(user_id!("a@prose.org"))
-                            .set_payload("Message 101")
-                            .build_archived_message("q1", None),
-                        MessageBuilder::new_with_index(102)
-                            .set_from(user_id!("b@prose.org"))
-                            .set_payload("Message 102")
-                            .build_archived_message("q1", None),
-                        MessageBuilder::new_with_index(103)
-                            .set_from(user_id!("a@prose.org"))
-                            .set_payload(MessageLikePayload::Reaction {
-                                target_id: MessageBuilder::remote_id_for_index(101).into(),
-                                emojis: vec!["🍕".into()],
-                            })
-                            .build_archived_message("q1", None),
-                        MessageBuilder::new_with_index(104)
-                            .set_from(user_id!("a@prose.org"))
-                            .set_payload(MessageLikePayload::Reaction {
-                                target_id: MessageBuilder::remote_id_for_index(102).into(),
-                                emojis: vec!["🎉".into()],
-                            })
-                            .build_archived_message("q1", None),
-                    ],
-                    is_last: false,
-                })
+                Ok(vec![MessageBuilder::new_with_index(1)
+                    .set_server_id(None)
+                    .build_message_like()])
             })
         });
 
-    deps.message_archive_service
-        .expect_load_messages_before()
+    deps.message_repo
+        .expect_resolve_message_id()
         .once()
-        .return_once(|_, before, page_size| {
-            assert_eq!(5, page_size);
-            assert_eq!(Some(&MessageBuilder::stanza_id_for_index(100)), before);
-
+        .return_once(|_, _, _| {
             Box::pin(async {
-                Ok(MessagePage {
-                    messages: vec![
-                        MessageBuilder::new_with_index(90)
-                            .set_from(user_id!("a@prose.org"))
-                            .set_payload("Message 90")
-                            .build_archived_message("q2", None),
-                        MessageBuilder::new_with_index(91)
-                            .set_from(user_id!("a@prose.org"))
-                            .set_payload(MessageLikePayload::Reaction {
-                                target_id: MessageBuilder::remote_id_for_index(90).into(),
-                                emojis: vec!["✅".into()],
-                            })
-                            .build_archived_message("q2", None),
-                        MessageBuilder::new_with_index(92)
-                            .set_from(user_id!("b@prose.org"))
-                            .set_payload("Message 92")
-                            .build_archived_message("q2", None),
-                        MessageBuilder::new_with_index(93)
-                            .set_from(user_id!("a@prose.org"))
-                            .set_payload("Message 93")
-                            .build_archived_message("q2", None),
-                        MessageBuilder::new_with_index(94)
-                            .set_from(user_id!("a@prose.org"))
-                            .set_payload("Message 94")
-                            .build_archived_message("q2", None),
-                    ],
-                    is_last: true,
-                })
+                Ok(Some(MessageIdTriple {
+                    id: MessageBuilder::id_for_index(1),
+                    remote_id: Some(MessageBuilder::remote_id_for_index(1)),
+                    server_id: None,
+                }))
             })
         });
 
-    deps.user_info_domain_service
-        .expect_get_user_info()
-        .returning(|_, _| Box::pin(async { Ok(None) }));
+    deps.messaging_service.expect_react_to_muc_message().never();
 
-    deps.message_repo
-        .expect_resolve_server_id()
-        .times(10)
-        .returning(|_, _, _| Box::pin(async { Ok(None) }));
+    let room = RoomFactory::from(deps)
+        .build(Room::group(muc_id!("room@conference.prose.org")))
+        .to_generic_room();
+    let err = room
+        .toggle_reaction_to_message(MessageBuilder::id_for_index(1), "🍕".into())
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        Some(&ReactionError::MissingServerId),
+        err.downcast_ref::<ReactionError>()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_toggle_reaction_from_known_reactions() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
 
+    deps.message_repo.expect_get().never();
     deps.message_repo
-        .expect_append()
-        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+        .expect_resolve_message_id()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::Muc(muc_id!("room@conference.prose.org"))),
+            predicate::eq(MessageBuilder::id_for_index(1)),
+        )
+        .return_once(|_, _, _| {
+            Box::pin(async {
+                Ok(Some(MessageIdTriple {
+                    id: MessageBuilder::id_for_index(1),
+                    remote_id: Some(MessageBuilder::remote_id_for_index(1)),
+                    server_id: Some(MessageBuilder::stanza_id_for_index(1)),
+                }))
+            })
+        });
+
+    deps.messaging_service
+        .expect_react_to_muc_message()
+        .once()
+        .with(
+            predicate::eq(muc_id!("room@conference.prose.org")),
+            predicate::eq(MessageBuilder::stanza_id_for_index(1)),
+            predicate::eq(vec!["🍻".into(), "✅".into(), "🍕".into()]),
+        )
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
 
     let room = RoomFactory::from(deps)
-        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .build(Room::group(muc_id!("room@conference.prose.org")))
         .to_generic_room();
+    room.set_my_reactions_from_known(
+        MessageBuilder::id_for_index(1),
+        Some(vec!["🍻".into(), "✅".into()]),
+        "🍕".into(),
+    )
+    .await?;
 
-    let result = room.load_latest_messages().await?;
+    Ok(())
+}
 
-    assert_eq!(None, result.last_message_id.as_ref().map(|id| id.as_ref()));
+#[tokio::test]
+async fn test_retry_decryption_replaces_placeholder() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
 
-    assert_eq!(
-        vec![
-            MessageBuilder::new_with_index(90)
-                .set_id("msg-id-10")
-                .set_from(user_id!("a@prose.org"))
-                .set_from_name("A")
-                .set_payload("Message 90")
-                .set_reactions([Reaction {
-                    emoji: "✅".into(),
-                    from: vec![
-                        user_id!("a@prose.org").into(),
-                        user_id!("b@prose.org").into(),
-                    ]
-                },])
-                .build_message_dto(),
-            MessageBuilder::new_with_index(92)
-                .set_id("msg-id-8")
-                .set_from(user_id!("b@prose.org"))
-                .set_from_name("B")
-                .set_payload("Message 92")
-                .build_message_dto(),
-            MessageBuilder::new_with_index(93)
-                .set_id("msg-id-7")
-                .set_from(user_id!("a@prose.org"))
-                .set_from_name("A")
-                .set_payload("Message 93")
-                .build_message_dto(),
-            MessageBuilder::new_with_index(94)
-                .set_id("msg-id-6")
-                .set_from(user_id!("a@prose.org"))
-                .set_from_name("A")
-                .set_payload("Message 94")
-                .build_message_dto(),
-            MessageBuilder::new_with_index(101)
-                .set_id("msg-id-4")
-                .set_from(user_id!("a@prose.org"))
-                .set_from_name("A")
-                .set_payload("Message 101")
-                .set_reactions([Reaction {
-                    emoji: "🍕".into(),
-                    from: vec![user_id!("a@prose.org").into()]
-                }])
-                .build_message_dto(),
-            MessageBuilder::new_with_index(102)
-                .set_id("msg-id-3")
-                .set_from(user_id!("b@prose.org"))
-                .set_from_name("B")
-                .set_payload("Message 102")
-                .set_reactions([Reaction {
-                    emoji: "🎉".into(),
-                    from: vec![user_id!("a@prose.org").into(),]
-                }])
-                .build_message_dto()
-        ],
-        result.messages
-    );
-
-    Ok(())
-}
-
-#[tokio::test]
-async fn test_stops_at_max_message_pages_to_load() -> Result<()> {
-    let mut deps = MockRoomFactoryDependencies::default();
-    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
-
-    deps.ctx.config.message_page_size = 5;
-    deps.ctx.config.max_message_pages_to_load = 2;
+    let encrypted_payload = EncryptedPayload {
+        device_id: DeviceId::from(2),
+        iv: Box::new([1, 2, 3]),
+        keys: vec![],
+        payload: Box::new([4, 5, 6]),
+    };
+
+    let placeholder = MessageBuilder::new_with_index(1)
+        .set_from(user_id!("user@prose.org"))
+        .set_payload(MessageLikePayload::Message {
+            body: MessageLikeBody::text(""),
+            attachments: vec![],
+            encryption_info: Some(MessageLikeEncryptionInfo {
+                sender: DeviceId::from(2),
+                trust: None,
+                decryption_failed: true,
+                undecrypted_payload: Some(encrypted_payload.clone()),
+            }),
+            is_transient: false,
+            reply_to: None,
+            thread_id: None,
+            is_markable: false,
+        })
+        .build_message_like();
 
-    deps.message_archive_service
-        .expect_load_messages_before()
+    deps.message_repo
+        .expect_get()
         .once()
-        .return_once(|_, before, page_size| {
-            assert_eq!(5, page_size);
-            assert_eq!(None, before);
-
-            Box::pin(async {
-                Ok(MessagePage {
-                    messages: (96..=99)
-                        .into_iter()
-                        .map(|idx| {
-                            MessageBuilder::new_with_index(idx)
-                                .set_payload(MessageLikePayload::Reaction {
-                                    target_id: MessageBuilder::remote_id_for_index(1001).into(),
-                                    emojis: vec![],
-                                })
-                                .build_archived_message("q1", None)
-                        })
-                        .chain(iter::once(
-                            MessageBuilder::new_with_index(100)
-                                .set_from(user_id!("a@prose.org"))
-                                .set_payload("Message 100")
-                                .build_archived_message("q1", None),
-                        ))
-                        .collect(),
-                    is_last: false,
-                })
-            })
-        });
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::User(user_id!("user@prose.org"))),
+            predicate::eq(MessageBuilder::id_for_index(1)),
+        )
+        .return_once(|_, _, _| Box::pin(async { Ok(vec![placeholder]) }));
 
-    deps.message_archive_service
-        .expect_load_messages_before()
+    deps.encryption_domain_service
+        .expect_decrypt_message()
         .once()
-        .return_once(|_, before, page_size| {
-            assert_eq!(5, page_size);
-            assert_eq!(Some(&MessageBuilder::stanza_id_for_index(96)), before);
-
+        .with(
+            predicate::eq(user_id!("user@prose.org")),
+            predicate::always(),
+            predicate::eq(Some(MessageBuilder::id_for_index(1))),
+            predicate::eq(encrypted_payload),
+            predicate::always(),
+        )
+        .return_once(|_, _, _, _, _| {
             Box::pin(async {
-                Ok(MessagePage {
-                    messages: (91..=95)
-                        .into_iter()
-                        .map(|idx| {
-                            MessageBuilder::new_with_index(idx)
-                                .set_payload(MessageLikePayload::Reaction {
-                                    target_id: MessageBuilder::remote_id_for_index(1001).into(),
-                                    emojis: vec![],
-                                })
-                                .build_archived_message("q1", None)
-                        })
-                        .collect(),
-                    is_last: false,
+                Ok(DecryptedMessage {
+                    message: "Hello".to_string(),
+                    sender_device_id: DeviceId::from(2),
+                    trust: None,
                 })
             })
         });
 
-    deps.user_info_domain_service
-        .expect_get_user_info()
-        .returning(|_, _| Box::pin(async { Ok(None) }));
-
-    deps.message_repo
-        .expect_resolve_server_id()
-        .times(10)
-        .returning(|_, _, _| Box::pin(async { Ok(None) }));
-
     deps.message_repo
         .expect_append()
-        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+        .once()
+        .withf(|_, _, messages| {
+            let [message] = messages else { return false };
+            let MessageLikePayload::Message {
+                body,
+                encryption_info: Some(encryption_info),
+                ..
+            } = &message.payload
+            else {
+                return false;
+            };
+            body.raw == "Hello"
+                && !encryption_info.decryption_failed
+                && encryption_info.undecrypted_payload.is_none()
+        })
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![MessageBuilder::id_for_index(1)],
+            }),
+        )
+        .return_once(|_, _| ());
 
     let room = RoomFactory::from(deps)
-        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .build(Room::direct_message(
+            user_id!("user@prose.org"),
+            Availability::Available,
+        ))
         .to_generic_room();
+    room.retry_decryption(&MessageBuilder::id_for_index(1))
+        .await?;
 
-    let result = room.load_latest_messages().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fails_to_encrypt_for_unresolved_participants() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
 
-    assert_eq!(Some(MessageId::from("msg-id-10")), result.last_message_id);
+    deps.room_management_service
+        .expect_load_room_members()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(vec![]) }));
+    deps.encryption_domain_service
+        .expect_encrypt_message()
+        .never();
+
+    let internals = Room::group(muc_id!("room@conference.prose.org")).by_adding_participants([(
+        occupant_id!("room@conference.prose.org/b"),
+        Participant::owner(),
+    )]);
+    internals.with_settings_mut(|settings| settings.encryption_enabled = true);
 
+    let room = RoomFactory::from(deps).build(internals).to_generic_room();
+    let err = room
+        .send_message(SendMessageRequest {
+            body: Some(SendMessageRequestBody {
+                text: Markdown::new("Hello"),
+            }),
+            attachments: vec![],
+            reply_to: None,
+            thread_id: None,
+        })
+        .await
+        .unwrap_err();
+
+    let Some(EncryptionError::UnresolvedParticipants { participants }) =
+        err.downcast_ref::<EncryptionError>()
+    else {
+        panic!("Unexpected error {err}");
+    };
     assert_eq!(
-        vec![MessageBuilder::new_with_index(100)
-            .set_id("msg-id-1")
-            .set_from(user_id!("a@prose.org"))
-            .set_from_name("A")
-            .set_payload("Message 100")
-            .build_message_dto()],
-        result.messages
+        participants,
+        &vec![ParticipantId::Occupant(occupant_id!(
+            "room@conference.prose.org/b"
+        ))]
     );
 
     Ok(())
 }
 
 #[tokio::test]
-async fn test_stops_at_last_page() -> Result<()> {
+async fn test_encrypts_for_room_members_and_reports_undecided_devices() -> Result<()> {
     let mut deps = MockRoomFactoryDependencies::default();
 
-    deps.ctx.config.message_page_size = 100;
-    deps.ctx.config.max_message_pages_to_load = 100;
-
-    deps.message_archive_service
-        .expect_load_messages_before()
+    // The member list is only loaded once, since the participants don't change in between…
+    deps.room_management_service
+        .expect_load_room_members()
         .once()
-        .return_once(|_, _, _| {
+        .with(predicate::eq(muc_id!("room@conference.prose.org")))
+        .return_once(|_| {
             Box::pin(async {
-                Ok(MessagePage {
-                    messages: (96..=100)
-                        .into_iter()
-                        .map(|idx| {
-                            MessageBuilder::new_with_index(idx).build_archived_message("q1", None)
-                        })
-                        .collect(),
-                    is_last: false,
-                })
+                Ok(vec![
+                    RoomSessionMember {
+                        id: user_id!("a@prose.org"),
+                        affiliation: RoomAffiliation::Owner,
+                    },
+                    RoomSessionMember {
+                        id: mock_data::account_jid().into_user_id(),
+                        affiliation: RoomAffiliation::Member,
+                    },
+                    RoomSessionMember {
+                        id: user_id!("c@prose.org"),
+                        affiliation: RoomAffiliation::Member,
+                    },
+                ])
             })
         });
 
-    deps.message_archive_service
-        .expect_load_messages_before()
-        .once()
-        .return_once(|_, _, _| {
+    deps.encryption_domain_service
+        .expect_encrypt_message()
+        .times(2)
+        .withf(|user_ids, _, _| user_ids == [user_id!("a@prose.org"), user_id!("c@prose.org")])
+        .returning(|_, _, _| {
             Box::pin(async {
-                Ok(MessagePage {
-                    messages: (93..=95)
-                        .into_iter()
-                        .map(|idx| {
-                            MessageBuilder::new_with_index(idx).build_archived_message("q1", None)
-                        })
-                        .collect(),
-                    is_last: true,
+                Err(EncryptionError::UntrustedDevices {
+                    devices: vec![(user_id!("c@prose.org"), DeviceId::from(1))],
                 })
             })
         });
 
-    deps.user_info_domain_service
-        .expect_get_user_info()
-        .returning(|_, _| Box::pin(async { Ok(None) }));
-
-    deps.message_repo
-        .expect_resolve_server_id()
-        .times(8)
-        .returning(|_, _, _| Box::pin(async { Ok(None) }));
+    // …and the UI is only informed about the undecided device once.
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::EncryptionRecipientsChanged),
+        )
+        .return_once(|_, _| ());
 
-    deps.message_repo
-        .expect_append()
-        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+    let internals = Room::group(muc_id!("room@conference.prose.org"))
+        .with_members([RegisteredMember {
+            user_id: user_id!("a@prose.org"),
+            name: None,
+            affiliation: RoomAffiliation::Owner,
+            is_self: false,
+        }])
+        .by_adding_participants([(
+            occupant_id!("room@conference.prose.org/b"),
+            Participant::owner(),
+        )]);
+    internals.with_settings_mut(|settings| settings.encryption_enabled = true);
 
-    let room = RoomFactory::from(deps)
-        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
-        .to_generic_room();
+    let room = RoomFactory::from(deps).build(internals).to_generic_room();
 
-    let result = room.load_latest_messages().await?;
+    for _ in 0..2 {
+        let err = room
+            .send_message(SendMessageRequest {
+                body: Some(SendMessageRequestBody {
+                    text: Markdown::new("Hello"),
+                }),
+                attachments: vec![],
+                reply_to: None,
+                thread_id: None,
+            })
+            .await
+            .unwrap_err();
 
-    assert_eq!(None, result.last_message_id.as_ref().map(|id| id.as_ref()));
-    assert_eq!(8, result.messages.len());
+        assert!(matches!(
+            err.downcast_ref::<EncryptionError>(),
+            Some(EncryptionError::UntrustedDevices { .. })
+        ));
+    }
 
     Ok(())
 }
 
 #[tokio::test]
-async fn test_resolves_targeted_messages_when_loading_messages() -> Result<()> {
+async fn test_reports_undecided_devices_messages_were_encrypted_for() -> Result<()> {
     let mut deps = MockRoomFactoryDependencies::default();
-    deps.ctx.config.max_message_pages_to_load = 1;
-    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
 
-    deps.message_repo
-        .expect_resolve_message_id()
+    // The member list is loaded only once, since a new nickname doesn't change the recipients…
+    deps.room_management_service
+        .expect_load_room_members()
         .once()
-        .with(
-            predicate::always(),
-            predicate::always(),
-            predicate::eq(MessageId::from("some-stanza-id")),
-        )
-        .return_once(move |_, _, _| {
+        .return_once(|_| {
             Box::pin(async {
-                Ok(Some(MessageIdTriple {
-                    id: "some-stanza-id".into(),
-                    remote_id: None,
-                    server_id: Some("some-server-id".into()),
-                }))
+                Ok(vec![RoomSessionMember {
+                    id: user_id!("b@prose.org"),
+                    affiliation: RoomAffiliation::Owner,
+                }])
             })
         });
 
-    deps.message_archive_service
-        .expect_load_messages_before()
-        .once()
-        .return_once(|_, before, _| {
-            assert!(before.is_some());
-
+    // The default trust policy encrypts for undecided devices instead of failing…
+    deps.encryption_domain_service
+        .expect_encrypt_message()
+        .times(2)
+        .withf(|user_ids, _, trust_policy| {
+            user_ids == [user_id!("b@prose.org")]
+                && trust_policy == &TrustPolicy::EncryptToUndecided
+        })
+        .returning(|_, _, _| {
             Box::pin(async {
-                Ok(MessagePage {
-                    messages: vec![
-                        MessageBuilder::new_with_index(1)
-                            .set_from(user_id!("user@prose.org"))
-                            .build_archived_message("q1", None),
-                        MessageBuilder::new_with_index(2)
+                Ok(EncryptionResult {
+                    payload: encrypted_payload(),
+                    recipients_without_devices: vec![],
+                    undecided_devices: vec![(user_id!("b@prose.org"), DeviceId::from(1))],
+                })
+            })
+        });
+
+    deps.message_repo
+        .expect_append()
+        .times(2)
+        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+    deps.pending_messages_repo
+        .expect_put()
+        .times(2)
+        .returning(|_, _| Box::pin(async { Ok(()) }));
+    deps.messaging_service
+        .expect_send_message()
+        .times(2)
+        .returning(|_, _| Box::pin(async { Ok(()) }));
+
+    // …but the UI is still informed about the undecided device, though only once.
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::EncryptionRecipientsChanged),
+        )
+        .return_const(());
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .times(2)
+        .with(
+            predicate::always(),
+            predicate::function(|event| {
+                matches!(event, ClientRoomEventType::MessagesAppended { .. })
+            }),
+        )
+        .return_const(());
+
+    let internals = Room::group(muc_id!("room@conference.prose.org")).by_adding_participants([(
+        occupant_id!("room@conference.prose.org/b"),
+        Participant::owner(),
+    )]);
+    internals.with_settings_mut(|settings| settings.encryption_enabled = true);
+
+    let room = RoomFactory::from(deps)
+        .build(internals.clone())
+        .to_generic_room();
+
+    for nickname in ["B", "Bernhard"] {
+        internals.with_participants_mut(|participants| {
+            participants.set_presence(
+                &occupant_id!("room@conference.prose.org/b").into(),
+                false,
+                Presence {
+                    availability: Availability::Available,
+                    nickname: Some(nickname.to_string()),
+                    ..Default::default()
+                },
+            )
+        });
+
+        room.send_message(SendMessageRequest {
+            body: Some(SendMessageRequestBody {
+                text: Markdown::new("Hello"),
+            }),
+            attachments: vec![],
+            reply_to: None,
+            thread_id: None,
+        })
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sends_keys_of_encrypted_attachments_only_in_encrypted_body() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    deps.encryption_domain_service
+        .expect_encrypt_message()
+        .once()
+        .withf(|user_ids, message, _| {
+            user_ids == [user_id!("other@prose.org")]
+                && message
+                    == &format!(
+                        "Hello\naesgcm://uploads.prose.org/image.jpg#{KEY}\naesgcm://uploads.prose.org/thumbnail.jpg#{KEY}"
+                    )
+        })
+        .return_once(|_, _, _| {
+            Box::pin(async {
+                Ok(EncryptionResult {
+                    payload: encrypted_payload(),
+                    recipients_without_devices: vec![],
+                    undecided_devices: vec![],
+                })
+            })
+        });
+
+    // The local copy of the message keeps the keys, so that we can decrypt the files later…
+    deps.message_repo
+        .expect_append()
+        .once()
+        .withf(|_, _, messages| {
+            matches!(
+                &messages[0].payload,
+                MessageLikePayload::Message { attachments, .. } if attachments == &[encrypted_image()]
+            )
+        })
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+    deps.pending_messages_repo
+        .expect_put()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    // …while the metadata is sent with the download URLs only.
+    deps.messaging_service
+        .expect_send_message()
+        .once()
+        .withf(|_, request| {
+            matches!(
+                request.body.as_ref().map(|body| &body.payload),
+                Some(send_message_request::Payload::Encrypted(_))
+            ) && request.attachments
+                == [Attachment {
+                    r#type: AttachmentType::Image {
+                        thumbnail: Some(Thumbnail {
+                            url: "https://uploads.prose.org/thumbnail.jpg".parse().unwrap(),
+                            ..encrypted_thumbnail()
+                        }),
+                    },
+                    url: "https://uploads.prose.org/image.jpg".parse().unwrap(),
+                    ..encrypted_image()
+                }]
+        })
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    deps.messaging_service
+        .expect_acknowledges_sent_messages()
+        .return_const(true);
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::MessagesAppended {
+                message_ids: vec!["msg-id-1".into()],
+            }),
+        )
+        .return_const(());
+
+    let internals = Room::direct_message(user_id!("other@prose.org"), Availability::Available);
+    internals.with_settings_mut(|settings| settings.encryption_enabled = true);
+
+    let room = RoomFactory::from(deps).build(internals).to_generic_room();
+    room.send_message(SendMessageRequest {
+        body: Some(SendMessageRequestBody {
+            text: Markdown::new("Hello"),
+        }),
+        attachments: vec![encrypted_image()],
+        reply_to: None,
+        thread_id: None,
+    })
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_does_not_send_encrypted_attachments_unencrypted() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    deps.message_repo.expect_append().never();
+    deps.messaging_service.expect_send_message().never();
+
+    let room = RoomFactory::from(deps)
+        .build(Room::direct_message(
+            user_id!("other@prose.org"),
+            Availability::Available,
+        ))
+        .to_generic_room();
+
+    // Neither the key of the file nor the one of its thumbnail must leak…
+    let plain_image_with_encrypted_thumbnail = Attachment {
+        url: "https://uploads.prose.org/image.jpg".parse()?,
+        ..encrypted_image()
+    };
+
+    for attachment in [encrypted_image(), plain_image_with_encrypted_thumbnail] {
+        let result = room
+            .send_message(SendMessageRequest {
+                body: None,
+                attachments: vec![attachment],
+                reply_to: None,
+                thread_id: None,
+            })
+            .await;
+        assert!(result.is_err());
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sends_unencrypted_attachments_unchanged() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    let image = Attachment {
+        r#type: AttachmentType::Image {
+            thumbnail: Some(Thumbnail {
+                url: "https://uploads.prose.org/thumbnail.jpg".parse()?,
+                ..encrypted_thumbnail()
+            }),
+        },
+        url: "https://uploads.prose.org/image.jpg".parse()?,
+        ..encrypted_image()
+    };
+
+    deps.message_repo
+        .expect_append()
+        .once()
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+    deps.pending_messages_repo
+        .expect_put()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    {
+        let image = image.clone();
+        deps.messaging_service
+            .expect_send_message()
+            .once()
+            .withf(move |_, request| {
+                request.body.is_none() && request.attachments == [image.clone()]
+            })
+            .return_once(|_, _| Box::pin(async { Ok(()) }));
+    }
+    deps.messaging_service
+        .expect_acknowledges_sent_messages()
+        .return_const(true);
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .return_const(());
+
+    let room = RoomFactory::from(deps)
+        .build(Room::direct_message(
+            user_id!("other@prose.org"),
+            Availability::Available,
+        ))
+        .to_generic_room();
+
+    room.send_message(SendMessageRequest {
+        body: None,
+        attachments: vec![image],
+        reply_to: None,
+        thread_id: None,
+    })
+    .await?;
+
+    Ok(())
+}
+
+const KEY: &str =
+    "000102030405060708090a0b000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+fn encrypted_image() -> Attachment {
+    Attachment {
+        r#type: AttachmentType::Image {
+            thumbnail: Some(encrypted_thumbnail()),
+        },
+        url: format!("aesgcm://uploads.prose.org/image.jpg#{KEY}")
+            .parse()
+            .unwrap(),
+        media_type: mime::IMAGE_JPEG,
+        file_name: "image.jpg".to_string(),
+        file_size: Some(1024),
+    }
+}
+
+fn encrypted_thumbnail() -> Thumbnail {
+    Thumbnail {
+        url: format!("aesgcm://uploads.prose.org/thumbnail.jpg#{KEY}")
+            .parse()
+            .unwrap(),
+        media_type: mime::IMAGE_JPEG,
+        width: Some(100),
+        height: Some(100),
+    }
+}
+
+fn encrypted_payload() -> EncryptedPayload {
+    EncryptedPayload {
+        device_id: DeviceId::from(2),
+        iv: Box::new([1, 2, 3]),
+        keys: vec![],
+        payload: Box::new([4, 5, 6]),
+    }
+}
+
+#[tokio::test]
+async fn test_renames_channel_in_sidebar() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    deps.sidebar_domain_service
+        .expect_rename_item()
+        .once()
+        .with(
+            predicate::eq(muc_id!("room@conference.prose.org")),
+            predicate::eq("New Name"),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    let room = RoomFactory::from(deps)
+        .build(Room::public_channel(muc_id!("room@conference.prose.org")).with_name("Old Name"))
+        .to_generic_room();
+
+    room.set_name("New Name").await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_destroys_room_if_owner() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    deps.sidebar_domain_service
+        .expect_destroy_room()
+        .once()
+        .with(
+            predicate::eq(muc_id!("room@conference.prose.org")),
+            predicate::eq(Some(muc_id!("new-room@conference.prose.org"))),
+            predicate::always(),
+        )
+        .return_once(|_, _, reason| {
+            assert_eq!(Some("We've moved"), reason);
+            Box::pin(async { Ok(()) })
+        });
+
+    let internals = Room::private_channel(muc_id!("room@conference.prose.org"));
+    let self_id = ParticipantId::Occupant(occupant_id!("room@conference.prose.org/me"));
+    internals.with_participants_mut(|p| p.set_affiliation(&self_id, true, RoomAffiliation::Member));
+
+    let room = RoomFactory::from(deps)
+        .build(internals.clone())
+        .to_generic_room();
+
+    let result = room
+        .destroy(
+            Some(muc_id!("new-room@conference.prose.org")),
+            Some("We've moved"),
+        )
+        .await;
+    assert!(matches!(result, Err(RoomError::NotRoomOwner)));
+
+    internals.with_participants_mut(|p| p.set_affiliation(&self_id, true, RoomAffiliation::Owner));
+
+    room.destroy(
+        Some(muc_id!("new-room@conference.prose.org")),
+        Some("We've moved"),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_loads_room_configuration_if_owner() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    let configuration = RoomConfiguration {
+        members_only: true,
+        moderated: false,
+        persistent: true,
+        max_history_fetch: Some(20),
+        allow_invites: true,
+        password: None,
+    };
+
+    {
+        let configuration = configuration.clone();
+        deps.room_management_service
+            .expect_load_room_configuration()
+            .once()
+            .with(predicate::eq(muc_id!("room@conference.prose.org")))
+            .return_once(|_| Box::pin(async { Ok(configuration) }));
+    }
+
+    let internals = Room::private_channel(muc_id!("room@conference.prose.org"));
+    let self_id = ParticipantId::Occupant(occupant_id!("room@conference.prose.org/me"));
+    internals.with_participants_mut(|p| p.set_affiliation(&self_id, true, RoomAffiliation::Admin));
+
+    let room = RoomFactory::from(deps)
+        .build(internals.clone())
+        .to_generic_room();
+
+    let result = room.load_configuration().await;
+    assert!(matches!(result, Err(RoomError::NotRoomOwner)));
+
+    internals.with_participants_mut(|p| p.set_affiliation(&self_id, true, RoomAffiliation::Owner));
+
+    assert_eq!(room.load_configuration().await?, configuration);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_updates_room_configuration_if_owner() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    let configuration = RoomConfiguration {
+        members_only: true,
+        moderated: true,
+        persistent: true,
+        max_history_fetch: Some(50),
+        allow_invites: false,
+        password: Some("secret".to_string()),
+    };
+
+    deps.room_management_service
+        .expect_update_room_configuration()
+        .once()
+        .with(
+            predicate::eq(muc_id!("room@conference.prose.org")),
+            predicate::eq(configuration.clone()),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    deps.sidebar_domain_service
+        .expect_handle_changed_room_config()
+        .once()
+        .with(predicate::eq(muc_id!("room@conference.prose.org")))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    let internals = Room::private_channel(muc_id!("room@conference.prose.org"));
+    let self_id = ParticipantId::Occupant(occupant_id!("room@conference.prose.org/me"));
+    internals.with_participants_mut(|p| p.set_affiliation(&self_id, true, RoomAffiliation::Admin));
+
+    let room = RoomFactory::from(deps)
+        .build(internals.clone())
+        .to_generic_room();
+
+    let result = room.update_configuration(configuration.clone()).await;
+    assert!(matches!(result, Err(RoomError::NotRoomOwner)));
+
+    internals.with_participants_mut(|p| p.set_affiliation(&self_id, true, RoomAffiliation::Owner));
+
+    room.update_configuration(configuration).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grants_affiliation_to_user_not_in_room() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    deps.participation_service
+        .expect_set_affiliation()
+        .once()
+        .with(
+            predicate::eq(muc_id!("room@conference.prose.org")),
+            predicate::eq(user_id!("a@prose.org")),
+            predicate::eq(RoomAffiliation::Member),
+            predicate::always(),
+        )
+        .return_once(|_, _, _, reason| {
+            assert_eq!(None, reason);
+            Box::pin(async { Ok(()) })
+        });
+    deps.participation_service
+        .expect_set_affiliation()
+        .once()
+        .with(
+            predicate::eq(muc_id!("room@conference.prose.org")),
+            predicate::eq(user_id!("a@prose.org")),
+            predicate::eq(RoomAffiliation::None),
+            predicate::always(),
+        )
+        .return_once(|_, _, _, reason| {
+            assert_eq!(None, reason);
+            Box::pin(async { Ok(()) })
+        });
+
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .once()
+        .with(
+            predicate::eq(user_id!("a@prose.org")),
+            predicate::eq(CachePolicy::ReturnCacheDataElseLoad),
+        )
+        .return_once(|_, _| {
+            Box::pin(async {
+                Ok(Some(UserInfo {
+                    name: UserName {
+                        nickname: Some("Alice".to_string()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }))
+            })
+        });
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .times(2)
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                ids: vec![ParticipantId::User(user_id!("a@prose.org"))],
+            }),
+        )
+        .return_const(());
+
+    let internals = Room::private_channel(muc_id!("room@conference.prose.org"))
+        .by_adding_participants([(
+            occupant_id!("room@conference.prose.org/me"),
+            Participant {
+                is_self: true,
+                affiliation: RoomAffiliation::Admin,
+                ..Default::default()
+            },
+        )]);
+
+    let room = RoomFactory::from(deps)
+        .build(internals.clone())
+        .to_generic_room();
+
+    // Admins can't appoint other admins…
+    let result = room
+        .grant_affiliation(&user_id!("a@prose.org"), RoomAffiliation::Admin)
+        .await;
+    assert!(matches!(result, Err(RoomError::InsufficientPrivileges)));
+
+    room.grant_affiliation(&user_id!("a@prose.org"), RoomAffiliation::Member)
+        .await?;
+
+    assert_eq!(
+        internals.with_participants(|p| p
+            .get(&ParticipantId::User(user_id!("a@prose.org")))
+            .map(|p| (p.affiliation, p.name.vcard.clone()))),
+        Some((RoomAffiliation::Member, Some("Alice".to_string())))
+    );
+
+    room.revoke_membership(&user_id!("a@prose.org")).await?;
+
+    assert!(internals.with_participants(|p| p
+        .get(&ParticipantId::User(user_id!("a@prose.org")))
+        .is_none()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_grants_role_if_moderator() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    deps.participation_service
+        .expect_set_role()
+        .once()
+        .with(
+            predicate::eq(occupant_id!("room@conference.prose.org/other")),
+            predicate::eq(RoomRole::Visitor),
+            predicate::always(),
+        )
+        .return_once(|_, _, reason| {
+            assert_eq!(None, reason);
+            Box::pin(async { Ok(()) })
+        });
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                ids: vec![ParticipantId::Occupant(occupant_id!(
+                    "room@conference.prose.org/other"
+                ))],
+            }),
+        )
+        .return_const(());
+
+    let self_id = ParticipantId::Occupant(occupant_id!("room@conference.prose.org/me"));
+    let other_id = ParticipantId::Occupant(occupant_id!("room@conference.prose.org/other"));
+
+    let internals = Room::public_channel(muc_id!("room@conference.prose.org"))
+        .by_adding_participants([
+            (
+                self_id.clone(),
+                Participant {
+                    is_self: true,
+                    affiliation: RoomAffiliation::Member,
+                    role: RoomRole::Participant,
+                    ..Default::default()
+                },
+            ),
+            (
+                other_id.clone(),
+                Participant {
+                    role: RoomRole::Participant,
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+    let room = RoomFactory::from(deps)
+        .build(internals.clone())
+        .to_generic_room();
+
+    let result = room
+        .grant_role(
+            &occupant_id!("room@conference.prose.org/other"),
+            RoomRole::Visitor,
+        )
+        .await;
+    assert!(matches!(result, Err(RoomError::InsufficientPrivileges)));
+
+    internals.with_participants_mut(|p| p.set_role(&self_id, RoomRole::Moderator));
+
+    // Only admins and owners may appoint moderators…
+    let result = room
+        .grant_role(
+            &occupant_id!("room@conference.prose.org/other"),
+            RoomRole::Moderator,
+        )
+        .await;
+    assert!(matches!(result, Err(RoomError::InsufficientPrivileges)));
+
+    room.grant_role(
+        &occupant_id!("room@conference.prose.org/other"),
+        RoomRole::Visitor,
+    )
+    .await?;
+
+    assert_eq!(
+        internals.with_participants(|p| p.get(&other_id).map(|p| p.role)),
+        Some(RoomRole::Visitor)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_kicks_and_bans_users() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    deps.participation_service
+        .expect_set_role()
+        .once()
+        .with(
+            predicate::eq(occupant_id!("room@conference.prose.org/other")),
+            predicate::eq(RoomRole::None),
+            predicate::always(),
+        )
+        .return_once(|_, _, reason| {
+            assert_eq!(Some("Spam"), reason);
+            Box::pin(async { Ok(()) })
+        });
+
+    deps.participation_service
+        .expect_set_affiliation()
+        .once()
+        .with(
+            predicate::eq(muc_id!("room@conference.prose.org")),
+            predicate::eq(user_id!("b@prose.org")),
+            predicate::eq(RoomAffiliation::Outcast),
+            predicate::always(),
+        )
+        .return_once(|_, _, _, reason| {
+            assert_eq!(Some("Trolling"), reason);
+            Box::pin(async { Ok(()) })
+        });
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                ids: vec![ParticipantId::Occupant(occupant_id!(
+                    "room@conference.prose.org/other"
+                ))],
+            }),
+        )
+        .return_const(());
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                ids: vec![ParticipantId::Occupant(occupant_id!(
+                    "room@conference.prose.org/b"
+                ))],
+            }),
+        )
+        .return_const(());
+
+    let self_id = ParticipantId::Occupant(occupant_id!("room@conference.prose.org/me"));
+    let other_id = ParticipantId::Occupant(occupant_id!("room@conference.prose.org/other"));
+    let b_id = ParticipantId::Occupant(occupant_id!("room@conference.prose.org/b"));
+
+    let internals = Room::public_channel(muc_id!("room@conference.prose.org"))
+        .by_adding_participants([
+            (
+                self_id.clone(),
+                Participant {
+                    is_self: true,
+                    affiliation: RoomAffiliation::Member,
+                    role: RoomRole::Participant,
+                    ..Default::default()
+                },
+            ),
+            (
+                other_id.clone(),
+                Participant {
+                    affiliation: RoomAffiliation::Member,
+                    role: RoomRole::Participant,
+                    ..Default::default()
+                },
+            ),
+            (
+                b_id.clone(),
+                Participant {
+                    real_id: Some(user_id!("b@prose.org")),
+                    affiliation: RoomAffiliation::Member,
+                    role: RoomRole::Participant,
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+    let room = RoomFactory::from(deps)
+        .build(internals.clone())
+        .to_generic_room();
+
+    let result = room
+        .kick_user(
+            &occupant_id!("room@conference.prose.org/other"),
+            Some("Spam"),
+        )
+        .await;
+    assert!(matches!(result, Err(RoomError::InsufficientPrivileges)));
+
+    let result = room
+        .ban_user(&user_id!("b@prose.org"), Some("Trolling"))
+        .await;
+    assert!(matches!(result, Err(RoomError::InsufficientPrivileges)));
+
+    let result = room.load_banned_users().await;
+    assert!(matches!(result, Err(RoomError::InsufficientPrivileges)));
+
+    internals.with_participants_mut(|p| {
+        p.set_affiliation(&self_id, true, RoomAffiliation::Admin);
+        p.set_role(&self_id, RoomRole::Moderator);
+    });
+
+    room.kick_user(
+        &occupant_id!("room@conference.prose.org/other"),
+        Some("Spam"),
+    )
+    .await?;
+    assert!(internals.with_participants(|p| p.get(&other_id).is_none()));
+
+    room.ban_user(&user_id!("b@prose.org"), Some("Trolling"))
+        .await?;
+    assert!(internals.with_participants(|p| p.get(&b_id).is_none()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_clear_local_history_only_deletes_messages_of_room() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    // Other rooms and the synced room settings must not be touched, which the mocks ensure by
+    // failing on any unexpected call.
+    deps.message_repo
+        .expect_clear_room()
+        .once()
+        .with(
+            predicate::eq(mock_data::account()),
+            predicate::eq(RoomId::from(user_id!("user@prose.org"))),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::MessagesNeedReload),
+        )
+        .return_const(());
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_const(());
+
+    let room = RoomFactory::from(deps)
+        .build(Room::direct_message(
+            user_id!("user@prose.org"),
+            Availability::Available,
+        ))
+        .to_generic_room();
+    room.clear_local_history().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_sends_read_marker_only_if_supported_and_enabled() -> Result<()> {
+    async fn mark_room_as_read(chat_markers: bool, send_read_receipts: bool) -> Result<bool> {
+        let mut deps = MockRoomFactoryDependencies::default();
+
+        deps.message_repo
+            .expect_get_last_received_message()
+            .returning(|_, _, _| {
+                Box::pin(async {
+                    Ok(Some(ArchivedMessageRef {
+                        stanza_id: MessageBuilder::stanza_id_for_index(1),
+                        timestamp: MessageBuilder::timestamp_for_index(1),
+                    }))
+                })
+            });
+        deps.message_repo
+            .expect_resolve_server_id()
+            .returning(|_, _, _| {
+                Box::pin(async {
+                    Ok(Some(MessageIdTriple {
+                        id: MessageBuilder::id_for_index(1),
+                        remote_id: Some(MessageBuilder::remote_id_for_index(1)),
+                        server_id: Some(MessageBuilder::stanza_id_for_index(1)),
+                    }))
+                })
+            });
+        deps.message_repo.expect_get().returning(|_, _, _| {
+            Box::pin(async {
+                Ok(vec![MessageBuilder::new_with_index(1)
+                    .set_from(occupant_id!("room@conference.prose.org/other"))
+                    .set_payload(MessageLikePayload::Message {
+                        body: MessageLikeBody::text("Hello"),
+                        attachments: vec![],
+                        encryption_info: None,
+                        is_transient: false,
+                        reply_to: None,
+                        thread_id: None,
+                        is_markable: true,
+                    })
+                    .build_message_like()])
+            })
+        });
+        deps.account_settings_repo
+            .expect_get()
+            .returning(|_| Box::pin(async { Ok(Default::default()) }));
+        deps.synced_room_settings_service
+            .expect_save_settings()
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        deps.client_event_dispatcher
+            .expect_dispatch_event()
+            .return_const(());
+
+        let did_send_marker = Arc::new(Mutex::new(false));
+        {
+            let did_send_marker = did_send_marker.clone();
+            deps.messaging_service
+                .expect_send_read_receipt()
+                .with(
+                    predicate::eq(RoomId::from(muc_id!("room@conference.prose.org"))),
+                    predicate::eq(MessageTargetId::from(MessageBuilder::stanza_id_for_index(
+                        1,
+                    ))),
+                )
+                .returning(move |_, _| {
+                    *did_send_marker.lock().unwrap() = true;
+                    Box::pin(async { Ok(()) })
+                });
+        }
+
+        let internals =
+            Room::group(muc_id!("room@conference.prose.org")).with_features(RoomFeatures {
+                chat_markers,
+                ..Default::default()
+            });
+        internals.with_settings_mut(|settings| settings.send_read_receipts = send_read_receipts);
+
+        let room = RoomFactory::from(deps).build(internals).to_generic_room();
+        room.mark_as_read().await?;
+
+        let did_send_marker = *did_send_marker.lock().unwrap();
+        Ok(did_send_marker)
+    }
+
+    assert!(mark_room_as_read(true, true).await?);
+    assert!(!mark_room_as_read(false, true).await?);
+    assert!(!mark_room_as_read(true, false).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fills_result_set_when_loading_messages() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
+
+    deps.ctx.config.message_page_size = 5;
+
+    deps.message_archive_service
+        .expect_load_messages_before()
+        .once()
+        .return_once(|_, before, page_size| {
+            assert_eq!(5, page_size);
+            assert!(before.is_none());
+
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: vec![
+                        MessageBuilder::new_with_index(100)
+                            .set_from(user_id!("b@prose.org"))
+                            .set_payload(MessageLikePayload::Reaction {
+                                target_id: MessageBuilder::remote_id_for_index(90).into(),
+                                emojis: vec!["✅".into()],
+                            })
+                            .build_archived_message("q1", None),
+                        MessageBuilder::new_with_index(101)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload("Message 101")
+                            .build_archived_message("q1", None),
+                        MessageBuilder::new_with_index(102)
+                            .set_from(user_id!("b@prose.org"))
+                            .set_payload("Message 102")
+                            .build_archived_message("q1", None),
+                        MessageBuilder::new_with_index(103)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload(MessageLikePayload::Reaction {
+                                target_id: MessageBuilder::remote_id_for_index(101).into(),
+                                emojis: vec!["🍕".into()],
+                            })
+                            .build_archived_message("q1", None),
+                        MessageBuilder::new_with_index(104)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload(MessageLikePayload::Reaction {
+                                target_id: MessageBuilder::remote_id_for_index(102).into(),
+                                emojis: vec!["🎉".into()],
+                            })
+                            .build_archived_message("q1", None),
+                    ],
+                    is_last: false,
+                })
+            })
+        });
+
+    deps.message_archive_service
+        .expect_load_messages_before()
+        .once()
+        .return_once(|_, before, page_size| {
+            assert_eq!(5, page_size);
+            assert_eq!(Some(&MessageBuilder::stanza_id_for_index(100)), before);
+
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: vec![
+                        MessageBuilder::new_with_index(90)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload("Message 90")
+                            .build_archived_message("q2", None),
+                        MessageBuilder::new_with_index(91)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload(MessageLikePayload::Reaction {
+                                target_id: MessageBuilder::remote_id_for_index(90).into(),
+                                emojis: vec!["✅".into()],
+                            })
+                            .build_archived_message("q2", None),
+                        MessageBuilder::new_with_index(92)
+                            .set_from(user_id!("b@prose.org"))
+                            .set_payload("Message 92")
+                            .build_archived_message("q2", None),
+                        MessageBuilder::new_with_index(93)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload("Message 93")
+                            .build_archived_message("q2", None),
+                        MessageBuilder::new_with_index(94)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload("Message 94")
+                            .build_archived_message("q2", None),
+                    ],
+                    is_last: true,
+                })
+            })
+        });
+
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .returning(|_, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_resolve_server_id()
+        .times(10)
+        .returning(|_, _, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_append()
+        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+    let room = RoomFactory::from(deps)
+        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .to_generic_room();
+
+    let result = room.load_latest_messages().await?;
+
+    assert_eq!(None, result.before);
+    assert_eq!(None, result.after);
+    assert!(result.is_complete);
+
+    assert_eq!(
+        vec![
+            MessageBuilder::new_with_index(90)
+                .set_id("msg-id-10")
+                .set_from(user_id!("a@prose.org"))
+                .set_from_name("A")
+                .set_payload("Message 90")
+                .set_reactions([Reaction {
+                    emoji: "✅".into(),
+                    from: vec![
+                        user_id!("a@prose.org").into(),
+                        user_id!("b@prose.org").into(),
+                    ],
+                    last_updated: Some(MessageBuilder::timestamp_for_index(100)),
+                },])
+                .build_message_dto(),
+            MessageBuilder::new_with_index(92)
+                .set_id("msg-id-8")
+                .set_from(user_id!("b@prose.org"))
+                .set_from_name("B")
+                .set_payload("Message 92")
+                .build_message_dto(),
+            MessageBuilder::new_with_index(93)
+                .set_id("msg-id-7")
+                .set_from(user_id!("a@prose.org"))
+                .set_from_name("A")
+                .set_payload("Message 93")
+                .build_message_dto(),
+            MessageBuilder::new_with_index(94)
+                .set_id("msg-id-6")
+                .set_from(user_id!("a@prose.org"))
+                .set_from_name("A")
+                .set_payload("Message 94")
+                .build_message_dto(),
+            MessageBuilder::new_with_index(101)
+                .set_id("msg-id-4")
+                .set_from(user_id!("a@prose.org"))
+                .set_from_name("A")
+                .set_payload("Message 101")
+                .set_reactions([Reaction {
+                    emoji: "🍕".into(),
+                    from: vec![user_id!("a@prose.org").into()],
+                    last_updated: Some(MessageBuilder::timestamp_for_index(103)),
+                }])
+                .build_message_dto(),
+            MessageBuilder::new_with_index(102)
+                .set_id("msg-id-3")
+                .set_from(user_id!("b@prose.org"))
+                .set_from_name("B")
+                .set_payload("Message 102")
+                .set_reactions([Reaction {
+                    emoji: "🎉".into(),
+                    from: vec![user_id!("a@prose.org").into(),],
+                    last_updated: Some(MessageBuilder::timestamp_for_index(104)),
+                }])
+                .build_message_dto()
+        ],
+        result.messages
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stops_at_max_message_pages_to_load() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
+
+    deps.ctx.config.message_page_size = 5;
+    deps.ctx.config.max_message_pages_to_load = 2;
+
+    deps.message_archive_service
+        .expect_load_messages_before()
+        .once()
+        .return_once(|_, before, page_size| {
+            assert_eq!(5, page_size);
+            assert_eq!(None, before);
+
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: (96..=99)
+                        .into_iter()
+                        .map(|idx| {
+                            MessageBuilder::new_with_index(idx)
+                                .set_payload(MessageLikePayload::Reaction {
+                                    target_id: MessageBuilder::remote_id_for_index(1001).into(),
+                                    emojis: vec![],
+                                })
+                                .build_archived_message("q1", None)
+                        })
+                        .chain(iter::once(
+                            MessageBuilder::new_with_index(100)
+                                .set_from(user_id!("a@prose.org"))
+                                .set_payload("Message 100")
+                                .build_archived_message("q1", None),
+                        ))
+                        .collect(),
+                    is_last: false,
+                })
+            })
+        });
+
+    deps.message_archive_service
+        .expect_load_messages_before()
+        .once()
+        .return_once(|_, before, page_size| {
+            assert_eq!(5, page_size);
+            assert_eq!(Some(&MessageBuilder::stanza_id_for_index(96)), before);
+
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: (91..=95)
+                        .into_iter()
+                        .map(|idx| {
+                            MessageBuilder::new_with_index(idx)
+                                .set_payload(MessageLikePayload::Reaction {
+                                    target_id: MessageBuilder::remote_id_for_index(1001).into(),
+                                    emojis: vec![],
+                                })
+                                .build_archived_message("q1", None)
+                        })
+                        .collect(),
+                    is_last: false,
+                })
+            })
+        });
+
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .returning(|_, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_resolve_server_id()
+        .times(10)
+        .returning(|_, _, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_append()
+        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+    let room = RoomFactory::from(deps)
+        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .to_generic_room();
+
+    let result = room.load_latest_messages().await?;
+
+    assert_eq!(
+        Some(MessageCursor::from(MessageBuilder::stanza_id_for_index(91))),
+        result.before
+    );
+    assert!(!result.is_complete);
+
+    assert_eq!(
+        vec![MessageBuilder::new_with_index(100)
+            .set_id("msg-id-1")
+            .set_from(user_id!("a@prose.org"))
+            .set_from_name("A")
+            .set_payload("Message 100")
+            .build_message_dto()],
+        result.messages
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stops_at_last_page() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    deps.ctx.config.message_page_size = 100;
+    deps.ctx.config.max_message_pages_to_load = 100;
+
+    deps.message_archive_service
+        .expect_load_messages_before()
+        .once()
+        .return_once(|_, _, _| {
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: (96..=100)
+                        .into_iter()
+                        .map(|idx| {
+                            MessageBuilder::new_with_index(idx).build_archived_message("q1", None)
+                        })
+                        .collect(),
+                    is_last: false,
+                })
+            })
+        });
+
+    deps.message_archive_service
+        .expect_load_messages_before()
+        .once()
+        .return_once(|_, _, _| {
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: (93..=95)
+                        .into_iter()
+                        .map(|idx| {
+                            MessageBuilder::new_with_index(idx).build_archived_message("q1", None)
+                        })
+                        .collect(),
+                    is_last: true,
+                })
+            })
+        });
+
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .returning(|_, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_resolve_server_id()
+        .times(8)
+        .returning(|_, _, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_append()
+        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+    let room = RoomFactory::from(deps)
+        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .to_generic_room();
+
+    let result = room.load_latest_messages().await?;
+
+    assert_eq!(None, result.before);
+    assert_eq!(8, result.messages.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resolves_targeted_messages_when_loading_messages() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.ctx.config.max_message_pages_to_load = 1;
+    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
+
+    deps.message_archive_service
+        .expect_load_messages_before()
+        .once()
+        .return_once(|_, before, _| {
+            assert_eq!(Some(&MessageServerId::from("some-server-id")), before);
+
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: vec![
+                        MessageBuilder::new_with_index(1)
+                            .set_from(user_id!("user@prose.org"))
+                            .build_archived_message("q1", None),
+                        MessageBuilder::new_with_index(2)
                             .set_from(user_id!("user@prose.org"))
                             .build_archived_message("q1", None),
                         MessageBuilder::new_with_index(3)
                             .set_from(user_id!("a@prose.org"))
                             .set_payload(MessageLikePayload::Reaction {
-                                target_id: MessageBuilder::remote_id_for_index(2).into(),
+                                target_id: MessageBuilder::remote_id_for_index(2).into(),
+                                emojis: vec!["🍕".into()],
+                            })
+                            .build_archived_message("q1", None),
+                        MessageBuilder::new_with_index(4)
+                            .set_from(user_id!("user@prose.org"))
+                            .build_archived_message("q1", None),
+                        MessageBuilder::new_with_index(5)
+                            .set_from(user_id!("user@prose.org"))
+                            .set_timestamp(Utc.with_ymd_and_hms(2024, 02, 23, 0, 0, 0).unwrap())
+                            .build_archived_message("q1", None),
+                    ],
+                    is_last: false,
+                })
+            })
+        });
+
+    deps.message_repo
+        .expect_get_messages_targeting()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::from(muc_id!("room@conference.prose.org"))),
+            predicate::eq(vec![
+                MessageBuilder::remote_id_for_index(5).into(),
+                MessageBuilder::stanza_id_for_index(5).into(),
+                MessageBuilder::remote_id_for_index(4).into(),
+                MessageBuilder::stanza_id_for_index(4).into(),
+                MessageBuilder::remote_id_for_index(2).into(),
+                MessageBuilder::stanza_id_for_index(2).into(),
+                MessageBuilder::remote_id_for_index(1).into(),
+                MessageBuilder::stanza_id_for_index(1).into(),
+            ]),
+            predicate::eq(Utc.with_ymd_and_hms(2024, 02, 23, 0, 0, 0).unwrap()),
+        )
+        .return_once(|_, _, _, _| {
+            Box::pin(async {
+                Ok(vec![
+                    MessageBuilder::new_with_index(6)
+                        .set_from(user_id!("b@prose.org"))
+                        .set_payload(MessageLikePayload::Reaction {
+                            target_id: MessageBuilder::remote_id_for_index(1).into(),
+                            emojis: vec!["🧩".into()],
+                        })
+                        .build_message_like(),
+                    MessageBuilder::new_with_index(7)
+                        .set_from(user_id!("a@prose.org"))
+                        .set_payload(MessageLikePayload::Reaction {
+                            target_id: MessageBuilder::remote_id_for_index(4).into(),
+                            emojis: vec!["🍻".into()],
+                        })
+                        .build_message_like(),
+                    // This should win over message 3 since `get_messages_targeting`
+                    // returns newer messages.
+                    MessageBuilder::new_with_index(8)
+                        .set_from(user_id!("a@prose.org"))
+                        .set_payload(MessageLikePayload::Reaction {
+                            target_id: MessageBuilder::remote_id_for_index(2).into(),
+                            emojis: vec!["🍔".into()],
+                        })
+                        .build_message_like(),
+                    MessageBuilder::new_with_index(9)
+                        .set_from(user_id!("a@prose.org"))
+                        .set_payload(MessageLikePayload::Reaction {
+                            target_id: MessageBuilder::remote_id_for_index(1).into(),
+                            emojis: vec!["❌".into()],
+                        })
+                        .build_message_like(),
+                    // This should win over message 9 since it is newer
+                    MessageBuilder::new_with_index(10)
+                        .set_from(user_id!("a@prose.org"))
+                        .set_payload(MessageLikePayload::Reaction {
+                            target_id: MessageBuilder::remote_id_for_index(1).into(),
+                            emojis: vec!["✅".into()],
+                        })
+                        .build_message_like(),
+                ])
+            })
+        });
+
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .returning(|_, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_resolve_server_id()
+        .times(5)
+        .returning(|_, _, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_append()
+        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+    let room = RoomFactory::from(deps)
+        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .to_generic_room();
+
+    let result = room
+        .load_messages_before(&MessageServerId::from("some-server-id").into())
+        .await?;
+
+    assert_eq!(
+        vec![
+            MessageBuilder::new_with_index(1)
+                .set_id("msg-id-5")
+                .set_from(user_id!("user@prose.org"))
+                .set_from_name("User")
+                .set_reactions([
+                    Reaction {
+                        emoji: "🧩".into(),
+                        from: vec![user_id!("b@prose.org").into()],
+                        last_updated: Some(MessageBuilder::timestamp_for_index(6)),
+                    },
+                    Reaction {
+                        emoji: "✅".into(),
+                        from: vec![user_id!("a@prose.org").into()],
+                        last_updated: Some(MessageBuilder::timestamp_for_index(10)),
+                    }
+                ])
+                .build_message_dto(),
+            MessageBuilder::new_with_index(2)
+                .set_id("msg-id-4")
+                .set_from(user_id!("user@prose.org"))
+                .set_from_name("User")
+                .set_reactions([Reaction {
+                    emoji: "🍔".into(),
+                    from: vec![user_id!("a@prose.org").into()],
+                    last_updated: Some(MessageBuilder::timestamp_for_index(8)),
+                }])
+                .build_message_dto(),
+            MessageBuilder::new_with_index(4)
+                .set_id("msg-id-2")
+                .set_from(user_id!("user@prose.org"))
+                .set_from_name("User")
+                .set_reactions([Reaction {
+                    emoji: "🍻".into(),
+                    from: vec![user_id!("a@prose.org").into()],
+                    last_updated: Some(MessageBuilder::timestamp_for_index(7)),
+                }])
+                .build_message_dto(),
+            MessageBuilder::new_with_index(5)
+                .set_id("msg-id-1")
+                .set_from(user_id!("user@prose.org"))
+                .set_from_name("User")
+                .set_timestamp(Utc.with_ymd_and_hms(2024, 02, 23, 0, 0, 0).unwrap())
+                .build_message_dto()
+        ],
+        result.messages
+    );
+
+    assert_eq!(
+        Some(MessageCursor::from(MessageBuilder::stanza_id_for_index(1))),
+        result.before
+    );
+    assert_eq!(
+        Some(MessageCursor::from(MessageBuilder::stanza_id_for_index(5))),
+        result.after
+    );
+    assert!(!result.is_complete);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_loads_messages_after_cursor() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.message_id_provider = Arc::new(WrappingMessageIdProvider::incrementing("msg-id"));
+
+    deps.ctx.config.message_page_size = 2;
+    deps.ctx.config.max_message_pages_to_load = 3;
+
+    deps.message_archive_service
+        .expect_load_messages_after()
+        .once()
+        .return_once(|_, after, page_size| {
+            assert_eq!(2, page_size);
+            assert_eq!(&MessageBuilder::stanza_id_for_index(10), after);
+
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: vec![
+                        MessageBuilder::new_with_index(11)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload(MessageLikePayload::Reaction {
+                                target_id: MessageBuilder::remote_id_for_index(10).into(),
                                 emojis: vec!["🍕".into()],
                             })
                             .build_archived_message("q1", None),
-                        MessageBuilder::new_with_index(4)
-                            .set_from(user_id!("user@prose.org"))
-                            .build_archived_message("q1", None),
-                        MessageBuilder::new_with_index(5)
-                            .set_from(user_id!("user@prose.org"))
-                            .set_timestamp(Utc.with_ymd_and_hms(2024, 02, 23, 0, 0, 0).unwrap())
+                        MessageBuilder::new_with_index(12)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload("Message 12")
                             .build_archived_message("q1", None),
                     ],
                     is_last: false,
@@ -756,134 +2471,619 @@ async fn test_resolves_targeted_messages_when_loading_messages() -> Result<()> {
             })
         });
 
+    deps.message_archive_service
+        .expect_load_messages_after()
+        .once()
+        .return_once(|_, after, page_size| {
+            assert_eq!(2, page_size);
+            assert_eq!(&MessageBuilder::stanza_id_for_index(12), after);
+
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: vec![
+                        MessageBuilder::new_with_index(13)
+                            .set_from(user_id!("b@prose.org"))
+                            .set_payload("Message 13")
+                            .build_archived_message("q2", None),
+                        MessageBuilder::new_with_index(14)
+                            .set_from(user_id!("a@prose.org"))
+                            .set_payload("Message 14")
+                            .build_archived_message("q2", None),
+                    ],
+                    is_last: false,
+                })
+            })
+        });
+
+    deps.message_repo
+        .expect_get_messages_targeting()
+        .once()
+        .return_once(|_, _, _, _| Box::pin(async { Ok(vec![]) }));
+
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .returning(|_, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_resolve_server_id()
+        .times(4)
+        .returning(|_, _, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_append()
+        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+    let room = RoomFactory::from(deps)
+        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .to_generic_room();
+
+    let result = room
+        .load_messages_after(&MessageBuilder::stanza_id_for_index(10).into())
+        .await?;
+
+    assert_eq!(
+        vec![
+            MessageBuilder::new_with_index(12)
+                .set_id("msg-id-2")
+                .set_from(user_id!("a@prose.org"))
+                .set_from_name("A")
+                .set_payload("Message 12")
+                .build_message_dto(),
+            MessageBuilder::new_with_index(13)
+                .set_id("msg-id-3")
+                .set_from(user_id!("b@prose.org"))
+                .set_from_name("B")
+                .set_payload("Message 13")
+                .build_message_dto(),
+            MessageBuilder::new_with_index(14)
+                .set_id("msg-id-4")
+                .set_from(user_id!("a@prose.org"))
+                .set_from_name("A")
+                .set_payload("Message 14")
+                .build_message_dto(),
+        ],
+        result.messages
+    );
+
+    assert_eq!(
+        Some(MessageCursor::from(MessageBuilder::stanza_id_for_index(11))),
+        result.before
+    );
+    assert_eq!(
+        Some(MessageCursor::from(MessageBuilder::stanza_id_for_index(14))),
+        result.after
+    );
+    assert!(!result.is_complete);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reuses_message_senders_across_page_loads() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.ctx.config.max_message_pages_to_load = 1;
+
+    deps.message_archive_service
+        .expect_load_messages_before()
+        .times(2)
+        .returning(|_, _, _| {
+            Box::pin(async {
+                Ok(MessagePage {
+                    messages: vec![MessageBuilder::new_with_index(1)
+                        .set_from(user_id!("a@prose.org"))
+                        .build_archived_message("q1", None)],
+                    is_last: false,
+                })
+            })
+        });
+
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .once()
+        .with(
+            predicate::eq(user_id!("a@prose.org")),
+            predicate::eq(CachePolicy::ReturnCacheDataDontLoad),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(None) }));
+
+    deps.message_repo
+        .expect_resolve_server_id()
+        .returning(|_, _, _| Box::pin(async { Ok(None) }));
+    deps.message_repo
+        .expect_get_messages_targeting()
+        .returning(|_, _, _, _| Box::pin(async { Ok(vec![]) }));
+    deps.message_repo
+        .expect_append()
+        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+    let room = RoomFactory::from(deps)
+        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .to_generic_room();
+
+    let result = room.load_latest_messages().await?;
+    let cursor = result.before.clone().expect("Expected a cursor");
+    assert_eq!("A", result.messages[0].from.name);
+
+    let result = room.load_messages_before(&cursor).await?;
+    assert_eq!("A", result.messages[0].from.name);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_composing_timeout_is_configurable() -> Result<()> {
+    let internals = Room::group(muc_id!("room@conference.prose.org")).by_adding_participants([(
+        occupant_id!("room@conference.prose.org/a"),
+        Participant::owner()
+            .set_vcard_name("Aron Doe")
+            .set_compose_state(ComposeState::Composing)
+            .set_compose_state_updated(Utc.with_ymd_and_hms(2023, 01, 04, 0, 0, 0).unwrap()),
+    )]);
+
+    let composing_users = vec![ParticipantBasicInfo {
+        id: occupant_id!("room@conference.prose.org/a").into(),
+        name: "Aron Doe".to_string(),
+        avatar: None,
+    }];
+
+    let time_provider = Arc::new(ConstantTimeProvider::ymd_hms(2023, 01, 04, 0, 0, 29));
+
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.time_provider = time_provider.clone();
+
+    let room = RoomFactory::from(deps)
+        .build(internals.clone())
+        .to_generic_room();
+    assert_eq!(composing_users, room.load_composing_users().await?);
+
+    time_provider.set_ymd_hms(2023, 01, 04, 0, 0, 31);
+    assert!(room.load_composing_users().await?.is_empty());
+
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.time_provider = time_provider.clone();
+    deps.ctx.config.composing_timeout_secs = 60;
+
+    let room = RoomFactory::from(deps).build(internals).to_generic_room();
+    assert_eq!(composing_users, room.load_composing_users().await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_load_latest_messages_from_cache_when_not_connected() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.ctx.connection_properties.write().take();
+    deps.ctx.set_cached_account(Some(mock_data::account()));
+
     deps.message_repo
-        .expect_get_messages_targeting()
+        .expect_get_latest_messages()
         .once()
         .with(
-            predicate::always(),
-            predicate::eq(RoomId::from(muc_id!("room@conference.prose.org"))),
-            predicate::eq(vec![
-                MessageBuilder::remote_id_for_index(5).into(),
-                MessageBuilder::stanza_id_for_index(5).into(),
-                MessageBuilder::remote_id_for_index(4).into(),
-                MessageBuilder::stanza_id_for_index(4).into(),
-                MessageBuilder::remote_id_for_index(2).into(),
-                MessageBuilder::stanza_id_for_index(2).into(),
-                MessageBuilder::remote_id_for_index(1).into(),
-                MessageBuilder::stanza_id_for_index(1).into(),
-            ]),
-            predicate::eq(Utc.with_ymd_and_hms(2024, 02, 23, 0, 0, 0).unwrap()),
+            predicate::eq(mock_data::account()),
+            predicate::eq(RoomId::User(user_id!("user@prose.org"))),
+            predicate::eq(100),
         )
-        .return_once(|_, _, _, _| {
+        .return_once(|_, _, _| {
             Box::pin(async {
                 Ok(vec![
-                    MessageBuilder::new_with_index(6)
-                        .set_from(user_id!("b@prose.org"))
-                        .set_payload(MessageLikePayload::Reaction {
-                            target_id: MessageBuilder::remote_id_for_index(1).into(),
-                            emojis: vec!["🧩".into()],
-                        })
-                        .build_message_like(),
-                    MessageBuilder::new_with_index(7)
-                        .set_from(user_id!("a@prose.org"))
-                        .set_payload(MessageLikePayload::Reaction {
-                            target_id: MessageBuilder::remote_id_for_index(4).into(),
-                            emojis: vec!["🍻".into()],
-                        })
-                        .build_message_like(),
-                    // This should win over message 3 since `get_messages_targeting`
-                    // returns newer messages.
-                    MessageBuilder::new_with_index(8)
-                        .set_from(user_id!("a@prose.org"))
-                        .set_payload(MessageLikePayload::Reaction {
-                            target_id: MessageBuilder::remote_id_for_index(2).into(),
-                            emojis: vec!["🍔".into()],
-                        })
-                        .build_message_like(),
-                    MessageBuilder::new_with_index(9)
-                        .set_from(user_id!("a@prose.org"))
-                        .set_payload(MessageLikePayload::Reaction {
-                            target_id: MessageBuilder::remote_id_for_index(1).into(),
-                            emojis: vec!["❌".into()],
-                        })
+                    MessageBuilder::new_with_index(1)
+                        .set_from(user_id!("user@prose.org"))
                         .build_message_like(),
-                    // This should win over message 9 since it is newer
-                    MessageBuilder::new_with_index(10)
-                        .set_from(user_id!("a@prose.org"))
-                        .set_payload(MessageLikePayload::Reaction {
-                            target_id: MessageBuilder::remote_id_for_index(1).into(),
-                            emojis: vec!["✅".into()],
-                        })
+                    MessageBuilder::new_with_index(2)
+                        .set_from(user_id!("user@prose.org"))
                         .build_message_like(),
                 ])
             })
         });
 
-    deps.user_info_domain_service
-        .expect_get_user_info()
-        .returning(|_, _| Box::pin(async { Ok(None) }));
+    let room = RoomFactory::from(deps)
+        .build(Room::direct_message(
+            user_id!("user@prose.org"),
+            Availability::Available,
+        ))
+        .to_generic_room();
 
-    deps.message_repo
-        .expect_resolve_server_id()
-        .times(5)
-        .returning(|_, _, _| Box::pin(async { Ok(None) }));
+    let result = room.load_latest_messages().await?;
+
+    assert_eq!(
+        result
+            .messages
+            .iter()
+            .map(|m| m.id.clone())
+            .collect::<Vec<_>>(),
+        vec![
+            MessageBuilder::id_for_index(1),
+            MessageBuilder::id_for_index(2)
+        ]
+    );
+    assert_eq!(
+        result.before,
+        Some(MessageCursor::from(MessageBuilder::stanza_id_for_index(1)))
+    );
+    assert_eq!(result.after, None);
+    assert!(!result.is_complete);
+    assert_eq!(result.source, DataSource::Cached);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_export_history() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.ctx.config.history_export_page_size = 2;
+
+    let internals = Room::group(muc_id!("room@conference.prose.org"))
+        .with_members([RegisteredMember {
+            user_id: user_id!("a@prose.org"),
+            name: Some("Aron Doe".to_string()),
+            affiliation: RoomAffiliation::Owner,
+            is_self: false,
+        }])
+        .by_adding_participants([(
+            occupant_id!("room@conference.prose.org/b"),
+            Participant::owner().set_vcard_name("Bernhard Doe"),
+        )]);
+
+    let messages = vec![
+        MessageBuilder::new_with_index(1)
+            .set_from(user_id!("a@prose.org"))
+            .build_message_like(),
+        MessageBuilder::new_with_index(2)
+            .set_from(occupant_id!("room@conference.prose.org/b"))
+            .build_message_like(),
+        MessageBuilder::new_with_index(3)
+            .set_from(user_id!("a@prose.org"))
+            .build_message_like(),
+        MessageBuilder::new_with_index(4)
+            .set_from(user_id!("a@prose.org"))
+            .set_payload(MessageLikePayload::Retraction {
+                target_id: MessageBuilder::stanza_id_for_index(3).into(),
+                reason: None,
+                moderated_by: None,
+            })
+            .build_message_like(),
+    ];
+
+    // Pages are loaded in chronological order, starting at the timestamp of the last message
+    // that was held back…
+    let pages = Arc::new(Mutex::new(vec![]));
+    deps.message_repo.expect_get_messages_in_range().returning({
+        let messages = messages.clone();
+        let pages = pages.clone();
+        move |_, _, start, _, limit| {
+            pages.lock().unwrap().push(start);
+            let page = messages
+                .iter()
+                .filter(|message| message.timestamp >= start)
+                .take(limit as usize)
+                .cloned()
+                .collect::<Vec<_>>();
+            Box::pin(async { Ok(page) })
+        }
+    });
+
+    // …and resolved together with the messages targeting them.
+    deps.message_repo.expect_get_all().returning({
+        let messages = messages.clone();
+        move |_, _, ids| {
+            let parts = messages
+                .iter()
+                .filter(|message| {
+                    ids.contains(&message.id)
+                        || (message.payload.target_id()
+                            == Some(&MessageBuilder::stanza_id_for_index(3).into())
+                            && ids.contains(&MessageBuilder::id_for_index(3)))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            Box::pin(async { Ok(parts) })
+        }
+    });
+
+    let room = RoomFactory::from(deps).build(internals).to_generic_room();
+
+    let history = serde_json::from_slice::<ExportedHistory>(
+        &room.export_history(ExportFormat::Json, None).await?,
+    )?;
+
+    assert_eq!(ExportedHistory::VERSION, history.version);
+    assert_eq!("room@conference.prose.org", history.room_id);
+    assert_eq!(
+        vec![
+            (
+                MessageBuilder::id_for_index(1).to_string(),
+                "Aron Doe".to_string(),
+                Some("Message 1".to_string()),
+                false
+            ),
+            (
+                MessageBuilder::id_for_index(2).to_string(),
+                "Bernhard Doe".to_string(),
+                Some("Message 2".to_string()),
+                false
+            ),
+            (
+                MessageBuilder::id_for_index(3).to_string(),
+                "Aron Doe".to_string(),
+                None,
+                true
+            ),
+        ],
+        history
+            .messages
+            .into_iter()
+            .map(|message| (
+                message.id,
+                message.from.name,
+                message.body,
+                message.is_retracted
+            ))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![
+            DateTime::<Utc>::MIN_UTC,
+            MessageBuilder::timestamp_for_index(2),
+            MessageBuilder::timestamp_for_index(3),
+            MessageBuilder::timestamp_for_index(4),
+        ],
+        *pages.lock().unwrap()
+    );
+
+    let text = room
+        .export_history(
+            ExportFormat::PlainText,
+            Some(DateRange {
+                start: MessageBuilder::timestamp_for_index(2),
+                end: MessageBuilder::timestamp_for_index(4),
+            }),
+        )
+        .await?;
+
+    assert_eq!(
+        "[2021-09-06 00:02:00 UTC] Bernhard Doe: Message 2\n[2021-09-06 00:03:00 UTC] Aron Doe: <retracted>\n",
+        String::from_utf8(text)?
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_import_history() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.ctx.config.history_import_batch_size = 2;
 
+    // The cached messages are looked up once per batch…
     deps.message_repo
-        .expect_append()
-        .returning(|_, _, _| Box::pin(async { Ok(()) }));
+        .expect_find_cached()
+        .times(3)
+        .returning(|_, _, messages| {
+            let cached_ids = messages
+                .iter()
+                .filter(|message| {
+                    message.server_id == Some("s2".into()) || message.remote_id == Some("r4".into())
+                })
+                .map(|message| message.id.clone())
+                .collect::<HashSet<_>>();
+            Box::pin(async move { Ok(cached_ids) })
+        });
+
+    let batches = Arc::new(Mutex::new(vec![]));
+    deps.message_repo.expect_append().times(3).returning({
+        let batches = batches.clone();
+        move |_, _, messages| {
+            batches.lock().unwrap().push(
+                messages
+                    .iter()
+                    .map(|message| (message.server_id.clone(), message.from.clone()))
+                    .collect::<Vec<_>>(),
+            );
+            Box::pin(async { Ok(()) })
+        }
+    });
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::MessagesNeedReload),
+        )
+        .return_once(|_, _| ());
 
     let room = RoomFactory::from(deps)
-        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .build(Room::group(muc_id!("room@conference.prose.org")))
         .to_generic_room();
 
-    let result = room
-        .load_messages_before(&MessageId::from("some-stanza-id"))
+    let mut dump = br#"{"stanza_id":"s1","id":"r1","from":"room@conference.prose.org/a","timestamp":"2024-03-01T10:00:00Z","body":"Hello"}
+{"stanza_id":"s1","id":"r1","from":"room@conference.prose.org/a","timestamp":"2024-03-01T10:00:00Z","body":"Hello"}
+not json
+
+"#
+    .to_vec();
+    // Invalid UTF-8 fails only this line…
+    dump.extend_from_slice(b"\xff\xfe\n");
+    dump.extend_from_slice(
+        br#"{"stanza_id":"s2","from":"b@prose.org","timestamp":"2024-03-01T10:01:00Z","body":"Cached"}
+{"stanza_id":"s3","from":"b@prose.org","timestamp":"2024-03-01T10:02:00Z","body":"*Hi*"}
+{"id":"r4","from":"c@prose.org","timestamp":"2024-03-01T10:03:00Z","body":"Cached by id"}
+{"id":"r5","from":"d@prose.org","timestamp":"2024-03-01T10:04:00Z","body":"Only an id"}
+{"id":"r5","from":"d@prose.org","timestamp":"2024-03-01T10:04:00Z","body":"Only an id"}
+{"from":"e@prose.org","timestamp":"2024-03-01T10:05:00Z","body":"No ids"}
+"#,
+    );
+
+    let mut progress = vec![];
+    let summary = room
+        .import_history(dump.as_slice(), |summary| progress.push(summary.clone()))
         .await?;
 
     assert_eq!(
+        summary,
+        HistoryImportSummary {
+            imported: 4,
+            skipped: 4,
+            failed: 2,
+        }
+    );
+    assert_eq!(
+        progress
+            .iter()
+            .map(|summary| summary.imported)
+            .collect::<Vec<_>>(),
+        vec![1, 2, 4]
+    );
+    assert_eq!(
+        *batches.lock().unwrap(),
         vec![
-            MessageBuilder::new_with_index(1)
-                .set_id("msg-id-5")
-                .set_from(user_id!("user@prose.org"))
-                .set_from_name("User")
-                .set_reactions([
-                    Reaction {
-                        emoji: "🧩".into(),
-                        from: vec![user_id!("b@prose.org").into()]
-                    },
-                    Reaction {
-                        emoji: "✅".into(),
-                        from: vec![user_id!("a@prose.org").into()]
-                    }
-                ])
-                .build_message_dto(),
-            MessageBuilder::new_with_index(2)
-                .set_id("msg-id-4")
-                .set_from(user_id!("user@prose.org"))
-                .set_from_name("User")
-                .set_reactions([Reaction {
-                    emoji: "🍔".into(),
-                    from: vec![user_id!("a@prose.org").into()]
-                }])
-                .build_message_dto(),
-            MessageBuilder::new_with_index(4)
-                .set_id("msg-id-2")
-                .set_from(user_id!("user@prose.org"))
-                .set_from_name("User")
-                .set_reactions([Reaction {
-                    emoji: "🍻".into(),
-                    from: vec![user_id!("a@prose.org").into()]
-                }])
-                .build_message_dto(),
-            MessageBuilder::new_with_index(5)
-                .set_id("msg-id-1")
-                .set_from(user_id!("user@prose.org"))
-                .set_from_name("User")
-                .set_timestamp(Utc.with_ymd_and_hms(2024, 02, 23, 0, 0, 0).unwrap())
-                .build_message_dto()
-        ],
-        result.messages
+            vec![(
+                Some("s1".into()),
+                ParticipantId::Occupant(occupant_id!("room@conference.prose.org/a"))
+            )],
+            vec![(
+                Some("s3".into()),
+                ParticipantId::User(user_id!("b@prose.org"))
+            )],
+            vec![
+                (None, ParticipantId::User(user_id!("d@prose.org"))),
+                (None, ParticipantId::User(user_id!("e@prose.org"))),
+            ],
+        ]
     );
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_reloads_messages_imported_before_import_failed() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.ctx.config.history_import_batch_size = 1;
+
+    deps.message_repo
+        .expect_find_cached()
+        .times(2)
+        .returning(|_, _, _| Box::pin(async { Ok(HashSet::new()) }));
+
+    let mut seq = Sequence::new();
+    deps.message_repo
+        .expect_append()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+    deps.message_repo
+        .expect_append()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _, _| Box::pin(async { Err(anyhow::anyhow!("Disk full")) }));
+
+    // The first message is in the cache already, so the UI needs to reload anyway.
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::MessagesNeedReload),
+        )
+        .return_once(|_, _| ());
+
+    let room = RoomFactory::from(deps)
+        .build(Room::group(muc_id!("room@conference.prose.org")))
+        .to_generic_room();
+
+    let dump = r#"{"stanza_id":"s1","from":"b@prose.org","timestamp":"2024-03-01T10:00:00Z","body":"Hello"}
+{"stanza_id":"s2","from":"b@prose.org","timestamp":"2024-03-01T10:01:00Z","body":"World"}
+"#;
+
+    assert!(room.import_history(dump.as_bytes(), |_| {}).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_moderate_message_fails_if_room_does_not_support_it() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+    deps.participation_service.expect_moderate_message().never();
+
+    let room = RoomFactory::from(deps)
+        .build(Room::public_channel(muc_id!("room@conference.prose.org")))
+        .to_generic_room();
+
+    let result = room.moderate_message("msg-1".into(), Some("Spam")).await;
+    assert!(matches!(
+        result,
+        Err(RoomError::MessageModerationNotSupported)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_moderates_message() -> Result<()> {
+    let mut deps = MockRoomFactoryDependencies::default();
+
+    let internals =
+        Room::public_channel(muc_id!("room@conference.prose.org")).with_features(RoomFeatures {
+            message_moderation: true,
+            ..Default::default()
+        });
+    let moderator = ParticipantId::Occupant(internals.occupant_id().unwrap());
+
+    deps.message_repo
+        .expect_resolve_message_id()
+        .once()
+        .return_once(|_, _, _| {
+            Box::pin(async {
+                Ok(Some(MessageIdTriple {
+                    id: "msg-1".into(),
+                    remote_id: None,
+                    server_id: Some("stanza-id-1".into()),
+                }))
+            })
+        });
+
+    deps.participation_service
+        .expect_moderate_message()
+        .once()
+        .with(
+            predicate::eq(muc_id!("room@conference.prose.org")),
+            predicate::eq(MessageServerId::from("stanza-id-1")),
+            predicate::eq(Some("Spam")),
+        )
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
+
+    {
+        let moderator = moderator.clone();
+        deps.message_repo
+            .expect_append()
+            .once()
+            .return_once(move |_, _, messages| {
+                assert_eq!(1, messages.len());
+                assert_eq!(moderator, messages[0].from);
+                assert_eq!(
+                    MessageLikePayload::Retraction {
+                        target_id: MessageTargetId::ServerId("stanza-id-1".into()),
+                        reason: Some("Spam".to_string()),
+                        moderated_by: Some(moderator.clone()),
+                    },
+                    messages[0].payload
+                );
+                Box::pin(async { Ok(()) })
+            });
+    }
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::MessagesUpdated {
+                message_ids: vec!["msg-1".into()],
+            }),
+        )
+        .return_const(());
+
+    let room = RoomFactory::from(deps).build(internals).to_generic_room();
+    room.moderate_message("msg-1".into(), Some("Spam")).await?;
+
+    Ok(())
+}
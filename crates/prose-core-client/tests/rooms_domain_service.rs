@@ -15,7 +15,8 @@ use pretty_assertions::assert_eq;
 use prose_core_client::domain::connection::models::{ConnectionProperties, ServerFeatures};
 use prose_core_client::domain::rooms::models::{
     ParticipantName, RegisteredMember, Room, RoomAffiliation, RoomConfig, RoomError, RoomInfo,
-    RoomSessionInfo, RoomSessionMember, RoomSessionParticipant, RoomSidebarState, RoomSpec,
+    RoomRole, RoomSessionInfo, RoomSessionMember, RoomSessionParticipant, RoomSidebarState,
+    RoomSpec,
 };
 use prose_core_client::domain::rooms::services::impls::RoomsDomainService;
 use prose_core_client::domain::rooms::services::{
@@ -74,7 +75,10 @@ async fn test_joins_room() -> Result<()> {
             Box::pin(async {
                 Ok(AccountSettings {
                     availability: Availability::DoNotDisturb,
+                    status_message: None,
                     resource: None,
+                    send_read_receipts: true,
+                    user_status: None,
                 })
             })
         });
@@ -117,6 +121,8 @@ async fn test_joins_room() -> Result<()> {
                         room_type: RoomType::PrivateChannel,
                         mam_version: None,
                         supports_self_ping_optimization: false,
+                        supports_message_moderation: false,
+                        supports_chat_markers: false,
                     },
                     topic: Some("The Room Topic".to_string()),
                     user_nickname: "User".to_string(),
@@ -141,6 +147,7 @@ async fn test_joins_room() -> Result<()> {
                             anon_id: None,
                             real_id: Some(user_id!("user1@prose.org")),
                             affiliation: RoomAffiliation::Owner,
+                            role: RoomRole::Moderator,
                             presence: Presence {
                                 availability: Availability::Available,
                                 ..Default::default()
@@ -152,6 +159,7 @@ async fn test_joins_room() -> Result<()> {
                             anon_id: None,
                             real_id: Some(user_id!("user2@prose.org")),
                             affiliation: RoomAffiliation::Member,
+                            role: RoomRole::Participant,
                             presence: Presence {
                                 availability: Availability::Available,
                                 ..Default::default()
@@ -358,7 +366,10 @@ async fn test_creates_group() -> Result<()> {
             Box::pin(async {
                 Ok(AccountSettings {
                     availability: Availability::Away,
+                    status_message: None,
                     resource: None,
+                    send_read_receipts: true,
+                    user_status: None,
                 })
             })
         });
@@ -877,7 +888,10 @@ async fn test_converts_group_to_private_channel() -> Result<()> {
             Box::pin(async {
                 Ok(AccountSettings {
                     availability: Availability::DoNotDisturb,
+                    status_message: None,
                     resource: None,
+                    send_read_receipts: true,
+                    user_status: None,
                 })
             })
         });
@@ -981,14 +995,16 @@ async fn test_converts_group_to_private_channel() -> Result<()> {
         .return_once(|_, _| Box::pin(async { Ok(()) }));
 
     deps.room_participation_service
-        .expect_grant_membership()
+        .expect_set_affiliation()
         .times(2)
         .in_sequence(&mut seq)
         .with(
             predicate::eq(channel_id.clone()),
             predicate::in_iter(vec![user_id!("a@prose.org"), user_id!("b@prose.org")]),
+            predicate::eq(RoomAffiliation::Member),
+            predicate::always(),
         )
-        .returning(|_, _| Box::pin(async { Ok(()) }));
+        .returning(|_, _, _, _| Box::pin(async { Ok(()) }));
 
     deps.room_management_service
         .expect_destroy_room()
@@ -997,8 +1013,9 @@ async fn test_converts_group_to_private_channel() -> Result<()> {
         .with(
             predicate::eq(muc_id!("group@conf.prose.org")),
             predicate::eq(Some(channel_id.clone())),
+            predicate::always(),
         )
-        .return_once(|_, _| Box::pin(async { Ok(()) }));
+        .return_once(|_, _, _| Box::pin(async { Ok(()) }));
 
     let service = RoomsDomainService::from(deps.into_deps());
 
@@ -1192,6 +1209,8 @@ async fn test_updates_pending_dm_message_room() -> Result<()> {
             jid: user_id!("user2@prose.org").into(),
             r#type: BookmarkType::DirectMessage,
             sidebar_state: RoomSidebarState::InSidebar,
+            order_index: None,
+            invitation: None,
         },
         "User1",
     );
@@ -1315,6 +1334,8 @@ async fn test_updates_pending_public_channel() -> Result<()> {
             jid: muc_id!("room@conf.prose.org").into(),
             r#type: BookmarkType::PublicChannel,
             sidebar_state: RoomSidebarState::InSidebar,
+            order_index: None,
+            invitation: None,
         },
         "User1",
     )));
@@ -1379,6 +1400,8 @@ async fn test_updates_pending_public_channel() -> Result<()> {
                         room_type: RoomType::PublicChannel,
                         mam_version: None,
                         supports_self_ping_optimization: false,
+                        supports_message_moderation: false,
+                        supports_chat_markers: false,
                     },
                     topic: None,
                     user_nickname: "User".to_string(),
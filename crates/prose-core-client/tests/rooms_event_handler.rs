@@ -16,18 +16,17 @@ use prose_core_client::app::event_handlers::{
 };
 use prose_core_client::domain::connection::models::ConnectionProperties;
 use prose_core_client::domain::rooms::models::{
-    ComposeState, ParticipantName, Room, RoomAffiliation, RoomSidebarState,
-};
-use prose_core_client::domain::rooms::services::{
-    CreateOrEnterRoomRequest, JoinRoomBehavior, RoomFactory,
+    ComposeState, ParticipantName, Room, RoomAffiliation, RoomRemovalReason, RoomRole,
+    RoomSidebarState,
 };
+use prose_core_client::domain::rooms::services::RoomFactory;
 use prose_core_client::domain::settings::models::SyncedRoomSettings;
 use prose_core_client::domain::shared::models::{
     CachePolicy, MucId, OccupantId, UserId, UserOrResourceId, UserResourceId,
 };
 use prose_core_client::domain::user_info::models::{Presence, UserName};
 use prose_core_client::dtos::{
-    Availability, Participant, ParticipantBasicInfo, ParticipantInfo, UserInfo,
+    Availability, MessageId, Participant, ParticipantBasicInfo, ParticipantInfo, UserInfo,
 };
 use prose_core_client::test::{
     ConstantTimeProvider, MockAppDependencies, MockRoomFactoryDependencies,
@@ -79,7 +78,9 @@ async fn test_adds_participant() -> Result<()> {
         .times(2)
         .with(
             predicate::eq(room.clone()),
-            predicate::eq(ClientRoomEventType::ParticipantsChanged),
+            predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                ids: vec![occupant_id!("room@conference.prose.org/nick").into()],
+            }),
         )
         .returning(|_, _| ());
 
@@ -104,6 +105,7 @@ async fn test_adds_participant() -> Result<()> {
             is_self: false,
             r#type: OccupantEventType::AffiliationChanged {
                 affiliation: RoomAffiliation::Member,
+                role: RoomRole::Participant,
             },
         }))
         .await?;
@@ -124,6 +126,7 @@ async fn test_adds_participant() -> Result<()> {
             real_id: Some(user_id!("real-jid@prose.org")),
             name: ParticipantName::from_vcard("George Washington"),
             affiliation: RoomAffiliation::Member,
+            role: RoomRole::Participant,
             availability: Availability::Available,
             ..Default::default()
         }
@@ -132,6 +135,88 @@ async fn test_adds_participant() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_reloads_messages_of_occupant_once_real_id_is_known() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    let room =
+        Room::public_channel(muc_id!("room@conference.prose.org")).by_adding_participants([(
+            occupant_id!("room@conference.prose.org/nick"),
+            Participant {
+                affiliation: RoomAffiliation::Member,
+                role: RoomRole::Participant,
+                ..Default::default()
+            },
+        )]);
+    room.track_message_with_unresolved_sender(
+        &occupant_id!("room@conference.prose.org/nick"),
+        &MessageId::from("message-1"),
+    );
+    room.track_message_with_unresolved_sender(
+        &occupant_id!("room@conference.prose.org/nick"),
+        &MessageId::from("message-2"),
+    );
+
+    {
+        let room = room.clone();
+        deps.connected_rooms_repo
+            .expect_get()
+            .once()
+            .returning(move |_, _| Some(room.clone()));
+    }
+
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(None) }));
+
+    let mut seq = Sequence::new();
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(room.clone()),
+            predicate::eq(ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![MessageId::from("message-1"), MessageId::from("message-2")],
+            }),
+        )
+        .return_const(());
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(room.clone()),
+            predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                ids: vec![occupant_id!("room@conference.prose.org/nick").into()],
+            }),
+        )
+        .return_const(());
+
+    let event_handler = RoomsEventHandler::from(&deps.into_deps());
+
+    event_handler
+        .handle_event(ServerEvent::Occupant(OccupantEvent {
+            occupant_id: occupant_id!("room@conference.prose.org/nick"),
+            anon_occupant_id: None,
+            real_id: Some(user_id!("real-jid@prose.org")),
+            is_self: false,
+            r#type: OccupantEventType::AffiliationChanged {
+                affiliation: RoomAffiliation::Member,
+                role: RoomRole::Participant,
+            },
+        }))
+        .await?;
+
+    assert!(room
+        .take_messages_with_unresolved_sender(&occupant_id!("room@conference.prose.org/nick"))
+        .is_empty());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_adds_invited_participant() -> Result<()> {
     let mut deps = MockAppDependencies::default();
@@ -174,7 +259,9 @@ async fn test_adds_invited_participant() -> Result<()> {
         .once()
         .with(
             predicate::eq(room.clone()),
-            predicate::eq(ClientRoomEventType::ParticipantsChanged),
+            predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                ids: vec![user_id!("user@prose.org").into()],
+            }),
         )
         .return_once(|_, _| ());
 
@@ -241,7 +328,9 @@ async fn test_handles_disconnected_participant() -> Result<()> {
         .times(2)
         .with(
             predicate::eq(room.clone()),
-            predicate::eq(ClientRoomEventType::ParticipantsChanged),
+            predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                ids: vec![occupant_id!("room@conference.prose.org/a").into()],
+            }),
         )
         .returning(|_, _| ());
 
@@ -266,6 +355,7 @@ async fn test_handles_disconnected_participant() -> Result<()> {
             is_self: false,
             r#type: OccupantEventType::AffiliationChanged {
                 affiliation: RoomAffiliation::Member,
+                role: RoomRole::Participant,
             },
         }))
         .await?;
@@ -273,6 +363,7 @@ async fn test_handles_disconnected_participant() -> Result<()> {
     assert_eq!(
         vec![Participant {
             affiliation: RoomAffiliation::Member,
+            role: RoomRole::Participant,
             availability: Availability::Unavailable,
             ..Default::default()
         }],
@@ -308,7 +399,9 @@ async fn test_handles_kicked_user() -> Result<()> {
         .once()
         .with(
             predicate::eq(room.clone()),
-            predicate::eq(ClientRoomEventType::ParticipantsChanged),
+            predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                ids: vec![occupant_id!("room@conference.prose.org/nickname").into()],
+            }),
         )
         .return_once(|_, _| ());
 
@@ -322,7 +415,9 @@ async fn test_handles_kicked_user() -> Result<()> {
             anon_occupant_id: None,
             real_id: None,
             is_self: false,
-            r#type: OccupantEventType::PermanentlyRemoved,
+            r#type: OccupantEventType::PermanentlyRemoved {
+                reason: RoomRemovalReason::Kicked,
+            },
         }))
         .await?;
 
@@ -354,7 +449,7 @@ async fn test_handles_kicked_self() -> Result<()> {
         .once()
         .with(
             predicate::eq(muc_id!("room@conference.prose.org")),
-            predicate::eq(true),
+            predicate::eq(RoomRemovalReason::Kicked),
         )
         .return_once(|_, _| Box::pin(async { Ok(()) }));
 
@@ -366,7 +461,9 @@ async fn test_handles_kicked_self() -> Result<()> {
             anon_occupant_id: None,
             real_id: None,
             is_self: true,
-            r#type: OccupantEventType::PermanentlyRemoved,
+            r#type: OccupantEventType::PermanentlyRemoved {
+                reason: RoomRemovalReason::Kicked,
+            },
         }))
         .await?;
 
@@ -481,6 +578,11 @@ async fn test_handles_compose_state_for_muc_room() -> Result<()> {
 async fn test_handles_compose_state_for_direct_message_room() -> Result<()> {
     let mut deps = MockAppDependencies::default();
 
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(false) }));
+
     let room = Room::for_direct_message(
         &user_id!("contact@prose.org"),
         "Janice Doe",
@@ -564,15 +666,15 @@ async fn test_handles_invite() -> Result<()> {
     let mut deps = MockAppDependencies::default();
 
     deps.sidebar_domain_service
-        .expect_insert_item_by_creating_or_joining_room()
+        .expect_handle_received_invitation()
         .once()
-        .with(predicate::eq(CreateOrEnterRoomRequest::JoinRoom {
-            room_id: muc_id!("group@conference.prose.org").into(),
-            password: None,
-            behavior: JoinRoomBehavior::system_initiated(),
-            decryption_context: None,
-        }))
-        .return_once(|_| Box::pin(async move { Ok(muc_id!("group@conference.prose.org").into()) }));
+        .with(
+            predicate::eq(muc_id!("group@conference.prose.org")),
+            predicate::eq(user_resource_id!("user@prose.org/res")),
+            predicate::eq(None),
+            predicate::eq(Some("Join us".to_string())),
+        )
+        .return_once(|_, _, _, _| Box::pin(async { Ok(()) }));
 
     let event_handler = RoomsEventHandler::from(&deps.into_deps());
 
@@ -582,6 +684,7 @@ async fn test_handles_invite() -> Result<()> {
             r#type: RoomEventType::ReceivedInvitation {
                 sender: user_resource_id!("user@prose.org/res"),
                 password: None,
+                reason: Some("Join us".to_string()),
             },
         }))
         .await?;
@@ -593,6 +696,11 @@ async fn test_handles_invite() -> Result<()> {
 async fn test_handles_user_presence() -> Result<()> {
     let mut deps = MockAppDependencies::default();
 
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(false) }));
+
     let room = Room::for_direct_message(
         &user_id!("sender@prose.org"),
         "Janice Doe",
@@ -685,7 +793,9 @@ async fn test_handles_occupant_presence() -> Result<()> {
             .once()
             .with(
                 predicate::eq(room.clone()),
-                predicate::eq(ClientRoomEventType::ParticipantsChanged),
+                predicate::eq(ClientRoomEventType::ParticipantsChanged {
+                    ids: vec![occupant_id!("room@muc.prose.org/nick").into()],
+                }),
             )
             .return_once(|_, _| ());
     }
@@ -728,6 +838,11 @@ async fn test_handles_occupant_presence() -> Result<()> {
 async fn test_handles_contact_presence_with_no_room() -> Result<()> {
     let mut deps = MockAppDependencies::default();
 
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(false) }));
+
     deps.connected_rooms_repo
         .expect_get()
         .times(2)
@@ -770,6 +885,140 @@ async fn test_handles_contact_presence_with_no_room() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_remembers_when_contact_went_offline() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    deps.time_provider = Arc::new(ConstantTimeProvider::ymd(2024, 05, 01));
+
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(false) }));
+    deps.connected_rooms_repo
+        .expect_get()
+        .times(2)
+        .returning(move |_, _| None);
+    deps.user_info_domain_service
+        .expect_handle_user_presence_changed()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .once()
+        .with(
+            predicate::eq(user_id!("sender@prose.org")),
+            predicate::eq(CachePolicy::ReturnCacheDataDontLoad),
+        )
+        .return_once(|_, _| {
+            Box::pin(async {
+                Ok(Some(UserInfo {
+                    availability: Availability::Unavailable,
+                    ..Default::default()
+                }))
+            })
+        });
+    deps.contact_list_domain_service
+        .expect_set_last_seen()
+        .once()
+        .with(
+            predicate::eq(user_id!("sender@prose.org")),
+            predicate::eq(Utc.with_ymd_and_hms(2024, 05, 01, 0, 0, 0).unwrap()),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    let event_handler = RoomsEventHandler::from(&deps.into_deps());
+
+    event_handler
+        .handle_event(ServerEvent::UserStatus(UserStatusEvent {
+            user_id: user_resource_id!("sender@prose.org/resource").into(),
+            r#type: UserStatusEventType::PresenceChanged {
+                presence: Presence {
+                    availability: Availability::Unavailable,
+                    ..Default::default()
+                },
+            },
+        }))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_does_not_remember_contact_as_offline_while_other_resource_is_online() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .return_once(|_| Box::pin(async { Ok(false) }));
+    deps.connected_rooms_repo
+        .expect_get()
+        .times(2)
+        .returning(move |_, _| None);
+    deps.user_info_domain_service
+        .expect_handle_user_presence_changed()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+    deps.user_info_domain_service
+        .expect_get_user_info()
+        .once()
+        .return_once(|_, _| {
+            Box::pin(async {
+                Ok(Some(UserInfo {
+                    availability: Availability::Available,
+                    ..Default::default()
+                }))
+            })
+        });
+    deps.contact_list_domain_service
+        .expect_set_last_seen()
+        .never();
+
+    let event_handler = RoomsEventHandler::from(&deps.into_deps());
+
+    event_handler
+        .handle_event(ServerEvent::UserStatus(UserStatusEvent {
+            user_id: user_resource_id!("sender@prose.org/phone").into(),
+            r#type: UserStatusEventType::PresenceChanged {
+                presence: Presence {
+                    availability: Availability::Unavailable,
+                    ..Default::default()
+                },
+            },
+        }))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ignores_presence_of_blocked_user() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+
+    deps.block_list_domain_service
+        .expect_is_user_blocked()
+        .once()
+        .with(predicate::eq(user_id!("sender@prose.org")))
+        .return_once(|_| Box::pin(async { Ok(true) }));
+
+    let event_handler = RoomsEventHandler::from(&deps.into_deps());
+
+    assert!(event_handler
+        .handle_event(ServerEvent::UserStatus(UserStatusEvent {
+            user_id: user_resource_id!("sender@prose.org/resource").into(),
+            r#type: UserStatusEventType::PresenceChanged {
+                presence: Presence {
+                    availability: Availability::Available,
+                    ..Default::default()
+                },
+            },
+        }))
+        .await?
+        .is_none());
+
+    Ok(())
+}
+
 #[tokio::test]
 /// Test that UserStateEventHandler does not send an event when a self-presence is received and
 /// that the event is consumed, i.e. cannot be forwarded to other handlers.
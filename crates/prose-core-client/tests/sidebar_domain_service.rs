@@ -3,22 +3,30 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::sync::Arc;
+
 use anyhow::{format_err, Result};
 use mockall::{predicate, Sequence};
+use parking_lot::Mutex;
 use xmpp_parsers::stanza_error::{DefinedCondition, ErrorType, StanzaError};
 
 use prose_core_client::domain::connection::models::ConnectionProperties;
-use prose_core_client::domain::rooms::models::{Room, RoomError, RoomSidebarState, RoomSpec};
+use prose_core_client::domain::rooms::models::{
+    Room, RoomError, RoomFeatures, RoomRemovalReason, RoomSidebarState, RoomSpec,
+};
+use prose_core_client::domain::rooms::repos::RoomAlreadyExistsError;
 use prose_core_client::domain::rooms::services::{CreateOrEnterRoomRequest, JoinRoomBehavior};
 use prose_core_client::domain::shared::models::{MucId, OccupantId, UserId, UserResourceId};
-use prose_core_client::domain::sidebar::models::{Bookmark, BookmarkType};
+use prose_core_client::domain::sidebar::models::{Bookmark, BookmarkInvitation, BookmarkType};
 use prose_core_client::domain::sidebar::services::impls::SidebarDomainService;
 use prose_core_client::domain::sidebar::services::SidebarDomainService as SidebarDomainServiceTrait;
 use prose_core_client::dtos::{Availability, DecryptionContext, RoomId, RoomState};
 use prose_core_client::test::{
     DisconnectedState, MessageBuilder, MockSidebarDomainServiceDependencies,
 };
-use prose_core_client::{muc_id, occupant_id, user_id, user_resource_id, ClientEvent};
+use prose_core_client::{
+    muc_id, occupant_id, user_id, user_resource_id, ClientEvent, ClientRoomEventType,
+};
 use prose_xmpp::{bare, RequestError};
 
 #[tokio::test]
@@ -148,6 +156,184 @@ async fn test_extend_items_updates_room() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_leaves_room_removed_from_sidebar_on_other_device() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    deps.ctx.set_connection_properties(ConnectionProperties {
+        connection_timestamp: Default::default(),
+        connected_jid: user_resource_id!("user1@prose.org/res"),
+        server_features: Default::default(),
+        rooms_caught_up: false,
+        decryption_context: None,
+    });
+
+    let room = Room::public_channel(muc_id!("channel@conference.prose.org"))
+        .with_name("Channel Name")
+        .with_sidebar_state(RoomSidebarState::InSidebar);
+
+    deps.connected_rooms_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_| vec![room]);
+
+    deps.connected_rooms_repo
+        .expect_delete()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(bare!("channel@conference.prose.org")),
+        )
+        .return_once(|_, _| None);
+
+    deps.room_management_service
+        .expect_exit_room()
+        .once()
+        .with(predicate::eq(occupant_id!(
+            "channel@conference.prose.org/jane.doe"
+        )))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_once(|_| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .handle_changed_items(
+            vec![
+                Bookmark::public_channel(muc_id!("channel@conference.prose.org"), "Channel Name")
+                    .set_sidebar_state(RoomSidebarState::NotInSidebar),
+            ],
+            Default::default(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_keeps_room_name_when_bookmark_was_renamed_on_other_device() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    deps.ctx.set_connection_properties(ConnectionProperties {
+        connection_timestamp: Default::default(),
+        connected_jid: user_resource_id!("user1@prose.org/res"),
+        server_features: Default::default(),
+        rooms_caught_up: false,
+        decryption_context: None,
+    });
+
+    let room = Room::public_channel(muc_id!("channel@conference.prose.org"))
+        .with_name("Channel Name")
+        .with_sidebar_state(RoomSidebarState::InSidebar);
+
+    {
+        let room = room.clone();
+        deps.connected_rooms_repo
+            .expect_get_all()
+            .once()
+            .return_once(|_| vec![room]);
+    }
+
+    // The name is owned by the room, so the room is neither rejoined nor is the sidebar
+    // changed…
+    deps.rooms_domain_service
+        .expect_create_or_join_room()
+        .never();
+    deps.client_event_dispatcher.expect_dispatch_event().never();
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .handle_changed_items(
+            vec![Bookmark::public_channel(
+                muc_id!("channel@conference.prose.org"),
+                "Renamed Channel",
+            )
+            .set_sidebar_state(RoomSidebarState::InSidebar)],
+            Default::default(),
+        )
+        .await?;
+
+    assert_eq!(Some("Channel Name".to_string()), room.name());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_joins_room_when_invitation_was_accepted_on_other_device() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    deps.ctx.set_connection_properties(ConnectionProperties {
+        connection_timestamp: Default::default(),
+        connected_jid: user_resource_id!("user1@prose.org/res"),
+        server_features: Default::default(),
+        rooms_caught_up: false,
+        decryption_context: None,
+    });
+
+    deps.connected_rooms_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_| {
+            vec![Room::invited(
+                &muc_id!("channel@conference.prose.org"),
+                "User1",
+                user_id!("user2@prose.org"),
+                None,
+                None,
+            )]
+        });
+
+    // The room is in the repository already…
+    deps.connected_rooms_repo
+        .expect_set()
+        .once()
+        .return_once(|_, _| Err(RoomAlreadyExistsError));
+
+    deps.rooms_domain_service
+        .expect_create_or_join_room()
+        .once()
+        .with(
+            predicate::eq(CreateOrEnterRoomRequest::JoinRoom {
+                room_id: muc_id!("channel@conference.prose.org"),
+                password: None,
+                behavior: JoinRoomBehavior::system_initiated(),
+                decryption_context: Some(DecryptionContext::default()),
+            }),
+            predicate::eq(RoomSidebarState::InSidebar),
+        )
+        .return_once(|_, _| {
+            Box::pin(async {
+                Ok(
+                    Room::public_channel(muc_id!("channel@conference.prose.org"))
+                        .with_name("Channel Name"),
+                )
+            })
+        });
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .times(2)
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .returning(|_| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .handle_changed_items(
+            vec![
+                Bookmark::public_channel(muc_id!("channel@conference.prose.org"), "Channel Name")
+                    .set_sidebar_state(RoomSidebarState::InSidebar),
+            ],
+            Default::default(),
+        )
+        .await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_extend_items_deletes_hidden_gone_rooms() -> Result<()> {
     let mut deps = MockSidebarDomainServiceDependencies::default();
@@ -480,6 +666,8 @@ async fn test_removes_group_from_sidebar() -> Result<()> {
             jid: muc_id!("group@conference.prose.org").into(),
             r#type: BookmarkType::Group,
             sidebar_state: RoomSidebarState::NotInSidebar,
+            order_index: None,
+            invitation: None,
         }))
         .return_once(|_| Box::pin(async { Ok(()) }));
 
@@ -530,6 +718,8 @@ async fn test_removes_private_channel_from_sidebar() -> Result<()> {
             jid: muc_id!("channel@conference.prose.org").into(),
             r#type: BookmarkType::PrivateChannel,
             sidebar_state: RoomSidebarState::NotInSidebar,
+            order_index: None,
+            invitation: None,
         }))
         .return_once(|_| Box::pin(async { Ok(()) }));
 
@@ -600,6 +790,8 @@ async fn test_insert_item_for_received_group_message_if_needed() -> Result<()> {
             jid: muc_id!("group@conference.prose.org").into(),
             r#type: BookmarkType::Group,
             sidebar_state: RoomSidebarState::InSidebar,
+            order_index: None,
+            invitation: None,
         }))
         .return_once(|_| Box::pin(async { Ok(()) }));
 
@@ -667,6 +859,8 @@ async fn test_insert_item_for_received_direct_message_if_needed() -> Result<()>
             jid: user_id!("contact@prose.org").into(),
             r#type: BookmarkType::DirectMessage,
             sidebar_state: RoomSidebarState::InSidebar,
+            order_index: None,
+            invitation: None,
         }))
         .return_once(|_| Box::pin(async { Ok(()) }));
 
@@ -726,6 +920,8 @@ async fn test_renames_channel_in_sidebar() -> Result<()> {
             jid: muc_id!("room@conference.prose.org").into(),
             r#type: BookmarkType::PublicChannel,
             sidebar_state: RoomSidebarState::Favorite,
+            order_index: None,
+            invitation: None,
         }))
         .return_once(|_| Box::pin(async move { Ok(()) }));
 
@@ -773,6 +969,8 @@ async fn test_toggle_favorite() -> Result<()> {
             jid: muc_id!("channel@conference.prose.org").into(),
             r#type: BookmarkType::PublicChannel,
             sidebar_state: RoomSidebarState::Favorite,
+            order_index: None,
+            invitation: None,
         }))
         .return_once(|_| Box::pin(async { Ok(()) }));
 
@@ -792,6 +990,76 @@ async fn test_toggle_favorite() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_set_manual_order() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    let channel = Room::public_channel(muc_id!("channel@conference.prose.org"))
+        .with_name("Channel Name")
+        .with_sidebar_state(RoomSidebarState::InSidebar);
+    let group = Room::group(muc_id!("group@conference.prose.org"))
+        .with_name("Group Name")
+        .with_sidebar_state(RoomSidebarState::InSidebar);
+    group.set_order_index(Some(0));
+    let hidden_channel = Room::public_channel(muc_id!("hidden@conference.prose.org"))
+        .with_name("Hidden")
+        .with_sidebar_state(RoomSidebarState::NotInSidebar);
+
+    {
+        let rooms = vec![channel.clone(), group.clone(), hidden_channel.clone()];
+        deps.connected_rooms_repo
+            .expect_get_all()
+            .once()
+            .return_once(|_| rooms);
+    }
+
+    deps.bookmarks_service
+        .expect_save_bookmark()
+        .once()
+        .with(predicate::eq(Bookmark {
+            name: "Channel Name".to_string(),
+            jid: muc_id!("channel@conference.prose.org").into(),
+            r#type: BookmarkType::PublicChannel,
+            sidebar_state: RoomSidebarState::InSidebar,
+            order_index: Some(0),
+            invitation: None,
+        }))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    deps.bookmarks_service
+        .expect_save_bookmark()
+        .once()
+        .with(predicate::eq(Bookmark {
+            name: "Group Name".to_string(),
+            jid: muc_id!("group@conference.prose.org").into(),
+            r#type: BookmarkType::Group,
+            sidebar_state: RoomSidebarState::InSidebar,
+            order_index: None,
+            invitation: None,
+        }))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_once(|_| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .set_manual_order(&[
+            muc_id!("channel@conference.prose.org").into(),
+            muc_id!("hidden@conference.prose.org").into(),
+        ])
+        .await?;
+
+    assert_eq!(channel.order_index(), Some(0));
+    assert_eq!(group.order_index(), None);
+    assert_eq!(hidden_channel.order_index(), None);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_convert_group_to_private_channel() -> Result<()> {
     let mut deps = MockSidebarDomainServiceDependencies::default();
@@ -884,10 +1152,14 @@ async fn test_destroys_room_and_deletes_bookmark() -> Result<()> {
         .once()
         .with(
             predicate::eq(muc_id!("room@conf.prose.org")),
-            predicate::eq(None),
+            predicate::eq(Some(muc_id!("new-room@conf.prose.org"))),
+            predicate::always(),
         )
         .in_sequence(&mut seq)
-        .return_once(|_, _| Box::pin(async { Ok(()) }));
+        .return_once(|_, _, reason| {
+            assert_eq!(Some("We've moved"), reason);
+            Box::pin(async { Ok(()) })
+        });
 
     deps.connected_rooms_repo
         .expect_delete()
@@ -899,6 +1171,26 @@ async fn test_destroys_room_and_deletes_bookmark() -> Result<()> {
         .in_sequence(&mut seq)
         .return_once(|_, _| Some(Room::private_channel(muc_id!("room@conf.prose.org"))));
 
+    deps.messages_repo
+        .expect_clear_room()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::from(muc_id!("room@conf.prose.org"))),
+        )
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::eq(Room::private_channel(muc_id!("room@conf.prose.org"))),
+            predicate::eq(ClientRoomEventType::Destroyed),
+        )
+        .in_sequence(&mut seq)
+        .return_once(|_, _| ());
+
     deps.bookmarks_service
         .expect_delete_bookmark()
         .once()
@@ -915,7 +1207,11 @@ async fn test_destroys_room_and_deletes_bookmark() -> Result<()> {
 
     let service = SidebarDomainService::from(deps.into_deps());
     service
-        .destroy_room(&muc_id!("room@conf.prose.org"))
+        .destroy_room(
+            &muc_id!("room@conf.prose.org"),
+            Some(muc_id!("new-room@conf.prose.org")),
+            Some("We've moved"),
+        )
         .await?;
 
     Ok(())
@@ -942,14 +1238,17 @@ async fn test_does_not_delete_bookmark_when_destroy_room_fails() -> Result<()> {
         .with(
             predicate::eq(muc_id!("room@conf.prose.org")),
             predicate::eq(None),
+            predicate::always(),
         )
         .in_sequence(&mut seq)
-        .return_once(|_, _| {
+        .return_once(|_, _, _| {
             Box::pin(async { Err(RoomError::Anyhow(format_err!("Something went wrong"))) })
         });
 
     let service = SidebarDomainService::from(deps.into_deps());
-    let result = service.destroy_room(&muc_id!("room@conf.prose.org")).await;
+    let result = service
+        .destroy_room(&muc_id!("room@conf.prose.org"), None, None)
+        .await;
 
     assert!(result.is_err());
 
@@ -977,9 +1276,10 @@ async fn test_deletes_bookmark_when_trying_to_destroy_gone_room() -> Result<()>
         .with(
             predicate::eq(muc_id!("room@conf.prose.org")),
             predicate::eq(None),
+            predicate::always(),
         )
         .in_sequence(&mut seq)
-        .return_once(|_, _| {
+        .return_once(|_, _, _| {
             Box::pin(async {
                 Err(RoomError::RequestError(RequestError::XMPP {
                     err: StanzaError::new(
@@ -1002,6 +1302,22 @@ async fn test_deletes_bookmark_when_trying_to_destroy_gone_room() -> Result<()>
         .in_sequence(&mut seq)
         .return_once(|_, _| Some(Room::private_channel(muc_id!("room@conf.prose.org"))));
 
+    deps.messages_repo
+        .expect_clear_room()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::Destroyed),
+        )
+        .in_sequence(&mut seq)
+        .return_once(|_, _| ());
+
     deps.bookmarks_service
         .expect_delete_bookmark()
         .once()
@@ -1018,7 +1334,7 @@ async fn test_deletes_bookmark_when_trying_to_destroy_gone_room() -> Result<()>
 
     let service = SidebarDomainService::from(deps.into_deps());
     service
-        .destroy_room(&muc_id!("room@conf.prose.org"))
+        .destroy_room(&muc_id!("room@conf.prose.org"), None, None)
         .await?;
 
     Ok(())
@@ -1055,6 +1371,26 @@ async fn test_handles_destroyed_room_with_alternate_room() -> Result<()> {
             )
         });
 
+    deps.messages_repo
+        .expect_clear_room()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::from(muc_id!("group@muc.prose.org"))),
+        )
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::Destroyed),
+        )
+        .in_sequence(&mut seq)
+        .return_once(|_, _| ());
+
     deps.connected_rooms_repo
         .expect_get()
         .once()
@@ -1076,6 +1412,8 @@ async fn test_handles_destroyed_room_with_alternate_room() -> Result<()> {
                     jid: muc_id!("channel@muc.prose.org").into(),
                     r#type: BookmarkType::Group,
                     sidebar_state: RoomSidebarState::Favorite,
+                    order_index: None,
+                    invitation: None,
                 },
                 "User1",
             )),
@@ -1126,6 +1464,8 @@ async fn test_handles_destroyed_room_with_alternate_room() -> Result<()> {
             jid: muc_id!("channel@muc.prose.org").into(),
             r#type: BookmarkType::PrivateChannel,
             sidebar_state: RoomSidebarState::Favorite,
+            order_index: None,
+            invitation: None,
         }))
         .return_once(|_| Box::pin(async { Ok(()) }));
 
@@ -1152,13 +1492,75 @@ async fn test_handles_destroyed_room_without_alternate_room() -> Result<()> {
     let mut deps = MockSidebarDomainServiceDependencies::default();
     let mut seq = Sequence::new();
 
-    let room =
-        Room::private_channel(muc_id!("room@conf.prose.org")).with_state(RoomState::Connected);
-    assert_eq!(
-        room.is_disconnected(),
-        DisconnectedState {
-            is_disconnected: false,
-            can_retry: false
+    deps.connected_rooms_repo
+        .expect_delete()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::always(),
+            predicate::eq(bare!("room@conf.prose.org")),
+        )
+        .return_once(|_, _| {
+            Some(
+                Room::private_channel(muc_id!("room@conf.prose.org"))
+                    .with_state(RoomState::Connected),
+            )
+        });
+
+    deps.messages_repo
+        .expect_clear_room()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(RoomId::from(muc_id!("room@conf.prose.org"))),
+        )
+        .in_sequence(&mut seq)
+        .return_once(|_, _| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::Destroyed),
+        )
+        .in_sequence(&mut seq)
+        .return_once(|_, _| ());
+
+    deps.bookmarks_service
+        .expect_delete_bookmark()
+        .once()
+        .with(predicate::eq(bare!("room@conf.prose.org")))
+        .in_sequence(&mut seq)
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_once(|_| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .handle_destroyed_room(&muc_id!("room@conf.prose.org"), None)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_handles_temporary_removal_from_room() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+    let mut seq = Sequence::new();
+
+    let room =
+        Room::private_channel(muc_id!("room@conf.prose.org")).with_state(RoomState::Connected);
+    assert_eq!(
+        room.is_disconnected(),
+        DisconnectedState {
+            is_disconnected: false,
+            can_retry: false
         }
     );
 
@@ -1175,6 +1577,18 @@ async fn test_handles_destroyed_room_without_alternate_room() -> Result<()> {
             .return_once(|_, _| Some(room));
     }
 
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(room.clone()),
+            predicate::eq(ClientRoomEventType::RemovedFromRoom {
+                reason: RoomRemovalReason::ServiceShutdown,
+            }),
+        )
+        .return_once(|_, _| ());
+
     deps.client_event_dispatcher
         .expect_dispatch_event()
         .once()
@@ -1184,14 +1598,17 @@ async fn test_handles_destroyed_room_without_alternate_room() -> Result<()> {
 
     let service = SidebarDomainService::from(deps.into_deps());
     service
-        .handle_destroyed_room(&muc_id!("room@conf.prose.org"), None)
+        .handle_removal_from_room(
+            &muc_id!("room@conf.prose.org"),
+            RoomRemovalReason::ServiceShutdown,
+        )
         .await?;
 
     assert_eq!(
         room.is_disconnected(),
         DisconnectedState {
             is_disconnected: true,
-            can_retry: false
+            can_retry: true
         }
     );
 
@@ -1199,7 +1616,7 @@ async fn test_handles_destroyed_room_without_alternate_room() -> Result<()> {
 }
 
 #[tokio::test]
-async fn test_handles_temporary_removal_from_room() -> Result<()> {
+async fn test_handles_kick_from_room_as_temporary_removal() -> Result<()> {
     let mut deps = MockSidebarDomainServiceDependencies::default();
     let mut seq = Sequence::new();
 
@@ -1226,6 +1643,18 @@ async fn test_handles_temporary_removal_from_room() -> Result<()> {
             .return_once(|_, _| Some(room));
     }
 
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(room.clone()),
+            predicate::eq(ClientRoomEventType::RemovedFromRoom {
+                reason: RoomRemovalReason::Kicked,
+            }),
+        )
+        .return_once(|_, _| ());
+
     deps.client_event_dispatcher
         .expect_dispatch_event()
         .once()
@@ -1235,7 +1664,7 @@ async fn test_handles_temporary_removal_from_room() -> Result<()> {
 
     let service = SidebarDomainService::from(deps.into_deps());
     service
-        .handle_removal_from_room(&muc_id!("room@conf.prose.org"), false)
+        .handle_removal_from_room(&muc_id!("room@conf.prose.org"), RoomRemovalReason::Kicked)
         .await?;
 
     assert_eq!(
@@ -1277,6 +1706,18 @@ async fn test_handles_permanent_removal_from_room() -> Result<()> {
             .return_once(|_, _| Some(room));
     }
 
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(room.clone()),
+            predicate::eq(ClientRoomEventType::RemovedFromRoom {
+                reason: RoomRemovalReason::Banned,
+            }),
+        )
+        .return_once(|_, _| ());
+
     deps.client_event_dispatcher
         .expect_dispatch_event()
         .once()
@@ -1286,7 +1727,7 @@ async fn test_handles_permanent_removal_from_room() -> Result<()> {
 
     let service = SidebarDomainService::from(deps.into_deps());
     service
-        .handle_removal_from_room(&muc_id!("room@conf.prose.org"), true)
+        .handle_removal_from_room(&muc_id!("room@conf.prose.org"), RoomRemovalReason::Banned)
         .await?;
 
     assert_eq!(
@@ -1336,6 +1777,16 @@ async fn test_handles_changed_room_config() -> Result<()> {
             })
         });
 
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::always(),
+            predicate::eq(ClientRoomEventType::AttributesChanged),
+        )
+        .return_once(|_, _| ());
+
     deps.bookmarks_service
         .expect_save_bookmark()
         .with(predicate::eq(
@@ -1360,6 +1811,66 @@ async fn test_handles_changed_room_config() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_handles_changed_room_features() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+    let mut seq = Sequence::new();
+
+    deps.connected_rooms_repo
+        .expect_get()
+        .once()
+        .in_sequence(&mut seq)
+        .return_once(move |_, _| {
+            Some(
+                Room::private_channel(muc_id!("room@conf.prose.org"))
+                    .with_name("Room Name")
+                    .with_sidebar_state(RoomSidebarState::InSidebar),
+            )
+        });
+
+    let updated_room = Room::private_channel(muc_id!("room@conf.prose.org"))
+        .with_name("Room Name")
+        .with_sidebar_state(RoomSidebarState::InSidebar)
+        .with_features(RoomFeatures {
+            message_moderation: true,
+            ..Default::default()
+        });
+
+    {
+        let updated_room = updated_room.clone();
+        deps.rooms_domain_service
+            .expect_reevaluate_room_spec()
+            .once()
+            .in_sequence(&mut seq)
+            .return_once(|_| Box::pin(async { Ok(updated_room) }));
+    }
+
+    // The replaced room is reported, but the bookmark doesn't need to be updated…
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(
+            predicate::eq(updated_room),
+            predicate::eq(ClientRoomEventType::AttributesChanged),
+        )
+        .return_once(|_, _| ());
+    deps.bookmarks_service.expect_save_bookmark().never();
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .in_sequence(&mut seq)
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_once(|_| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .handle_changed_room_config(&muc_id!("room@conf.prose.org"))
+        .await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_ignores_changed_config_for_connecting_room() -> Result<()> {
     let mut deps = MockSidebarDomainServiceDependencies::default();
@@ -1561,3 +2072,402 @@ async fn test_does_not_update_sidebar_state_of_already_joined_room_if_not_needed
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_handles_received_invitation() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    deps.ctx.set_connection_properties(ConnectionProperties {
+        connection_timestamp: Default::default(),
+        connected_jid: user_resource_id!("user1@prose.org/res"),
+        server_features: Default::default(),
+        rooms_caught_up: false,
+        decryption_context: None,
+    });
+
+    let invited_room = Room::invited(
+        &muc_id!("room@conf.prose.org"),
+        "User1",
+        user_id!("user2@prose.org"),
+        Some("secret".to_string()),
+        None,
+    );
+
+    deps.connected_rooms_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _| None);
+
+    deps.connected_rooms_repo
+        .expect_set()
+        .once()
+        .with(predicate::always(), predicate::eq(invited_room.clone()))
+        .return_once(|_, _| Ok(()));
+
+    deps.bookmarks_service
+        .expect_save_bookmark()
+        .once()
+        .with(predicate::eq(invitation_bookmark(Some("secret"), None)))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_once(|_| ());
+
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .once()
+        .with(
+            predicate::eq(invited_room),
+            predicate::eq(ClientRoomEventType::InvitationReceived {
+                sender: user_id!("user2@prose.org"),
+            }),
+        )
+        .return_once(|_, _| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .handle_received_invitation(
+            &muc_id!("room@conf.prose.org"),
+            &user_resource_id!("user2@prose.org/res"),
+            Some("secret".to_string()),
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_merges_repeated_invitation_into_pending_invitation() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    deps.ctx.set_connection_properties(ConnectionProperties {
+        connection_timestamp: Default::default(),
+        connected_jid: user_resource_id!("user1@prose.org/res"),
+        server_features: Default::default(),
+        rooms_caught_up: false,
+        decryption_context: None,
+    });
+
+    // A direct invitation carrying the password was received already…
+    let invited_room = Room::invited(
+        &muc_id!("room@conf.prose.org"),
+        "User1",
+        user_id!("user2@prose.org"),
+        Some("secret".to_string()),
+        None,
+    );
+
+    deps.connected_rooms_repo.expect_get().once().return_once({
+        let room = invited_room.clone();
+        |_, _| Some(room)
+    });
+
+    // The duplicate invitation doesn't show up as a new invitation…
+    deps.connected_rooms_repo.expect_set().never();
+    deps.client_event_dispatcher
+        .expect_dispatch_room_event()
+        .never();
+    deps.client_event_dispatcher.expect_dispatch_event().never();
+
+    // …but the bookmark is updated.
+    deps.bookmarks_service
+        .expect_save_bookmark()
+        .once()
+        .with(predicate::eq(invitation_bookmark(
+            Some("secret"),
+            Some("Join us"),
+        )))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .handle_received_invitation(
+            &muc_id!("room@conf.prose.org"),
+            &user_resource_id!("user2@prose.org/res"),
+            None,
+            Some("Join us".to_string()),
+        )
+        .await?;
+
+    assert_eq!(
+        invited_room.state(),
+        RoomState::Invited {
+            sender: user_id!("user2@prose.org"),
+            password: Some("secret".to_string()),
+            reason: Some("Join us".to_string()),
+        }
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_joins_room_when_invited_by_own_user() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    deps.ctx.set_connection_properties(ConnectionProperties {
+        connection_timestamp: Default::default(),
+        connected_jid: user_resource_id!("user1@prose.org/res"),
+        server_features: Default::default(),
+        rooms_caught_up: false,
+        decryption_context: None,
+    });
+
+    deps.connected_rooms_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _| None);
+
+    deps.rooms_domain_service
+        .expect_create_or_join_room()
+        .once()
+        .with(
+            predicate::eq(CreateOrEnterRoomRequest::JoinRoom {
+                room_id: muc_id!("room@conf.prose.org"),
+                password: None,
+                behavior: JoinRoomBehavior::system_initiated(),
+                decryption_context: None,
+            }),
+            predicate::always(),
+        )
+        .return_once(|_, _| {
+            Box::pin(async move { Ok(Room::private_channel(muc_id!("room@conf.prose.org"))) })
+        });
+
+    deps.bookmarks_service
+        .expect_save_bookmark()
+        .once()
+        .return_once(|_| Box::pin(async move { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_once(|_| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .handle_received_invitation(
+            &muc_id!("room@conf.prose.org"),
+            &user_resource_id!("user1@prose.org/other-res"),
+            None,
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_declines_invitation() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    deps.connected_rooms_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _| {
+            Some(Room::invited(
+                &muc_id!("room@conf.prose.org"),
+                "User1",
+                user_id!("user2@prose.org"),
+                None,
+                None,
+            ))
+        });
+
+    deps.connected_rooms_repo
+        .expect_delete()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(bare!("room@conf.prose.org")),
+        )
+        .return_once(|_, _| None);
+
+    deps.bookmarks_service
+        .expect_delete_bookmark()
+        .once()
+        .with(predicate::eq(bare!("room@conf.prose.org")))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_once(|_| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .decline_invitation(&muc_id!("room@conf.prose.org"))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_accepts_invitation() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    let invited_room = Room::invited(
+        &muc_id!("room@conf.prose.org"),
+        "User1",
+        user_id!("user2@prose.org"),
+        Some("secret".to_string()),
+        None,
+    );
+
+    deps.connected_rooms_repo.expect_get().once().return_once({
+        let room = invited_room.clone();
+        |_, _| Some(room)
+    });
+
+    // The room is joined with the password from the invitation…
+    deps.rooms_domain_service
+        .expect_create_or_join_room()
+        .once()
+        .with(
+            predicate::eq(CreateOrEnterRoomRequest::JoinRoom {
+                room_id: muc_id!("room@conf.prose.org"),
+                password: Some("secret".to_string()),
+                behavior: JoinRoomBehavior::user_initiated(),
+                decryption_context: None,
+            }),
+            predicate::eq(RoomSidebarState::InSidebar),
+        )
+        .return_once(|_, _| {
+            Box::pin(async {
+                Ok(Room::private_channel(muc_id!("room@conf.prose.org"))
+                    .with_name("Room Name")
+                    .with_sidebar_state(RoomSidebarState::InSidebar))
+            })
+        });
+
+    // …and its bookmark is replaced with one that has autojoin on.
+    deps.bookmarks_service
+        .expect_save_bookmark()
+        .once()
+        .with(predicate::eq(
+            Bookmark::private_channel(muc_id!("room@conf.prose.org"), "Room Name")
+                .set_sidebar_state(RoomSidebarState::InSidebar),
+        ))
+        .return_once(|_| Box::pin(async { Ok(()) }));
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_once(|_| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    let room_id = service
+        .accept_invitation(&muc_id!("room@conf.prose.org"))
+        .await?;
+
+    assert_eq!(room_id, RoomId::from(muc_id!("room@conf.prose.org")));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_does_not_accept_unknown_invitation() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    deps.connected_rooms_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _| Some(Room::private_channel(muc_id!("room@conf.prose.org"))));
+    deps.rooms_domain_service
+        .expect_create_or_join_room()
+        .never();
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    assert!(service
+        .accept_invitation(&muc_id!("room@conf.prose.org"))
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_restores_pending_invitation_from_bookmark() -> Result<()> {
+    let mut deps = MockSidebarDomainServiceDependencies::default();
+
+    deps.ctx.set_connection_properties(ConnectionProperties {
+        connection_timestamp: Default::default(),
+        connected_jid: user_resource_id!("user1@prose.org/res"),
+        server_features: Default::default(),
+        rooms_caught_up: false,
+        decryption_context: None,
+    });
+
+    deps.connected_rooms_repo
+        .expect_get_all()
+        .once()
+        .return_once(|_| vec![]);
+
+    let inserted_room = Arc::new(Mutex::new(None));
+    {
+        let inserted_room = inserted_room.clone();
+        deps.connected_rooms_repo
+            .expect_set()
+            .once()
+            .return_once(move |_, room| {
+                inserted_room.lock().replace(room);
+                Ok(())
+            });
+    }
+
+    // Bookmarks with autojoin off are not joined…
+    deps.rooms_domain_service
+        .expect_create_or_join_room()
+        .never();
+
+    deps.client_event_dispatcher
+        .expect_dispatch_event()
+        .once()
+        .with(predicate::eq(ClientEvent::SidebarChanged))
+        .return_once(|_| ());
+
+    let service = SidebarDomainService::from(deps.into_deps());
+    service
+        .handle_changed_items(
+            vec![invitation_bookmark(Some("secret"), None)],
+            Default::default(),
+        )
+        .await?;
+
+    let room = inserted_room
+        .lock()
+        .take()
+        .expect("Expected a room to be inserted");
+    assert_eq!(
+        room.state(),
+        RoomState::Invited {
+            sender: user_id!("user2@prose.org"),
+            password: Some("secret".to_string()),
+            reason: None,
+        }
+    );
+
+    Ok(())
+}
+
+fn invitation_bookmark(password: Option<&str>, reason: Option<&str>) -> Bookmark {
+    Bookmark {
+        name: "room@conf.prose.org".to_string(),
+        jid: muc_id!("room@conf.prose.org").into(),
+        r#type: BookmarkType::Generic,
+        sidebar_state: RoomSidebarState::InSidebar,
+        order_index: None,
+        invitation: Some(BookmarkInvitation {
+            sender: user_id!("user2@prose.org"),
+            password: password.map(ToString::to_string),
+            reason: reason.map(ToString::to_string),
+        }),
+    }
+}
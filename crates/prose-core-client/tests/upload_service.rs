@@ -0,0 +1,242 @@
+// prose-core-client/prose-core-client
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use url::Url;
+
+use prose_core_client::domain::connection::models::{
+    ConnectionProperties, HttpUploadService, ServerFeatures,
+};
+use prose_core_client::domain::shared::models::UserResourceId;
+use prose_core_client::domain::uploads::models::{UploadHeader, UploadSlot};
+use prose_core_client::dtos::{UploadError, UploadSource};
+use prose_core_client::services::UploadService;
+use prose_core_client::test::MockAppDependencies;
+use prose_core_client::user_resource_id;
+use prose_xmpp::bare;
+
+#[tokio::test]
+async fn test_uploads_file_and_reports_progress() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    set_max_file_size(&deps, 1024);
+
+    let (upload_url, request) = serve_once(201).await?;
+
+    deps.upload_service
+        .expect_request_upload_slot()
+        .once()
+        .return_once(move |host, file_name, file_size, media_type| {
+            assert_eq!(&bare!("upload.prose.org"), host);
+            assert_eq!("file.txt", file_name);
+            assert_eq!(11, file_size);
+            assert_eq!(&mime::TEXT_PLAIN, media_type);
+
+            Box::pin(async move {
+                Ok(UploadSlot {
+                    upload_url,
+                    upload_headers: vec![UploadHeader {
+                        name: "Authorization".to_string(),
+                        value: "Basic c2VjcmV0".to_string(),
+                    }],
+                    download_url: Url::parse("https://uploads.prose.org/file.txt")?,
+                })
+            })
+        });
+
+    let progress = Arc::new(Mutex::new(vec![]));
+
+    let service = UploadService::from(&deps.into_deps());
+    let (_, upload) = {
+        let progress = progress.clone();
+        service.upload_file(
+            UploadSource::Bytes(b"Hello World".to_vec()),
+            "file.txt",
+            None,
+            false,
+            move |bytes_sent, total_bytes| progress.lock().push((bytes_sent, total_bytes)),
+        )
+    };
+    let slot = upload.await?;
+
+    assert_eq!(
+        Url::parse("https://uploads.prose.org/file.txt")?,
+        slot.download_url
+    );
+
+    let request = request.await??.to_lowercase();
+    assert!(request.starts_with("put /upload/file.txt http/1.1\r\n"));
+    assert!(request.contains("\r\nauthorization: basic c2vjcmv0\r\n"));
+    assert!(request.contains("\r\ncontent-type: text/plain\r\n"));
+    assert!(request.contains("\r\ncontent-length: 11\r\n"));
+    assert!(request.ends_with("\r\n\r\nhello world"));
+
+    let progress = progress.lock().clone();
+    assert_eq!(Some(&(0, 11)), progress.first());
+    assert_eq!(Some(&(11, 11)), progress.last());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reports_unexpected_http_status() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    set_max_file_size(&deps, 1024);
+
+    let (upload_url, request) = serve_once(413).await?;
+
+    deps.upload_service
+        .expect_request_upload_slot()
+        .once()
+        .return_once(move |_, _, _, _| {
+            Box::pin(async move {
+                Ok(UploadSlot {
+                    upload_url,
+                    upload_headers: vec![],
+                    download_url: Url::parse("https://uploads.prose.org/file.txt")?,
+                })
+            })
+        });
+
+    let service = UploadService::from(&deps.into_deps());
+    let (_, upload) = service.upload_file(
+        UploadSource::Bytes(b"Hello World".to_vec()),
+        "file.txt",
+        None,
+        false,
+        |_, _| {},
+    );
+
+    assert!(matches!(
+        upload.await,
+        Err(UploadError::UnexpectedStatus { status: 413 })
+    ));
+    request.await??;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rejects_file_exceeding_max_file_size() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    set_max_file_size(&deps, 5);
+
+    deps.upload_service.expect_request_upload_slot().never();
+
+    let service = UploadService::from(&deps.into_deps());
+    let (_, upload) = service.upload_file(
+        UploadSource::Bytes(b"Hello World".to_vec()),
+        "file.txt",
+        None,
+        false,
+        |_, _| {},
+    );
+
+    assert!(matches!(
+        upload.await,
+        Err(UploadError::FileTooLarge {
+            max_file_size: Some(5)
+        })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_cancels_upload() -> Result<()> {
+    let mut deps = MockAppDependencies::default();
+    set_max_file_size(&deps, 1024);
+
+    deps.upload_service.expect_request_upload_slot().never();
+
+    let service = UploadService::from(&deps.into_deps());
+    let (handle, upload) = service.upload_file(
+        UploadSource::Bytes(b"Hello World".to_vec()),
+        "file.txt",
+        None,
+        false,
+        |_, _| {},
+    );
+
+    handle.cancel();
+
+    assert!(handle.is_cancelled());
+    assert!(matches!(upload.await, Err(UploadError::Cancelled)));
+
+    Ok(())
+}
+
+fn set_max_file_size(deps: &MockAppDependencies, max_file_size: u64) {
+    *deps.ctx.connection_properties.write() = Some(ConnectionProperties {
+        connection_timestamp: Default::default(),
+        connected_jid: user_resource_id!("user@prose.org/res"),
+        server_features: ServerFeatures {
+            http_upload_service: Some(HttpUploadService {
+                host: bare!("upload.prose.org"),
+                max_file_size,
+            }),
+            ..Default::default()
+        },
+        rooms_caught_up: false,
+        decryption_context: None,
+    });
+}
+
+/// Accepts a single HTTP request, answers it with `status` and resolves to the raw request.
+async fn serve_once(status: u16) -> Result<(Url, JoinHandle<Result<String>>)> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let url = Url::parse(&format!(
+        "http://{}/upload/file.txt",
+        listener.local_addr()?
+    ))?;
+
+    let request = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await?;
+        let mut request = vec![];
+        let mut buffer = [0; 1024];
+
+        while !is_complete_request(&request) {
+            let len = stream.read(&mut buffer).await?;
+            if len == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..len]);
+        }
+
+        stream
+            .write_all(
+                format!(
+                    "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await?;
+
+        Ok(String::from_utf8(request)?)
+    });
+
+    Ok((url, request))
+}
+
+fn is_complete_request(request: &[u8]) -> bool {
+    let request = String::from_utf8_lossy(request);
+    let Some((head, body)) = request.split_once("\r\n\r\n") else {
+        return false;
+    };
+
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    body.len() >= content_length
+}
@@ -10,6 +10,7 @@ use chrono::{TimeZone, Utc};
 use mockall::predicate;
 
 use prose_core_client::domain::shared::models::{UserId, UserResourceId};
+use prose_core_client::domain::user_info::models::{PruneStats, UserInfo};
 use prose_core_client::domain::user_info::services::impls::UserInfoDomainService;
 use prose_core_client::domain::user_info::services::UserInfoDomainService as UserInfoDomainServiceTrait;
 use prose_core_client::test::{ConstantTimeProvider, MockUserInfoDomainServiceDependencies};
@@ -55,3 +56,110 @@ async fn test_load_user_metadata_resolves_full_jid() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_load_last_activity_prefers_idle_since_from_presence() -> Result<()> {
+    let mut deps = MockUserInfoDomainServiceDependencies::default();
+
+    deps.block_list_repo
+        .expect_contains()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(false) }));
+
+    deps.user_info_repo
+        .expect_get()
+        .once()
+        .with(predicate::always(), predicate::eq(user_id!("a@prose.org")))
+        .return_once(|_, _| {
+            Box::pin(async {
+                Ok(Some(UserInfo {
+                    idle_since: Some(Utc.with_ymd_and_hms(2023, 09, 10, 15, 0, 0).unwrap()),
+                    ..Default::default()
+                }))
+            })
+        });
+
+    let service = UserInfoDomainService::from(deps.into_deps());
+
+    assert_eq!(
+        service.load_last_activity(&user_id!("a@prose.org")).await?,
+        Some(Utc.with_ymd_and_hms(2023, 09, 10, 15, 0, 0).unwrap())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_load_last_activity_falls_back_to_last_activity_query() -> Result<()> {
+    let mut deps = MockUserInfoDomainServiceDependencies::default();
+
+    deps.time_provider = Arc::new(ConstantTimeProvider::ymd(2023, 09, 11));
+
+    deps.block_list_repo
+        .expect_contains()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(false) }));
+
+    deps.user_info_repo
+        .expect_get()
+        .once()
+        .return_once(|_, _| Box::pin(async { Ok(Some(UserInfo::default())) }));
+
+    deps.user_info_service
+        .expect_load_last_activity()
+        .once()
+        .with(
+            predicate::eq(user_id!("a@prose.org")),
+            predicate::eq(Utc.with_ymd_and_hms(2023, 09, 11, 0, 0, 0).unwrap()),
+        )
+        .return_once(|_, _| {
+            Box::pin(async { Ok(Some(Utc.with_ymd_and_hms(2023, 09, 8, 0, 0, 0).unwrap())) })
+        });
+
+    let service = UserInfoDomainService::from(deps.into_deps());
+
+    assert_eq!(
+        service.load_last_activity(&user_id!("a@prose.org")).await?,
+        Some(Utc.with_ymd_and_hms(2023, 09, 8, 0, 0, 0).unwrap())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prunes_expired_cache_entries() -> Result<()> {
+    let mut deps = MockUserInfoDomainServiceDependencies::default();
+
+    deps.time_provider = Arc::new(ConstantTimeProvider::ymd(2023, 09, 11));
+
+    deps.user_profile_repo
+        .expect_prune()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(Utc.with_ymd_and_hms(2023, 08, 12, 0, 0, 0).unwrap()),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(3) }));
+    deps.avatar_repo
+        .expect_prune()
+        .once()
+        .with(
+            predicate::always(),
+            predicate::eq(Utc.with_ymd_and_hms(2023, 08, 12, 0, 0, 0).unwrap()),
+        )
+        .return_once(|_, _| Box::pin(async { Ok(2) }));
+
+    let service = UserInfoDomainService::from(deps.into_deps());
+    let stats = service.prune_cache().await?;
+
+    assert_eq!(
+        stats,
+        PruneStats {
+            user_profiles: 3,
+            avatars: 2,
+            messages: 0,
+        }
+    );
+
+    Ok(())
+}
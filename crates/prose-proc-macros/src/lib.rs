@@ -139,3 +139,32 @@ pub fn mt_test(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     TokenStream::from(expanded)
 }
+
+/// Runs an async test against every store driver, i.e. once with a store of the persistent driver
+/// of the platform and once with a store of the in-memory driver. The test receives the store as
+/// its only argument. The module of the test must provide the stores via `store` and
+/// `in_memory_store` as well as the test attribute via `async_test`.
+#[proc_macro_attribute]
+pub fn store_test(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+    let name = &input.sig.ident;
+    let output = &input.sig.output;
+
+    let expanded = quote! {
+        #input
+
+        mod #name {
+            #[super::async_test]
+            async fn persistent() #output {
+                super::#name(super::store().await?).await
+            }
+
+            #[super::async_test]
+            async fn in_memory() #output {
+                super::#name(super::in_memory_store().await?).await
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
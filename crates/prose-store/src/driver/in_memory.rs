@@ -0,0 +1,1388 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::iter::zip;
+use std::marker::PhantomData;
+use std::ops::Bound;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+use prose_wasm_utils::SendUnlessWasm;
+
+use crate::driver::{ReadMode, ReadOnly, ReadWrite, Upgrade, WriteMode};
+use crate::{
+    Collection, CollectionUsage, Database, IndexSpec, IndexedCollection, KeyTuple, KeyType, Query,
+    QueryDirection, RawKey, ReadTransaction, ReadableCollection, StoreError, Transaction,
+    UpgradeTransaction, VersionChangeEvent, WritableCollection, WriteTransaction,
+};
+
+use super::Driver;
+
+/// A driver for a database that lives in memory only and is available on all platforms.
+///
+/// It mirrors the behavior of the SQLite driver (comparison and sort order of keys, unique
+/// constraints, full-text search, …) so that it can stand in for the platform driver in tests
+/// and in sessions that shouldn't leave any traces on disk. Unlike with SQLite, changes of a
+/// write transaction are visible to other transactions before they are committed and the
+/// full-text search only removes diacritics from Latin characters.
+///
+/// Clones of a driver share their database, i.e. opening a clone again returns the data of the
+/// previously opened database.
+#[derive(Clone, Default)]
+pub struct InMemoryDriver {
+    state: Arc<Mutex<DriverState>>,
+}
+
+#[derive(Default)]
+struct DriverState {
+    version: u32,
+    tables: Tables,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Collection {collection} does not exist")]
+    UnknownCollection { collection: String },
+
+    #[error("Collection {collection} already exists")]
+    CollectionExists { collection: String },
+
+    #[error("Index {index} does not exist on collection {collection}")]
+    UnknownIndex { collection: String, index: String },
+
+    #[error("Index {index} already exists on collection {collection}")]
+    IndexExists { collection: String, index: String },
+
+    #[error("Collection {collection} is not a member of the current transaction")]
+    NotMemberOfTransaction { collection: String },
+
+    #[error("Constraint {constraint} failed on collection {collection}")]
+    ConstraintViolation {
+        collection: String,
+        constraint: String,
+    },
+
+    #[error("Column {column} of collection {collection} does not contain text")]
+    InvalidColumnType { collection: String, column: String },
+
+    #[error(transparent)]
+    JSON(#[from] serde_json::Error),
+
+    #[error("The database has version {found} but only versions up to {supported} are supported")]
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+impl StoreError for Error {
+    fn is_schema_mismatch(&self) -> bool {
+        match self {
+            Error::UnknownCollection { .. }
+            | Error::CollectionExists { .. }
+            | Error::UnknownIndex { .. }
+            | Error::IndexExists { .. }
+            | Error::InvalidColumnType { .. }
+            | Error::JSON(_) => true,
+            Error::NotMemberOfTransaction { .. }
+            | Error::ConstraintViolation { .. }
+            | Error::UnsupportedVersion { .. } => false,
+        }
+    }
+}
+
+impl InMemoryDriver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the database like `Driver::open` but lets `update_handler` fail with errors of
+    /// another type, so that the driver can stand in for the platform driver
+    /// (see `PlatformDriver`).
+    pub(crate) fn open_with<E: From<Error>>(
+        self,
+        version: u32,
+        update_handler: impl Fn(&VersionChangeEvent<InMemoryTransaction<'_, Upgrade>>) -> Result<(), E>,
+    ) -> Result<InMemoryDB, E> {
+        let (current_version, tables) = {
+            let state = self.state.lock();
+            (state.version, state.tables.clone())
+        };
+
+        if current_version > version {
+            return Err(Error::UnsupportedVersion {
+                found: current_version,
+                supported: version,
+            }
+            .into());
+        }
+
+        if current_version == version {
+            return Ok(InMemoryDB { tables, version });
+        }
+
+        // The upgrade is applied to a copy of the database, which replaces the database only if
+        // the update handler succeeds.
+        let tables = Tables::new(tables.read().clone());
+        let event = VersionChangeEvent {
+            tx: InMemoryTransaction::new(
+                tables.table_names().into_iter().collect(),
+                tables.clone(),
+            ),
+            old_version: current_version,
+            new_version: version,
+            phantom: Default::default(),
+        };
+        update_handler(&event)?;
+
+        let mut state = self.state.lock();
+        state.version = version;
+        state.tables = tables.clone();
+
+        Ok(InMemoryDB { tables, version })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl Driver for InMemoryDriver {
+    type Error = Error;
+
+    type UpgradeTransaction<'db> = InMemoryTransaction<'db, Upgrade>;
+    type Database = InMemoryDB;
+
+    async fn open<F>(self, version: u32, update_handler: F) -> Result<Self::Database, Self::Error>
+    where
+        F: Fn(&VersionChangeEvent<Self::UpgradeTransaction<'_>>) -> Result<(), Self::Error>
+            + Send
+            + 'static,
+    {
+        self.open_with(version, update_handler)
+    }
+
+    /// Discards the database. Databases that are still open keep their data.
+    async fn reset(&self) -> Result<(), Self::Error> {
+        *self.state.lock() = Default::default();
+        Ok(())
+    }
+}
+
+pub struct InMemoryDB {
+    tables: Tables,
+    version: u32,
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl Database for InMemoryDB {
+    type Error = Error;
+
+    type ReadTransaction<'db> = InMemoryTransaction<'db, ReadOnly>;
+    type ReadWriteTransaction<'db> = InMemoryTransaction<'db, ReadWrite>;
+
+    fn schema_version(&self) -> u32 {
+        self.version
+    }
+
+    async fn collection_names(&self) -> Result<Vec<String>, Self::Error> {
+        Ok(self.tables.table_names())
+    }
+
+    async fn transaction_for_reading(
+        &self,
+        collections: &[&str],
+    ) -> Result<Self::ReadTransaction<'_>, Self::Error> {
+        self.transaction(collections)
+    }
+
+    async fn transaction_for_reading_and_writing(
+        &self,
+        collections: &[&str],
+    ) -> Result<Self::ReadWriteTransaction<'_>, Self::Error> {
+        self.transaction(collections)
+    }
+}
+
+impl InMemoryDB {
+    fn transaction<Mode>(&self, collections: &[&str]) -> Result<InMemoryTransaction<Mode>, Error> {
+        {
+            let tables = self.tables.read();
+            for collection in collections {
+                if !tables.contains_key(*collection) {
+                    return Err(Error::UnknownCollection {
+                        collection: collection.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(InMemoryTransaction::new(
+            collections.iter().map(ToString::to_string).collect(),
+            self.tables.clone(),
+        ))
+    }
+}
+
+/// Changes are applied to the database right away. To be able to roll back a transaction, the
+/// previous state of each changed row is recorded in `undo_log`.
+pub struct InMemoryTransaction<'db, Mode> {
+    member_collections: HashSet<String>,
+    tables: Tables,
+    undo_log: UndoLog,
+    phantom: PhantomData<&'db Mode>,
+}
+
+impl<'db, Mode> InMemoryTransaction<'db, Mode> {
+    fn new(member_collections: HashSet<String>, tables: Tables) -> Self {
+        Self {
+            member_collections,
+            tables,
+            undo_log: Default::default(),
+            phantom: Default::default(),
+        }
+    }
+
+    fn collection<CollectionMode>(
+        &self,
+        name: &str,
+    ) -> Result<InMemoryCollection<CollectionMode>, Error> {
+        if !self.member_collections.contains(name) {
+            return Err(Error::NotMemberOfTransaction {
+                collection: name.to_string(),
+            });
+        }
+        Ok(InMemoryCollection::new(
+            name.to_string(),
+            self.tables.clone(),
+            self.undo_log.clone(),
+        ))
+    }
+}
+
+impl<'db> UpgradeTransaction<'db> for InMemoryTransaction<'db, Upgrade> {
+    type Error = Error;
+    type ReadWriteTransaction<'tx>
+        = InMemoryTransaction<'tx, ReadWrite>
+    where
+        Self: 'tx;
+
+    fn collection_names(&self) -> Result<Vec<String>, Self::Error> {
+        Ok(self.tables.table_names())
+    }
+
+    fn create_collection(
+        &self,
+        name: &str,
+    ) -> Result<
+        <Self::ReadWriteTransaction<'_> as WriteTransaction<'_>>::WritableCollection<'_>,
+        Self::Error,
+    > {
+        {
+            let mut tables = self.tables.write();
+            if tables.contains_key(name) {
+                return Err(Error::CollectionExists {
+                    collection: name.to_string(),
+                });
+            }
+            tables.insert(name.to_string(), Table::default());
+        }
+
+        Ok(InMemoryCollection::new(
+            name.to_string(),
+            self.tables.clone(),
+            self.undo_log.clone(),
+        ))
+    }
+
+    fn delete_collection(&self, name: &str) -> Result<(), Self::Error> {
+        self.tables
+            .write()
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| Error::UnknownCollection {
+                collection: name.to_string(),
+            })
+    }
+}
+
+impl<'db, Mode: Send + Sync> Transaction<'db> for InMemoryTransaction<'db, Mode> {
+    type Error = Error;
+}
+
+impl<'db, Mode: Send + Sync> ReadTransaction<'db> for InMemoryTransaction<'db, Mode>
+where
+    Mode: ReadMode,
+{
+    type ReadableCollection<'tx>
+        = InMemoryCollection<'tx, ReadOnly>
+    where
+        Self: 'tx;
+
+    fn readable_collection(&self, name: &str) -> Result<Self::ReadableCollection<'_>, Self::Error> {
+        self.collection(name)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl<'db, Mode: Send + Sync> WriteTransaction<'db> for InMemoryTransaction<'db, Mode>
+where
+    Mode: WriteMode + Sync,
+{
+    type WritableCollection<'tx>
+        = InMemoryCollection<'tx, ReadWrite>
+    where
+        Self: 'tx;
+
+    fn writeable_collection(
+        &self,
+        name: &str,
+    ) -> Result<Self::WritableCollection<'_>, Self::Error> {
+        self.collection(name)
+    }
+
+    async fn commit(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    async fn rollback(self) -> Result<(), Self::Error> {
+        let entries = std::mem::take(&mut *self.undo_log.lock());
+        let mut tables = self.tables.write();
+
+        for entry in entries.into_iter().rev() {
+            let Some(table) = tables.get_mut(&entry.collection) else {
+                continue;
+            };
+            match entry.row {
+                Some(row) => table.rows.insert(entry.key, row),
+                None => table.rows.remove(&entry.key),
+            };
+        }
+
+        Ok(())
+    }
+}
+
+pub struct InMemoryCollection<'tx, Mode> {
+    name: String,
+    columns: Vec<Column>,
+    tables: Tables,
+    undo_log: UndoLog,
+    phantom: PhantomData<&'tx Mode>,
+}
+
+impl<'tx, Mode> InMemoryCollection<'tx, Mode> {
+    fn new(name: String, tables: Tables, undo_log: UndoLog) -> Self {
+        Self {
+            name,
+            columns: vec![Column::Key],
+            tables,
+            undo_log,
+            phantom: Default::default(),
+        }
+    }
+
+    fn read_table<T>(&self, f: impl FnOnce(&Table) -> Result<T, Error>) -> Result<T, Error> {
+        let tables = self.tables.read();
+        let table = tables
+            .get(&self.name)
+            .ok_or_else(|| Error::UnknownCollection {
+                collection: self.name.clone(),
+            })?;
+        f(table)
+    }
+
+    fn write_table<T>(
+        &self,
+        f: impl FnOnce(&mut Table, &mut Changes) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut changes = Changes::new();
+
+        let result = {
+            let mut tables = self.tables.write();
+            let table = tables
+                .get_mut(&self.name)
+                .ok_or_else(|| Error::UnknownCollection {
+                    collection: self.name.clone(),
+                })?;
+            f(table, &mut changes)
+        };
+
+        self.undo_log
+            .lock()
+            .extend(changes.into_iter().map(|(key, row)| UndoEntry {
+                collection: self.name.clone(),
+                key,
+                row,
+            }));
+
+        result
+    }
+
+    /// Returns the keys and serialized values of all rows matching `predicate` in the order of
+    /// the collection's columns.
+    fn select(
+        &self,
+        predicate: &Predicate,
+        direction: QueryDirection,
+    ) -> Result<Vec<(String, String)>, Error> {
+        self.read_table(|table| {
+            Ok(table
+                .select(&self.columns, predicate, direction)
+                .into_iter()
+                .map(|(key, row)| (key.clone(), row.json.clone()))
+                .collect())
+        })
+    }
+
+    fn assert_key_matches_columns(&self, values: &[RawKey]) {
+        assert_eq!(
+            values.len(),
+            self.columns.len(),
+            "The number of tuple fields should match the number of columns in the index"
+        );
+    }
+}
+
+impl<'tx, Mode: Send + Sync> Collection<'tx> for InMemoryCollection<'tx, Mode> {
+    type Error = Error;
+}
+
+impl<'tx, Mode: Send> IndexedCollection<'tx> for InMemoryCollection<'tx, Mode>
+where
+    Mode: ReadMode + Sync,
+{
+    type Index<'coll>
+        = InMemoryCollection<'coll, Mode>
+    where
+        Self: 'coll;
+
+    fn index(&self, columns: &[&str]) -> Result<Self::Index<'_>, Self::Error> {
+        let index_name = columns.join("_");
+
+        self.read_table(|table| {
+            if !table.indexes.contains_key(&index_name) {
+                return Err(Error::UnknownIndex {
+                    collection: self.name.clone(),
+                    index: index_name,
+                });
+            }
+            Ok(())
+        })?;
+
+        Ok(InMemoryCollection {
+            name: self.name.clone(),
+            columns: columns
+                .iter()
+                .map(|column| Column::Field(column.to_string()))
+                .collect(),
+            tables: self.tables.clone(),
+            undo_log: self.undo_log.clone(),
+            phantom: Default::default(),
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl<'tx, Mode: Send + Sync> ReadableCollection<'tx> for InMemoryCollection<'tx, Mode>
+where
+    Mode: ReadMode + Sync,
+{
+    async fn get<K: KeyTuple + ?Sized, V: DeserializeOwned>(
+        &self,
+        key: &K,
+    ) -> Result<Option<V>, Self::Error> {
+        let values = key.to_raw_keys();
+        self.assert_key_matches_columns(&values);
+
+        let predicate = Predicate::equal(values, &self.columns);
+        let data = self.read_table(|table| {
+            Ok(table
+                .select(&self.columns, &predicate, QueryDirection::Forward)
+                .first()
+                .map(|(_, row)| row.json.clone()))
+        })?;
+
+        Ok(data.map(|data| serde_json::from_str(&data)).transpose()?)
+    }
+
+    async fn contains_key<K: KeyTuple + ?Sized>(&self, key: &K) -> Result<bool, Self::Error> {
+        let values = key.to_raw_keys();
+        self.assert_key_matches_columns(&values);
+
+        let predicate = Predicate::equal(values, &self.columns);
+        self.read_table(|table| {
+            Ok(table
+                .rows
+                .iter()
+                .any(|(key, row)| predicate.matches(&Column::values(&self.columns, key, row))))
+        })
+    }
+
+    async fn all_keys(&self) -> Result<Vec<String>, Self::Error> {
+        assert_eq!(
+            self.columns.len(),
+            1,
+            "all_keys is not supported for multi-column indexes."
+        );
+
+        self.read_table(|table| {
+            table
+                .select(
+                    &self.columns,
+                    &Predicate::default(),
+                    QueryDirection::Forward,
+                )
+                .into_iter()
+                .map(|(key, row)| match self.columns[0].value(key, row) {
+                    SqlValue::Text(value) => Ok(value),
+                    _ => Err(Error::InvalidColumnType {
+                        collection: self.name.clone(),
+                        column: self.columns[0].to_string(),
+                    }),
+                })
+                .collect()
+        })
+    }
+
+    async fn get_all<Value: DeserializeOwned + Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        direction: QueryDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<(String, Value)>, Self::Error> {
+        self.get_all_filtered(query, direction, limit, |key, value| Some((key, value)))
+            .await
+    }
+
+    async fn get_all_filtered<Value: DeserializeOwned + Send, T: Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        direction: QueryDirection,
+        limit: Option<usize>,
+        mut filter: impl FnMut(String, Value) -> Option<T> + SendUnlessWasm,
+    ) -> Result<Vec<T>, Self::Error> {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut result = vec![];
+
+        if limit == 0 {
+            return Ok(result);
+        }
+
+        let predicate = Predicate::new(query, &self.columns);
+        for (key, data) in self.select(&predicate, direction)? {
+            if let Some(transformed_value) = filter(key, serde_json::from_str(&data)?) {
+                result.push(transformed_value);
+            }
+            if result.len() == limit {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn fold<Value: DeserializeOwned + Send, T: Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        init: T,
+        mut f: impl FnMut(T, (String, Value)) -> T + SendUnlessWasm,
+    ) -> Result<T, Self::Error> {
+        let predicate = Predicate::new(query, &self.columns);
+        let mut last_value = init;
+
+        for (key, data) in self.select(&predicate, QueryDirection::default())? {
+            last_value = f(last_value, (key, serde_json::from_str(&data)?));
+        }
+
+        Ok(last_value)
+    }
+
+    async fn search<Value: DeserializeOwned + Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        column: &str,
+        text: &str,
+        direction: QueryDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<Value>, Self::Error> {
+        let predicate = Predicate::new(query, &self.columns);
+        let phrases = text
+            .split_whitespace()
+            .map(tokenize)
+            .filter(|tokens| !tokens.is_empty())
+            .collect::<Vec<_>>();
+        let text_column = Column::Field(column.to_string());
+
+        let rows = self.read_table(|table| {
+            if !table.fulltext_columns.contains(column) {
+                return Err(Error::UnknownIndex {
+                    collection: self.name.clone(),
+                    index: format!("{column}_fts"),
+                });
+            }
+
+            if phrases.is_empty() {
+                return Ok(vec![]);
+            }
+
+            Ok(table
+                .select(&self.columns, &predicate, direction)
+                .into_iter()
+                .filter(|(key, row)| {
+                    let Some(text) = text_column.value(key, row).into_text() else {
+                        return false;
+                    };
+                    let tokens = tokenize(&text);
+                    phrases
+                        .iter()
+                        .all(|phrase| contains_phrase(&tokens, phrase))
+                })
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|(_, row)| row.json.clone())
+                .collect::<Vec<_>>())
+        })?;
+
+        let mut values = vec![];
+        for data in rows {
+            values.push(serde_json::from_str(&data)?);
+        }
+
+        Ok(values)
+    }
+
+    async fn usage(&self, query: Query<impl KeyTuple>) -> Result<CollectionUsage, Self::Error> {
+        let predicate = Predicate::new(query, &self.columns);
+
+        self.read_table(|table| {
+            Ok(table
+                .rows
+                .iter()
+                .filter(|(key, row)| predicate.matches(&Column::values(&self.columns, key, row)))
+                .fold(CollectionUsage::default(), |usage, (_, row)| {
+                    CollectionUsage {
+                        rows: usage.rows + 1,
+                        bytes: usage.bytes + row.json.len() as u64,
+                    }
+                }))
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl<'tx> WritableCollection<'tx> for InMemoryCollection<'tx, ReadWrite> {
+    fn add_index(&self, idx: IndexSpec) -> Result<(), Self::Error> {
+        let index_name = idx.keys.join("_");
+        let columns = idx.keys.into_iter().map(Column::Field).collect::<Vec<_>>();
+
+        self.write_table(|table, _| {
+            if table.indexes.contains_key(&index_name) {
+                return Err(Error::IndexExists {
+                    collection: self.name.clone(),
+                    index: index_name,
+                });
+            }
+
+            if idx.unique && table.contains_duplicates(&columns) {
+                return Err(Error::ConstraintViolation {
+                    collection: self.name.clone(),
+                    constraint: index_name,
+                });
+            }
+
+            table.indexes.insert(
+                index_name,
+                Index {
+                    columns,
+                    unique: idx.unique,
+                },
+            );
+            Ok(())
+        })
+    }
+
+    fn add_fulltext_index(&self, column: &str) -> Result<(), Self::Error> {
+        self.write_table(|table, _| {
+            if !table.fulltext_columns.insert(column.to_string()) {
+                return Err(Error::IndexExists {
+                    collection: self.name.clone(),
+                    index: format!("{column}_fts"),
+                });
+            }
+            Ok(())
+        })
+    }
+
+    async fn set<K: KeyType + ?Sized, V: Serialize + ?Sized + Send + Sync>(
+        &self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), Self::Error> {
+        let key = raw_key_to_text(key.to_raw_key());
+        let json = serde_json::to_string(value)?;
+        let value = serde_json::from_str::<Value>(&json)?;
+
+        self.write_table(|table, changes| {
+            let constraint = if table.rows.contains_key(&key) {
+                Some("key".to_string())
+            } else {
+                table
+                    .unique_conflicts(&key, &value)
+                    .into_iter()
+                    .next()
+                    .map(|(index_name, _)| index_name)
+            };
+
+            if let Some(constraint) = constraint {
+                return Err(Error::ConstraintViolation {
+                    collection: self.name.clone(),
+                    constraint,
+                });
+            }
+
+            table.insert(key, json, value, changes);
+            Ok(())
+        })
+    }
+
+    /// Replaces the row with the same key as well as all rows that conflict with `value` in a
+    /// unique index, like `INSERT OR REPLACE` does.
+    fn put<K: KeyType + ?Sized, V: Serialize>(
+        &self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), Self::Error> {
+        let key = raw_key_to_text(key.to_raw_key());
+        let json = serde_json::to_string(value)?;
+        let value = serde_json::from_str::<Value>(&json)?;
+
+        self.write_table(|table, changes| {
+            table.remove(&key, changes);
+            for (_, conflicting_key) in table.unique_conflicts(&key, &value) {
+                table.remove(&conflicting_key, changes);
+            }
+            table.insert(key, json, value, changes);
+            Ok(())
+        })
+    }
+
+    async fn delete<K: KeyTuple + ?Sized>(&self, key: &K) -> Result<(), Self::Error> {
+        let values = key.to_raw_keys();
+        self.assert_key_matches_columns(&values);
+
+        let predicate = Predicate::equal(values, &self.columns);
+        self.write_table(|table, changes| {
+            table.remove_matching(&self.columns, &predicate, changes);
+            Ok(())
+        })
+    }
+
+    async fn delete_all_in_index(
+        &self,
+        columns: &[&str],
+        query: Query<impl KeyTuple>,
+    ) -> Result<(), Self::Error> {
+        let columns = columns
+            .iter()
+            .map(|column| Column::Field(column.to_string()))
+            .collect::<Vec<_>>();
+        let predicate = Predicate::new(query, &columns);
+
+        self.write_table(|table, changes| {
+            table.remove_matching(&columns, &predicate, changes);
+            Ok(())
+        })
+    }
+
+    fn truncate(&self) -> Result<(), Self::Error> {
+        self.write_table(|table, changes| {
+            table.remove_matching(&[], &Predicate::default(), changes);
+            Ok(())
+        })
+    }
+}
+
+#[derive(Clone, Default)]
+struct Tables(Arc<RwLock<HashMap<String, Table>>>);
+
+impl Tables {
+    fn new(tables: HashMap<String, Table>) -> Self {
+        Self(Arc::new(RwLock::new(tables)))
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.read().keys().cloned().collect()
+    }
+}
+
+impl std::ops::Deref for Tables {
+    type Target = RwLock<HashMap<String, Table>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+type UndoLog = Arc<Mutex<Vec<UndoEntry>>>;
+/// The keys of the rows changed by a write and their previous state.
+type Changes = Vec<(String, Option<Row>)>;
+
+struct UndoEntry {
+    collection: String,
+    key: String,
+    /// The row before the change or `None` if the row didn't exist.
+    row: Option<Row>,
+}
+
+#[derive(Clone, Default)]
+struct Table {
+    rows: BTreeMap<String, Row>,
+    indexes: HashMap<String, Index>,
+    fulltext_columns: HashSet<String>,
+    last_rowid: i64,
+}
+
+/// Rows are stored both serialized, to return the exact representation that was saved, and
+/// parsed, to evaluate indexed columns.
+#[derive(Clone)]
+struct Row {
+    /// Breaks ties between rows with the same values in the sorted columns.
+    rowid: i64,
+    json: String,
+    value: Value,
+}
+
+#[derive(Clone)]
+struct Index {
+    columns: Vec<Column>,
+    unique: bool,
+}
+
+impl Table {
+    fn select(
+        &self,
+        columns: &[Column],
+        predicate: &Predicate,
+        direction: QueryDirection,
+    ) -> Vec<(&String, &Row)> {
+        let mut rows = self
+            .rows
+            .iter()
+            .map(|(key, row)| (Column::values(columns, key, row), key, row))
+            .filter(|(values, _, _)| predicate.matches(values))
+            .collect::<Vec<_>>();
+
+        rows.sort_by(|(lhs_values, _, lhs_row), (rhs_values, _, rhs_row)| {
+            SqlValue::sort_tuples(lhs_values, rhs_values).then(lhs_row.rowid.cmp(&rhs_row.rowid))
+        });
+
+        if matches!(direction, QueryDirection::Backward) {
+            rows.reverse();
+        }
+
+        rows.into_iter().map(|(_, key, row)| (key, row)).collect()
+    }
+
+    fn insert(&mut self, key: String, json: String, value: Value, changes: &mut Changes) {
+        self.last_rowid += 1;
+        let row = Row {
+            rowid: self.last_rowid,
+            json,
+            value,
+        };
+        let previous_row = self.rows.insert(key.clone(), row);
+        changes.push((key, previous_row));
+    }
+
+    fn remove(&mut self, key: &str, changes: &mut Changes) {
+        if let Some(row) = self.rows.remove(key) {
+            changes.push((key.to_string(), Some(row)));
+        }
+    }
+
+    fn remove_matching(
+        &mut self,
+        columns: &[Column],
+        predicate: &Predicate,
+        changes: &mut Changes,
+    ) {
+        let keys = self
+            .rows
+            .iter()
+            .filter(|(key, row)| predicate.matches(&Column::values(columns, key, row)))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        for key in keys {
+            self.remove(&key, changes);
+        }
+    }
+
+    /// Returns the name of the violated index and the key of the conflicting row for each row
+    /// other than `key` that has the same values as `value` in a unique index. Like in SQLite
+    /// values containing NULL never conflict.
+    fn unique_conflicts(&self, key: &str, value: &Value) -> Vec<(String, String)> {
+        let mut conflicts = vec![];
+
+        for (index_name, index) in self.indexes.iter().filter(|(_, index)| index.unique) {
+            let values = index
+                .columns
+                .iter()
+                .map(|column| column.extract(value))
+                .collect::<Vec<_>>();
+
+            if values.contains(&SqlValue::Null) {
+                continue;
+            }
+
+            for (other_key, row) in &self.rows {
+                if other_key == key {
+                    continue;
+                }
+                let other_values = Column::values(&index.columns, other_key, row);
+                if SqlValue::equal_tuples(&values, &other_values) {
+                    conflicts.push((index_name.clone(), other_key.clone()));
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    fn contains_duplicates(&self, columns: &[Column]) -> bool {
+        let mut tuples = self
+            .rows
+            .iter()
+            .map(|(key, row)| Column::values(columns, key, row))
+            .filter(|values| !values.contains(&SqlValue::Null))
+            .collect::<Vec<_>>();
+        tuples.sort_by(|lhs, rhs| SqlValue::sort_tuples(lhs, rhs));
+        tuples
+            .windows(2)
+            .any(|pair| SqlValue::equal_tuples(&pair[0], &pair[1]))
+    }
+}
+
+#[derive(Clone)]
+enum Column {
+    /// The primary key of the collection, which is stored as text.
+    Key,
+    /// A field of the stored value, like `json_extract("data", '$.{field}')`.
+    Field(String),
+}
+
+impl Column {
+    fn values(columns: &[Column], key: &str, row: &Row) -> Vec<SqlValue> {
+        columns
+            .iter()
+            .map(|column| column.value(key, row))
+            .collect()
+    }
+
+    fn value(&self, key: &str, row: &Row) -> SqlValue {
+        match self {
+            Column::Key => SqlValue::Text(key.to_string()),
+            Column::Field(_) => self.extract(&row.value),
+        }
+    }
+
+    fn extract(&self, value: &Value) -> SqlValue {
+        let Column::Field(path) = self else {
+            return SqlValue::Null;
+        };
+
+        let value = path
+            .split('.')
+            .try_fold(value, |value, field| value.get(field));
+
+        match value {
+            None | Some(Value::Null) => SqlValue::Null,
+            Some(Value::Bool(value)) => SqlValue::Integer(*value as i64),
+            Some(Value::Number(number)) => number
+                .as_i64()
+                .map(SqlValue::Integer)
+                .unwrap_or_else(|| SqlValue::Real(number.as_f64().unwrap_or_default())),
+            Some(Value::String(value)) => SqlValue::Text(value.clone()),
+            Some(value) => SqlValue::Text(value.to_string()),
+        }
+    }
+
+    /// Converts a query parameter like SQLite would when comparing it with the column. Since
+    /// the key column has TEXT affinity, numbers are compared as text there.
+    fn param(&self, key: RawKey) -> SqlValue {
+        match self {
+            Column::Key => SqlValue::Text(raw_key_to_text(key)),
+            Column::Field(_) => key.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Column::Key => write!(f, "key"),
+            Column::Field(field) => write!(f, "{field}"),
+        }
+    }
+}
+
+/// A value as seen by SQLite, which sorts NULL before numbers and numbers before text.
+#[derive(Debug, Clone, PartialEq)]
+enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+impl SqlValue {
+    /// Compares two values like the comparison operators of SQLite, i.e. returns `None` if one
+    /// of the values is NULL.
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (SqlValue::Null, _) | (_, SqlValue::Null) => None,
+            _ => Some(self.sort_order(other)),
+        }
+    }
+
+    fn sort_order(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (SqlValue::Integer(lhs), SqlValue::Integer(rhs)) => lhs.cmp(rhs),
+            (SqlValue::Text(lhs), SqlValue::Text(rhs)) => lhs.cmp(rhs),
+            (
+                SqlValue::Integer(_) | SqlValue::Real(_),
+                SqlValue::Integer(_) | SqlValue::Real(_),
+            ) => self
+                .as_f64()
+                .partial_cmp(&other.as_f64())
+                .unwrap_or(Ordering::Equal),
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+
+    fn sort_tuples(lhs: &[SqlValue], rhs: &[SqlValue]) -> Ordering {
+        zip(lhs, rhs)
+            .map(|(lhs, rhs)| lhs.sort_order(rhs))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+
+    fn equal_tuples(lhs: &[SqlValue], rhs: &[SqlValue]) -> bool {
+        zip(lhs, rhs).all(|(lhs, rhs)| lhs.compare(rhs) == Some(Ordering::Equal))
+    }
+
+    fn type_rank(&self) -> u8 {
+        match self {
+            SqlValue::Null => 0,
+            SqlValue::Integer(_) | SqlValue::Real(_) => 1,
+            SqlValue::Text(_) => 2,
+        }
+    }
+
+    fn as_f64(&self) -> f64 {
+        match self {
+            SqlValue::Integer(value) => *value as f64,
+            SqlValue::Real(value) => *value,
+            SqlValue::Null | SqlValue::Text(_) => 0.0,
+        }
+    }
+
+    fn into_text(self) -> Option<String> {
+        match self {
+            SqlValue::Null => None,
+            SqlValue::Integer(value) => Some(value.to_string()),
+            SqlValue::Real(value) => Some(value.to_string()),
+            SqlValue::Text(value) => Some(value),
+        }
+    }
+}
+
+impl From<RawKey> for SqlValue {
+    fn from(value: RawKey) -> Self {
+        match value {
+            RawKey::Integer(value) => SqlValue::Integer(value),
+            RawKey::Real(value) => SqlValue::Real(value),
+            RawKey::Text(value) => SqlValue::Text(value),
+        }
+    }
+}
+
+fn raw_key_to_text(key: RawKey) -> String {
+    match key {
+        RawKey::Integer(value) => value.to_string(),
+        RawKey::Real(value) => format!("{value:?}"),
+        RawKey::Text(value) => value,
+    }
+}
+
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// The conditions a row needs to fulfill to match a `Query`. Like the SQLite driver, range
+/// queries are evaluated column by column, not lexicographically.
+#[derive(Default)]
+struct Predicate {
+    conditions: Vec<(usize, Operator, SqlValue)>,
+}
+
+impl Predicate {
+    fn new<T: KeyTuple>(query: Query<T>, columns: &[Column]) -> Self {
+        match query {
+            Query::All => Self::default(),
+            Query::Only(values) => Self::equal(values.to_raw_keys(), columns),
+            Query::Range { start, end } => {
+                fn into_bounds<T: KeyTuple>(bound: Bound<T>) -> Option<Vec<Bound<RawKey>>> {
+                    match bound {
+                        Bound::Included(tuple) => Some(
+                            tuple
+                                .to_raw_keys()
+                                .into_iter()
+                                .map(Bound::Included)
+                                .collect(),
+                        ),
+                        Bound::Excluded(tuple) => Some(
+                            tuple
+                                .to_raw_keys()
+                                .into_iter()
+                                .map(Bound::Excluded)
+                                .collect(),
+                        ),
+                        Bound::Unbounded => None,
+                    }
+                }
+
+                let unbounded = |len: usize| -> Vec<Bound<RawKey>> {
+                    (0..len).map(|_| Bound::Unbounded).collect()
+                };
+                let (start, end) = match (into_bounds(start), into_bounds(end)) {
+                    (None, None) => (vec![], vec![]),
+                    (Some(start), None) => {
+                        let len = start.len();
+                        (start, unbounded(len))
+                    }
+                    (None, Some(end)) => (unbounded(end.len()), end),
+                    (Some(start), Some(end)) => (start, end),
+                };
+
+                assert_eq!(
+                    start.len(),
+                    end.len(),
+                    "Both bounds should have the same number of tuple fields."
+                );
+                assert!(
+                    start.is_empty() || start.len() == columns.len(),
+                    "The number of tuple fields should match the number of columns in the index"
+                );
+
+                let mut conditions = vec![];
+
+                for (idx, (start, end)) in zip(start, end).enumerate() {
+                    let column = &columns[idx];
+
+                    match (start, end) {
+                        (Bound::Included(start), Bound::Excluded(end))
+                        | (Bound::Excluded(start), Bound::Included(end))
+                            if start == end =>
+                        {
+                            conditions.push((idx, Operator::Equal, column.param(start)))
+                        }
+                        (Bound::Excluded(start), Bound::Excluded(end)) if start == end => {
+                            conditions.push((idx, Operator::NotEqual, column.param(start)))
+                        }
+                        (start, end) => {
+                            match start {
+                                Bound::Included(start) => conditions.push((
+                                    idx,
+                                    Operator::GreaterOrEqual,
+                                    column.param(start),
+                                )),
+                                Bound::Excluded(start) => {
+                                    conditions.push((idx, Operator::Greater, column.param(start)))
+                                }
+                                Bound::Unbounded => (),
+                            }
+                            match end {
+                                Bound::Included(end) => {
+                                    conditions.push((idx, Operator::LessOrEqual, column.param(end)))
+                                }
+                                Bound::Excluded(end) => {
+                                    conditions.push((idx, Operator::Less, column.param(end)))
+                                }
+                                Bound::Unbounded => (),
+                            }
+                        }
+                    }
+                }
+
+                Self { conditions }
+            }
+        }
+    }
+
+    fn equal(values: Vec<RawKey>, columns: &[Column]) -> Self {
+        Self {
+            conditions: zip(values, columns)
+                .enumerate()
+                .map(|(idx, (value, column))| (idx, Operator::Equal, column.param(value)))
+                .collect(),
+        }
+    }
+
+    fn matches(&self, values: &[SqlValue]) -> bool {
+        self.conditions.iter().all(|(idx, operator, param)| {
+            let Some(ordering) = values[*idx].compare(param) else {
+                return false;
+            };
+
+            match operator {
+                Operator::Equal => ordering.is_eq(),
+                Operator::NotEqual => ordering.is_ne(),
+                Operator::Less => ordering.is_lt(),
+                Operator::LessOrEqual => ordering.is_le(),
+                Operator::Greater => ordering.is_gt(),
+                Operator::GreaterOrEqual => ordering.is_ge(),
+            }
+        })
+    }
+}
+
+/// Splits `text` into lowercased tokens without diacritics, similar to the `unicode61`
+/// tokenizer of SQLite.
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| {
+            token
+                .chars()
+                .flat_map(char::to_lowercase)
+                .map(remove_diacritic)
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns true if `tokens` contain the tokens of `phrase` in a row, where the last token of
+/// `phrase` only needs to be a prefix, just like the `"word"*` queries of the SQLite driver.
+fn contains_phrase(tokens: &[String], phrase: &[String]) -> bool {
+    let Some((last, leading)) = phrase.split_last() else {
+        return false;
+    };
+
+    tokens.windows(phrase.len()).any(|window| {
+        zip(leading, window).all(|(lhs, rhs)| lhs == rhs) && window[leading.len()].starts_with(last)
+    })
+}
+
+fn remove_diacritic(c: char) -> char {
+    match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' => 'd',
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' => 'h',
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò'..='ö' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' => 't',
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Crème brûlée, s'il-vous-plaît!"),
+            vec!["creme", "brulee", "s", "il", "vous", "plait"]
+        );
+        assert!(tokenize(" - ").is_empty());
+    }
+
+    #[test]
+    fn test_contains_phrase() {
+        let tokens = tokenize("Lunch was great");
+        assert!(contains_phrase(&tokens, &tokenize("gre")));
+        assert!(contains_phrase(&tokens, &tokenize("lunch-wa")));
+        assert!(!contains_phrase(&tokens, &tokenize("unch")));
+        assert!(!contains_phrase(&tokens, &tokenize("lunch great")));
+    }
+
+    #[test]
+    fn test_sort_order() {
+        let mut values = vec![
+            SqlValue::Text("1".to_string()),
+            SqlValue::Real(1.5),
+            SqlValue::Null,
+            SqlValue::Integer(2),
+            SqlValue::Integer(1),
+        ];
+        values.sort_by(SqlValue::sort_order);
+
+        assert_eq!(
+            values,
+            vec![
+                SqlValue::Null,
+                SqlValue::Integer(1),
+                SqlValue::Real(1.5),
+                SqlValue::Integer(2),
+                SqlValue::Text("1".to_string()),
+            ]
+        );
+        assert_eq!(SqlValue::Null.compare(&SqlValue::Null), None);
+    }
+
+    #[test]
+    fn test_range_predicate() {
+        let columns = [
+            Column::Field("account".to_string()),
+            Column::Field("user_id".to_string()),
+        ];
+        let row = |account: &str, user_id: i64| {
+            vec![
+                SqlValue::Text(account.to_string()),
+                SqlValue::Integer(user_id),
+            ]
+        };
+
+        let predicate = Predicate::new(
+            Query::from_range(("a@prose.org", 2)..("a@prose.org", 4)),
+            &columns,
+        );
+        assert!(predicate.matches(&row("a@prose.org", 2)));
+        assert!(predicate.matches(&row("a@prose.org", 3)));
+        assert!(!predicate.matches(&row("a@prose.org", 4)));
+        assert!(!predicate.matches(&row("b@prose.org", 3)));
+
+        let predicate = Predicate::new(
+            Query::Range {
+                start: Bound::Excluded(("a@prose.org", 2)),
+                end: Bound::Excluded(("a@prose.org", 4)),
+            },
+            &columns,
+        );
+        assert!(predicate.matches(&row("b@prose.org", 3)));
+        assert!(!predicate.matches(&row("a@prose.org", 3)));
+    }
+}
@@ -16,11 +16,12 @@ use wasm_bindgen::JsValue;
 
 use crate::driver::{Driver, ReadMode, ReadOnly, ReadWrite, WriteMode};
 use crate::{
-    Collection, Database, IndexSpec, IndexedCollection, KeyTuple, KeyType, Query, QueryDirection,
-    ReadTransaction, ReadableCollection, StoreError, Transaction, UpgradeTransaction,
-    VersionChangeEvent, WritableCollection, WriteTransaction,
+    Collection, CollectionUsage, Database, IndexSpec, IndexedCollection, KeyTuple, KeyType, Query,
+    QueryDirection, ReadTransaction, ReadableCollection, StoreError, Transaction,
+    UpgradeTransaction, VersionChangeEvent, WritableCollection, WriteTransaction,
 };
 
+#[derive(Clone)]
 pub struct IndexedDBDriver {
     db_name: String,
 }
@@ -46,7 +47,19 @@ pub enum Error {
     IndexedDB(String),
 }
 
-impl StoreError for Error {}
+impl StoreError for Error {
+    fn is_schema_mismatch(&self) -> bool {
+        match self {
+            // Thrown when creating an object store or index that exists already or when deleting
+            // one that doesn't exist.
+            Error::DomException { name, .. } => {
+                name == "ConstraintError" || name == "NotFoundError"
+            }
+            Error::JSON(_) => true,
+            _ => false,
+        }
+    }
+}
 
 impl From<DomException> for Error {
     fn from(value: DomException) -> Self {
@@ -96,6 +109,12 @@ impl Driver for IndexedDBDriver {
             db: db_req.into_future().await?,
         })
     }
+
+    /// Deletes the database, since IndexedDB doesn't support renaming databases.
+    async fn reset(&self) -> Result<(), Self::Error> {
+        IdbDatabase::delete_by_name(&self.db_name)?.await?;
+        Ok(())
+    }
 }
 
 pub struct IndexedDB {
@@ -115,6 +134,10 @@ impl Database for IndexedDB {
     where
         Self: 'db;
 
+    fn schema_version(&self) -> u32 {
+        self.db.version() as u32
+    }
+
     async fn collection_names(&self) -> Result<Vec<String>, Self::Error> {
         Ok(self.db.object_store_names().collect())
     }
@@ -347,6 +370,55 @@ where
         )
         .await
     }
+
+    async fn search<Value: DeserializeOwned + Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        column: &str,
+        text: &str,
+        direction: QueryDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<Value>, Self::Error> {
+        let words = text
+            .split_whitespace()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>();
+
+        if words.is_empty() || limit == Some(0) {
+            return Ok(vec![]);
+        }
+
+        // IndexedDB doesn't support full-text indexes, so we're walking the cursor instead…
+        let matches = self
+            .get_all_filtered::<serde_json::Value, _>(query, direction, limit, |_, value| {
+                let haystack = value.get(column)?.as_str()?.to_lowercase();
+                words
+                    .iter()
+                    .all(|word| haystack.contains(word))
+                    .then_some(value)
+            })
+            .await?;
+
+        Ok(matches
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    async fn usage(&self, query: Query<impl KeyTuple>) -> Result<CollectionUsage, Self::Error> {
+        // IndexedDB doesn't expose the size of an object store, so we're measuring the
+        // serialized values instead…
+        self._fold::<serde_json::Value, _>(
+            query,
+            QueryDirection::default(),
+            CollectionUsage::default(),
+            |usage, (_, value), _stop| CollectionUsage {
+                rows: usage.rows + 1,
+                bytes: usage.bytes + value.to_string().len() as u64,
+            },
+        )
+        .await
+    }
 }
 
 impl<'tx, QuerySource: IdbQuerySource, Mode> IndexedDBCollection<'tx, QuerySource, Mode> {
@@ -430,6 +502,11 @@ impl<'tx> WritableCollection<'tx> for IndexedDBCollection<'tx, IdbObjectStore<'t
         Ok(())
     }
 
+    fn add_fulltext_index(&self, _column: &str) -> Result<(), Self::Error> {
+        // Nothing to do here, since `search` walks the cursor.
+        Ok(())
+    }
+
     async fn set<K: KeyType + ?Sized, V: Serialize + ?Sized>(
         &self,
         key: &K,
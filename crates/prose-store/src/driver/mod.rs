@@ -2,8 +2,10 @@ use crate::{Database, StoreError, UpgradeTransaction, VersionChangeEvent};
 use async_trait::async_trait;
 use prose_wasm_utils::SendUnlessWasm;
 
+pub mod in_memory;
 #[cfg(target_arch = "wasm32")]
 pub mod indexed_db;
+pub mod platform;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod sqlite;
 
@@ -12,6 +14,8 @@ pub trait WriteMode {}
 
 pub struct ReadOnly;
 pub struct ReadWrite;
+/// The mode of transactions passed to the update handler in `Driver::open`.
+pub struct Upgrade;
 
 impl ReadMode for ReadOnly {}
 impl ReadMode for ReadWrite {}
@@ -29,4 +33,8 @@ pub trait Driver: SendUnlessWasm + 'static {
         F: Fn(&VersionChangeEvent<Self::UpgradeTransaction<'_>>) -> Result<(), Self::Error>
             + Send
             + 'static;
+
+    /// Moves the database aside so that the next call to `open` starts with an empty database.
+    /// Drivers that can't move a database delete it instead.
+    async fn reset(&self) -> Result<(), Self::Error>;
 }
@@ -0,0 +1,528 @@
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use prose_wasm_utils::SendUnlessWasm;
+
+use crate::driver::in_memory::{Error as InMemoryError, InMemoryDB, InMemoryTransaction};
+#[cfg(target_arch = "wasm32")]
+use crate::driver::indexed_db::{Error as PersistentError, IndexedDBDriver as PersistentDriver};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::driver::sqlite::{Error as PersistentError, SqliteDriver as PersistentDriver};
+use crate::driver::Upgrade;
+use crate::{
+    Collection, CollectionUsage, Database, IndexSpec, IndexedCollection, KeyTuple, KeyType, Query,
+    QueryDirection, ReadTransaction, ReadableCollection, StoreError, Transaction,
+    UpgradeTransaction, VersionChangeEvent, WritableCollection, WriteTransaction,
+};
+
+use super::in_memory::InMemoryDriver;
+use super::Driver;
+
+/// The driver used by the client. It either persists the database with the driver of the platform
+/// (SQLite or IndexedDB) or keeps it in memory, for sessions that shouldn't leave any traces.
+#[derive(Clone)]
+pub enum PlatformDriver {
+    Persistent(PersistentDriver),
+    InMemory(InMemoryDriver),
+}
+
+/// The database, transactions and collections of a `PlatformDriver`, which belong to either of
+/// its drivers.
+pub enum DriverVariant<P, M> {
+    Persistent(P),
+    InMemory(M),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Persistent(#[from] PersistentError),
+
+    #[error(transparent)]
+    InMemory(#[from] InMemoryError),
+}
+
+impl StoreError for Error {
+    fn is_schema_mismatch(&self) -> bool {
+        match self {
+            Error::Persistent(error) => error.is_schema_mismatch(),
+            Error::InMemory(error) => error.is_schema_mismatch(),
+        }
+    }
+}
+
+impl Error {
+    /// The update handler of a persistent database only ever sees its transaction, so that it
+    /// can't run into errors of the in-memory driver unless it makes them up.
+    fn into_persistent(self) -> PersistentError {
+        match self {
+            Error::Persistent(error) => error,
+            #[cfg(target_arch = "wasm32")]
+            Error::InMemory(error) => PersistentError::IndexedDB(error.to_string()),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::InMemory(error) => PersistentError::Interact(error.to_string()),
+        }
+    }
+}
+
+impl PlatformDriver {
+    /// Returns a driver for the SQLite database at `path`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+        Self::Persistent(PersistentDriver::new(path))
+    }
+
+    /// Returns a driver for the IndexedDB database named `db_name`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn new(db_name: impl AsRef<str>) -> Self {
+        Self::Persistent(PersistentDriver::new(db_name))
+    }
+
+    /// Returns a driver for a database that lives in memory only (see `InMemoryDriver`).
+    pub fn in_memory() -> Self {
+        Self::InMemory(InMemoryDriver::new())
+    }
+}
+
+impl From<PersistentDriver> for PlatformDriver {
+    fn from(value: PersistentDriver) -> Self {
+        Self::Persistent(value)
+    }
+}
+
+impl From<InMemoryDriver> for PlatformDriver {
+    fn from(value: InMemoryDriver) -> Self {
+        Self::InMemory(value)
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl Driver for PlatformDriver {
+    type Error = Error;
+
+    type UpgradeTransaction<'db> = DriverVariant<
+        &'db <PersistentDriver as Driver>::UpgradeTransaction<'db>,
+        &'db InMemoryTransaction<'db, Upgrade>,
+    >;
+    type Database = DriverVariant<<PersistentDriver as Driver>::Database, InMemoryDB>;
+
+    async fn open<F>(self, version: u32, update_handler: F) -> Result<Self::Database, Self::Error>
+    where
+        F: Fn(&VersionChangeEvent<Self::UpgradeTransaction<'_>>) -> Result<(), Self::Error>
+            + Send
+            + 'static,
+    {
+        match self {
+            Self::Persistent(driver) => {
+                let db = driver
+                    .open(version, move |event| {
+                        update_handler(&VersionChangeEvent {
+                            tx: DriverVariant::Persistent(&event.tx),
+                            old_version: event.old_version,
+                            new_version: event.new_version,
+                            phantom: Default::default(),
+                        })
+                        .map_err(Error::into_persistent)
+                    })
+                    .await?;
+                Ok(DriverVariant::Persistent(db))
+            }
+            Self::InMemory(driver) => {
+                let db = driver.open_with(version, move |event| {
+                    update_handler(&VersionChangeEvent {
+                        tx: DriverVariant::InMemory(&event.tx),
+                        old_version: event.old_version,
+                        new_version: event.new_version,
+                        phantom: Default::default(),
+                    })
+                })?;
+                Ok(DriverVariant::InMemory(db))
+            }
+        }
+    }
+
+    async fn reset(&self) -> Result<(), Self::Error> {
+        match self {
+            Self::Persistent(driver) => driver.reset().await?,
+            Self::InMemory(driver) => driver.reset().await?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl<P, M> Database for DriverVariant<P, M>
+where
+    P: Database<Error = PersistentError>,
+    M: Database<Error = InMemoryError>,
+{
+    type Error = Error;
+
+    type ReadTransaction<'db>
+        = DriverVariant<P::ReadTransaction<'db>, M::ReadTransaction<'db>>
+    where
+        Self: 'db;
+    type ReadWriteTransaction<'db>
+        = DriverVariant<P::ReadWriteTransaction<'db>, M::ReadWriteTransaction<'db>>
+    where
+        Self: 'db;
+
+    fn schema_version(&self) -> u32 {
+        match self {
+            Self::Persistent(db) => db.schema_version(),
+            Self::InMemory(db) => db.schema_version(),
+        }
+    }
+
+    async fn collection_names(&self) -> Result<Vec<String>, Error> {
+        Ok(match self {
+            Self::Persistent(db) => db.collection_names().await?,
+            Self::InMemory(db) => db.collection_names().await?,
+        })
+    }
+
+    async fn transaction_for_reading(
+        &self,
+        collections: &[&str],
+    ) -> Result<Self::ReadTransaction<'_>, Error> {
+        Ok(match self {
+            Self::Persistent(db) => {
+                DriverVariant::Persistent(db.transaction_for_reading(collections).await?)
+            }
+            Self::InMemory(db) => {
+                DriverVariant::InMemory(db.transaction_for_reading(collections).await?)
+            }
+        })
+    }
+
+    async fn transaction_for_reading_and_writing(
+        &self,
+        collections: &[&str],
+    ) -> Result<Self::ReadWriteTransaction<'_>, Error> {
+        Ok(match self {
+            Self::Persistent(db) => DriverVariant::Persistent(
+                db.transaction_for_reading_and_writing(collections).await?,
+            ),
+            Self::InMemory(db) => {
+                DriverVariant::InMemory(db.transaction_for_reading_and_writing(collections).await?)
+            }
+        })
+    }
+
+    async fn vacuum(&self) -> Result<(), Error> {
+        match self {
+            Self::Persistent(db) => db.vacuum().await?,
+            Self::InMemory(db) => db.vacuum().await?,
+        }
+        Ok(())
+    }
+}
+
+impl<'db, P, M> UpgradeTransaction<'db> for DriverVariant<&'db P, &'db M>
+where
+    P: UpgradeTransaction<'db, Error = PersistentError>,
+    M: UpgradeTransaction<'db, Error = InMemoryError>,
+{
+    type Error = Error;
+    type ReadWriteTransaction<'tx>
+        = DriverVariant<P::ReadWriteTransaction<'tx>, M::ReadWriteTransaction<'tx>>
+    where
+        Self: 'tx;
+
+    fn collection_names(&self) -> Result<Vec<String>, Error> {
+        Ok(match self {
+            Self::Persistent(tx) => tx.collection_names()?,
+            Self::InMemory(tx) => tx.collection_names()?,
+        })
+    }
+
+    fn create_collection(
+        &self,
+        name: &str,
+    ) -> Result<
+        <Self::ReadWriteTransaction<'_> as WriteTransaction<'_>>::WritableCollection<'_>,
+        Error,
+    > {
+        Ok(match self {
+            Self::Persistent(tx) => DriverVariant::Persistent(tx.create_collection(name)?),
+            Self::InMemory(tx) => DriverVariant::InMemory(tx.create_collection(name)?),
+        })
+    }
+
+    fn delete_collection(&self, name: &str) -> Result<(), Error> {
+        match self {
+            Self::Persistent(tx) => tx.delete_collection(name)?,
+            Self::InMemory(tx) => tx.delete_collection(name)?,
+        }
+        Ok(())
+    }
+}
+
+impl<'db, P, M> Transaction<'db> for DriverVariant<P, M>
+where
+    P: Transaction<'db, Error = PersistentError>,
+    M: Transaction<'db, Error = InMemoryError>,
+{
+    type Error = Error;
+}
+
+impl<'db, P, M> ReadTransaction<'db> for DriverVariant<P, M>
+where
+    P: ReadTransaction<'db, Error = PersistentError>,
+    M: ReadTransaction<'db, Error = InMemoryError>,
+{
+    type ReadableCollection<'tx>
+        = DriverVariant<P::ReadableCollection<'tx>, M::ReadableCollection<'tx>>
+    where
+        Self: 'tx;
+
+    fn readable_collection(&self, name: &str) -> Result<Self::ReadableCollection<'_>, Error> {
+        Ok(match self {
+            Self::Persistent(tx) => DriverVariant::Persistent(tx.readable_collection(name)?),
+            Self::InMemory(tx) => DriverVariant::InMemory(tx.readable_collection(name)?),
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl<'db, P, M> WriteTransaction<'db> for DriverVariant<P, M>
+where
+    P: WriteTransaction<'db, Error = PersistentError>,
+    M: WriteTransaction<'db, Error = InMemoryError>,
+{
+    type WritableCollection<'tx>
+        = DriverVariant<P::WritableCollection<'tx>, M::WritableCollection<'tx>>
+    where
+        Self: 'tx;
+
+    fn writeable_collection(&self, name: &str) -> Result<Self::WritableCollection<'_>, Error> {
+        Ok(match self {
+            Self::Persistent(tx) => DriverVariant::Persistent(tx.writeable_collection(name)?),
+            Self::InMemory(tx) => DriverVariant::InMemory(tx.writeable_collection(name)?),
+        })
+    }
+
+    async fn commit(self) -> Result<(), Error> {
+        match self {
+            Self::Persistent(tx) => tx.commit().await?,
+            Self::InMemory(tx) => tx.commit().await?,
+        }
+        Ok(())
+    }
+
+    async fn rollback(self) -> Result<(), Error> {
+        match self {
+            Self::Persistent(tx) => tx.rollback().await?,
+            Self::InMemory(tx) => tx.rollback().await?,
+        }
+        Ok(())
+    }
+}
+
+impl<'tx, P, M> Collection<'tx> for DriverVariant<P, M>
+where
+    P: Collection<'tx, Error = PersistentError>,
+    M: Collection<'tx, Error = InMemoryError>,
+{
+    type Error = Error;
+}
+
+impl<'tx, P, M> IndexedCollection<'tx> for DriverVariant<P, M>
+where
+    P: IndexedCollection<'tx, Error = PersistentError>,
+    M: IndexedCollection<'tx, Error = InMemoryError>,
+{
+    type Index<'coll>
+        = DriverVariant<P::Index<'coll>, M::Index<'coll>>
+    where
+        Self: 'coll;
+
+    fn index(&self, columns: &[&str]) -> Result<Self::Index<'_>, Error> {
+        Ok(match self {
+            Self::Persistent(collection) => DriverVariant::Persistent(collection.index(columns)?),
+            Self::InMemory(collection) => DriverVariant::InMemory(collection.index(columns)?),
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl<'tx, P, M> ReadableCollection<'tx> for DriverVariant<P, M>
+where
+    P: ReadableCollection<'tx, Error = PersistentError>,
+    M: ReadableCollection<'tx, Error = InMemoryError>,
+{
+    async fn get<K: KeyTuple + ?Sized, V: DeserializeOwned>(
+        &self,
+        key: &K,
+    ) -> Result<Option<V>, Error> {
+        Ok(match self {
+            Self::Persistent(collection) => collection.get(key).await?,
+            Self::InMemory(collection) => collection.get(key).await?,
+        })
+    }
+
+    async fn contains_key<K: KeyTuple + ?Sized>(&self, key: &K) -> Result<bool, Error> {
+        Ok(match self {
+            Self::Persistent(collection) => collection.contains_key(key).await?,
+            Self::InMemory(collection) => collection.contains_key(key).await?,
+        })
+    }
+
+    async fn all_keys(&self) -> Result<Vec<String>, Error> {
+        Ok(match self {
+            Self::Persistent(collection) => collection.all_keys().await?,
+            Self::InMemory(collection) => collection.all_keys().await?,
+        })
+    }
+
+    async fn get_all<Value: DeserializeOwned + Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        direction: QueryDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<(String, Value)>, Error> {
+        Ok(match self {
+            Self::Persistent(collection) => collection.get_all(query, direction, limit).await?,
+            Self::InMemory(collection) => collection.get_all(query, direction, limit).await?,
+        })
+    }
+
+    async fn get_all_filtered<Value: DeserializeOwned + Send, T: Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        direction: QueryDirection,
+        limit: Option<usize>,
+        filter: impl FnMut(String, Value) -> Option<T> + SendUnlessWasm,
+    ) -> Result<Vec<T>, Error> {
+        Ok(match self {
+            Self::Persistent(collection) => {
+                collection
+                    .get_all_filtered(query, direction, limit, filter)
+                    .await?
+            }
+            Self::InMemory(collection) => {
+                collection
+                    .get_all_filtered(query, direction, limit, filter)
+                    .await?
+            }
+        })
+    }
+
+    async fn fold<Value: DeserializeOwned + Send, T: Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        init: T,
+        f: impl FnMut(T, (String, Value)) -> T + SendUnlessWasm,
+    ) -> Result<T, Error> {
+        Ok(match self {
+            Self::Persistent(collection) => collection.fold(query, init, f).await?,
+            Self::InMemory(collection) => collection.fold(query, init, f).await?,
+        })
+    }
+
+    async fn search<Value: DeserializeOwned + Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        column: &str,
+        text: &str,
+        direction: QueryDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<Value>, Error> {
+        Ok(match self {
+            Self::Persistent(collection) => {
+                collection
+                    .search(query, column, text, direction, limit)
+                    .await?
+            }
+            Self::InMemory(collection) => {
+                collection
+                    .search(query, column, text, direction, limit)
+                    .await?
+            }
+        })
+    }
+
+    async fn usage(&self, query: Query<impl KeyTuple>) -> Result<CollectionUsage, Error> {
+        Ok(match self {
+            Self::Persistent(collection) => collection.usage(query).await?,
+            Self::InMemory(collection) => collection.usage(query).await?,
+        })
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
+#[async_trait]
+impl<'tx, P, M> WritableCollection<'tx> for DriverVariant<P, M>
+where
+    P: WritableCollection<'tx, Error = PersistentError>,
+    M: WritableCollection<'tx, Error = InMemoryError>,
+{
+    fn add_index(&self, idx: IndexSpec) -> Result<(), Error> {
+        match self {
+            Self::Persistent(collection) => collection.add_index(idx)?,
+            Self::InMemory(collection) => collection.add_index(idx)?,
+        }
+        Ok(())
+    }
+
+    fn add_fulltext_index(&self, column: &str) -> Result<(), Error> {
+        match self {
+            Self::Persistent(collection) => collection.add_fulltext_index(column)?,
+            Self::InMemory(collection) => collection.add_fulltext_index(column)?,
+        }
+        Ok(())
+    }
+
+    async fn set<K: KeyType + ?Sized, V: Serialize + ?Sized + Send + Sync>(
+        &self,
+        key: &K,
+        value: &V,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Persistent(collection) => collection.set(key, value).await?,
+            Self::InMemory(collection) => collection.set(key, value).await?,
+        }
+        Ok(())
+    }
+
+    fn put<K: KeyType + ?Sized, V: Serialize>(&self, key: &K, value: &V) -> Result<(), Error> {
+        match self {
+            Self::Persistent(collection) => collection.put(key, value)?,
+            Self::InMemory(collection) => collection.put(key, value)?,
+        }
+        Ok(())
+    }
+
+    async fn delete<K: KeyTuple + ?Sized>(&self, key: &K) -> Result<(), Error> {
+        match self {
+            Self::Persistent(collection) => collection.delete(key).await?,
+            Self::InMemory(collection) => collection.delete(key).await?,
+        }
+        Ok(())
+    }
+
+    async fn delete_all_in_index(
+        &self,
+        columns: &[&str],
+        query: Query<impl KeyTuple>,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Persistent(collection) => collection.delete_all_in_index(columns, query).await?,
+            Self::InMemory(collection) => collection.delete_all_in_index(columns, query).await?,
+        }
+        Ok(())
+    }
+
+    fn truncate(&self) -> Result<(), Error> {
+        match self {
+            Self::Persistent(collection) => collection.truncate()?,
+            Self::InMemory(collection) => collection.truncate()?,
+        }
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use std::marker::PhantomData;
 use std::ops::Bound;
 use std::path::PathBuf;
 use std::sync::{Arc, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use deadpool_sqlite::{
@@ -19,18 +20,19 @@ use tracing::debug;
 
 use prose_wasm_utils::SendUnlessWasm;
 
-use crate::driver::{ReadMode, ReadOnly, ReadWrite, WriteMode};
+use crate::driver::{ReadMode, ReadOnly, ReadWrite, Upgrade, WriteMode};
 use crate::prelude::Error::NotMemberOfTransaction;
 use crate::{
-    Collection, Database, IndexSpec, IndexedCollection, KeyTuple, KeyType, Query, QueryDirection,
-    RawKey, ReadTransaction, ReadableCollection, StoreError, Transaction, UpgradeTransaction,
-    VersionChangeEvent, WritableCollection, WriteTransaction,
+    Collection, CollectionUsage, Database, IndexSpec, IndexedCollection, KeyTuple, KeyType, Query,
+    QueryDirection, RawKey, ReadTransaction, ReadableCollection, StoreError, Transaction,
+    UpgradeTransaction, VersionChangeEvent, WritableCollection, WriteTransaction,
 };
 
 use super::Driver;
 
 const SETTINGS_TABLE: &str = "__store_settings";
 
+#[derive(Clone)]
 pub struct SqliteDriver {
     path: PathBuf,
 }
@@ -64,11 +66,28 @@ pub enum Error {
     #[error(transparent)]
     JSON(#[from] serde_json::Error),
 
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     #[error("{0}")]
     Poison(String),
+
+    #[error("The database has version {found} but only versions up to {supported} are supported")]
+    UnsupportedVersion { found: u32, supported: u32 },
 }
 
-impl StoreError for Error {}
+impl StoreError for Error {
+    fn is_schema_mismatch(&self) -> bool {
+        match self {
+            // SQLITE_ERROR is returned for missing or already existing tables, columns or indexes.
+            Error::Sqlite(rusqlite::Error::SqliteFailure(error, _)) => {
+                error.code == rusqlite::ErrorCode::Unknown
+            }
+            Error::JSON(_) => true,
+            _ => false,
+        }
+    }
+}
 
 impl From<InteractError> for Error {
     fn from(value: InteractError) -> Self {
@@ -153,9 +172,19 @@ impl Driver for SqliteDriver {
             (current_version, sql_conn.database_description()?)
         };
 
+        // A newer version of the app might have migrated the database already, in which case we
+        // can't make any assumptions about its schema.
+        if current_version > version {
+            return Err(Error::UnsupportedVersion {
+                found: current_version,
+                supported: version,
+            });
+        }
+
         let db = SqliteDB {
             pool,
             description: description.clone(),
+            version,
         };
 
         if current_version != version {
@@ -175,7 +204,12 @@ impl Driver for SqliteDriver {
                     phantom: Default::default(),
                 };
 
-                update_handler(&event)?;
+                // The transaction would be committed when dropped, so we need to roll back
+                // explicitly to not leave the database in a partially migrated state.
+                if let Err(err) = update_handler(&event) {
+                    event.tx.rollback_()?;
+                    return Err(err);
+                }
                 event.tx
             };
 
@@ -193,11 +227,38 @@ impl Driver for SqliteDriver {
 
         Ok(db)
     }
+
+    /// Renames the database file (and its WAL files) to `<path>.broken-<timestamp>`.
+    async fn reset(&self) -> Result<(), Self::Error> {
+        // In-memory databases are discarded anyway once all connections are closed.
+        if !self.path.exists() {
+            return Ok(());
+        }
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        for suffix in ["", "-wal", "-shm"] {
+            let path = PathBuf::from(format!("{}{suffix}", self.path.display()));
+            if !path.exists() {
+                continue;
+            }
+            std::fs::rename(
+                &path,
+                format!("{}.broken-{timestamp}{suffix}", self.path.display()),
+            )?;
+        }
+
+        Ok(())
+    }
 }
 
 pub struct SqliteDB {
     pool: Pool,
     description: DatabaseDescription,
+    version: u32,
 }
 
 #[async_trait]
@@ -207,6 +268,10 @@ impl Database for SqliteDB {
     type ReadTransaction<'db> = SqliteTransaction<'db, ReadOnly>;
     type ReadWriteTransaction<'db> = SqliteTransaction<'db, ReadWrite>;
 
+    fn schema_version(&self) -> u32 {
+        self.version
+    }
+
     async fn collection_names(&self) -> Result<Vec<String>, Self::Error> {
         Ok(self.description.all_table_names())
     }
@@ -252,6 +317,12 @@ impl Database for SqliteDB {
             DropBehavior::Commit,
         )
     }
+
+    async fn vacuum(&self) -> Result<(), Self::Error> {
+        let obj = self.pool.get().await?;
+        obj.lock()?.execute_batch("VACUUM")?;
+        Ok(())
+    }
 }
 
 impl SqliteDB {
@@ -268,8 +339,6 @@ impl SqliteDB {
     }
 }
 
-pub struct Upgrade;
-
 pub struct SqliteTransaction<'db, Mode> {
     member_collections: HashSet<String>,
     obj: Arc<deadpool::managed::Object<Manager>>,
@@ -319,9 +388,15 @@ impl<'db> UpgradeTransaction<'db> for SqliteTransaction<'db, Upgrade> {
     }
 
     fn delete_collection(&self, name: &str) -> Result<(), Self::Error> {
-        self.obj
-            .lock()?
-            .execute(&format!(r#"DROP TABLE "{}""#, name), params![])?;
+        let conn = self.obj.lock()?;
+        // Full-text indexes live in their own tables which are not dropped automatically…
+        for column in self.description.fulltext_columns(name) {
+            conn.execute(
+                &format!(r#"DROP TABLE "{}""#, fulltext_table_name(name, &column)),
+                params![],
+            )?;
+        }
+        conn.execute(&format!(r#"DROP TABLE "{}""#, name), params![])?;
         self.description.remove_table(name);
         Ok(())
     }
@@ -670,6 +745,79 @@ where
         )
         .await
     }
+
+    async fn search<Value: DeserializeOwned + Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        column: &str,
+        text: &str,
+        direction: QueryDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<Value>, Self::Error> {
+        let index_name = fulltext_index_name(column);
+
+        if !self
+            .description
+            .table_contains_index(&self.name, &index_name)
+        {
+            return Err(Error::UnknownIndex {
+                collection: self.name.to_string(),
+                index: index_name,
+            });
+        }
+
+        let Some(match_expression) = fulltext_match_expression(text) else {
+            return Ok(vec![]);
+        };
+
+        if limit == Some(0) {
+            return Ok(vec![]);
+        }
+
+        let (sql, params) = query.into_search_sql(
+            &self.name,
+            &fulltext_table_name(&self.name, column),
+            self.qualified_key_columns(),
+            match_expression,
+            direction,
+            limit,
+        );
+
+        let conn = self.obj.lock()?;
+        let mut statement = conn.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(params), |row| row.get::<_, String>(0))?;
+
+        let mut values = vec![];
+        for row in rows {
+            values.push(serde_json::from_str(&row?)?);
+        }
+
+        Ok(values)
+    }
+
+    async fn usage(&self, query: Query<impl KeyTuple>) -> Result<CollectionUsage, Self::Error> {
+        let mut sql = format!(
+            r#"SELECT COUNT(*), COALESCE(SUM(LENGTH(CAST("data" AS BLOB))), 0) FROM "{}""#,
+            self.name
+        );
+        let params = match query.into_sql_predicate(self.qualified_key_columns()) {
+            Some((predicate, params)) => {
+                sql.push_str(&format!(" WHERE {predicate}"));
+                params
+            }
+            None => vec![],
+        };
+
+        let conn = self.obj.lock()?;
+        let (rows, bytes) = conn.query_row(&sql, params_from_iter(params), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+        })?;
+
+        Ok(CollectionUsage {
+            rows: rows as u64,
+            bytes: bytes as u64,
+        })
+    }
 }
 
 impl<'tx, Mode> SqliteCollection<'tx, Mode>
@@ -764,6 +912,47 @@ impl<'tx> WritableCollection<'tx> for SqliteCollection<'tx, ReadWrite> {
         Ok(())
     }
 
+    fn add_fulltext_index(&self, column: &str) -> Result<(), Self::Error> {
+        let conn = self.obj.lock()?;
+        let table_name = &self.name;
+        let fts_table_name = fulltext_table_name(table_name, column);
+
+        // The FTS table shares the rowids of the collection's table and is kept in sync via
+        // triggers. Since `INSERT OR REPLACE` doesn't fire delete triggers, replaced rows are
+        // removed before inserting their replacement.
+        conn.execute_batch(&format!(
+            r#"
+            CREATE VIRTUAL TABLE "{fts_table_name}" USING fts5(
+                "text",
+                tokenize = 'unicode61 remove_diacritics 2'
+            );
+            CREATE TRIGGER "{fts_table_name}_replace" BEFORE INSERT ON "{table_name}" BEGIN
+                DELETE FROM "{fts_table_name}" WHERE rowid IN (
+                    SELECT rowid FROM "{table_name}" WHERE "key" = new."key"
+                );
+            END;
+            CREATE TRIGGER "{fts_table_name}_insert" AFTER INSERT ON "{table_name}" BEGIN
+                INSERT INTO "{fts_table_name}" (rowid, "text")
+                VALUES (new.rowid, json_extract(new."data", '$.{column}'));
+            END;
+            CREATE TRIGGER "{fts_table_name}_update" AFTER UPDATE ON "{table_name}" BEGIN
+                DELETE FROM "{fts_table_name}" WHERE rowid = old.rowid;
+                INSERT INTO "{fts_table_name}" (rowid, "text")
+                VALUES (new.rowid, json_extract(new."data", '$.{column}'));
+            END;
+            CREATE TRIGGER "{fts_table_name}_delete" AFTER DELETE ON "{table_name}" BEGIN
+                DELETE FROM "{fts_table_name}" WHERE rowid = old.rowid;
+            END;
+            INSERT INTO "{fts_table_name}" (rowid, "text")
+            SELECT rowid, json_extract("data", '$.{column}') FROM "{table_name}";
+            "#
+        ))?;
+
+        self.description
+            .add_index(&self.name, &fulltext_index_name(column));
+        Ok(())
+    }
+
     async fn set<K: KeyType + ?Sized, V: Serialize + ?Sized + Send + Sync>(
         &self,
         key: &K,
@@ -891,6 +1080,41 @@ impl DatabaseDescription {
             .or_insert(HashSet::new())
             .insert(index_name.to_string());
     }
+
+    fn fulltext_columns(&self, table_name: &str) -> Vec<String> {
+        let map = self.tables_to_indexes_map.read();
+        let Some(indexes) = map.get(table_name) else {
+            return vec![];
+        };
+        indexes
+            .iter()
+            .filter_map(|index_name| index_name.strip_suffix("_fts"))
+            .map(ToString::to_string)
+            .collect()
+    }
+}
+
+fn fulltext_index_name(column: &str) -> String {
+    format!("{column}_fts")
+}
+
+fn fulltext_table_name(table_name: &str, column: &str) -> String {
+    format!("prose_{table_name}_{column}_fts")
+}
+
+/// Builds an FTS5 query that matches all rows containing every word of `text` as a prefix of
+/// one of their tokens. Returns `None` if `text` doesn't contain any words.
+fn fulltext_match_expression(text: &str) -> Option<String> {
+    let words = text
+        .split_whitespace()
+        .map(|word| format!(r#""{}"*"#, word.replace('"', r#""""#)))
+        .collect::<Vec<_>>();
+
+    if words.is_empty() {
+        return None;
+    }
+
+    Some(words.join(" "))
 }
 
 trait ConnectionExt {
@@ -913,11 +1137,28 @@ impl ConnectionExt for rusqlite::Connection {
 
             match type_.as_str() {
                 "table" => {
-                    if name == SETTINGS_TABLE {
+                    // Skip our settings and the tables backing full-text indexes…
+                    if name == SETTINGS_TABLE || name.starts_with("prose_") {
                         continue;
                     }
                     tables_to_indexes_map.entry(name).or_insert(HashSet::new());
                 }
+                "trigger" => {
+                    let table_name = row.get::<_, String>(2)?;
+
+                    // e.g: prose_{table_name}_field_fts_insert
+                    let Some(column) = name
+                        .strip_prefix(&format!("prose_{table_name}_"))
+                        .and_then(|name| name.strip_suffix("_fts_insert"))
+                    else {
+                        continue;
+                    };
+
+                    tables_to_indexes_map
+                        .entry(table_name)
+                        .or_insert(HashSet::new())
+                        .insert(fulltext_index_name(column));
+                }
                 "index" => {
                     if name.strip_prefix("prose_").is_none() {
                         continue;
@@ -990,6 +1231,46 @@ impl<T: KeyTuple> Query<T> {
         (sql, params)
     }
 
+    fn into_search_sql(
+        self,
+        table: &str,
+        fts_table: &str,
+        columns: &[String],
+        match_expression: String,
+        direction: QueryDirection,
+        limit: Option<usize>,
+    ) -> (String, Vec<RawKey>) {
+        let order = match direction {
+            QueryDirection::Forward => "ASC",
+            QueryDirection::Backward => "DESC",
+        };
+
+        let mut sql = format!(
+            r#"SELECT "{table}"."data" FROM "{table}" JOIN "{fts_table}" ON "{fts_table}".rowid = "{table}".rowid WHERE "{fts_table}" MATCH ?"#
+        );
+        let mut params = vec![RawKey::Text(match_expression)];
+
+        if let Some((predicate, predicate_params)) = self.into_sql_predicate(columns) {
+            sql.push_str(&format!(" AND {predicate}"));
+            params.extend(predicate_params);
+        }
+
+        sql.push_str(&format!(
+            " ORDER BY {column_order}",
+            column_order = columns
+                .iter()
+                .map(|column| format!(r#"{column} {order}"#))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+
+        if let Some(limit) = limit {
+            sql.push_str(&format!(" LIMIT {limit}"))
+        }
+
+        (sql, params)
+    }
+
     fn into_sql_predicate(self, columns: &[impl AsRef<str>]) -> Option<(String, Vec<RawKey>)> {
         let (predicates, params) = match self {
             Query::All => (vec![], vec![]),
@@ -1237,4 +1518,34 @@ mod tests {
             &query.into_sql(table, columns, Default::default(), None).0
         );
     }
+
+    #[test]
+    fn test_query_to_search_sql() {
+        let columns = &["account".to_string(), "timestamp".to_string()];
+
+        let query = Query::from_range(("a@prose.org", 2)..("a@prose.org", 3));
+        let (sql, params) = query.into_search_sql(
+            "table",
+            "prose_table_body_fts",
+            columns,
+            fulltext_match_expression("hello world").unwrap(),
+            QueryDirection::Backward,
+            Some(10),
+        );
+
+        assert_eq!(
+            r#"SELECT "table"."data" FROM "table" JOIN "prose_table_body_fts" ON "prose_table_body_fts".rowid = "table".rowid WHERE "prose_table_body_fts" MATCH ? AND account = ? AND (timestamp >= ? AND timestamp < ?) ORDER BY account DESC, timestamp DESC LIMIT 10"#,
+            &sql
+        );
+        assert_eq!(params[0], RawKey::Text(r#""hello"* "world"*"#.to_string()));
+    }
+
+    #[test]
+    fn test_fulltext_match_expression() {
+        assert_eq!(fulltext_match_expression("  "), None);
+        assert_eq!(
+            fulltext_match_expression(r#"say "hi""#),
+            Some(r#""say"* """hi"""*"#.to_string())
+        );
+    }
 }
@@ -19,7 +19,12 @@ pub mod prelude;
 mod repository;
 mod store;
 
-pub trait StoreError: Error {}
+pub trait StoreError: Error {
+    /// Returns true if the error was caused by a schema that doesn't match what a migration
+    /// expects, e.g. a collection that exists already or stored data that can't be read anymore.
+    /// Transient errors like a locked database or a full disk return false.
+    fn is_schema_mismatch(&self) -> bool;
+}
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[async_trait]
@@ -34,6 +39,9 @@ pub trait Database: SendUnlessWasm + SyncUnlessWasm {
     where
         Self: 'db;
 
+    /// Returns the version the database was opened with.
+    fn schema_version(&self) -> u32;
+
     /// Returns the name of all collections in the database
     async fn collection_names(&self) -> Result<Vec<String>, Self::Error>;
 
@@ -46,6 +54,11 @@ pub trait Database: SendUnlessWasm + SyncUnlessWasm {
         &self,
         collections: &[&str],
     ) -> Result<Self::ReadWriteTransaction<'_>, Self::Error>;
+
+    /// Gives the space of deleted items back to the file system, if supported by the driver.
+    async fn vacuum(&self) -> Result<(), Self::Error> {
+        Ok(())
+    }
 }
 
 pub struct VersionChangeEvent<'db, Tx: UpgradeTransaction<'db>> {
@@ -175,6 +188,22 @@ pub trait ReadableCollection<'tx>: Collection<'tx> {
         init: T,
         f: impl FnMut(T, (String, Value)) -> T + SendUnlessWasm,
     ) -> Result<T, Self::Error>;
+
+    /// Collects all items matching `query` whose text field `column` contains all words of
+    /// `text`. The collection must have a full-text index on `column`
+    /// (see `WritableCollection::add_fulltext_index`).
+    async fn search<Value: DeserializeOwned + Send>(
+        &self,
+        query: Query<impl KeyTuple>,
+        column: &str,
+        text: &str,
+        direction: QueryDirection,
+        limit: Option<usize>,
+    ) -> Result<Vec<Value>, Self::Error>;
+
+    /// Returns the number of items matching `query` and the size of their serialized
+    /// representation.
+    async fn usage(&self, query: Query<impl KeyTuple>) -> Result<CollectionUsage, Self::Error>;
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -182,6 +211,10 @@ pub trait ReadableCollection<'tx>: Collection<'tx> {
 pub trait WritableCollection<'tx>: Collection<'tx> {
     fn add_index(&self, idx: IndexSpec) -> Result<(), Self::Error>;
 
+    /// Adds a full-text index over the text field `column`, which allows for efficient
+    /// `ReadableCollection::search` queries.
+    fn add_fulltext_index(&self, column: &str) -> Result<(), Self::Error>;
+
     async fn set<K: KeyType + ?Sized, V: Serialize + ?Sized + Send + Sync>(
         &self,
         key: &K,
@@ -253,6 +286,23 @@ impl IndexSpecBuilder {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollectionUsage {
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+impl std::ops::Add for CollectionUsage {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            rows: self.rows + rhs.rows,
+            bytes: self.bytes + rhs.bytes,
+        }
+    }
+}
+
 #[derive(Default)]
 pub enum QueryDirection {
     #[default]
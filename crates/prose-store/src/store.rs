@@ -1,24 +1,31 @@
 use crate::{
-    driver::Driver, Database, KeyTuple, KeyType, ReadTransaction, ReadableCollection,
-    VersionChangeEvent, WritableCollection, WriteTransaction,
+    driver::Driver, Database, KeyTuple, KeyType, Query, QueryDirection, ReadTransaction,
+    ReadableCollection, StoreError, VersionChangeEvent, WritableCollection, WriteTransaction,
 };
+use parking_lot::Mutex;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::ops::Deref;
 use std::sync::Arc;
+use tracing::warn;
 
 pub struct Store<D: Driver> {
     db: Arc<D::Database>,
+    was_reset: bool,
 }
 
 impl<D: Driver> Clone for Store<D> {
     fn clone(&self) -> Self {
         Store {
             db: self.db.clone(),
+            was_reset: self.was_reset,
         }
     }
 }
 
+/// The raw items of a collection, keyed by the name of the collection.
+type CollectionItems = Vec<(String, Vec<(String, serde_json::Value)>)>;
+
 impl<D: Driver> Store<D> {
     pub async fn open<F>(driver: D, version: u32, update_handler: F) -> Result<Self, D::Error>
     where
@@ -30,8 +37,158 @@ impl<D: Driver> Store<D> {
 
         Ok(Self {
             db: Arc::new(driver.open(version, update_handler).await?),
+            was_reset: false,
         })
     }
+
+    /// Opens the database like `open`, but if `update_handler` fails because the schema of the
+    /// database doesn't match its version (see `StoreError::is_schema_mismatch`), the broken
+    /// database is moved aside (see `Driver::reset`) and an empty database is created instead.
+    /// Since `update_handler` is then called with an `old_version` of 0, it must be able to create
+    /// the database from scratch.
+    ///
+    /// The items of `preserved_collections` are copied over to the new database, as long as the
+    /// new schema still contains these collections.
+    ///
+    /// Other errors, like a database that was created by a newer version or a transient failure,
+    /// are returned as is and leave the database untouched.
+    pub async fn open_or_recover<F>(
+        driver: D,
+        version: u32,
+        preserved_collections: &[&str],
+        update_handler: F,
+    ) -> Result<Self, D::Error>
+    where
+        D: Clone,
+        F: Fn(&VersionChangeEvent<D::UpgradeTransaction<'_>>) -> Result<(), D::Error>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let update_handler = Arc::new(update_handler);
+        // The version of the database whose migration failed, if any.
+        let failed_version = Arc::new(Mutex::new(None));
+
+        let inner_update_handler = update_handler.clone();
+        let inner_failed_version = failed_version.clone();
+        let result = Self::open(driver.clone(), version, move |event| {
+            inner_update_handler(event).inspect_err(|err| {
+                if err.is_schema_mismatch() {
+                    inner_failed_version.lock().replace(event.old_version);
+                }
+            })
+        })
+        .await;
+
+        let failed_version = failed_version.lock().take();
+        let (err, failed_version) = match (result, failed_version) {
+            (Err(err), Some(failed_version)) => (err, failed_version),
+            (result, _) => return result,
+        };
+
+        warn!("Failed to migrate database from version {failed_version} to {version}. Starting over with an empty database. {err}");
+
+        let preserved_items =
+            match Self::read_collections(&driver, failed_version, preserved_collections).await {
+                Ok(items) => items,
+                Err(err) => {
+                    warn!("Could not read the collections to preserve. {err}");
+                    vec![]
+                }
+            };
+
+        driver.reset().await?;
+
+        let mut store = Self::open(driver, version, move |event| update_handler(event)).await?;
+        store.write_collections(preserved_items).await?;
+        store.was_reset = true;
+
+        Ok(store)
+    }
+
+    /// Returns true if `open_or_recover` had to start over with an empty database.
+    pub fn was_reset(&self) -> bool {
+        self.was_reset
+    }
+}
+
+impl<D: Driver> Store<D> {
+    /// Reads all items of `collections` from the database without migrating it.
+    async fn read_collections(
+        driver: &D,
+        version: u32,
+        collections: &[&str],
+    ) -> Result<CollectionItems, D::Error>
+    where
+        D: Clone,
+    {
+        if version == 0 || collections.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Opening the database with its current version doesn't call the update handler.
+        let store = Self::open(driver.clone(), version, |_| Ok(())).await?;
+
+        let existing_collections = store.db.collection_names().await?;
+        let collections = collections
+            .iter()
+            .copied()
+            .filter(|name| {
+                existing_collections
+                    .iter()
+                    .any(|existing| existing.as_str() == *name)
+            })
+            .collect::<Vec<_>>();
+
+        if collections.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let tx = store.db.transaction_for_reading(&collections).await?;
+        let mut items = vec![];
+        for name in collections {
+            let collection = tx.readable_collection(name)?;
+            items.push((
+                name.to_string(),
+                collection
+                    .get_all::<serde_json::Value>(Query::<&str>::All, QueryDirection::Forward, None)
+                    .await?,
+            ));
+        }
+
+        Ok(items)
+    }
+
+    /// Writes `items` into their collections, skipping collections that don't exist.
+    async fn write_collections(&self, items: CollectionItems) -> Result<(), D::Error> {
+        let existing_collections = self.db.collection_names().await?;
+        let items = items
+            .into_iter()
+            .filter(|(name, _)| existing_collections.contains(name))
+            .collect::<Vec<_>>();
+
+        if items.is_empty() {
+            return Ok(());
+        }
+
+        let collection_names = items
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        let tx = self
+            .db
+            .transaction_for_reading_and_writing(&collection_names)
+            .await?;
+        for (name, items) in &items {
+            let collection = tx.writeable_collection(name)?;
+            for (key, value) in items {
+                collection.put(key, value)?;
+            }
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
 }
 
 impl<D: Driver> Deref for Store<D> {
@@ -32,6 +32,7 @@ pub struct ClientBuilder {
     id_provider: Box<dyn IDProvider>,
     time_provider: Box<dyn TimeProvider>,
     event_handler: EventHandler,
+    is_stream_management_enabled: bool,
 }
 
 impl ClientBuilder {
@@ -42,6 +43,7 @@ impl ClientBuilder {
             id_provider: Box::new(UUIDProvider::new()),
             time_provider: Box::new(SystemTimeProvider::default()),
             event_handler: Box::new(|_, _| Box::pin(async {}) as PinnedFuture<_>),
+            is_stream_management_enabled: false,
         }
         // Order matters…
         .add_mod(mods::Bookmark2::default())
@@ -66,6 +68,7 @@ impl ClientBuilder {
             id_provider: self.id_provider,
             time_provider: self.time_provider,
             event_handler: self.event_handler,
+            is_stream_management_enabled: self.is_stream_management_enabled,
         }
     }
 
@@ -85,6 +88,7 @@ impl ClientBuilder {
                 let fut = handler(client, event);
                 Box::pin(async move { fut.await }) as PinnedFuture<_>
             }),
+            is_stream_management_enabled: self.is_stream_management_enabled,
         }
     }
 
@@ -98,6 +102,13 @@ impl ClientBuilder {
         self
     }
 
+    /// Enables XEP-0198 Stream Management, which allows resuming the session after a short
+    /// disconnect without losing any stanzas.
+    pub fn set_stream_management_enabled(mut self, is_enabled: bool) -> Self {
+        self.is_stream_management_enabled = is_enabled;
+        self
+    }
+
     pub fn build(self) -> Client {
         let mut mods = self.mods;
         mods.push((
@@ -113,6 +124,9 @@ impl ClientBuilder {
             connection: Default::default(),
             mods: Arc::downgrade(&mods),
             mod_futures: Default::default(),
+            stream_error: Default::default(),
+            is_stream_management_enabled: self.is_stream_management_enabled,
+            stream_management: Default::default(),
             id_provider: self.id_provider,
             time_provider: self.time_provider,
             event_handler: self.event_handler,
@@ -13,19 +13,22 @@ use std::time::{Duration, SystemTime};
 use anyhow::Result;
 use jid::FullJid;
 use minidom::Element;
-use secrecy::SecretString;
-use tracing::{error, warn};
+use secrecy::{ExposeSecret, SecretString};
+use tracing::{error, info, warn};
 
 use prose_wasm_utils::PinnedFuture;
 
 use crate::client::builder::ClientBuilder;
 use crate::client::module_context::ModuleContextInner;
-use crate::client::{Event, ModuleLookup};
-use crate::connector::{ConnectionError, ConnectionEvent};
+use crate::client::stream_management::{StreamManagement, StreamManagementAction};
+use crate::client::{Event, ModuleLookup, SessionKind};
+use crate::connector::{
+    ConnectionError, ConnectionEvent, ConnectionEventHandler, DisconnectReason,
+};
 use crate::mods::AnyModule;
 use crate::util::{ModuleFuturePoll, XMPPElement};
 use crate::Event as ClientEvent;
-use crate::{mods, RequestError};
+use crate::{mods, ns, RequestError};
 
 #[derive(Clone)]
 pub struct Client {
@@ -43,18 +46,62 @@ impl Client {
         ClientBuilder::new()
     }
 
+    /// Connects to the server. If Stream Management is enabled, the connector supports
+    /// resumption and the previous session was lost unexpectedly, tries to resume that session
+    /// first.
     pub async fn connect(
         &self,
         jid: &FullJid,
         password: SecretString,
-    ) -> Result<(), ConnectionError> {
+    ) -> Result<SessionKind, ConnectionError> {
         self.inner.clone().connect(jid, password).await
     }
 
     pub fn disconnect(&self) {
+        // A deliberate disconnect ends the session, so there's nothing to resume later.
+        self.inner.context.stream_management.lock().reset();
         self.inner.disconnect()
     }
 
+    /// Returns the outgoing stanzas which were never acknowledged by the server in a previous
+    /// session that couldn't be resumed. Each stanza is returned only once. Since the new
+    /// session is bound to a different resource, their `from` is updated to our current JID.
+    pub fn take_unacknowledged_stanzas(&self) -> Vec<Element> {
+        let jid = self.connected_jid();
+        let mut stanzas = self
+            .inner
+            .context
+            .stream_management
+            .lock()
+            .take_lost_stanzas();
+
+        if let Some(jid) = jid {
+            for stanza in stanzas.iter_mut().filter(|s| s.attr("from").is_some()) {
+                stanza.set_attr("from", jid.to_string());
+            }
+        }
+
+        stanzas
+    }
+
+    /// Returns the number of outgoing stanzas matching `predicate` which were not acknowledged
+    /// by the server (yet), including those of a previous session that couldn't be resumed.
+    pub fn count_unacknowledged_stanzas(&self, predicate: impl Fn(&Element) -> bool) -> usize {
+        self.inner
+            .context
+            .stream_management
+            .lock()
+            .unacked_stanzas()
+            .filter(|stanza| predicate(stanza))
+            .count()
+    }
+
+    /// Whether the server acknowledges the stanzas we send in the current session (XEP-0198).
+    /// If it does, `Event::MessagesAcknowledged` is dispatched for every sent message.
+    pub fn is_stream_management_active(&self) -> bool {
+        self.inner.context.stream_management.lock().is_requested()
+    }
+
     pub fn connected_jid(&self) -> Option<FullJid> {
         self.inner.context.jid.read().clone()
     }
@@ -80,26 +127,79 @@ impl ClientInner {
         self: Arc<Self>,
         jid: &FullJid,
         password: SecretString,
-    ) -> Result<(), ConnectionError> {
+    ) -> Result<SessionKind, ConnectionError> {
         self.disconnect();
 
         *self.context.jid.write() = Some(jid.clone());
+        self.context.stream_error.lock().take();
+
+        let connector = (self.context.connector_provider)();
+
+        let resumption = self
+            .context
+            .is_stream_management_enabled
+            .then(|| self.context.stream_management.lock().resumption())
+            .flatten();
+
+        let mut session = SessionKind::New;
+
+        if let Some(resumption) = resumption {
+            match connector
+                .resume(
+                    jid,
+                    SecretString::from(password.expose_secret().to_string()),
+                    &resumption,
+                    self.clone().connection_event_handler(),
+                )
+                .await
+            {
+                Ok((connection, handled_count)) => {
+                    self.context.connection.write().replace(connection);
+                    session = SessionKind::Resumed;
+
+                    let (acked_stanzas, unacked_stanzas) = self
+                        .context
+                        .stream_management
+                        .lock()
+                        .did_resume(handled_count);
+                    self.context
+                        .clone()
+                        .dispatch_acknowledged_messages(&acked_stanzas);
+
+                    info!(
+                        "Resumed session. Resending {} unacknowledged stanzas…",
+                        unacked_stanzas.len()
+                    );
+                    for stanza in unacked_stanzas {
+                        if let Err(err) = self.context.send_stanza(stanza) {
+                            warn!("Failed to resend stanza. {}", err);
+                        }
+                    }
+                }
+                Err(err) => warn!("Failed to resume session. {}", err),
+            }
+        }
 
-        let inner = self.clone();
-
-        let connection = (self.context.connector_provider)()
-            .connect(
-                jid,
-                password,
-                Box::new(move |_, event| {
-                    let inner = inner.clone();
-
-                    Box::pin(async move { inner.handle_event(event).await }) as PinnedFuture<_>
-                }),
-            )
-            .await?;
-
-        self.context.connection.write().replace(connection);
+        if session == SessionKind::New {
+            let connection = connector
+                .connect(jid, password, self.clone().connection_event_handler())
+                .await?;
+            let supports_stream_management = connection.supports_stream_management();
+            self.context.connection.write().replace(connection);
+
+            let enable = {
+                let mut stream_management = self.context.stream_management.lock();
+                stream_management.start_stream();
+                (self.context.is_stream_management_enabled && supports_stream_management)
+                    .then(|| stream_management.enable(connector.supports_resumption()))
+            };
+
+            if let Some(enable) = enable {
+                if let Err(err) = self.context.send_stanza(enable) {
+                    warn!("Failed to enable stream management. {}", err);
+                }
+            }
+        }
 
         for (_, m) in self.mods.iter() {
             if let Err(err) = m.read().handle_connect() {
@@ -109,9 +209,17 @@ impl ClientInner {
 
         self.context
             .clone()
-            .schedule_event(ClientEvent::Client(Event::Connected));
+            .schedule_event(ClientEvent::Client(Event::Connected { session }));
 
-        Ok(())
+        Ok(session)
+    }
+
+    fn connection_event_handler(self: Arc<Self>) -> ConnectionEventHandler {
+        Box::new(move |_, event| {
+            let inner = self.clone();
+
+            Box::pin(async move { inner.handle_event(event).await }) as PinnedFuture<_>
+        })
     }
 
     fn disconnect(&self) {
@@ -130,15 +238,58 @@ impl ClientInner {
         match event {
             ConnectionEvent::Disconnected { error } => {
                 Self::cancel_pending_futures(&self.context);
+
+                // A stream error explains the disconnect better than whatever error the
+                // connection reports after the server closed the stream.
+                let error = match self.context.stream_error.lock().take() {
+                    Some(reason) => Some(ConnectionError::StreamError { reason }),
+                    None => error,
+                };
+
                 self.context
                     .clone()
                     .schedule_event(ClientEvent::Client(Event::Disconnected { error }))
             }
+            ConnectionEvent::Stanza(stanza) if stanza.is("error", ns::STREAM) => {
+                match DisconnectReason::try_from(&stanza) {
+                    Ok(reason) => {
+                        warn!("Received stream error {:?}", reason);
+                        self.context.stream_error.lock().replace(reason);
+                    }
+                    Err(err) => error!("Failed to parse stream error. {}", err),
+                }
+            }
+            ConnectionEvent::Stanza(stanza) if stanza.has_ns(ns::SM) => {
+                let action = self
+                    .context
+                    .stream_management
+                    .lock()
+                    .handle_element(&stanza);
+
+                match action {
+                    StreamManagementAction::None => (),
+                    StreamManagementAction::Send(element) => {
+                        if let Err(err) = self.context.send_stanza(element) {
+                            warn!("Failed to answer stream management request. {}", err);
+                        }
+                    }
+                    StreamManagementAction::Acknowledged(stanzas) => self
+                        .context
+                        .clone()
+                        .dispatch_acknowledged_messages(&stanzas),
+                }
+            }
             ConnectionEvent::Stanza(stanza) => {
+                self.context
+                    .stream_management
+                    .lock()
+                    .did_receive_stanza(&stanza);
                 Self::handle_stanza(&self.context, &self.mods, stanza)
             }
             ConnectionEvent::TimeoutTimer => Self::purge_expired_futures(&self.context),
             ConnectionEvent::PingTimer => {
+                self.request_stream_management_ack();
+
                 let ping = self.get_mod::<mods::Ping>();
                 match ping.send_ping_to_server().await {
                     Ok(_) => (),
@@ -151,6 +302,21 @@ impl ClientInner {
         }
     }
 
+    fn request_stream_management_ack(&self) {
+        let should_request_ack = {
+            let stream_management = self.context.stream_management.lock();
+            stream_management.is_enabled() && stream_management.has_unacked_stanzas()
+        };
+
+        if !should_request_ack {
+            return;
+        }
+
+        if let Err(err) = self.context.send_stanza(StreamManagement::ack_request()) {
+            warn!("Failed to request stream management ack. {}", err);
+        }
+    }
+
     fn handle_stanza(ctx: &ModuleContextInner, mods: &ModuleLookup, stanza: Element) {
         let element = match XMPPElement::try_from_element(stanza) {
             Ok(None) => return,
@@ -4,6 +4,7 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use std::any::TypeId;
+use std::time::Duration;
 
 use parking_lot::RwLock;
 
@@ -15,11 +16,13 @@ use prose_wasm_utils::PinnedFuture;
 use crate::connector::ConnectionError;
 use crate::connector::Connector;
 use crate::mods::AnyModule;
+use crate::stanza::message;
 use crate::Event as ClientEvent;
 
 mod builder;
 mod client;
 mod module_context;
+mod stream_management;
 
 #[cfg(target_arch = "wasm32")]
 pub type EventHandler = Box<dyn Fn(Client, ClientEvent) -> PinnedFuture<()>>;
@@ -33,9 +36,35 @@ pub type ConnectorProvider = Box<dyn Fn() -> Box<dyn Connector>>;
 #[cfg(not(target_arch = "wasm32"))]
 pub type ConnectorProvider = Box<dyn Fn() -> Box<dyn Connector> + Send + Sync>;
 
+/// Describes how a session was established by `Client::connect`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SessionKind {
+    /// A new session was established, i.e. the server doesn't know anything about our previous
+    /// session (presence, joined rooms, …).
+    #[default]
+    New,
+    /// The previous session was resumed via XEP-0198 Stream Management. No stanzas were lost.
+    Resumed,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Event {
-    Connected,
-    Disconnected { error: Option<ConnectionError> },
+    Connected {
+        session: SessionKind,
+    },
+    Disconnected {
+        error: Option<ConnectionError>,
+    },
     PingTimer,
+    /// The server answered our ping after `round_trip_time`.
+    PingAnswered {
+        round_trip_time: Duration,
+    },
+    /// The server didn't answer our ping in time. The connection is closed if the following ping
+    /// times out as well.
+    PingTimeout,
+    /// The server acknowledged the receipt of the messages with `ids` (XEP-0198).
+    MessagesAcknowledged {
+        ids: Vec<message::Id>,
+    },
 }
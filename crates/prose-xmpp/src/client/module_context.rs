@@ -8,6 +8,7 @@ use std::sync::{Arc, Weak};
 use std::time::SystemTime;
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use jid::{BareJid, DomainPart, FullJid, NodePart, ResourcePart};
 use minidom::Element;
 use parking_lot::{Mutex, RwLock};
@@ -18,10 +19,13 @@ use xmpp_parsers::pubsub;
 
 use prose_wasm_utils::PinnedFuture;
 
+use crate::client;
 use crate::client::builder::UndefinedConnector;
+use crate::client::stream_management::StreamManagement;
 use crate::client::{ConnectorProvider, EventHandler, ModuleLookup};
-use crate::connector::Connection;
+use crate::connector::{Connection, DisconnectReason};
 use crate::deps::{IDProvider, SystemTimeProvider, TimeProvider, UUIDProvider};
+use crate::stanza::message;
 use crate::util::{ModuleFutureState, PubSubQuery, RequestError, RequestFuture};
 use crate::{ns, Event};
 
@@ -136,6 +140,10 @@ impl ModuleContext {
         self.inner.id_provider.new_id()
     }
 
+    pub(crate) fn now(&self) -> DateTime<Utc> {
+        self.inner.time_provider.now()
+    }
+
     pub(crate) fn schedule_event(&self, event: Event) {
         self.inner.clone().schedule_event(event)
     }
@@ -152,6 +160,11 @@ pub(super) struct ModuleContextInner {
     pub event_handler: EventHandler,
     pub mods: Weak<ModuleLookup>,
     pub mod_futures: Mutex<Vec<ModFutureStateEntry>>,
+    /// The stream error received from the server before it closes the connection.
+    pub stream_error: Mutex<Option<DisconnectReason>>,
+    /// Whether XEP-0198 Stream Management should be enabled after connecting.
+    pub is_stream_management_enabled: bool,
+    pub stream_management: Mutex<StreamManagement>,
     pub id_provider: Box<dyn IDProvider>,
     pub time_provider: Box<dyn TimeProvider>,
 }
@@ -161,10 +174,45 @@ impl ModuleContextInner {
         let Some(conn) = &*self.connection.read() else {
             return Err(RequestError::Disconnected);
         };
-        conn.send_stanza(stanza.into())
+        let stanza = stanza.into();
+        let should_request_ack = {
+            let mut stream_management = self.stream_management.lock();
+            // Keep a copy of the stanza until the server acknowledged it…
+            stream_management.did_send_stanza(&stanza);
+            // …and ask for the acknowledgement of messages right away, so that we know early
+            // whether they were received.
+            stream_management.is_requested() && stanza.name() == "message"
+        };
+
+        conn.send_stanza(stanza)
             .map_err(|err| RequestError::Generic {
                 msg: err.to_string(),
-            })
+            })?;
+
+        if should_request_ack {
+            conn.send_stanza(StreamManagement::ack_request())
+                .map_err(|err| RequestError::Generic {
+                    msg: err.to_string(),
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Dispatches `client::Event::MessagesAcknowledged` for the messages in `stanzas`.
+    pub(crate) fn dispatch_acknowledged_messages(self: Arc<Self>, stanzas: &[Element]) {
+        let ids = stanzas
+            .iter()
+            .filter(|stanza| stanza.name() == "message")
+            .filter_map(|stanza| stanza.attr("id"))
+            .map(|id| message::Id::from(id.to_string()))
+            .collect::<Vec<_>>();
+
+        if ids.is_empty() {
+            return;
+        }
+
+        self.schedule_event(Event::Client(client::Event::MessagesAcknowledged { ids }));
     }
 
     #[cfg(any(not(feature = "test"), target_arch = "wasm32"))]
@@ -213,6 +261,9 @@ impl Default for ModuleContext {
                 event_handler: Box::new(|_, _| Box::pin(async {}) as PinnedFuture<_>),
                 mods: Default::default(),
                 mod_futures: Default::default(),
+                stream_error: Default::default(),
+                is_stream_management_enabled: false,
+                stream_management: Default::default(),
                 id_provider: Box::new(UUIDProvider::new()),
                 time_provider: Box::new(SystemTimeProvider::default()),
             }),
@@ -0,0 +1,340 @@
+// prose-core-client/prose-xmpp
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::collections::VecDeque;
+
+use minidom::Element;
+use tracing::warn;
+
+use crate::connector::SessionResumption;
+use crate::ns;
+
+/// Keeps track of the XEP-0198 Stream Management state of a session, i.e. counts handled
+/// incoming stanzas and keeps the outgoing stanzas which were not acknowledged by the server yet.
+#[derive(Debug, Default)]
+pub(super) struct StreamManagement {
+    /// Whether we've sent `<enable/>` for the current stream.
+    is_requested: bool,
+    /// Whether the server confirmed with `<enabled/>`.
+    is_enabled: bool,
+    resumption: Option<Resumption>,
+    inbound_count: u32,
+    acked_count: u32,
+    unacked_stanzas: VecDeque<Element>,
+    /// Stanzas which were never acknowledged by a session that could not be resumed.
+    lost_stanzas: Vec<Element>,
+}
+
+#[derive(Debug, Clone)]
+struct Resumption {
+    id: String,
+    location: Option<String>,
+}
+
+/// What to do after a Stream Management element (a "nonza") was received.
+#[derive(Debug, PartialEq)]
+pub(super) enum StreamManagementAction {
+    None,
+    Send(Element),
+    /// The server acknowledged the receipt of these stanzas.
+    Acknowledged(Vec<Element>),
+}
+
+impl StreamManagement {
+    /// Prepares for a new stream. Stanzas which were not acknowledged in the previous stream are
+    /// moved aside so that they can be collected via `take_lost_stanzas`. Lost stanzas of earlier
+    /// streams are discarded since they're outdated by now.
+    pub fn start_stream(&mut self) {
+        self.lost_stanzas = self.unacked_stanzas.drain(..).collect();
+        self.is_requested = false;
+        self.is_enabled = false;
+        self.resumption = None;
+        self.inbound_count = 0;
+        self.acked_count = 0;
+    }
+
+    /// Returns the `<enable/>` element to send. The server is asked to keep our session around
+    /// after the connection was lost only if we're able to `resume` it.
+    pub fn enable(&mut self, resume: bool) -> Element {
+        self.is_requested = true;
+
+        let enable = Element::builder("enable", ns::SM);
+        if resume {
+            enable.attr("resume", "true").build()
+        } else {
+            enable.build()
+        }
+    }
+
+    /// Forgets everything about the current session, e.g. after a deliberate disconnect.
+    pub fn reset(&mut self) {
+        *self = Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled
+    }
+
+    /// Whether the stanzas we send are tracked to be acknowledged by the server, i.e. we've sent
+    /// `<enable/>` for the current stream and the server didn't refuse.
+    pub fn is_requested(&self) -> bool {
+        self.is_requested
+    }
+
+    /// Returns the information needed to resume the session if the server allowed resumption.
+    pub fn resumption(&self) -> Option<SessionResumption> {
+        if !self.is_enabled {
+            return None;
+        }
+        let resumption = self.resumption.as_ref()?;
+
+        Some(SessionResumption {
+            id: resumption.id.clone(),
+            location: resumption.location.clone(),
+            handled_count: self.inbound_count,
+        })
+    }
+
+    pub fn has_unacked_stanzas(&self) -> bool {
+        !self.unacked_stanzas.is_empty()
+    }
+
+    /// Returns the outgoing stanzas which were not acknowledged by the server, including those
+    /// of a previous session that could not be resumed.
+    pub fn unacked_stanzas(&self) -> impl Iterator<Item = &Element> {
+        self.lost_stanzas.iter().chain(self.unacked_stanzas.iter())
+    }
+
+    pub fn take_lost_stanzas(&mut self) -> Vec<Element> {
+        std::mem::take(&mut self.lost_stanzas)
+    }
+
+    pub fn did_receive_stanza(&mut self, stanza: &Element) {
+        if self.is_enabled && is_stanza(stanza) {
+            self.inbound_count = self.inbound_count.wrapping_add(1);
+        }
+    }
+
+    pub fn did_send_stanza(&mut self, stanza: &Element) {
+        if self.is_requested && is_stanza(stanza) {
+            self.unacked_stanzas.push_back(stanza.clone());
+        }
+    }
+
+    /// Handles an element in the `urn:xmpp:sm:3` namespace.
+    pub fn handle_element(&mut self, element: &Element) -> StreamManagementAction {
+        match element.name() {
+            "enabled" => {
+                self.is_enabled = true;
+                self.resumption = element
+                    .attr("id")
+                    .filter(|_| matches!(element.attr("resume"), Some("true") | Some("1")))
+                    .map(|id| Resumption {
+                        id: id.to_string(),
+                        location: element.attr("location").map(ToString::to_string),
+                    });
+            }
+            "failed" => {
+                warn!("Server failed to enable stream management.");
+                self.is_requested = false;
+                self.is_enabled = false;
+                self.resumption = None;
+                self.lost_stanzas.extend(self.unacked_stanzas.drain(..));
+            }
+            "r" => {
+                return StreamManagementAction::Send(
+                    Element::builder("a", ns::SM)
+                        .attr("h", self.inbound_count.to_string())
+                        .build(),
+                )
+            }
+            "a" => match element.attr("h").and_then(|h| h.parse::<u32>().ok()) {
+                Some(h) => return StreamManagementAction::Acknowledged(self.acknowledge(h)),
+                None => warn!("Received stream management ack without a valid 'h' attribute."),
+            },
+            _ => (),
+        }
+        StreamManagementAction::None
+    }
+
+    /// Handles a successful resumption where the server reported to have handled `h` of our
+    /// stanzas. Returns the stanzas that were acknowledged thereby and those that need to be sent
+    /// again.
+    pub fn did_resume(&mut self, h: u32) -> (Vec<Element>, Vec<Element>) {
+        let acknowledged_stanzas = self.acknowledge(h);
+        // The retransmitted stanzas are counted (and queued) again once they're sent…
+        (
+            acknowledged_stanzas,
+            self.unacked_stanzas.drain(..).collect(),
+        )
+    }
+
+    /// Builds an `<r/>` element to request an acknowledgement from the server.
+    pub fn ack_request() -> Element {
+        Element::builder("r", ns::SM).build()
+    }
+
+    fn acknowledge(&mut self, h: u32) -> Vec<Element> {
+        let newly_acked = h.wrapping_sub(self.acked_count) as usize;
+
+        if newly_acked > self.unacked_stanzas.len() {
+            warn!(
+                "Server acknowledged more stanzas ({}) than we've sent ({}).",
+                newly_acked,
+                self.unacked_stanzas.len()
+            );
+        }
+
+        let newly_acked = newly_acked.min(self.unacked_stanzas.len());
+        self.acked_count = h;
+        self.unacked_stanzas.drain(..newly_acked).collect()
+    }
+}
+
+fn is_stanza(element: &Element) -> bool {
+    element.ns() == ns::JABBER_CLIENT && ["message", "presence", "iq"].contains(&element.name())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn message(id: &str) -> Element {
+        Element::builder("message", ns::JABBER_CLIENT)
+            .attr("id", id)
+            .build()
+    }
+
+    fn enabled_sm() -> StreamManagement {
+        let mut sm = StreamManagement::default();
+        sm.start_stream();
+        sm.enable(true);
+        sm.handle_element(
+            &Element::from_str(r#"<enabled xmlns="urn:xmpp:sm:3" id="session-1" resume="true"/>"#)
+                .unwrap(),
+        );
+        sm
+    }
+
+    #[test]
+    fn test_acks_handled_stanzas() {
+        let mut sm = enabled_sm();
+
+        sm.did_receive_stanza(&message("1"));
+        sm.did_receive_stanza(&message("2"));
+        sm.did_receive_stanza(&Element::builder("a", ns::SM).attr("h", "0").build());
+
+        assert_eq!(
+            sm.handle_element(&StreamManagement::ack_request()),
+            StreamManagementAction::Send(Element::builder("a", ns::SM).attr("h", "2").build())
+        );
+    }
+
+    #[test]
+    fn test_drops_acknowledged_stanzas() {
+        let mut sm = enabled_sm();
+
+        sm.did_send_stanza(&message("1"));
+        sm.did_send_stanza(&message("2"));
+        sm.did_send_stanza(&message("3"));
+
+        assert_eq!(
+            sm.handle_element(&Element::builder("a", ns::SM).attr("h", "1").build()),
+            StreamManagementAction::Acknowledged(vec![message("1")])
+        );
+        assert_eq!(
+            sm.handle_element(&Element::builder("a", ns::SM).attr("h", "2").build()),
+            StreamManagementAction::Acknowledged(vec![message("2")])
+        );
+        assert!(sm.has_unacked_stanzas());
+
+        assert_eq!(sm.did_resume(2), (vec![], vec![message("3")]));
+        assert!(!sm.has_unacked_stanzas());
+    }
+
+    #[test]
+    fn test_acknowledges_stanzas_on_resumption() {
+        let mut sm = enabled_sm();
+
+        sm.did_send_stanza(&message("1"));
+        sm.did_send_stanza(&message("2"));
+
+        assert_eq!(sm.did_resume(1), (vec![message("1")], vec![message("2")]));
+    }
+
+    #[test]
+    fn test_provides_resumption() {
+        let mut sm = enabled_sm();
+        sm.did_receive_stanza(&message("1"));
+
+        assert_eq!(
+            sm.resumption(),
+            Some(SessionResumption {
+                id: "session-1".to_string(),
+                location: None,
+                handled_count: 1,
+            })
+        );
+
+        sm.reset();
+        assert_eq!(sm.resumption(), None);
+    }
+
+    #[test]
+    fn test_keeps_unacked_stanzas_of_lost_session() {
+        let mut sm = enabled_sm();
+
+        sm.did_send_stanza(&message("1"));
+        sm.did_send_stanza(&message("2"));
+        sm.handle_element(&Element::builder("a", ns::SM).attr("h", "1").build());
+
+        sm.start_stream();
+        sm.enable(true);
+
+        sm.did_send_stanza(&message("3"));
+        assert_eq!(
+            sm.unacked_stanzas().collect::<Vec<_>>(),
+            vec![&message("2"), &message("3")]
+        );
+
+        assert_eq!(sm.take_lost_stanzas(), vec![message("2")]);
+        assert!(sm.take_lost_stanzas().is_empty());
+    }
+
+    #[test]
+    fn test_requests_resumption_only_if_supported() {
+        let mut sm = StreamManagement::default();
+        sm.start_stream();
+
+        assert_eq!(sm.enable(false), Element::builder("enable", ns::SM).build());
+        assert_eq!(
+            sm.enable(true),
+            Element::builder("enable", ns::SM)
+                .attr("resume", "true")
+                .build()
+        );
+
+        sm.handle_element(
+            &Element::from_str(r#"<enabled xmlns="urn:xmpp:sm:3" id="session-1"/>"#).unwrap(),
+        );
+        assert!(sm.is_enabled());
+        assert_eq!(sm.resumption(), None);
+    }
+
+    #[test]
+    fn test_keeps_unacked_stanzas_when_not_enabling_again() {
+        let mut sm = enabled_sm();
+        sm.did_send_stanza(&message("1"));
+
+        // The next stream doesn't support Stream Management…
+        sm.start_stream();
+        sm.did_send_stanza(&message("2"));
+
+        assert!(!sm.has_unacked_stanzas());
+        assert_eq!(sm.take_lost_stanzas(), vec![message("1")]);
+    }
+}
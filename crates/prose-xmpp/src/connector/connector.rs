@@ -3,6 +3,8 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::time::Duration;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use jid::FullJid;
@@ -16,10 +18,141 @@ pub enum ConnectionError {
     TimedOut,
     #[error("Invalid credentials")]
     InvalidCredentials,
+    /// The credentials were accepted but the server refused the login.
+    #[error("Access denied: {reason:?}")]
+    Forbidden { reason: ForbiddenReason },
+    #[error("TLS error: {msg}")]
+    TlsError { msg: String },
+    /// The server could not be reached, e.g. because the connection was refused.
+    #[error("Server unreachable: {msg}")]
+    ServerUnreachable { msg: String },
+    #[error("Stream error: {reason:?}")]
+    StreamError { reason: DisconnectReason },
     #[error("{msg:?}")]
     Generic { msg: String },
 }
 
+impl ConnectionError {
+    /// Maps the defined condition of a SASL failure (RFC 6120, Section 6.5) to a
+    /// `ConnectionError`.
+    pub fn from_sasl_condition(condition: &str) -> Self {
+        match condition {
+            "not-authorized" => Self::InvalidCredentials,
+            "account-disabled" => Self::Forbidden {
+                reason: ForbiddenReason::AccountDisabled,
+            },
+            "credentials-expired" => Self::Forbidden {
+                reason: ForbiddenReason::CredentialsExpired,
+            },
+            _ => Self::Generic {
+                msg: format!("Authentication failed ({condition})."),
+            },
+        }
+    }
+
+    /// Maps a transport-level IO error to a `ConnectionError`.
+    pub fn from_io_error(error: &std::io::Error) -> Self {
+        use std::io::ErrorKind;
+
+        match error.kind() {
+            ErrorKind::TimedOut => Self::TimedOut,
+            ErrorKind::ConnectionRefused
+            | ErrorKind::ConnectionReset
+            | ErrorKind::ConnectionAborted
+            | ErrorKind::NotConnected
+            | ErrorKind::AddrNotAvailable => Self::ServerUnreachable {
+                msg: error.to_string(),
+            },
+            _ => Self::Generic {
+                msg: error.to_string(),
+            },
+        }
+    }
+
+    /// Returns the reason for the disconnect if the server closed the stream with a stream error.
+    pub fn disconnect_reason(&self) -> Option<&DisconnectReason> {
+        match self {
+            Self::StreamError { reason } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+/// The reason the server refused the login of an account whose credentials were accepted
+/// (RFC 6120, Section 6.5).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ForbiddenReason {
+    /// The account was temporarily disabled (`<account-disabled/>`).
+    AccountDisabled,
+    /// The credentials have expired and must be renewed (`<credentials-expired/>`).
+    CredentialsExpired,
+}
+
+/// The delay after which a client should try to reconnect when the server is shutting down.
+const SYSTEM_SHUTDOWN_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+/// The delay after which a client should try to reconnect after any other stream error.
+const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// The condition of a stream error sent by the server (RFC 6120, Section 4.9.3).
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisconnectReason {
+    /// Another session with the same resource took over (`<conflict/>`).
+    Conflict,
+    /// The server is being shut down (`<system-shutdown/>`).
+    SystemShutdown,
+    /// The client violated a local service policy (`<policy-violation/>`).
+    PolicyViolation,
+    /// Any other stream error condition.
+    Other { condition: String },
+}
+
+impl DisconnectReason {
+    pub fn from_condition(condition: &str) -> Self {
+        match condition {
+            "conflict" => Self::Conflict,
+            "system-shutdown" => Self::SystemShutdown,
+            "policy-violation" => Self::PolicyViolation,
+            _ => Self::Other {
+                condition: condition.to_string(),
+            },
+        }
+    }
+
+    /// Returns the delay after which the client should try to reconnect or `None` if it should
+    /// stay disconnected.
+    pub fn reconnect_delay(&self) -> Option<Duration> {
+        match self {
+            // Another session took over, reconnecting would just kick that one out again.
+            Self::Conflict => None,
+            Self::PolicyViolation => None,
+            Self::SystemShutdown => Some(SYSTEM_SHUTDOWN_RECONNECT_DELAY),
+            Self::Other { .. } => Some(DEFAULT_RECONNECT_DELAY),
+        }
+    }
+
+    pub fn should_reconnect(&self) -> bool {
+        self.reconnect_delay().is_some()
+    }
+}
+
+impl TryFrom<&Element> for DisconnectReason {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &Element) -> Result<Self> {
+        if !value.is("error", crate::ns::STREAM) {
+            anyhow::bail!("Element is not a stream error.")
+        }
+
+        let condition = value
+            .children()
+            .find(|child| child.has_ns(crate::ns::XMPP_STREAMS) && child.name() != "text")
+            .map(|child| child.name())
+            .unwrap_or("undefined-condition");
+
+        Ok(Self::from_condition(condition))
+    }
+}
+
 #[cfg(target_arch = "wasm32")]
 pub type ConnectionEventHandler =
     Box<dyn Fn(Box<dyn Connection>, ConnectionEvent) -> PinnedFuture<()>>;
@@ -27,6 +160,17 @@ pub type ConnectionEventHandler =
 pub type ConnectionEventHandler =
     Box<dyn Fn(Box<dyn Connection>, ConnectionEvent) -> PinnedFuture<()> + Send + Sync>;
 
+/// The information needed to resume a XEP-0198 Stream Management session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionResumption {
+    /// The id of the session to resume (`previd`).
+    pub id: String,
+    /// The preferred location the server wants us to reconnect to.
+    pub location: Option<String>,
+    /// The number of stanzas we've handled in the previous session (`h`).
+    pub handled_count: u32,
+}
+
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
 #[cfg_attr(not(target_arch = "wasm32"), async_trait)]
 pub trait Connector: SendUnlessWasm + SyncUnlessWasm {
@@ -36,6 +180,29 @@ pub trait Connector: SendUnlessWasm + SyncUnlessWasm {
         password: SecretString,
         event_handler: ConnectionEventHandler,
     ) -> Result<Box<dyn Connection>, ConnectionError>;
+
+    /// Whether `resume` is implemented. If it isn't, we don't ask the server to keep our session
+    /// around after the connection was lost.
+    fn supports_resumption(&self) -> bool {
+        false
+    }
+
+    /// Authenticates and sends `<resume/>` instead of binding a new resource. Returns the
+    /// connection together with the number of our stanzas the server has handled (`h`).
+    ///
+    /// Resumption has to happen before resource binding, which not all transports allow us to
+    /// hook into. Connectors that don't support it fail and we'll fall back to `connect`.
+    async fn resume(
+        &self,
+        _jid: &FullJid,
+        _password: SecretString,
+        _resumption: &SessionResumption,
+        _event_handler: ConnectionEventHandler,
+    ) -> Result<(Box<dyn Connection>, u32), ConnectionError> {
+        Err(ConnectionError::Generic {
+            msg: "The connector does not support stream resumption.".to_string(),
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -50,10 +217,72 @@ pub enum ConnectionEvent {
 pub trait Connection {
     fn send_stanza(&self, stanza: Element) -> Result<()>;
     fn disconnect(&self);
+
+    /// Whether the server advertised XEP-0198 Stream Management in its stream features.
+    /// Sending `<enable/>` to a server that doesn't support it would end the stream.
+    fn supports_stream_management(&self) -> bool {
+        false
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 pub trait Connection: Send + Sync {
     fn send_stanza(&self, stanza: Element) -> Result<()>;
     fn disconnect(&self);
+
+    /// Whether the server advertised XEP-0198 Stream Management in its stream features.
+    /// Sending `<enable/>` to a server that doesn't support it would end the stream.
+    fn supports_stream_management(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn test_maps_sasl_conditions() {
+        assert_eq!(
+            ConnectionError::from_sasl_condition("not-authorized"),
+            ConnectionError::InvalidCredentials
+        );
+        assert_eq!(
+            ConnectionError::from_sasl_condition("account-disabled"),
+            ConnectionError::Forbidden {
+                reason: ForbiddenReason::AccountDisabled
+            }
+        );
+        assert_eq!(
+            ConnectionError::from_sasl_condition("credentials-expired"),
+            ConnectionError::Forbidden {
+                reason: ForbiddenReason::CredentialsExpired
+            }
+        );
+        assert_eq!(
+            ConnectionError::from_sasl_condition("malformed-request"),
+            ConnectionError::Generic {
+                msg: "Authentication failed (malformed-request).".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_maps_io_errors() {
+        assert_eq!(
+            ConnectionError::from_io_error(&io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "Connection refused"
+            )),
+            ConnectionError::ServerUnreachable {
+                msg: "Connection refused".to_string()
+            }
+        );
+        assert_eq!(
+            ConnectionError::from_io_error(&io::Error::from(io::ErrorKind::TimedOut)),
+            ConnectionError::TimedOut
+        );
+    }
 }
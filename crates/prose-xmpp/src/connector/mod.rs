@@ -5,6 +5,7 @@
 
 pub use connector::{
     Connection, ConnectionError, ConnectionEvent, ConnectionEventHandler, Connector,
+    DisconnectReason, ForbiddenReason, SessionResumption,
 };
 pub use proxy_connector::{ProxyConnector, ProxyTransformer};
 
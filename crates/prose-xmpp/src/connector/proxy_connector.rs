@@ -13,7 +13,7 @@ use secrecy::SecretString;
 
 use prose_wasm_utils::{spawn, SendUnlessWasm, SyncUnlessWasm};
 
-use crate::connector::{ConnectionEvent, ConnectionEventHandler};
+use crate::connector::{ConnectionEvent, ConnectionEventHandler, SessionResumption};
 use crate::{Connection, ConnectionError, Connector};
 
 #[cfg_attr(target_arch = "wasm32", async_trait(? Send))]
@@ -53,9 +53,42 @@ impl<C: Connector, T: ProxyTransformer + SendUnlessWasm + 'static> Connector
         password: SecretString,
         event_handler: ConnectionEventHandler,
     ) -> Result<Box<dyn Connection>, ConnectionError> {
+        let connection = self
+            .connector
+            .connect(jid, password, self.proxy_event_handler(event_handler))
+            .await?;
+        Ok(self.proxy_connection(connection))
+    }
+
+    fn supports_resumption(&self) -> bool {
+        self.connector.supports_resumption()
+    }
+
+    async fn resume(
+        &self,
+        jid: &FullJid,
+        password: SecretString,
+        resumption: &SessionResumption,
+        event_handler: ConnectionEventHandler,
+    ) -> Result<(Box<dyn Connection>, u32), ConnectionError> {
+        let (connection, handled_count) = self
+            .connector
+            .resume(
+                jid,
+                password,
+                resumption,
+                self.proxy_event_handler(event_handler),
+            )
+            .await?;
+        Ok((self.proxy_connection(connection), handled_count))
+    }
+}
+
+impl<C: Connector, T: ProxyTransformer + SendUnlessWasm + 'static> ProxyConnector<C, T> {
+    fn proxy_event_handler(&self, event_handler: ConnectionEventHandler) -> ConnectionEventHandler {
         let orig_event_handler = Arc::new(event_handler);
 
-        let event_handler: ConnectionEventHandler = Box::new({
+        Box::new({
             let transformer = self.transformer.clone();
             let orig_event_handler = orig_event_handler.clone();
 
@@ -69,14 +102,14 @@ impl<C: Connector, T: ProxyTransformer + SendUnlessWasm + 'static> Connector
                         .await;
                 })
             }
-        });
-
-        let connection = self.connector.connect(jid, password, event_handler).await?;
+        })
+    }
 
-        Ok(Box::new(ProxyConnection {
+    fn proxy_connection(&self, connection: Box<dyn Connection>) -> Box<dyn Connection> {
+        Box::new(ProxyConnection {
             connection: connection.into(),
             transformer: self.transformer.clone(),
-        }))
+        })
     }
 }
 
@@ -99,4 +132,8 @@ impl<T: ProxyTransformer + 'static> Connection for ProxyConnection<T> {
     fn disconnect(&self) {
         self.connection.disconnect()
     }
+
+    fn supports_stream_management(&self) -> bool {
+        self.connection.supports_stream_management()
+    }
 }
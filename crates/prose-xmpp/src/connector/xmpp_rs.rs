@@ -18,7 +18,7 @@ use tokio::sync::mpsc::UnboundedSender;
 use tokio::task::JoinHandle;
 use tokio::{task, time};
 use tokio_xmpp::starttls::ServerConfig;
-use tokio_xmpp::{AsyncClient, Error, Event, Packet};
+use tokio_xmpp::{AsyncClient, AuthError, Error, Event, Packet};
 use tracing::error;
 
 use crate::client::ConnectorProvider;
@@ -35,6 +35,10 @@ impl Connector {
     }
 }
 
+// `AsyncClient` always binds a resource after authenticating, so there's no way to send
+// `<resume/>` instead. It doesn't expose the stream features either, so we can't tell whether the
+// server supports Stream Management. We rely on the defaults, i.e. neither enable Stream
+// Management nor try to resume.
 #[async_trait]
 impl ConnectorTrait for Connector {
     async fn connect(
@@ -52,12 +56,7 @@ impl ConnectorTrait for Connector {
 
             while let Some(event) = client.next().await {
                 match event {
-                    Event::Disconnected(Error::Auth(_)) => {
-                        return Err(ConnectionError::InvalidCredentials);
-                    }
-                    Event::Disconnected(e) => {
-                        return Err(ConnectionError::Generic { msg: e.to_string() });
-                    }
+                    Event::Disconnected(e) => return Err(connection_error_from(e)),
                     Event::Online { .. } => break,
                     Event::Stanza(stanza) => {
                         return Err(ConnectionError::Generic {
@@ -76,6 +75,24 @@ impl ConnectorTrait for Connector {
     }
 }
 
+fn connection_error_from(error: Error) -> ConnectionError {
+    match error {
+        Error::Auth(AuthError::Fail(condition)) => {
+            ConnectionError::from_sasl_condition(Element::from(condition).name())
+        }
+        Error::Auth(_) => ConnectionError::InvalidCredentials,
+        Error::Io(err) => ConnectionError::from_io_error(&err),
+        // The connector error is opaque and covers everything from resolving the server to
+        // the TLS handshake, i.e. no stream could be established.
+        Error::Connection(err) => ConnectionError::ServerUnreachable {
+            msg: err.to_string(),
+        },
+        err => ConnectionError::Generic {
+            msg: err.to_string(),
+        },
+    }
+}
+
 pub struct Connection {
     sender: Arc<UnboundedSender<Packet>>,
     _stream_read_handle: Option<JoinHandle<()>>,
@@ -106,9 +123,7 @@ impl Connection {
                             (event_handler)(
                                 Box::new(conn),
                                 ConnectionEvent::Disconnected {
-                                    error: Some(ConnectionError::Generic {
-                                        msg: err.to_string(),
-                                    }),
+                                    error: Some(connection_error_from(err)),
                                 },
                             )
                             .await;
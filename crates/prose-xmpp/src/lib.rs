@@ -6,8 +6,10 @@
 pub use jid::{BareJid, FullJid, Jid};
 pub use secrecy::SecretString;
 
-pub use client::{Client, ClientBuilder};
-pub use connector::{Connection, ConnectionError, Connector};
+pub use client::{Client, ClientBuilder, SessionKind};
+pub use connector::{
+    Connection, ConnectionError, Connector, DisconnectReason, ForbiddenReason, SessionResumption,
+};
 pub use deps::{IDProvider, SystemTimeProvider, TimeProvider, UUIDProvider};
 pub use event::Event;
 pub use stanza::ns;
@@ -5,6 +5,7 @@
 
 use anyhow::Result;
 use jid::Jid;
+use tracing::warn;
 use xmpp_parsers::blocking::{Block, BlocklistRequest, BlocklistResult, Unblock};
 use xmpp_parsers::iq::{Iq, IqType};
 
@@ -35,31 +36,52 @@ impl Module for BlockList {
             return Ok(());
         };
 
-        let event = match payload {
-            _ if payload.is("block", ns::BLOCKING) => {
-                let mut block = Block::try_from(payload.clone())?;
-                let Some(jid) = block.items.pop() else {
-                    return Ok(());
-                };
-                Event::UserBlocked { jid }
-            }
+        // https://xmpp.org/extensions/xep-0191.html#push
+        let events = match payload {
+            _ if payload.is("block", ns::BLOCKING) => Block::try_from(payload.clone())?
+                .items
+                .into_iter()
+                .map(|jid| Event::UserBlocked { jid })
+                .collect::<Vec<_>>(),
             _ if payload.is("unblock", ns::BLOCKING) => {
-                let mut unblock = Unblock::try_from(payload.clone())?;
-                if let Some(jid) = unblock.items.pop() {
-                    Event::UserUnblocked { jid }
+                let unblock = Unblock::try_from(payload.clone())?;
+                if unblock.items.is_empty() {
+                    vec![Event::BlockListCleared]
                 } else {
-                    Event::BlockListCleared
+                    unblock
+                        .items
+                        .into_iter()
+                        .map(|jid| Event::UserUnblocked { jid })
+                        .collect()
                 }
             }
             _ => return Ok(()),
         };
 
-        self.ctx.schedule_event(ClientEvent::BlockList(event));
+        // Pushes are only accepted from our own account (i.e. without a 'from' attribute or
+        // with our bare JID).
+        if let Some(from) = &stanza.from {
+            if from.to_bare() != self.ctx.bare_jid() {
+                warn!("Ignoring block list push from invalid sender {from}.");
+                return Ok(());
+            }
+        }
+
+        for event in events {
+            self.ctx.schedule_event(ClientEvent::BlockList(event));
+        }
+
+        self.ctx.send_stanza(Iq {
+            from: None,
+            to: stanza.from.clone(),
+            id: stanza.id.clone(),
+            payload: IqType::Result(None),
+        })?;
+
         Ok(())
     }
 }
 
-/// https://xmpp.org/extensions/xep-0191.html
 impl BlockList {
     pub async fn load_block_list(&self) -> Result<Vec<Jid>> {
         let response = self
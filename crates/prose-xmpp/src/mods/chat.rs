@@ -186,6 +186,7 @@ impl Chat {
         id: message::Id,
         to: impl Into<Jid>,
         message_type: &MessageType,
+        reason: Option<&str>,
     ) -> Result<()> {
         let stanza = Message::new()
         .set_type(message_type.clone())
@@ -193,7 +194,9 @@ impl Chat {
         .set_from(self.ctx.full_jid())
         .set_to(to)
         .set_body("This person attempted to retract a previous message, but it's unsupported by your client.")
-        .set_fastening(ApplyTo::new(id).with_payload(Retract::default()))
+        .set_fastening(ApplyTo::new(id).with_payload(Retract {
+          reason: reason.map(ToString::to_string),
+        }))
         .set_fallback(Fallback {
           r#for: None,
           subjects: vec![],
@@ -10,9 +10,9 @@ use jid::{BareJid, FullJid, Jid};
 use minidom::Element;
 use xmpp_parsers::data_forms::{DataForm, DataFormType};
 use xmpp_parsers::disco::{DiscoItemsQuery, DiscoItemsResult};
-use xmpp_parsers::iq::Iq;
+use xmpp_parsers::iq::{Iq, IqType};
 use xmpp_parsers::message::MessageType;
-use xmpp_parsers::muc::user::{Affiliation, Status};
+use xmpp_parsers::muc::user::{Affiliation, Role as OccupantRole, Status};
 use xmpp_parsers::nick::Nick;
 use xmpp_parsers::presence;
 use xmpp_parsers::presence::{Presence, Show};
@@ -23,6 +23,9 @@ use crate::client::ModuleContext;
 use crate::event::Event as ClientEvent;
 use crate::mods::Module;
 use crate::ns;
+use crate::stanza::message;
+use crate::stanza::message::fasten::ApplyTo;
+use crate::stanza::message::moderate::Moderate;
 use crate::stanza::muc::mediated_invite::MediatedInvite;
 use crate::stanza::muc::query::{Destroy, Role};
 use crate::stanza::muc::{DirectInvite, MucUser, Query};
@@ -154,6 +157,36 @@ impl MUC {
         Ok(rooms)
     }
 
+    /// Loads the occupants of a room. Rooms may choose to not disclose their occupants in which
+    /// case an empty list is returned.
+    /// https://xmpp.org/extensions/xep-0045.html#disco-roomitems
+    pub async fn load_occupants(&self, room_jid: &BareJid) -> Result<Vec<FullJid>> {
+        let response = self
+            .ctx
+            .send_iq(
+                Iq::from_get(
+                    self.ctx.generate_id(),
+                    DiscoItemsQuery {
+                        node: None,
+                        rsm: None,
+                    },
+                )
+                .with_to(Jid::from(room_jid.clone())),
+            )
+            .await?
+            .ok_or(RequestError::UnexpectedResponse)?;
+
+        let items = DiscoItemsResult::try_from(response)?;
+
+        let occupants = items
+            .items
+            .into_iter()
+            .filter_map(|item| item.jid.try_into_full().ok())
+            .collect();
+
+        Ok(occupants)
+    }
+
     /// Enters a room.
     /// https://xmpp.org/extensions/xep-0045.html#enter
     pub async fn enter_room(
@@ -307,12 +340,13 @@ impl MUC {
         &self,
         jid: &BareJid,
         alternate_room: Option<&BareJid>,
+        reason: Option<&str>,
     ) -> Result<(), RequestError> {
         let iq = Iq::from_set(
             self.ctx.generate_id(),
             Query::new(Role::Owner).with_payload(Destroy {
                 jid: alternate_room.cloned(),
-                reason: None,
+                reason: reason.map(ToString::to_string),
             }),
         )
         .with_to(jid.clone().into());
@@ -377,24 +411,76 @@ impl MUC {
         // we're sending all at once…
 
         for (jid, affiliation) in users.into_iter() {
-            let iq = Iq::from_set(
-                self.ctx.generate_id(),
-                muc::Query {
-                    role: Role::Admin,
-                    payloads: vec![Element::builder("item", &Role::Admin.to_string())
-                        .attr("jid", jid)
-                        .attr("affiliation", affiliation)
-                        .build()],
-                },
-            )
-            .with_to(room_jid.clone().into());
-
-            self.ctx.send_iq(iq).await?;
+            self.update_user_affiliation(room_jid, jid, affiliation, None)
+                .await?;
         }
 
         Ok(())
     }
 
+    /// Modifies the affiliation of a single user, optionally with a `reason` that is relayed to
+    /// the affected user, e.g. when banning them.
+    /// https://xmpp.org/extensions/xep-0045.html#ban
+    pub async fn update_user_affiliation(
+        &self,
+        room_jid: &BareJid,
+        jid: BareJid,
+        affiliation: Affiliation,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        let iq = Iq::from_set(
+            self.ctx.generate_id(),
+            muc::Query {
+                role: Role::Admin,
+                payloads: vec![Element::builder("item", &Role::Admin.to_string())
+                    .attr("jid", jid)
+                    .attr("affiliation", affiliation)
+                    .append_all(reason.map(|reason| {
+                        Element::builder("reason", &Role::Admin.to_string())
+                            .append(reason)
+                            .build()
+                    }))
+                    .build()],
+            },
+        )
+        .with_to(room_jid.clone().into());
+
+        self.ctx.send_iq(iq).await?;
+        Ok(())
+    }
+
+    /// Modifies the role of the occupant with the nickname `nick`. Setting the role to `None`
+    /// kicks the occupant from the room.
+    /// https://xmpp.org/extensions/xep-0045.html#modifyvoice
+    /// https://xmpp.org/extensions/xep-0045.html#kick
+    pub async fn update_occupant_role(
+        &self,
+        room_jid: &BareJid,
+        nick: &str,
+        role: OccupantRole,
+        reason: Option<&str>,
+    ) -> Result<()> {
+        let iq = Iq::from_set(
+            self.ctx.generate_id(),
+            muc::Query {
+                role: Role::Admin,
+                payloads: vec![Element::builder("item", &Role::Admin.to_string())
+                    .attr("nick", nick)
+                    .attr("role", role)
+                    .append_all(reason.map(|reason| {
+                        Element::builder("reason", &Role::Admin.to_string())
+                            .append(reason)
+                            .build()
+                    }))
+                    .build()],
+            },
+        )
+        .with_to(room_jid.clone().into());
+
+        self.ctx.send_iq(iq).await?;
+        Ok(())
+    }
+
     /// Sends a direct invite to a user.
     /// https://xmpp.org/extensions/xep-0045.html#invite-direct
     pub async fn send_direct_invite(
@@ -421,6 +507,30 @@ impl MUC {
         Ok(())
     }
 
+    /// Retracts the message with the given stanza-id in a room (requires moderator privileges).
+    /// https://xmpp.org/extensions/xep-0425.html#usecases-retract
+    pub async fn moderate_message(
+        &self,
+        room_jid: &BareJid,
+        stanza_id: impl Into<message::Id>,
+        reason: Option<&str>,
+    ) -> Result<(), RequestError> {
+        let iq = Iq {
+            from: None,
+            to: Some(room_jid.clone().into()),
+            id: self.ctx.generate_id(),
+            payload: IqType::Set(
+                ApplyTo::new(stanza_id.into())
+                    .with_payload(Moderate {
+                        reason: reason.map(ToString::to_string),
+                    })
+                    .into(),
+            ),
+        };
+        self.ctx.send_iq(iq).await?;
+        Ok(())
+    }
+
     pub async fn set_room_subject(&self, room_jid: &BareJid, subject: Option<&str>) -> Result<()> {
         let message = Message::new()
             .set_id(self.ctx.generate_id().into())
@@ -3,13 +3,16 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
 use anyhow::{bail, Result};
 use jid::Jid;
 use tracing::info;
 use xmpp_parsers::iq::{Iq, IqType};
 use xmpp_parsers::ping;
 
-use crate::client::ModuleContext;
+use crate::client::{self, ModuleContext};
 use crate::event::Event as ClientEvent;
 use crate::mods::Module;
 use crate::ns;
@@ -18,9 +21,14 @@ use crate::util::RequestError;
 // XEP-0199: XMPP Ping
 // https://xmpp.org/extensions/xep-0199.html
 
+/// The number of consecutive pings to the server that may go unanswered before we consider the
+/// connection dead.
+const MAX_MISSED_PINGS: u32 = 2;
+
 #[derive(Default, Clone)]
 pub struct Ping {
     ctx: ModuleContext,
+    missed_pings: Arc<AtomicU32>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -33,6 +41,12 @@ impl Module for Ping {
         self.ctx = context
     }
 
+    fn handle_connect(&self) -> Result<()> {
+        // Pings missed by a previous connection must not count against the new one…
+        self.missed_pings.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
     fn handle_iq_stanza(&self, stanza: &Iq) -> Result<()> {
         let IqType::Get(payload) = &stanza.payload else {
             return Ok(());
@@ -77,7 +91,12 @@ impl Ping {
 }
 
 impl Ping {
+    /// Pings the server and reports the round-trip time via `Event::PingAnswered`. If the ping
+    /// times out, `Event::PingTimeout` is scheduled. After `MAX_MISSED_PINGS` consecutive
+    /// timeouts the connection is closed.
     pub(crate) async fn send_ping_to_server(&self) -> Result<()> {
+        let sent_at = self.ctx.now();
+
         let result = self
             .ctx
             .send_iq(
@@ -87,9 +106,27 @@ impl Ping {
             .await;
 
         match result {
-            Ok(_) => Ok(()),
+            Ok(_) => {
+                self.missed_pings.store(0, Ordering::Relaxed);
+                let round_trip_time = (self.ctx.now() - sent_at).to_std().unwrap_or_default();
+                self.ctx
+                    .schedule_event(ClientEvent::Client(client::Event::PingAnswered {
+                        round_trip_time,
+                    }));
+                Ok(())
+            }
             Err(RequestError::TimedOut) => {
-                info!("Ping timed out. Disconnecting…");
+                let missed_pings = self.missed_pings.fetch_add(1, Ordering::Relaxed) + 1;
+
+                if missed_pings < MAX_MISSED_PINGS {
+                    info!("Ping timed out.");
+                    self.ctx
+                        .schedule_event(ClientEvent::Client(client::Event::PingTimeout));
+                    return Ok(());
+                }
+
+                info!("Ping timed out {missed_pings} times in a row. Disconnecting…");
+                self.missed_pings.store(0, Ordering::Relaxed);
                 self.ctx.disconnect();
                 Ok(())
             }
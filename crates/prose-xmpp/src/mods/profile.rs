@@ -16,6 +16,7 @@ use xmpp_parsers::iq::{Iq, IqType};
 use xmpp_parsers::pubsub;
 use xmpp_parsers::pubsub::pubsub::{Items, PublishOptions};
 use xmpp_parsers::pubsub::{NodeName, PubSub, PubSubEvent};
+use xmpp_parsers::stanza_error::{DefinedCondition, ErrorType, StanzaError};
 use xmpp_parsers::time::{TimeQuery, TimeResult};
 use xmpp_parsers::version::{VersionQuery, VersionResult};
 
@@ -267,8 +268,9 @@ impl Profile {
         Ok(())
     }
 
+    /// Clears the vCard-temp by publishing an empty one.
     pub async fn delete_vcard(&self) -> Result<()> {
-        let mut iq = Iq::from_set(self.ctx.generate_id(), VCard4::new());
+        let mut iq = Iq::from_set(self.ctx.generate_id(), VCard::default());
         iq.to = Some(self.ctx.bare_jid().into());
         self.ctx.send_iq(iq).await?;
         Ok(())
@@ -355,6 +357,27 @@ impl Profile {
         Ok(())
     }
 
+    /// Disables the avatar by publishing an empty metadata element.
+    /// https://xmpp.org/extensions/xep-0084.html#pub-disable
+    pub async fn delete_avatar_metadata(&self) -> Result<()> {
+        let iq = Iq::from_set(
+            self.ctx.generate_id(),
+            pubsub::PubSub::Publish {
+                publish: pubsub::pubsub::Publish {
+                    node: NodeName(ns::AVATAR_METADATA.to_string()),
+                    items: vec![pubsub::pubsub::Item(pubsub::Item {
+                        id: None,
+                        publisher: None,
+                        payload: Some(avatar::Metadata { infos: vec![] }.into()),
+                    })],
+                },
+                publish_options: None,
+            },
+        );
+        self.ctx.send_iq(iq).await?;
+        Ok(())
+    }
+
     pub async fn load_avatar_image(
         &self,
         from: impl Into<Jid>,
@@ -542,4 +565,28 @@ impl Profile {
         )?;
         Ok(())
     }
+
+    /// Declines a last activity request of an entity that is not allowed to see our presence
+    /// (https://xmpp.org/extensions/xep-0012.html#impl).
+    pub async fn send_last_activity_forbidden_response(
+        &self,
+        to: Jid,
+        id: impl AsRef<str>,
+    ) -> Result<()> {
+        self.ctx.send_stanza(
+            Iq::from_error(
+                id.as_ref(),
+                StanzaError {
+                    type_: ErrorType::Auth,
+                    by: None,
+                    defined_condition: DefinedCondition::Forbidden,
+                    texts: Default::default(),
+                    other: None,
+                    alternate_address: None,
+                },
+            )
+            .with_to(to),
+        )?;
+        Ok(())
+    }
 }
@@ -13,7 +13,9 @@ use xmpp_parsers::roster::{Group, Item, Roster as Query, Subscription};
 
 use crate::client::ModuleContext;
 use crate::event::Event as ClientEvent;
-use crate::mods::roster::Event::{PresenceSubscriptionRequest, RosterItemChanged};
+use crate::mods::roster::Event::{
+    PresenceSubscriptionRequest, PresenceSubscriptionWithdrawn, RosterItemChanged,
+};
 use crate::mods::Module;
 use crate::ns;
 use crate::util::RequestError;
@@ -29,6 +31,10 @@ pub enum Event {
         from: BareJid,
         nickname: Option<String>,
     },
+    /// The contact withdrew its presence subscription request or unsubscribed from our presence.
+    PresenceSubscriptionWithdrawn {
+        from: BareJid,
+    },
     RosterItemChanged {
         item: Item,
     },
@@ -40,14 +46,22 @@ impl Module for Roster {
     }
 
     fn handle_presence_stanza(&self, stanza: &Presence) -> Result<()> {
-        if stanza.type_ != Type::Subscribe {
-            return Ok(());
-        }
-
         let Some(jid) = &stanza.from else {
             return Ok(());
         };
 
+        match stanza.type_ {
+            Type::Subscribe => (),
+            Type::Unsubscribe => {
+                self.ctx
+                    .schedule_event(ClientEvent::Roster(PresenceSubscriptionWithdrawn {
+                        from: jid.to_bare(),
+                    }));
+                return Ok(());
+            }
+            _ => return Ok(()),
+        }
+
         let nickname = stanza
             .payloads
             .iter()
@@ -147,12 +161,15 @@ impl Roster {
         Ok(xmpp_parsers::roster::Roster::try_from(response)?)
     }
 
+    /// Adds `jid` to the roster. If the item exists already, its name and groups are replaced.
+    ///
     /// https://xmpp.org/rfcs/rfc6121.html#roster-add
+    /// https://xmpp.org/rfcs/rfc6121.html#roster-update
     pub async fn add_contact(
         &self,
         jid: &BareJid,
         name: Option<&str>,
-        group: Option<&str>,
+        groups: &[String],
     ) -> Result<()> {
         let iq = Iq::from_set(
             self.ctx.generate_id(),
@@ -163,9 +180,7 @@ impl Roster {
                     name: name.map(ToString::to_string),
                     subscription: Default::default(),
                     ask: Default::default(),
-                    groups: group
-                        .map(|group| vec![Group(group.to_string())])
-                        .unwrap_or_else(|| vec![]),
+                    groups: groups.iter().cloned().map(Group).collect(),
                 }],
             },
         );
@@ -4,7 +4,9 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use jid::Jid;
+use xmpp_parsers::idle::Idle;
 use xmpp_parsers::iq::Iq;
 use xmpp_parsers::presence::{Presence, Type};
 use xmpp_parsers::pubsub::{NodeName, PubSub, PubSubEvent};
@@ -87,6 +89,9 @@ impl Module for Status {
 impl Status {
     /// XMPP: Instant Messaging and Presence
     /// https://xmpp.org/rfcs/rfc6121.html#presence
+    ///
+    /// XEP-0319: Last User Interaction in Presence
+    /// https://xmpp.org/extensions/xep-0319.html
     pub fn send_presence(
         &self,
         to: Option<Jid>,
@@ -94,6 +99,7 @@ impl Status {
         status: Option<&str>,
         caps: Option<xmpp_parsers::caps::Caps>,
         priority: Option<i8>,
+        idle_since: Option<DateTime<Utc>>,
     ) -> Result<()> {
         let mut presence = Presence::new(presence::Type::None);
         presence.to = to;
@@ -108,6 +114,11 @@ impl Status {
         if let Some(priority) = priority {
             presence.priority = priority
         }
+        if let Some(idle_since) = idle_since {
+            presence.add_payload(Idle {
+                since: xmpp_parsers::date::DateTime(idle_since.into()),
+            })
+        }
 
         self.ctx.send_stanza(presence)?;
         Ok(())
@@ -115,7 +126,12 @@ impl Status {
 
     /// XEP-0108: User Activity
     /// https://xmpp.org/extensions/xep-0108.html
+    ///
+    /// Publishing an empty `UserActivity` clears the current activity. Fails without sending
+    /// anything if `activity` isn't valid as per the XEP's vocabulary.
     pub async fn publish_activity(&self, activity: UserActivity) -> Result<()> {
+        activity.validate()?;
+
         let iq = Iq::from_set(
             self.ctx.generate_id(),
             PubSub::Publish {
@@ -157,6 +157,28 @@ impl Message {
         self
     }
 
+    /// Marks the message as a private message to an occupant of a MUC room.
+    /// https://xmpp.org/extensions/xep-0045.html#privatemessage
+    pub fn set_muc_private_message(mut self) -> Self {
+        self.payloads
+            .push(Element::builder("x", ns::MUC_USER).build());
+        self
+    }
+
+    /// XEP-0184: Message Delivery Receipts
+    /// https://xmpp.org/extensions/xep-0184.html
+    pub fn set_receipt_request(mut self) -> Self {
+        self.payloads.push(xmpp_parsers::receipts::Request.into());
+        self
+    }
+
+    /// XEP-0184: Message Delivery Receipts
+    /// https://xmpp.org/extensions/xep-0184.html
+    pub fn set_delivery_receipt(mut self, receipt: xmpp_parsers::receipts::Received) -> Self {
+        self.payloads.push(receipt.into());
+        self
+    }
+
     pub fn set_muc_user(mut self, user: MucUser) -> Self {
         self.payloads.push(user.into());
         self
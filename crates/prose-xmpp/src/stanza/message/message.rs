@@ -15,6 +15,7 @@ use xmpp_parsers::legacy_omemo;
 use xmpp_parsers::message::{Message as RawMessage, MessagePayload};
 use xmpp_parsers::message_correct::Replace;
 use xmpp_parsers::occupant_id::OccupantId;
+use xmpp_parsers::receipts;
 use xmpp_parsers::stanza_error::StanzaError;
 
 use prose_utils::id_string;
@@ -158,6 +159,21 @@ impl Message {
         self.typed_payload("displayed", ns::CHAT_MARKERS)
     }
 
+    /// XEP-0184: Message Delivery Receipts
+    pub fn delivery_receipt(&self) -> Option<receipts::Received> {
+        self.typed_payload("received", ns::RECEIPTS)
+    }
+
+    /// XEP-0184: Message Delivery Receipts
+    pub fn requests_delivery_receipt(&self) -> bool {
+        self.payloads.iter().any(|p| p.is("request", ns::RECEIPTS))
+    }
+
+    pub fn is_markable(&self) -> bool {
+        self.typed_payload::<chat_marker::Markable>("markable", ns::CHAT_MARKERS)
+            .is_some()
+    }
+
     pub fn muc_user(&self) -> Option<MucUser> {
         self.typed_payload("x", ns::MUC_USER)
     }
@@ -175,6 +191,25 @@ impl Message {
     }
 
     pub fn media_shares(&self) -> Vec<MediaShare> {
+        self.media_share_elements()
+            .into_iter()
+            .filter_map(|elem| match MediaShare::try_from(elem.clone()) {
+                Ok(share) => Some(share),
+                Err(err) => {
+                    println!(
+                        "Failed to parse 'media-share' {}. {}",
+                        String::from(elem),
+                        err.to_string()
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the unparsed 'media-sharing' elements contained in data references. Useful if
+    /// a media-sharing element should be salvaged even if it fails to parse.
+    pub fn media_share_elements(&self) -> Vec<&Element> {
         self.payloads
             .iter()
             .filter_map(|elem| {
@@ -190,17 +225,7 @@ impl Message {
                     return None;
                 }
 
-                match MediaShare::try_from(child.clone()) {
-                    Ok(share) => Some(share),
-                    Err(err) => {
-                        println!(
-                            "Failed to parse 'media-share' {}. {}",
-                            String::from(elem),
-                            err.to_string()
-                        );
-                        None
-                    }
-                }
+                Some(child)
             })
             .collect()
     }
@@ -22,6 +22,7 @@ pub mod fasten;
 mod forwarding;
 pub mod mam;
 mod message;
+pub mod moderate;
 mod muc_invite;
 mod muc_user;
 mod reactions;
@@ -0,0 +1,153 @@
+// prose-core-client/prose-xmpp
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use jid::Jid;
+use minidom::Element;
+
+use crate::ns;
+use crate::stanza::message::fasten;
+use crate::stanza::message::fasten::ApplyTo;
+use crate::stanza::message::retract::Retract;
+use crate::util::ElementExt;
+
+/// XEP-0425: Message Moderation
+/// The request sent by a moderator to the room to retract a message.
+/// https://xmpp.org/extensions/xep-0425.html#usecases-retract
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Moderate {
+    pub reason: Option<String>,
+}
+
+/// XEP-0425: Message Moderation
+/// The notification sent by the room after a message was retracted by a moderator.
+/// https://xmpp.org/extensions/xep-0425.html#usecases-retract
+#[derive(Debug, PartialEq, Clone)]
+pub struct Moderated {
+    pub by: Option<Jid>,
+    pub retract: bool,
+    pub reason: Option<String>,
+}
+
+impl From<Moderate> for Element {
+    fn from(value: Moderate) -> Self {
+        Element::builder("moderate", ns::MODERATE)
+            .append(Element::from(Retract::default()))
+            .append_all(
+                value
+                    .reason
+                    .map(|reason| Element::builder("reason", ns::MODERATE).append(reason)),
+            )
+            .build()
+    }
+}
+
+impl TryFrom<Element> for Moderate {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Element) -> Result<Self, Self::Error> {
+        value.expect_is("moderate", ns::MODERATE)?;
+
+        Ok(Moderate {
+            reason: value
+                .get_child("reason", ns::MODERATE)
+                .map(|reason| reason.text()),
+        })
+    }
+}
+
+impl fasten::ApplyToPayload for Moderate {}
+
+impl TryFrom<Element> for Moderated {
+    type Error = anyhow::Error;
+
+    fn try_from(value: Element) -> Result<Self, Self::Error> {
+        value.expect_is("moderated", ns::MODERATE)?;
+
+        Ok(Moderated {
+            by: value.attr("by").map(str::parse).transpose()?,
+            retract: value.has_child("retract", ns::RETRACT),
+            reason: value
+                .get_child("reason", ns::MODERATE)
+                .map(|reason| reason.text()),
+        })
+    }
+}
+
+impl From<Moderated> for Element {
+    fn from(value: Moderated) -> Self {
+        Element::builder("moderated", ns::MODERATE)
+            .attr("by", value.by)
+            .append_all(value.retract.then(|| Element::from(Retract::default())))
+            .append_all(
+                value
+                    .reason
+                    .map(|reason| Element::builder("reason", ns::MODERATE).append(reason)),
+            )
+            .build()
+    }
+}
+
+impl fasten::ApplyToPayload for Moderated {}
+
+impl ApplyTo {
+    /// Returns the moderation notification if the fastening contains one.
+    pub fn moderated(&self) -> Option<Moderated> {
+        self.payloads
+            .iter()
+            .find(|p| p.is("moderated", ns::MODERATE))
+            .and_then(|p| Moderated::try_from(p.clone()).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use anyhow::Result;
+    use jid::FullJid;
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize_moderated() -> Result<()> {
+        let xml = r#"<apply-to xmlns="urn:xmpp:fasten:0" id="stanza-id-1">
+          <moderated xmlns="urn:xmpp:message-moderate:0" by="room@muc.prose.org/mod">
+            <retract xmlns="urn:xmpp:message-retract:0"/>
+            <reason>Spam</reason>
+          </moderated>
+        </apply-to>"#;
+
+        let apply_to = ApplyTo::try_from(Element::from_str(xml)?)?;
+
+        assert_eq!(apply_to.id.as_ref(), "stanza-id-1");
+        assert_eq!(
+            apply_to.moderated(),
+            Some(Moderated {
+                by: Some(FullJid::from_str("room@muc.prose.org/mod")?.into()),
+                retract: true,
+                reason: Some("Spam".to_string()),
+            })
+        );
+        assert!(!apply_to.retract());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_moderate() -> Result<()> {
+        let apply_to: Element = ApplyTo::new("stanza-id-1".into())
+            .with_payload(Moderate {
+                reason: Some("Spam".to_string()),
+            })
+            .into();
+
+        assert_eq!(
+            String::from(&apply_to),
+            r#"<apply-to xmlns="urn:xmpp:fasten:0" id="stanza-id-1"><moderate xmlns="urn:xmpp:message-moderate:0"><retract xmlns="urn:xmpp:message-retract:0"/><reason>Spam</reason></moderate></apply-to>"#
+        );
+
+        Ok(())
+    }
+}
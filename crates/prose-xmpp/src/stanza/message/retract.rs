@@ -10,17 +10,20 @@ use crate::ns;
 use crate::stanza::message::fasten;
 use crate::stanza::message::fasten::ApplyTo;
 
-pub struct Retract {}
-
-impl Default for Retract {
-    fn default() -> Self {
-        Retract {}
-    }
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Retract {
+    pub reason: Option<String>,
 }
 
 impl From<Retract> for Element {
-    fn from(_value: Retract) -> Self {
-        Element::builder("retract", ns::RETRACT).build()
+    fn from(value: Retract) -> Self {
+        Element::builder("retract", ns::RETRACT)
+            .append_all(
+                value
+                    .reason
+                    .map(|reason| Element::builder("reason", ns::RETRACT).append(reason)),
+            )
+            .build()
     }
 }
 
@@ -29,7 +32,11 @@ impl TryFrom<Element> for Retract {
 
     fn try_from(value: Element) -> Result<Self, Self::Error> {
         value.expect_is("retract", ns::RETRACT)?;
-        Ok(Retract::default())
+        Ok(Retract {
+            reason: value
+                .get_child("reason", ns::RETRACT)
+                .map(|reason| reason.text()),
+        })
     }
 }
 
@@ -42,4 +49,12 @@ impl ApplyTo {
             .find(|p| p.is("retract", ns::RETRACT))
             .is_some()
     }
+
+    /// Returns the retraction if the fastening contains one.
+    pub fn retraction(&self) -> Option<Retract> {
+        self.payloads
+            .iter()
+            .find(|p| p.is("retract", ns::RETRACT))
+            .and_then(|p| Retract::try_from(p.clone()).ok())
+    }
 }
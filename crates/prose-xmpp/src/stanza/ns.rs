@@ -7,6 +7,12 @@ pub use xmpp_parsers::ns::*;
 
 // See all at: https://xmpp.org/registrar/namespaces.html
 
+/// RFC 6120: Stream error conditions
+pub const XMPP_STREAMS: &str = "urn:ietf:params:xml:ns:xmpp-streams";
+
+/// XEP-0198: Stream Management
+pub const SM: &str = "urn:xmpp:sm:3";
+
 /// XEP-0292: vCard4 Over XMPP
 pub const VCARD4: &str = "urn:ietf:params:xml:ns:vcard-4.0";
 
@@ -19,6 +25,9 @@ pub const RETRACT: &str = "urn:xmpp:message-retract:0";
 /// XEP-0422: Message Fastening
 pub const FASTEN: &str = "urn:xmpp:fasten:0";
 
+/// XEP-0425: Message Moderation
+pub const MODERATE: &str = "urn:xmpp:message-moderate:0";
+
 /// XEP-0203: Delayed Delivery
 pub const DELAY: &str = "urn:xmpp:delay";
 
@@ -5,8 +5,8 @@
 
 use crate::ns;
 use crate::util::ElementExt;
-use anyhow::Context;
 use anyhow::Result;
+use anyhow::{bail, Context};
 use minidom::Element;
 use std::str::FromStr;
 
@@ -22,6 +22,36 @@ pub struct Activity {
     pub specific: Option<activity::Specific>,
 }
 
+impl UserActivity {
+    /// Fails if the activity combines a general and specific category that XEP-0108 doesn't
+    /// define. An empty `UserActivity` is valid and clears a previously published activity.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(activity) = &self.activity {
+            activity.validate()?;
+        }
+        Ok(())
+    }
+}
+
+impl Activity {
+    /// See `UserActivity::validate`.
+    pub fn validate(&self) -> Result<()> {
+        let Some(specific) = &self.specific else {
+            return Ok(());
+        };
+
+        if !self.general.allows(specific) {
+            bail!(
+                "{} is not a specific activity of {}",
+                specific,
+                self.general
+            )
+        }
+
+        Ok(())
+    }
+}
+
 impl TryFrom<Element> for UserActivity {
     type Error = anyhow::Error;
 
@@ -127,6 +157,101 @@ pub mod activity {
         Working,
     }
 
+    impl General {
+        /// Returns true if `specific` is one of the specific activities XEP-0108 lists for this
+        /// general category. `Specific::Other` is allowed for all of them.
+        pub fn allows(&self, specific: &Specific) -> bool {
+            use Specific::*;
+
+            if let Other(_) = specific {
+                return true;
+            }
+
+            match self {
+                General::DoingChores => matches!(
+                    specific,
+                    BuyingGroceries
+                        | Cleaning
+                        | Cooking
+                        | DoingMaintenance
+                        | DoingTheDishes
+                        | DoingTheLaundry
+                        | Gardening
+                        | RunningAnErrand
+                        | WalkingTheDog
+                ),
+                General::Drinking => matches!(specific, HavingABeer | HavingCoffee | HavingTea),
+                General::Eating => matches!(
+                    specific,
+                    HavingASnack | HavingBreakfast | HavingDinner | HavingLunch
+                ),
+                General::Exercising => matches!(
+                    specific,
+                    Cycling
+                        | Dancing
+                        | Hiking
+                        | Jogging
+                        | PlayingSports
+                        | Running
+                        | Skiing
+                        | Swimming
+                        | WorkingOut
+                ),
+                General::Grooming => matches!(
+                    specific,
+                    AtTheSpa
+                        | BrushingTeeth
+                        | GettingAHaircut
+                        | Shaving
+                        | TakingABath
+                        | TakingAShower
+                ),
+                General::HavingAppointment => false,
+                General::Inactive => matches!(
+                    specific,
+                    DayOff
+                        | HangingOut
+                        | Hiding
+                        | OnVacation
+                        | Praying
+                        | ScheduledHoliday
+                        | Sleeping
+                        | Thinking
+                ),
+                General::Relaxing => matches!(
+                    specific,
+                    Fishing
+                        | Gaming
+                        | GoingOut
+                        | Partying
+                        | Reading
+                        | Rehearsing
+                        | Shopping
+                        | Smoking
+                        | Socializing
+                        | Sunbathing
+                        | WatchingTv
+                        | WatchingAMovie
+                ),
+                General::Talking => matches!(specific, InRealLife | OnThePhone | OnVideoPhone),
+                General::Traveling => matches!(
+                    specific,
+                    Commuting
+                        | Cycling
+                        | Driving
+                        | InACar
+                        | OnABus
+                        | OnAPlane
+                        | OnATrain
+                        | OnATrip
+                        | Walking
+                ),
+                General::Undefined => false,
+                General::Working => matches!(specific, Coding | InAMeeting | Studying | Writing),
+            }
+        }
+    }
+
     #[derive(Debug, PartialEq, Display, EnumString, Clone)]
     #[strum(serialize_all = "snake_case")]
     pub enum Specific {
@@ -329,4 +454,32 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_validates_activity() {
+        let activity = |general, specific| UserActivity {
+            activity: Some(Activity { general, specific }),
+            text: None,
+        };
+
+        assert!(UserActivity::default().validate().is_ok());
+        assert!(activity(General::Relaxing, None).validate().is_ok());
+        assert!(activity(General::Relaxing, Some(Specific::Partying))
+            .validate()
+            .is_ok());
+        assert!(activity(General::Traveling, Some(Specific::Cycling))
+            .validate()
+            .is_ok());
+        assert!(
+            activity(General::Undefined, Some(Specific::Other(Some("🌮".into()))))
+                .validate()
+                .is_ok()
+        );
+        assert!(activity(General::Eating, Some(Specific::Partying))
+            .validate()
+            .is_err());
+        assert!(activity(General::Undefined, Some(Specific::Coding))
+            .validate()
+            .is_err());
+    }
 }
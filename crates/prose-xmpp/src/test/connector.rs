@@ -3,6 +3,7 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -59,6 +60,7 @@ struct ConnectionInner {
     sent_stanzas: Mutex<Vec<Element>>,
     stanza_handler: Mutex<Option<Box<SentStanzaHandler>>>,
     event_handler: RwLock<Option<ConnectionEventHandler>>,
+    supports_stream_management: AtomicBool,
 }
 
 impl Connection {
@@ -69,6 +71,12 @@ impl Connection {
         *self.inner.stanza_handler.lock() = Some(Box::new(handler))
     }
 
+    pub fn set_supports_stream_management(&self, supports_stream_management: bool) {
+        self.inner
+            .supports_stream_management
+            .store(supports_stream_management, Ordering::Relaxed)
+    }
+
     pub fn use_start_sequence_handler(&self) {
         self.set_stanza_handler(|st| {
             if st.name() != "iq" || st.attr("id") != Some("id-2") {
@@ -118,6 +126,37 @@ impl Connection {
         };
         (event_handler)(Box::new(conn), ConnectionEvent::Stanza(stanza.into())).await
     }
+
+    pub async fn simulate_disconnect(&self, error: Option<ConnectionError>) {
+        let guard = self.inner.event_handler.read();
+        let event_handler = guard.as_ref().expect("No event handler registered");
+        let conn = Connection {
+            inner: self.inner.clone(),
+        };
+        (event_handler)(Box::new(conn), ConnectionEvent::Disconnected { error }).await
+    }
+
+    pub async fn simulate_ping_timer(&self) {
+        self.handle_event(ConnectionEvent::PingTimer).await
+    }
+
+    pub async fn simulate_timeout_timer(&self) {
+        self.handle_event(ConnectionEvent::TimeoutTimer).await
+    }
+
+    /// Hands `event` to the client without holding on to the event handler while it's being
+    /// processed, so that the returned future can be spawned.
+    async fn handle_event(&self, event: ConnectionEvent) {
+        let fut = {
+            let guard = self.inner.event_handler.read();
+            let event_handler = guard.as_ref().expect("No event handler registered");
+            let conn = Connection {
+                inner: self.inner.clone(),
+            };
+            (event_handler)(Box::new(conn), event)
+        };
+        fut.await
+    }
 }
 
 impl ConnectionTrait for Connection {
@@ -144,4 +183,10 @@ impl ConnectionTrait for Connection {
     }
 
     fn disconnect(&self) {}
+
+    fn supports_stream_management(&self) -> bool {
+        self.inner
+            .supports_stream_management
+            .load(Ordering::Relaxed)
+    }
 }
@@ -0,0 +1,120 @@
+// prose-core-client/prose-xmpp
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use jid::BareJid;
+use minidom::Element;
+use xmpp_parsers::blocking::{Block, Unblock};
+use xmpp_parsers::iq::{Iq, IqType};
+
+use prose_xmpp::test::{BareJidTestAdditions, ClientTestAdditions, ConnectedClient};
+use prose_xmpp::{jid, mods, Client, Event};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_handles_block_push_with_multiple_items() -> Result<()> {
+    let ConnectedClient {
+        connection,
+        sent_events,
+        ..
+    } = Client::connected_client().await?;
+    connection.reset();
+
+    connection
+        .receive_stanza(Iq::from_set(
+            "push-1",
+            Block {
+                items: vec![jid!("a@prose.org"), jid!("b@prose.org")],
+            },
+        ))
+        .await;
+
+    assert_eq!(
+        *sent_events.read(),
+        vec![
+            Event::BlockList(mods::block_list::Event::UserBlocked {
+                jid: jid!("a@prose.org")
+            }),
+            Event::BlockList(mods::block_list::Event::UserBlocked {
+                jid: jid!("b@prose.org")
+            }),
+        ]
+    );
+    assert_eq!(
+        connection.sent_stanzas(),
+        vec![Element::from(Iq {
+            from: None,
+            to: None,
+            id: "push-1".to_string(),
+            payload: IqType::Result(None),
+        })]
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_handles_unblock_push() -> Result<()> {
+    let ConnectedClient {
+        connection,
+        sent_events,
+        ..
+    } = Client::connected_client().await?;
+    connection.reset();
+
+    connection
+        .receive_stanza(
+            Iq::from_set(
+                "push-1",
+                Unblock {
+                    items: vec![jid!("a@prose.org")],
+                },
+            )
+            .with_from(BareJid::ours().into()),
+        )
+        .await;
+    connection
+        .receive_stanza(Iq::from_set("push-2", Unblock { items: vec![] }))
+        .await;
+
+    assert_eq!(
+        *sent_events.read(),
+        vec![
+            Event::BlockList(mods::block_list::Event::UserUnblocked {
+                jid: jid!("a@prose.org")
+            }),
+            Event::BlockList(mods::block_list::Event::BlockListCleared),
+        ]
+    );
+    assert_eq!(connection.sent_stanzas().len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_ignores_block_push_from_other_sender() -> Result<()> {
+    let ConnectedClient {
+        connection,
+        sent_events,
+        ..
+    } = Client::connected_client().await?;
+    connection.reset();
+
+    connection
+        .receive_stanza(
+            Iq::from_set(
+                "push-1",
+                Block {
+                    items: vec![jid!("a@prose.org")],
+                },
+            )
+            .with_from(jid!("mallory@prose.org")),
+        )
+        .await;
+
+    assert!(sent_events.read().is_empty());
+    assert!(connection.sent_stanzas().is_empty());
+
+    Ok(())
+}
@@ -320,7 +320,12 @@ async fn test_retract_message() -> Result<()> {
     } = Client::connected_client().await?;
 
     let chat = client.get_mod::<mods::Chat>();
-    chat.retract_message("msg-id".into(), jid!("recv@prose.org"), &MessageType::Chat)?;
+    chat.retract_message(
+        "msg-id".into(),
+        jid!("recv@prose.org"),
+        &MessageType::Chat,
+        None,
+    )?;
 
     let sent_stanzas = connection.sent_stanza_strings();
     assert_eq!(sent_stanzas.len(), 1);
@@ -331,6 +336,29 @@ async fn test_retract_message() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_retract_message_with_reason() -> Result<()> {
+    let ConnectedClient {
+        connection, client, ..
+    } = Client::connected_client().await?;
+
+    let chat = client.get_mod::<mods::Chat>();
+    chat.retract_message(
+        "msg-id".into(),
+        jid!("recv@prose.org"),
+        &MessageType::Chat,
+        Some("Typo"),
+    )?;
+
+    let sent_stanzas = connection.sent_stanza_strings();
+    assert_eq!(sent_stanzas.len(), 1);
+    assert_snapshot!(sent_stanzas[0], @r###"
+        <message xmlns='jabber:client' from="test@prose.org/test" id="id-1" to="recv@prose.org" type="chat"><body>This person attempted to retract a previous message, but it's unsupported by your client.</body><apply-to xmlns='urn:xmpp:fasten:0' id="msg-id"><retract xmlns='urn:xmpp:message-retract:0'><reason>Typo</reason></retract></apply-to><fallback xmlns='urn:xmpp:fallback:0'/></message>
+    "###);
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn test_mark_message_received() -> Result<()> {
     let ConnectedClient {
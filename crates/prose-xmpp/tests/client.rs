@@ -0,0 +1,271 @@
+// prose-core-client/prose-xmpp
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use minidom::Element;
+use parking_lot::RwLock;
+
+use prose_xmpp::client::Event as ClientEvent;
+use prose_xmpp::test::{ClientTestAdditions, ConnectedClient, Connection, Connector};
+use prose_xmpp::{ns, Client, ConnectionError, DisconnectReason, Event, FullJid};
+
+fn stream_error(condition: &str) -> Element {
+    Element::from_str(&format!(
+        r#"<error xmlns="http://etherx.jabber.org/streams">
+            <{condition} xmlns="urn:ietf:params:xml:ns:xmpp-streams"/>
+            <text xmlns="urn:ietf:params:xml:ns:xmpp-streams">Some text</text>
+        </error>"#
+    ))
+    .unwrap()
+}
+
+async fn disconnect_with_stream_error(condition: &str) -> Result<Vec<Event>> {
+    let ConnectedClient {
+        connection,
+        sent_events,
+        ..
+    } = Client::connected_client().await?;
+
+    connection.receive_stanza(stream_error(condition)).await;
+    assert!(sent_events.read().is_empty());
+
+    connection
+        .simulate_disconnect(Some(ConnectionError::Generic {
+            msg: "Stream closed".to_string(),
+        }))
+        .await;
+
+    let events = sent_events.read().clone();
+    Ok(events)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_maps_conflict_stream_error() -> Result<()> {
+    let events = disconnect_with_stream_error("conflict").await?;
+
+    assert_eq!(
+        events,
+        vec![Event::Client(ClientEvent::Disconnected {
+            error: Some(ConnectionError::StreamError {
+                reason: DisconnectReason::Conflict
+            })
+        })]
+    );
+    assert_eq!(DisconnectReason::Conflict.reconnect_delay(), None);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_maps_system_shutdown_stream_error() -> Result<()> {
+    let events = disconnect_with_stream_error("system-shutdown").await?;
+
+    assert_eq!(
+        events,
+        vec![Event::Client(ClientEvent::Disconnected {
+            error: Some(ConnectionError::StreamError {
+                reason: DisconnectReason::SystemShutdown
+            })
+        })]
+    );
+    assert_eq!(
+        DisconnectReason::SystemShutdown.reconnect_delay(),
+        Some(Duration::from_secs(30))
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_maps_policy_violation_stream_error() -> Result<()> {
+    let events = disconnect_with_stream_error("policy-violation").await?;
+
+    assert_eq!(
+        events,
+        vec![Event::Client(ClientEvent::Disconnected {
+            error: Some(ConnectionError::StreamError {
+                reason: DisconnectReason::PolicyViolation
+            })
+        })]
+    );
+    assert!(!DisconnectReason::PolicyViolation.should_reconnect());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_maps_unknown_stream_error() -> Result<()> {
+    let events = disconnect_with_stream_error("connection-timeout").await?;
+
+    let reason = DisconnectReason::Other {
+        condition: "connection-timeout".to_string(),
+    };
+    assert!(reason.should_reconnect());
+    assert_eq!(
+        events,
+        vec![Event::Client(ClientEvent::Disconnected {
+            error: Some(ConnectionError::StreamError { reason })
+        })]
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_keeps_connection_error_without_stream_error() -> Result<()> {
+    let ConnectedClient {
+        connection,
+        sent_events,
+        ..
+    } = Client::connected_client().await?;
+
+    connection
+        .simulate_disconnect(Some(ConnectionError::TimedOut))
+        .await;
+
+    assert_eq!(
+        *sent_events.read(),
+        vec![Event::Client(ClientEvent::Disconnected {
+            error: Some(ConnectionError::TimedOut)
+        })]
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_does_not_enable_stream_management_if_unsupported_by_server() -> Result<()> {
+    let connection = Connection::default();
+    let client = client_with_stream_management(&connection);
+
+    client
+        .connect(&FullJid::from_str("user@prose.org/res")?, "".into())
+        .await?;
+
+    assert!(connection.sent_stanzas().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_enables_stream_management_without_resumption() -> Result<()> {
+    let connection = Connection::default();
+    connection.set_supports_stream_management(true);
+    let client = client_with_stream_management(&connection);
+
+    client
+        .connect(&FullJid::from_str("user@prose.org/res")?, "".into())
+        .await?;
+
+    // The test connector can't resume sessions, so there's no point in asking for it…
+    assert_eq!(
+        connection.sent_stanzas(),
+        vec![Element::builder("enable", ns::SM).build()]
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_updates_sender_of_unacknowledged_stanzas() -> Result<()> {
+    let connection = Connection::default();
+    connection.set_supports_stream_management(true);
+    let client = client_with_stream_management(&connection);
+
+    client
+        .connect(&FullJid::from_str("user@prose.org/res1")?, "".into())
+        .await?;
+    connection
+        .receive_stanza(Element::from_str(
+            r#"<enabled xmlns="urn:xmpp:sm:3" id="session-1"/>"#,
+        )?)
+        .await;
+
+    client.send_raw_stanza(Element::from_str(
+        r#"<message xmlns="jabber:client" from="user@prose.org/res1" id="msg-1" to="other@prose.org"/>"#,
+    )?)?;
+
+    connection.simulate_disconnect(None).await;
+    client
+        .connect(&FullJid::from_str("user@prose.org/res2")?, "".into())
+        .await?;
+
+    assert_eq!(
+        client.take_unacknowledged_stanzas(),
+        vec![Element::from_str(
+            r#"<message xmlns="jabber:client" from="user@prose.org/res2" id="msg-1" to="other@prose.org"/>"#,
+        )?]
+    );
+    assert!(client.take_unacknowledged_stanzas().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_dispatches_acknowledged_messages() -> Result<()> {
+    let connection = Connection::default();
+    connection.set_supports_stream_management(true);
+    let sent_events = Arc::new(RwLock::new(vec![]));
+
+    let client = {
+        let sent_events = sent_events.clone();
+        Client::builder()
+            .set_connector_provider(Connector::provider(connection.clone()))
+            .set_stream_management_enabled(true)
+            .set_event_handler(move |_, event| {
+                sent_events.write().push(event);
+                async {}
+            })
+            .build()
+    };
+
+    client
+        .connect(&FullJid::from_str("user@prose.org/res")?, "".into())
+        .await?;
+    connection
+        .receive_stanza(Element::from_str(
+            r#"<enabled xmlns="urn:xmpp:sm:3" id="session-1"/>"#,
+        )?)
+        .await;
+    connection.reset();
+    sent_events.write().clear();
+
+    assert!(client.is_stream_management_active());
+
+    client.send_raw_stanza(Element::from_str(
+        r#"<message xmlns="jabber:client" from="user@prose.org/res" id="msg-1" to="other@prose.org"/>"#,
+    )?)?;
+
+    // Sent messages are followed by an ack request…
+    assert_eq!(
+        connection.sent_stanzas()[1],
+        Element::builder("r", ns::SM).build()
+    );
+
+    connection
+        .receive_stanza(Element::from_str(r#"<a xmlns="urn:xmpp:sm:3" h="1"/>"#)?)
+        .await;
+
+    assert_eq!(
+        sent_events.read().clone(),
+        vec![Event::Client(ClientEvent::MessagesAcknowledged {
+            ids: vec!["msg-1".into()]
+        })]
+    );
+
+    Ok(())
+}
+
+fn client_with_stream_management(connection: &Connection) -> Client {
+    Client::builder()
+        .set_connector_provider(Connector::provider(connection.clone()))
+        .set_stream_management_enabled(true)
+        .set_event_handler(|_, _| async {})
+        .build()
+}
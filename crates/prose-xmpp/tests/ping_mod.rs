@@ -0,0 +1,183 @@
+// prose-core-client/prose-xmpp
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+use parking_lot::RwLock;
+use tokio::task::JoinHandle;
+use xmpp_parsers::iq::{Iq, IqType};
+
+use prose_xmpp::client::Event as ClientEvent;
+use prose_xmpp::test::{Connection, Connector};
+use prose_xmpp::{jid, Client, Event, FullJid, TimeProvider};
+
+#[derive(Clone)]
+struct MockTimeProvider(Arc<RwLock<DateTime<Utc>>>);
+
+impl MockTimeProvider {
+    fn advance(&self, delta: TimeDelta) {
+        let mut now = self.0.write();
+        *now = *now + delta;
+    }
+}
+
+impl TimeProvider for MockTimeProvider {
+    fn now(&self) -> DateTime<Utc> {
+        *self.0.read()
+    }
+}
+
+struct PingTestClient {
+    client: Client,
+    connection: Connection,
+    time_provider: MockTimeProvider,
+    sent_events: Arc<RwLock<Vec<Event>>>,
+}
+
+impl PingTestClient {
+    async fn connected() -> Result<Self> {
+        let connection = Connection::default();
+        let time_provider = MockTimeProvider(Arc::new(RwLock::new(
+            Utc.with_ymd_and_hms(2024, 05, 01, 0, 0, 0).unwrap(),
+        )));
+        let sent_events = Arc::new(RwLock::new(vec![]));
+
+        let client = {
+            let sent_events = sent_events.clone();
+            Client::builder()
+                .set_connector_provider(Connector::provider(connection.clone()))
+                .set_time_provider(time_provider.clone())
+                .set_event_handler(move |_, event| {
+                    sent_events.write().push(event);
+                    async {}
+                })
+                .build()
+        };
+
+        let client = Self {
+            client,
+            connection,
+            time_provider,
+            sent_events,
+        };
+        client.connect().await?;
+
+        Ok(client)
+    }
+
+    async fn connect(&self) -> Result<()> {
+        self.client
+            .connect(&FullJid::from_str("user@prose.org/res")?, "".into())
+            .await?;
+        self.connection.reset();
+        self.sent_events.write().clear();
+        Ok(())
+    }
+
+    /// Fires the ping timer and returns the id of the ping sent to the server.
+    async fn send_ping(&self) -> (String, JoinHandle<()>) {
+        let connection = self.connection.clone();
+        let handle = tokio::spawn(async move { connection.simulate_ping_timer().await });
+
+        loop {
+            if let Some(id) = self
+                .connection
+                .sent_stanzas()
+                .iter()
+                .find(|stanza| stanza.name() == "iq")
+                .and_then(|stanza| stanza.attr("id"))
+            {
+                return (id.to_string(), handle);
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
+    async fn miss_ping(&self) -> Result<()> {
+        let (_, handle) = self.send_ping().await;
+        self.time_provider.advance(TimeDelta::seconds(20));
+        self.connection.simulate_timeout_timer().await;
+        handle.await?;
+        self.connection.reset();
+        Ok(())
+    }
+
+    fn ping_events(&self) -> Vec<ClientEvent> {
+        self.sent_events
+            .read()
+            .iter()
+            .filter_map(|event| match event {
+                Event::Client(event @ ClientEvent::PingAnswered { .. })
+                | Event::Client(event @ ClientEvent::PingTimeout) => Some(event.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_reports_round_trip_time_of_answered_ping() -> Result<()> {
+    let client = PingTestClient::connected().await?;
+
+    let (id, handle) = client.send_ping().await;
+    client.time_provider.advance(TimeDelta::milliseconds(120));
+    client
+        .connection
+        .receive_stanza(Iq {
+            from: Some(jid!("prose.org")),
+            to: Some(jid!("user@prose.org/res")),
+            id,
+            payload: IqType::Result(None),
+        })
+        .await;
+    handle.await?;
+
+    assert_eq!(
+        client.ping_events(),
+        vec![ClientEvent::PingAnswered {
+            round_trip_time: Duration::from_millis(120)
+        }]
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_disconnects_after_second_missed_ping() -> Result<()> {
+    let client = PingTestClient::connected().await?;
+
+    client.miss_ping().await?;
+    assert_eq!(client.ping_events(), vec![ClientEvent::PingTimeout]);
+
+    // The second consecutive timeout closes the connection instead of being reported…
+    client.miss_ping().await?;
+    assert_eq!(client.ping_events(), vec![ClientEvent::PingTimeout]);
+
+    // …so that no further pings are sent.
+    client.connection.simulate_ping_timer().await;
+    assert!(client.connection.sent_stanzas().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_resets_missed_pings_on_connect() -> Result<()> {
+    let client = PingTestClient::connected().await?;
+
+    client.miss_ping().await?;
+    assert_eq!(client.ping_events(), vec![ClientEvent::PingTimeout]);
+
+    client.connect().await?;
+
+    // The new connection starts with a clean slate, so the timeout is only reported…
+    client.miss_ping().await?;
+    assert_eq!(client.ping_events(), vec![ClientEvent::PingTimeout]);
+
+    Ok(())
+}
@@ -25,7 +25,7 @@ use url::Url;
 
 use common::{enable_debug_logging, load_credentials, Level};
 use prose_core_client::dtos::{
-    Address, Attachment, AttachmentType, Availability, Avatar, ParticipantId, RoomEnvelope, RoomId,
+    Address, AttachmentType, Availability, Avatar, ParticipantId, RoomEnvelope, RoomId,
     SendMessageRequest, SendMessageRequestBody, UploadSlot, UserId,
 };
 use prose_core_client::infra::encryption::{EncryptionKeysRepository, SessionRepository};
@@ -417,7 +417,7 @@ async fn reply_to_message(client: &Client) -> Result<()> {
     };
 
     room.to_generic_room()
-        .reply_to_message(message.id, request)
+        .reply_in_thread(message.id, request)
         .await
 }
 
@@ -438,18 +438,16 @@ async fn collect_send_message_request(
     let mut request = SendMessageRequest {
         body: (!body.text.as_ref().is_empty()).then_some(body),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     };
 
     while let Some(file) = select_file("Path to attachment (Press enter to skip)") {
         let slot = upload_file(client, &file).await?;
 
-        request.attachments.push(Attachment {
-            r#type: AttachmentType::File,
-            url: slot.download_url,
-            media_type: slot.media_type,
-            file_name: slot.file_name,
-            file_size: Some(slot.file_size),
-        });
+        request
+            .attachments
+            .push(slot.into_attachment(AttachmentType::File));
     }
 
     Ok(Some(request))
@@ -631,8 +629,6 @@ enum Selection {
     JoinRoomByJid,
     #[strum(serialize = "Leave room")]
     LeaveRoom,
-    #[strum(serialize = "Destroy public room")]
-    DestroyPublicRoom,
     #[strum(serialize = "Destroy connected room")]
     DestroyConnectedRoom,
     #[strum(serialize = "List connected rooms")]
@@ -709,7 +705,11 @@ async fn main() -> Result<()> {
                 ) else {
                     continue;
                 };
-                client.account.set_availability(availability).await?;
+                let status_message = prompt_opt_string("Status message", None);
+                client
+                    .account
+                    .set_availability(availability, status_message)
+                    .await?;
             }
             Selection::LoadUserAvatar => {
                 let Some(room) = select_room(&client, |_| true).await? else {
@@ -735,7 +735,10 @@ async fn main() -> Result<()> {
             }
             Selection::AddContact => {
                 let jid = prompt_bare_jid(None);
-                client.contact_list.add_contact(&jid.into()).await?;
+                client
+                    .contact_list
+                    .add_contact(&jid.into(), None, &[], true)
+                    .await?;
             }
             Selection::RemoveContact => {
                 let Some(contact) = select_contact(&client).await? else {
@@ -809,7 +812,7 @@ async fn main() -> Result<()> {
                     Response::Approve => {
                         client
                             .contact_list
-                            .approve_presence_sub_request(&req.id)
+                            .approve_presence_sub_request(&req.id, true)
                             .await?
                     }
                     Response::Deny => {
@@ -841,6 +844,8 @@ async fn main() -> Result<()> {
                                 text: format!("Message {idx}").into(),
                             }),
                             attachments: vec![],
+                            reply_to: None,
+                            thread_id: None,
                         })
                         .await?;
                     idx += 1;
@@ -868,6 +873,8 @@ async fn main() -> Result<()> {
                     .send_message(SendMessageRequest {
                         body: Some(SendMessageRequestBody { text: body.into() }),
                         attachments: vec![],
+                        reply_to: None,
+                        thread_id: None,
                     })
                     .await?;
             }
@@ -902,6 +909,8 @@ async fn main() -> Result<()> {
                     SendMessageRequest {
                         body: Some(SendMessageRequestBody { text: body.into() }),
                         attachments: vec![],
+                        reply_to: None,
+                        thread_id: None,
                     },
                 )
                 .await?;
@@ -962,40 +971,15 @@ async fn main() -> Result<()> {
                     .remove_from_sidebar(room.room.to_generic_room().jid())
                     .await?;
             }
-            Selection::DestroyPublicRoom => {
-                let rooms = client
-                    .rooms
-                    .load_public_rooms()
-                    .await?
-                    .into_iter()
-                    .map(JidWithName::from)
-                    .collect::<Vec<_>>();
-
-                if rooms.is_empty() {
-                    println!("No rooms to destroy");
-                    continue;
-                }
-
-                let selection = Select::with_theme(&ColorfulTheme::default())
-                    .with_prompt("Select a room to destroy")
-                    .default(0)
-                    .items(rooms.as_slice())
-                    .interact()
-                    .unwrap();
-                println!();
-                client
-                    .rooms
-                    .destroy_room(&rooms[selection].jid.clone().into())
-                    .await?;
-            }
             Selection::DestroyConnectedRoom => {
-                let Some(room) = select_room(&client, |_| true).await? else {
+                let Some(room) = select_room(&client, |item| {
+                    item.room.to_generic_room().jid().is_muc_room()
+                })
+                .await?
+                else {
                     continue;
                 };
-                client
-                    .rooms
-                    .destroy_room(room.to_generic_room().muc_id())
-                    .await?;
+                room.to_generic_room().destroy(None, None).await?;
             }
             Selection::ListConnectedRooms => {
                 list_connected_rooms(&client).await?;
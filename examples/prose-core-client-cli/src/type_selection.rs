@@ -12,8 +12,8 @@ use dialoguer::{Input, MultiSelect, Select};
 use jid::BareJid;
 
 use prose_core_client::dtos::{
-    DeviceId, Message, MessageId, MessageServerId, ParticipantInfo, PublicRoomInfo, RoomEnvelope,
-    SidebarItem, UserId,
+    DeviceId, Message, MessageCursor, MessageId, MessageServerId, ParticipantInfo, PublicRoomInfo,
+    RoomEnvelope, SidebarItem, UserId,
 };
 use prose_core_client::services::{Generic, Room};
 use prose_core_client::Client;
@@ -199,22 +199,22 @@ pub fn select_file(prompt: &str) -> Option<PathBuf> {
 }
 
 pub async fn load_messages(room: &Room<Generic>, pages: u32) -> Result<Vec<Message>> {
-    let mut stanza_id: Option<MessageId> = None;
+    let mut cursor: Option<MessageCursor> = None;
     let mut messages = vec![];
     let mut page = 0;
 
     loop {
-        let result_set = if let Some(stanza_id) = &stanza_id {
-            room.load_messages_before(stanza_id).await
+        let result_set = if let Some(cursor) = &cursor {
+            room.load_messages_before(cursor).await
         } else {
             room.load_latest_messages().await
         }?;
 
-        stanza_id = result_set.last_message_id.clone();
+        cursor = result_set.before.clone();
         messages.extend(&mut result_set.into_iter().rev());
         page += 1;
 
-        if page == pages || stanza_id.is_none() {
+        if page == pages || cursor.is_none() {
             break;
         }
     }
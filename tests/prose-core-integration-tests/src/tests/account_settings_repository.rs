@@ -10,12 +10,14 @@ use prose_core_client::domain::settings::models::AccountSettings;
 use prose_core_client::domain::settings::repos::AccountSettingsRepository as DomainAccountSettingsRepository;
 use prose_core_client::domain::shared::models::{AccountId, Availability};
 use prose_core_client::infra::settings::AccountSettingsRepository;
+use prose_proc_macros::store_test;
+use prose_store::prelude::{PlatformDriver, Store};
 
-use crate::tests::{async_test, store};
+use crate::tests::{async_test, in_memory_store, store};
 
-#[async_test]
-async fn test_save_and_load_account_settings() -> Result<()> {
-    let repo = AccountSettingsRepository::new(store().await?);
+#[store_test]
+async fn test_save_and_load_account_settings(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = AccountSettingsRepository::new(store);
 
     assert_eq!(
         repo.get(&account_id!("a@prose.org")).await?,
@@ -32,7 +34,10 @@ async fn test_save_and_load_account_settings() -> Result<()> {
 
     let expected_settings = AccountSettings {
         availability: Availability::Away,
+        status_message: None,
         resource: None,
+        send_read_receipts: true,
+        user_status: None,
     };
     assert_ne!(expected_settings, AccountSettings::default());
 
@@ -0,0 +1,442 @@
+// prose-core-client/prose-core-integration-tests
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use pretty_assertions::assert_eq;
+
+use prose_core_client::domain::shared::models::{
+    AccountId, AvatarId, ParticipantIdRef, StoreUsage, UserId,
+};
+use prose_core_client::domain::user_info::models::{AvatarInfo, AvatarSize};
+use prose_core_client::domain::user_info::repos::AvatarRepository;
+#[cfg(not(target_arch = "wasm32"))]
+use prose_core_client::infra::user_info::FsAvatarRepository;
+use prose_core_client::infra::user_info::InMemoryAvatarRepository;
+#[cfg(target_arch = "wasm32")]
+use prose_core_client::infra::user_info::StoreAvatarRepository;
+use prose_core_client::{account_id, user_id};
+#[cfg(target_arch = "wasm32")]
+use prose_proc_macros::store_test;
+#[cfg(target_arch = "wasm32")]
+use prose_store::prelude::{PlatformDriver, Store};
+use prose_xmpp::mods::AvatarData;
+
+use crate::tests::async_test;
+#[cfg(target_arch = "wasm32")]
+use crate::tests::{in_memory_store, store};
+
+#[async_test]
+async fn test_in_memory_repository_evicts_least_recently_used_avatars() -> Result<()> {
+    let repo = InMemoryAvatarRepository::new(25);
+    let account = account_id!("account@prose.org");
+    let updated_at = Utc.with_ymd_and_hms(2024, 05, 24, 10, 00, 00).unwrap();
+    let data = AvatarData::Data(vec![0; 10].into_boxed_slice());
+    let avatar_info = |checksum: &str| AvatarInfo {
+        checksum: AvatarId::from_str_unchecked(checksum),
+        mime_type: "image/png".to_string(),
+    };
+
+    for (user, checksum) in [("a@prose.org", "aaa"), ("b@prose.org", "bbb")] {
+        repo.set(
+            &account,
+            ParticipantIdRef::User(&user_id!(user)),
+            &avatar_info(checksum),
+            &data,
+            updated_at,
+        )
+        .await?;
+    }
+
+    // Accessing the avatar of a makes b the least recently used one…
+    assert!(repo
+        .get(
+            &account,
+            ParticipantIdRef::User(&user_id!("a@prose.org")),
+            &AvatarId::from_str_unchecked("aaa"),
+            AvatarSize::Full
+        )
+        .await?
+        .is_some());
+
+    repo.set(
+        &account,
+        ParticipantIdRef::User(&user_id!("c@prose.org")),
+        &avatar_info("ccc"),
+        &data,
+        updated_at,
+    )
+    .await?;
+
+    for (user, checksum, is_cached) in [
+        ("a@prose.org", "aaa", true),
+        ("b@prose.org", "bbb", false),
+        ("c@prose.org", "ccc", true),
+    ] {
+        assert_eq!(
+            is_cached,
+            repo.get(
+                &account,
+                ParticipantIdRef::User(&user_id!(user)),
+                &AvatarId::from_str_unchecked(checksum),
+                AvatarSize::Full
+            )
+            .await?
+            .is_some(),
+            "Unexpected cache state for {user}"
+        );
+    }
+
+    assert_eq!(
+        StoreUsage { rows: 2, bytes: 20 },
+        repo.usage(&account).await?
+    );
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_in_memory_repository_prunes_outdated_avatars() -> Result<()> {
+    let repo = InMemoryAvatarRepository::new(1024);
+    let data = AvatarData::Data(vec![0; 10].into_boxed_slice());
+
+    for (account, user, checksum, day) in [
+        ("account@prose.org", "a@prose.org", "aaa", 1),
+        ("account@prose.org", "b@prose.org", "bbb", 20),
+        ("other@prose.org", "a@prose.org", "ccc", 1),
+    ] {
+        repo.set(
+            &account_id!(account),
+            ParticipantIdRef::User(&user_id!(user)),
+            &AvatarInfo {
+                checksum: AvatarId::from_str_unchecked(checksum),
+                mime_type: "image/png".to_string(),
+            },
+            &data,
+            Utc.with_ymd_and_hms(2024, 05, day, 10, 00, 00).unwrap(),
+        )
+        .await?;
+    }
+
+    assert_eq!(
+        1,
+        repo.prune(
+            &account_id!("account@prose.org"),
+            Utc.with_ymd_and_hms(2024, 05, 10, 00, 00, 00).unwrap()
+        )
+        .await?
+    );
+
+    // Only the outdated avatar of the pruned account is gone…
+    for (account, user, checksum, is_cached) in [
+        ("account@prose.org", "a@prose.org", "aaa", false),
+        ("account@prose.org", "b@prose.org", "bbb", true),
+        ("other@prose.org", "a@prose.org", "ccc", true),
+    ] {
+        assert_eq!(
+            is_cached,
+            repo.get(
+                &account_id!(account),
+                ParticipantIdRef::User(&user_id!(user)),
+                &AvatarId::from_str_unchecked(checksum),
+                AvatarSize::Full
+            )
+            .await?
+            .is_some(),
+            "Unexpected cache state for {user} in {account}"
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_test]
+async fn test_fs_repository_prunes_outdated_avatars() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let repo = FsAvatarRepository::new(dir.path())?;
+    let account = account_id!("account@prose.org");
+
+    for (user, checksum, day) in [("a@prose.org", "aaa", 1), ("b@prose.org", "bbb", 20)] {
+        repo.set(
+            &account,
+            ParticipantIdRef::User(&user_id!(user)),
+            &png_avatar_info(checksum),
+            &png_avatar_data(),
+            Utc.with_ymd_and_hms(2024, 05, day, 10, 00, 00).unwrap(),
+        )
+        .await?;
+    }
+
+    // Creates a downscaled variant of the outdated avatar, which is pruned along with it…
+    assert!(repo
+        .get(
+            &account,
+            ParticipantIdRef::User(&user_id!("a@prose.org")),
+            &AvatarId::from_str_unchecked("aaa"),
+            AvatarSize::Small
+        )
+        .await?
+        .is_some());
+
+    // The files were all written just now, so only the stored dates tell them apart.
+    assert_eq!(
+        2,
+        repo.prune(
+            &account,
+            Utc.with_ymd_and_hms(2024, 05, 10, 00, 00, 00).unwrap()
+        )
+        .await?
+    );
+
+    for (user, checksum, size, is_cached) in [
+        ("a@prose.org", "aaa", AvatarSize::Full, false),
+        ("a@prose.org", "aaa", AvatarSize::Small, false),
+        ("b@prose.org", "bbb", AvatarSize::Full, true),
+    ] {
+        assert_eq!(
+            is_cached,
+            repo.get(
+                &account,
+                ParticipantIdRef::User(&user_id!(user)),
+                &AvatarId::from_str_unchecked(checksum),
+                size
+            )
+            .await?
+            .is_some(),
+            "Unexpected cache state for {user} ({size:?})"
+        );
+    }
+
+    assert_eq!(1, repo.usage(&account).await?.rows);
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_test]
+async fn test_fs_repository_keeps_avatars_of_other_accounts() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let repo = FsAvatarRepository::new(dir.path())?;
+    let user = user_id!("user@prose.org");
+    let updated_at = Utc.with_ymd_and_hms(2024, 05, 24, 10, 00, 00).unwrap();
+
+    for (account, checksum) in [("a@prose.org", "aaa"), ("b@prose.org", "bbb")] {
+        repo.set(
+            &account_id!(account),
+            ParticipantIdRef::User(&user),
+            &png_avatar_info(checksum),
+            &png_avatar_data(),
+            updated_at,
+        )
+        .await?;
+    }
+
+    // Each account still sees the avatar it received last…
+    for (account, checksum) in [("a@prose.org", "aaa"), ("b@prose.org", "bbb")] {
+        assert!(
+            repo.get(
+                &account_id!(account),
+                ParticipantIdRef::User(&user),
+                &AvatarId::from_str_unchecked(checksum),
+                AvatarSize::Full
+            )
+            .await?
+            .is_some(),
+            "Missing avatar for {account}"
+        );
+    }
+
+    repo.clear_cache(&account_id!("a@prose.org")).await?;
+
+    // …and clearing the cache of one account leaves the other one untouched.
+    assert!(repo
+        .get(
+            &account_id!("a@prose.org"),
+            ParticipantIdRef::User(&user),
+            &AvatarId::from_str_unchecked("aaa"),
+            AvatarSize::Full
+        )
+        .await?
+        .is_none());
+    assert!(repo
+        .get(
+            &account_id!("b@prose.org"),
+            ParticipantIdRef::User(&user),
+            &AvatarId::from_str_unchecked("bbb"),
+            AvatarSize::Full
+        )
+        .await?
+        .is_some());
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_test]
+async fn test_fs_repository_removes_stale_avatars() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let repo = FsAvatarRepository::new(dir.path())?;
+    let account = account_id!("account@prose.org");
+    let user = user_id!("user@prose.org");
+    let updated_at = Utc.with_ymd_and_hms(2024, 05, 24, 10, 00, 00).unwrap();
+
+    repo.set(
+        &account,
+        ParticipantIdRef::User(&user),
+        &png_avatar_info("aaa"),
+        &png_avatar_data(),
+        updated_at,
+    )
+    .await?;
+
+    // Creates the downscaled variant of the first avatar…
+    assert!(repo
+        .get(
+            &account,
+            ParticipantIdRef::User(&user),
+            &AvatarId::from_str_unchecked("aaa"),
+            AvatarSize::Small
+        )
+        .await?
+        .is_some());
+
+    repo.set(
+        &account,
+        ParticipantIdRef::User(&user),
+        &png_avatar_info("bbb"),
+        &png_avatar_data(),
+        updated_at,
+    )
+    .await?;
+
+    for (checksum, size, is_cached) in [
+        ("aaa", AvatarSize::Full, false),
+        ("aaa", AvatarSize::Small, false),
+        ("bbb", AvatarSize::Full, true),
+    ] {
+        let path = repo
+            .get(
+                &account,
+                ParticipantIdRef::User(&user),
+                &AvatarId::from_str_unchecked(checksum),
+                size,
+            )
+            .await?;
+        assert_eq!(
+            is_cached,
+            path.is_some(),
+            "Unexpected cache state for {checksum} ({size:?})"
+        );
+    }
+
+    assert_eq!(1, repo.usage(&account).await?.rows);
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_test]
+async fn test_fs_repository_evicts_avatars_exceeding_usage() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let repo = FsAvatarRepository::new(dir.path())?;
+    let account = account_id!("account@prose.org");
+    let updated_at = Utc.with_ymd_and_hms(2024, 05, 24, 10, 00, 00).unwrap();
+
+    for (user, checksum) in [("a@prose.org", "aaa"), ("b@prose.org", "bbb")] {
+        repo.set(
+            &account,
+            ParticipantIdRef::User(&user_id!(user)),
+            &png_avatar_info(checksum),
+            &png_avatar_data(),
+            updated_at,
+        )
+        .await?;
+    }
+
+    let usage = repo.usage(&account).await?;
+    assert_eq!(2, usage.rows);
+
+    // The cache fits exactly into the reported usage…
+    assert_eq!(0, repo.evict(&account, usage.bytes).await?);
+    assert_eq!(usage, repo.usage(&account).await?);
+
+    assert_eq!(2, repo.evict(&account, 0).await?);
+    assert_eq!(StoreUsage::default(), repo.usage(&account).await?);
+
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+#[store_test]
+async fn test_store_repository_evicts_oldest_avatars_exceeding_usage(
+    store: Store<PlatformDriver>,
+) -> Result<()> {
+    let repo = StoreAvatarRepository::new(store);
+    let account = account_id!("account@prose.org");
+
+    for (user, checksum, day) in [
+        ("a@prose.org", "aaa", 20),
+        ("b@prose.org", "bbb", 1),
+        ("c@prose.org", "ccc", 10),
+    ] {
+        repo.set(
+            &account,
+            ParticipantIdRef::User(&user_id!(user)),
+            &png_avatar_info(checksum),
+            &png_avatar_data(),
+            Utc.with_ymd_and_hms(2024, 05, day, 10, 00, 00).unwrap(),
+        )
+        .await?;
+    }
+
+    let usage = repo.usage(&account).await?;
+    assert_eq!(3, usage.rows);
+
+    // The cache fits exactly into the reported usage…
+    assert_eq!(0, repo.evict(&account, usage.bytes).await?);
+    assert_eq!(usage, repo.usage(&account).await?);
+
+    // …but a single byte less evicts the avatar that was updated least recently.
+    assert_eq!(1, repo.evict(&account, usage.bytes - 1).await?);
+
+    for (user, checksum, is_cached) in [
+        ("a@prose.org", "aaa", true),
+        ("b@prose.org", "bbb", false),
+        ("c@prose.org", "ccc", true),
+    ] {
+        assert_eq!(
+            is_cached,
+            repo.get(
+                &account,
+                ParticipantIdRef::User(&user_id!(user)),
+                &AvatarId::from_str_unchecked(checksum),
+                AvatarSize::Full
+            )
+            .await?
+            .is_some(),
+            "Unexpected cache state for {user}"
+        );
+    }
+
+    assert_eq!(2, repo.usage(&account).await?.rows);
+
+    Ok(())
+}
+
+fn png_avatar_info(checksum: &str) -> AvatarInfo {
+    AvatarInfo {
+        checksum: AvatarId::from_str_unchecked(checksum),
+        mime_type: "image/png".to_string(),
+    }
+}
+
+/// A transparent 1x1 PNG.
+fn png_avatar_data() -> AvatarData {
+    AvatarData::Base64(
+        "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg=="
+            .to_string(),
+    )
+}
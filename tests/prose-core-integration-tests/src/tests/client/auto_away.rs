@@ -0,0 +1,101 @@
+// prose-core-client/prose-core-integration-tests
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use chrono::Duration;
+use pretty_assertions::assert_eq;
+
+use prose_core_client::dtos::{Availability, UserId};
+use prose_core_client::{user_id, ClientEvent};
+use prose_proc_macros::mt_test;
+
+use crate::{event, recv, send};
+
+use super::helpers::TestClient;
+
+#[mt_test]
+async fn test_switches_to_auto_away_and_back() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    client
+        .account
+        .set_auto_away(Duration::minutes(5), Availability::Away)
+        .await?;
+
+    // Not idle long enough yet…
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 4, 0);
+    expect_server_ping(&client);
+    client.simulate_ping_timer().await;
+
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 6, 0);
+    expect_server_ping(&client);
+
+    send!(
+        client,
+        r#"
+        <presence xmlns='jabber:client'>
+            <show>away</show>
+            <c xmlns='http://jabber.org/protocol/caps' hash="sha-1" node="https://prose.org" ver="{{CAPS_HASH}}" />
+            <idle xmlns="urn:xmpp:idle:1" since="2024-02-19T00:00:00+00:00" />
+        </presence>"#
+    );
+    event!(client, ClientEvent::AccountInfoChanged);
+
+    client.simulate_ping_timer().await;
+
+    assert_eq!(
+        client.account.account_info().await?.availability,
+        Availability::Away
+    );
+
+    // The transition should only happen once…
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 8, 0);
+    expect_server_ping(&client);
+    client.simulate_ping_timer().await;
+
+    send!(
+        client,
+        r#"
+        <presence xmlns='jabber:client'>
+            <show>chat</show>
+            <c xmlns='http://jabber.org/protocol/caps' hash="sha-1" node="https://prose.org" ver="{{CAPS_HASH}}" />
+        </presence>"#
+    );
+    event!(client, ClientEvent::AccountInfoChanged);
+
+    client.account.report_user_activity().await?;
+
+    assert_eq!(
+        client.account.account_info().await?.availability,
+        Availability::Available
+    );
+
+    // Reporting activity while not being idle doesn't send anything.
+    client.account.report_user_activity().await?;
+
+    Ok(())
+}
+
+fn expect_server_ping(client: &TestClient) {
+    send!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" id="{{ID}}" type="get">
+          <ping xmlns="urn:xmpp:ping" />
+        </iq>
+        "#
+    );
+
+    recv!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="{{ID}}" to="{{USER_RESOURCE_ID}}" type="result" />
+        "#
+    );
+}
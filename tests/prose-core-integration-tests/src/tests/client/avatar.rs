@@ -301,12 +301,16 @@ async fn test_resolves_avatars_in_muc() -> Result<()> {
         room_event!(
             client,
             muc_id!("room@conference.prose.org"),
-            ClientRoomEventType::ParticipantsChanged
+            ClientRoomEventType::ParticipantsChanged {
+                ids: vec![occupant_id!("room@conference.prose.org/user").into()]
+            }
         );
         room_event!(
             client,
             muc_id!("room@conference.prose.org"),
-            ClientRoomEventType::ParticipantsChanged
+            ClientRoomEventType::ParticipantsChanged {
+                ids: vec![occupant_id!("room@conference.prose.org/user").into()]
+            }
         );
     }
     client.receive_next().await;
@@ -329,12 +333,16 @@ async fn test_resolves_avatars_in_muc() -> Result<()> {
         room_event!(
             client,
             muc_id!("room@conference.prose.org"),
-            ClientRoomEventType::ParticipantsChanged
+            ClientRoomEventType::ParticipantsChanged {
+                ids: vec![occupant_id!("room@conference.prose.org/them").into()]
+            }
         );
         room_event!(
             client,
             muc_id!("room@conference.prose.org"),
-            ClientRoomEventType::ParticipantsChanged
+            ClientRoomEventType::ParticipantsChanged {
+                ids: vec![occupant_id!("room@conference.prose.org/them").into()]
+            }
         );
     }
     client.receive_next().await;
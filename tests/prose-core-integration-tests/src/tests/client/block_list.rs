@@ -0,0 +1,100 @@
+// prose-core-client/prose-core-integration-tests
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+use prose_core_client::dtos::UserId;
+use prose_core_client::{user_id, ClientEvent};
+use prose_proc_macros::mt_test;
+
+use crate::{event, recv, send};
+
+use super::helpers::TestClient;
+
+#[mt_test]
+async fn test_blocks_and_unblocks_users() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    send!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="{{ID}}" type="set">
+            <block xmlns="urn:xmpp:blocking">
+                <item jid="spammer@prose.org" />
+            </block>
+        </iq>
+        "#
+    );
+    recv!(
+        client,
+        r#"<iq xmlns="jabber:client" id="{{ID}}" type="result" />"#
+    );
+    event!(client, ClientEvent::BlockListChanged);
+
+    client
+        .block_list
+        .block_user(&user_id!("spammer@prose.org"))
+        .await?;
+
+    assert_eq!(
+        vec![user_id!("spammer@prose.org")],
+        client.block_list.blocked_users().await?
+    );
+
+    // Another device of ours blocks a user…
+    recv!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="push-1" type="set">
+            <block xmlns="urn:xmpp:blocking">
+                <item jid="troll@prose.org" />
+            </block>
+        </iq>
+        "#
+    );
+    event!(client, ClientEvent::BlockListChanged);
+    send!(
+        client,
+        r#"<iq xmlns="jabber:client" id="push-1" type="result" />"#
+    );
+    client.receive_next().await;
+
+    let mut blocked_users = client.block_list.blocked_users().await?;
+    blocked_users.sort_by_key(|user_id| user_id.to_string());
+    assert_eq!(
+        vec![user_id!("spammer@prose.org"), user_id!("troll@prose.org")],
+        blocked_users
+    );
+
+    // …and unblocks the first one again.
+    recv!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="push-2" type="set">
+            <unblock xmlns="urn:xmpp:blocking">
+                <item jid="spammer@prose.org" />
+            </unblock>
+        </iq>
+        "#
+    );
+    event!(client, ClientEvent::BlockListChanged);
+    send!(
+        client,
+        r#"<iq xmlns="jabber:client" id="push-2" type="result" />"#
+    );
+    client.receive_next().await;
+
+    assert_eq!(
+        vec![user_id!("troll@prose.org")],
+        client.block_list.blocked_users().await?
+    );
+
+    Ok(())
+}
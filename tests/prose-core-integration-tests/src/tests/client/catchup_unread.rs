@@ -14,7 +14,7 @@ use prose_core_client::domain::messaging::models::{
 };
 use prose_core_client::domain::messaging::repos::MessagesRepository;
 use prose_core_client::domain::rooms::models::RoomSidebarState;
-use prose_core_client::domain::settings::models::SyncedRoomSettings;
+use prose_core_client::domain::settings::models::{RoomNotificationMode, SyncedRoomSettings};
 use prose_core_client::domain::shared::models::AccountId;
 use prose_core_client::domain::sidebar::models::BookmarkType;
 use prose_core_client::dtos::{Bookmark, Mention, MucId, OccupantId, RoomId, UserId};
@@ -78,6 +78,9 @@ async fn test_maintains_message_count_from_prior_runs() -> Result<()> {
             stanza_id: MessageBuilder::stanza_id_for_index(1),
             timestamp: Utc.with_ymd_and_hms(2024, 04, 25, 10, 00, 00).unwrap(),
         }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
     });
     join_room_strategy.expect_catchup = Box::new(|client, room_id| {
         client.expect_muc_catchup_with_config(
@@ -210,6 +213,9 @@ async fn test_rounds_timestamps() -> Result<()> {
                 // Timestamp should be rounded up…
                 timestamp: Utc.with_ymd_and_hms(2024, 04, 05, 10, 00, 01).unwrap(),
             }),
+            pinned_messages: vec![],
+            notifications: Default::default(),
+            send_read_receipts: true,
         });
 
         event!(client, ClientEvent::SidebarChanged);
@@ -316,6 +322,9 @@ async fn test_rounds_timestamps() -> Result<()> {
                                             .with_ymd_and_hms(2024, 04, 05, 10, 00, 01)
                                             .unwrap(),
                                     }),
+                                    pinned_messages: vec![],
+                                    notifications: Default::default(),
+                                    send_read_receipts: true,
                                 }),
                             )
                         })
@@ -529,6 +538,9 @@ async fn test_loads_unread_messages() -> Result<()> {
             stanza_id: MessageBuilder::stanza_id_for_index(1),
             timestamp: Utc.with_ymd_and_hms(2024, 04, 25, 10, 00, 00).unwrap(),
         }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
     });
 
     client
@@ -580,6 +592,7 @@ async fn test_updates_unread_count_after_sync() -> Result<()> {
                         is_transient: false,
                         reply_to: None,
                         thread_id: None,
+                        is_markable: false,
                     })
                     .build_message_like(),
                 MessageBuilder::new_with_index(2)
@@ -603,6 +616,7 @@ async fn test_updates_unread_count_after_sync() -> Result<()> {
                         is_transient: false,
                         reply_to: None,
                         thread_id: None,
+                        is_markable: false,
                     })
                     .build_message_like(),
             ],
@@ -691,6 +705,9 @@ async fn test_marks_first_unread_message() -> Result<()> {
                     stanza_id: MessageBuilder::stanza_id_for_index(2),
                     timestamp: Utc.with_ymd_and_hms(2024, 04, 26, 11, 00, 00).unwrap(),
                 }),
+                pinned_messages: vec![],
+                notifications: Default::default(),
+                send_read_receipts: true,
             }),
         )
     });
@@ -819,6 +836,9 @@ async fn test_mark_as_unread_saves_settings() -> Result<()> {
             stanza_id: MessageBuilder::stanza_id_for_index(1),
             timestamp: Utc.with_ymd_and_hms(2024, 04, 25, 10, 00, 00).unwrap(),
         }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
     });
 
     client
@@ -840,6 +860,9 @@ async fn test_mark_as_unread_saves_settings() -> Result<()> {
             stanza_id: MessageBuilder::stanza_id_for_index(3),
             timestamp: Utc.with_ymd_and_hms(2024, 04, 26, 11, 00, 00).unwrap(),
         }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
     });
 
     event!(client, ClientEvent::SidebarChanged);
@@ -907,6 +930,9 @@ async fn test_set_unread_message_saves_settings() -> Result<()> {
             stanza_id: MessageBuilder::stanza_id_for_index(1),
             timestamp: Utc.with_ymd_and_hms(2024, 04, 25, 10, 00, 00).unwrap(),
         }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
     });
 
     client
@@ -920,6 +946,9 @@ async fn test_set_unread_message_saves_settings() -> Result<()> {
             stanza_id: MessageBuilder::stanza_id_for_index(2),
             timestamp: messages[1].timestamp.clone(),
         }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
     });
 
     room_event!(
@@ -954,6 +983,9 @@ async fn test_set_unread_message_saves_settings() -> Result<()> {
             stanza_id: MessageBuilder::stanza_id_for_index(5),
             timestamp: messages[4].timestamp.clone(),
         }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
     });
 
     room_event!(
@@ -982,3 +1014,198 @@ async fn test_set_unread_message_saves_settings() -> Result<()> {
 
     Ok(())
 }
+
+#[mt_test]
+async fn test_mark_as_read_until_moves_read_marker_backwards() -> Result<()> {
+    let store = store().await.expect("Failed to set up store.");
+
+    let account = account_id!("user@prose.org");
+    let muc_id = muc_id!("room@conf.prose.org");
+    let room_id = RoomId::Muc(muc_id.clone());
+
+    let mut messages = [
+        MessageBuilder::new_with_index(1)
+            .set_from(occupant_id!("room@conf.prose.org/friend"))
+            .set_timestamp(Utc.with_ymd_and_hms(2024, 04, 25, 10, 00, 00).unwrap())
+            .build_message_like(),
+        MessageBuilder::new_with_index(2)
+            .set_from(occupant_id!("room@conf.prose.org/friend"))
+            .set_timestamp(Utc.with_ymd_and_hms(2024, 04, 26, 10, 00, 00).unwrap())
+            .build_message_like(),
+        MessageBuilder::new_with_index(3)
+            .set_from(occupant_id!("room@conf.prose.org/friend"))
+            .set_timestamp(Utc.with_ymd_and_hms(2024, 04, 27, 10, 00, 00).unwrap())
+            .build_message_like(),
+        MessageBuilder::new_with_index(4)
+            .set_from(occupant_id!("room@conf.prose.org/friend"))
+            .set_timestamp(Utc.with_ymd_and_hms(2024, 04, 28, 10, 00, 00).unwrap())
+            .build_message_like(),
+    ];
+
+    // Message 4 has not been received by the server yet.
+    messages[3].server_id = None;
+
+    let message_repo = CachingMessageRepository::new(store.clone());
+    message_repo.append(&account, &room_id, &messages).await?;
+
+    let client = TestClient::builder()
+        .set_store(store)
+        .set_time_provider(ConstantTimeProvider::new(Utc::now()))
+        .build()
+        .await;
+    client.expect_login(account.to_user_id(), "secret").await?;
+
+    let mut join_room_strategy = JoinRoomStrategy::default();
+    join_room_strategy.room_settings = Some(SyncedRoomSettings {
+        room_id: room_id.clone(),
+        encryption_enabled: false,
+        last_read_message: Some(ArchivedMessageRef {
+            stanza_id: MessageBuilder::stanza_id_for_index(3),
+            timestamp: messages[2].timestamp.clone(),
+        }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
+    });
+
+    client
+        .join_room_with_strategy(muc_id.clone(), "anon-id", join_room_strategy)
+        .await?;
+
+    let room = client.get_room(room_id.clone()).await.to_generic_room();
+
+    assert!(room
+        .mark_as_read_until(&MessageBuilder::id_for_index(4))
+        .await
+        .is_err());
+
+    client.expect_save_synced_room_settings(SyncedRoomSettings {
+        room_id: room_id.clone(),
+        encryption_enabled: false,
+        last_read_message: Some(ArchivedMessageRef {
+            stanza_id: MessageBuilder::stanza_id_for_index(1),
+            timestamp: messages[0].timestamp.clone(),
+        }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
+    });
+
+    room_event!(
+        client,
+        room_id.clone(),
+        ClientRoomEventType::MessagesUpdated {
+            message_ids: vec![
+                MessageBuilder::id_for_index(3),
+                MessageBuilder::id_for_index(1)
+            ]
+        }
+    );
+
+    event!(client, ClientEvent::SidebarChanged);
+
+    room.mark_as_read_until(&MessageBuilder::id_for_index(1))
+        .await?;
+
+    let sidebar_items = client.sidebar.sidebar_items().await;
+    let sidebar_item = sidebar_items
+        .get(0)
+        .expect("Expected at least one SidebarItem");
+    assert_eq!(3, sidebar_item.unread_count);
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_respects_room_notification_mode() -> Result<()> {
+    let store = store().await.expect("Failed to set up store.");
+
+    let account = account_id!("user@prose.org");
+    let muc_id = muc_id!("room@conf.prose.org");
+    let room_id = RoomId::Muc(muc_id.clone());
+
+    let message_repo = CachingMessageRepository::new(store.clone());
+    message_repo
+        .append(
+            &account,
+            &room_id,
+            &[
+                MessageBuilder::new_with_index(1)
+                    .set_from(occupant_id!("room@conf.prose.org/friend"))
+                    .set_timestamp(Utc.with_ymd_and_hms(2024, 04, 25, 10, 00, 00).unwrap())
+                    .build_message_like(),
+                MessageBuilder::new_with_index(2)
+                    .set_from(occupant_id!("room@conf.prose.org/friend"))
+                    .set_timestamp(Utc.with_ymd_and_hms(2024, 04, 26, 10, 00, 00).unwrap())
+                    .set_payload(MessageLikePayload::Message {
+                        body: MessageLikeBody::text("Hello @user").with_mentions([Mention {
+                            user: user_id!("user@prose.org"),
+                            range: None,
+                        }]),
+                        attachments: vec![],
+                        encryption_info: None,
+                        is_transient: false,
+                        reply_to: None,
+                        thread_id: None,
+                        is_markable: false,
+                    })
+                    .build_message_like(),
+                MessageBuilder::new_with_index(3)
+                    .set_from(occupant_id!("room@conf.prose.org/friend"))
+                    .set_timestamp(Utc.with_ymd_and_hms(2024, 04, 26, 11, 00, 00).unwrap())
+                    .build_message_like(),
+            ],
+        )
+        .await?;
+
+    let client = TestClient::builder()
+        .set_store(store)
+        .set_time_provider(ConstantTimeProvider::new(Utc::now()))
+        .build()
+        .await;
+    client.expect_login(account.to_user_id(), "secret").await?;
+
+    let settings = SyncedRoomSettings {
+        room_id: room_id.clone(),
+        encryption_enabled: false,
+        last_read_message: Some(ArchivedMessageRef {
+            stanza_id: MessageBuilder::stanza_id_for_index(1),
+            timestamp: Utc.with_ymd_and_hms(2024, 04, 25, 10, 00, 00).unwrap(),
+        }),
+        pinned_messages: vec![],
+        notifications: RoomNotificationMode::MentionsOnly,
+        send_read_receipts: true,
+    };
+
+    let mut join_room_strategy = JoinRoomStrategy::default();
+    join_room_strategy.room_settings = Some(settings.clone());
+
+    client
+        .join_room_with_strategy(muc_id.clone(), "anon-id", join_room_strategy)
+        .await?;
+
+    // Only the message mentioning us is counted…
+    let sidebar_items = client.sidebar.sidebar_items().await;
+    assert_eq!(1, sidebar_items[0].unread_count);
+    assert_eq!(1, sidebar_items[0].mentions_count);
+    assert_eq!(
+        RoomNotificationMode::MentionsOnly,
+        sidebar_items[0].notification_mode
+    );
+
+    client.expect_save_synced_room_settings(SyncedRoomSettings {
+        notifications: RoomNotificationMode::None,
+        ..settings
+    });
+    event!(client, ClientEvent::SidebarChanged);
+
+    let room = client.get_room(room_id).await.to_generic_room();
+    room.set_notification_mode(RoomNotificationMode::None).await;
+
+    // …and muted rooms are never unread.
+    let sidebar_items = client.sidebar.sidebar_items().await;
+    assert_eq!(0, sidebar_items[0].unread_count);
+    assert_eq!(0, sidebar_items[0].mentions_count);
+
+    Ok(())
+}
@@ -4,6 +4,7 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use anyhow::Result;
+use chrono::{TimeZone, Utc};
 use pretty_assertions::assert_eq;
 use xmpp_parsers::roster::{Item as RosterItem, Subscription};
 
@@ -13,7 +14,7 @@ use prose_proc_macros::mt_test;
 use prose_xmpp::bare;
 use prose_xmpp::stanza::{vcard4, VCard4};
 
-use crate::{event, recv};
+use crate::{event, recv, send};
 
 use super::helpers::{LoginStrategy, TestClient};
 
@@ -176,6 +177,8 @@ async fn test_contact_list_name_cascade() -> Result<()> {
                 avatar: None,
                 availability: Default::default(),
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::Mutual,
             },
@@ -186,6 +189,8 @@ async fn test_contact_list_name_cascade() -> Result<()> {
                 avatar: None,
                 availability: Default::default(),
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::Mutual,
             },
@@ -196,6 +201,8 @@ async fn test_contact_list_name_cascade() -> Result<()> {
                 avatar: None,
                 availability: Default::default(),
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Other,
                 presence_subscription: PresenceSubscription::Mutual,
             }
@@ -205,3 +212,438 @@ async fn test_contact_list_name_cascade() -> Result<()> {
 
     Ok(())
 }
+
+#[mt_test]
+async fn test_add_contact_requests_presence_sub() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    event!(client, ClientEvent::ContactListChanged);
+
+    send!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="{{ID}}" type="set">
+            <query xmlns="jabber:iq:roster">
+                <item jid="friend@prose.org" name="Jane Doe" />
+            </query>
+        </iq>
+        "#
+    );
+    recv!(
+        client,
+        r#"<iq xmlns="jabber:client" id="{{ID}}" type="result" />"#
+    );
+
+    send!(
+        client,
+        r#"<presence xmlns="jabber:client" to="friend@prose.org" type="subscribed" />"#
+    );
+    send!(
+        client,
+        r#"<presence xmlns="jabber:client" to="friend@prose.org" type="subscribe" />"#
+    );
+
+    event!(
+        client,
+        ClientEvent::ContactChanged {
+            ids: vec![user_id!("friend@prose.org")]
+        }
+    );
+
+    client
+        .contact_list
+        .add_contact(&user_id!("friend@prose.org"), Some("Jane Doe"), &[], true)
+        .await?;
+
+    let contacts = client.contact_list.load_contacts().await?;
+    assert_eq!(1, contacts.len());
+    assert_eq!(
+        PresenceSubscription::Requested,
+        contacts[0].presence_subscription
+    );
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_remove_contact() -> Result<()> {
+    let client = TestClient::new().await;
+
+    let strategy = LoginStrategy::default().with_roster_items([RosterItem {
+        jid: bare!("friend@prose.org"),
+        name: None,
+        subscription: Subscription::Both,
+        ask: Default::default(),
+        groups: vec![],
+    }]);
+
+    client
+        .expect_login_with_strategy(user_id!("user@prose.org"), "secret", strategy)
+        .await?;
+
+    assert_eq!(1, client.contact_list.load_contacts().await?.len());
+
+    event!(client, ClientEvent::ContactListChanged);
+
+    // Only the roster item is removed. No unsubscribe/unsubscribed presences are sent since the
+    // server cancels the subscriptions in both directions itself (RFC 6121 §3.3).
+    send!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="{{ID}}" type="set">
+            <query xmlns="jabber:iq:roster">
+                <item jid="friend@prose.org" subscription="remove" />
+            </query>
+        </iq>
+        "#
+    );
+    recv!(
+        client,
+        r#"<iq xmlns="jabber:client" id="{{ID}}" type="result" />"#
+    );
+
+    client
+        .contact_list
+        .remove_contact(&user_id!("friend@prose.org"))
+        .await?;
+
+    assert!(client.contact_list.load_contacts().await?.is_empty());
+    assert!(client
+        .contact_list
+        .load_presence_sub_requests()
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_approve_and_deny_presence_sub_requests() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    recv!(
+        client,
+        r#"
+        <presence xmlns="jabber:client" from="a@prose.org" to="{{USER_RESOURCE_ID}}" type="subscribe" xml:lang="en" />
+        "#
+    );
+    event!(client, ClientEvent::PresenceSubRequestsChanged);
+    client.receive_next().await;
+
+    recv!(
+        client,
+        r#"
+        <presence xmlns="jabber:client" from="b@prose.org" to="{{USER_RESOURCE_ID}}" type="subscribe" xml:lang="en" />
+        "#
+    );
+    event!(client, ClientEvent::PresenceSubRequestsChanged);
+    client.receive_next().await;
+
+    send!(
+        client,
+        r#"<presence xmlns="jabber:client" to="a@prose.org" type="subscribed" />"#
+    );
+    event!(client, ClientEvent::PresenceSubRequestsChanged);
+
+    client
+        .contact_list
+        .approve_presence_sub_request(&user_id!("a@prose.org").into(), false)
+        .await?;
+
+    send!(
+        client,
+        r#"<presence xmlns="jabber:client" to="b@prose.org" type="unsubscribed" />"#
+    );
+    event!(client, ClientEvent::PresenceSubRequestsChanged);
+
+    client
+        .contact_list
+        .deny_presence_sub_request(&user_id!("b@prose.org").into())
+        .await?;
+
+    assert!(client
+        .contact_list
+        .load_presence_sub_requests()
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_presence_sub_request_stanza_flow() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    recv!(
+        client,
+        r#"
+        <presence xmlns="jabber:client" from="friend@prose.org" to="{{USER_RESOURCE_ID}}" type="subscribe" xml:lang="en">
+            <nick xmlns="http://jabber.org/protocol/nick">Jane Doe</nick>
+        </presence>
+        "#
+    );
+    event!(client, ClientEvent::PresenceSubRequestsChanged);
+    event!(
+        client,
+        ClientEvent::ContactChanged {
+            ids: vec![user_id!("friend@prose.org")]
+        }
+    );
+    client.receive_next().await;
+
+    // The request is withdrawn…
+    recv!(
+        client,
+        r#"
+        <presence xmlns="jabber:client" from="friend@prose.org" to="{{USER_RESOURCE_ID}}" type="unsubscribe" xml:lang="en" />
+        "#
+    );
+    event!(client, ClientEvent::PresenceSubRequestsChanged);
+    client.receive_next().await;
+
+    assert!(client
+        .contact_list
+        .load_presence_sub_requests()
+        .await?
+        .is_empty());
+
+    // …and sent again. The nickname didn't change, so the contact doesn't change either.
+    recv!(
+        client,
+        r#"
+        <presence xmlns="jabber:client" from="friend@prose.org" to="{{USER_RESOURCE_ID}}" type="subscribe" xml:lang="en">
+            <nick xmlns="http://jabber.org/protocol/nick">Jane Doe</nick>
+        </presence>
+        "#
+    );
+    event!(client, ClientEvent::PresenceSubRequestsChanged);
+    client.receive_next().await;
+
+    assert_eq!(
+        1,
+        client
+            .contact_list
+            .load_presence_sub_requests()
+            .await?
+            .len()
+    );
+
+    send!(
+        client,
+        r#"<presence xmlns="jabber:client" to="friend@prose.org" type="subscribed" />"#
+    );
+    event!(client, ClientEvent::PresenceSubRequestsChanged);
+    event!(client, ClientEvent::ContactListChanged);
+
+    send!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="{{ID}}" type="set">
+            <query xmlns="jabber:iq:roster">
+                <item jid="friend@prose.org" name="Jane Doe" />
+            </query>
+        </iq>
+        "#
+    );
+    recv!(
+        client,
+        r#"<iq xmlns="jabber:client" id="{{ID}}" type="result" />"#
+    );
+
+    send!(
+        client,
+        r#"<presence xmlns="jabber:client" to="friend@prose.org" type="subscribed" />"#
+    );
+    send!(
+        client,
+        r#"<presence xmlns="jabber:client" to="friend@prose.org" type="subscribe" />"#
+    );
+
+    client
+        .contact_list
+        .approve_presence_sub_request(&user_id!("friend@prose.org").into(), true)
+        .await?;
+
+    assert!(client
+        .contact_list
+        .load_presence_sub_requests()
+        .await?
+        .is_empty());
+
+    let contacts = client.contact_list.load_contacts().await?;
+    assert_eq!(1, contacts.len());
+    assert_eq!(
+        PresenceSubscription::Requested,
+        contacts[0].presence_subscription
+    );
+
+    // Once the contact unsubscribes, there's no pending request left to remove.
+    recv!(
+        client,
+        r#"
+        <presence xmlns="jabber:client" from="friend@prose.org" to="{{USER_RESOURCE_ID}}" type="unsubscribe" xml:lang="en" />
+        "#
+    );
+    client.receive_next().await;
+
+    assert!(client
+        .contact_list
+        .load_presence_sub_requests()
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_set_contact_groups() -> Result<()> {
+    let client = TestClient::new().await;
+
+    let strategy = LoginStrategy::default().with_roster_items([RosterItem {
+        jid: bare!("friend@prose.org"),
+        name: Some("Jane Doe".to_string()),
+        subscription: Subscription::Both,
+        ask: Default::default(),
+        groups: vec![],
+    }]);
+
+    client
+        .expect_login_with_strategy(user_id!("user@prose.org"), "secret", strategy)
+        .await?;
+
+    event!(
+        client,
+        ClientEvent::ContactChanged {
+            ids: vec![user_id!("friend@prose.org")]
+        }
+    );
+
+    // Each group is sent as its own element, so group names may contain any character…
+    send!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="{{ID}}" type="set">
+            <query xmlns="jabber:iq:roster">
+                <item jid="friend@prose.org" name="Jane Doe">
+                    <group>Friends, Family</group>
+                    <group>Team</group>
+                </item>
+            </query>
+        </iq>
+        "#
+    );
+    recv!(
+        client,
+        r#"<iq xmlns="jabber:client" id="{{ID}}" type="result" />"#
+    );
+
+    client
+        .contact_list
+        .set_groups(
+            &user_id!("friend@prose.org"),
+            &["Friends, Family".to_string(), "Team".to_string()],
+        )
+        .await?;
+
+    // …and the presence subscription is kept.
+    let contacts = client.contact_list.load_contacts().await?;
+    assert_eq!(1, contacts.len());
+    assert_eq!(
+        PresenceSubscription::Mutual,
+        contacts[0].presence_subscription
+    );
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_load_last_activity_of_contact() -> Result<()> {
+    let client = TestClient::new().await;
+
+    let strategy = LoginStrategy::default().with_roster_items([RosterItem {
+        jid: bare!("friend@prose.org"),
+        name: None,
+        subscription: Subscription::Both,
+        ask: Default::default(),
+        groups: vec![],
+    }]);
+
+    client
+        .expect_login_with_strategy(user_id!("user@prose.org"), "secret", strategy)
+        .await?;
+
+    // XEP-0012: Last Activity
+    send!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="{{ID}}" to="friend@prose.org" type="get">
+            <query xmlns="jabber:iq:last" />
+        </iq>
+        "#
+    );
+    recv!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" from="friend@prose.org" id="{{ID}}" type="result">
+            <query xmlns="jabber:iq:last" seconds="3600" />
+        </iq>
+        "#
+    );
+
+    assert_eq!(
+        Some(Utc.with_ymd_and_hms(2024, 02, 18, 23, 0, 0).unwrap()),
+        client
+            .contact_list
+            .load_last_activity(&user_id!("friend@prose.org"))
+            .await?
+    );
+
+    // If their server doesn't tell anymore, we fall back to when we've last seen them…
+    send!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" id="{{ID}}" to="friend@prose.org" type="get">
+            <query xmlns="jabber:iq:last" />
+        </iq>
+        "#
+    );
+    recv!(
+        client,
+        r#"
+        <iq xmlns="jabber:client" from="friend@prose.org" id="{{ID}}" type="error">
+          <error type="cancel">
+            <service-unavailable xmlns="urn:ietf:params:xml:ns:xmpp-stanzas" />
+          </error>
+        </iq>
+        "#
+    );
+
+    assert_eq!(
+        Some(Utc.with_ymd_and_hms(2024, 02, 18, 23, 0, 0).unwrap()),
+        client
+            .contact_list
+            .load_last_activity(&user_id!("friend@prose.org"))
+            .await?
+    );
+
+    // …which also shows up in the contact list, since they're offline.
+    let contacts = client.contact_list.load_contacts().await?;
+    assert_eq!(
+        Some(Utc.with_ymd_and_hms(2024, 02, 18, 23, 0, 0).unwrap()),
+        contacts[0].last_seen
+    );
+
+    Ok(())
+}
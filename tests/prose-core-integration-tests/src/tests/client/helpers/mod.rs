@@ -44,7 +44,7 @@ impl TestDeviceBundle for DeviceBundle {
             Arc::new(StepRngProvider::default()),
         );
         service
-            .generate_local_encryption_bundle(&account_id.into(), device_id.into())
+            .generate_local_encryption_bundle(&account_id.into(), device_id.into(), 100)
             .await
             .unwrap()
             .into_device_bundle()
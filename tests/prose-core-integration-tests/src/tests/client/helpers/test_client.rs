@@ -7,6 +7,7 @@ use std::cmp::max;
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::Duration;
 
 use minidom::Element;
 use parking_lot::Mutex;
@@ -23,13 +24,14 @@ use prose_core_client::test::ConstantTimeProvider;
 use prose_core_client::{
     Client, ClientEvent, ClientRoomEventType, FsAvatarRepository, SignalServiceHandle,
 };
-use prose_store::prelude::Store;
+use prose_store::prelude::{PlatformDriver, Store};
+use prose_wasm_utils::sleep;
 use prose_xmpp::stanza::VCard4;
 use prose_xmpp::test::IncrementingIDProvider;
 
 use crate::tests::client::helpers::delegate::Delegate;
 use crate::tests::client::helpers::id_provider::IncrementingOffsettingIDProvider;
-use crate::tests::{store, PlatformDriver};
+use crate::tests::store;
 
 use super::{connector::Connector, test_message_queue::TestMessageQueue};
 
@@ -94,6 +96,14 @@ impl TestClientBuilder {
             app_config: self.app_config,
         }
     }
+
+    pub fn set_config(self, app_config: AppConfig) -> Self {
+        Self {
+            time_provider: self.time_provider,
+            store: self.store,
+            app_config,
+        }
+    }
 }
 
 impl TestClientBuilder {
@@ -319,6 +329,21 @@ impl TestClient {
     pub async fn simulate_timeout_timer(&self) {
         self.connector.send_timeout_timer_event().await
     }
+
+    /// Waits until the client has sent all queued stanzas on its own, e.g. from a timer.
+    pub async fn wait_for_queued_stanzas(&self) {
+        for _ in 0..500 {
+            if self.messages.len() == 0 {
+                return;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        panic!(
+            "Timed out waiting for the client to send the queued stanzas.\n\n{:?}",
+            self.messages
+        );
+    }
 }
 
 impl TestClient {
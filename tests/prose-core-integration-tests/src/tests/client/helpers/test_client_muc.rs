@@ -27,6 +27,7 @@ pub struct JoinRoomStrategy {
     pub room_name: String,
     pub room_type: RoomType,
     pub room_settings: Option<SyncedRoomSettings>,
+    pub password: Option<String>,
     pub owners: Vec<BareJid>,
     pub members: Vec<BareJid>,
     pub admins: Vec<BareJid>,
@@ -42,6 +43,7 @@ impl Default for JoinRoomStrategy {
             room_name: "general".to_string(),
             room_type: RoomType::PublicChannel,
             room_settings: None,
+            password: None,
             owners: vec![],
             members: vec![],
             admins: vec![],
@@ -64,6 +66,11 @@ impl JoinRoomStrategy {
         self
     }
 
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
     pub fn with_catch_up_handler(
         mut self,
         handler: impl FnOnce(&TestClient, &MucId) + 'static,
@@ -209,6 +216,13 @@ impl TestClient {
             ("ROOM_ID", room_id.to_string()),
             ("ROOM_NAME", strategy.room_name.into()),
             ("ANON_OCCUPANT_ID", anon_occupant_id.to_string()),
+            (
+                "ROOM_PASSWORD",
+                strategy
+                    .password
+                    .map(|password| format!("<password>{password}</password>"))
+                    .unwrap_or_default(),
+            ),
         ]);
 
         send!(
@@ -217,6 +231,7 @@ impl TestClient {
         <presence xmlns='jabber:client' to="{{OCCUPANT_ID}}">
             <show>chat</show>
             <x xmlns='http://jabber.org/protocol/muc'>
+              {{ROOM_PASSWORD}}
               <history maxstanzas="0" />
             </x>
             <c xmlns='http://jabber.org/protocol/caps' hash="sha-1" node="https://prose.org" ver="{{CAPS_HASH}}"/>
@@ -8,6 +8,7 @@ use std::sync::Arc;
 use minidom::Element;
 
 use prose_core_client::app::deps::DynEncryptionDomainService;
+use prose_core_client::app::event_handlers::MockClientEventDispatcherTrait;
 use prose_core_client::domain::connection::models::ConnectionProperties;
 use prose_core_client::domain::encryption::models::Device;
 use prose_core_client::domain::encryption::repos::mocks::MockUserDeviceRepository;
@@ -277,6 +278,7 @@ impl TestClient {
             .return_once(|_| Box::pin(async { Ok(()) }));
 
         let deps = EncryptionDomainServiceDependencies {
+            client_event_dispatcher: Arc::new(MockClientEventDispatcherTrait::new()),
             ctx: Arc::new(Default::default()),
             encryption_keys_repo,
             encryption_service,
@@ -4,22 +4,31 @@
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
 use crate::tests::client::helpers::{StartDMStrategy, TestClient};
-use crate::tests::store;
+use crate::tests::{in_memory_store, store};
 use crate::{event, recv, room_event, send};
 use anyhow::Result;
 use chrono::{DateTime, Duration, TimeZone, Utc};
 use itertools::Itertools;
 use minidom::Element;
 use pretty_assertions::assert_eq;
+use prose_core_client::app::deps::AppConfig;
+use prose_core_client::domain::messaging::models::{
+    ArchivedMessageRef, Attachment, AttachmentType, MessageLikePayload, PinnedMessageRef,
+};
 use prose_core_client::domain::messaging::repos::MessagesRepository;
+use prose_core_client::domain::messaging::services::SendMessageError;
+use prose_core_client::domain::rooms::models::constants::COMPOSE_STATE_TIMER_INTERVAL;
+use prose_core_client::domain::settings::models::SyncedRoomSettings;
 use prose_core_client::domain::shared::models::AnonOccupantId;
 use prose_core_client::dtos::{
-    AccountId, MucId, RoomId, SendMessageRequest, SendMessageRequestBody, UserId,
+    AccountId, DeliveryState, MucId, RoomId, SendMessageRequest, SendMessageRequestBody, UserId,
 };
 use prose_core_client::infra::messaging::CachingMessageRepository;
 use prose_core_client::test::MessageBuilder;
 use prose_core_client::{account_id, muc_id, user_id, ClientEvent, ClientRoomEventType};
 use prose_proc_macros::mt_test;
+use prose_store::prelude::{PlatformDriver, Store};
+use prose_wasm_utils::sleep;
 use prose_xmpp::stanza::Message;
 use prose_xmpp::{bare, TimeProvider};
 use xmpp_parsers::mam::QueryId;
@@ -98,7 +107,15 @@ async fn test_receives_message_with_same_id_twice() -> Result<()> {
 
 #[mt_test]
 async fn test_updates_existing_messages_on_catchup() -> Result<()> {
-    let store = store().await.expect("Failed to set up store.");
+    updates_existing_messages_on_catchup(store().await?).await
+}
+
+#[mt_test]
+async fn test_updates_existing_messages_on_catchup_with_in_memory_store() -> Result<()> {
+    updates_existing_messages_on_catchup(in_memory_store().await?).await
+}
+
+async fn updates_existing_messages_on_catchup(store: Store<PlatformDriver>) -> Result<()> {
     let client = TestClient::builder().set_store(store.clone()).build().await;
 
     let account = account_id!("user@prose.org");
@@ -395,6 +412,8 @@ async fn test_sends_and_updates_message_to_muc_room() -> Result<()> {
             text: "Hello".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -412,6 +431,15 @@ async fn test_sends_and_updates_message_to_muc_room() -> Result<()> {
         "#
     );
 
+    // The reflection confirms that the message was sent…
+    room_event!(
+        client,
+        room_id.clone(),
+        ClientRoomEventType::MessagesUpdated {
+            message_ids: vec![message_id.clone().into()]
+        }
+    );
+
     client.receive_next().await;
 
     let messages = CachingMessageRepository::new(store)
@@ -457,6 +485,8 @@ async fn test_sends_and_updates_message_to_muc_room() -> Result<()> {
                 text: "Hello World".into(),
             }),
             attachments: vec![],
+            reply_to: None,
+            thread_id: None,
         },
     )
     .await?;
@@ -493,3 +523,724 @@ async fn test_sends_and_updates_message_to_muc_room() -> Result<()> {
 
     Ok(())
 }
+
+#[mt_test]
+async fn test_reflected_muc_message_is_not_duplicated() -> Result<()> {
+    let store = store().await.expect("Failed to set up store.");
+    let client = TestClient::builder().set_store(store.clone()).build().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room_id = muc_id!("room@conference.prose.org");
+    let occupant_id = client.build_occupant_id(&room_id);
+    let anon_occupant_id = AnonOccupantId::from("anon-occupant-id");
+
+    client
+        .join_room(room_id.clone(), anon_occupant_id.clone())
+        .await?;
+
+    client.push_ctx([
+        ("OCCUPANT_ID", occupant_id.to_string()),
+        ("ROOM_ID", room_id.to_string()),
+        ("ANON_OCCUPANT_ID", anon_occupant_id.to_string()),
+    ]);
+
+    let message_id = client.get_next_message_id();
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" id="{{MSG_ID}}" to="{{ROOM_ID}}" type="groupchat">
+          <body>Hello</body>
+          <content xmlns="urn:xmpp:content" type="text/markdown">Hello</content>
+          <active xmlns="http://jabber.org/protocol/chatstates" />
+          <markable xmlns="urn:xmpp:chat-markers:0" />
+          <store xmlns="urn:xmpp:hints" />
+        </message>
+        "#
+    );
+
+    room_event!(
+        client,
+        room_id.clone(),
+        ClientRoomEventType::MessagesAppended {
+            message_ids: vec![message_id.clone().into()]
+        }
+    );
+
+    let room = client.get_room(room_id.clone()).await.to_generic_room();
+    room.send_message(SendMessageRequest {
+        body: Some(SendMessageRequestBody {
+            text: "Hello".into(),
+        }),
+        attachments: vec![],
+        reply_to: None,
+        thread_id: None,
+    })
+    .await?;
+
+    let messages = room
+        .load_messages_with_ids(&[message_id.clone().into()])
+        .await?;
+    assert_eq!(1, messages.len());
+    assert_eq!(DeliveryState::Sending, messages[0].flags.delivery_state);
+
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{OCCUPANT_ID}}" id="{{LAST_MSG_ID}}" to="{{USER_RESOURCE_ID}}" type="groupchat" xml:lang="en">
+          <body>Hello</body>
+          <active xmlns="http://jabber.org/protocol/chatstates" />
+          <markable xmlns="urn:xmpp:chat-markers:0" />
+          <store xmlns="urn:xmpp:hints" />
+          <occupant-id xmlns="urn:xmpp:occupant-id:0" id="{{ANON_OCCUPANT_ID}}" />
+          <stanza-id xmlns="urn:xmpp:sid:0" by="{{ROOM_ID}}" id="opZdWmO7r50ee_aGKnWvBMbK" />
+        </message>
+        "#
+    );
+
+    // The reflection confirms that the message was sent…
+    room_event!(
+        client,
+        room_id.clone(),
+        ClientRoomEventType::MessagesUpdated {
+            message_ids: vec![message_id.clone().into()]
+        }
+    );
+
+    client.receive_next().await;
+
+    // The reflected message should be merged into the one we sent instead of being appended
+    // as a new message from the room…
+    let messages = CachingMessageRepository::new(store)
+        .get_latest_messages(&bare!("user@prose.org").into(), &room_id.clone().into(), 10)
+        .await?;
+    assert_eq!(1, messages.len());
+
+    let messages = room
+        .load_messages_with_ids(&[message_id.clone().into()])
+        .await?;
+    assert_eq!(1, messages.len());
+    assert_eq!(DeliveryState::Sent, messages[0].flags.delivery_state);
+
+    client.pop_ctx();
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_keeps_body_when_updating_attachments_only() -> Result<()> {
+    let store = store().await.expect("Failed to set up store.");
+    let client = TestClient::builder().set_store(store.clone()).build().await;
+
+    let account = account_id!("user@prose.org");
+
+    client.expect_login(account.to_user_id(), "secret").await?;
+
+    let room = client
+        .start_dm(user_id!("them@prose.org"))
+        .await?
+        .to_generic_room();
+
+    let first_attachment = Attachment {
+        r#type: AttachmentType::Image { thumbnail: None },
+        url: "https://uploads.prose.org/first.jpg".parse().unwrap(),
+        media_type: "image/jpeg".parse().unwrap(),
+        file_name: "first.jpg".to_string(),
+        file_size: Some(100),
+    };
+    let second_attachment = Attachment {
+        r#type: AttachmentType::Image { thumbnail: None },
+        url: "https://uploads.prose.org/second.jpg".parse().unwrap(),
+        media_type: "image/jpeg".parse().unwrap(),
+        file_name: "second.jpg".to_string(),
+        file_size: Some(200),
+    };
+
+    let mut payload = MessageLikePayload::message("hello");
+    if let MessageLikePayload::Message { attachments, .. } = &mut payload {
+        attachments.push(first_attachment.clone());
+    }
+
+    let message_id = MessageBuilder::id_for_index(1);
+
+    CachingMessageRepository::new(store.clone())
+        .append(
+            &account,
+            &room.jid().clone().into(),
+            &[MessageBuilder::new_with_index(1)
+                .set_from(account.to_user_id())
+                .set_payload(payload)
+                .build_message_like()],
+        )
+        .await?;
+
+    client.push_ctx([(
+        "INITIAL_MESSAGE_ID",
+        MessageBuilder::remote_id_for_index(1).to_string(),
+    )]);
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" id="{{MSG_ID}}" to="them@prose.org" type="chat">
+          <body>hello</body>
+          <content xmlns="urn:xmpp:content" type="text/markdown">hello</content>
+          <replace xmlns="urn:xmpp:message-correct:0" id="{{INITIAL_MESSAGE_ID}}" />
+          <store xmlns="urn:xmpp:hints" />
+          <reference xmlns="urn:xmpp:reference:0" type="data">
+            <media-sharing xmlns="urn:xmpp:sims:1">
+              <file xmlns="urn:xmpp:jingle:apps:file-transfer:5">
+                <media-type>image/jpeg</media-type>
+                <name>first.jpg</name>
+                <size>100</size>
+              </file>
+              <sources>
+                <reference xmlns="urn:xmpp:reference:0" type="data" uri="https://uploads.prose.org/first.jpg" />
+              </sources>
+            </media-sharing>
+          </reference>
+          <x xmlns="jabber:x:oob">
+            <url>https://uploads.prose.org/first.jpg</url>
+          </x>
+          <reference xmlns="urn:xmpp:reference:0" type="data">
+            <media-sharing xmlns="urn:xmpp:sims:1">
+              <file xmlns="urn:xmpp:jingle:apps:file-transfer:5">
+                <media-type>image/jpeg</media-type>
+                <name>second.jpg</name>
+                <size>200</size>
+              </file>
+              <sources>
+                <reference xmlns="urn:xmpp:reference:0" type="data" uri="https://uploads.prose.org/second.jpg" />
+              </sources>
+            </media-sharing>
+          </reference>
+          <x xmlns="jabber:x:oob">
+            <url>https://uploads.prose.org/second.jpg</url>
+          </x>
+        </message>
+        "#
+    );
+
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::MessagesUpdated {
+            message_ids: vec![message_id.clone()]
+        }
+    );
+
+    room.update_message(
+        message_id.clone(),
+        SendMessageRequest {
+            body: None,
+            attachments: vec![first_attachment, second_attachment],
+            reply_to: None,
+            thread_id: None,
+        },
+    )
+    .await?;
+
+    let messages = room.load_messages_with_ids(&[message_id]).await?;
+    assert_eq!(1, messages.len());
+    assert_eq!("hello", messages[0].body.raw);
+    assert_eq!(2, messages[0].attachments.len());
+
+    client.pop_ctx();
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_rejects_message_exceeding_max_stanza_size() -> Result<()> {
+    let client = TestClient::builder()
+        .set_config(AppConfig {
+            max_stanza_size: 1024,
+            ..Default::default()
+        })
+        .build()
+        .await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room_id = muc_id!("room@conference.prose.org");
+    client.join_room(room_id.clone(), "anon-id").await?;
+
+    let room = client.get_room(room_id.clone()).await.to_generic_room();
+
+    room_event!(
+        client,
+        room_id.clone(),
+        ClientRoomEventType::MessagesAppended {
+            message_ids: vec![client.get_next_message_id()]
+        }
+    );
+
+    // No stanza is expected to be sent, so the connector would panic if we did.
+    let result = room
+        .send_message(SendMessageRequest {
+            body: Some(SendMessageRequestBody {
+                text: "a".repeat(2048).into(),
+            }),
+            attachments: vec![],
+            reply_to: None,
+            thread_id: None,
+        })
+        .await;
+
+    let Err(error) = result else {
+        panic!("Expected sending the message to fail.")
+    };
+
+    assert!(matches!(
+        error.downcast_ref::<SendMessageError>(),
+        Some(SendMessageError::StanzaTooLarge { max_size: 1024, .. })
+    ));
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_pins_and_unpins_message() -> Result<()> {
+    let client = TestClient::new().await;
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let other_user_id = user_id!("other@prose.org");
+    client.push_ctx([("OTHER_USER_ID", other_user_id.to_string())]);
+
+    let room = client
+        .start_dm(other_user_id.clone())
+        .await?
+        .to_generic_room();
+
+    let message_id = client.get_next_message_id();
+
+    {
+        recv!(
+            client,
+            r#"
+            <message xmlns="jabber:client" from="{{OTHER_USER_ID}}/res" id="message-id" to="{{USER_RESOURCE_ID}}" type="chat">
+              <body>Pin me</body>
+              <stanza-id xmlns="urn:xmpp:sid:0" by="{{USER_ID}}" id="stanza-id" />
+            </message>
+            "#
+        );
+
+        event!(client, ClientEvent::SidebarChanged);
+        room_event!(
+            client,
+            room.jid().clone(),
+            ClientRoomEventType::MessagesAppended {
+                message_ids: vec![message_id.clone()]
+            }
+        );
+    }
+    client.receive_next().await;
+
+    let timestamp = room.load_messages_with_ids(&[message_id.clone()]).await?[0].timestamp;
+
+    let pinned_settings = SyncedRoomSettings {
+        room_id: room.jid().clone(),
+        encryption_enabled: false,
+        last_read_message: None,
+        pinned_messages: vec![PinnedMessageRef {
+            remote_id: Some("message-id".into()),
+            stanza_id: Some("stanza-id".into()),
+            timestamp,
+            pinned_by: user_id!("user@prose.org"),
+        }],
+        notifications: Default::default(),
+        send_read_receipts: true,
+    };
+
+    client.expect_save_synced_room_settings(pinned_settings.clone());
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::PinnedMessagesChanged
+    );
+    room.pin_message(message_id.clone()).await?;
+
+    // Pinning the same message again doesn't change anything…
+    room.pin_message(message_id.clone()).await?;
+
+    let pinned_messages = room.pinned_messages().await?;
+    assert_eq!(1, pinned_messages.len());
+    assert_eq!(message_id, pinned_messages[0].id);
+    assert_eq!("Pin me", pinned_messages[0].body.raw);
+
+    client.expect_save_synced_room_settings(SyncedRoomSettings {
+        pinned_messages: vec![],
+        ..pinned_settings
+    });
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::PinnedMessagesChanged
+    );
+    room.unpin_message(message_id.clone()).await?;
+
+    // …and neither does unpinning a message that isn't pinned.
+    room.unpin_message(message_id).await?;
+
+    assert!(room.pinned_messages().await?.is_empty());
+
+    // Messages that don't exist in the room cannot be pinned.
+    assert!(room.pin_message("unknown-id".into()).await.is_err());
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_flags_and_discards_message_that_failed_to_send() -> Result<()> {
+    let client = TestClient::builder()
+        .set_config(AppConfig {
+            max_stanza_size: 1024,
+            ..Default::default()
+        })
+        .build()
+        .await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room = client
+        .start_dm(user_id!("other@prose.org"))
+        .await?
+        .to_generic_room();
+
+    let message_id = client.get_next_message_id();
+
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::MessagesAppended {
+            message_ids: vec![message_id.clone()]
+        }
+    );
+
+    // No stanza is expected to be sent, so the connector would panic if we did.
+    assert!(room
+        .send_message(SendMessageRequest {
+            body: Some(SendMessageRequestBody {
+                text: "a".repeat(2048).into(),
+            }),
+            attachments: vec![],
+            reply_to: None,
+            thread_id: None,
+        })
+        .await
+        .is_err());
+
+    let messages = room.load_messages_with_ids(&[message_id.clone()]).await?;
+    assert_eq!(1, messages.len());
+    assert!(!messages[0].flags.is_pending);
+    assert!(messages[0].flags.send_error.is_some());
+
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::MessagesDeleted {
+            message_ids: vec![message_id.clone()]
+        }
+    );
+    room.discard_failed_message(message_id.clone()).await?;
+
+    assert!(room.load_messages_with_ids(&[message_id]).await?.is_empty());
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_sends_and_receives_read_receipts() -> Result<()> {
+    let client = TestClient::new().await;
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let other_user_id = user_id!("other@prose.org");
+    client.push_ctx([("OTHER_USER_ID", other_user_id.to_string())]);
+
+    let room = client
+        .start_dm(other_user_id.clone())
+        .await?
+        .to_generic_room();
+
+    let message_id = client.get_next_message_id();
+
+    {
+        recv!(
+            client,
+            r#"
+            <message xmlns="jabber:client" from="{{OTHER_USER_ID}}/res" id="message-id-1" to="{{USER_RESOURCE_ID}}" type="chat">
+              <body>Did you read this?</body>
+              <markable xmlns="urn:xmpp:chat-markers:0" />
+              <stanza-id xmlns="urn:xmpp:sid:0" by="{{USER_ID}}" id="stanza-id-1" />
+            </message>
+            "#
+        );
+
+        event!(client, ClientEvent::SidebarChanged);
+        room_event!(
+            client,
+            room.jid().clone(),
+            ClientRoomEventType::MessagesAppended {
+                message_ids: vec![message_id.clone()]
+            }
+        );
+    }
+    client.receive_next().await;
+
+    let timestamp = room.load_messages_with_ids(&[message_id]).await?[0].timestamp;
+
+    // Marking the room as read tells the sender that we've read their message…
+    client.expect_save_synced_room_settings(SyncedRoomSettings {
+        room_id: room.jid().clone(),
+        encryption_enabled: false,
+        last_read_message: Some(ArchivedMessageRef {
+            stanza_id: "stanza-id-1".into(),
+            timestamp,
+        }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
+    });
+    event!(client, ClientEvent::SidebarChanged);
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" to="{{OTHER_USER_ID}}" type="chat">
+          <displayed xmlns="urn:xmpp:chat-markers:0" id="message-id-1" />
+          <store xmlns="urn:xmpp:hints" />
+        </message>
+        "#
+    );
+    room.mark_as_read().await?;
+
+    // …but only once.
+    room.mark_as_read().await?;
+
+    // We reply and the other party reads our message…
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" id="{{MSG_ID}}" to="{{OTHER_USER_ID}}" type="chat">
+          <body>Yes!</body>
+          <content xmlns="urn:xmpp:content" type="text/markdown">Yes!</content>
+          <active xmlns="http://jabber.org/protocol/chatstates" />
+          <markable xmlns="urn:xmpp:chat-markers:0" />
+          <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
+        </message>
+        "#
+    );
+    let reply_id = client.get_last_message_id();
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::MessagesAppended {
+            message_ids: vec![reply_id.clone()]
+        }
+    );
+    room.send_message(SendMessageRequest {
+        body: Some(SendMessageRequestBody {
+            text: "Yes!".into(),
+        }),
+        attachments: vec![],
+        reply_to: None,
+        thread_id: None,
+    })
+    .await?;
+
+    assert!(
+        !room.load_messages_with_ids(&[reply_id.clone()]).await?[0]
+            .flags
+            .is_read
+    );
+
+    client.push_ctx([("REPLY_ID", reply_id.to_string())]);
+    {
+        recv!(
+            client,
+            r#"
+            <message xmlns="jabber:client" from="{{OTHER_USER_ID}}/res" to="{{USER_RESOURCE_ID}}" type="chat">
+              <displayed xmlns="urn:xmpp:chat-markers:0" id="{{REPLY_ID}}" />
+            </message>
+            "#
+        );
+
+        room_event!(
+            client,
+            room.jid().clone(),
+            ClientRoomEventType::MessagesUpdated {
+                message_ids: vec![reply_id.clone()]
+            }
+        );
+    }
+    client.receive_next().await;
+
+    assert!(
+        room.load_messages_with_ids(&[reply_id]).await?[0]
+            .flags
+            .is_read
+    );
+
+    // Once our user opts out, no read receipts are sent anymore.
+    client.account.set_send_read_receipts(false).await?;
+
+    let message_id = client.get_next_message_id();
+    {
+        recv!(
+            client,
+            r#"
+            <message xmlns="jabber:client" from="{{OTHER_USER_ID}}/res" id="message-id-2" to="{{USER_RESOURCE_ID}}" type="chat">
+              <body>And this?</body>
+              <markable xmlns="urn:xmpp:chat-markers:0" />
+              <stanza-id xmlns="urn:xmpp:sid:0" by="{{USER_ID}}" id="stanza-id-2" />
+            </message>
+            "#
+        );
+
+        event!(client, ClientEvent::SidebarChanged);
+        room_event!(
+            client,
+            room.jid().clone(),
+            ClientRoomEventType::MessagesAppended {
+                message_ids: vec![message_id.clone()]
+            }
+        );
+    }
+    client.receive_next().await;
+
+    let timestamp = room.load_messages_with_ids(&[message_id]).await?[0].timestamp;
+
+    client.expect_save_synced_room_settings(SyncedRoomSettings {
+        room_id: room.jid().clone(),
+        encryption_enabled: false,
+        last_read_message: Some(ArchivedMessageRef {
+            stanza_id: "stanza-id-2".into(),
+            timestamp,
+        }),
+        pinned_messages: vec![],
+        notifications: Default::default(),
+        send_read_receipts: true,
+    });
+    event!(client, ClientEvent::SidebarChanged);
+    room.mark_as_read().await?;
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_throttles_and_pauses_chat_states() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room = client
+        .start_dm(user_id!("them@prose.org"))
+        .await?
+        .to_generic_room();
+
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 0, 0);
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" to="them@prose.org" type="chat">
+            <composing xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room.set_user_is_composing(true).await?;
+
+    // Repeated composing states should be throttled…
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 0, 5);
+    room.set_user_is_composing(true).await?;
+
+    // Not quite 30 seconds since the last refresh, so the user is still typing…
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 0, 34);
+    sleep(2 * COMPOSE_STATE_TIMER_INTERVAL).await;
+
+    // The user stopped typing without telling us, so we should pause on their behalf…
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" to="them@prose.org" type="chat">
+            <paused xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 0, 35);
+    client.wait_for_queued_stanzas().await;
+
+    room.set_user_is_composing(false).await?;
+
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 0, 40);
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" to="them@prose.org" type="chat">
+            <composing xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room.set_user_is_composing(true).await?;
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" id="{{MSG_ID}}" to="them@prose.org" type="chat">
+          <body>Hello</body>
+          <content xmlns="urn:xmpp:content" type="text/markdown">Hello</content>
+          <active xmlns="http://jabber.org/protocol/chatstates" />
+          <markable xmlns="urn:xmpp:chat-markers:0" />
+          <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
+        </message>
+        "#
+    );
+
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::MessagesAppended {
+            message_ids: vec![client.get_last_message_id()]
+        }
+    );
+
+    room.send_message(SendMessageRequest {
+        body: Some(SendMessageRequestBody {
+            text: "Hello".into(),
+        }),
+        attachments: vec![],
+        reply_to: None,
+        thread_id: None,
+    })
+    .await?;
+
+    // The message ended the composing state, so typing again is sent right away…
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 0, 41);
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" to="them@prose.org" type="chat">
+            <composing xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room.set_user_is_composing(true).await?;
+
+    Ok(())
+}
@@ -34,6 +34,7 @@ async fn test_send_markdown_message() -> Result<()> {
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -51,6 +52,8 @@ async fn test_send_markdown_message() -> Result<()> {
             text: "Some **bold**, _italic_, ~~strikethrough~~ and **_bold italic_** text.".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
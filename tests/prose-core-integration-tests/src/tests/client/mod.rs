@@ -3,7 +3,9 @@
 // Copyright: 2024, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+mod auto_away;
 mod avatar;
+mod block_list;
 mod catchup_unread;
 mod contact_list;
 mod helpers;
@@ -11,8 +13,10 @@ mod message_handling;
 mod message_styling;
 mod muc;
 mod muc_omemo;
+mod multi_account;
 mod omemo;
 mod reactions;
 mod reconnect;
 mod reply;
+mod room_subscription;
 mod user_info;
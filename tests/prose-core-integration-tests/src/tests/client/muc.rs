@@ -5,14 +5,18 @@
 
 use anyhow::Result;
 use jid::BareJid;
+use minidom::IntoAttributeValue;
 use pretty_assertions::assert_eq;
 
 use super::helpers::{JoinRoomStrategy, TestClient};
 use crate::{event, recv, room_event, send};
 use itertools::Itertools;
 use prose_core_client::domain::sidebar::models::BookmarkType;
-use prose_core_client::dtos::{MucId, ParticipantId, UserId};
-use prose_core_client::{muc_id, user_id, ClientEvent, ClientRoomEventType};
+use prose_core_client::dtos::{
+    MucId, OccupantId, ParticipantId, RoomId, RoomState, SendMessageRequest,
+    SendMessageRequestBody, UserId,
+};
+use prose_core_client::{muc_id, occupant_id, user_id, ClientEvent, ClientRoomEventType};
 use prose_proc_macros::mt_test;
 use prose_xmpp::bare;
 
@@ -50,6 +54,77 @@ async fn test_joins_room() -> Result<()> {
     Ok(())
 }
 
+#[mt_test]
+async fn test_joins_password_protected_room_after_wrong_password() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room_id = muc_id!("room@conference.prose.org");
+
+    client.push_ctx([
+        (
+            "OCCUPANT_ID",
+            client.build_occupant_id(&room_id).to_string(),
+        ),
+        ("ROOM_ID", room_id.to_string()),
+    ]);
+
+    send!(
+        client,
+        r#"
+        <presence xmlns='jabber:client' to="{{OCCUPANT_ID}}">
+            <show>chat</show>
+            <x xmlns='http://jabber.org/protocol/muc'>
+              <password>wrong-password</password>
+              <history maxstanzas="0" />
+            </x>
+            <c xmlns='http://jabber.org/protocol/caps' hash="sha-1" node="https://prose.org" ver="{{CAPS_HASH}}"/>
+            <nick xmlns="http://jabber.org/protocol/nick">{{USER_NICKNAME}}</nick>
+        </presence>
+        "#
+    );
+    recv!(
+        client,
+        r#"
+        <presence xmlns="jabber:client" from="{{OCCUPANT_ID}}" type="error">
+          <x xmlns="http://jabber.org/protocol/muc" />
+          <error by="{{ROOM_ID}}" type="auth">
+            <not-authorized xmlns="urn:ietf:params:xml:ns:xmpp-stanzas" />
+          </error>
+        </presence>
+        "#
+    );
+    event!(client, ClientEvent::SidebarChanged);
+
+    client.pop_ctx();
+
+    client.expect_join_room_with_strategy(
+        room_id.clone(),
+        "anon-id",
+        JoinRoomStrategy::default().with_password("room-password"),
+    );
+    client.expect_set_bookmark(room_id.clone(), "general", BookmarkType::PublicChannel);
+    event!(client, ClientEvent::SidebarChanged);
+
+    let mut rejected_passwords = vec![];
+
+    client
+        .rooms
+        .join_room_with_password_prompt(&room_id, Some("wrong-password"), |password| {
+            rejected_passwords.push(password);
+            async { Some("room-password".to_string()) }
+        })
+        .await?;
+
+    assert_eq!(vec![Some("wrong-password".to_string())], rejected_passwords);
+    assert_eq!(1, client.sidebar.sidebar_items().await.len());
+
+    Ok(())
+}
+
 #[mt_test]
 async fn test_creates_public_channel() -> Result<()> {
     let client = TestClient::new().await;
@@ -517,13 +592,17 @@ async fn test_receives_chat_states() -> Result<()> {
     room_event!(
         client,
         room_id.clone(),
-        ClientRoomEventType::ParticipantsChanged
+        ClientRoomEventType::ParticipantsChanged {
+            ids: vec![room_id.occupant_id_with_nickname("their-nick")?.into()]
+        }
     );
 
     room_event!(
         client,
         room_id.clone(),
-        ClientRoomEventType::ParticipantsChanged
+        ClientRoomEventType::ParticipantsChanged {
+            ids: vec![room_id.occupant_id_with_nickname("their-nick")?.into()]
+        }
     );
 
     client.receive_next().await;
@@ -594,3 +673,232 @@ async fn test_receives_chat_states() -> Result<()> {
 
     Ok(())
 }
+
+#[mt_test]
+async fn test_sends_chat_states_to_room() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room_id = muc_id!("room@conference.prose.org");
+
+    client.join_room(room_id.clone(), "anon-id").await?;
+
+    let room = client.get_room(room_id.clone()).await.to_generic_room();
+
+    client.push_ctx([("ROOM_ID", room_id.to_string())]);
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" to="{{ROOM_ID}}" type="groupchat">
+            <composing xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room.set_user_is_composing(true).await?;
+
+    // Repeated composing states should be throttled…
+    room.set_user_is_composing(true).await?;
+
+    client.time_provider.set_ymd_hms(2024, 02, 19, 0, 0, 11);
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" to="{{ROOM_ID}}" type="groupchat">
+            <composing xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room.set_user_is_composing(true).await?;
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" to="{{ROOM_ID}}" type="groupchat">
+            <paused xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room.set_user_is_composing(false).await?;
+    room.set_user_is_composing(false).await?;
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_sends_private_message_to_occupant() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room_id = muc_id!("room@conference.prose.org");
+
+    client.join_room(room_id.clone(), "anon-id").await?;
+
+    let room = client.get_room(room_id.clone()).await.to_generic_room();
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" id="{{MSG_ID}}" to="room@conference.prose.org/them" type="chat">
+          <body>Psst</body>
+          <content xmlns="urn:xmpp:content" type="text/markdown">Psst</content>
+          <active xmlns="http://jabber.org/protocol/chatstates" />
+          <x xmlns="http://jabber.org/protocol/muc#user" />
+          <request xmlns="urn:xmpp:receipts" />
+          <markable xmlns="urn:xmpp:chat-markers:0" />
+        </message>
+        "#
+    );
+
+    let message_id = client.get_last_message_id();
+
+    room_event!(
+        client,
+        room_id.clone(),
+        ClientRoomEventType::PrivateMessagesAppended {
+            occupant_id: occupant_id!("room@conference.prose.org/them"),
+            message_ids: vec![message_id.clone()]
+        }
+    );
+
+    room.send_private_message(
+        &occupant_id!("room@conference.prose.org/them"),
+        SendMessageRequest {
+            body: Some(SendMessageRequestBody {
+                text: "Psst".into(),
+            }),
+            attachments: vec![],
+            reply_to: None,
+            thread_id: None,
+        },
+    )
+    .await?;
+
+    let messages = room.load_messages_with_ids(&[message_id]).await?;
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].flags.is_transient);
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_receives_direct_invitation() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room_id = muc_id!("room@conference.prose.org");
+
+    client.push_ctx([("ROOM_ID", room_id.to_string())]);
+
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="them@prose.org/res" to="{{USER_RESOURCE_ID}}">
+            <x xmlns="jabber:x:conference" jid="{{ROOM_ID}}" password="cauldronburn" reason="Join us!" />
+        </message>
+        "#
+    );
+
+    event!(client, ClientEvent::SidebarChanged);
+    room_event!(
+        client,
+        room_id.clone(),
+        ClientRoomEventType::InvitationReceived {
+            sender: user_id!("them@prose.org")
+        }
+    );
+
+    client.receive_next().await;
+
+    // A mediated invitation for the same room shouldn't add another item…
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{ROOM_ID}}" to="{{USER_RESOURCE_ID}}">
+            <x xmlns="http://jabber.org/protocol/muc#user">
+                <invite from="them@prose.org/res" />
+            </x>
+        </message>
+        "#
+    );
+
+    client.receive_next().await;
+
+    let sidebar_items = client.sidebar.sidebar_items().await;
+    assert_eq!(1, sidebar_items.len());
+
+    let room = sidebar_items[0].room.to_generic_room();
+    assert_eq!(&RoomId::from(room_id), room.jid());
+    assert_eq!(
+        RoomState::Invited {
+            sender: user_id!("them@prose.org"),
+            password: Some("cauldronburn".to_string()),
+            reason: Some("Join us!".to_string()),
+        },
+        room.state()
+    );
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_joins_room_bookmarked_on_other_device() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room_id = muc_id!("room@conference.prose.org");
+
+    client.push_ctx([
+        ("ROOM_ID", room_id.to_string()),
+        (
+            "BOOKMARK_TYPE",
+            BookmarkType::PublicChannel.into_attribute_value().unwrap(),
+        ),
+    ]);
+
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_ID}}" id="bookmark-event" to="{{USER_RESOURCE_ID}}" type="headline">
+          <event xmlns="http://jabber.org/protocol/pubsub#event">
+            <items node="https://prose.org/protocol/bookmark">
+              <item id="{{ROOM_ID}}" publisher="{{USER_ID}}">
+                <bookmark xmlns="https://prose.org/protocol/bookmark" jid="{{ROOM_ID}}" name="general" sidebar="1" type="{{BOOKMARK_TYPE}}" />
+              </item>
+            </items>
+          </event>
+        </message>
+        "#
+    );
+
+    client.pop_ctx();
+
+    // The pending room is inserted first…
+    event!(client, ClientEvent::SidebarChanged);
+
+    client.expect_join_room_with_strategy(room_id.clone(), "anon-id", JoinRoomStrategy::default());
+
+    // …and updated once it's connected.
+    event!(client, ClientEvent::SidebarChanged);
+
+    client.receive_next().await;
+
+    let room = client.get_room(room_id).await.to_generic_room();
+    assert_eq!(RoomState::Connected, room.state());
+    assert_eq!(Some("general".to_string()), room.name());
+
+    Ok(())
+}
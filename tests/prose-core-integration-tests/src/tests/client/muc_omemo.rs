@@ -9,7 +9,7 @@ use minidom::Element;
 use prose_core_client::domain::shared::models::AccountId;
 use prose_core_client::domain::sidebar::models::BookmarkType;
 use prose_core_client::dtos::{
-    DeviceBundle, MucId, RoomId, SendMessageRequest, SendMessageRequestBody, UserId,
+    DeviceBundle, MucId, RoomId, SendMessageRequest, SendMessageRequestBody, TrustPolicy, UserId,
 };
 use prose_core_client::{account_id, muc_id, user_id, ClientEvent, ClientRoomEventType};
 use prose_proc_macros::mt_test;
@@ -235,10 +235,12 @@ async fn test_decrypts_message_from_private_nonanonymous_muc_room() -> Result<()
     let service = TestClient::their_encryption_domain_service(user_id!("user2@prose.org")).await;
     let encrypted_payload = service
         .encrypt_message(
-            vec![user_id!("user@prose.org")],
+            &[user_id!("user@prose.org")],
             "Can you read this?".to_string(),
+            TrustPolicy::default(),
         )
-        .await?;
+        .await?
+        .payload;
 
     client.push_ctx([(
         "ENCRYPTED_PAYLOAD",
@@ -523,6 +525,8 @@ async fn test_encrypts_message_in_private_nonanonymous_muc_room() -> Result<()>
               <item id="{{ROOM_ID}}">
                 <room-settings xmlns="https://prose.org/protocol/room_settings" room-id="muc:{{ROOM_ID}}">
                   <encryption type="omemo" />
+                  <notifications mode="all" />
+                  <read-receipts enabled="true" />
                 </room-settings>
               </item>
             </publish>
@@ -621,6 +625,8 @@ async fn test_encrypts_message_in_private_nonanonymous_muc_room() -> Result<()>
             text: "Hello World".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -648,6 +654,14 @@ async fn test_encrypts_message_in_private_nonanonymous_muc_room() -> Result<()>
         "#
     );
 
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::MessagesUpdated {
+            message_ids: vec![message_id.clone().into()]
+        }
+    );
+
     client.receive_next().await;
 
     let messages = room.load_messages_with_ids(&[message_id.into()]).await?;
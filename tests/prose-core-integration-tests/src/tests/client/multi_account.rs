@@ -0,0 +1,131 @@
+// prose-core-client/prose-core-integration-tests
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+use prose_core_client::dtos::{ParticipantId, SendMessageRequest, SendMessageRequestBody, UserId};
+use prose_core_client::{user_id, ClientEvent, ClientRoomEventType};
+use prose_proc_macros::mt_test;
+
+use crate::tests::store;
+use crate::{event, recv, room_event, send};
+
+use super::helpers::TestClient;
+
+#[mt_test]
+async fn test_clients_of_different_accounts_are_isolated() -> Result<()> {
+    // Both clients share a single store, so that anything not keyed by the account would leak
+    // from one account into the other…
+    let store = store().await?;
+    let alice = TestClient::builder().set_store(store.clone()).build().await;
+    let bob = TestClient::builder().set_store(store).build().await;
+
+    let alice_id = user_id!("alice@prose.org");
+    let bob_id = user_id!("bob@prose.org");
+
+    alice.expect_login(alice_id.clone(), "secret").await?;
+    bob.expect_login(bob_id.clone(), "secret").await?;
+
+    alice.push_ctx([("OTHER_USER_ID", bob_id.to_string())]);
+    bob.push_ctx([("OTHER_USER_ID", alice_id.to_string())]);
+
+    let alice_room = alice.start_dm(bob_id.clone()).await?.to_generic_room();
+    let bob_room = bob.start_dm(alice_id.clone()).await?.to_generic_room();
+
+    // Bob sends a message…
+    send!(
+        bob,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" id="{{MSG_ID}}" to="{{OTHER_USER_ID}}" type="chat">
+          <body>Hi Alice</body>
+          <content xmlns="urn:xmpp:content" type="text/markdown">Hi Alice</content>
+          <active xmlns="http://jabber.org/protocol/chatstates" />
+          <markable xmlns="urn:xmpp:chat-markers:0" />
+          <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
+        </message>
+        "#
+    );
+    let bob_message_id = bob.get_last_message_id();
+    room_event!(
+        bob,
+        bob_room.jid().clone(),
+        ClientRoomEventType::MessagesAppended {
+            message_ids: vec![bob_message_id.clone()]
+        }
+    );
+    bob_room
+        .send_message(SendMessageRequest {
+            body: Some(SendMessageRequestBody {
+                text: "Hi Alice".into(),
+            }),
+            attachments: vec![],
+            reply_to: None,
+            thread_id: None,
+        })
+        .await?;
+
+    // …which Alice receives.
+    let alice_message_id = alice.get_next_message_id();
+    {
+        recv!(
+            alice,
+            r#"
+            <message xmlns="jabber:client" from="{{OTHER_USER_ID}}/res" id="message-id-1" to="{{USER_RESOURCE_ID}}" type="chat">
+              <body>Hi Alice</body>
+              <markable xmlns="urn:xmpp:chat-markers:0" />
+              <stanza-id xmlns="urn:xmpp:sid:0" by="{{USER_ID}}" id="stanza-id-1" />
+            </message>
+            "#
+        );
+
+        event!(alice, ClientEvent::SidebarChanged);
+        room_event!(
+            alice,
+            alice_room.jid().clone(),
+            ClientRoomEventType::MessagesAppended {
+                message_ids: vec![alice_message_id.clone()]
+            }
+        );
+    }
+    alice.receive_next().await;
+
+    let alice_messages = alice_room
+        .load_messages_with_ids(&[alice_message_id])
+        .await?;
+    assert_eq!(1, alice_messages.len());
+    assert_eq!("Hi Alice", alice_messages[0].body.raw);
+    assert_eq!(
+        ParticipantId::from(bob_id.clone()),
+        alice_messages[0].from.id
+    );
+
+    let bob_messages = bob_room.load_messages_with_ids(&[bob_message_id]).await?;
+    assert_eq!(1, bob_messages.len());
+    assert_eq!("Hi Alice", bob_messages[0].body.raw);
+    assert_eq!(ParticipantId::from(bob_id.clone()), bob_messages[0].from.id);
+
+    // Each client only knows about its own account…
+    assert_eq!(alice_id, alice.connected_user_id().unwrap().into_user_id());
+    assert_eq!(bob_id, bob.connected_user_id().unwrap().into_user_id());
+
+    // …and only sees its own copy of the message.
+    assert_eq!(1, alice.cache.cache_usage().await?.messages.rows);
+    assert_eq!(1, bob.cache.cache_usage().await?.messages.rows);
+
+    let alice_sidebar = alice.sidebar.sidebar_items().await;
+    assert_eq!(1, alice_sidebar.len());
+    assert_eq!(
+        alice_room.jid(),
+        alice_sidebar[0].room.to_generic_room().jid()
+    );
+
+    let bob_sidebar = bob.sidebar.sidebar_items().await;
+    assert_eq!(1, bob_sidebar.len());
+    assert_eq!(bob_room.jid(), bob_sidebar[0].room.to_generic_room().jid());
+
+    Ok(())
+}
@@ -6,11 +6,12 @@
 use anyhow::Result;
 use minidom::Element;
 
+use prose_core_client::domain::encryption::services::EncryptionError;
 use prose_core_client::domain::settings::models::SyncedRoomSettings;
 use prose_core_client::domain::shared::models::AccountId;
 use prose_core_client::dtos::{
     DeviceBundle, DeviceId, DeviceInfo, DeviceTrust, SendMessageRequest, SendMessageRequestBody,
-    UserId,
+    TrustPolicy, UserId,
 };
 use prose_core_client::{account_id, user_id, ClientEvent, ClientRoomEventType};
 use prose_proc_macros::mt_test;
@@ -149,6 +150,7 @@ async fn test_does_not_start_session_when_sending_message_in_non_encrypted_room(
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -166,6 +168,8 @@ async fn test_does_not_start_session_when_sending_message_in_non_encrypted_room(
             text: "Hello World".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -199,6 +203,8 @@ async fn test_sending_encrypted_message_fails_if_recipient_has_no_devices() -> R
                 text: "Hello World".into(),
             }),
             attachments: vec![],
+            reply_to: None,
+            thread_id: None,
         })
         .await;
 
@@ -271,6 +277,7 @@ async fn test_start_session_when_sending_message_in_encrypted_room() -> Result<(
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -288,6 +295,8 @@ async fn test_start_session_when_sending_message_in_encrypted_room() -> Result<(
             text: "Hello World".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -311,6 +320,7 @@ async fn test_start_session_when_sending_message_in_encrypted_room() -> Result<(
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -328,6 +338,8 @@ async fn test_start_session_when_sending_message_in_encrypted_room() -> Result<(
             text: "Hello World 2".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -376,6 +388,7 @@ async fn test_starts_session_for_new_devices_when_sending() -> Result<()> {
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -393,6 +406,8 @@ async fn test_starts_session_for_new_devices_when_sending() -> Result<()> {
             text: "Hello World".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -439,6 +454,7 @@ async fn test_starts_session_for_new_devices_when_sending() -> Result<()> {
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -456,6 +472,8 @@ async fn test_starts_session_for_new_devices_when_sending() -> Result<()> {
             text: "Hello World 2".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -510,6 +528,7 @@ async fn test_marks_disappeared_devices_as_inactive_and_reappeared_as_active() -
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -527,6 +546,8 @@ async fn test_marks_disappeared_devices_as_inactive_and_reappeared_as_active() -
             text: "Hello World".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -563,6 +584,14 @@ async fn test_marks_disappeared_devices_as_inactive_and_reappeared_as_active() -
         "#
     );
 
+    event!(
+        client,
+        ClientEvent::NewDeviceDetected {
+            user_id: user_id!("them@prose.org"),
+            device_id: 333.into(),
+        }
+    );
+
     client.receive_next().await;
 
     client.expect_load_device_bundle(
@@ -588,6 +617,7 @@ async fn test_marks_disappeared_devices_as_inactive_and_reappeared_as_active() -
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -605,6 +635,8 @@ async fn test_marks_disappeared_devices_as_inactive_and_reappeared_as_active() -
             text: "Hello World".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -642,6 +674,14 @@ async fn test_marks_disappeared_devices_as_inactive_and_reappeared_as_active() -
         "#
     );
 
+    event!(
+        client,
+        ClientEvent::NewDeviceDetected {
+            user_id: user_id!("them@prose.org"),
+            device_id: 111.into(),
+        }
+    );
+
     client.receive_next().await;
 
     send!(
@@ -662,6 +702,7 @@ async fn test_marks_disappeared_devices_as_inactive_and_reappeared_as_active() -
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -679,6 +720,8 @@ async fn test_marks_disappeared_devices_as_inactive_and_reappeared_as_active() -
             text: "Hello World".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -753,6 +796,7 @@ async fn test_marks_own_disappeared_devices_as_inactive() -> Result<()> {
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -770,6 +814,8 @@ async fn test_marks_own_disappeared_devices_as_inactive() -> Result<()> {
             text: "Hello World".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -810,6 +856,7 @@ async fn test_marks_own_disappeared_devices_as_inactive() -> Result<()> {
           <active xmlns="http://jabber.org/protocol/chatstates" />
           <markable xmlns="urn:xmpp:chat-markers:0" />
           <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
         </message>
         "#
     );
@@ -827,6 +874,8 @@ async fn test_marks_own_disappeared_devices_as_inactive() -> Result<()> {
             text: "Hello World".into(),
         }),
         attachments: vec![],
+        reply_to: None,
+        thread_id: None,
     })
     .await?;
 
@@ -855,10 +904,12 @@ async fn test_starts_session_and_decrypts_received_messages() -> Result<()> {
     let service = TestClient::their_encryption_domain_service(user_id!("them@prose.org")).await;
     let encrypted_payload = service
         .encrypt_message(
-            vec![user_id!("user@prose.org")],
+            &[user_id!("user@prose.org")],
             "Can you read this?".to_string(),
+            TrustPolicy::default(),
         )
-        .await?;
+        .await?
+        .payload;
 
     client.push_ctx([(
         "ENCRYPTED_PAYLOAD",
@@ -930,6 +981,32 @@ async fn test_starts_session_and_decrypts_received_messages() -> Result<()> {
         .load_user_device_infos(&user_id!("them@prose.org"))
         .await?;
 
+    // The message is flagged with our trust in the device that sent it…
+    assert_eq!(
+        messages.first().unwrap().flags.sender_device_trust,
+        Some(device_infos[0].trust)
+    );
+
+    let fingerprint = client
+        .user_data
+        .load_user_device_fingerprint(
+            &user_id!("them@prose.org"),
+            &TestClient::their_device_id().into(),
+        )
+        .await?
+        .expect("Missing fingerprint");
+    assert_eq!(fingerprint.to_string(), device_infos[0].fingerprint());
+    assert!(fingerprint
+        .hex_groups()
+        .iter()
+        .all(|group| group.len() == 8 && group.to_lowercase() == *group));
+
+    assert!(client
+        .user_data
+        .load_user_device_fingerprint(&user_id!("them@prose.org"), &DeviceId::from(1))
+        .await?
+        .is_none());
+
     assert_eq!(
         vec![DeviceInfoTest::new(
             TestClient::their_device_id(),
@@ -940,12 +1017,70 @@ async fn test_starts_session_and_decrypts_received_messages() -> Result<()> {
         device_infos.into_device_info_test(),
     );
 
+    // Our own device can't be (un)trusted…
+    assert!(client
+        .user_data
+        .set_user_device_trust(
+            &user_id!("user@prose.org"),
+            &TestClient::device_id().into(),
+            DeviceTrust::Untrusted,
+        )
+        .await
+        .is_err());
+
+    // A fingerprint that doesn't match leaves the trust unchanged…
+    assert!(
+        !client
+            .user_data
+            .verify_user_device_fingerprint(
+                &user_id!("them@prose.org"),
+                &TestClient::their_device_id().into(),
+                "00000000 11111111 22222222 33333333 44444444 55555555 66666666 77777777",
+            )
+            .await?
+    );
+
+    event!(
+        client,
+        ClientEvent::UserDevicesChanged {
+            ids: vec![user_id!("them@prose.org")]
+        }
+    );
+
+    // …while a matching one verifies the device, regardless of its formatting.
+    assert!(
+        client
+            .user_data
+            .verify_user_device_fingerprint(
+                &user_id!("them@prose.org"),
+                &TestClient::their_device_id().into(),
+                &fingerprint.to_string().to_uppercase(),
+            )
+            .await?
+    );
+
+    assert_eq!(
+        vec![DeviceInfoTest::new(
+            TestClient::their_device_id(),
+            DeviceTrust::Verified,
+            true,
+            false
+        )],
+        client
+            .user_data
+            .load_user_device_infos(&user_id!("them@prose.org"))
+            .await?
+            .into_device_info_test(),
+    );
+
     let encrypted_payload = service
         .encrypt_message(
-            vec![user_id!("user@prose.org")],
+            &[user_id!("user@prose.org")],
             "Can you read this too?".to_string(),
+            TrustPolicy::default(),
         )
-        .await?;
+        .await?
+        .payload;
 
     client.push_ctx([(
         "ENCRYPTED_PAYLOAD",
@@ -1006,6 +1141,95 @@ async fn test_starts_session_and_decrypts_received_messages() -> Result<()> {
         "<p>Can you read this too?</p>"
     );
 
+    // Our own device's session can't be reset…
+    assert!(client
+        .user_data
+        .reset_user_device_session(&user_id!("user@prose.org"), &TestClient::device_id().into(),)
+        .await
+        .is_err());
+
+    event!(
+        client,
+        ClientEvent::UserDevicesChanged {
+            ids: vec![user_id!("them@prose.org")]
+        }
+    );
+
+    client
+        .user_data
+        .reset_user_device_session(
+            &user_id!("them@prose.org"),
+            &TestClient::their_device_id().into(),
+        )
+        .await?;
+
+    // The identity is gone along with the session…
+    assert!(client
+        .user_data
+        .load_user_device_fingerprint(
+            &user_id!("them@prose.org"),
+            &TestClient::their_device_id().into()
+        )
+        .await?
+        .is_none());
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_encrypt_message_respects_trust_policy() -> Result<()> {
+    let service = TestClient::their_encryption_domain_service(user_id!("them@prose.org")).await;
+
+    // Our session with user@prose.org is undecided, so it must not be used…
+    let result = service
+        .encrypt_message(
+            &[user_id!("user@prose.org")],
+            "Can you read this?".to_string(),
+            TrustPolicy::EncryptToTrustedOnly,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(EncryptionError::UntrustedDevices { devices }) if devices == vec![(user_id!("user@prose.org"), TestClient::device_id().into())]
+    ));
+
+    // Blind trust marks the undecided session as trusted…
+    service
+        .encrypt_message(
+            &[user_id!("user@prose.org")],
+            "Can you read this?".to_string(),
+            TrustPolicy::BlindTrust,
+        )
+        .await?;
+
+    // …so that it can be used with the stricter policy afterwards.
+    service
+        .encrypt_message(
+            &[user_id!("user@prose.org")],
+            "Can you read this too?".to_string(),
+            TrustPolicy::EncryptToTrustedOnly,
+        )
+        .await?;
+
+    // Recipients without usable devices don't prevent encrypting the message for the others,
+    // but are reported. Duplicates and ourselves are ignored.
+    let result = service
+        .encrypt_message(
+            &[
+                user_id!("user@prose.org"),
+                user_id!("other@prose.org"),
+                user_id!("user@prose.org"),
+                user_id!("them@prose.org"),
+            ],
+            "Can you all read this?".to_string(),
+            TrustPolicy::EncryptToTrustedOnly,
+        )
+        .await?;
+    assert_eq!(
+        result.recipients_without_devices,
+        vec![user_id!("other@prose.org")]
+    );
+
     Ok(())
 }
 
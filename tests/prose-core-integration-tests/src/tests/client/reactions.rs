@@ -112,7 +112,8 @@ async fn test_reactions() -> Result<()> {
                         name: "Huxx".to_string(),
                         avatar: None,
                     }
-                ]
+                ],
+                last_updated: Some("2024-07-02T09:37:32Z".parse()?),
             },
             Reaction {
                 emoji: "👍🏽".into(),
@@ -120,11 +121,21 @@ async fn test_reactions() -> Result<()> {
                     id: occupant_id!("room@conf.prose.org/flux").into(),
                     name: "Flux".to_string(),
                     avatar: None,
-                }]
+                }],
+                last_updated: Some("2024-07-02T09:37:32Z".parse()?),
             }
         ],
         message.reactions
     );
 
+    assert_eq!(
+        vec!["👍", "👍🏽"],
+        message
+            .reactions_sorted_by_count()
+            .into_iter()
+            .map(|reaction| reaction.emoji.as_ref())
+            .collect::<Vec<_>>()
+    );
+
     Ok(())
 }
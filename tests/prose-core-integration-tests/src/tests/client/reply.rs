@@ -134,3 +134,163 @@ Reply 2</body>
 
     Ok(())
 }
+
+#[mt_test]
+async fn test_sends_reply() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room = client
+        .start_dm(user_id!("them@prose.org"))
+        .await?
+        .to_generic_room();
+
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="them@prose.org/res" id="their-message-id" to="{{USER_RESOURCE_ID}}" type="chat">
+          <body>Lunch?</body>
+          <markable xmlns="urn:xmpp:chat-markers:0" />
+          <store xmlns="urn:xmpp:hints" />
+        </message>
+        "#
+    );
+
+    let message_id = client.get_next_message_id();
+
+    event!(client, ClientEvent::SidebarChanged);
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::MessagesAppended {
+            message_ids: vec![message_id.clone()]
+        }
+    );
+
+    client.receive_next().await;
+
+    send!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{USER_RESOURCE_ID}}" id="{{MSG_ID}}" to="them@prose.org" type="chat">
+          <body>&gt; Lunch?
+Sure!</body>
+          <content xmlns="urn:xmpp:content" type="text/markdown">Sure!</content>
+          <reply xmlns="urn:xmpp:reply:0" id="their-message-id" to="them@prose.org" />
+          <fallback xmlns="urn:xmpp:fallback:0" for="urn:xmpp:reply:0">
+            <body start="0" end="9" />
+          </fallback>
+          <active xmlns="http://jabber.org/protocol/chatstates" />
+          <markable xmlns="urn:xmpp:chat-markers:0" />
+          <store xmlns="urn:xmpp:hints" />
+          <request xmlns="urn:xmpp:receipts" />
+        </message>
+        "#
+    );
+
+    let reply_id = client.get_last_message_id();
+
+    room_event!(
+        client,
+        room.jid().clone(),
+        ClientRoomEventType::MessagesAppended {
+            message_ids: vec![reply_id.clone()]
+        }
+    );
+
+    room.send_message(SendMessageRequest {
+        body: Some(SendMessageRequestBody {
+            text: "Sure!".into(),
+        }),
+        attachments: vec![],
+        reply_to: Some(message_id.clone()),
+        thread_id: None,
+    })
+    .await?;
+
+    let messages = room.load_messages_with_ids(&[reply_id]).await?;
+
+    assert_eq!(1, messages.len());
+    assert_eq!("Sure!", messages[0].body.raw);
+
+    let reply_to = messages[0].reply_to.clone().expect("Missing reply_to");
+    assert_eq!(Some(message_id), reply_to.id);
+    assert_eq!(Some("Lunch?".to_string()), reply_to.body);
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_loads_thread() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room = client
+        .start_dm(user_id!("them@prose.org"))
+        .await?
+        .to_generic_room();
+
+    let mut message_ids = vec![];
+
+    for (idx, body) in ["Lunch?", "Pizza or Sushi?", "Let's do Pizza."]
+        .into_iter()
+        .enumerate()
+    {
+        client.push_ctx([
+            ("REMOTE_ID", format!("their-message-{idx}")),
+            ("BODY", body.to_string()),
+        ]);
+
+        recv!(
+            client,
+            r#"
+            <message xmlns="jabber:client" from="them@prose.org/res" id="{{REMOTE_ID}}" to="{{USER_RESOURCE_ID}}" type="chat">
+              <body>{{BODY}}</body>
+              <thread>lunch-thread</thread>
+              <markable xmlns="urn:xmpp:chat-markers:0" />
+              <store xmlns="urn:xmpp:hints" />
+            </message>
+            "#
+        );
+
+        let message_id = client.get_next_message_id();
+
+        event!(client, ClientEvent::SidebarChanged);
+        room_event!(
+            client,
+            room.jid().clone(),
+            ClientRoomEventType::MessagesAppended {
+                message_ids: vec![message_id.clone()]
+            }
+        );
+
+        client.receive_next().await;
+        client.pop_ctx();
+
+        message_ids.push(message_id);
+    }
+
+    let messages = room.load_thread(&"lunch-thread".into()).await?;
+
+    assert_eq!(
+        message_ids,
+        messages
+            .iter()
+            .map(|message| message.id.clone())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(2, messages[0].thread_reply_count);
+    assert_eq!(
+        Some(messages[2].timestamp),
+        messages[0].last_thread_reply_at
+    );
+    assert_eq!(0, messages[1].thread_reply_count);
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+// prose-core-client/prose-core-integration-tests
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::{FutureExt, StreamExt};
+use parking_lot::Mutex;
+use pretty_assertions::assert_eq;
+
+use prose_core_client::dtos::{MucId, RoomId, UserId};
+use prose_core_client::{muc_id, user_id, ClientEvent, ClientRoomEventType};
+use prose_proc_macros::mt_test;
+
+use crate::{recv, room_event};
+
+use super::helpers::TestClient;
+
+#[mt_test]
+async fn test_room_subscription_only_receives_events_of_its_room() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room1_id = muc_id!("room1@conference.prose.org");
+    let room2_id = muc_id!("room2@conference.prose.org");
+
+    client.join_room(room1_id.clone(), "anon-id-1").await?;
+    client.join_room(room2_id.clone(), "anon-id-2").await?;
+
+    let room1 = client.get_room(room1_id.clone()).await.to_generic_room();
+
+    let updates = Arc::new(Mutex::new(vec![]));
+    let subscription = {
+        let updates = updates.clone();
+        room1.subscribe_with_handler(Box::new(move |update| updates.lock().push(update)))
+    };
+
+    client.push_ctx([
+        ("ROOM1_OCCUPANT_ID", format!("{room1_id}/their-nick")),
+        ("ROOM2_OCCUPANT_ID", format!("{room2_id}/their-nick")),
+    ]);
+
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{ROOM2_OCCUPANT_ID}}" id="message-id-1" to="{{USER_RESOURCE_ID}}" type="groupchat">
+            <composing xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room_event!(
+        client,
+        room2_id.clone(),
+        ClientRoomEventType::ComposingUsersChanged
+    );
+    client.receive_next().await;
+
+    assert!(updates.lock().is_empty());
+
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{ROOM1_OCCUPANT_ID}}" id="message-id-2" to="{{USER_RESOURCE_ID}}" type="groupchat">
+            <composing xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room_event!(
+        client,
+        room1_id.clone(),
+        ClientRoomEventType::ComposingUsersChanged
+    );
+    client.receive_next().await;
+
+    assert_eq!(
+        vec![ClientRoomEventType::ComposingUsersChanged],
+        *updates.lock()
+    );
+
+    // Dropping the subscription unsubscribes…
+    drop(subscription);
+
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{ROOM1_OCCUPANT_ID}}" id="message-id-3" to="{{USER_RESOURCE_ID}}" type="groupchat">
+            <paused xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room_event!(
+        client,
+        room1_id.clone(),
+        ClientRoomEventType::ComposingUsersChanged
+    );
+    client.receive_next().await;
+
+    assert_eq!(1, updates.lock().len());
+
+    Ok(())
+}
+
+#[mt_test]
+async fn test_event_streams_deliver_events_in_order() -> Result<()> {
+    let client = TestClient::new().await;
+
+    client
+        .expect_login(user_id!("user@prose.org"), "secret")
+        .await?;
+
+    let room1_id = muc_id!("room1@conference.prose.org");
+    let room2_id = muc_id!("room2@conference.prose.org");
+
+    client.join_room(room1_id.clone(), "anon-id-1").await?;
+    client.join_room(room2_id.clone(), "anon-id-2").await?;
+
+    let mut events = client.subscribe();
+    let mut room1_events = client.room_events(room1_id.clone().into());
+
+    client.push_ctx([
+        ("ROOM1_OCCUPANT_ID", format!("{room1_id}/their-nick")),
+        ("ROOM2_OCCUPANT_ID", format!("{room2_id}/their-nick")),
+    ]);
+
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{ROOM2_OCCUPANT_ID}}" id="message-id-1" to="{{USER_RESOURCE_ID}}" type="groupchat">
+            <composing xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room_event!(
+        client,
+        room2_id.clone(),
+        ClientRoomEventType::ComposingUsersChanged
+    );
+    client.receive_next().await;
+
+    recv!(
+        client,
+        r#"
+        <message xmlns="jabber:client" from="{{ROOM1_OCCUPANT_ID}}" id="message-id-2" to="{{USER_RESOURCE_ID}}" type="groupchat">
+            <composing xmlns="http://jabber.org/protocol/chatstates" />
+        </message>
+        "#
+    );
+    room_event!(
+        client,
+        room1_id.clone(),
+        ClientRoomEventType::ComposingUsersChanged
+    );
+    client.receive_next().await;
+
+    let mut received_room_ids = vec![];
+    while let Some(Some(event)) = events.next().now_or_never() {
+        let ClientEvent::RoomChanged { room, r#type } = event else {
+            panic!("Expected to receive a ClientEvent::RoomChanged. Received: {event:?}");
+        };
+        assert_eq!(ClientRoomEventType::ComposingUsersChanged, r#type);
+        received_room_ids.push(room.to_generic_room().jid().clone());
+    }
+
+    assert_eq!(
+        vec![RoomId::from(room2_id), RoomId::from(room1_id)],
+        received_room_ids
+    );
+    assert_eq!(0, events.missed_events());
+
+    assert_eq!(
+        Some(Some(ClientRoomEventType::ComposingUsersChanged)),
+        room1_events.next().now_or_never()
+    );
+    assert_eq!(None, room1_events.next().now_or_never());
+
+    Ok(())
+}
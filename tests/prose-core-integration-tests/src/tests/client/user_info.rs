@@ -63,6 +63,8 @@ async fn test_aggregates_user_data() -> Result<()> {
                 avatar: None,
                 availability: Default::default(),
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::Mutual,
             },
@@ -73,6 +75,8 @@ async fn test_aggregates_user_data() -> Result<()> {
                 avatar: None,
                 availability: Default::default(),
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::Mutual,
             }
@@ -113,6 +117,8 @@ async fn test_aggregates_user_data() -> Result<()> {
                 avatar: None,
                 availability: Default::default(),
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::Mutual,
             },
@@ -123,6 +129,8 @@ async fn test_aggregates_user_data() -> Result<()> {
                 avatar: None,
                 availability: Default::default(),
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::Mutual,
             }
@@ -153,6 +161,8 @@ async fn test_aggregates_user_data() -> Result<()> {
                 avatar: None,
                 availability: Default::default(),
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::Mutual,
             },
@@ -163,6 +173,8 @@ async fn test_aggregates_user_data() -> Result<()> {
                 avatar: None,
                 availability: Default::default(),
                 status: None,
+                status_message: None,
+                last_seen: None,
                 group: Group::Team,
                 presence_subscription: PresenceSubscription::Mutual,
             }
@@ -265,13 +277,17 @@ async fn test_joins_room() -> Result<()> {
     room_event!(
         client,
         room_id.clone(),
-        ClientRoomEventType::ParticipantsChanged
+        ClientRoomEventType::ParticipantsChanged {
+            ids: vec![room_id.occupant_id_with_nickname("user2")?.into()]
+        }
     );
 
     room_event!(
         client,
         room_id.clone(),
-        ClientRoomEventType::ParticipantsChanged
+        ClientRoomEventType::ParticipantsChanged {
+            ids: vec![room_id.occupant_id_with_nickname("user2")?.into()]
+        }
     );
 
     client.receive_next().await;
@@ -5,7 +5,8 @@
 
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{format_err, Result};
+use chrono::{TimeZone, Utc};
 
 use prose_core_client::domain::contacts::models::Contact;
 use prose_core_client::domain::contacts::repos::ContactListRepository;
@@ -14,20 +15,24 @@ use prose_core_client::domain::shared::models::{AccountId, UserId};
 use prose_core_client::dtos::PresenceSubscription;
 use prose_core_client::infra::contacts::CachingContactsRepository;
 use prose_core_client::{account_id, user_id};
+use prose_proc_macros::store_test;
+use prose_store::prelude::{PlatformDriver, Store};
 
-use crate::tests::async_test;
+use crate::tests::{async_test, in_memory_store, store};
 
-#[async_test]
-async fn test_loads_and_caches_contacts() -> Result<()> {
+#[store_test]
+async fn test_loads_and_caches_contacts(store: Store<PlatformDriver>) -> Result<()> {
     let contacts = vec![
         Contact {
             id: user_id!("a@prose.org"),
             name: Some("User A".to_string()),
+            groups: vec![],
             presence_subscription: PresenceSubscription::Requested,
         },
         Contact {
             id: user_id!("b@prose.org"),
             name: None,
+            groups: vec![],
             presence_subscription: PresenceSubscription::Requested,
         },
     ];
@@ -42,7 +47,7 @@ async fn test_loads_and_caches_contacts() -> Result<()> {
         service
     };
 
-    let repo = CachingContactsRepository::new(Arc::new(service));
+    let repo = CachingContactsRepository::new(Arc::new(service), store);
     assert_eq!(
         repo.get_all(&account_id!("user@prose.org")).await?,
         contacts
@@ -54,3 +59,110 @@ async fn test_loads_and_caches_contacts() -> Result<()> {
 
     Ok(())
 }
+
+#[store_test]
+async fn test_serves_stored_contacts_while_offline(store: Store<PlatformDriver>) -> Result<()> {
+    let contacts = vec![Contact {
+        id: user_id!("a@prose.org"),
+        name: Some("User A".to_string()),
+        groups: vec!["Friends".to_string()],
+        presence_subscription: PresenceSubscription::Mutual,
+    }];
+
+    let account = account_id!("user@prose.org");
+
+    let service = {
+        let contacts = contacts.clone();
+        let mut service = MockContactListService::new();
+        service
+            .expect_load_contacts()
+            .times(1)
+            .return_once(|| Box::pin(async move { Ok(contacts) }));
+        service
+    };
+
+    let repo = CachingContactsRepository::new(Arc::new(service), store.clone());
+    assert_eq!(repo.get_all(&account).await?, contacts);
+
+    let service = {
+        let mut service = MockContactListService::new();
+        service
+            .expect_load_contacts()
+            .times(1)
+            .return_once(|| Box::pin(async { Err(format_err!("Not connected")) }));
+        service
+    };
+
+    let repo = CachingContactsRepository::new(Arc::new(service), store.clone());
+    assert_eq!(repo.get_all(&account).await?, contacts);
+
+    // Without any stored contacts the error is passed on…
+    repo.clear_cache(&account).await?;
+
+    let service = {
+        let mut service = MockContactListService::new();
+        service
+            .expect_load_contacts()
+            .times(1)
+            .return_once(|| Box::pin(async { Err(format_err!("Not connected")) }));
+        service
+    };
+
+    let repo = CachingContactsRepository::new(Arc::new(service), store);
+    assert!(repo.get_all(&account).await.is_err());
+
+    Ok(())
+}
+
+#[store_test]
+async fn test_keeps_last_seen_when_roster_is_reloaded(store: Store<PlatformDriver>) -> Result<()> {
+    let contacts = vec![Contact {
+        id: user_id!("a@prose.org"),
+        name: None,
+        groups: vec![],
+        presence_subscription: PresenceSubscription::Mutual,
+    }];
+
+    let account = account_id!("user@prose.org");
+    let last_seen = Utc.with_ymd_and_hms(2024, 05, 01, 12, 0, 0).unwrap();
+
+    let service = {
+        let contacts = contacts.clone();
+        let mut service = MockContactListService::new();
+        service.expect_load_contacts().times(2).returning(move || {
+            let contacts = contacts.clone();
+            Box::pin(async move { Ok(contacts) })
+        });
+        service
+    };
+    let service = Arc::new(service);
+
+    let repo = CachingContactsRepository::new(service.clone(), store.clone());
+    repo.set_last_seen(&account, &user_id!("a@prose.org"), last_seen)
+        .await?;
+    // Contacts that are not in the roster are ignored…
+    repo.set_last_seen(&account, &user_id!("stranger@prose.org"), last_seen)
+        .await?;
+
+    assert_eq!(
+        repo.get_last_seen(&account, &user_id!("a@prose.org"))
+            .await?,
+        Some(last_seen)
+    );
+    assert_eq!(
+        repo.get_last_seen(&account, &user_id!("stranger@prose.org"))
+            .await?,
+        None
+    );
+
+    // A fresh repository loads the roster again and replaces the stored contacts…
+    let repo = CachingContactsRepository::new(service, store);
+    assert_eq!(repo.get_all(&account).await?, contacts);
+    assert_eq!(
+        repo.get_last_seen(&account, &user_id!("a@prose.org"))
+            .await?,
+        Some(last_seen)
+    );
+
+    Ok(())
+}
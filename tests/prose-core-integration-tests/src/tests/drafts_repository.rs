@@ -5,16 +5,19 @@
 
 use anyhow::Result;
 
+use prose_core_client::domain::messaging::models::{Draft, Mention};
 use prose_core_client::domain::messaging::repos::DraftsRepository as DomainDraftsRepository;
-use prose_core_client::domain::shared::models::{AccountId, RoomId, UserId};
+use prose_core_client::domain::shared::models::{AccountId, RoomId, UnicodeScalarIndex, UserId};
 use prose_core_client::infra::messaging::DraftsRepository;
 use prose_core_client::{account_id, user_id};
+use prose_proc_macros::store_test;
+use prose_store::prelude::{PlatformDriver, Store};
 
-use crate::tests::{async_test, store};
+use crate::tests::{async_test, in_memory_store, store};
 
-#[async_test]
-async fn test_saves_and_loads_draft() -> Result<()> {
-    let repo = DraftsRepository::new(store().await?);
+#[store_test]
+async fn test_saves_and_loads_draft(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = DraftsRepository::new(store);
 
     let jid_a = RoomId::from(user_id!("a@prose.org"));
     let jid_b = RoomId::from(user_id!("b@prose.org"));
@@ -23,16 +26,39 @@ async fn test_saves_and_loads_draft() -> Result<()> {
     assert_eq!(repo.get(&account, &jid_a).await?, None);
     assert_eq!(repo.get(&account, &jid_b).await?, None);
 
-    repo.set(&account, &jid_a, Some("Hello")).await?;
-    repo.set(&account, &jid_b, Some("World")).await?;
+    repo.set(&account, &jid_a, Some(&Draft::new("Hello")))
+        .await?;
+    repo.set(&account, &jid_b, Some(&Draft::new("World")))
+        .await?;
 
-    assert_eq!(repo.get(&account, &jid_a).await?, Some("Hello".to_string()));
-    assert_eq!(repo.get(&account, &jid_b).await?, Some("World".to_string()));
+    assert_eq!(repo.get(&account, &jid_a).await?, Some(Draft::new("Hello")));
+    assert_eq!(repo.get(&account, &jid_b).await?, Some(Draft::new("World")));
 
     repo.set(&account, &jid_b, None).await?;
 
-    assert_eq!(repo.get(&account, &jid_a).await?, Some("Hello".to_string()));
+    assert_eq!(repo.get(&account, &jid_a).await?, Some(Draft::new("Hello")));
     assert_eq!(repo.get(&account, &jid_b).await?, None);
 
     Ok(())
 }
+
+#[store_test]
+async fn test_saves_and_loads_draft_with_mentions(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = DraftsRepository::new(store);
+
+    let room_id = RoomId::from(user_id!("a@prose.org"));
+    let account = account_id!("user@prose.org");
+
+    let draft = Draft {
+        text: "Hey [@Jane](xmpp:jane@prose.org), how are you?".into(),
+        mentions: vec![Mention {
+            user: user_id!("jane@prose.org"),
+            range: Some(UnicodeScalarIndex::new(4)..UnicodeScalarIndex::new(32)),
+        }],
+    };
+
+    repo.set(&account, &room_id, Some(&draft)).await?;
+    assert_eq!(repo.get(&account, &room_id).await?, Some(draft));
+
+    Ok(())
+}
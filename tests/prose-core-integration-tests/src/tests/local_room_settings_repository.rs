@@ -12,12 +12,14 @@ use prose_core_client::domain::settings::repos::LocalRoomSettingsRepository as L
 use prose_core_client::domain::shared::models::{AccountId, UserId};
 use prose_core_client::infra::settings::LocalRoomSettingsRepository;
 use prose_core_client::{account_id, user_id};
+use prose_proc_macros::store_test;
+use prose_store::prelude::{PlatformDriver, Store};
 
-use crate::tests::{async_test, store};
+use crate::tests::{async_test, in_memory_store, store};
 
-#[async_test]
-async fn test_save_and_load_local_room_settings() -> Result<()> {
-    let repo = LocalRoomSettingsRepository::new(store().await?);
+#[store_test]
+async fn test_save_and_load_local_room_settings(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = LocalRoomSettingsRepository::new(store);
 
     assert_eq!(
         repo.get(
@@ -3,24 +3,31 @@
 // Copyright: 2023, Marc Bauer <mb@nesium.com>
 // License: Mozilla Public License v2.0 (MPL v2.0)
 
+use std::collections::HashSet;
+
 use anyhow::Result;
 use chrono::{DateTime, TimeZone, Utc};
 use pretty_assertions::assert_eq;
 
 use prose_core_client::domain::messaging::models::{
-    ArchivedMessageRef, MessageLike, MessageLikePayload, MessageTargetId,
+    ArchivedMessageRef, Attachment, AttachmentKind, AttachmentType, MessageLike, MessageLikeBody,
+    MessageLikePayload, MessageTargetId,
 };
 use prose_core_client::domain::messaging::repos::MessagesRepository;
-use prose_core_client::domain::shared::models::{AccountId, MucId, RoomId, UserId};
-use prose_core_client::infra::messaging::CachingMessageRepository;
+use prose_core_client::domain::shared::models::{
+    AccountId, MucId, OccupantId, ParticipantId, RoomId, UserId,
+};
+use prose_core_client::infra::messaging::{CachingMessageRepository, MessageRecord, StoredPayload};
 use prose_core_client::test::MessageBuilder;
-use prose_core_client::{account_id, muc_id, user_id};
+use prose_core_client::{account_id, muc_id, occupant_id, user_id};
+use prose_proc_macros::store_test;
+use prose_store::prelude::*;
 
-use crate::tests::{async_test, store};
+use crate::tests::{async_test, in_memory_store, store};
 
-#[async_test]
-async fn test_can_insert_same_message_twice() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_can_insert_same_message_twice(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("a@prose.org"));
     let message = MessageBuilder::new_with_index(123)
@@ -49,9 +56,9 @@ async fn test_can_insert_same_message_twice() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_loads_message_with_reactions() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_loads_message_with_reactions(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("a@prose.org"));
 
@@ -91,9 +98,9 @@ async fn test_loads_message_with_reactions() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_loads_groupchat_message_with_reactions() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_loads_groupchat_message_with_reactions(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("a@prose.org"));
 
@@ -129,9 +136,9 @@ async fn test_loads_groupchat_message_with_reactions() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_load_messages_targeting() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_load_messages_targeting(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("a@prose.org"));
 
@@ -140,6 +147,8 @@ async fn test_load_messages_targeting() -> Result<()> {
     let message3 = MessageBuilder::new_with_index(3)
         .set_payload(MessageLikePayload::Retraction {
             target_id: MessageBuilder::remote_id_for_index(1).into(),
+            reason: None,
+            moderated_by: None,
         })
         .build_message_like();
     let message4 = MessageBuilder::new_with_index(4)
@@ -183,9 +192,9 @@ async fn test_load_messages_targeting() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_load_only_messages_targeting() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_load_only_messages_targeting(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("a@prose.org"));
 
@@ -195,6 +204,8 @@ async fn test_load_only_messages_targeting() -> Result<()> {
     let message3 = MessageBuilder::new_with_index(3)
         .set_payload(MessageLikePayload::Retraction {
             target_id: MessageBuilder::remote_id_for_index(1).into(),
+            reason: None,
+            moderated_by: None,
         })
         .set_timestamp(Utc.with_ymd_and_hms(2024, 01, 01, 0, 0, 0).unwrap())
         .build_message_like();
@@ -255,27 +266,33 @@ async fn test_load_only_messages_targeting() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_load_only_messages_targeting_sort_order() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_load_only_messages_targeting_sort_order(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("a@prose.org"));
 
     let message1 = MessageBuilder::new_with_index(1)
         .set_payload(MessageLikePayload::Retraction {
             target_id: MessageBuilder::remote_id_for_index(100).into(),
+            reason: None,
+            moderated_by: None,
         })
         .set_timestamp(Utc.with_ymd_and_hms(2024, 01, 02, 0, 0, 0).unwrap())
         .build_message_like();
     let message2 = MessageBuilder::new_with_index(2)
         .set_payload(MessageLikePayload::Retraction {
             target_id: MessageBuilder::remote_id_for_index(100).into(),
+            reason: None,
+            moderated_by: None,
         })
         .set_timestamp(Utc.with_ymd_and_hms(2024, 01, 03, 0, 0, 0).unwrap())
         .build_message_like();
     let message3 = MessageBuilder::new_with_index(3)
         .set_payload(MessageLikePayload::Retraction {
             target_id: MessageBuilder::remote_id_for_index(100).into(),
+            reason: None,
+            moderated_by: None,
         })
         .set_timestamp(Utc.with_ymd_and_hms(2024, 01, 01, 0, 0, 0).unwrap())
         .build_message_like();
@@ -303,9 +320,9 @@ async fn test_load_only_messages_targeting_sort_order() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_resolves_server_id_to_message_id() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_resolves_server_id_to_message_id(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("a@prose.org"));
     let message = MessageBuilder::new_with_index(101).build_message_like();
@@ -338,9 +355,9 @@ async fn test_resolves_server_id_to_message_id() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_resolves_remote_id_to_message_id() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_resolves_remote_id_to_message_id(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("a@prose.org"));
     let message = MessageBuilder::new_with_index(101).build_message_like();
@@ -373,9 +390,61 @@ async fn test_resolves_remote_id_to_message_id() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_resolves_message_id_to_remote_id() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_finds_cached_messages_by_server_or_remote_id(
+    store: Store<PlatformDriver>,
+) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
+
+    let room_id = RoomId::from(user_id!("a@prose.org"));
+    repo.append(
+        &account_id!("account@prose.org"),
+        &room_id,
+        &[
+            MessageBuilder::new_with_index(1).build_message_like(),
+            MessageBuilder::new_with_index(2)
+                .set_server_id(None)
+                .build_message_like(),
+        ],
+    )
+    .await?;
+
+    let cached_ids = repo
+        .find_cached(
+            &account_id!("account@prose.org"),
+            &room_id,
+            &[
+                // Cached under its server id…
+                MessageBuilder::new_with_index(1)
+                    .set_id("import-1")
+                    .build_message_like(),
+                // …without a server id, the remote id is used…
+                MessageBuilder::new_with_index(2)
+                    .set_id("import-2")
+                    .set_server_id(None)
+                    .build_message_like(),
+                // …but a different server id makes it a different message.
+                MessageBuilder::new_with_index(2)
+                    .set_id("import-3")
+                    .build_message_like(),
+                MessageBuilder::new_with_index(4)
+                    .set_id("import-4")
+                    .build_message_like(),
+            ],
+        )
+        .await?;
+
+    assert_eq!(
+        HashSet::from(["import-1".into(), "import-2".into()]),
+        cached_ids
+    );
+
+    Ok(())
+}
+
+#[store_test]
+async fn test_resolves_message_id_to_remote_id(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("a@prose.org"));
     let message = MessageBuilder::new_with_index(101).build_message_like();
@@ -408,9 +477,9 @@ async fn test_resolves_message_id_to_remote_id() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_get_messages_after() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_get_messages_after(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     repo.append(
         &account_id!("a@prose.org"),
@@ -489,9 +558,81 @@ async fn test_get_messages_after() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_clears_cache() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_get_messages_in_range(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
+
+    repo.append(
+        &account_id!("a@prose.org"),
+        &muc_id!("room@prose.org").into(),
+        &[
+            MessageBuilder::new_with_index(1)
+                .set_timestamp(Utc.with_ymd_and_hms(2024, 05, 24, 10, 00, 00).unwrap())
+                .build_message_like(),
+            MessageBuilder::new_with_index(2)
+                .set_timestamp(Utc.with_ymd_and_hms(2024, 05, 24, 11, 00, 00).unwrap())
+                .build_message_like(),
+            MessageBuilder::new_with_index(3)
+                .set_timestamp(Utc.with_ymd_and_hms(2024, 05, 24, 12, 00, 00).unwrap())
+                .build_message_like(),
+            MessageBuilder::new_with_index(4)
+                .set_timestamp(Utc.with_ymd_and_hms(2024, 05, 24, 13, 00, 00).unwrap())
+                .build_message_like(),
+        ],
+    )
+    .await?;
+
+    repo.append(
+        &account_id!("a@prose.org"),
+        &muc_id!("other-room@prose.org").into(),
+        &[MessageBuilder::new_with_index(10)
+            .set_timestamp(Utc.with_ymd_and_hms(2024, 05, 24, 11, 30, 00).unwrap())
+            .build_message_like()],
+    )
+    .await?;
+
+    assert_eq!(
+        vec![
+            MessageBuilder::remote_id_for_index(2),
+            MessageBuilder::remote_id_for_index(3)
+        ],
+        repo.get_messages_in_range(
+            &account_id!("a@prose.org"),
+            &muc_id!("room@prose.org").into(),
+            Utc.with_ymd_and_hms(2024, 05, 24, 11, 00, 00).unwrap(),
+            Utc.with_ymd_and_hms(2024, 05, 24, 12, 00, 00).unwrap(),
+            10
+        )
+        .await?
+        .into_iter()
+        .map(|m| m.remote_id.unwrap())
+        .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        vec![
+            MessageBuilder::remote_id_for_index(1),
+            MessageBuilder::remote_id_for_index(2)
+        ],
+        repo.get_messages_in_range(
+            &account_id!("a@prose.org"),
+            &muc_id!("room@prose.org").into(),
+            DateTime::<Utc>::MIN_UTC,
+            DateTime::<Utc>::MAX_UTC,
+            2
+        )
+        .await?
+        .into_iter()
+        .map(|m| m.remote_id.unwrap())
+        .collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
+#[store_test]
+async fn test_clears_cache(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     repo.append(
         &account_id!("a@prose.org"),
@@ -590,9 +731,9 @@ async fn test_clears_cache() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_loads_latest_received_message() -> Result<()> {
-    let repo = CachingMessageRepository::new(store().await?);
+#[store_test]
+async fn test_loads_latest_received_message(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
 
     let room_id = RoomId::from(user_id!("room@prose.org"));
 
@@ -657,3 +798,527 @@ async fn test_loads_latest_received_message() -> Result<()> {
 
     Ok(())
 }
+
+#[store_test]
+async fn test_skips_messages_with_unknown_payload(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store.clone());
+
+    let account = account_id!("account@prose.org");
+    let room_id = RoomId::from(user_id!("a@prose.org"));
+
+    let message1 = MessageBuilder::new_with_index(1).build_message_like();
+    let message3 = MessageBuilder::new_with_index(3).build_message_like();
+
+    repo.append(&account, &room_id, &[message1.clone(), message3.clone()])
+        .await?;
+
+    // Simulate a message that was written by a newer version of the client…
+    let mut record = MessageRecord::from_message(
+        account.clone(),
+        room_id.clone(),
+        MessageBuilder::new_with_index(2).build_message_like(),
+    );
+    record.payload = StoredPayload::Unknown(serde_json::json!({
+        "type": "Moderation",
+        "reason": "Spam",
+    }));
+
+    let tx = store
+        .transaction_for_reading_and_writing(&[MessageRecord::collection()])
+        .await?;
+    tx.writeable_collection(MessageRecord::collection())?
+        .put_entity(&record)?;
+    tx.commit().await?;
+
+    assert_eq!(
+        vec![message1.clone(), message3.clone()],
+        repo.get_all(
+            &account,
+            &room_id,
+            &[
+                MessageBuilder::id_for_index(1),
+                MessageBuilder::id_for_index(2),
+                MessageBuilder::id_for_index(3)
+            ]
+        )
+        .await?
+    );
+
+    assert_eq!(
+        vec![message1.clone(), message3.clone()],
+        repo.get_messages_after(&account, &room_id, DateTime::<Utc>::MIN_UTC)
+            .await?
+    );
+
+    let mut strict_repo = CachingMessageRepository::new(store);
+    strict_repo.set_skip_unreadable_messages(false);
+
+    assert!(strict_repo
+        .get_all(&account, &room_id, &[MessageBuilder::id_for_index(2)])
+        .await
+        .is_err());
+
+    Ok(())
+}
+
+#[store_test]
+async fn test_search_messages(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
+
+    let account = account_id!("account@prose.org");
+    let room_id = RoomId::from(user_id!("a@prose.org"));
+    let other_room_id = RoomId::from(user_id!("b@prose.org"));
+
+    let messages = vec![
+        MessageBuilder::new_with_index(1)
+            .set_payload("Let's grab lunch")
+            .build_message_like(),
+        MessageBuilder::new_with_index(2)
+            .set_payload("Lunch was great")
+            .build_message_like(),
+        MessageBuilder::new_with_index(3)
+            .set_payload("Something else")
+            .build_message_like(),
+        MessageBuilder::new_with_index(4)
+            .set_payload(MessageLikePayload::Correction {
+                target_id: MessageTargetId::RemoteId(MessageBuilder::remote_id_for_index(3)),
+                body: MessageLikeBody::text("Lunch tomorrow?"),
+                attachments: vec![],
+                encryption_info: None,
+            })
+            .build_message_like(),
+    ];
+    let other_message = MessageBuilder::new_with_index(5)
+        .set_payload("lunch in other room")
+        .build_message_like();
+
+    repo.append(&account, &room_id, &messages).await?;
+    repo.append(&account, &other_room_id, &[other_message.clone()])
+        .await?;
+
+    assert_eq!(
+        vec![
+            messages[3].clone(),
+            messages[1].clone(),
+            messages[0].clone()
+        ],
+        repo.search(&account, Some(&room_id), "lunch", 10).await?
+    );
+    assert_eq!(
+        vec![
+            other_message.clone(),
+            messages[3].clone(),
+            messages[1].clone(),
+            messages[0].clone()
+        ],
+        repo.search(&account, None, "lunch", 10).await?
+    );
+    assert_eq!(
+        vec![messages[3].clone(), messages[1].clone()],
+        repo.search(&account, Some(&room_id), "lunch", 2).await?
+    );
+    assert_eq!(
+        vec![messages[1].clone()],
+        repo.search(&account, Some(&room_id), "great LUNCH", 10)
+            .await?
+    );
+    assert!(repo
+        .search(&account, Some(&room_id), "", 10)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[store_test]
+async fn test_get_attachments(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
+
+    let account = account_id!("account@prose.org");
+    let room_id = RoomId::from(user_id!("a@prose.org"));
+
+    let attachment = |r#type: AttachmentType, file_name: &str| Attachment {
+        r#type,
+        url: format!("https://uploads.prose.org/{file_name}")
+            .parse()
+            .unwrap(),
+        media_type: "application/octet-stream".parse().unwrap(),
+        file_name: file_name.to_string(),
+        file_size: None,
+    };
+
+    let image1 = attachment(AttachmentType::Image { thumbnail: None }, "1.jpg");
+    let file = attachment(AttachmentType::File, "2.pdf");
+    let image2 = attachment(AttachmentType::Image { thumbnail: None }, "4.jpg");
+    let video = attachment(
+        AttachmentType::Video {
+            duration: None,
+            thumbnail: None,
+        },
+        "5.mp4",
+    );
+
+    repo.append(
+        &account,
+        &room_id,
+        &[
+            message_with_attachment(1, image1.clone()),
+            message_with_attachment(2, file.clone()),
+            MessageBuilder::new_with_index(3).build_message_like(),
+            message_with_attachment(4, image2.clone()),
+            message_with_attachment(5, video.clone()),
+        ],
+    )
+    .await?;
+    repo.append(
+        &account,
+        &RoomId::from(user_id!("b@prose.org")),
+        &[message_with_attachment(6, image1.clone())],
+    )
+    .await?;
+
+    assert_eq!(
+        vec![image2.clone(), image1.clone()],
+        repo.get_attachments(&account, &room_id, &[AttachmentKind::Image], 10, 0)
+            .await?
+    );
+    assert_eq!(
+        vec![video.clone(), image2.clone(), file.clone()],
+        repo.get_attachments(
+            &account,
+            &room_id,
+            &[
+                AttachmentKind::Image,
+                AttachmentKind::Video,
+                AttachmentKind::File
+            ],
+            3,
+            0
+        )
+        .await?
+    );
+    assert_eq!(
+        vec![image2.clone(), file.clone()],
+        repo.get_attachments(&account, &room_id, &[], 2, 1).await?
+    );
+
+    repo.delete(&account, &room_id, &MessageBuilder::id_for_index(4))
+        .await?;
+
+    assert_eq!(
+        vec![image1],
+        repo.get_attachments(&account, &room_id, &[AttachmentKind::Image], 10, 0)
+            .await?
+    );
+
+    Ok(())
+}
+
+#[store_test]
+async fn test_removes_attachments_of_retracted_messages(
+    store: Store<PlatformDriver>,
+) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
+
+    let account = account_id!("account@prose.org");
+    let room_id = RoomId::from(muc_id!("room@prose.org"));
+
+    repo.append(
+        &account,
+        &room_id,
+        &[
+            message_with_attachment(1, image_attachment("1.jpg")),
+            message_with_attachment(2, image_attachment("2.jpg")),
+            message_with_attachment(3, image_attachment("3.jpg")),
+        ],
+    )
+    .await?;
+
+    repo.append(
+        &account,
+        &room_id,
+        &[
+            MessageBuilder::new_with_index(4)
+                .set_payload(MessageLikePayload::Retraction {
+                    target_id: MessageBuilder::remote_id_for_index(1).into(),
+                    reason: None,
+                    moderated_by: None,
+                })
+                .build_message_like(),
+            MessageBuilder::new_with_index(5)
+                .set_payload(MessageLikePayload::Retraction {
+                    target_id: MessageBuilder::stanza_id_for_index(3).into(),
+                    reason: Some("Spam".to_string()),
+                    moderated_by: Some(ParticipantId::Occupant(occupant_id!(
+                        "room@prose.org/moderator"
+                    ))),
+                })
+                .build_message_like(),
+        ],
+    )
+    .await?;
+
+    assert_eq!(
+        vec![image_attachment("2.jpg")],
+        repo.get_attachments(&account, &room_id, &[], 10, 0).await?
+    );
+
+    Ok(())
+}
+
+#[store_test]
+async fn test_ignores_attachments_of_messages_retracted_before_they_were_cached(
+    store: Store<PlatformDriver>,
+) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
+
+    let account = account_id!("account@prose.org");
+    let room_id = RoomId::from(user_id!("a@prose.org"));
+
+    repo.append(
+        &account,
+        &room_id,
+        &[MessageBuilder::new_with_index(2)
+            .set_payload(MessageLikePayload::Retraction {
+                target_id: MessageBuilder::remote_id_for_index(1).into(),
+                reason: None,
+                moderated_by: None,
+            })
+            .build_message_like()],
+    )
+    .await?;
+    repo.append(
+        &account,
+        &room_id,
+        &[message_with_attachment(1, image_attachment("1.jpg"))],
+    )
+    .await?;
+
+    assert!(repo
+        .get_attachments(&account, &room_id, &[], 10, 0)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[store_test]
+async fn test_replaces_attachments_of_corrected_messages(
+    store: Store<PlatformDriver>,
+) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
+
+    let account = account_id!("account@prose.org");
+    let room_id = RoomId::from(user_id!("a@prose.org"));
+
+    let correction = |idx: u32, attachments: Vec<Attachment>| {
+        MessageBuilder::new_with_index(idx)
+            .set_payload(MessageLikePayload::Correction {
+                target_id: MessageBuilder::remote_id_for_index(1).into(),
+                body: MessageLikeBody::text("Corrected"),
+                attachments,
+                encryption_info: None,
+            })
+            .build_message_like()
+    };
+
+    repo.append(
+        &account,
+        &room_id,
+        &[
+            message_with_attachment(1, image_attachment("1.jpg")),
+            message_with_attachment(2, image_attachment("2.jpg")),
+        ],
+    )
+    .await?;
+
+    repo.append(
+        &account,
+        &room_id,
+        &[correction(3, vec![image_attachment("3.jpg")])],
+    )
+    .await?;
+
+    // The attachments of a correction keep the position of the corrected message…
+    assert_eq!(
+        vec![image_attachment("2.jpg"), image_attachment("3.jpg")],
+        repo.get_attachments(&account, &room_id, &[], 10, 0).await?
+    );
+
+    // Corrections arriving out of order don't override later ones…
+    repo.append(
+        &account,
+        &room_id,
+        &[
+            correction(5, vec![]),
+            correction(4, vec![image_attachment("5.jpg")]),
+        ],
+    )
+    .await?;
+
+    assert_eq!(
+        vec![image_attachment("2.jpg")],
+        repo.get_attachments(&account, &room_id, &[], 10, 0).await?
+    );
+
+    Ok(())
+}
+
+fn image_attachment(file_name: &str) -> Attachment {
+    Attachment {
+        r#type: AttachmentType::Image { thumbnail: None },
+        url: format!("https://uploads.prose.org/{file_name}")
+            .parse()
+            .unwrap(),
+        media_type: "image/jpeg".parse().unwrap(),
+        file_name: file_name.to_string(),
+        file_size: None,
+    }
+}
+
+fn message_with_attachment(idx: u32, attachment: Attachment) -> MessageLike {
+    let mut payload = MessageLikePayload::message("hello");
+    if let MessageLikePayload::Message { attachments, .. } = &mut payload {
+        attachments.push(attachment);
+    }
+    MessageBuilder::new_with_index(idx)
+        .set_payload(payload)
+        .build_message_like()
+}
+
+#[store_test]
+async fn test_delete_messages_before(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
+
+    let account = account_id!("a@prose.org");
+    let room_id = RoomId::from(muc_id!("room@prose.org"));
+    let other_room_id = RoomId::from(muc_id!("other-room@prose.org"));
+
+    repo.append(
+        &account,
+        &room_id,
+        &(1..=5)
+            .map(|idx| {
+                MessageBuilder::new_with_index(idx)
+                    .set_timestamp(Utc.with_ymd_and_hms(2024, 05, 24, 10, idx, 00).unwrap())
+                    .build_message_like()
+            })
+            .collect::<Vec<_>>(),
+    )
+    .await?;
+    repo.append(
+        &account,
+        &other_room_id,
+        &[MessageBuilder::new_with_index(10)
+            .set_timestamp(Utc.with_ymd_and_hms(2024, 05, 24, 10, 00, 00).unwrap())
+            .build_message_like()],
+    )
+    .await?;
+
+    let usage = repo.usage(&account).await?;
+    assert_eq!(usage.rows, 6);
+    assert!(usage.bytes > 0);
+
+    assert_eq!(
+        3,
+        repo.delete_messages_before(
+            &account,
+            &room_id,
+            Utc.with_ymd_and_hms(2024, 05, 24, 10, 04, 00).unwrap(),
+            2
+        )
+        .await?
+    );
+
+    assert_eq!(
+        vec![
+            MessageBuilder::remote_id_for_index(4),
+            MessageBuilder::remote_id_for_index(5)
+        ],
+        repo.get_latest_messages(&account, &room_id, 10)
+            .await?
+            .into_iter()
+            .map(|m| m.remote_id.unwrap())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        1,
+        repo.get_latest_messages(&account, &other_room_id, 10)
+            .await?
+            .len()
+    );
+
+    let usage_after_delete = repo.usage(&account).await?;
+    assert_eq!(usage_after_delete.rows, 3);
+    assert!(usage_after_delete.bytes < usage.bytes);
+
+    Ok(())
+}
+
+#[store_test]
+async fn test_get_nth_latest_message_timestamp_counts_only_messages(
+    store: Store<PlatformDriver>,
+) -> Result<()> {
+    let repo = CachingMessageRepository::new(store);
+
+    let account = account_id!("a@prose.org");
+    let room_id = RoomId::from(muc_id!("room@prose.org"));
+    let timestamp = |minute| Utc.with_ymd_and_hms(2024, 05, 24, 10, minute, 00).unwrap();
+
+    repo.append(
+        &account,
+        &room_id,
+        &[
+            MessageBuilder::new_with_index(1)
+                .set_timestamp(timestamp(1))
+                .build_message_like(),
+            MessageBuilder::new_with_index(2)
+                .set_timestamp(timestamp(2))
+                .build_message_like(),
+            MessageBuilder::new_with_index(3)
+                .set_timestamp(timestamp(3))
+                .build_message_like(),
+            MessageBuilder::new_with_index(4)
+                .set_timestamp(timestamp(4))
+                .build_reaction_to(3, &["🍿".into()]),
+            MessageBuilder::new_with_index(5)
+                .set_timestamp(timestamp(5))
+                .set_payload(MessageLikePayload::Retraction {
+                    target_id: MessageBuilder::remote_id_for_index(2).into(),
+                    reason: None,
+                    moderated_by: None,
+                })
+                .build_message_like(),
+        ],
+    )
+    .await?;
+
+    // The reaction and the retraction are skipped…
+    assert_eq!(
+        Some(timestamp(3)),
+        repo.get_nth_latest_message_timestamp(&account, &room_id, 1)
+            .await?
+    );
+    assert_eq!(
+        Some(timestamp(1)),
+        repo.get_nth_latest_message_timestamp(&account, &room_id, 3)
+            .await?
+    );
+    // …so that the room contains only three messages.
+    assert_eq!(
+        None,
+        repo.get_nth_latest_message_timestamp(&account, &room_id, 4)
+            .await?
+    );
+    assert_eq!(
+        None,
+        repo.get_nth_latest_message_timestamp(
+            &account,
+            &RoomId::from(muc_id!("other-room@prose.org")),
+            1
+        )
+        .await?
+    );
+
+    Ok(())
+}
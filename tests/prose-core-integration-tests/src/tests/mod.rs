@@ -13,31 +13,28 @@ use prose_store::prelude::*;
 pub use wasm_bindgen_test::wasm_bindgen_test as async_test;
 
 mod account_settings_repository;
+mod avatar_repository;
 #[cfg(not(target_arch = "wasm32"))]
 mod client;
 mod contacts_repository;
 mod drafts_repository;
 mod local_room_settings_repository;
 mod messages_repository;
+mod store;
 mod user_info_repository;
 
 #[cfg(target_arch = "wasm32")]
-type PlatformDriver = IndexedDBDriver;
-#[cfg(not(target_arch = "wasm32"))]
-type PlatformDriver = SqliteDriver;
-
-#[cfg(target_arch = "wasm32")]
-pub fn platform_driver(name: impl AsRef<str>) -> IndexedDBDriver {
-    IndexedDBDriver::new(name)
+pub fn platform_driver(name: impl AsRef<str>) -> PlatformDriver {
+    PlatformDriver::new(name)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-pub fn platform_driver(_name: impl AsRef<str>) -> SqliteDriver {
+pub fn platform_driver(_name: impl AsRef<str>) -> PlatformDriver {
     let path = tempfile::tempdir().unwrap().path().join("test.sqlite");
     let parent = path.parent().unwrap();
     std::fs::create_dir_all(parent).unwrap();
     println!("Opening DB at {:?}", path);
-    SqliteDriver::new(path)
+    PlatformDriver::new(path)
 }
 
 async fn store() -> Result<Store<PlatformDriver>> {
@@ -53,3 +50,7 @@ async fn store() -> Result<Store<PlatformDriver>> {
     store.truncate_all_collections().await?;
     Ok(store)
 }
+
+async fn in_memory_store() -> Result<Store<PlatformDriver>> {
+    Ok(open_store(PlatformDriver::in_memory()).await?)
+}
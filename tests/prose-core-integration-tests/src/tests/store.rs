@@ -0,0 +1,199 @@
+// prose-core-client/prose-core-integration-tests
+//
+// Copyright: 2024, Marc Bauer <mb@nesium.com>
+// License: Mozilla Public License v2.0 (MPL v2.0)
+
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use pretty_assertions::assert_eq;
+
+use prose_core_client::domain::encryption::models::{
+    DeviceId, IdentityKey, IdentityKeyPair, LocalEncryptionBundle, PrivateKey, PublicKey,
+    SignedPreKey, SignedPreKeyId,
+};
+use prose_core_client::domain::encryption::repos::EncryptionKeysRepository as EncryptionKeysRepositoryTrait;
+use prose_core_client::domain::messaging::models::{PendingMessage, PendingMessageState};
+use prose_core_client::domain::messaging::repos::{
+    MessagesRepository, PendingMessagesRepository as PendingMessagesRepositoryTrait,
+};
+use prose_core_client::domain::shared::models::{AccountId, Markdown, RoomId, UserId};
+use prose_core_client::infra::contacts::ContactRecord;
+use prose_core_client::infra::encryption::EncryptionKeysRepository;
+use prose_core_client::infra::messaging::{CachingMessageRepository, PendingMessagesRepository};
+use prose_core_client::infra::platform_dependencies::{migrate_store, open_store, DB_VERSION};
+use prose_core_client::test::MessageBuilder;
+use prose_core_client::{account_id, user_id};
+use prose_store::prelude::*;
+
+use crate::tests::{async_test, platform_driver};
+
+#[async_test]
+async fn test_upgrades_database_with_data_intact() -> Result<()> {
+    let driver = platform_driver("store_upgrade");
+    driver.reset().await?;
+
+    let account = account_id!("account@prose.org");
+    let room_id = RoomId::from(user_id!("a@prose.org"));
+    let message = MessageBuilder::new_with_index(1).build_message_like();
+
+    let store = Store::open(driver.clone(), DB_VERSION - 1, |event| {
+        migrate_store::<PlatformDriver>(event)
+    })
+    .await?;
+    CachingMessageRepository::new(store.clone())
+        .append(&account, &room_id, &[message.clone()])
+        .await?;
+    drop(store);
+
+    let store = open_store(driver).await?;
+
+    assert_eq!(store.schema_version(), DB_VERSION);
+    assert!(!store.was_reset());
+    assert!(store
+        .collection_names()
+        .await?
+        .contains(&ContactRecord::collection().to_string()));
+    assert_eq!(
+        vec![message.clone()],
+        CachingMessageRepository::new(store)
+            .get_all(&account, &room_id, &[message.id])
+            .await?
+    );
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_starts_over_after_failed_migration() -> Result<()> {
+    let driver = platform_driver("store_recovery");
+    driver.reset().await?;
+
+    let account = account_id!("account@prose.org");
+    let room_id = RoomId::from(user_id!("a@prose.org"));
+    let message = MessageBuilder::new_with_index(1).build_message_like();
+
+    // A cache whose schema doesn't match its version, so that the next migration fails…
+    let store = Store::open(driver.clone(), DB_VERSION - 1, |event| {
+        migrate_store::<PlatformDriver>(event)?;
+        event.tx.delete_collection(ContactRecord::collection())?;
+        Ok(())
+    })
+    .await?;
+    EncryptionKeysRepository::new(store.clone())
+        .put_local_encryption_bundle(&account, &local_encryption_bundle())
+        .await?;
+    PendingMessagesRepository::new(store.clone())
+        .put(&account, &pending_message())
+        .await?;
+    CachingMessageRepository::new(store.clone())
+        .append(&account, &room_id, &[message.clone()])
+        .await?;
+    drop(store);
+
+    let store = open_store(driver).await?;
+
+    assert_eq!(store.schema_version(), DB_VERSION);
+    assert!(store.was_reset());
+    assert!(CachingMessageRepository::new(store.clone())
+        .get_all(&account, &room_id, &[message.id])
+        .await?
+        .is_empty());
+
+    let encryption_keys_repo = EncryptionKeysRepository::new(store.clone());
+    assert_eq!(
+        Some(DeviceId::from(1)),
+        encryption_keys_repo
+            .get_local_device(&account)
+            .await?
+            .map(|device| device.device_id)
+    );
+    assert_eq!(
+        Some(SignedPreKeyId::from(1)),
+        encryption_keys_repo
+            .get_signed_pre_key(&account, SignedPreKeyId::from(1))
+            .await?
+            .map(|key| key.id)
+    );
+    assert_eq!(
+        vec![pending_message()],
+        PendingMessagesRepository::new(store)
+            .get_all(&account)
+            .await?
+    );
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_does_not_start_over_after_transient_error() -> Result<()> {
+    let driver = platform_driver("store_transient_error");
+    driver.reset().await?;
+
+    let store = Store::open(driver.clone(), 1, |event| {
+        event.tx.create_collection("items")?;
+        Ok(())
+    })
+    .await?;
+    store.set("items", "a", &"Hello").await?;
+    drop(store);
+
+    let result = Store::open_or_recover(driver.clone(), 2, &[], |event| {
+        if event.old_version < 1 {
+            event.tx.create_collection("items")?;
+        }
+        if event.old_version < 2 {
+            return Err(transient_error());
+        }
+        Ok(())
+    })
+    .await;
+    assert!(result.is_err());
+
+    let store = Store::open(driver, 1, |_| Ok(())).await?;
+    assert_eq!(
+        store.get::<_, String>("items", &"a".to_string()).await?,
+        Some("Hello".to_string())
+    );
+
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn transient_error() -> DriverError {
+    DriverError::Persistent(Error::IndexedDB("Transaction was aborted".to_string()))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn transient_error() -> DriverError {
+    DriverError::Persistent(Error::Interact("Database is locked".to_string()))
+}
+
+fn local_encryption_bundle() -> LocalEncryptionBundle {
+    LocalEncryptionBundle {
+        device_id: DeviceId::from(1),
+        identity_key_pair: IdentityKeyPair {
+            identity_key: IdentityKey::from([5u8; 33].as_slice()),
+            private_key: PrivateKey::from([6u8; 32].as_slice()),
+        },
+        signed_pre_key: SignedPreKey {
+            id: SignedPreKeyId::from(1),
+            public_key: PublicKey::from([7u8; 33].as_slice()),
+            private_key: PrivateKey::from([8u8; 32].as_slice()),
+            signature: Box::new([9u8; 64]),
+            timestamp: 0,
+        },
+        pre_keys: vec![],
+    }
+}
+
+fn pending_message() -> PendingMessage {
+    PendingMessage {
+        id: "pending-message".into(),
+        room_id: RoomId::from(user_id!("a@prose.org")),
+        body: Some(Markdown::new("Hello")),
+        attachments: vec![],
+        reply_to: None,
+        timestamp: Utc.with_ymd_and_hms(2024, 05, 01, 0, 0, 0).unwrap(),
+        state: PendingMessageState::Pending,
+    }
+}
@@ -0,0 +1,344 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+
+use prose_store::prelude::*;
+
+use crate::tests::{
+    async_test, collections, multi_column_index_store_with_driver, platform_drivers,
+    store_with_driver, IndexedRecord, Person,
+};
+
+#[async_test]
+async fn test_reopens_database_with_data_intact() -> Result<()> {
+    for driver in platform_drivers("reopen") {
+        driver.reset().await?;
+
+        let store = store_with_driver(driver.clone()).await?;
+        store
+            .set(collections::PERSON, "id-1", &Person::jane_doe())
+            .await?;
+        drop(store);
+
+        let store = Store::open(driver, 2, |event| {
+            assert_eq!(event.old_version, 1);
+            event.tx.create_collection("tags")?;
+            Ok(())
+        })
+        .await?;
+
+        assert_eq!(store.schema_version(), 2);
+        assert_eq!(
+            store.get(collections::PERSON, "id-1").await?,
+            Some(Person::jane_doe())
+        );
+    }
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_rolls_back_failed_upgrade() -> Result<()> {
+    for driver in platform_drivers("failed_upgrade") {
+        driver.reset().await?;
+
+        let result = Store::open(driver.clone(), 1, |event| {
+            event.tx.create_collection(collections::PERSON)?;
+            event.tx.delete_collection("missing")?;
+            Ok(())
+        })
+        .await;
+        assert!(result.is_err());
+
+        Store::open(driver, 1, |event| {
+            assert_eq!(event.old_version, 0);
+            assert!(event.tx.collection_names()?.is_empty());
+            Ok(())
+        })
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_test]
+async fn test_refuses_to_open_newer_database() -> Result<()> {
+    for driver in platform_drivers("newer_version") {
+        let store = Store::open(driver.clone(), 2, |event| {
+            event.tx.create_collection(collections::PERSON)?;
+            Ok(())
+        })
+        .await?;
+        drop(store);
+
+        let result = Store::open(driver, 1, |_| Ok(())).await;
+
+        assert!(matches!(
+            result,
+            Err(DriverError::Persistent(Error::UnsupportedVersion {
+                found: 2,
+                supported: 1
+            })) | Err(DriverError::InMemory(
+                InMemoryDriverError::UnsupportedVersion {
+                    found: 2,
+                    supported: 1
+                }
+            ))
+        ));
+    }
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_reset_discards_database() -> Result<()> {
+    for driver in platform_drivers("reset") {
+        let store = store_with_driver(driver.clone()).await?;
+        store
+            .set(collections::PERSON, "id-1", &Person::jane_doe())
+            .await?;
+        drop(store);
+
+        driver.reset().await?;
+
+        let store = store_with_driver(driver).await?;
+        assert!(!store.contains_key(collections::PERSON, "id-1").await?);
+    }
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_rolls_back_transaction() -> Result<()> {
+    for driver in platform_drivers("rollback") {
+        let store = store_with_driver(driver).await?;
+        store
+            .set(collections::PERSON, "id-1", &Person::jane_doe())
+            .await?;
+
+        let tx = store
+            .transaction_for_reading_and_writing(&[collections::PERSON])
+            .await?;
+        {
+            let people = tx.writeable_collection(collections::PERSON)?;
+            people.put("id-1", &Person::john_doe())?;
+            people.set("id-2", &Person::named("Benjamin")).await?;
+            people.delete("id-1").await?;
+        }
+        tx.rollback().await?;
+
+        assert_eq!(
+            store.get(collections::PERSON, "id-1").await?,
+            Some(Person::jane_doe())
+        );
+        assert!(!store.contains_key(collections::PERSON, "id-2").await?);
+    }
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_in_memory_databases_are_isolated() -> Result<()> {
+    let store1 = store_with_driver(PlatformDriver::in_memory()).await?;
+    let store2 = store_with_driver(PlatformDriver::in_memory()).await?;
+
+    store1
+        .set(collections::PERSON, "id-1", &Person::jane_doe())
+        .await?;
+
+    assert!(store1.contains_key(collections::PERSON, "id-1").await?);
+    assert!(!store2.contains_key(collections::PERSON, "id-1").await?);
+
+    Ok(())
+}
+
+#[async_test]
+async fn test_search_ignores_case_and_diacritics() -> Result<()> {
+    for driver in platform_drivers("search") {
+        let store = multi_column_index_store_with_driver(driver).await?;
+        store
+            .set(
+                IndexedRecord::collection(),
+                "1",
+                &IndexedRecord::new("a@prose.org", 1, 1, "Crème brûlée for dessert"),
+            )
+            .await?;
+
+        let tx = store
+            .transaction_for_reading(&[IndexedRecord::collection()])
+            .await?;
+        let records = tx.readable_collection(IndexedRecord::collection())?;
+        let idx = records.index(&IndexedRecord::account_idx())?;
+
+        for (text, is_found) in [
+            ("CREME", true),
+            ("brul dess", true),
+            ("creme-brulee", true),
+            ("rulee", false),
+            ("creme cake", false),
+        ] {
+            assert_eq!(
+                is_found,
+                !idx.search::<IndexedRecord>(
+                    Query::Only("a@prose.org"),
+                    "value",
+                    text,
+                    Default::default(),
+                    None
+                )
+                .await?
+                .is_empty(),
+                "Unexpected result for {text}"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_test]
+async fn test_drivers_behave_alike() -> Result<()> {
+    let [persistent, in_memory] = platform_drivers("parity");
+    let expected_results =
+        run_scenario(multi_column_index_store_with_driver(persistent).await?).await?;
+    let results = run_scenario(multi_column_index_store_with_driver(in_memory).await?).await?;
+
+    assert_eq!(expected_results, results);
+
+    Ok(())
+}
+
+/// Runs the same operations against `store` and records their results, so that the results of
+/// different drivers can be compared.
+#[cfg(not(target_arch = "wasm32"))]
+async fn run_scenario(store: Store<PlatformDriver>) -> Result<Vec<String>> {
+    use std::ops::Bound;
+
+    let mut results = vec![];
+
+    let tx = store
+        .transaction_for_reading_and_writing(&[IndexedRecord::collection()])
+        .await?;
+    {
+        let records = tx.writeable_collection(IndexedRecord::collection())?;
+        for record in [
+            IndexedRecord::new("a@prose.org", 1, 1, "Lunch at noon"),
+            IndexedRecord::new("a@prose.org", 2, 2, "Dinner at eight"),
+            IndexedRecord::new("a@prose.org", 2, 3, "Crème brûlée for dessert"),
+            IndexedRecord::new("a@prose.org", 10, 4, "lunch was great"),
+            IndexedRecord::new("b@prose.org", 1, 5, "Lunch for b"),
+        ] {
+            records.set_entity(&record).await?;
+        }
+
+        let duplicate = IndexedRecord::new("a@prose.org", 1, 1, "Duplicate");
+        results.push(format!(
+            "{:?}",
+            records.set_entity(&duplicate).await.is_err()
+        ));
+        records.put_entity(&IndexedRecord::new(
+            "a@prose.org",
+            1,
+            1,
+            "Breakfast at noon",
+        ))?;
+    }
+    tx.commit().await?;
+
+    let tx = store
+        .transaction_for_reading_and_writing(&[IndexedRecord::collection()])
+        .await?;
+    {
+        let records = tx.writeable_collection(IndexedRecord::collection())?;
+        records.put_entity(&IndexedRecord::new("a@prose.org", 3, 6, "Rolled back"))?;
+        records
+            .delete_all_in_index(&["account_id"], Query::Only("b@prose.org"))
+            .await?;
+    }
+    tx.rollback().await?;
+
+    let tx = store
+        .transaction_for_reading(&[IndexedRecord::collection()])
+        .await?;
+    let records = tx.readable_collection(IndexedRecord::collection())?;
+    let idx = records.index(&IndexedRecord::user_idx())?;
+
+    // The order of the keys is unspecified…
+    let mut keys = records.all_keys().await?;
+    keys.sort();
+    results.push(format!("{keys:?}"));
+    results.push(format!(
+        "{:?}",
+        idx.get::<_, IndexedRecord>(&("a@prose.org", 2)).await?
+    ));
+    results.push(format!(
+        "{:?}",
+        idx.contains_key(&("b@prose.org", 2)).await?
+    ));
+
+    for (query, direction, limit) in [
+        (
+            Query::from_range(("a@prose.org", 1)..("a@prose.org", 10)),
+            QueryDirection::Forward,
+            None,
+        ),
+        (
+            Query::from_range(("a@prose.org", 1)..=("a@prose.org", 10)),
+            QueryDirection::Backward,
+            Some(2),
+        ),
+        (
+            Query::Range {
+                start: Bound::Excluded(("a@prose.org", 0)),
+                end: Bound::Excluded(("a@prose.org", 10)),
+            },
+            QueryDirection::Forward,
+            None,
+        ),
+        (
+            Query::from_range(..=("a@prose.org", 2)),
+            QueryDirection::Forward,
+            None,
+        ),
+        (
+            Query::Only(("a@prose.org", 2)),
+            QueryDirection::Backward,
+            None,
+        ),
+    ] {
+        let records = idx
+            .get_all_values::<IndexedRecord>(query, direction, limit)
+            .await?;
+        results.push(format!("{records:?}"));
+    }
+
+    for text in [
+        "lunch",
+        "LUNCH great",
+        "creme",
+        "dessert bru",
+        "at",
+        "noo",
+        " ",
+    ] {
+        let records = idx
+            .search::<IndexedRecord>(
+                Query::from_range(("a@prose.org", 0)..("b@prose.org", 100)),
+                "value",
+                text,
+                QueryDirection::Backward,
+                Some(3),
+            )
+            .await?;
+        results.push(format!("{records:?}"));
+    }
+
+    results.push(format!("{:?}", records.usage(Query::<String>::All).await?));
+    results.push(format!(
+        "{:?}",
+        idx.usage(Query::Only(("a@prose.org", 2))).await?
+    ));
+
+    Ok(results)
+}
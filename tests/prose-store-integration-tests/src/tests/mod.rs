@@ -12,8 +12,10 @@ pub use tokio::test as async_test;
 #[cfg(target_arch = "wasm32")]
 pub use wasm_bindgen_test::wasm_bindgen_test as async_test;
 
+use prose_proc_macros::store_test;
 use prose_store::prelude::*;
 
+mod drivers;
 mod repository;
 #[cfg(not(target_arch = "wasm32"))]
 mod sqlite;
@@ -22,6 +24,7 @@ pub mod collections {
     pub const PERSON: &str = "person";
     pub const CAMERA: &str = "camera";
     pub const BOOK: &str = "book";
+    pub const USER: &str = "user";
 
     pub mod person {
         pub const BIRTHDAY: &str = "birthday";
@@ -119,7 +122,13 @@ define_entity!(IndexedRecord, "indexed_record",
 );
 
 async fn multi_column_index_store(name: impl AsRef<str>) -> Result<Store<PlatformDriver>> {
-    let driver = platform_driver(name);
+    multi_column_index_store_with_driver(PlatformDriver::from(platform_driver(name))).await
+}
+
+async fn multi_column_index_store_with_driver<D: Driver>(driver: D) -> Result<Store<D>>
+where
+    D::Error: 'static,
+{
     let store = Store::open(driver, 1, |event| {
         let tx = &event.tx;
 
@@ -127,6 +136,7 @@ async fn multi_column_index_store(name: impl AsRef<str>) -> Result<Store<Platfor
         for idx_spec in IndexedRecord::indexes() {
             collection.add_index(idx_spec)?;
         }
+        collection.add_fulltext_index("value")?;
 
         Ok(())
     })
@@ -149,15 +159,34 @@ pub fn platform_driver(_name: impl AsRef<str>) -> SqliteDriver {
     SqliteDriver::new(path)
 }
 
+/// Returns a persistent and an in-memory driver, so that tests of driver behaviour run against
+/// both kinds of databases that `PlatformDriver` supports.
+fn platform_drivers(name: impl AsRef<str>) -> [PlatformDriver; 2] {
+    [
+        PlatformDriver::from(platform_driver(name)),
+        PlatformDriver::in_memory(),
+    ]
+}
+
 async fn store() -> Result<Store<PlatformDriver>> {
-    let driver = platform_driver(
+    store_with_driver(PlatformDriver::from(platform_driver(
         std::path::Path::new(file!())
             .file_stem()
             .unwrap()
             .to_str()
             .unwrap(),
-    );
+    )))
+    .await
+}
+
+async fn in_memory_store() -> Result<Store<PlatformDriver>> {
+    store_with_driver(PlatformDriver::in_memory()).await
+}
 
+async fn store_with_driver<D: Driver>(driver: D) -> Result<Store<D>>
+where
+    D::Error: 'static,
+{
     let store = Store::open(driver, 1, |event| {
         let store = event.tx.create_collection(collections::PERSON)?;
         store.add_index(
@@ -167,6 +196,7 @@ async fn store() -> Result<Store<PlatformDriver>> {
         )?;
 
         event.tx.create_collection(collections::CAMERA)?;
+        event.tx.create_collection(collections::USER)?;
 
         let store = event.tx.create_collection(collections::BOOK)?;
         store.add_index(
@@ -189,7 +219,8 @@ async fn store() -> Result<Store<PlatformDriver>> {
             vec![
                 collections::BOOK.to_string(),
                 collections::CAMERA.to_string(),
-                collections::PERSON.to_string()
+                collections::PERSON.to_string(),
+                collections::USER.to_string()
             ]
         );
 
@@ -200,10 +231,8 @@ async fn store() -> Result<Store<PlatformDriver>> {
     Ok(store)
 }
 
-#[async_test]
-async fn test_set_and_get() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_set_and_get(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON, collections::CAMERA])
         .await?;
@@ -235,10 +264,10 @@ async fn test_set_and_get() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_begin_transaction_with_invalid_collection() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_begin_transaction_with_invalid_collection(
+    store: Store<PlatformDriver>,
+) -> Result<()> {
     let result = store
         .transaction_for_reading_and_writing(&["does-not-exist"])
         .await;
@@ -247,10 +276,10 @@ async fn test_begin_transaction_with_invalid_collection() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_access_invalid_collection_from_transaction() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_access_invalid_collection_from_transaction(
+    store: Store<PlatformDriver>,
+) -> Result<()> {
     {
         let tx = store
             .transaction_for_reading_and_writing(&[collections::PERSON])
@@ -275,10 +304,8 @@ async fn test_access_invalid_collection_from_transaction() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_access_invalid_index() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_access_invalid_index(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading(&[collections::PERSON])
         .await?;
@@ -290,10 +317,10 @@ async fn test_access_invalid_index() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_access_collection_not_included_in_transaction() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_access_collection_not_included_in_transaction(
+    store: Store<PlatformDriver>,
+) -> Result<()> {
     {
         let tx = store
             .transaction_for_reading_and_writing(&[collections::PERSON])
@@ -318,10 +345,8 @@ async fn test_access_collection_not_included_in_transaction() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_get_from_collection_and_index() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_get_from_collection_and_index(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON])
         .await?;
@@ -390,10 +415,8 @@ async fn test_get_from_collection_and_index() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_set_conflict() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_set_conflict(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON])
         .await?;
@@ -517,10 +540,8 @@ async fn test_chrono_types() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_date_time_precision() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_date_time_precision(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::BOOK])
         .await?;
@@ -556,10 +577,8 @@ async fn test_date_time_precision() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_put_no_conflict() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_put_no_conflict(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON])
         .await?;
@@ -580,10 +599,8 @@ async fn test_put_no_conflict() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_contains_key() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_contains_key(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON, collections::CAMERA])
         .await?;
@@ -601,10 +618,8 @@ async fn test_contains_key() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_delete() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_delete(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON, collections::CAMERA])
         .await?;
@@ -630,10 +645,8 @@ async fn test_delete() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_collection_names() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_collection_names(store: Store<PlatformDriver>) -> Result<()> {
     let mut names = store.collection_names().await?;
     names.sort();
 
@@ -642,17 +655,16 @@ async fn test_collection_names() -> Result<()> {
         vec![
             collections::BOOK.to_string(),
             collections::CAMERA.to_string(),
-            collections::PERSON.to_string()
+            collections::PERSON.to_string(),
+            collections::USER.to_string()
         ]
     );
 
     Ok(())
 }
 
-#[async_test]
-async fn test_truncate() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_truncate(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON, collections::CAMERA])
         .await?;
@@ -676,10 +688,8 @@ async fn test_truncate() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_truncate_all() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_truncate_all(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON, collections::CAMERA])
         .await?;
@@ -711,10 +721,8 @@ async fn test_truncate_all() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_get_all_values() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_get_all_values(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON])
         .await?;
@@ -843,10 +851,8 @@ async fn test_get_all_values() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_get_all_values_with_order_and_limit() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_get_all_values_with_order_and_limit(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON])
         .await?;
@@ -885,10 +891,8 @@ async fn test_get_all_values_with_order_and_limit() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_get_all_with_filter() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_get_all_with_filter(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON])
         .await?;
@@ -936,10 +940,8 @@ async fn test_get_all_with_filter() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_index_keys() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_index_keys(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON])
         .await?;
@@ -1012,10 +1014,8 @@ async fn test_index_keys() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_get_all_values_on_empty_collection() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_get_all_values_on_empty_collection(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading(&[collections::PERSON])
         .await?;
@@ -1029,10 +1029,8 @@ async fn test_get_all_values_on_empty_collection() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_index() -> Result<()> {
-    let store = store().await?;
-
+#[store_test]
+async fn test_index(store: Store<PlatformDriver>) -> Result<()> {
     let tx = store
         .transaction_for_reading_and_writing(&[collections::PERSON])
         .await?;
@@ -1389,3 +1387,83 @@ async fn test_delete_all_index_in_multicolumn_index() -> Result<()> {
 
     Ok(())
 }
+
+#[async_test]
+async fn test_search() -> Result<()> {
+    let store = multi_column_index_store("test_search").await?;
+
+    let tx = store
+        .transaction_for_reading_and_writing(&[IndexedRecord::collection()])
+        .await?;
+    {
+        let records = tx.writeable_collection(IndexedRecord::collection())?;
+        records
+            .set_entity(&IndexedRecord::new("a@prose.org", 1, 1, "Lunch at noon"))
+            .await?;
+        records
+            .set_entity(&IndexedRecord::new("a@prose.org", 2, 2, "Dinner at eight"))
+            .await?;
+        records
+            .set_entity(&IndexedRecord::new("a@prose.org", 3, 3, "lunch was great"))
+            .await?;
+        records
+            .set_entity(&IndexedRecord::new("b@prose.org", 1, 4, "Lunch for b"))
+            .await?;
+    }
+    tx.commit().await?;
+
+    let tx = store
+        .transaction_for_reading(&[IndexedRecord::collection()])
+        .await?;
+    let records = tx.readable_collection(IndexedRecord::collection())?;
+    let idx = records.index(&IndexedRecord::user_idx())?;
+    let range = || Query::from_range(("a@prose.org", 0)..("a@prose.org", 10));
+
+    assert_eq!(
+        vec![
+            IndexedRecord::new("a@prose.org", 3, 3, "lunch was great"),
+            IndexedRecord::new("a@prose.org", 1, 1, "Lunch at noon"),
+        ],
+        idx.search::<IndexedRecord>(range(), "value", "lunch", QueryDirection::Backward, None)
+            .await?
+    );
+    assert_eq!(
+        vec![IndexedRecord::new("a@prose.org", 1, 1, "Lunch at noon")],
+        idx.search::<IndexedRecord>(range(), "value", "lunch", QueryDirection::Forward, Some(1))
+            .await?
+    );
+    assert_eq!(
+        vec![IndexedRecord::new("a@prose.org", 3, 3, "lunch was great")],
+        idx.search::<IndexedRecord>(range(), "value", "LUNCH great", Default::default(), None)
+            .await?
+    );
+    assert!(idx
+        .search::<IndexedRecord>(range(), "value", " ", Default::default(), None)
+        .await?
+        .is_empty());
+    drop(tx);
+
+    // Replaced records should not be found by their previous value.
+    let tx = store
+        .transaction_for_reading_and_writing(&[IndexedRecord::collection()])
+        .await?;
+    {
+        let records = tx.writeable_collection(IndexedRecord::collection())?;
+        records.put_entity(&IndexedRecord::new("a@prose.org", 1, 1, "Breakfast"))?;
+    }
+    tx.commit().await?;
+
+    let tx = store
+        .transaction_for_reading(&[IndexedRecord::collection()])
+        .await?;
+    let records = tx.readable_collection(IndexedRecord::collection())?;
+    let idx = records.index(&IndexedRecord::user_idx())?;
+
+    assert_eq!(
+        vec![IndexedRecord::new("a@prose.org", 3, 3, "lunch was great")],
+        idx.search::<IndexedRecord>(range(), "value", "lunch", Default::default(), None)
+            .await?
+    );
+
+    Ok(())
+}
@@ -1,7 +1,8 @@
-use crate::tests::{async_test, platform_driver};
+use crate::tests::{async_test, collections, in_memory_store, store};
 use anyhow::Result;
 use async_trait::async_trait;
 use jid::BareJid;
+use prose_proc_macros::store_test;
 use prose_store::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
@@ -13,7 +14,7 @@ struct User {
     company: Option<Company>,
 }
 
-define_entity!(User, "user", BareJid);
+define_entity!(User, collections::USER, BareJid);
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 struct Company {
@@ -80,29 +81,9 @@ impl Company {
     }
 }
 
-async fn store() -> Result<Store<PlatformDriver>> {
-    let store = Store::open(
-        platform_driver(
-            std::path::Path::new(file!())
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap(),
-        ),
-        1,
-        |event| {
-            event.tx.create_collection(User::collection())?;
-            Ok(())
-        },
-    )
-    .await?;
-    store.truncate_all_collections().await?;
-    Ok(store)
-}
-
-#[async_test]
-async fn test_get() -> Result<()> {
-    let repo = Repository::<_, User>::new(store().await?);
+#[store_test]
+async fn test_get(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = Repository::<_, User>::new(store);
 
     repo.put(&User::developer()).await?;
     repo.put(&User::designer()).await?;
@@ -121,9 +102,9 @@ async fn test_get() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_get_all() -> Result<()> {
-    let repo = Repository::<_, User>::new(store().await?);
+#[store_test]
+async fn test_get_all(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = Repository::<_, User>::new(store);
 
     repo.put(&User::developer()).await?;
     repo.put(&User::designer()).await?;
@@ -137,9 +118,9 @@ async fn test_get_all() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_delete() -> Result<()> {
-    let repo = Repository::<_, User>::new(store().await?);
+#[store_test]
+async fn test_delete(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = Repository::<_, User>::new(store);
 
     repo.put(&User::developer()).await?;
     repo.put(&User::designer()).await?;
@@ -161,9 +142,9 @@ async fn test_delete() -> Result<()> {
     Ok(())
 }
 
-#[async_test]
-async fn test_update_entry() -> Result<()> {
-    let repo = Repository::<_, User>::new(store().await?);
+#[store_test]
+async fn test_update_entry(store: Store<PlatformDriver>) -> Result<()> {
+    let repo = Repository::<_, User>::new(store);
 
     assert_eq!(repo.get(&User::applicant().id).await?, None);
 
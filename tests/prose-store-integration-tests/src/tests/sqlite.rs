@@ -1,4 +1,4 @@
-use crate::tests::{collections, platform_driver, store, Person};
+use crate::tests::{collections, platform_driver, store_with_driver, Person};
 use anyhow::Result;
 use chrono::NaiveDate;
 use insta::assert_snapshot;
@@ -9,7 +9,7 @@ use tokio::sync::Semaphore;
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_concurrent_write_transactions_succeed() -> Result<()> {
-    let store = store().await?;
+    let store = store_with_driver(platform_driver("sqlite")).await?;
     let store1 = store.clone();
     let store2 = store1.clone();
 
@@ -84,7 +84,7 @@ async fn test_concurrent_write_transactions_succeed() -> Result<()> {
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_table_structure() -> Result<()> {
-    let store = store().await?;
+    let store = store_with_driver(platform_driver("sqlite")).await?;
     let sql = store.describe_table(collections::PERSON).await?;
     assert_snapshot!(sql);
     Ok(())
@@ -92,7 +92,7 @@ async fn test_table_structure() -> Result<()> {
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn test_query_uses_index() -> Result<()> {
-    let store = store().await?;
+    let store = store_with_driver(platform_driver("sqlite")).await?;
 
     let tx = store
         .transaction_for_reading(&[collections::PERSON])